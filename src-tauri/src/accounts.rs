@@ -0,0 +1,521 @@
+// Load balancing across ChatGPT accounts
+//
+// Besides the primary login, extra ChatGPT accounts can be signed in, e.g. a
+// Plus and a Pro subscription. Every upstream request borrows one account
+// from the pool, taking turns or picking the account with the fewest requests
+// in flight. An account that answered with a rate limit is passed over until
+// its cooldown ends; when every account is cooling down, the one that becomes
+// available first is tried anyway, unless backpressure turns the request away
+// until then.
+//
+// Requests that name a session stick to the account that served the
+// session's first request, since the ChatGPT backend keeps conversations per
+// account. A session only moves when its account is cooling down or gone.
+
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::managers::auth_manager::AuthManager;
+use crate::managers::config_manager::{AccountsConfig, BalancingStrategy};
+use crate::plan;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// ID of the account signed in through the regular login
+pub const PRIMARY_ACCOUNT: &str = "primary";
+
+/// Window of the per-account request rate
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Sessions kept pinned at most; the least recently used is dropped first
+pub const MAX_SESSIONS: usize = 10_000;
+
+/// Headers naming the session or conversation a request belongs to
+pub const SESSION_HEADERS: [&str; 2] = ["x-session-id", "x-conversation-id"];
+
+/// How a borrowed account's request ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestOutcome {
+    Success,
+    RateLimited,
+    Failed,
+}
+
+/// Live usage of one account
+#[derive(Debug, Default)]
+pub struct AccountLoad {
+    in_flight: usize,
+    /// Start times of the requests within the rate window
+    recent: VecDeque<Instant>,
+    requests: u64,
+    errors: u64,
+    rate_limited: u64,
+    cooldown_until: Option<Instant>,
+}
+
+impl AccountLoad {
+    pub fn start(&mut self, now: Instant) {
+        self.in_flight += 1;
+        self.requests += 1;
+        self.recent.push_back(now);
+        self.prune(now);
+    }
+
+    pub fn end(&mut self) {
+        self.in_flight = self.in_flight.saturating_sub(1);
+    }
+
+    pub fn record(&mut self, outcome: RequestOutcome, now: Instant, cooldown: Duration) {
+        match outcome {
+            RequestOutcome::Success => {},
+            RequestOutcome::RateLimited => {
+                self.errors += 1;
+                self.rate_limited += 1;
+                self.cooldown_until = Some(now + cooldown);
+            },
+            RequestOutcome::Failed => self.errors += 1,
+        }
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight
+    }
+
+    /// Requests started within the last minute
+    pub fn requests_per_minute(&mut self, now: Instant) -> usize {
+        self.prune(now);
+        self.recent.len()
+    }
+
+    /// Time left until the account may be used again after a rate limit
+    pub fn cooldown_left(&self, now: Instant) -> Option<Duration> {
+        self.cooldown_until
+            .filter(|until| *until > now)
+            .map(|until| until - now)
+    }
+
+    fn prune(&mut self, now: Instant) {
+        while self
+            .recent
+            .front()
+            .is_some_and(|started| now.duration_since(*started) >= RATE_WINDOW)
+        {
+            self.recent.pop_front();
+        }
+    }
+}
+
+/// Indexes of `loads` in the order they should be tried. `cursor` is the
+/// round-robin turn.
+pub fn rank(
+    strategy: BalancingStrategy,
+    loads: &[&AccountLoad],
+    cursor: usize,
+    now: Instant,
+) -> Vec<usize> {
+    let count = loads.len();
+    let mut order: Vec<usize> = match strategy {
+        BalancingStrategy::RoundRobin => (0..count).map(|i| (cursor + i) % count.max(1)).collect(),
+        BalancingStrategy::LeastLoaded => {
+            let mut order: Vec<usize> = (0..count).collect();
+            // Stable, so ties keep their round-robin position
+            order.rotate_left(cursor % count.max(1));
+            order.sort_by_key(|&i| loads[i].in_flight);
+            order
+        },
+    };
+
+    // Accounts cooling down go last, the one available soonest first
+    order.sort_by_key(|&i| loads[i].cooldown_left(now));
+    order
+}
+
+/// `order` with the pinned account moved to the front, unless it is cooling
+/// down
+pub fn prefer(
+    mut order: Vec<usize>,
+    pinned: Option<usize>,
+    loads: &[&AccountLoad],
+    now: Instant,
+) -> Vec<usize> {
+    let Some(pinned) = pinned.filter(|&i| loads[i].cooldown_left(now).is_none()) else {
+        return order;
+    };
+    order.retain(|&i| i != pinned);
+    order.insert(0, pinned);
+    order
+}
+
+#[derive(Debug)]
+struct Pin {
+    account_id: String,
+    last_used: Instant,
+}
+
+/// The account each session is pinned to
+#[derive(Debug, Default)]
+pub struct SessionPins {
+    pins: HashMap<String, Pin>,
+}
+
+impl SessionPins {
+    /// Account `session` is pinned to, unless it has been idle for `ttl`
+    pub fn get(&self, session: &str, ttl: Duration, now: Instant) -> Option<&str> {
+        self.pins
+            .get(session)
+            .filter(|pin| now.duration_since(pin.last_used) < ttl)
+            .map(|pin| pin.account_id.as_str())
+    }
+
+    /// Pin `session` to `account_id`. Returns the account it was pinned to
+    /// before, if that was another one.
+    pub fn pin(
+        &mut self,
+        session: &str,
+        account_id: &str,
+        ttl: Duration,
+        now: Instant,
+    ) -> Option<String> {
+        let previous = self
+            .get(session, ttl, now)
+            .filter(|previous| *previous != account_id)
+            .map(str::to_string);
+
+        if !self.pins.contains_key(session) {
+            self.pins
+                .retain(|_, pin| now.duration_since(pin.last_used) < ttl);
+            if self.pins.len() >= MAX_SESSIONS {
+                if let Some(oldest) = self
+                    .pins
+                    .iter()
+                    .min_by_key(|(_, pin)| pin.last_used)
+                    .map(|(session, _)| session.clone())
+                {
+                    self.pins.remove(&oldest);
+                }
+            }
+        }
+        self.pins.insert(
+            session.to_string(),
+            Pin {
+                account_id: account_id.to_string(),
+                last_used: now,
+            },
+        );
+        previous
+    }
+
+    /// Sessions pinned to `account_id`
+    pub fn count(&self, account_id: &str, ttl: Duration, now: Instant) -> usize {
+        self.pins
+            .values()
+            .filter(|pin| pin.account_id == account_id && now.duration_since(pin.last_used) < ttl)
+            .count()
+    }
+}
+
+/// One signed-in account and its load
+#[derive(Debug)]
+pub struct PooledAccount {
+    pub id: String,
+    pub auth: Arc<RwLock<AuthManager>>,
+    load: Mutex<AccountLoad>,
+}
+
+impl PooledAccount {
+    fn new(id: &str, auth: Arc<RwLock<AuthManager>>) -> Arc<Self> {
+        Arc::new(Self {
+            id: id.to_string(),
+            auth,
+            load: Mutex::new(AccountLoad::default()),
+        })
+    }
+
+    fn load(&self) -> std::sync::MutexGuard<'_, AccountLoad> {
+        self.load.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// An account borrowed for one upstream request. Dropping it ends the request.
+#[derive(Debug)]
+pub struct AccountLease {
+    account: Arc<PooledAccount>,
+    access_token: String,
+    cooldown: Duration,
+}
+
+impl AccountLease {
+    pub fn id(&self) -> &str {
+        &self.account.id
+    }
+
+    pub fn access_token(&self) -> &str {
+        &self.access_token
+    }
+
+    /// Record how the upstream request went
+    pub fn record<T>(&self, result: &MindLinkResult<T>) {
+        let outcome = match result {
+            Ok(_) => RequestOutcome::Success,
+            Err(e) => match crate::api_error::upstream_status(e) {
+                Some(upstream) if upstream.status == 429 => RequestOutcome::RateLimited,
+                _ => RequestOutcome::Failed,
+            },
+        };
+        self.account
+            .load()
+            .record(outcome, Instant::now(), self.cooldown);
+    }
+}
+
+impl Drop for AccountLease {
+    fn drop(&mut self) {
+        self.account.load().end();
+    }
+}
+
+/// Usage of one account, for the UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountStatus {
+    pub id: String,
+    pub authenticated: bool,
+    pub in_flight: usize,
+    pub requests_per_minute: usize,
+    pub requests: u64,
+    pub errors: u64,
+    pub rate_limited: u64,
+    /// Seconds until a rate-limited account is used again
+    pub cooldown_secs: Option<u64>,
+    /// Sessions currently kept on this account
+    pub pinned_sessions: usize,
+}
+
+/// The accounts upstream requests are spread across
+#[derive(Debug)]
+pub struct AccountPool {
+    accounts: RwLock<Vec<Arc<PooledAccount>>>,
+    config: RwLock<AccountsConfig>,
+    cursor: AtomicUsize,
+    sessions: Mutex<SessionPins>,
+}
+
+impl AccountPool {
+    pub fn new(config: AccountsConfig) -> Self {
+        Self {
+            accounts: RwLock::new(Vec::new()),
+            config: RwLock::new(config),
+            cursor: AtomicUsize::new(0),
+            sessions: Mutex::new(SessionPins::default()),
+        }
+    }
+
+    /// Directory holding the tokens of the extra accounts, one file per account
+    pub fn accounts_dir() -> MindLinkResult<PathBuf> {
+        dirs::home_dir()
+            .map(|home| home.join(".mindlink").join("accounts"))
+            .ok_or_else(|| MindLinkError::SystemResource {
+                message: "Cannot determine home directory".to_string(),
+                resource_type: "home directory".to_string(),
+                source: None,
+            })
+    }
+
+    /// Token file of the extra account `id`
+    pub fn account_path(id: &str) -> MindLinkResult<PathBuf> {
+        let valid = !id.is_empty()
+            && id != PRIMARY_ACCOUNT
+            && id.len() <= 64
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(MindLinkError::Configuration {
+                message: format!(
+                    "Invalid account name '{}': use letters, digits, '-' and '_'",
+                    id
+                ),
+                config_key: Some("accounts".to_string()),
+                source: None,
+            });
+        }
+        Ok(Self::accounts_dir()?.join(format!("{}.json", id)))
+    }
+
+    /// Add the saved extra accounts that are not in the pool yet
+    pub async fn load_saved(&self) -> MindLinkResult<usize> {
+        let dir = Self::accounts_dir()?;
+        let mut entries = match tokio::fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => {
+                return Err(MindLinkError::FileSystem {
+                    message: "Failed to list saved accounts".to_string(),
+                    path: Some(dir.to_string_lossy().to_string()),
+                    operation: "read directory".to_string(),
+                    source: Some(e.into()),
+                })
+            },
+        };
+
+        let mut loaded = 0;
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            if self.contains(id).await {
+                continue;
+            }
+            let auth = AuthManager::open(path.clone()).await?;
+            self.add(id, Arc::new(RwLock::new(auth))).await;
+            loaded += 1;
+        }
+        Ok(loaded)
+    }
+
+    pub async fn configure(&self, config: AccountsConfig) {
+        *self.config.write().await = config;
+    }
+
+    /// Use `auth` as the primary account, ahead of the extra ones
+    pub async fn set_primary(&self, auth: Arc<RwLock<AuthManager>>) {
+        let mut accounts = self.accounts.write().await;
+        accounts.retain(|account| account.id != PRIMARY_ACCOUNT);
+        accounts.insert(0, PooledAccount::new(PRIMARY_ACCOUNT, auth));
+    }
+
+    /// Add or replace an extra account
+    pub async fn add(&self, id: &str, auth: Arc<RwLock<AuthManager>>) {
+        let mut accounts = self.accounts.write().await;
+        accounts.retain(|account| account.id != id);
+        accounts.push(PooledAccount::new(id, auth));
+    }
+
+    pub async fn remove(&self, id: &str) -> Option<Arc<PooledAccount>> {
+        let mut accounts = self.accounts.write().await;
+        let index = accounts.iter().position(|account| account.id == id)?;
+        Some(accounts.remove(index))
+    }
+
+    pub async fn contains(&self, id: &str) -> bool {
+        self.accounts.read().await.iter().any(|a| a.id == id)
+    }
+
+    /// Upstream requests in flight across all accounts
+    pub async fn in_flight(&self) -> usize {
+        let accounts = self.accounts.read().await.clone();
+        accounts
+            .iter()
+            .map(|account| account.load().in_flight())
+            .sum()
+    }
+
+    /// Concurrent requests the accounts are expected to sustain together,
+    /// given their plans. `None` when the plan of any of them is unknown.
+    pub async fn plan_max_in_flight(&self) -> Option<usize> {
+        let accounts = self.accounts.read().await.clone();
+        let mut quotas = Vec::with_capacity(accounts.len());
+        for account in &accounts {
+            quotas.push(account.auth.read().await.quota()?);
+        }
+        plan::pool_max_in_flight(&quotas)
+    }
+
+    /// How long until an account is available again, when every account is
+    /// cooling down after a rate limit
+    pub async fn saturated_for(&self) -> Option<Duration> {
+        let accounts = self.accounts.read().await.clone();
+        let now = Instant::now();
+        let mut shortest: Option<Duration> = None;
+        for account in &accounts {
+            let left = account.load().cooldown_left(now)?;
+            shortest = Some(shortest.map_or(left, |shortest| shortest.min(left)));
+        }
+        shortest
+    }
+
+    fn sessions(&self) -> std::sync::MutexGuard<'_, SessionPins> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// How long sessions stay pinned, or `None` when they are not
+    async fn session_ttl(&self) -> Option<Duration> {
+        let config = self.config.read().await;
+        config
+            .sticky_sessions
+            .then(|| Duration::from_secs(config.session_ttl_minutes * 60))
+    }
+
+    /// Accounts in the order the next request should try them. A request of
+    /// a pinned `session` tries the session's account first.
+    pub async fn candidates(&self, session: Option<&str>) -> Vec<Arc<PooledAccount>> {
+        let accounts = self.accounts.read().await.clone();
+        let strategy = self.config.read().await.strategy;
+        let cursor = self.cursor.fetch_add(1, Ordering::Relaxed);
+        let now = Instant::now();
+
+        let pinned = match (session, self.session_ttl().await) {
+            (Some(session), Some(ttl)) => self
+                .sessions()
+                .get(session, ttl, now)
+                .and_then(|id| accounts.iter().position(|account| account.id == id)),
+            _ => None,
+        };
+
+        let guards: Vec<_> = accounts.iter().map(|account| account.load()).collect();
+        let loads: Vec<&AccountLoad> = guards.iter().map(|guard| &**guard).collect();
+        let order = prefer(rank(strategy, &loads, cursor, now), pinned, &loads, now);
+        drop(guards);
+
+        order.into_iter().map(|i| accounts[i].clone()).collect()
+    }
+
+    /// Keep `session` on `account_id`. Returns the account the session was
+    /// on before, if it moved.
+    pub async fn pin(&self, session: &str, account_id: &str) -> Option<String> {
+        let ttl = self.session_ttl().await?;
+        self.sessions()
+            .pin(session, account_id, ttl, Instant::now())
+    }
+
+    /// Borrow `account` for a request made with `access_token`
+    pub async fn lease(&self, account: Arc<PooledAccount>, access_token: String) -> AccountLease {
+        let cooldown = Duration::from_secs(self.config.read().await.rate_limit_cooldown_secs);
+        account.load().start(Instant::now());
+        AccountLease {
+            account,
+            access_token,
+            cooldown,
+        }
+    }
+
+    pub async fn status(&self) -> Vec<AccountStatus> {
+        let accounts = self.accounts.read().await.clone();
+        let ttl = self.session_ttl().await;
+        let now = Instant::now();
+        let mut statuses = Vec::with_capacity(accounts.len());
+        for account in accounts {
+            let authenticated = account.auth.read().await.is_authenticated().await;
+            let pinned_sessions = ttl.map_or(0, |ttl| self.sessions().count(&account.id, ttl, now));
+            let mut load = account.load();
+            statuses.push(AccountStatus {
+                id: account.id.clone(),
+                authenticated,
+                in_flight: load.in_flight(),
+                requests_per_minute: load.requests_per_minute(now),
+                requests: load.requests,
+                errors: load.errors,
+                rate_limited: load.rate_limited,
+                cooldown_secs: load.cooldown_left(now).map(|left| left.as_secs().max(1)),
+                pinned_sessions,
+            });
+        }
+        statuses
+    }
+}
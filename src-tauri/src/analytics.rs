@@ -0,0 +1,609 @@
+// Request analytics persisted to SQLite behind a write-behind buffer
+//
+// The request path only ever does a non-blocking channel send. A background
+// flusher batches records into SQLite transactions; the database runs in WAL
+// mode so committed batches survive a crash and are recovered on the next
+// open. When the disk cannot keep up, recording degrades to sampling (each
+// kept record carries its sample weight) and finally to dropping, but it
+// never blocks a request.
+
+use crate::budgets::PeriodBudget;
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::managers::config_manager::AnalyticsConfig;
+use crate::{log_error, log_info, log_warn};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+/// One handled API request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestRecord {
+    pub timestamp: DateTime<Utc>,
+    pub method: String,
+    pub route: String,
+    pub status: u16,
+    pub latency_ms: u64,
+    /// How many requests this record stands for (greater than 1 while sampling)
+    pub sample_weight: u32,
+    /// `x-request-id` of the request, for tracing it through the logs
+    pub request_id: Option<String>,
+    /// Model named in the request, for completion routes
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Estimated tokens consumed by the request
+    #[serde(default)]
+    pub tokens: u64,
+    /// Client address the request came from
+    #[serde(default)]
+    pub client: Option<String>,
+    /// Fingerprint of the bearer key the client presented; never the key itself
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// ISO 639-3 code of the detected prompt language
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Side of a running canary the request was routed through
+    #[serde(default)]
+    pub variant: Option<String>,
+    /// Why the request failed, as told to the client
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Requests and server-side errors (5xx) of one variant
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct VariantStats {
+    pub requests: u64,
+    pub errors: u64,
+}
+
+impl VariantStats {
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.errors as f64 / self.requests as f64
+        }
+    }
+}
+
+/// Token usage of one key, device and model combination
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaAttributionEntry {
+    pub api_key: Option<String>,
+    pub client: Option<String>,
+    pub model: Option<String>,
+    /// Estimated requests, scaled by sample weight
+    pub requests: u64,
+    /// Estimated tokens, scaled by sample weight
+    pub tokens: u64,
+    pub last_seen: DateTime<Utc>,
+    /// Token budgets of the authorized app the key belongs to: limit, used
+    /// and remaining tokens of each
+    #[serde(default)]
+    pub budgets: Vec<PeriodBudget>,
+}
+
+/// Who has been using the account's quota recently
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaAttribution {
+    pub since: DateTime<Utc>,
+    pub total_requests: u64,
+    pub total_tokens: u64,
+    /// Streaming responses in flight right now
+    pub active_streams: i64,
+    /// Heaviest users first
+    pub entries: Vec<QuotaAttributionEntry>,
+}
+
+impl QuotaAttribution {
+    pub fn new(
+        since: DateTime<Utc>,
+        entries: Vec<QuotaAttributionEntry>,
+        active_streams: i64,
+    ) -> Self {
+        Self {
+            since,
+            total_requests: entries.iter().map(|e| e.requests).sum(),
+            total_tokens: entries.iter().map(|e| e.tokens).sum(),
+            active_streams,
+            entries,
+        }
+    }
+}
+
+/// SQLite storage for request records
+#[derive(Debug)]
+pub struct AnalyticsStore {
+    conn: Connection,
+}
+
+impl AnalyticsStore {
+    /// Open (or create) the database. Opening replays any WAL frames left by
+    /// a crash; the checkpoint then folds them into the main database file.
+    pub fn open(path: &Path) -> MindLinkResult<Self> {
+        let conn = Connection::open(path).map_err(|e| db_error("open analytics database", e))?;
+
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))
+            .map_err(|e| db_error("enable WAL", e))?;
+        conn.pragma_update(None, "synchronous", "NORMAL")
+            .map_err(|e| db_error("set synchronous mode", e))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS requests (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp TEXT NOT NULL,
+                method TEXT NOT NULL,
+                route TEXT NOT NULL,
+                status INTEGER NOT NULL,
+                latency_ms INTEGER NOT NULL,
+                sample_weight INTEGER NOT NULL DEFAULT 1,
+                request_id TEXT,
+                model TEXT,
+                tokens INTEGER NOT NULL DEFAULT 0,
+                client TEXT,
+                api_key TEXT,
+                language TEXT,
+                variant TEXT,
+                error TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_requests_timestamp ON requests(timestamp);",
+        )
+        .map_err(|e| db_error("initialize analytics schema", e))?;
+        Self::migrate(&conn)?;
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+            .map_err(|e| db_error("checkpoint recovered WAL", e))?;
+
+        Ok(Self { conn })
+    }
+
+    /// Bring databases created by older versions up to the current schema
+    fn migrate(conn: &Connection) -> MindLinkResult<()> {
+        let added_columns = [
+            ("request_id", "TEXT"),
+            ("model", "TEXT"),
+            ("tokens", "INTEGER NOT NULL DEFAULT 0"),
+            ("client", "TEXT"),
+            ("api_key", "TEXT"),
+            ("language", "TEXT"),
+            ("variant", "TEXT"),
+            ("error", "TEXT"),
+        ];
+
+        for (column, definition) in added_columns {
+            let exists = conn
+                .prepare("SELECT 1 FROM pragma_table_info('requests') WHERE name = ?1")
+                .and_then(|mut stmt| stmt.exists(params![column]))
+                .map_err(|e| db_error("inspect analytics schema", e))?;
+
+            if !exists {
+                conn.execute(
+                    &format!("ALTER TABLE requests ADD COLUMN {} {}", column, definition),
+                    [],
+                )
+                .map_err(|e| db_error("add analytics column", e))?;
+            }
+        }
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_requests_request_id ON requests(request_id)",
+            [],
+        )
+        .map_err(|e| db_error("index request IDs", e))?;
+
+        Ok(())
+    }
+
+    /// Default location: `~/.mindlink/analytics.db`
+    pub fn default_path() -> MindLinkResult<PathBuf> {
+        dirs::home_dir()
+            .map(|home| home.join(".mindlink").join("analytics.db"))
+            .ok_or_else(|| MindLinkError::SystemResource {
+                message: "Cannot determine home directory".to_string(),
+                resource_type: "home directory".to_string(),
+                source: None,
+            })
+    }
+
+    /// Insert a batch of records in a single transaction
+    pub fn insert_batch(&mut self, records: &[RequestRecord]) -> MindLinkResult<usize> {
+        let tx = self
+            .conn
+            .transaction()
+            .map_err(|e| db_error("begin transaction", e))?;
+        {
+            let mut stmt = tx
+                .prepare_cached(
+                    "INSERT INTO requests
+                        (timestamp, method, route, status, latency_ms, sample_weight, request_id,
+                         model, tokens, client, api_key, language, variant, error)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+                )
+                .map_err(|e| db_error("prepare insert", e))?;
+            for record in records {
+                stmt.execute(params![
+                    record.timestamp.to_rfc3339(),
+                    record.method,
+                    record.route,
+                    record.status,
+                    record.latency_ms,
+                    record.sample_weight,
+                    record.request_id,
+                    record.model,
+                    record.tokens,
+                    record.client,
+                    record.api_key,
+                    record.language,
+                    record.variant,
+                    record.error,
+                ])
+                .map_err(|e| db_error("insert request record", e))?;
+            }
+        }
+        tx.commit().map_err(|e| db_error("commit batch", e))?;
+        Ok(records.len())
+    }
+
+    /// Look up the stored record of a request by its `x-request-id`
+    pub fn find_by_request_id(&self, request_id: &str) -> MindLinkResult<Option<RequestRecord>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT timestamp, method, route, status, latency_ms, sample_weight, request_id,
+                        model, tokens, client, api_key, language, variant, error
+                 FROM requests WHERE request_id = ?1 LIMIT 1",
+            )
+            .map_err(|e| db_error("prepare request lookup", e))?;
+
+        let mut rows = stmt
+            .query(params![request_id])
+            .map_err(|e| db_error("look up request", e))?;
+
+        let Some(row) = rows.next().map_err(|e| db_error("read request", e))? else {
+            return Ok(None);
+        };
+
+        read_record(row).map(Some)
+    }
+
+    /// The `limit` most recently stored records, newest first
+    pub fn recent(&self, limit: usize) -> MindLinkResult<Vec<RequestRecord>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT timestamp, method, route, status, latency_ms, sample_weight, request_id,
+                        model, tokens, client, api_key, language, variant, error
+                 FROM requests ORDER BY timestamp DESC LIMIT ?1",
+            )
+            .map_err(|e| db_error("prepare recent requests", e))?;
+
+        let mut rows = stmt
+            .query(params![limit as i64])
+            .map_err(|e| db_error("query recent requests", e))?;
+
+        let mut records = Vec::new();
+        while let Some(row) = rows.next().map_err(|e| db_error("read request", e))? {
+            records.push(read_record(row)?);
+        }
+        Ok(records)
+    }
+
+    /// Token usage since `since`, grouped by key, device and model, heaviest first.
+    /// Only requests that named a model are counted.
+    pub fn quota_attribution(
+        &self,
+        since: DateTime<Utc>,
+    ) -> MindLinkResult<Vec<QuotaAttributionEntry>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT api_key, client, model, SUM(sample_weight), SUM(tokens * sample_weight),
+                        MAX(timestamp)
+                 FROM requests
+                 WHERE timestamp >= ?1 AND model IS NOT NULL
+                 GROUP BY api_key, client, model
+                 ORDER BY 5 DESC, 4 DESC",
+            )
+            .map_err(|e| db_error("prepare attribution query", e))?;
+
+        let rows = stmt
+            .query_map(params![since.to_rfc3339()], |row| {
+                Ok(QuotaAttributionEntry {
+                    api_key: row.get(0)?,
+                    client: row.get(1)?,
+                    model: row.get(2)?,
+                    requests: row.get::<_, i64>(3)? as u64,
+                    tokens: row.get::<_, i64>(4)? as u64,
+                    last_seen: parse_timestamp(&row.get::<_, String>(5)?),
+                    budgets: Vec::new(),
+                })
+            })
+            .map_err(|e| db_error("query token attribution", e))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| db_error("decode token attribution", e))
+    }
+
+    /// Requests and errors per variant since `since`, scaled by sample weight
+    pub fn variant_stats(
+        &self,
+        since: DateTime<Utc>,
+    ) -> MindLinkResult<HashMap<String, VariantStats>> {
+        let mut stmt = self
+            .conn
+            .prepare(
+                "SELECT variant, SUM(sample_weight),
+                        SUM(CASE WHEN status >= 500 THEN sample_weight ELSE 0 END)
+                 FROM requests
+                 WHERE timestamp >= ?1 AND variant IS NOT NULL
+                 GROUP BY variant",
+            )
+            .map_err(|e| db_error("prepare variant query", e))?;
+
+        let rows = stmt
+            .query_map(params![since.to_rfc3339()], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    VariantStats {
+                        requests: row.get::<_, i64>(1)? as u64,
+                        errors: row.get::<_, i64>(2)? as u64,
+                    },
+                ))
+            })
+            .map_err(|e| db_error("query variant stats", e))?;
+
+        rows.collect::<Result<HashMap<_, _>, _>>()
+            .map_err(|e| db_error("decode variant stats", e))
+    }
+
+    /// Estimated number of requests stored, scaling sampled records by their weight
+    pub fn estimated_requests(&self) -> MindLinkResult<u64> {
+        self.conn
+            .query_row(
+                "SELECT COALESCE(SUM(sample_weight), 0) FROM requests",
+                [],
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|total| total as u64)
+            .map_err(|e| db_error("count requests", e))
+    }
+}
+
+/// Decode a row selected with the full column list, in table order
+fn read_record(row: &rusqlite::Row<'_>) -> MindLinkResult<RequestRecord> {
+    let read = |e: rusqlite::Error| db_error("decode request", e);
+    let timestamp: String = row.get(0).map_err(read)?;
+    Ok(RequestRecord {
+        timestamp: parse_timestamp(&timestamp),
+        method: row.get(1).map_err(read)?,
+        route: row.get(2).map_err(read)?,
+        status: row.get(3).map_err(read)?,
+        latency_ms: row.get(4).map_err(read)?,
+        sample_weight: row.get(5).map_err(read)?,
+        request_id: row.get(6).map_err(read)?,
+        model: row.get(7).map_err(read)?,
+        tokens: row.get(8).map_err(read)?,
+        client: row.get(9).map_err(read)?,
+        api_key: row.get(10).map_err(read)?,
+        language: row.get(11).map_err(read)?,
+        variant: row.get(12).map_err(read)?,
+        error: row.get(13).map_err(read)?,
+    })
+}
+
+fn parse_timestamp(value: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+}
+
+fn db_error(operation: &str, e: rusqlite::Error) -> MindLinkError {
+    MindLinkError::SystemResource {
+        message: format!("Analytics database failed to {}", operation),
+        resource_type: "analytics database".to_string(),
+        source: Some(e.into()),
+    }
+}
+
+/// Counters describing the recorder, shared across server restarts
+#[derive(Debug, Default)]
+pub struct AnalyticsStats {
+    recorded: AtomicU64,
+    sampled_out: AtomicU64,
+    dropped: AtomicU64,
+    flushed: AtomicU64,
+    failed_flushes: AtomicU64,
+    pending: AtomicU64,
+    /// While set, records are only written once a full batch is buffered
+    paused: AtomicBool,
+}
+
+/// Point-in-time view of [`AnalyticsStats`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsStatsSnapshot {
+    pub recorded: u64,
+    pub sampled_out: u64,
+    pub dropped: u64,
+    pub flushed: u64,
+    pub failed_flushes: u64,
+    pub pending: u64,
+    pub paused: bool,
+}
+
+impl AnalyticsStats {
+    pub fn snapshot(&self) -> AnalyticsStatsSnapshot {
+        AnalyticsStatsSnapshot {
+            recorded: self.recorded.load(Ordering::Relaxed),
+            sampled_out: self.sampled_out.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            flushed: self.flushed.load(Ordering::Relaxed),
+            failed_flushes: self.failed_flushes.load(Ordering::Relaxed),
+            pending: self.pending.load(Ordering::Relaxed),
+            paused: self.paused.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Pause or resume the periodic flush, e.g. to save power on battery
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+}
+
+/// Non-blocking handle used on the request path. The flusher exits, after a
+/// final flush, once every clone of the recorder has been dropped.
+#[derive(Debug, Clone)]
+pub struct AnalyticsRecorder {
+    tx: mpsc::Sender<RequestRecord>,
+    capacity: usize,
+    sample_rate: u32,
+    sample_counter: Arc<AtomicU64>,
+    stats: Arc<AnalyticsStats>,
+}
+
+impl AnalyticsRecorder {
+    /// Open the store and start the background flusher
+    pub fn spawn(
+        path: &Path,
+        config: &AnalyticsConfig,
+        stats: Arc<AnalyticsStats>,
+    ) -> MindLinkResult<Self> {
+        let store = AnalyticsStore::open(path)?;
+        Ok(Self::spawn_with_store(store, config, stats))
+    }
+
+    pub fn spawn_with_store(
+        store: AnalyticsStore,
+        config: &AnalyticsConfig,
+        stats: Arc<AnalyticsStats>,
+    ) -> Self {
+        let (tx, rx) = mpsc::channel(config.buffer_capacity);
+
+        tokio::spawn(run_flusher(
+            rx,
+            Arc::new(Mutex::new(store)),
+            config.batch_size,
+            Duration::from_millis(config.flush_interval_ms),
+            stats.clone(),
+        ));
+
+        Self {
+            tx,
+            capacity: config.buffer_capacity,
+            sample_rate: config.pressure_sample_rate.max(1),
+            sample_counter: Arc::new(AtomicU64::new(0)),
+            stats,
+        }
+    }
+
+    /// Queue a record without ever waiting. Above half the buffer capacity
+    /// only one in `pressure_sample_rate` records is kept; a full buffer drops.
+    pub fn record(&self, mut record: RequestRecord) {
+        let pending = self.capacity - self.tx.capacity();
+
+        if pending >= self.capacity / 2 && self.sample_rate > 1 {
+            let n = self.sample_counter.fetch_add(1, Ordering::Relaxed);
+            if n % u64::from(self.sample_rate) != 0 {
+                self.stats.sampled_out.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+            record.sample_weight = self.sample_rate;
+        }
+
+        match self.tx.try_send(record) {
+            Ok(()) => {
+                self.stats.recorded.fetch_add(1, Ordering::Relaxed);
+                self.stats.pending.fetch_add(1, Ordering::Relaxed);
+            },
+            Err(TrySendError::Full(_)) | Err(TrySendError::Closed(_)) => {
+                self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+            },
+        }
+    }
+
+    pub fn stats(&self) -> AnalyticsStatsSnapshot {
+        self.stats.snapshot()
+    }
+}
+
+async fn run_flusher(
+    mut rx: mpsc::Receiver<RequestRecord>,
+    store: Arc<Mutex<AnalyticsStore>>,
+    batch_size: usize,
+    flush_interval: Duration,
+    stats: Arc<AnalyticsStats>,
+) {
+    let mut buffer = Vec::with_capacity(batch_size);
+    let mut ticker = tokio::time::interval(flush_interval);
+
+    loop {
+        tokio::select! {
+            received = rx.recv() => match received {
+                Some(record) => {
+                    buffer.push(record);
+                    if buffer.len() >= batch_size {
+                        flush(&store, &mut buffer, &stats).await;
+                    }
+                },
+                None => {
+                    flush(&store, &mut buffer, &stats).await;
+                    log_info!("Analytics", "Analytics recorder stopped");
+                    return;
+                },
+            },
+            _ = ticker.tick() => {
+                if !buffer.is_empty() && !stats.paused.load(Ordering::Relaxed) {
+                    flush(&store, &mut buffer, &stats).await;
+                }
+            },
+        }
+    }
+}
+
+/// Write the buffered records on the blocking pool. A failed batch is dropped
+/// rather than retried so a broken disk cannot grow memory without bound.
+async fn flush(
+    store: &Arc<Mutex<AnalyticsStore>>,
+    buffer: &mut Vec<RequestRecord>,
+    stats: &Arc<AnalyticsStats>,
+) {
+    if buffer.is_empty() {
+        return;
+    }
+
+    let batch = std::mem::take(buffer);
+    let count = batch.len() as u64;
+    let store = store.clone();
+
+    let result = tokio::task::spawn_blocking(move || match store.lock() {
+        Ok(mut store) => store.insert_batch(&batch),
+        Err(_) => Err(MindLinkError::Internal {
+            message: "Analytics store lock poisoned".to_string(),
+            component: Some("Analytics".to_string()),
+            source: None,
+        }),
+    })
+    .await;
+
+    stats.pending.fetch_sub(count, Ordering::Relaxed);
+
+    match result {
+        Ok(Ok(_)) => {
+            stats.flushed.fetch_add(count, Ordering::Relaxed);
+        },
+        Ok(Err(e)) => {
+            stats.failed_flushes.fetch_add(1, Ordering::Relaxed);
+            stats.dropped.fetch_add(count, Ordering::Relaxed);
+            log_error!("Analytics", e);
+        },
+        Err(e) => {
+            stats.failed_flushes.fetch_add(1, Ordering::Relaxed);
+            stats.dropped.fetch_add(count, Ordering::Relaxed);
+            log_warn!("Analytics", format!("Analytics flush task failed: {}", e));
+        },
+    }
+}
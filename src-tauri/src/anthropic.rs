@@ -0,0 +1,201 @@
+// Claude subscriptions behind the OpenAI API
+//
+// With a Claude account signed in, chat completion requests for `claude-*`
+// models are sent to Anthropic's Messages API with the account's OAuth
+// access token instead of to ChatGPT. Requests are translated into Messages
+// requests, and replies and stream events back into chat completions and
+// chunks, so clients talk to both subscriptions through the same API.
+
+use crate::managers::server_manager::{ChatCompletionRequest, Usage};
+use serde_json::{json, Value};
+
+pub const MESSAGES_URL: &str = "https://api.anthropic.com/v1/messages";
+
+pub const API_VERSION: &str = "2023-06-01";
+
+/// Beta that lets OAuth access tokens call the Messages API
+pub const OAUTH_BETA: &str = "oauth-2025-04-20";
+
+/// Output limit of requests that set none, which the Messages API requires
+pub const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Models listed while a Claude account is signed in
+pub const MODELS: [&str; 3] = ["claude-sonnet-4-5", "claude-opus-4-1", "claude-haiku-4-5"];
+
+/// Whether requests for `model` go to Anthropic
+pub fn is_claude_model(model: &str) -> bool {
+    model.starts_with("claude-")
+}
+
+/// Whether `request` offers the model tools, which are not translated into
+/// the Messages API
+pub fn uses_tools(request: &ChatCompletionRequest) -> bool {
+    ["tools", "functions"].iter().any(|name| {
+        request
+            .other
+            .get(*name)
+            .and_then(Value::as_array)
+            .is_some_and(|tools| !tools.is_empty())
+    })
+}
+
+/// Messages API request for `request` to `model`, with `system_prompt` ahead
+/// of the request's own system messages. Tool messages must have been
+/// flattened to text already.
+pub fn messages_request(
+    request: &ChatCompletionRequest,
+    model: &str,
+    system_prompt: Option<&str>,
+    stream: bool,
+) -> Value {
+    let mut system: Vec<&str> = system_prompt.into_iter().collect();
+    let mut messages = Vec::with_capacity(request.messages.len());
+    for message in &request.messages {
+        match message.role.as_str() {
+            "system" | "developer" => system.push(&message.content),
+            role => messages.push(json!({
+                "role": if role == "assistant" { "assistant" } else { "user" },
+                "content": message.content,
+            })),
+        }
+    }
+
+    let max_tokens = request
+        .max_tokens
+        .or_else(|| {
+            request
+                .other
+                .get("max_completion_tokens")
+                .and_then(Value::as_u64)
+                .and_then(|tokens| u32::try_from(tokens).ok())
+        })
+        .unwrap_or(DEFAULT_MAX_TOKENS);
+
+    let mut body = json!({
+        "model": model,
+        "messages": messages,
+        "max_tokens": max_tokens,
+        "stream": stream,
+    });
+    if !system.is_empty() {
+        body["system"] = json!(system.join("\n\n"));
+    }
+    if let Some(temperature) = request.temperature {
+        body["temperature"] = json!(temperature);
+    }
+    if let Some(top_p) = request.other.get("top_p") {
+        body["top_p"] = top_p.clone();
+    }
+    match request.other.get("stop") {
+        Some(Value::String(stop)) => body["stop_sequences"] = json!([stop]),
+        Some(stop @ Value::Array(_)) => body["stop_sequences"] = stop.clone(),
+        _ => {},
+    }
+    body
+}
+
+/// OpenAI finish reason of an Anthropic `stop_reason`
+pub fn finish_reason(stop_reason: Option<&str>) -> &'static str {
+    match stop_reason {
+        Some("max_tokens") => "length",
+        Some("tool_use") => "tool_calls",
+        Some("refusal") => "content_filter",
+        _ => "stop",
+    }
+}
+
+/// Text of a Messages API reply, its text blocks joined
+pub fn response_text(response: &Value) -> String {
+    response
+        .get("content")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|block| block.get("type").and_then(Value::as_str) == Some("text"))
+        .filter_map(|block| block.get("text").and_then(Value::as_str))
+        .collect()
+}
+
+/// Token counts of a Messages API reply
+pub fn response_usage(response: &Value) -> Option<Usage> {
+    let usage = response.get("usage")?;
+    let count = |name: &str| {
+        usage
+            .get(name)
+            .and_then(Value::as_u64)
+            .and_then(|tokens| u32::try_from(tokens).ok())
+            .unwrap_or(0)
+    };
+    let prompt_tokens = count("input_tokens");
+    let completion_tokens = count("output_tokens");
+    Some(Usage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens + completion_tokens,
+    })
+}
+
+/// Take the token counts of a Messages API stream event into `usage`. The
+/// prompt is counted when the message starts and the output as it ends.
+pub fn count_stream_usage(event: &Value, usage: &mut Usage) {
+    let tokens = |pointer: &str| {
+        event
+            .pointer(pointer)
+            .and_then(Value::as_u64)
+            .and_then(|tokens| u32::try_from(tokens).ok())
+    };
+    match event.get("type").and_then(Value::as_str) {
+        Some("message_start") => {
+            if let Some(tokens) = tokens("/message/usage/input_tokens") {
+                usage.prompt_tokens = tokens;
+            }
+        },
+        Some("message_delta") => {
+            if let Some(tokens) = tokens("/usage/output_tokens") {
+                usage.completion_tokens = tokens;
+            }
+        },
+        _ => return,
+    }
+    usage.total_tokens = usage.prompt_tokens + usage.completion_tokens;
+}
+
+/// What a Messages API stream event means for the chat completion stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamEvent {
+    /// More text of the reply
+    Text(String),
+    /// The reply is complete, with this OpenAI finish reason
+    Finished(&'static str),
+    /// The stream failed upstream
+    Error(String),
+}
+
+/// The meaning of `event`, `None` for events without one such as pings
+pub fn stream_event(event: &Value) -> Option<StreamEvent> {
+    match event.get("type").and_then(Value::as_str)? {
+        "content_block_delta" => {
+            let delta = event.get("delta")?;
+            (delta.get("type").and_then(Value::as_str) == Some("text_delta"))
+                .then(|| delta.get("text").and_then(Value::as_str))
+                .flatten()
+                .map(|text| StreamEvent::Text(text.to_string()))
+        },
+        "message_delta" => {
+            let stop_reason = event
+                .get("delta")
+                .and_then(|delta| delta.get("stop_reason"))
+                .and_then(Value::as_str);
+            stop_reason.map(|reason| StreamEvent::Finished(finish_reason(Some(reason))))
+        },
+        "error" => Some(StreamEvent::Error(
+            event
+                .get("error")
+                .and_then(|error| error.get("message"))
+                .and_then(Value::as_str)
+                .unwrap_or("Claude stream failed")
+                .to_string(),
+        )),
+        _ => None,
+    }
+}
@@ -0,0 +1,181 @@
+// OpenAI-compatible error responses
+//
+// Clients branch on `error.type` and `error.code`, so every API error uses
+// OpenAI's schema with the type and code OpenAI uses for the same status.
+// Failures of the ChatGPT backend keep their upstream status: the request
+// functions attach it to the returned error as an `UpstreamStatus` source, and
+// `ApiError::from_error` maps it to what OpenAI would have answered.
+
+use crate::error::MindLinkError;
+use crate::middleware::analytics::FailureReason;
+use axum::{
+    body::Body,
+    http::{Response, StatusCode},
+    response::{IntoResponse, Json},
+};
+use serde_json::{json, Value};
+use std::fmt;
+
+/// Upstream bodies are only kept this long, for messages and logs
+const MAX_BODY_CHARS: usize = 2000;
+
+/// A non-success response from the ChatGPT backend
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpstreamStatus {
+    pub status: u16,
+    pub body: String,
+}
+
+impl UpstreamStatus {
+    pub fn new(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            body: body.chars().take(MAX_BODY_CHARS).collect(),
+        }
+    }
+
+    /// The backend's own explanation, when it sent one
+    pub fn detail(&self) -> Option<String> {
+        let body: Value = serde_json::from_str(&self.body).ok()?;
+        match body.get("detail")? {
+            Value::String(detail) => Some(detail.clone()),
+            detail => detail
+                .get("message")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+        }
+    }
+}
+
+impl fmt::Display for UpstreamStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ChatGPT backend returned status {}", self.status)
+    }
+}
+
+impl std::error::Error for UpstreamStatus {}
+
+/// The upstream response behind an error, if it came from one
+pub fn upstream_status(error: &MindLinkError) -> Option<&UpstreamStatus> {
+    let source = match error {
+        MindLinkError::Network { source, .. } => source.as_ref()?,
+        _ => return None,
+    };
+    source.downcast_ref::<UpstreamStatus>()
+}
+
+/// An error in OpenAI's format: `{"error": {"message", "type", "param", "code"}}`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub message: String,
+    pub error_type: &'static str,
+    pub code: Option<&'static str>,
+    pub param: Option<String>,
+}
+
+/// OpenAI's error type and code for a status
+fn classify(status: StatusCode) -> (&'static str, Option<&'static str>) {
+    match status.as_u16() {
+        401 => ("invalid_request_error", Some("invalid_api_key")),
+        403 => ("invalid_request_error", Some("permission_denied")),
+        404 => ("invalid_request_error", Some("not_found")),
+        413 => ("invalid_request_error", Some("request_too_large")),
+        429 => ("requests", Some("rate_limit_exceeded")),
+        500..=599 => ("server_error", None),
+        _ => ("invalid_request_error", None),
+    }
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        let (error_type, code) = classify(status);
+        Self {
+            status,
+            message: message.into(),
+            error_type,
+            code,
+            param: None,
+        }
+    }
+
+    /// Name the request field the error is about
+    pub fn with_param(mut self, param: &str) -> Self {
+        self.param = Some(param.to_string());
+        self
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    /// What OpenAI answers for a failure the ChatGPT backend reported
+    pub fn from_upstream(upstream: &UpstreamStatus) -> Self {
+        let status =
+            StatusCode::from_u16(upstream.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        let detail = upstream.detail();
+        let message = |fallback: &str| detail.clone().unwrap_or_else(|| fallback.to_string());
+
+        match upstream.status {
+            401 => Self::new(
+                status,
+                "The ChatGPT session has expired or was revoked. Sign in to MindLink again.",
+            ),
+            403 => Self::new(status, message("The ChatGPT backend refused the request")),
+            404 => Self::new(
+                status,
+                message("The model does not exist or is not available"),
+            )
+            .with_code("model_not_found")
+            .with_param("model"),
+            429 => {
+                let body = upstream.body.to_lowercase();
+                if body.contains("usage_limit") || body.contains("quota") {
+                    Self {
+                        error_type: "insufficient_quota",
+                        code: Some("insufficient_quota"),
+                        ..Self::new(status, message("The ChatGPT usage limit has been reached"))
+                    }
+                } else {
+                    Self::new(status, message("Rate limit reached for requests"))
+                }
+            },
+            400..=499 => Self::new(status, message("The ChatGPT backend rejected the request")),
+            _ => Self::new(
+                status,
+                message("The ChatGPT backend had an error while processing the request"),
+            ),
+        }
+    }
+
+    /// The response for a failed upstream call: mapped from the upstream
+    /// status when there was one, `502 Bad Gateway` when it was unreachable
+    pub fn from_error(error: &MindLinkError) -> Self {
+        match upstream_status(error) {
+            Some(upstream) => Self::from_upstream(upstream),
+            None => Self::new(StatusCode::BAD_GATEWAY, error.user_message()),
+        }
+    }
+
+    pub fn body(&self) -> Value {
+        json!({
+            "error": {
+                "message": self.message,
+                "type": self.error_type,
+                "param": self.param,
+                "code": self.code,
+            }
+        })
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response<Body> {
+        let mut response = (self.status, Json(self.body())).into_response();
+        response
+            .extensions_mut()
+            .insert(FailureReason(self.message));
+        response
+    }
+}
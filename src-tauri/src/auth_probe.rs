@@ -0,0 +1,196 @@
+// Periodic probe of the ChatGPT session
+//
+// Token expiry is only known locally, so a session revoked upstream used to
+// show up as failing client requests. The probe calls a cheap account
+// endpoint on its own schedule, without holding the auth lock during the
+// request, and its result feeds the `auth` component of the health model and
+// with it the tray. Whether the probe got an answer at all also tells if
+// ChatGPT is reachable, which is the `upstream` component. Status queries
+// reuse a recent result, or probe themselves, so a revoked session does not
+// show as signed in.
+
+use crate::health::ComponentHealth;
+use crate::proxy;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// Account endpoint answered without touching any conversation
+pub const AUTH_PROBE_URL: &str = "https://chatgpt.com/backend-api/me";
+
+/// How long a result answers status queries before the session is probed
+/// again
+pub const STATUS_MAX_AGE: Duration = Duration::from_secs(300);
+
+/// What the backend said about the session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+pub enum ProbeOutcome {
+    Valid,
+    /// The backend refused the access token
+    Rejected {
+        status: u16,
+    },
+    /// The session could not be verified either way
+    Unreachable {
+        reason: String,
+    },
+}
+
+impl ProbeOutcome {
+    pub fn for_status(status: u16) -> Self {
+        match status {
+            200..=299 => ProbeOutcome::Valid,
+            401 | 403 => ProbeOutcome::Rejected { status },
+            _ => ProbeOutcome::Unreachable {
+                reason: format!("ChatGPT returned status {}", status),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProbeResult {
+    pub outcome: ProbeOutcome,
+    /// The probed token, so a result is dropped once the user signs in again
+    token_fingerprint: u64,
+    probed_at: Instant,
+}
+
+impl ProbeResult {
+    pub fn new(outcome: ProbeOutcome, access_token: &str) -> Self {
+        Self {
+            outcome,
+            token_fingerprint: fingerprint(access_token),
+            probed_at: Instant::now(),
+        }
+    }
+
+    /// Whether this result was obtained with `access_token`
+    pub fn applies_to(&self, access_token: &str) -> bool {
+        self.token_fingerprint == fingerprint(access_token)
+    }
+
+    /// Whether this result is younger than `max_age`
+    pub fn is_fresh(&self, max_age: Duration) -> bool {
+        self.probed_at.elapsed() < max_age
+    }
+}
+
+fn fingerprint(access_token: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    access_token.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Client for the probe, separate from the one serving requests
+pub fn probe_client() -> Client {
+    proxy::apply(Client::builder())
+        .timeout(Duration::from_secs(10))
+        .user_agent("MindLink/1.0")
+        .build()
+        .unwrap_or_default()
+}
+
+/// Ask `url` whether `access_token` is still accepted
+pub async fn probe(client: &Client, url: &str, access_token: &str) -> ProbeResult {
+    let outcome = match client.get(url).bearer_auth(access_token).send().await {
+        Ok(response) => ProbeOutcome::for_status(response.status().as_u16()),
+        Err(e) => ProbeOutcome::Unreachable {
+            reason: e.to_string(),
+        },
+    };
+    ProbeResult::new(outcome, access_token)
+}
+
+/// What the backend says about `access_token`: the `latest` result when it
+/// is for this token and younger than `max_age`, otherwise a new probe, which
+/// becomes the latest result. No lock is held during the request.
+pub async fn cached_probe(
+    latest: &RwLock<Option<ProbeResult>>,
+    client: &Client,
+    url: &str,
+    access_token: &str,
+    max_age: Duration,
+) -> ProbeOutcome {
+    if let Some(result) = latest
+        .read()
+        .await
+        .as_ref()
+        .filter(|result| result.applies_to(access_token) && result.is_fresh(max_age))
+    {
+        return result.outcome.clone();
+    }
+
+    let result = probe(client, url, access_token).await;
+    let outcome = result.outcome.clone();
+    *latest.write().await = Some(result);
+    outcome
+}
+
+/// Health of the `auth` component from the local token state and the latest
+/// probe of the current token
+pub fn auth_health(
+    has_tokens: bool,
+    authenticated: bool,
+    probe: Option<&ProbeOutcome>,
+) -> ComponentHealth {
+    if !has_tokens {
+        return ComponentHealth::down("auth", "Not logged in to ChatGPT", true);
+    }
+    if !authenticated {
+        return ComponentHealth::down(
+            "auth",
+            "ChatGPT session expired - please log in again",
+            true,
+        );
+    }
+
+    match probe {
+        Some(ProbeOutcome::Rejected { .. }) => ComponentHealth::down(
+            "auth",
+            "ChatGPT rejected the session - please log in again",
+            true,
+        ),
+        Some(ProbeOutcome::Unreachable { reason }) => ComponentHealth::degraded(
+            "auth",
+            format!("Could not verify the ChatGPT session: {}", reason),
+            true,
+        ),
+        Some(ProbeOutcome::Valid) | None => ComponentHealth::ok("auth", true),
+    }
+}
+
+/// `health` with the expiry of the current access token
+pub fn with_token_expiry(
+    health: ComponentHealth,
+    expires_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> ComponentHealth {
+    health
+        .with_detail("expires_at", expires_at)
+        .with_detail("expires_in_secs", (expires_at - now).num_seconds())
+}
+
+/// Health of the `upstream` component: whether the latest probe reached
+/// ChatGPT, whatever it said about the session
+pub fn upstream_health(probe: Option<&ProbeOutcome>) -> ComponentHealth {
+    let health = match probe {
+        Some(ProbeOutcome::Unreachable { reason }) => ComponentHealth::down(
+            "upstream",
+            format!("ChatGPT is unreachable: {}", reason),
+            false,
+        ),
+        Some(_) => ComponentHealth::ok("upstream", false),
+        None => {
+            let mut health = ComponentHealth::ok("upstream", false);
+            health.reason = Some("Not probed yet".to_string());
+            health
+        },
+    };
+    health.with_detail("probe_url", AUTH_PROBE_URL)
+}
@@ -0,0 +1,84 @@
+// Authorized apps and their model restrictions
+//
+// Each app registered in the settings gets its own API key. Requests that
+// present an app's key are held to that app's model: a request for a model the
+// app may not use is either moved onto the app's model or rejected, depending
+// on whether the app lists any additional models. Apps may also have daily
+// and monthly token budgets, enforced by `middleware::budget`.
+
+use axum::http::{header::AUTHORIZATION, HeaderMap};
+use serde::{Deserialize, Serialize};
+
+/// Header Azure OpenAI clients send their key in
+const AZURE_API_KEY: &str = "api-key";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthorizedApp {
+    pub id: String,
+    pub name: String,
+    /// Model the app uses; requests for other models are moved onto it
+    pub model: String,
+    pub created_at: String,
+    /// Bearer key the app authenticates with. Apps saved before keys existed
+    /// have none and are not matched until a key is generated.
+    #[serde(default)]
+    pub api_key: String,
+    /// Further models the app may request. When set, requests for any other
+    /// model are rejected instead of being moved onto `model`.
+    #[serde(default)]
+    pub allowed_models: Vec<String>,
+    /// System prompt for the app's requests, in place of the configured default
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// Tokens the app may use per UTC day
+    #[serde(default)]
+    pub daily_token_budget: Option<u64>,
+    /// Tokens the app may use per UTC calendar month
+    #[serde(default)]
+    pub monthly_token_budget: Option<u64>,
+}
+
+impl AuthorizedApp {
+    /// The model a request from this app runs on, or why it is refused
+    pub fn model_for(&self, requested: &str) -> Result<String, String> {
+        if requested == self.model || self.allowed_models.iter().any(|m| m == requested) {
+            return Ok(requested.to_string());
+        }
+
+        if self.allowed_models.is_empty() {
+            Ok(self.model.clone())
+        } else {
+            Err(format!(
+                "Model '{}' is not allowed for app '{}'",
+                requested, self.name
+            ))
+        }
+    }
+
+    pub fn has_token_budget(&self) -> bool {
+        self.daily_token_budget.is_some() || self.monthly_token_budget.is_some()
+    }
+}
+
+/// New key for an app, shaped like the keys OpenAI clients expect
+pub fn generate_api_key() -> String {
+    format!("sk-mindlink-{}", uuid::Uuid::new_v4().simple())
+}
+
+/// The bearer token a client presented, if any. Azure OpenAI clients send
+/// theirs as an `api-key` header instead.
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    let token = match headers.get(AUTHORIZATION) {
+        Some(authorization) => authorization.to_str().ok()?.strip_prefix("Bearer ")?,
+        None => headers.get(AZURE_API_KEY)?.to_str().ok()?,
+    }
+    .trim();
+
+    Some(token).filter(|token| !token.is_empty())
+}
+
+/// The app a key belongs to
+pub fn find_app<'a>(apps: &'a [AuthorizedApp], api_key: &str) -> Option<&'a AuthorizedApp> {
+    apps.iter()
+        .find(|app| !app.api_key.is_empty() && app.api_key == api_key)
+}
@@ -0,0 +1,756 @@
+// OpenAI-compatible batch API
+//
+// Clients upload a JSONL file of chat completion requests through `/v1/files`
+// and submit it with `/v1/batches`. Batches run in the background, one request
+// at a time with a minimum interval between requests, so they never compete
+// with interactive traffic for the account's rate limit. Rate-limited and
+// failed upstream requests are retried with exponential backoff.
+//
+// Files and batch state live under `~/.mindlink/batches`, and unfinished
+// batches resume where they stopped when the server starts again. A crash
+// between writing a result and recording it can repeat that one request.
+
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::log_warn;
+use crate::managers::config_manager::BatchConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// The only endpoint batches can target
+pub const CHAT_COMPLETIONS_ENDPOINT: &str = "/v1/chat/completions";
+/// The only completion window OpenAI offers
+pub const COMPLETION_WINDOW: &str = "24h";
+const COMPLETION_WINDOW_SECS: i64 = 24 * 60 * 60;
+/// Largest accepted upload, as in the OpenAI batch API
+pub const MAX_FILE_BYTES: usize = 100 * 1024 * 1024;
+/// Purpose of uploaded batch input files
+pub const BATCH_PURPOSE: &str = "batch";
+const OUTPUT_PURPOSE: &str = "batch_output";
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FileObject {
+    pub id: String,
+    pub object: String,
+    pub bytes: u64,
+    pub created_at: i64,
+    pub filename: String,
+    pub purpose: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    Validating,
+    Failed,
+    InProgress,
+    Finalizing,
+    Completed,
+    Expired,
+    Cancelling,
+    Cancelled,
+}
+
+impl BatchStatus {
+    /// Whether the batch will not run any more requests
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            BatchStatus::Failed
+                | BatchStatus::Completed
+                | BatchStatus::Expired
+                | BatchStatus::Cancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RequestCounts {
+    pub total: usize,
+    pub completed: usize,
+    pub failed: usize,
+}
+
+/// Problem with an input file, reported when a batch fails validation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BatchError {
+    pub code: String,
+    pub message: String,
+    /// 1-based line of the input file
+    pub line: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchErrors {
+    pub object: String,
+    pub data: Vec<BatchError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Batch {
+    pub id: String,
+    pub object: String,
+    pub endpoint: String,
+    pub errors: Option<BatchErrors>,
+    pub input_file_id: String,
+    pub completion_window: String,
+    pub status: BatchStatus,
+    pub output_file_id: Option<String>,
+    pub error_file_id: Option<String>,
+    pub created_at: i64,
+    pub in_progress_at: Option<i64>,
+    pub expires_at: i64,
+    pub finalizing_at: Option<i64>,
+    pub completed_at: Option<i64>,
+    pub failed_at: Option<i64>,
+    pub expired_at: Option<i64>,
+    pub cancelling_at: Option<i64>,
+    pub cancelled_at: Option<i64>,
+    pub request_counts: RequestCounts,
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+/// Body of `POST /v1/batches`
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateBatchRequest {
+    pub input_file_id: String,
+    pub endpoint: String,
+    pub completion_window: String,
+    #[serde(default)]
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+/// One line of a batch input file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchRequestLine {
+    pub custom_id: String,
+    pub method: String,
+    pub url: String,
+    pub body: Value,
+}
+
+/// A batch as persisted, with the app that created it so that its model
+/// restrictions and system prompt still apply when the batch resumes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredBatch {
+    batch: Batch,
+    app_id: Option<String>,
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn line_error(line: usize, code: &str, message: impl Into<String>) -> BatchError {
+    BatchError {
+        code: code.to_string(),
+        message: message.into(),
+        line: Some(line),
+    }
+}
+
+/// Parse and validate a batch input file. Every problem is reported, with
+/// its line, so clients can fix a file in one go.
+pub fn parse_input(
+    content: &str,
+    endpoint: &str,
+) -> Result<Vec<BatchRequestLine>, Vec<BatchError>> {
+    let mut requests = Vec::new();
+    let mut errors = Vec::new();
+    let mut custom_ids = HashSet::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let number = index + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request: BatchRequestLine = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => {
+                errors.push(line_error(number, "invalid_json_line", e.to_string()));
+                continue;
+            },
+        };
+
+        if !request.method.eq_ignore_ascii_case("POST") {
+            errors.push(line_error(
+                number,
+                "invalid_method",
+                "Only POST is supported",
+            ));
+        }
+        if request.url != endpoint {
+            errors.push(line_error(
+                number,
+                "mismatched_endpoint",
+                format!(
+                    "URL {} does not match the batch endpoint {}",
+                    request.url, endpoint
+                ),
+            ));
+        }
+        if !request.body.is_object() {
+            errors.push(line_error(number, "invalid_body", "body must be an object"));
+        }
+        if !custom_ids.insert(request.custom_id.clone()) {
+            errors.push(line_error(
+                number,
+                "duplicate_custom_id",
+                format!("custom_id '{}' is used more than once", request.custom_id),
+            ));
+        }
+
+        requests.push(request);
+    }
+
+    if requests.is_empty() && errors.is_empty() {
+        errors.push(BatchError {
+            code: "empty_file".to_string(),
+            message: "The input file contains no requests".to_string(),
+            line: None,
+        });
+    }
+
+    if errors.is_empty() {
+        Ok(requests)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Output or error file line for a request's final response
+pub fn output_line(custom_id: &str, status_code: u16, body: &Value) -> String {
+    json!({
+        "id": format!("batch_req_{}", Uuid::new_v4().simple()),
+        "custom_id": custom_id,
+        "response": {
+            "status_code": status_code,
+            "request_id": format!("req_{}", Uuid::new_v4().simple()),
+            "body": body,
+        },
+        "error": null,
+    })
+    .to_string()
+}
+
+/// Whether a request with this status is worth retrying
+pub fn is_retryable(status: u16) -> bool {
+    status == 429 || status >= 500
+}
+
+/// Backoff before retry `attempt` (0-based): one second, doubling, capped
+pub fn retry_delay(attempt: u32) -> Duration {
+    Duration::from_secs(1u64 << attempt.min(6)).min(MAX_RETRY_DELAY)
+}
+
+fn fs_error(message: &str, path: &Path, operation: &str, e: std::io::Error) -> MindLinkError {
+    MindLinkError::FileSystem {
+        message: message.to_string(),
+        path: Some(path.to_string_lossy().to_string()),
+        operation: operation.to_string(),
+        source: Some(e.into()),
+    }
+}
+
+fn serialize_error(message: &str, e: serde_json::Error) -> MindLinkError {
+    MindLinkError::Internal {
+        message: message.to_string(),
+        component: Some("BatchStore".to_string()),
+        source: Some(e.into()),
+    }
+}
+
+#[derive(Debug)]
+pub struct BatchStore {
+    dir: PathBuf,
+    files: RwLock<HashMap<String, FileObject>>,
+    batches: RwLock<HashMap<String, StoredBatch>>,
+    /// Batches with a running task, so a resume never runs one twice
+    running: Mutex<HashSet<String>>,
+}
+
+/// Releases a batch's running claim when its task ends
+struct RunningClaim<'a> {
+    store: &'a BatchStore,
+    id: String,
+}
+
+impl Drop for RunningClaim<'_> {
+    fn drop(&mut self) {
+        self.store
+            .running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.id);
+    }
+}
+
+async fn read_json_dir<T: for<'de> Deserialize<'de>>(dir: &Path) -> MindLinkResult<Vec<T>> {
+    let mut entries = fs::read_dir(dir)
+        .await
+        .map_err(|e| fs_error("Failed to read batch directory", dir, "read_dir", e))?;
+    let mut items = Vec::new();
+
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|e| fs_error("Failed to read batch directory", dir, "read_dir", e))?
+    {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let content = fs::read_to_string(&path)
+            .await
+            .map_err(|e| fs_error("Failed to read batch state", &path, "read", e))?;
+        match serde_json::from_str(&content) {
+            Ok(item) => items.push(item),
+            // One damaged record must not take every other batch down with it
+            Err(e) => log_warn!(
+                "BatchStore",
+                &format!("Skipping unreadable {}: {}", path.display(), e)
+            ),
+        }
+    }
+
+    Ok(items)
+}
+
+impl BatchStore {
+    /// Default location: `~/.mindlink/batches`
+    pub fn default_dir() -> MindLinkResult<PathBuf> {
+        dirs::home_dir()
+            .map(|home| home.join(".mindlink").join("batches"))
+            .ok_or_else(|| MindLinkError::SystemResource {
+                message: "Cannot determine home directory".to_string(),
+                resource_type: "home directory".to_string(),
+                source: None,
+            })
+    }
+
+    /// Open the store in `dir`, loading every file and batch recorded there
+    pub async fn open(dir: &Path) -> MindLinkResult<Self> {
+        for sub in ["files", "batches"] {
+            let path = dir.join(sub);
+            fs::create_dir_all(&path)
+                .await
+                .map_err(|e| fs_error("Failed to create batch directory", &path, "create", e))?;
+        }
+
+        let files: Vec<FileObject> = read_json_dir(&dir.join("files")).await?;
+        let batches: Vec<StoredBatch> = read_json_dir(&dir.join("batches")).await?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            files: RwLock::new(files.into_iter().map(|f| (f.id.clone(), f)).collect()),
+            batches: RwLock::new(
+                batches
+                    .into_iter()
+                    .map(|b| (b.batch.id.clone(), b))
+                    .collect(),
+            ),
+            running: Mutex::new(HashSet::new()),
+        })
+    }
+
+    fn file_path(&self, id: &str, extension: &str) -> PathBuf {
+        self.dir.join("files").join(format!("{}.{}", id, extension))
+    }
+
+    async fn write_json<T: Serialize>(&self, path: &Path, value: &T) -> MindLinkResult<()> {
+        let json = serde_json::to_vec_pretty(value)
+            .map_err(|e| serialize_error("Failed to serialize batch state", e))?;
+        fs::write(path, json)
+            .await
+            .map_err(|e| fs_error("Failed to write batch state", path, "write", e))
+    }
+
+    /// Store an uploaded file
+    pub async fn create_file(
+        &self,
+        filename: &str,
+        purpose: &str,
+        content: &[u8],
+    ) -> MindLinkResult<FileObject> {
+        let file = FileObject {
+            id: format!("file-{}", Uuid::new_v4().simple()),
+            object: "file".to_string(),
+            bytes: content.len() as u64,
+            created_at: now(),
+            filename: filename.to_string(),
+            purpose: purpose.to_string(),
+        };
+
+        let path = self.file_path(&file.id, "jsonl");
+        fs::write(&path, content)
+            .await
+            .map_err(|e| fs_error("Failed to store file", &path, "write", e))?;
+        self.write_json(&self.file_path(&file.id, "json"), &file)
+            .await?;
+
+        self.files
+            .write()
+            .await
+            .insert(file.id.clone(), file.clone());
+        Ok(file)
+    }
+
+    pub async fn file(&self, id: &str) -> Option<FileObject> {
+        self.files.read().await.get(id).cloned()
+    }
+
+    /// Content of a stored file, `None` if there is no such file
+    pub async fn file_content(&self, id: &str) -> MindLinkResult<Option<Vec<u8>>> {
+        if self.file(id).await.is_none() {
+            return Ok(None);
+        }
+        let path = self.file_path(id, "jsonl");
+        fs::read(&path)
+            .await
+            .map(Some)
+            .map_err(|e| fs_error("Failed to read file", &path, "read", e))
+    }
+
+    /// Append a JSONL line to a stored file
+    async fn append(&self, id: &str, line: &str) -> MindLinkResult<()> {
+        let path = self.file_path(id, "jsonl");
+        let mut handle = fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .await
+            .map_err(|e| fs_error("Failed to open file", &path, "open", e))?;
+        handle
+            .write_all(format!("{}\n", line).as_bytes())
+            .await
+            .map_err(|e| fs_error("Failed to append to file", &path, "write", e))?;
+
+        let mut files = self.files.write().await;
+        if let Some(file) = files.get_mut(id) {
+            file.bytes += line.len() as u64 + 1;
+            let file = file.clone();
+            drop(files);
+            self.write_json(&self.file_path(id, "json"), &file).await?;
+        }
+        Ok(())
+    }
+
+    /// Record a new batch for an input file that exists. A batch whose input
+    /// fails validation is created as `failed` with the problems listed.
+    pub async fn create_batch(
+        &self,
+        request: CreateBatchRequest,
+        app_id: Option<String>,
+    ) -> MindLinkResult<Batch> {
+        let content = self
+            .file_content(&request.input_file_id)
+            .await?
+            .unwrap_or_default();
+        let created_at = now();
+
+        let mut batch = Batch {
+            id: format!("batch_{}", Uuid::new_v4().simple()),
+            object: "batch".to_string(),
+            endpoint: request.endpoint,
+            errors: None,
+            input_file_id: request.input_file_id,
+            completion_window: request.completion_window,
+            status: BatchStatus::Validating,
+            output_file_id: None,
+            error_file_id: None,
+            created_at,
+            in_progress_at: None,
+            expires_at: created_at + COMPLETION_WINDOW_SECS,
+            finalizing_at: None,
+            completed_at: None,
+            failed_at: None,
+            expired_at: None,
+            cancelling_at: None,
+            cancelled_at: None,
+            request_counts: RequestCounts::default(),
+            metadata: request.metadata,
+        };
+
+        match parse_input(&String::from_utf8_lossy(&content), &batch.endpoint) {
+            Ok(requests) => batch.request_counts.total = requests.len(),
+            Err(errors) => {
+                batch.status = BatchStatus::Failed;
+                batch.failed_at = Some(created_at);
+                batch.errors = Some(BatchErrors {
+                    object: "list".to_string(),
+                    data: errors,
+                });
+            },
+        }
+
+        self.save(StoredBatch {
+            batch: batch.clone(),
+            app_id,
+        })
+        .await?;
+        Ok(batch)
+    }
+
+    async fn save(&self, stored: StoredBatch) -> MindLinkResult<()> {
+        let path = self
+            .dir
+            .join("batches")
+            .join(format!("{}.json", stored.batch.id));
+        self.write_json(&path, &stored).await?;
+        self.batches
+            .write()
+            .await
+            .insert(stored.batch.id.clone(), stored);
+        Ok(())
+    }
+
+    /// Apply `change` to a batch and persist it
+    async fn update(
+        &self,
+        id: &str,
+        change: impl FnOnce(&mut Batch),
+    ) -> MindLinkResult<Option<Batch>> {
+        let Some(mut stored) = self.batches.read().await.get(id).cloned() else {
+            return Ok(None);
+        };
+        change(&mut stored.batch);
+        let batch = stored.batch.clone();
+        self.save(stored).await?;
+        Ok(Some(batch))
+    }
+
+    pub async fn batch(&self, id: &str) -> Option<Batch> {
+        self.batches.read().await.get(id).map(|b| b.batch.clone())
+    }
+
+    /// The app whose API key created a batch
+    pub async fn app_id(&self, id: &str) -> Option<String> {
+        self.batches
+            .read()
+            .await
+            .get(id)
+            .and_then(|b| b.app_id.clone())
+    }
+
+    /// Batches, newest first, starting after the batch `after`
+    pub async fn list(&self, after: Option<&str>, limit: usize) -> Vec<Batch> {
+        let mut batches: Vec<Batch> = self
+            .batches
+            .read()
+            .await
+            .values()
+            .map(|b| b.batch.clone())
+            .collect();
+        batches.sort_by(|a, b| b.created_at.cmp(&a.created_at).then(b.id.cmp(&a.id)));
+
+        let start = after
+            .and_then(|after| batches.iter().position(|b| b.id == after))
+            .map_or(0, |position| position + 1);
+        batches.into_iter().skip(start).take(limit).collect()
+    }
+
+    /// Ask a batch to stop. Batches that have not started are cancelled right
+    /// away; running ones finish their current request first.
+    pub async fn cancel(&self, id: &str) -> MindLinkResult<Option<Batch>> {
+        self.update(id, |batch| {
+            let now = now();
+            match batch.status {
+                BatchStatus::Validating => {
+                    batch.status = BatchStatus::Cancelled;
+                    batch.cancelling_at = Some(now);
+                    batch.cancelled_at = Some(now);
+                },
+                BatchStatus::InProgress => {
+                    batch.status = BatchStatus::Cancelling;
+                    batch.cancelling_at = Some(now);
+                },
+                _ => {},
+            }
+        })
+        .await
+    }
+
+    /// Batches that still have requests to run
+    pub async fn unfinished(&self) -> Vec<String> {
+        self.batches
+            .read()
+            .await
+            .values()
+            .filter(|b| !b.batch.status.is_terminal())
+            .map(|b| b.batch.id.clone())
+            .collect()
+    }
+
+    fn claim(&self, id: &str) -> Option<RunningClaim<'_>> {
+        let mut running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        running.insert(id.to_string()).then(|| RunningClaim {
+            store: self,
+            id: id.to_string(),
+        })
+    }
+
+    /// Append a line to the batch's output or error file, creating it first
+    async fn record(&self, id: &str, error_file: bool, line: &str) -> MindLinkResult<()> {
+        let Some(batch) = self.batch(id).await else {
+            return Ok(());
+        };
+        let existing = if error_file {
+            batch.error_file_id
+        } else {
+            batch.output_file_id
+        };
+
+        let file_id = match existing {
+            Some(file_id) => file_id,
+            None => {
+                let name = if error_file { "error" } else { "output" };
+                let file = self
+                    .create_file(&format!("{}_{}.jsonl", id, name), OUTPUT_PURPOSE, &[])
+                    .await?;
+                self.update(id, |batch| {
+                    if error_file {
+                        batch.error_file_id = Some(file.id.clone());
+                    } else {
+                        batch.output_file_id = Some(file.id.clone());
+                    }
+                })
+                .await?;
+                file.id
+            },
+        };
+
+        self.append(&file_id, line).await
+    }
+}
+
+/// Run the remaining requests of a batch through `execute`, which returns the
+/// HTTP status and JSON body of a chat completion. Returns once the batch has
+/// completed, failed, expired or been cancelled.
+pub async fn run_batch<F, Fut>(
+    store: Arc<BatchStore>,
+    id: String,
+    config: BatchConfig,
+    execute: F,
+) -> MindLinkResult<()>
+where
+    F: Fn(Value) -> Fut,
+    Fut: Future<Output = (u16, Value)>,
+{
+    let Some(_claim) = store.claim(&id) else {
+        return Ok(());
+    };
+    let Some(batch) = store.batch(&id).await else {
+        return Ok(());
+    };
+    if batch.status.is_terminal() {
+        return Ok(());
+    }
+
+    let content = store
+        .file_content(&batch.input_file_id)
+        .await?
+        .unwrap_or_default();
+    let requests = match parse_input(&String::from_utf8_lossy(&content), &batch.endpoint) {
+        Ok(requests) => requests,
+        Err(errors) => {
+            store
+                .update(&id, |batch| {
+                    batch.status = BatchStatus::Failed;
+                    batch.failed_at = Some(now());
+                    batch.errors = Some(BatchErrors {
+                        object: "list".to_string(),
+                        data: errors,
+                    });
+                })
+                .await?;
+            return Ok(());
+        },
+    };
+
+    if batch.status == BatchStatus::Validating {
+        store
+            .update(&id, |batch| {
+                batch.status = BatchStatus::InProgress;
+                batch.in_progress_at = Some(now());
+            })
+            .await?;
+    }
+
+    let done = batch.request_counts.completed + batch.request_counts.failed;
+    let interval = Duration::from_millis(config.min_interval_ms);
+
+    for (index, request) in requests.iter().enumerate().skip(done) {
+        let Some(batch) = store.batch(&id).await else {
+            return Ok(());
+        };
+        if batch.status == BatchStatus::Cancelling {
+            store
+                .update(&id, |batch| {
+                    batch.status = BatchStatus::Cancelled;
+                    batch.cancelled_at = Some(now());
+                })
+                .await?;
+            return Ok(());
+        }
+        if now() >= batch.expires_at {
+            store
+                .update(&id, |batch| {
+                    batch.status = BatchStatus::Expired;
+                    batch.expired_at = Some(now());
+                })
+                .await?;
+            return Ok(());
+        }
+
+        if index > done {
+            tokio::time::sleep(interval).await;
+        }
+
+        let mut attempt = 0;
+        let (status, body) = loop {
+            let (status, body) = execute(request.body.clone()).await;
+            if !is_retryable(status) || attempt >= config.max_retries {
+                break (status, body);
+            }
+            tokio::time::sleep(retry_delay(attempt)).await;
+            attempt += 1;
+        };
+
+        let succeeded = (200..300).contains(&status);
+        store
+            .record(
+                &id,
+                !succeeded,
+                &output_line(&request.custom_id, status, &body),
+            )
+            .await?;
+        store
+            .update(&id, |batch| {
+                if succeeded {
+                    batch.request_counts.completed += 1;
+                } else {
+                    batch.request_counts.failed += 1;
+                }
+            })
+            .await?;
+    }
+
+    store
+        .update(&id, |batch| {
+            let now = now();
+            batch.status = BatchStatus::Completed;
+            batch.finalizing_at = Some(now);
+            batch.completed_at = Some(now);
+        })
+        .await?;
+    Ok(())
+}
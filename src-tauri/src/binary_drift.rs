@@ -0,0 +1,216 @@
+// Drift detection for managed binaries
+//
+// When MindLink downloads cloudflared or builds bifrost-http it records the
+// binary's path, SHA-256 and version in `binaries.json` next to the binaries.
+// At startup the records are compared with what is on disk and with the copy
+// that would actually run: cloudflared is taken from PATH before the managed
+// download, so another cloudflared installed by a package manager silently
+// takes its place. Every difference is reported with a command that fixes it.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+pub const MANIFEST_FILE: &str = "binaries.json";
+
+/// What was installed, as recorded at install time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BinaryRecord {
+    pub path: PathBuf,
+    pub sha256: String,
+    /// First line of `--version`, if the binary answered it
+    pub version: Option<String>,
+    pub recorded_at: DateTime<Utc>,
+    /// Whether the binary matched the checksum its publisher released
+    #[serde(default)]
+    pub verified: bool,
+    /// Release it was downloaded from, e.g. `2024.6.1`
+    #[serde(default)]
+    pub release: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BinaryManifest {
+    pub binaries: BTreeMap<String, BinaryRecord>,
+}
+
+impl BinaryManifest {
+    /// The manifest at `path`; empty when there is none or it cannot be read
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Drift {
+    /// The recorded binary is gone
+    Missing,
+    /// The recorded binary was replaced or changed on disk
+    Modified {
+        recorded_version: Option<String>,
+        found_version: Option<String>,
+    },
+    /// A different binary runs instead of the recorded one
+    Shadowed {
+        path: PathBuf,
+        version: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftFinding {
+    pub binary: String,
+    pub recorded_path: PathBuf,
+    pub drift: Drift,
+    /// Shell command that resolves the drift
+    pub remediation: String,
+}
+
+impl DriftFinding {
+    pub fn summary(&self) -> String {
+        let path = self.recorded_path.display();
+        match &self.drift {
+            Drift::Missing => format!("{} is missing from {}", self.binary, path),
+            Drift::Modified { found_version, .. } => format!(
+                "{} at {} changed since it was installed (now {})",
+                self.binary,
+                path,
+                found_version.as_deref().unwrap_or("unknown version")
+            ),
+            Drift::Shadowed {
+                path: other,
+                version,
+            } => format!(
+                "{} at {} runs instead of the managed copy at {} ({})",
+                self.binary,
+                other.display(),
+                path,
+                version.as_deref().unwrap_or("unknown version")
+            ),
+        }
+    }
+}
+
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 8192];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// First line of `path --version`
+pub async fn binary_version(path: &Path) -> Option<String> {
+    let output = Command::new(path).arg("--version").output().await.ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // cloudflared prints its version to stderr on some platforms
+    let text = if output.stdout.is_empty() {
+        output.stderr
+    } else {
+        output.stdout
+    };
+    String::from_utf8_lossy(&text)
+        .lines()
+        .next()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+}
+
+/// Where `name` is found on `search_path` (the PATH variable's value)
+pub fn find_in_path(name: &str, search_path: &OsStr) -> Option<PathBuf> {
+    let file_name = format!("{}{}", name, std::env::consts::EXE_SUFFIX);
+    std::env::split_paths(search_path)
+        .map(|dir| dir.join(&file_name))
+        .find(|candidate| candidate.is_file())
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+fn remediation(binary: &str, recorded: &BinaryRecord, drift: &Drift) -> String {
+    match (binary, drift) {
+        ("bifrost-http", Drift::Shadowed { path, .. }) => remove_command(path),
+        ("bifrost-http", _) => "bash scripts/tauri-build-bifrost.sh --force".to_string(),
+        // `cloudflared update` replaces the binary it is run from
+        (_, Drift::Shadowed { path, .. }) => format!("\"{}\" update", path.display()),
+        // MindLink downloads cloudflared again when the managed copy is gone
+        (_, _) => remove_command(&recorded.path),
+    }
+}
+
+fn remove_command(path: &Path) -> String {
+    if cfg!(windows) {
+        format!("del \"{}\"", path.display())
+    } else {
+        format!("rm -f \"{}\"", path.display())
+    }
+}
+
+/// Compare the recorded binaries with the disk. `resolved` maps a binary to
+/// the copy that would run now, where that is known.
+pub async fn check(
+    manifest: &BinaryManifest,
+    resolved: &BTreeMap<String, PathBuf>,
+) -> Vec<DriftFinding> {
+    let mut findings = Vec::new();
+
+    for (binary, record) in &manifest.binaries {
+        let mut drifts = Vec::new();
+        match sha256_file(&record.path) {
+            Err(_) => drifts.push(Drift::Missing),
+            Ok(sha256) if sha256 != record.sha256 => drifts.push(Drift::Modified {
+                recorded_version: record.version.clone(),
+                found_version: binary_version(&record.path).await,
+            }),
+            Ok(_) => {},
+        }
+
+        if let Some(running) = resolved.get(binary) {
+            let differs = !same_file(running, &record.path)
+                && sha256_file(running).map_or(true, |sha256| sha256 != record.sha256);
+            if differs {
+                drifts.push(Drift::Shadowed {
+                    path: running.clone(),
+                    version: binary_version(running).await,
+                });
+            }
+        }
+
+        for drift in drifts {
+            findings.push(DriftFinding {
+                binary: binary.clone(),
+                recorded_path: record.path.clone(),
+                remediation: remediation(binary, record, &drift),
+                drift,
+            });
+        }
+    }
+
+    findings
+}
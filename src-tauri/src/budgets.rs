@@ -0,0 +1,239 @@
+// Token budgets of authorized apps
+//
+// An app may have a daily and a monthly token budget. What each app consumed
+// in the current UTC day and month is kept in `~/.mindlink/token_budgets.json`,
+// a `json_ledger`, so that restarting MindLink does not hand out a fresh
+// budget. Consumption is estimated the same way the usage of responses is:
+// four bytes of text per token.
+
+use crate::authorized_apps::AuthorizedApp;
+use crate::error::MindLinkResult;
+use crate::json_ledger::{self, JsonLedger};
+use chrono::{DateTime, Datelike, Days, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetPeriod {
+    Daily,
+    Monthly,
+}
+
+impl BudgetPeriod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BudgetPeriod::Daily => "daily",
+            BudgetPeriod::Monthly => "monthly",
+        }
+    }
+
+    /// Names the period `now` falls in, e.g. `2024-05-31` or `2024-05`
+    fn key(self, now: DateTime<Utc>) -> String {
+        match self {
+            BudgetPeriod::Daily => now.format("%Y-%m-%d").to_string(),
+            BudgetPeriod::Monthly => now.format("%Y-%m").to_string(),
+        }
+    }
+
+    /// Start of the period after the one `now` falls in
+    pub fn resets_at(self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let today = now.date_naive();
+        let next = match self {
+            BudgetPeriod::Daily => today + Days::new(1),
+            BudgetPeriod::Monthly if today.month() == 12 => {
+                NaiveDate::from_ymd_opt(today.year() + 1, 1, 1).unwrap_or(today)
+            },
+            BudgetPeriod::Monthly => {
+                NaiveDate::from_ymd_opt(today.year(), today.month() + 1, 1).unwrap_or(today)
+            },
+        };
+        next.and_time(Default::default()).and_utc()
+    }
+}
+
+/// One budget of an app and how much of it is used
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeriodBudget {
+    pub period: BudgetPeriod,
+    pub limit: u64,
+    pub used: u64,
+    pub remaining: u64,
+    pub resets_at: DateTime<Utc>,
+}
+
+/// The budgets of an app
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppBudget {
+    pub app_id: String,
+    pub app_name: String,
+    pub budgets: Vec<PeriodBudget>,
+}
+
+impl AppBudget {
+    /// The used-up budget that resets last, if any
+    pub fn exhausted(&self) -> Option<&PeriodBudget> {
+        self.budgets
+            .iter()
+            .filter(|budget| budget.remaining == 0)
+            .max_by_key(|budget| budget.resets_at)
+    }
+
+    /// Tokens left until the first budget runs out
+    pub fn remaining(&self) -> Option<u64> {
+        self.budgets.iter().map(|budget| budget.remaining).min()
+    }
+}
+
+/// Tokens one app consumed in the current day and month
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Consumption {
+    day: String,
+    day_tokens: u64,
+    month: String,
+    month_tokens: u64,
+}
+
+impl Consumption {
+    fn used(&self, period: BudgetPeriod, now: DateTime<Utc>) -> u64 {
+        let (key, tokens) = match period {
+            BudgetPeriod::Daily => (&self.day, self.day_tokens),
+            BudgetPeriod::Monthly => (&self.month, self.month_tokens),
+        };
+        if *key == period.key(now) {
+            tokens
+        } else {
+            0
+        }
+    }
+
+    fn add(&mut self, tokens: u64, now: DateTime<Utc>) {
+        self.day_tokens = self.used(BudgetPeriod::Daily, now).saturating_add(tokens);
+        self.day = BudgetPeriod::Daily.key(now);
+        self.month_tokens = self.used(BudgetPeriod::Monthly, now).saturating_add(tokens);
+        self.month = BudgetPeriod::Monthly.key(now);
+    }
+}
+
+/// Token consumption of every app, by app ID. Without a path nothing is
+/// persisted.
+#[derive(Debug, Default)]
+pub struct BudgetLedger {
+    consumption: Arc<JsonLedger<HashMap<String, Consumption>>>,
+}
+
+impl BudgetLedger {
+    /// Default location: `~/.mindlink/token_budgets.json`
+    pub fn default_path() -> MindLinkResult<PathBuf> {
+        json_ledger::default_path("token_budgets.json")
+    }
+
+    /// The ledger saved at `path`; empty when there is none yet. When it
+    /// cannot be read, every budget counts as used up.
+    pub fn open(path: &Path) -> Self {
+        Self {
+            consumption: Arc::new(JsonLedger::open(path)),
+        }
+    }
+
+    /// The budgets of `app` as of `now`
+    pub fn budget(&self, app: &AuthorizedApp, now: DateTime<Utc>) -> AppBudget {
+        let consumption = self
+            .consumption
+            .lock()
+            .get(&app.id)
+            .cloned()
+            .unwrap_or_default();
+        let unreadable = self.consumption.is_unreadable();
+        let limits = [
+            (BudgetPeriod::Daily, app.daily_token_budget),
+            (BudgetPeriod::Monthly, app.monthly_token_budget),
+        ];
+        let budgets = limits
+            .into_iter()
+            .filter_map(|(period, limit)| {
+                let limit = limit?;
+                let used = if unreadable {
+                    limit
+                } else {
+                    consumption.used(period, now)
+                };
+                Some(PeriodBudget {
+                    period,
+                    limit,
+                    used,
+                    remaining: limit.saturating_sub(used),
+                    resets_at: period.resets_at(now),
+                })
+            })
+            .collect();
+        AppBudget {
+            app_id: app.id.clone(),
+            app_name: app.name.clone(),
+            budgets,
+        }
+    }
+
+    /// Count `tokens` against the budgets of an app
+    pub fn record(&self, app_id: &str, tokens: u64, now: DateTime<Utc>) {
+        self.consumption
+            .lock()
+            .entry(app_id.to_string())
+            .or_default()
+            .add(tokens, now);
+    }
+
+    /// Write the ledger to its file. Blocks, so async callers use
+    /// [`BudgetLedger::save_in_background`].
+    pub fn save(&self) -> MindLinkResult<()> {
+        self.consumption.save()
+    }
+
+    pub fn save_in_background(&self) {
+        self.consumption.save_in_background();
+    }
+}
+
+/// Estimates the completion tokens of a streamed response from the text it
+/// carries. Reads SSE (`data:` lines) and NDJSON bodies alike.
+#[derive(Debug, Default)]
+pub struct StreamTally {
+    buffer: Vec<u8>,
+    text_bytes: usize,
+}
+
+impl StreamTally {
+    pub fn push(&mut self, chunk: &[u8]) {
+        self.buffer.extend_from_slice(chunk);
+        while let Some(end) = self.buffer.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=end).collect();
+            self.text_bytes += text_bytes(&String::from_utf8_lossy(&line));
+        }
+    }
+
+    pub fn tokens(&self) -> u64 {
+        (self.text_bytes as u64).div_ceil(4)
+    }
+}
+
+/// Bytes of generated text in one line of a stream
+fn text_bytes(line: &str) -> usize {
+    let line = line.trim();
+    let json = line.strip_prefix("data:").unwrap_or(line).trim_start();
+    let Ok(value) = serde_json::from_str::<Value>(json) else {
+        return 0;
+    };
+    let len = |value: &Value| value.as_str().map_or(0, str::len);
+
+    // OpenAI chunks, then Ollama's chat and generate objects
+    let deltas: usize = value["choices"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|choice| len(&choice["delta"]["content"]) + len(&choice["delta"]["reasoning_content"]))
+        .sum();
+    deltas + len(&value["message"]["content"]) + len(&value["response"])
+}
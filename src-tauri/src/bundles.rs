@@ -0,0 +1,390 @@
+// Sharing prompt templates and routes between MindLink instances
+//
+// An export bundle holds the named prompt templates, model aliases and
+// language routes of one instance, signed with that instance's Ed25519 key.
+// Teammates add the key to `bundles.trusted_signers` and import the bundle
+// into their own configuration, so a team can share a setup without running
+// a server. Imported templates and aliases are prefixed with the bundle's
+// namespace, e.g. `team/summarize`; language routes are keyed by language and
+// keep their key. Entries that already exist are skipped, overwritten or
+// imported under a new name, as the importer chooses.
+
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::managers::config_manager::{
+    LanguageDetectionConfig, ModelAliasConfig, PromptConfig, TrustedSigner,
+};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Bundle format written by this version
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// What a bundle shares. Maps are ordered so the signed JSON is reproducible.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleContents {
+    pub version: u32,
+    /// Prefix of the imported names unless the importer picks another
+    pub namespace: String,
+    pub author: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub templates: BTreeMap<String, String>,
+    pub model_aliases: BTreeMap<String, String>,
+    /// Language code to model
+    pub language_routes: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedBundle {
+    pub contents: BundleContents,
+    /// Base64 Ed25519 public key of the exporting instance
+    pub public_key: String,
+    /// Base64 signature over the JSON of `contents`
+    pub signature: String,
+}
+
+/// What to do with an entry whose name is already taken
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictStrategy {
+    /// Keep the existing entry
+    #[default]
+    Skip,
+    Overwrite,
+    /// Import under the first free name with a `-2`, `-3`, ... suffix.
+    /// Language routes cannot be renamed and are skipped.
+    Rename,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    Template,
+    ModelAlias,
+    LanguageRoute,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum MergeOutcome {
+    Added,
+    /// Already present with the same value
+    Unchanged,
+    Overwritten,
+    Skipped,
+    Renamed {
+        to: String,
+    },
+}
+
+/// How one bundle entry was imported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportedEntry {
+    pub kind: EntryKind,
+    /// Name with the namespace applied
+    pub name: String,
+    #[serde(flatten)]
+    pub outcome: MergeOutcome,
+}
+
+/// Check that `namespace` can prefix template and model names
+pub fn validate_namespace(namespace: &str) -> MindLinkResult<()> {
+    let valid = !namespace.is_empty()
+        && namespace.len() <= 32
+        && namespace
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(MindLinkError::Configuration {
+            message: format!(
+                "Invalid bundle namespace '{}': use letters, digits, '-' and '_'",
+                namespace
+            ),
+            config_key: Some("bundles".to_string()),
+            source: None,
+        })
+    }
+}
+
+/// Bundle contents from the current configuration. The `*` alias is left
+/// out since it would catch every model of the importing instance.
+pub fn export(
+    namespace: &str,
+    author: Option<String>,
+    prompts: &PromptConfig,
+    model_aliases: &ModelAliasConfig,
+    language_detection: &LanguageDetectionConfig,
+) -> MindLinkResult<BundleContents> {
+    validate_namespace(namespace)?;
+    Ok(BundleContents {
+        version: BUNDLE_VERSION,
+        namespace: namespace.to_string(),
+        author,
+        created_at: Utc::now(),
+        templates: prompts.templates.clone().into_iter().collect(),
+        model_aliases: model_aliases
+            .aliases
+            .iter()
+            .filter(|(alias, _)| alias.as_str() != "*")
+            .map(|(alias, model)| (alias.clone(), model.clone()))
+            .collect(),
+        language_routes: language_detection.routes.clone().into_iter().collect(),
+    })
+}
+
+/// The Ed25519 key this instance signs its bundles with
+#[derive(Debug)]
+pub struct BundleSigner {
+    key: SigningKey,
+}
+
+impl BundleSigner {
+    /// Key file under `~/.mindlink`
+    pub fn key_path() -> MindLinkResult<PathBuf> {
+        dirs::home_dir()
+            .map(|home| home.join(".mindlink").join("bundle_signing.key"))
+            .ok_or_else(|| MindLinkError::SystemResource {
+                message: "Cannot determine home directory".to_string(),
+                resource_type: "home directory".to_string(),
+                source: None,
+            })
+    }
+
+    /// Load the key at `path`, generating and saving one on first use
+    pub async fn load_or_create(path: &Path) -> MindLinkResult<Self> {
+        let file_error =
+            |message: &str, operation: &str, e: std::io::Error| MindLinkError::FileSystem {
+                message: message.to_string(),
+                path: Some(path.to_string_lossy().to_string()),
+                operation: operation.to_string(),
+                source: Some(e.into()),
+            };
+
+        match tokio::fs::read_to_string(path).await {
+            Ok(encoded) => {
+                let seed: [u8; 32] = STANDARD
+                    .decode(encoded.trim())
+                    .ok()
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .ok_or_else(|| MindLinkError::Configuration {
+                        message: "The bundle signing key is corrupt".to_string(),
+                        config_key: None,
+                        source: None,
+                    })?;
+                Ok(Self {
+                    key: SigningKey::from_bytes(&seed),
+                })
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let key = SigningKey::generate(&mut rand::rngs::OsRng);
+                if let Some(dir) = path.parent() {
+                    tokio::fs::create_dir_all(dir).await.map_err(|e| {
+                        file_error("Failed to create key directory", "create directory", e)
+                    })?;
+                }
+                tokio::fs::write(path, STANDARD.encode(key.to_bytes()))
+                    .await
+                    .map_err(|e| file_error("Failed to save bundle signing key", "write", e))?;
+
+                // Only the owner may sign in their name
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    let permissions = std::fs::Permissions::from_mode(0o600);
+                    tokio::fs::set_permissions(path, permissions)
+                        .await
+                        .map_err(|e| file_error("Failed to protect signing key", "chmod", e))?;
+                }
+                Ok(Self { key })
+            },
+            Err(e) => Err(file_error("Failed to read bundle signing key", "read", e)),
+        }
+    }
+
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self {
+            key: SigningKey::from_bytes(&seed),
+        }
+    }
+
+    /// Base64 public key for teammates' `bundles.trusted_signers`
+    pub fn public_key(&self) -> String {
+        STANDARD.encode(self.key.verifying_key().to_bytes())
+    }
+
+    pub fn sign(&self, contents: BundleContents) -> MindLinkResult<SignedBundle> {
+        let signature = self.key.sign(&signed_bytes(&contents)?);
+        Ok(SignedBundle {
+            contents,
+            public_key: self.public_key(),
+            signature: STANDARD.encode(signature.to_bytes()),
+        })
+    }
+}
+
+fn signed_bytes(contents: &BundleContents) -> MindLinkResult<Vec<u8>> {
+    serde_json::to_vec(contents).map_err(|e| MindLinkError::Internal {
+        message: "Failed to serialize bundle".to_string(),
+        component: Some("Bundles".to_string()),
+        source: Some(e.into()),
+    })
+}
+
+fn rejected(message: String) -> MindLinkError {
+    MindLinkError::Configuration {
+        message,
+        config_key: Some("bundles.trusted_signers".to_string()),
+        source: None,
+    }
+}
+
+/// Check the signature of `bundle` and that its signer is this instance
+/// (`own_key`) or a trusted teammate. Returns the signer's name.
+pub fn verify(
+    bundle: &SignedBundle,
+    own_key: &str,
+    trusted: &[TrustedSigner],
+) -> MindLinkResult<String> {
+    if bundle.contents.version > BUNDLE_VERSION {
+        return Err(rejected(
+            "The bundle was exported by a newer MindLink version".to_string(),
+        ));
+    }
+
+    let signer = if bundle.public_key == own_key {
+        "this MindLink".to_string()
+    } else {
+        trusted
+            .iter()
+            .find(|signer| signer.public_key.trim() == bundle.public_key)
+            .map(|signer| signer.name.clone())
+            .ok_or_else(|| {
+                rejected(format!(
+                    "The bundle is signed by an untrusted key ({}); add it to the trusted signers to import it",
+                    bundle.public_key
+                ))
+            })?
+    };
+
+    let key: [u8; 32] = STANDARD
+        .decode(&bundle.public_key)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| rejected("The bundle's public key is invalid".to_string()))?;
+    let signature: [u8; 64] = STANDARD
+        .decode(&bundle.signature)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| rejected("The bundle's signature is invalid".to_string()))?;
+
+    let valid = VerifyingKey::from_bytes(&key).is_ok_and(|key| {
+        signed_bytes(&bundle.contents).is_ok_and(|bytes| {
+            key.verify(&bytes, &Signature::from_bytes(&signature))
+                .is_ok()
+        })
+    });
+    if !valid {
+        return Err(rejected(format!(
+            "The bundle was modified after {} signed it",
+            signer
+        )));
+    }
+    Ok(signer)
+}
+
+/// Merge `contents` into the configuration under `namespace`
+pub fn merge(
+    contents: &BundleContents,
+    namespace: &str,
+    strategy: ConflictStrategy,
+    prompts: &mut PromptConfig,
+    model_aliases: &mut ModelAliasConfig,
+    language_detection: &mut LanguageDetectionConfig,
+) -> Vec<ImportedEntry> {
+    let namespaced = |name: &str| format!("{}/{}", namespace, name);
+    let mut entries = Vec::new();
+
+    for (name, template) in &contents.templates {
+        entries.push(merge_entry(
+            &mut prompts.templates,
+            EntryKind::Template,
+            namespaced(name),
+            template.clone(),
+            strategy,
+        ));
+    }
+
+    for (alias, model) in &contents.model_aliases {
+        entries.push(merge_entry(
+            &mut model_aliases.aliases,
+            EntryKind::ModelAlias,
+            namespaced(alias),
+            model.clone(),
+            strategy,
+        ));
+    }
+
+    // Routes to a shared alias follow it into the namespace
+    for (language, model) in &contents.language_routes {
+        let model = if contents.model_aliases.contains_key(model) {
+            namespaced(model)
+        } else {
+            model.clone()
+        };
+        let strategy = match strategy {
+            ConflictStrategy::Rename => ConflictStrategy::Skip,
+            other => other,
+        };
+        entries.push(merge_entry(
+            &mut language_detection.routes,
+            EntryKind::LanguageRoute,
+            language.clone(),
+            model,
+            strategy,
+        ));
+    }
+
+    entries
+}
+
+fn merge_entry(
+    target: &mut HashMap<String, String>,
+    kind: EntryKind,
+    name: String,
+    value: String,
+    strategy: ConflictStrategy,
+) -> ImportedEntry {
+    let outcome = match target.get(&name) {
+        None => {
+            target.insert(name.clone(), value);
+            MergeOutcome::Added
+        },
+        Some(existing) if *existing == value => MergeOutcome::Unchanged,
+        Some(_) => match strategy {
+            ConflictStrategy::Skip => MergeOutcome::Skipped,
+            ConflictStrategy::Overwrite => {
+                target.insert(name.clone(), value);
+                MergeOutcome::Overwritten
+            },
+            ConflictStrategy::Rename => {
+                // A copy from an earlier import is reused rather than duplicated
+                let to = (2..)
+                    .map(|n| format!("{}-{}", name, n))
+                    .find(|candidate| target.get(candidate).map_or(true, |v| *v == value))
+                    .unwrap_or_default();
+                target.insert(to.clone(), value);
+                MergeOutcome::Renamed { to }
+            },
+        },
+    };
+    ImportedEntry {
+        kind,
+        name,
+        outcome,
+    }
+}
@@ -0,0 +1,198 @@
+// Canary releases of model routing changes
+//
+// A new model alias map can be tried on a share of the traffic, or only on
+// the requests of one API key, before it replaces the current one. Requests
+// are tagged with the side they ran on, and the analytics pipeline records
+// the tag with every request. Once the trial window passes, the canary is
+// promoted, unless its error rate rose too far above the stable routing's
+// first, in which case it is rolled back automatically.
+
+use crate::analytics::VariantStats;
+use crate::managers::config_manager::ModelAliasConfig;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+fn default_trial_minutes() -> u64 {
+    60
+}
+
+fn default_max_error_rate_increase() -> f64 {
+    0.05
+}
+
+fn default_min_requests() -> u64 {
+    20
+}
+
+/// A routing change to try, and how to judge it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanarySpec {
+    /// The complete alias map to try
+    pub model_aliases: HashMap<String, String>,
+    /// Share of requests (0-100) routed through the canary
+    #[serde(default)]
+    pub percentage: u8,
+    /// Route every request with this API key through the canary. Never
+    /// reported back; only its fingerprint is kept.
+    #[serde(default, skip_serializing)]
+    pub api_key: Option<String>,
+    #[serde(default = "default_trial_minutes")]
+    pub trial_minutes: u64,
+    /// Roll back once the canary's error rate exceeds the stable one by this
+    /// much (0.05 = five percentage points)
+    #[serde(default = "default_max_error_rate_increase")]
+    pub max_error_rate_increase: f64,
+    /// Canary requests needed before its error rate is judged
+    #[serde(default = "default_min_requests")]
+    pub min_requests: u64,
+}
+
+/// The side of a canary a request ran on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CanaryArm {
+    Stable,
+    Canary,
+}
+
+impl CanaryArm {
+    /// Label recorded in the analytics `variant` column
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CanaryArm::Stable => "stable",
+            CanaryArm::Canary => "canary",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CanaryState {
+    Running,
+    Promoted,
+    RolledBack,
+}
+
+/// What to do with a running canary
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    Continue,
+    Promote,
+    Rollback(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Canary {
+    pub spec: CanarySpec,
+    /// Fingerprint of `spec.api_key`, as recorded by analytics
+    pub api_key: Option<String>,
+    pub state: CanaryState,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    /// Why the canary was rolled back
+    pub reason: Option<String>,
+}
+
+/// A canary together with the traffic it has seen so far
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanaryStatus {
+    pub canary: Canary,
+    pub stable: VariantStats,
+    pub candidate: VariantStats,
+}
+
+impl Canary {
+    /// Start a canary. `fingerprint` turns the spec's API key into the label
+    /// analytics records for it.
+    pub fn start(spec: CanarySpec, fingerprint: impl Fn(&str) -> String) -> Result<Self, String> {
+        if spec.percentage > 100 {
+            return Err("Canary percentage must be between 0 and 100".to_string());
+        }
+        if spec.percentage == 0 && spec.api_key.is_none() {
+            return Err("A canary needs a traffic percentage or an API key".to_string());
+        }
+        if spec.trial_minutes == 0 {
+            return Err("Canary trial window must be at least one minute".to_string());
+        }
+        if !(0.0..=1.0).contains(&spec.max_error_rate_increase) {
+            return Err("Allowed error rate increase must be between 0 and 1".to_string());
+        }
+
+        Ok(Self {
+            api_key: spec.api_key.as_deref().map(fingerprint),
+            spec,
+            state: CanaryState::Running,
+            started_at: Utc::now(),
+            ended_at: None,
+            reason: None,
+        })
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state == CanaryState::Running
+    }
+
+    pub fn aliases(&self) -> ModelAliasConfig {
+        ModelAliasConfig {
+            aliases: self.spec.model_aliases.clone(),
+        }
+    }
+
+    /// Pick the side a request runs on, given its API key fingerprint.
+    /// `None` once the canary has ended.
+    pub fn arm(&self, api_key: Option<&str>) -> Option<CanaryArm> {
+        if !self.is_running() {
+            return None;
+        }
+        if self.api_key.is_some() && self.api_key.as_deref() == api_key {
+            return Some(CanaryArm::Canary);
+        }
+
+        let roll = (Uuid::new_v4().as_u128() % 100) as u8;
+        Some(if roll < self.spec.percentage {
+            CanaryArm::Canary
+        } else {
+            CanaryArm::Stable
+        })
+    }
+
+    /// Judge the canary on the traffic of both sides at `now`
+    pub fn evaluate(
+        &self,
+        stable: &VariantStats,
+        candidate: &VariantStats,
+        now: DateTime<Utc>,
+    ) -> Verdict {
+        if !self.is_running() {
+            return Verdict::Continue;
+        }
+
+        if candidate.requests >= self.spec.min_requests {
+            let increase = candidate.error_rate() - stable.error_rate();
+            if increase > self.spec.max_error_rate_increase {
+                return Verdict::Rollback(format!(
+                    "Canary error rate {:.1}% exceeds the stable {:.1}% by more than {:.1} points",
+                    candidate.error_rate() * 100.0,
+                    stable.error_rate() * 100.0,
+                    self.spec.max_error_rate_increase * 100.0
+                ));
+            }
+        }
+
+        let window = chrono::Duration::minutes(self.spec.trial_minutes as i64);
+        if now >= self.started_at + window {
+            Verdict::Promote
+        } else {
+            Verdict::Continue
+        }
+    }
+
+    /// Mark the canary as ended
+    pub fn finish(&mut self, state: CanaryState, reason: Option<String>) {
+        self.state = state;
+        self.ended_at = Some(Utc::now());
+        self.reason = reason;
+    }
+}
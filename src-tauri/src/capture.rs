@@ -0,0 +1,309 @@
+// Capture and replay of API exchanges for debugging
+//
+// With `capture.enabled` set, every completion request and the response
+// MindLink gave to it are written to `~/.mindlink/captures`, one JSON file per
+// exchange, so bugs in the translation between the OpenAI and ChatGPT formats
+// can be diagnosed after the fact. Credentials are never written: authorization
+// headers and cookies are replaced by a mask, and API keys in the bodies are
+// masked along with whatever the redaction rules mask. Only the newest
+// `capture.max_captures` exchanges are kept.
+//
+// A capture can be replayed against the running server. The replay carries an
+// `x-mindlink-replay` header naming the capture it repeats, so that its own
+// capture can be told apart and compared with the original.
+
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::log_warn;
+use crate::redaction::Redactor;
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub const REPLAY_HEADER: &str = "x-mindlink-replay";
+
+/// Body bytes kept per request or response; the rest is cut off
+pub const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Replaces the value of every credential header
+pub const MASK: &str = "[REDACTED]";
+
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+    "api-key",
+];
+
+/// Headers a replay does not send again: the HTTP client sets them itself
+const HOP_HEADERS: &[&str] = &[
+    "host",
+    "content-length",
+    "connection",
+    "transfer-encoding",
+    "accept-encoding",
+    "x-request-id",
+    REPLAY_HEADER,
+];
+
+/// One side of a captured exchange
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CapturedMessage {
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+    /// The body was longer than `MAX_BODY_BYTES`
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Capture {
+    pub id: String,
+    pub request_id: Option<String>,
+    /// ID of the capture this exchange replayed
+    pub replay_of: Option<String>,
+    pub captured_at: DateTime<Utc>,
+    pub method: String,
+    /// Path and query
+    pub path: String,
+    pub status: u16,
+    /// Until the last byte of the response body was sent
+    pub duration_ms: u64,
+    pub request: CapturedMessage,
+    pub response: CapturedMessage,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureSummary {
+    pub id: String,
+    pub request_id: Option<String>,
+    pub replay_of: Option<String>,
+    pub captured_at: DateTime<Utc>,
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    pub duration_ms: u64,
+}
+
+impl From<&Capture> for CaptureSummary {
+    fn from(capture: &Capture) -> Self {
+        Self {
+            id: capture.id.clone(),
+            request_id: capture.request_id.clone(),
+            replay_of: capture.replay_of.clone(),
+            captured_at: capture.captured_at,
+            method: capture.method.clone(),
+            path: capture.path.clone(),
+            status: capture.status,
+            duration_ms: capture.duration_ms,
+        }
+    }
+}
+
+/// Outcome of sending a captured request again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayResult {
+    pub capture_id: String,
+    pub original_status: u16,
+    pub status: u16,
+    pub duration_ms: u64,
+    pub body: String,
+}
+
+/// IDs sort in capture order, so the file names do too
+pub fn new_capture_id(captured_at: DateTime<Utc>) -> String {
+    let suffix = Uuid::new_v4().simple().to_string();
+    format!(
+        "cap_{}_{}",
+        captured_at.format("%Y%m%d%H%M%S%3f"),
+        &suffix[..8]
+    )
+}
+
+/// Only IDs made by [`new_capture_id`] can name a file in the store
+fn is_valid_capture_id(id: &str) -> bool {
+    id.starts_with("cap_")
+        && id.len() <= 64
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The headers as a map, credentials masked. Repeated headers are joined.
+pub fn sanitize_headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    let mut sanitized: BTreeMap<String, String> = BTreeMap::new();
+    for (name, value) in headers {
+        let name = name.as_str().to_string();
+        let value = if SENSITIVE_HEADERS.contains(&name.as_str()) {
+            MASK.to_string()
+        } else {
+            String::from_utf8_lossy(value.as_bytes()).into_owned()
+        };
+        sanitized
+            .entry(name)
+            .and_modify(|existing| {
+                if existing != MASK {
+                    existing.push_str(", ");
+                    existing.push_str(&value);
+                }
+            })
+            .or_insert(value);
+    }
+    sanitized
+}
+
+/// The headers a replay of `capture` sends
+pub fn replay_headers(capture: &Capture) -> Vec<(String, String)> {
+    capture
+        .request
+        .headers
+        .iter()
+        .filter(|(name, value)| value.as_str() != MASK && !HOP_HEADERS.contains(&name.as_str()))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+/// Masks API keys, and what the redaction rules mask when they are enabled
+#[derive(Debug)]
+pub struct Sanitizer {
+    api_keys: Redactor,
+    rules: Option<Arc<Redactor>>,
+}
+
+impl Sanitizer {
+    pub fn new(rules: Option<Arc<Redactor>>) -> Self {
+        Self {
+            api_keys: Redactor::api_keys(),
+            rules,
+        }
+    }
+
+    /// `body` as text, cut off at `MAX_BODY_BYTES` and masked
+    pub fn message(&self, headers: &HeaderMap, body: &[u8]) -> CapturedMessage {
+        let truncated = body.len() > MAX_BODY_BYTES;
+        let body = String::from_utf8_lossy(&body[..body.len().min(MAX_BODY_BYTES)]);
+        let body = match &self.rules {
+            Some(rules) => rules.redact(&body).0,
+            None => body.into_owned(),
+        };
+        CapturedMessage {
+            headers: sanitize_headers(headers),
+            body: self.api_keys.redact(&body).0,
+            truncated,
+        }
+    }
+}
+
+fn fs_error(message: &str, path: &Path, operation: &str, e: std::io::Error) -> MindLinkError {
+    MindLinkError::FileSystem {
+        message: message.to_string(),
+        path: Some(path.to_string_lossy().to_string()),
+        operation: operation.to_string(),
+        source: Some(e.into()),
+    }
+}
+
+/// Captures on disk. File operations block, so async callers go through the
+/// blocking pool.
+#[derive(Debug)]
+pub struct CaptureStore {
+    dir: PathBuf,
+    max_captures: usize,
+}
+
+impl CaptureStore {
+    /// Default location: `~/.mindlink/captures`
+    pub fn default_dir() -> MindLinkResult<PathBuf> {
+        dirs::home_dir()
+            .map(|home| home.join(".mindlink").join("captures"))
+            .ok_or_else(|| MindLinkError::SystemResource {
+                message: "Cannot determine home directory".to_string(),
+                resource_type: "home directory".to_string(),
+                source: None,
+            })
+    }
+
+    pub fn open(dir: &Path, max_captures: usize) -> MindLinkResult<Self> {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| fs_error("Failed to create capture directory", dir, "create", e))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            max_captures: max_captures.max(1),
+        })
+    }
+
+    /// Capture IDs, newest first
+    fn ids(&self) -> MindLinkResult<Vec<String>> {
+        let entries = std::fs::read_dir(&self.dir)
+            .map_err(|e| fs_error("Failed to read capture directory", &self.dir, "read_dir", e))?;
+        let mut ids: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                name.strip_suffix(".json")
+                    .filter(|id| is_valid_capture_id(id))
+                    .map(str::to_string)
+            })
+            .collect();
+        ids.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(ids)
+    }
+
+    /// Write `capture` and drop the oldest captures beyond the limit
+    pub fn save(&self, capture: &Capture) -> MindLinkResult<()> {
+        let path = self.dir.join(format!("{}.json", capture.id));
+        let json = serde_json::to_vec_pretty(capture).map_err(|e| MindLinkError::Internal {
+            message: "Failed to serialize capture".to_string(),
+            component: Some("CaptureStore".to_string()),
+            source: Some(e.into()),
+        })?;
+        std::fs::write(&path, json)
+            .map_err(|e| fs_error("Failed to write capture", &path, "write", e))?;
+
+        for id in self.ids()?.into_iter().skip(self.max_captures) {
+            let path = self.dir.join(format!("{}.json", id));
+            if let Err(e) = std::fs::remove_file(&path) {
+                log_warn!(
+                    "CaptureStore",
+                    &format!("Failed to remove old capture {}: {}", path.display(), e)
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// The capture with `id`, `None` if there is none
+    pub fn load(&self, id: &str) -> MindLinkResult<Option<Capture>> {
+        if !is_valid_capture_id(id) {
+            return Ok(None);
+        }
+        let path = self.dir.join(format!("{}.json", id));
+        let json = match std::fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(fs_error("Failed to read capture", &path, "read", e)),
+        };
+        serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| MindLinkError::Internal {
+                message: format!("Capture {} is unreadable", id),
+                component: Some("CaptureStore".to_string()),
+                source: Some(e.into()),
+            })
+    }
+
+    /// Up to `limit` captures, newest first. Unreadable files are skipped.
+    pub fn list(&self, limit: usize) -> MindLinkResult<Vec<CaptureSummary>> {
+        let mut summaries = Vec::new();
+        for id in self.ids()?.into_iter().take(limit) {
+            match self.load(&id) {
+                Ok(Some(capture)) => summaries.push(CaptureSummary::from(&capture)),
+                Ok(None) => {},
+                Err(e) => log_warn!("CaptureStore", &format!("Skipping capture {}: {}", id, e)),
+            }
+        }
+        Ok(summaries)
+    }
+}
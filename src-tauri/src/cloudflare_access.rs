@@ -0,0 +1,320 @@
+// Cloudflare Access protection of the named tunnel
+//
+// A named tunnel serves MindLink at a hostname anyone can reach. For users
+// whose zone is on Cloudflare, MindLink creates an Access application for
+// that hostname with the account's API: a service token for the paired
+// clients and a policy letting only that token in. Cloudflare then refuses
+// everyone else before a request reaches the tunnel. Requests that do get
+// through carry a JWT signed by the account's team domain, which the server
+// verifies for the tunnel's hostname, so the check holds even when the Access
+// application is removed or loosened.
+
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::managers::config_manager::CloudflareAccessConfig;
+use crate::{network_error, proxy};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use reqwest::{Client, Method};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// Cloudflare's API
+pub const API_BASE: &str = "https://api.cloudflare.com/client/v4";
+
+/// Header carrying the JWT of a request that got through Access
+pub const JWT_HEADER: &str = "cf-access-jwt-assertion";
+
+/// Headers clients authenticate with using the service token
+pub const CLIENT_ID_HEADER: &str = "CF-Access-Client-Id";
+pub const CLIENT_SECRET_HEADER: &str = "CF-Access-Client-Secret";
+
+/// How long the signing keys of the team domain are used before they are
+/// fetched again. Cloudflare rotates them every six weeks.
+const KEYS_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Least time between two fetches of the signing keys, so tokens with
+/// unknown key IDs cannot make every request fetch them
+const KEYS_MIN_REFRESH: Duration = Duration::from_secs(60);
+
+const API_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// The verifier of the running server, replaced when the tunnel gets
+/// protected without restarting it
+pub type SharedAccessVerifier = Arc<RwLock<Option<Arc<AccessVerifier>>>>;
+
+#[derive(Debug, Deserialize)]
+struct ApiResponse<T> {
+    success: bool,
+    #[serde(default)]
+    errors: Vec<ApiMessage>,
+    result: Option<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiMessage {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Organization {
+    auth_domain: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AccessApp {
+    id: String,
+    aud: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ServiceToken {
+    id: String,
+    client_id: String,
+    client_secret: String,
+}
+
+/// Access endpoints of one Cloudflare account
+struct AccessApi<'a> {
+    client: &'a Client,
+    base: String,
+    api_token: &'a str,
+}
+
+impl AccessApi<'_> {
+    async fn call<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> MindLinkResult<T> {
+        let url = format!("{}{}", self.base, path);
+        let mut request = self
+            .client
+            .request(method, &url)
+            .bearer_auth(self.api_token)
+            .timeout(API_TIMEOUT);
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| network_error!("Cloudflare API is unreachable", &url, e))?;
+        let status = response.status();
+        let response: ApiResponse<T> = response
+            .json()
+            .await
+            .map_err(|e| network_error!("Invalid response from the Cloudflare API", &url, e))?;
+
+        match response.result {
+            Some(result) if response.success => Ok(result),
+            _ => {
+                let errors: Vec<String> = response.errors.into_iter().map(|e| e.message).collect();
+                Err(MindLinkError::Tunnel {
+                    message: format!(
+                        "Cloudflare refused {} ({}): {}",
+                        path,
+                        status,
+                        errors.join("; ")
+                    ),
+                    tunnel_type: Some("named".to_string()),
+                    local_port: None,
+                    source: None,
+                })
+            },
+        }
+    }
+}
+
+/// Create what is still missing of the Access application protecting
+/// `hostname` through the API at `api_base`: the application, then a service
+/// token and the policy letting it in. `access` is filled in as each step
+/// succeeds, so a failed run can be resumed.
+pub async fn provision(
+    client: &Client,
+    api_base: &str,
+    hostname: &str,
+    access: &mut CloudflareAccessConfig,
+) -> MindLinkResult<()> {
+    let api_token = access.api_token.clone().unwrap_or_default();
+    let api = AccessApi {
+        client,
+        base: format!(
+            "{}/accounts/{}/access",
+            api_base.trim_end_matches('/'),
+            access.account_id
+        ),
+        api_token: &api_token,
+    };
+
+    if access.team_domain.is_none() {
+        let organization: Organization = api.call(Method::GET, "/organizations", None).await?;
+        access.team_domain = Some(organization.auth_domain);
+    }
+
+    let new_app = access.app_id.is_none();
+    if new_app {
+        let app: AccessApp = api
+            .call(
+                Method::POST,
+                "/apps",
+                Some(serde_json::json!({
+                    "name": format!("MindLink ({})", hostname),
+                    "domain": hostname,
+                    "type": "self_hosted",
+                    "session_duration": "24h",
+                })),
+            )
+            .await?;
+        access.app_id = Some(app.id);
+        access.audience = Some(app.aud);
+    }
+
+    // A policy names its token, so a new application gets a new token too
+    if new_app || access.client_id.is_none() {
+        let token: ServiceToken = api
+            .call(
+                Method::POST,
+                "/service_tokens",
+                Some(serde_json::json!({ "name": format!("MindLink clients ({})", hostname) })),
+            )
+            .await?;
+        let app_id = access.app_id.clone().unwrap_or_default();
+        let _: Value = api
+            .call(
+                Method::POST,
+                &format!("/apps/{}/policies", app_id),
+                Some(serde_json::json!({
+                    "name": "MindLink clients",
+                    "decision": "non_identity",
+                    "include": [{ "service_token": { "token_id": token.id } }],
+                    "precedence": 1,
+                })),
+            )
+            .await?;
+        access.client_id = Some(token.client_id);
+        access.client_secret = Some(token.client_secret);
+    }
+
+    Ok(())
+}
+
+/// Verifies the Access JWTs of requests for the tunnel's hostname
+#[derive(Debug)]
+pub struct AccessVerifier {
+    hostname: String,
+    issuer: String,
+    audience: String,
+    certs_url: String,
+    client: Client,
+    keys: RwLock<Option<(Instant, Arc<JwkSet>)>>,
+}
+
+impl AccessVerifier {
+    /// Verifier for `hostname`, when `access` is enabled and its application
+    /// was created
+    pub fn from_config(
+        hostname: &str,
+        access: &CloudflareAccessConfig,
+    ) -> MindLinkResult<Option<Self>> {
+        let (Some(team_domain), Some(audience)) = (&access.team_domain, &access.audience) else {
+            return Ok(None);
+        };
+        if !access.enabled {
+            return Ok(None);
+        }
+
+        let client = proxy::apply(Client::builder())
+            .timeout(API_TIMEOUT)
+            .build()
+            .map_err(|e| network_error!("Failed to create HTTP client", "", e))?;
+        Ok(Some(Self {
+            hostname: hostname.to_ascii_lowercase(),
+            issuer: format!("https://{}", team_domain),
+            audience: audience.clone(),
+            certs_url: format!("https://{}/cdn-cgi/access/certs", team_domain),
+            client,
+            keys: RwLock::new(None),
+        }))
+    }
+
+    /// Whether the `Host` of a request is the protected hostname
+    pub fn protects(&self, host: &str) -> bool {
+        let name = host.rsplit_once(':').map_or(host, |(name, _)| name);
+        name.eq_ignore_ascii_case(&self.hostname)
+    }
+
+    /// Check `token` against the current signing keys of the team domain
+    pub async fn verify(&self, token: &str) -> MindLinkResult<()> {
+        let kid = decode_header(token)
+            .ok()
+            .and_then(|header| header.kid)
+            .ok_or_else(|| rejected("malformed token", None))?;
+        let keys = self.keys_with(&kid).await?;
+        self.verify_with(token, &keys)
+    }
+
+    /// Check `token` against the signing keys `keys`
+    pub fn verify_with(&self, token: &str, keys: &JwkSet) -> MindLinkResult<()> {
+        let kid = decode_header(token)
+            .ok()
+            .and_then(|header| header.kid)
+            .ok_or_else(|| rejected("malformed token", None))?;
+        let jwk = keys
+            .find(&kid)
+            .ok_or_else(|| rejected("unknown signing key", None))?;
+        let key = DecodingKey::from_jwk(jwk)
+            .map_err(|e| rejected("unusable signing key", Some(e.into())))?;
+
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_audience(&[&self.audience]);
+        validation.set_issuer(&[&self.issuer]);
+        decode::<Value>(token, &key, &validation)
+            .map_err(|e| rejected("invalid token", Some(e.into())))?;
+        Ok(())
+    }
+
+    /// Signing keys, fetched again when they are old or lack `kid`
+    async fn keys_with(&self, kid: &str) -> MindLinkResult<Arc<JwkSet>> {
+        if let Some((fetched_at, keys)) = &*self.keys.read().await {
+            let age = fetched_at.elapsed();
+            let usable = age < KEYS_MAX_AGE && keys.find(kid).is_some();
+            if usable || age < KEYS_MIN_REFRESH {
+                return Ok(keys.clone());
+            }
+        }
+
+        let response = self
+            .client
+            .get(&self.certs_url)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| {
+                network_error!(
+                    "Failed to fetch the Access signing keys",
+                    &self.certs_url,
+                    e
+                )
+            })?;
+        let keys: Arc<JwkSet> = Arc::new(
+            response
+                .json()
+                .await
+                .map_err(|e| network_error!("Invalid Access signing keys", &self.certs_url, e))?,
+        );
+        *self.keys.write().await = Some((Instant::now(), keys.clone()));
+        Ok(keys)
+    }
+}
+
+fn rejected(reason: &str, source: Option<anyhow::Error>) -> MindLinkError {
+    MindLinkError::Authentication {
+        message: format!("Cloudflare Access JWT rejected: {}", reason),
+        source,
+    }
+}
@@ -0,0 +1,171 @@
+// Releases of cloudflared and their checksums
+//
+// cloudflared is downloaded from its GitHub releases, and Cloudflare lists
+// the SHA-256 of every asset in the notes of each release. A download is only
+// installed when its bytes match that checksum; the digest GitHub computed
+// when the asset was uploaded stands in for releases whose notes lack one.
+// Without either, the download is refused.
+//
+// Releases are named by date, e.g. `2024.6.1`. The managed copy follows the
+// latest release unless a version is pinned in the config, and the release it
+// was downloaded from is kept in the binary manifest so updates can be found.
+
+use crate::binary_drift;
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use ts_rs::TS;
+
+const RELEASES_URL: &str = "https://api.github.com/repos/cloudflare/cloudflared/releases";
+
+/// A file published with a release
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+    /// `sha256:<hex>`, as computed by GitHub
+    #[serde(default)]
+    pub digest: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Release {
+    /// The version, e.g. `2024.6.1`
+    pub tag_name: String,
+    /// Release notes, listing the checksums of the assets
+    #[serde(default)]
+    pub body: Option<String>,
+    pub assets: Vec<ReleaseAsset>,
+}
+
+impl Release {
+    pub fn asset(&self, name: &str) -> Result<&ReleaseAsset> {
+        self.assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .ok_or_else(|| anyhow!("cloudflared {} has no asset {}", self.tag_name, name))
+    }
+
+    /// Published SHA-256 of the asset `name`, in lowercase hex
+    pub fn checksum(&self, name: &str) -> Option<String> {
+        let from_notes = self.body.as_deref().and_then(|body| {
+            // Lines read `<asset>: <sha256>`; sha256sum's `<sha256>  <asset>`
+            // is understood as well
+            body.lines().find_map(|line| {
+                let tokens: Vec<&str> = line
+                    .split(|c: char| c.is_whitespace() || c == ':')
+                    .filter(|token| !token.is_empty())
+                    .collect();
+                match tokens.as_slice() {
+                    [a, b] if *a == name && is_sha256(b) => Some(b.to_ascii_lowercase()),
+                    [a, b] if *b == name && is_sha256(a) => Some(a.to_ascii_lowercase()),
+                    _ => None,
+                }
+            })
+        });
+        from_notes.or_else(|| {
+            let asset = self.assets.iter().find(|asset| asset.name == name)?;
+            let digest = asset.digest.as_deref()?.strip_prefix("sha256:")?;
+            is_sha256(digest).then(|| digest.to_ascii_lowercase())
+        })
+    }
+}
+
+/// Parts of the release `version`, e.g. `[2024, 6, 1]`; `None` unless it
+/// names a release
+pub fn version_parts(version: &str) -> Option<Vec<u64>> {
+    let parts = version
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect::<Option<Vec<u64>>>()?;
+    (parts.len() == 3).then_some(parts)
+}
+
+/// Whether the release `candidate` came out after `installed`. A version
+/// that names no release is taken to be older than any that does.
+pub fn is_newer(candidate: &str, installed: &str) -> bool {
+    match (version_parts(candidate), version_parts(installed)) {
+        (Some(candidate), Some(installed)) => candidate > installed,
+        (Some(_), None) => true,
+        (None, _) => false,
+    }
+}
+
+/// Outcome of updating the managed cloudflared
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+pub struct CloudflaredUpdate {
+    /// Release installed before, if MindLink had a verified copy
+    pub previous: Option<String>,
+    /// Release installed now
+    pub installed: String,
+    /// Whether a new release was downloaded
+    pub updated: bool,
+    /// Whether `installed` is the pinned release rather than the latest
+    pub pinned: bool,
+}
+
+fn is_sha256(token: &str) -> bool {
+    token.len() == 64 && token.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Name of the asset to install on `os` and `arch`, as in
+/// `std::env::consts`
+pub fn asset_name(os: &str, arch: &str) -> Option<&'static str> {
+    match (os, arch) {
+        ("linux", "x86_64") => Some("cloudflared-linux-amd64"),
+        ("linux", "aarch64") => Some("cloudflared-linux-arm64"),
+        ("macos", "x86_64") => Some("cloudflared-darwin-amd64.tgz"),
+        ("macos", "aarch64") => Some("cloudflared-darwin-amd64.tgz"),
+        ("windows", "x86_64") => Some("cloudflared-windows-amd64.exe"),
+        ("windows", "aarch64") => Some("cloudflared-windows-386.exe"),
+        _ => None,
+    }
+}
+
+/// The release `version` of cloudflared, or the latest one
+pub async fn release(client: &Client, version: Option<&str>) -> Result<Release> {
+    let (url, name) = match version {
+        Some(version) => (
+            format!("{}/tags/{}", RELEASES_URL, version),
+            format!("cloudflared release {}", version),
+        ),
+        None => (
+            format!("{}/latest", RELEASES_URL),
+            "the latest cloudflared release".to_string(),
+        ),
+    };
+    let response = client
+        .get(&url)
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .send()
+        .await
+        .map_err(|e| anyhow!("Failed to look up {}: {}", name, e))?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Failed to look up {}: HTTP {}",
+            name,
+            response.status()
+        ));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| anyhow!("Unreadable cloudflared release: {}", e))
+}
+
+/// Check the download at `path` against the published `expected`
+/// checksum, returning its SHA-256
+pub fn verify(path: &Path, expected: &str) -> Result<String> {
+    let actual = binary_drift::sha256_file(path)?;
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(anyhow!(
+            "Downloaded cloudflared does not match its published checksum \
+             (expected SHA-256 {}, got {}); refusing to install it",
+            expected,
+            actual
+        ));
+    }
+    Ok(actual)
+}
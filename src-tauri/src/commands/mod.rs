@@ -44,10 +44,50 @@
 //!
 //! All commands are designed to be thread-safe and can handle concurrent
 //! calls by using appropriate locking mechanisms through the `AppState`.
-use crate::error::MindLinkError;
+use crate::accounts::{AccountPool, AccountStatus};
+use crate::analytics::{AnalyticsStatsSnapshot, QuotaAttribution, RequestRecord};
+use crate::auth_probe::{self, ProbeOutcome};
+use crate::authorized_apps::{generate_api_key, AuthorizedApp};
+use crate::binary_drift::DriftFinding;
+use crate::budgets::AppBudget;
+use crate::bundles::{self, BundleSigner, ConflictStrategy, ImportedEntry, SignedBundle};
+use crate::canary::{Canary, CanarySpec, CanaryStatus, Verdict};
+use crate::capture::{Capture, CaptureSummary, ReplayResult};
+use crate::cloudflare_access::{self, AccessVerifier};
+use crate::cloudflared_release::CloudflaredUpdate;
+use crate::config_dry_run::{self, DryRunReport};
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::error_feed::ErrorFeedEntry;
+use crate::events::{self, AppEvent, NotificationKind};
+use crate::health::{self, HealthReport};
 use crate::logging::{get_logger, LogCategory, LogEntry, LogLevel};
-use crate::managers::config_manager::ConfigSchema;
+use crate::managers::auth_manager::{codex_auth_path, AuthManager, SavedAccount, UserIdentity};
+use crate::managers::config_manager::{
+    AnthropicConfig, AuthConfig, CloudflareAccessConfig, ConfigSchema, ModelAliasConfig,
+    PostProcessingConfig, PowerSaverMode, PromptConfig, ProxyConfig, RequestTransformConfig,
+    ServerConfig, TunnelConfig,
+};
+use crate::managers::local_model_manager::WarmModel;
+use crate::managers::plugin_manager::{
+    PluginLoadError, PluginManager, PluginManifest, PluginRegistry,
+};
+use crate::managers::server_manager::{Model, ServerManager};
+use crate::managers::tunnel_manager::{ServiceTunnel, TunnelManager};
+use crate::middleware::metrics::TunnelTraffic;
+use crate::middleware::tunnel_auth::TunnelTokenPolicy;
+use crate::pairing_qr;
+use crate::ping::ConnectionReport;
+use crate::plan::PlanQuota;
+use crate::power::{self, PowerStatus};
+use crate::request_log::{RequestSummary, StreamStats};
+use crate::secret_box;
+use crate::security_report::{build_report, ExposureInputs, Listener, SecurityReport};
+use crate::shadow::ShadowReport;
+use crate::token_store;
+use crate::tunnel_tokens::{self, TunnelToken, TunnelTokenRotated};
 use crate::AppState;
+use crate::{log_error, log_info, log_warn};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
 use tauri::{AppHandle, Manager};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -57,7 +97,9 @@ use chrono;
 use tokio::process::Command;
 use tokio::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::Instant;
+use tokio::sync::RwLock;
 
 /// Response type for status queries, providing comprehensive system state information.
 ///
@@ -72,7 +114,11 @@ use std::time::Instant;
 /// - `server_url`: Local API server URL (usually http://localhost:3001)
 /// - `bifrost_url`: Bifrost dashboard URL (if running)
 /// - `instance_token`: Unique token for this MindLink instance
-/// - `last_error`: Most recent error message (if any)
+/// - `last_error`: Most recent error message (if any); `get_error_feed` keeps the history
+/// - `health`: Latest health report with per-component levels and reasons
+/// - `accounts`: Signed-in ChatGPT accounts, the active one first, with whether their tokens are valid
+/// - `user`: Email, plan and account ID of the active account, from its ID token
+/// - `quota`: Plan of the active account and the concurrency it is expected to sustain
 #[derive(Debug, Serialize, Deserialize)]
 pub struct StatusResponse {
     pub is_serving: bool,
@@ -82,6 +128,10 @@ pub struct StatusResponse {
     pub bifrost_url: Option<String>,
     pub instance_token: Option<String>,
     pub last_error: Option<String>,
+    pub health: HealthReport,
+    pub accounts: Vec<SavedAccount>,
+    pub user: Option<UserIdentity>,
+    pub quota: Option<PlanQuota>,
 }
 
 /// Response type for QR data containing tunnel URL and instance token
@@ -89,9 +139,29 @@ pub struct StatusResponse {
 pub struct QrDataResponse {
     pub success: bool,
     pub qr_data: Option<String>,
+    /// The QR code of `qr_data` as a base64-encoded PNG
+    pub qr_png: Option<String>,
+    /// The QR code of `qr_data` as a base64-encoded SVG
+    pub qr_svg: Option<String>,
     pub error: Option<String>,
 }
 
+/// Token of clients paired through the tunnel, and until when the token it
+/// replaced keeps working
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TunnelTokenResponse {
+    pub token: TunnelToken,
+    pub previous_expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Service token clients send to get through Cloudflare Access, as the
+/// `CF-Access-Client-Id` and `CF-Access-Client-Secret` headers
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CloudflareAccessResponse {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
 /// Standard response type for service operations (start, stop, etc.).
 ///
 /// This structure provides a consistent format for all service management
@@ -137,26 +207,31 @@ pub struct ServiceResponse {
 /// ```
 #[tauri::command]
 pub async fn get_status(state: State<'_, AppState>) -> Result<StatusResponse, String> {
+    let server_config = {
+        let config_manager = state.config_manager.read().await;
+        config_manager.get_server_config().await
+    };
+
     // Check actual service states, not just internal flags
-    let is_serving = check_actual_server_running().await.unwrap_or(*state.is_serving.read().await);
+    let is_serving = check_actual_server_running(&server_config)
+        .await
+        .unwrap_or(*state.is_serving.read().await);
     let last_error = state.last_error.read().await.clone();
 
-    let is_authenticated = {
+    let (accounts, user, quota) = {
         let auth_manager = state.auth_manager.read().await;
-        auth_manager.is_authenticated().await
+        let accounts = auth_manager.accounts().await.unwrap_or_else(|e| {
+            log_warn!("Auth", &format!("Failed to list accounts: {}", e));
+            Vec::new()
+        });
+        (accounts, auth_manager.identity(), auth_manager.quota())
     };
+    let is_authenticated = session_is_valid(&state).await;
 
-    // Check for actual tunnel URL by detecting running cloudflare processes
-    let tunnel_url = match detect_actual_tunnel_url().await {
-        Some(url) => Some(url),
-        None => {
-            let tunnel_manager = state.tunnel_manager.read().await;
-            tunnel_manager.get_current_url().await
-        }
-    };
+    let tunnel_url = state.tunnel_manager.read().await.get_current_url().await;
 
     let server_url = if is_serving {
-        Some("http://127.0.0.1:3001".to_string())
+        Some(server_config.local_url())
     } else {
         let server_manager = state.server_manager.read().await;
         server_manager.get_local_url().await
@@ -182,9 +257,610 @@ pub async fn get_status(state: State<'_, AppState>) -> Result<StatusResponse, St
         bifrost_url,
         instance_token,
         last_error,
+        health: health::current_health().await,
+        accounts,
+        user,
+        quota,
     })
 }
 
+/// Returns the latest health report produced by the health monitor.
+///
+/// Each component (server, auth, tunnel, bifrost, dashboard) carries a level of
+/// `ok`, `degraded` or `down` plus a reason; the overall level is `down` only
+/// when a critical component (server or auth) is down.
+#[tauri::command]
+pub async fn get_health_report() -> Result<HealthReport, String> {
+    Ok(health::current_health().await)
+}
+
+/// Returns the write-behind counters of the request analytics store: records
+/// queued, sampled out under disk pressure, dropped, flushed and still pending.
+#[tauri::command]
+pub async fn get_analytics_stats(
+    state: State<'_, AppState>,
+) -> Result<AnalyticsStatsSnapshot, String> {
+    Ok(state.server_manager.read().await.analytics_stats())
+}
+
+/// Fetches the model list again from the ChatGPT account and a running Bifrost
+/// instance, bypassing the five-minute cache used by `/v1/models`.
+#[tauri::command]
+pub async fn refresh_models(state: State<'_, AppState>) -> Result<Vec<Model>, String> {
+    let server_manager = state.server_manager.read().await;
+    Ok(server_manager.refresh_models(&state.auth_manager).await)
+}
+
+/// Returns the map from requested model names to upstream ChatGPT models.
+#[tauri::command]
+pub async fn get_model_aliases(
+    state: State<'_, AppState>,
+) -> Result<HashMap<String, String>, String> {
+    let config_manager = state.config_manager.read().await;
+    Ok(config_manager.get_model_aliases().await.aliases)
+}
+
+/// Saves a new model alias map and applies it to the running server.
+///
+/// Keys are the model names clients request; `*` catches every other name.
+#[tauri::command]
+pub async fn set_model_aliases(
+    state: State<'_, AppState>,
+    aliases: HashMap<String, String>,
+) -> Result<(), String> {
+    let model_aliases = ModelAliasConfig { aliases };
+
+    state
+        .config_manager
+        .read()
+        .await
+        .set_model_aliases(model_aliases.clone())
+        .await
+        .map_err(|e| e.user_message())?;
+
+    state
+        .server_manager
+        .read()
+        .await
+        .set_model_aliases(model_aliases)
+        .await;
+    Ok(())
+}
+
+/// Returns the system prompt, named prompt templates and template variables.
+#[tauri::command]
+pub async fn get_prompt_config(state: State<'_, AppState>) -> Result<PromptConfig, String> {
+    let config_manager = state.config_manager.read().await;
+    Ok(config_manager.get_prompt_config().await)
+}
+
+/// Saves the system prompt and templates and applies them to the running server.
+#[tauri::command]
+pub async fn set_prompt_config(
+    state: State<'_, AppState>,
+    prompts: PromptConfig,
+) -> Result<(), String> {
+    state
+        .config_manager
+        .read()
+        .await
+        .set_prompt_config(prompts.clone())
+        .await
+        .map_err(|e| e.user_message())?;
+
+    state
+        .server_manager
+        .read()
+        .await
+        .set_prompt_config(prompts)
+        .await;
+    Ok(())
+}
+
+/// Returns the rules that rewrite completion text before it is returned.
+#[tauri::command]
+pub async fn get_post_processing_config(
+    state: State<'_, AppState>,
+) -> Result<PostProcessingConfig, String> {
+    let config_manager = state.config_manager.read().await;
+    Ok(config_manager.get_post_processing_config().await)
+}
+
+/// Saves new post-processing rules and applies them to the running server.
+#[tauri::command]
+pub async fn set_post_processing_config(
+    state: State<'_, AppState>,
+    post_processing: PostProcessingConfig,
+) -> Result<(), String> {
+    state
+        .config_manager
+        .read()
+        .await
+        .set_post_processing_config(post_processing.clone())
+        .await
+        .map_err(|e| e.user_message())?;
+
+    state
+        .server_manager
+        .read()
+        .await
+        .set_post_processing(&post_processing)
+        .await
+        .map_err(|e| e.user_message())
+}
+
+/// Returns the rules that rewrite incoming completion requests.
+#[tauri::command]
+pub async fn get_request_transform_config(
+    state: State<'_, AppState>,
+) -> Result<RequestTransformConfig, String> {
+    let config_manager = state.config_manager.read().await;
+    Ok(config_manager.get_request_transform_config().await)
+}
+
+/// Saves new request transformation rules and applies them to the running
+/// server.
+#[tauri::command]
+pub async fn set_request_transform_config(
+    state: State<'_, AppState>,
+    request_transforms: RequestTransformConfig,
+) -> Result<(), String> {
+    state
+        .config_manager
+        .read()
+        .await
+        .set_request_transform_config(request_transforms.clone())
+        .await
+        .map_err(|e| e.user_message())?;
+
+    state
+        .server_manager
+        .read()
+        .await
+        .set_request_transforms(&request_transforms)
+        .await
+        .map_err(|e| e.user_message())
+}
+
+/// Returns how ChatGPT accounts are signed in.
+#[tauri::command]
+pub async fn get_auth_config(state: State<'_, AppState>) -> Result<AuthConfig, String> {
+    let config_manager = state.config_manager.read().await;
+    Ok(config_manager.get_auth_config().await)
+}
+
+/// Saves how ChatGPT accounts are signed in, e.g. with a device code on a
+/// headless machine. Applies to the next login.
+#[tauri::command]
+pub async fn set_auth_config(state: State<'_, AppState>, auth: AuthConfig) -> Result<(), String> {
+    state
+        .config_manager
+        .read()
+        .await
+        .set_auth_config(auth.clone())
+        .await
+        .map_err(|e| e.user_message())?;
+
+    state.auth_manager.write().await.set_config(auth);
+    Ok(())
+}
+
+/// Returns the proxy for the traffic to OpenAI and ChatGPT.
+#[tauri::command]
+pub async fn get_proxy_config(state: State<'_, AppState>) -> Result<ProxyConfig, String> {
+    let config_manager = state.config_manager.read().await;
+    Ok(config_manager.get_proxy_config().await)
+}
+
+/// Saves the proxy for the traffic to OpenAI and ChatGPT. Logins and token
+/// refreshes use it right away, upstream requests once serving restarts.
+#[tauri::command]
+pub async fn set_proxy_config(
+    state: State<'_, AppState>,
+    proxy: ProxyConfig,
+) -> Result<(), String> {
+    state
+        .config_manager
+        .read()
+        .await
+        .set_proxy_config(proxy.clone())
+        .await
+        .map_err(|e| e.user_message())?;
+
+    crate::proxy::configure(proxy);
+    Ok(())
+}
+
+/// Returns how `claude-*` models are served from a Claude account.
+#[tauri::command]
+pub async fn get_anthropic_config(state: State<'_, AppState>) -> Result<AnthropicConfig, String> {
+    let config_manager = state.config_manager.read().await;
+    Ok(config_manager.get_anthropic_config().await)
+}
+
+/// Saves how `claude-*` models are served from a Claude account. The login
+/// settings apply to the next Claude login, enabling or disabling once
+/// serving restarts.
+#[tauri::command]
+pub async fn set_anthropic_config(
+    state: State<'_, AppState>,
+    anthropic: AnthropicConfig,
+) -> Result<(), String> {
+    state
+        .config_manager
+        .read()
+        .await
+        .set_anthropic_config(anthropic.clone())
+        .await
+        .map_err(|e| e.user_message())?;
+
+    state
+        .anthropic_auth
+        .write()
+        .await
+        .set_config(anthropic.auth_config());
+    Ok(())
+}
+
+/// Returns the public key this instance signs export bundles with, for
+/// teammates to add to their trusted signers.
+#[tauri::command]
+pub async fn get_bundle_public_key() -> Result<String, String> {
+    let path = BundleSigner::key_path().map_err(|e| e.user_message())?;
+    let signer = BundleSigner::load_or_create(&path)
+        .await
+        .map_err(|e| e.user_message())?;
+    Ok(signer.public_key())
+}
+
+/// Exports the prompt templates, model aliases and language routes as a
+/// signed bundle that teammates can import under `namespace`.
+#[tauri::command]
+pub async fn export_bundle(
+    state: State<'_, AppState>,
+    namespace: String,
+    author: Option<String>,
+) -> Result<SignedBundle, String> {
+    let config = state.config_manager.read().await.get_config().await;
+    let contents = bundles::export(
+        &namespace,
+        author,
+        &config.prompts,
+        &config.model_aliases,
+        &config.language_detection,
+    )
+    .map_err(|e| e.user_message())?;
+
+    let path = BundleSigner::key_path().map_err(|e| e.user_message())?;
+    BundleSigner::load_or_create(&path)
+        .await
+        .and_then(|signer| signer.sign(contents))
+        .map_err(|e| e.user_message())
+}
+
+/// Imports a bundle signed by this instance or a trusted signer. Templates
+/// and aliases are prefixed with `namespace` (default: the bundle's own);
+/// `conflicts` decides what happens to names that are taken (default: skip).
+/// Templates and aliases apply immediately, language routes the next time
+/// the server starts.
+#[tauri::command]
+pub async fn import_bundle(
+    state: State<'_, AppState>,
+    bundle: SignedBundle,
+    namespace: Option<String>,
+    conflicts: Option<ConflictStrategy>,
+) -> Result<Vec<ImportedEntry>, String> {
+    let path = BundleSigner::key_path().map_err(|e| e.user_message())?;
+    let own_key = BundleSigner::load_or_create(&path)
+        .await
+        .map_err(|e| e.user_message())?
+        .public_key();
+
+    let config_manager = state.config_manager.read().await;
+    let mut config = config_manager.get_config().await;
+    let signer = bundles::verify(&bundle, &own_key, &config.bundles.trusted_signers)
+        .map_err(|e| e.user_message())?;
+
+    let namespace = namespace.unwrap_or_else(|| bundle.contents.namespace.clone());
+    bundles::validate_namespace(&namespace).map_err(|e| e.user_message())?;
+
+    let entries = bundles::merge(
+        &bundle.contents,
+        &namespace,
+        conflicts.unwrap_or_default(),
+        &mut config.prompts,
+        &mut config.model_aliases,
+        &mut config.language_detection,
+    );
+    config_manager
+        .update_config(config.clone())
+        .await
+        .map_err(|e| e.user_message())?;
+    drop(config_manager);
+
+    let server_manager = state.server_manager.read().await;
+    server_manager.set_prompt_config(config.prompts).await;
+    server_manager.set_model_aliases(config.model_aliases).await;
+
+    log_info!(
+        "Bundles",
+        &format!(
+            "Imported {} entries from {} into namespace '{}'",
+            entries.len(),
+            signer,
+            namespace
+        )
+    );
+    Ok(entries)
+}
+
+/// Looks up the analytics journal entry of a request by its `x-request-id`,
+/// including the detected prompt language, for debugging client apps.
+#[tauri::command]
+pub async fn get_request_record(
+    state: State<'_, AppState>,
+    request_id: String,
+) -> Result<Option<RequestRecord>, String> {
+    state
+        .server_manager
+        .read()
+        .await
+        .request_record(request_id)
+        .await
+        .map_err(|e| e.user_message())
+}
+
+/// Returns up to `limit` (default 50) recently logged errors, newest first,
+/// each with the component that raised it, a user-facing message, technical
+/// details and the remedy to offer (retry, reauth, reinstall binary, ...).
+/// Unlike `last_error` in the status, earlier errors are not lost.
+#[tauri::command]
+pub async fn get_error_feed(limit: Option<usize>) -> Result<Vec<ErrorFeedEntry>, String> {
+    Ok(get_logger()
+        .map(|logger| logger.error_feed().recent(limit.unwrap_or(50)))
+        .unwrap_or_default())
+}
+
+/// Empties the error feed, e.g. once the user has dismissed it
+#[tauri::command]
+pub async fn clear_error_feed() -> Result<(), String> {
+    if let Some(logger) = get_logger() {
+        logger.error_feed().clear();
+    }
+    Ok(())
+}
+
+/// Journaled requests replayed by a config dry run, unless the caller asks
+/// for a different sample
+const DRY_RUN_SAMPLE: u32 = 500;
+const MAX_DRY_RUN_SAMPLE: u32 = 5000;
+
+/// Validates a proposed configuration without saving it, and replays the most
+/// recent `sample_size` journaled requests through the access policy, batch
+/// API switch, language routing and model aliases of both the current and the
+/// proposed configuration. Reports the requests that would be rejected,
+/// newly accepted or routed to a different model. Nothing is sent upstream.
+#[tauri::command]
+pub async fn dry_run_config(
+    state: State<'_, AppState>,
+    config: ConfigSchema,
+    sample_size: Option<u32>,
+) -> Result<DryRunReport, String> {
+    let current = state.config_manager.read().await.get_config().await;
+    let limit = sample_size
+        .unwrap_or(DRY_RUN_SAMPLE)
+        .min(MAX_DRY_RUN_SAMPLE) as usize;
+
+    let records = state
+        .server_manager
+        .read()
+        .await
+        .recent_requests(limit)
+        .await
+        .map_err(|e| e.user_message())?;
+
+    Ok(config_dry_run::dry_run(&current, &config, &records))
+}
+
+/// Returns who has been using the account's quota: estimated tokens over the
+/// last `window_minutes` (default 60) grouped by client key, device and model,
+/// heaviest first, with the limit, used and remaining tokens of each key's
+/// budgets, plus the number of streams currently in flight.
+#[tauri::command]
+pub async fn get_quota_attribution(
+    state: State<'_, AppState>,
+    window_minutes: Option<u32>,
+) -> Result<QuotaAttribution, String> {
+    let window = chrono::Duration::minutes(i64::from(window_minutes.unwrap_or(60).max(1)));
+
+    state
+        .server_manager
+        .read()
+        .await
+        .quota_attribution(window)
+        .await
+        .map_err(|e| e.user_message())
+}
+
+/// How often a running canary is judged
+const CANARY_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Starts trying a model alias map on a share of the traffic, or on one API
+/// key's requests. It is promoted and saved after its trial window, or rolled
+/// back as soon as its error rate rises too far above the current routing's.
+#[tauri::command]
+pub async fn start_canary(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    spec: CanarySpec,
+) -> Result<Canary, String> {
+    let canary = state
+        .server_manager
+        .read()
+        .await
+        .start_canary(spec)
+        .await
+        .map_err(|e| e.user_message())?;
+
+    tauri::async_runtime::spawn(watch_canary(app_handle));
+    Ok(canary)
+}
+
+/// Judge the running canary until it ends. A promoted alias map is saved as
+/// the configured one.
+async fn watch_canary(app_handle: AppHandle) {
+    loop {
+        tokio::time::sleep(CANARY_CHECK_INTERVAL).await;
+
+        let state = app_handle.state::<AppState>();
+        let checked = state.server_manager.read().await.check_canary().await;
+        match checked {
+            Ok(Some((Verdict::Continue, _))) => {},
+            Ok(Some((Verdict::Promote, canary))) => {
+                if let Err(e) = state
+                    .config_manager
+                    .read()
+                    .await
+                    .set_model_aliases(canary.aliases())
+                    .await
+                {
+                    log_error!("Canary", e);
+                }
+                events::notify(
+                    &app_handle,
+                    NotificationKind::Success,
+                    "Canary promoted",
+                    "The new model routing now serves all traffic",
+                );
+                return;
+            },
+            Ok(Some((Verdict::Rollback(reason), _))) => {
+                events::notify(
+                    &app_handle,
+                    NotificationKind::Warning,
+                    "Canary rolled back",
+                    reason,
+                );
+                return;
+            },
+            Ok(None) => return,
+            Err(e) => log_warn!(
+                "Canary",
+                &format!("Canary check failed: {}", e.user_message())
+            ),
+        }
+    }
+}
+
+/// Returns the current or last canary with the requests and errors of both
+/// sides since it started, or `None` if none was started.
+#[tauri::command]
+pub async fn get_canary_status(state: State<'_, AppState>) -> Result<Option<CanaryStatus>, String> {
+    state
+        .server_manager
+        .read()
+        .await
+        .canary_status()
+        .await
+        .map_err(|e| e.user_message())
+}
+
+/// Stops the running canary and keeps the current routing. Returns the
+/// stopped canary, or `None` if none was running.
+#[tauri::command]
+pub async fn rollback_canary(state: State<'_, AppState>) -> Result<Option<Canary>, String> {
+    Ok(state
+        .server_manager
+        .read()
+        .await
+        .rollback_canary("Rolled back manually")
+        .await)
+}
+
+/// Returns the power source and whether the battery power saver is active,
+/// detecting the power source again first.
+#[tauri::command]
+pub async fn get_power_status(app_handle: AppHandle) -> Result<PowerStatus, String> {
+    Ok(power::refresh_power_saver(&app_handle).await)
+}
+
+/// Sets the power saver mode (`auto`, `on` or `off`), saves it and applies it
+/// immediately.
+#[tauri::command]
+pub async fn set_power_saver_mode(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    mode: PowerSaverMode,
+) -> Result<PowerStatus, String> {
+    state
+        .config_manager
+        .read()
+        .await
+        .set_power_saver_mode(mode)
+        .await
+        .map_err(|e| e.user_message())?;
+
+    Ok(power::refresh_power_saver(&app_handle).await)
+}
+
+/// Audits what is reachable from outside right now: which listeners are bound
+/// to which interfaces, the tunnels and what authenticates them, CORS and the
+/// client access policy. Returns scored findings with remediation steps for
+/// the dashboard.
+#[tauri::command]
+pub async fn get_security_report(state: State<'_, AppState>) -> Result<SecurityReport, String> {
+    let (server_config, tunnel_config, access_control) = {
+        let config_manager = state.config_manager.read().await;
+        (
+            config_manager.get_server_config().await,
+            config_manager.get_tunnel_config().await,
+            config_manager.get_access_control_config().await,
+        )
+    };
+
+    let mut listeners = Vec::new();
+    let api_running = {
+        let server_manager = state.server_manager.read().await;
+        let running = server_manager.is_running().await;
+        if running {
+            let (host, port) = server_manager.bind_address();
+            listeners.push(Listener {
+                name: "API server".to_string(),
+                host,
+                port,
+                tls: server_config.tls.enabled,
+            });
+            for bind in server_manager.additional_binds() {
+                listeners.push(Listener {
+                    name: "API server".to_string(),
+                    host: bind.host.clone(),
+                    port: bind.port,
+                    tls: server_config.tls.enabled,
+                });
+            }
+        }
+        running
+    };
+
+    let bifrost_url = state.bifrost_manager.read().await.get_local_url().await;
+    let dashboard_url = state.dashboard_manager.read().await.get_local_url().await;
+    for (name, url) in [("Bifrost", bifrost_url), ("Dashboard", dashboard_url)] {
+        if let Some(listener) = url.and_then(|url| Listener::from_url(name, &url)) {
+            listeners.push(listener);
+        }
+    }
+
+    let tunnels = state.tunnel_manager.read().await.tunnels().await;
+
+    Ok(build_report(&ExposureInputs::new(
+        listeners,
+        tunnels,
+        &tunnel_config,
+        access_control,
+        api_running,
+    )))
+}
+
 /// Performs authentication and starts all required services (server + tunnel).
 ///
 /// This is the main command for starting the MindLink API service. It handles the
@@ -228,9 +904,10 @@ pub async fn login_and_serve(state: State<'_, AppState>) -> Result<ServiceRespon
 
     // Check authentication first
     let is_authenticated = {
+        let since = Instant::now();
         let mut auth_manager = state.auth_manager.write().await;
-        if !auth_manager.is_authenticated().await {
-            match auth_manager.login().await {
+        if !auth_manager.is_authenticated().await || auth_manager.reauth_required().is_some() {
+            match auth_manager.login_once(since).await {
                 Ok(_) => true,
                 Err(e) => {
                     let auth_error = MindLinkError::Authentication {
@@ -272,8 +949,265 @@ pub async fn login_and_serve(state: State<'_, AppState>) -> Result<ServiceRespon
     }
 
     // Start server
+    let (
+        server_config,
+        access_control_config,
+        tool_emulation_config,
+        analytics_config,
+        language_detection_config,
+        model_aliases,
+        prompts,
+        stream_continuation_config,
+        conversation_config,
+        batch_config,
+        accounts_config,
+        failover_config,
+        job_config,
+        redaction_config,
+        backpressure_config,
+        capture_config,
+        limits_config,
+        shadow_config,
+        http_config,
+        moderation_config,
+        post_processing_config,
+        request_transform_config,
+        anthropic_config,
+        tunnel_config,
+    ) = {
+        let config_manager = state.config_manager.read().await;
+        (
+            config_manager.get_server_config().await,
+            config_manager.get_access_control_config().await,
+            config_manager.get_tool_emulation_config().await,
+            config_manager.get_analytics_config().await,
+            config_manager.get_language_detection_config().await,
+            config_manager.get_model_aliases().await,
+            config_manager.get_prompt_config().await,
+            config_manager.get_stream_continuation_config().await,
+            config_manager.get_conversation_config().await,
+            config_manager.get_batch_config().await,
+            config_manager.get_accounts_config().await,
+            config_manager.get_failover_config().await,
+            config_manager.get_job_config().await,
+            config_manager.get_redaction_config().await,
+            config_manager.get_backpressure_config().await,
+            config_manager.get_capture_config().await,
+            config_manager.get_limits_config().await,
+            config_manager.get_shadow_config().await,
+            config_manager.get_http_config().await,
+            config_manager.get_moderation_config().await,
+            config_manager.get_post_processing_config().await,
+            config_manager.get_request_transform_config().await,
+            config_manager.get_anthropic_config().await,
+            config_manager.get_tunnel_config().await,
+        )
+    };
+
+    let bifrost_url = state.bifrost_manager.read().await.get_local_url().await;
+    let authorized_apps = read_authorized_apps().await.unwrap_or_default();
+    let tunnel_token_policy = TunnelTokenPolicy {
+        required: tunnel_config.require_token,
+        instance_token: get_or_create_instance_token(state.clone()).await.ok(),
+    };
+
     let server_url = {
         let mut server_manager = state.server_manager.write().await;
+        server_manager.set_bifrost_url(bifrost_url).await;
+        server_manager.set_model_aliases(model_aliases).await;
+        server_manager.set_prompt_config(prompts).await;
+        server_manager.set_authorized_apps(authorized_apps).await;
+        server_manager
+            .set_tunnel_token_policy(tunnel_token_policy)
+            .await;
+        if let Err(e) = server_manager
+            .set_post_processing(&post_processing_config)
+            .await
+        {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager
+            .set_request_transforms(&request_transform_config)
+            .await
+        {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager
+            .configure(server_config.host.clone(), server_config.port)
+            .await
+        {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        // Serving without the configured TLS would expose plain HTTP
+        if let Err(e) = server_manager
+            .configure_tls(server_config.tls.clone())
+            .await
+        {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+            return Ok(ServiceResponse {
+                success: false,
+                message: Some(e.user_message()),
+                server_url: None,
+                tunnel_url: None,
+                auth_url: None,
+            });
+        }
+        if let Err(e) = server_manager
+            .configure_socket(server_config.socket_path.clone())
+            .await
+        {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager
+            .configure_additional_binds(server_config.additional_binds.clone())
+            .await
+        {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager
+            .configure_access_control(&access_control_config)
+            .await
+        {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager
+            .configure_tool_emulation(tool_emulation_config)
+            .await
+        {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager.configure_analytics(analytics_config).await {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager
+            .configure_language_detection(language_detection_config)
+            .await
+        {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager
+            .configure_stream_continuation(stream_continuation_config)
+            .await
+        {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager
+            .configure_conversations(conversation_config)
+            .await
+        {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager.configure_batches(batch_config).await {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager.configure_accounts(accounts_config).await {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager.configure_failover(failover_config).await {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager.configure_jobs(job_config).await {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager.configure_redaction(&redaction_config).await {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager
+            .configure_backpressure(backpressure_config)
+            .await
+        {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager.configure_capture(capture_config).await {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager.configure_limits(limits_config).await {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager
+            .configure_tunnel_limits(tunnel_config.limits.clone())
+            .await
+        {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager.configure_shadow(shadow_config).await {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager.configure_http(http_config).await {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        if let Err(e) = server_manager.configure_moderation(moderation_config).await {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+        let anthropic_auth = anthropic_config
+            .enabled
+            .then(|| state.anthropic_auth.clone());
+        if let Err(e) = server_manager.configure_anthropic(anthropic_auth).await {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Server", &e, None);
+            }
+        }
+
+        for bind in server_config.bind_addresses() {
+            if bind.is_lan_exposed() {
+                log_warn!(
+                    "Server",
+                    format!(
+                        "API server is bound to {} and reachable from other devices on the network",
+                        bind
+                    )
+                );
+            }
+        }
+
         match server_manager.start(state.auth_manager.clone()).await {
             Ok(url) => {
                 if let Some(logger) = get_logger() {
@@ -310,11 +1244,28 @@ pub async fn login_and_serve(state: State<'_, AppState>) -> Result<ServiceRespon
     };
 
     // Create tunnel (enhanced error reporting but still non-fatal)
+    let tunnel_config = {
+        let config_manager = state.config_manager.read().await;
+        config_manager.get_tunnel_config().await
+    };
+    apply_cloudflare_access(&*state.server_manager.read().await, &tunnel_config).await;
     let tunnel_url = {
         let mut tunnel_manager = state.tunnel_manager.write().await;
-        match tunnel_manager.create_tunnel().await {
-            Ok(url) => {
+        tunnel_manager.set_local_port(server_config.port).await;
+        tunnel_manager.configure(&tunnel_config).await;
+        let now = chrono::Local::now().naive_local();
+        match tunnel_manager.create_scheduled_tunnel(now).await {
+            // Opened by the scheduler once a window begins
+            Ok(None) => {
+                if let Some(status) = tunnel_manager.schedule_status(now) {
+                    log_info!("Tunnel", &status.describe(now));
+                }
+                None
+            },
+            Ok(Some(url)) => {
                 println!("✅ Cloudflare tunnel created: {}", url);
+                remember_named_tunnel(&state, &tunnel_manager).await;
+                create_service_tunnels(&mut tunnel_manager).await;
                 if let Some(logger) = get_logger() {
                     let entry = LogEntry::new(
                         LogLevel::Info,
@@ -331,8 +1282,8 @@ pub async fn login_and_serve(state: State<'_, AppState>) -> Result<ServiceRespon
                 
                 let tunnel_error = MindLinkError::Tunnel {
                     message: format!("Tunnel creation failed: {}. Service running locally only.", e),
-                    tunnel_type: Some("quick".to_string()),
-                    local_port: Some(3001),
+                    tunnel_type: Some(tunnel_config.tunnel_type.clone()),
+                    local_port: Some(server_config.port),
                     source: Some(e),
                 };
 
@@ -413,7 +1364,8 @@ pub async fn get_config(
     
     // Add authentication status
     let auth_manager = state.auth_manager.read().await;
-    let (is_authenticated, user_email) = auth_manager.get_auth_status().await;
+    let (is_authenticated, user) = auth_manager.get_auth_status().await;
+    let quota = auth_manager.quota();
     
     drop(config_manager);
     drop(auth_manager);
@@ -431,8 +1383,12 @@ pub async fn get_config(
     
     // Add authentication info
     map.insert("is_authenticated".to_string(), serde_json::Value::Bool(is_authenticated));
-    map.insert("user_email".to_string(), 
-        user_email.map(serde_json::Value::String).unwrap_or(serde_json::Value::Null));
+    map.insert(
+        "user_email".to_string(),
+        serde_json::json!(user.as_ref().and_then(|user| user.email.as_ref())),
+    );
+    map.insert("user".to_string(), serde_json::json!(user));
+    map.insert("quota".to_string(), serde_json::json!(quota));
     
     Ok(map)
 }
@@ -448,10 +1404,87 @@ pub async fn save_config(
     let config_schema: ConfigSchema =
         serde_json::from_value(config_json).map_err(|e| format!("Invalid config format: {}", e))?;
 
+    let pinned_cloudflared = config_schema
+        .tunnel
+        .cloudflared_updates
+        .pinned_version
+        .clone();
     config_manager
         .update_config(config_schema)
         .await
-        .map_err(|e| format!("Failed to save config: {}", e))
+        .map_err(|e| format!("Failed to save config: {}", e))?;
+
+    state
+        .binary_manager
+        .write()
+        .await
+        .pin_cloudflared(pinned_cloudflared);
+    Ok(())
+}
+
+/// Bind address of the local API server as shown in the settings UI
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServerBindResponse {
+    pub host: String,
+    pub port: u16,
+    pub local_url: String,
+    pub lan_exposed: bool,
+    pub restart_required: bool,
+}
+
+/// Get the configured bind address of the local API server
+#[tauri::command]
+pub async fn get_server_bind_address(
+    state: State<'_, AppState>,
+) -> Result<ServerBindResponse, String> {
+    let server_config = {
+        let config_manager = state.config_manager.read().await;
+        config_manager.get_server_config().await
+    };
+
+    let restart_required = {
+        let server_manager = state.server_manager.read().await;
+        server_manager.is_running().await
+            && (server_manager.bind_address() != (server_config.host.clone(), server_config.port)
+                || server_manager.additional_binds() != server_config.additional_binds)
+    };
+
+    Ok(ServerBindResponse {
+        local_url: server_config.local_url(),
+        lan_exposed: server_config.is_lan_exposed(),
+        host: server_config.host,
+        port: server_config.port,
+        restart_required,
+    })
+}
+
+/// Set the bind address of the local API server, e.g. `0.0.0.0` to serve
+/// other devices on the LAN. Takes effect the next time serving starts.
+#[tauri::command]
+pub async fn set_server_bind_address(
+    state: State<'_, AppState>,
+    host: String,
+    port: u16,
+) -> Result<ServerBindResponse, String> {
+    if let Some(logger) = get_logger() {
+        logger.log_user_action(
+            "set_server_bind_address",
+            Some(&serde_json::json!({ "host": host, "port": port })),
+        );
+    }
+
+    {
+        let config_manager = state.config_manager.write().await;
+        let mut config = config_manager.get_config().await;
+        config.server.host = host;
+        config.server.port = port;
+        config_manager
+            .update_config(config)
+            .await
+            .map_err(|e| e.user_message())?;
+    }
+
+    get_server_bind_address(state).await
 }
 
 #[tauri::command]
@@ -625,6 +1658,12 @@ pub async fn start_bifrost(state: State<'_, AppState>) -> Result<ServiceResponse
         Ok(()) => {
             let url = bifrost_manager.get_local_url().await;
             println!("✅ Bifrost LLM Router started successfully: {:?}", url);
+            state
+                .server_manager
+                .read()
+                .await
+                .set_bifrost_url(url.clone())
+                .await;
             Ok(ServiceResponse {
                 success: true,
                 message: Some("Bifrost LLM Router started successfully".to_string()),
@@ -661,13 +1700,21 @@ pub async fn stop_bifrost(state: State<'_, AppState>) -> Result<ServiceResponse,
     }
 
     match bifrost_manager.stop().await {
-        Ok(()) => Ok(ServiceResponse {
-            success: true,
-            message: Some("Bifrost LLM Router stopped successfully".to_string()),
-            server_url: None,
-            tunnel_url: None,
-            auth_url: None,
-        }),
+        Ok(()) => {
+            state
+                .server_manager
+                .read()
+                .await
+                .set_bifrost_url(None)
+                .await;
+            Ok(ServiceResponse {
+                success: true,
+                message: Some("Bifrost LLM Router stopped successfully".to_string()),
+                server_url: None,
+                tunnel_url: None,
+                auth_url: None,
+            })
+        },
         Err(e) => Ok(ServiceResponse {
             success: false,
             message: Some(format!("Failed to stop Bifrost: {}", e)),
@@ -778,11 +1825,23 @@ pub async fn create_tunnel(state: State<'_, AppState>) -> Result<ServiceResponse
         logger.log_user_action("create_tunnel", None);
     }
 
+    let (local_port, tunnel_config) = {
+        let config_manager = state.config_manager.read().await;
+        (
+            config_manager.get_server_config().await.port,
+            config_manager.get_tunnel_config().await,
+        )
+    };
+
     let mut tunnel_manager = state.tunnel_manager.write().await;
+    tunnel_manager.set_local_port(local_port).await;
+    tunnel_manager.configure(&tunnel_config).await;
     
     match tunnel_manager.create_tunnel().await {
         Ok(url) => {
             println!("✅ Tunnel created successfully: {}", url);
+            remember_named_tunnel(&state, &tunnel_manager).await;
+            create_service_tunnels(&mut tunnel_manager).await;
             
             if let Some(logger) = get_logger() {
                 let entry = LogEntry::new(
@@ -807,8 +1866,8 @@ pub async fn create_tunnel(state: State<'_, AppState>) -> Result<ServiceResponse
             
             let tunnel_error = MindLinkError::Tunnel {
                 message: "Manual tunnel creation failed".to_string(),
-                tunnel_type: Some("quick".to_string()),
-                local_port: Some(3001),
+                tunnel_type: Some(tunnel_config.tunnel_type.clone()),
+                local_port: Some(local_port),
                 source: Some(e),
             };
 
@@ -878,20 +1937,6 @@ pub async fn close_tunnel(state: State<'_, AppState>) -> Result<ServiceResponse,
 /// Get current tunnel status and URL
 #[tauri::command]
 pub async fn get_tunnel_status(state: State<'_, AppState>) -> Result<ServiceResponse, String> {
-    // First check for actual running tunnel
-    let actual_tunnel_url = detect_actual_tunnel_url().await;
-    
-    if let Some(url) = actual_tunnel_url {
-        return Ok(ServiceResponse {
-            success: true,
-            message: Some("Tunnel is active".to_string()),
-            server_url: None,
-            tunnel_url: Some(url),
-            auth_url: None,
-        });
-    }
-    
-    // Fallback to managed tunnel state
     let tunnel_manager = state.tunnel_manager.read().await;
     let is_connected = tunnel_manager.is_connected().await;
     let tunnel_url = tunnel_manager.get_current_url().await;
@@ -946,11 +1991,52 @@ pub async fn install_cloudflared_binary(
     }
 }
 
+/// Managed binaries that changed, disappeared or are shadowed by another copy
+/// since MindLink installed them
+#[tauri::command]
+pub async fn get_binary_drift(state: State<'_, AppState>) -> Result<Vec<DriftFinding>, String> {
+    Ok(state.binary_manager.read().await.check_drift().await)
+}
+
+/// Bring the downloaded cloudflared to the pinned or latest release. A running
+/// tunnel keeps the cloudflared it started with until it restarts.
+#[tauri::command]
+pub async fn update_cloudflared(state: State<'_, AppState>) -> Result<CloudflaredUpdate, String> {
+    let pinned = {
+        let config_manager = state.config_manager.read().await;
+        config_manager
+            .get_tunnel_config()
+            .await
+            .cloudflared_updates
+            .pinned_version
+    };
+    state.binary_manager.write().await.pin_cloudflared(pinned);
+
+    let update = state
+        .binary_manager
+        .read()
+        .await
+        .update_cloudflared()
+        .await
+        .map_err(|e| format!("Failed to update cloudflared: {}", e))?;
+    if update.updated {
+        log_info!(
+            "BinaryManager",
+            &format!("cloudflared {} installed", update.installed)
+        );
+    }
+    Ok(update)
+}
+
 #[tauri::command]
 pub async fn logout(state: State<'_, AppState>) -> Result<ServiceResponse, String> {
-    let mut auth_manager = state.auth_manager.write().await;
+    let result = state.auth_manager.write().await.logout().await;
 
-    match auth_manager.logout().await {
+    // What was known about the session no longer applies
+    *state.auth_probe.write().await = None;
+    *state.session_warning.write().await = None;
+
+    match result {
         Ok(()) => Ok(ServiceResponse {
             success: true,
             message: Some("Logged out successfully".to_string()),
@@ -968,12 +2054,318 @@ pub async fn logout(state: State<'_, AppState>) -> Result<ServiceResponse, Strin
     }
 }
 
+/// Signs in an additional ChatGPT account under the name `id` and adds it to
+/// the pool requests are spread across. Opens the browser like the regular
+/// login; the account takes requests as soon as it is signed in.
+#[tauri::command]
+pub async fn add_chatgpt_account(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    let path = AccountPool::account_path(&id).map_err(|e| e.user_message())?;
+    let auth_config = state.auth_manager.read().await.config().clone();
+    let mut auth = AuthManager::open(path)
+        .await
+        .map_err(|e| e.user_message())?
+        .with_config(auth_config);
+
+    if !auth.is_authenticated().await {
+        auth.login().await.map_err(|e| {
+            let error = MindLinkError::Authentication {
+                message: format!("Login for account '{}' failed", id),
+                source: Some(e),
+            };
+            log_error!("Auth", error.clone());
+            error.user_message()
+        })?;
+    }
+
+    state
+        .server_manager
+        .read()
+        .await
+        .add_account(&id, Arc::new(RwLock::new(auth)))
+        .await;
+    log_info!("Auth", &format!("Added ChatGPT account '{}'", id));
+    Ok(())
+}
+
+/// Signs out an additional ChatGPT account and removes it from the pool.
+/// The primary account is signed out with `logout`.
+#[tauri::command]
+pub async fn remove_chatgpt_account(state: State<'_, AppState>, id: String) -> Result<(), String> {
+    let auth = state
+        .server_manager
+        .read()
+        .await
+        .remove_account(&id)
+        .await
+        .ok_or_else(|| format!("No additional account named '{}'", id))?;
+
+    let result = auth.write().await.logout().await;
+    result.map_err(|e| format!("Failed to sign out '{}': {}", id, e))
+}
+
+/// Returns every account requests are spread across, with its requests in
+/// flight, requests in the last minute, error and rate limit counts, and the
+/// remaining cooldown of an account that was rate limited.
+#[tauri::command]
+pub async fn get_account_status(state: State<'_, AppState>) -> Result<Vec<AccountStatus>, String> {
+    Ok(state.server_manager.read().await.account_status().await)
+}
+
+/// Lists the ChatGPT accounts signed in through the regular login, the
+/// active one first. Unlike the accounts of `add_chatgpt_account`, only the
+/// active one takes requests.
+#[tauri::command]
+pub async fn list_accounts(state: State<'_, AppState>) -> Result<Vec<SavedAccount>, String> {
+    let auth_manager = state.auth_manager.read().await;
+    auth_manager
+        .accounts()
+        .await
+        .map_err(|e| format!("Failed to list accounts: {}", e))
+}
+
+/// Signs in another ChatGPT account through the browser and makes it the
+/// active one. The account active before is kept for `select_account`.
+/// Returns the ID of the new account.
+#[tauri::command]
+pub async fn add_account(state: State<'_, AppState>) -> Result<String, String> {
+    let mut auth_manager = state.auth_manager.write().await;
+    auth_manager.add_account().await.map_err(|e| {
+        let error = MindLinkError::Authentication {
+            message: "Adding an account failed".to_string(),
+            source: Some(e),
+        };
+        log_error!("Auth", error.clone());
+        error.user_message()
+    })
+}
+
+/// Makes a signed-in account the active one; requests use it from then on
+#[tauri::command]
+pub async fn select_account(state: State<'_, AppState>, account_id: String) -> Result<(), String> {
+    let mut auth_manager = state.auth_manager.write().await;
+    auth_manager
+        .select_account(&account_id)
+        .await
+        .map_err(|e| format!("Failed to switch to account '{}': {}", account_id, e))
+}
+
+/// Signs out of an account. Removing the active account leaves none active
+/// until another one is selected or signed in.
+#[tauri::command]
+pub async fn remove_account(state: State<'_, AppState>, account_id: String) -> Result<(), String> {
+    let mut auth_manager = state.auth_manager.write().await;
+    auth_manager
+        .remove_account(&account_id)
+        .await
+        .map_err(|e| format!("Failed to remove account '{}': {}", account_id, e))
+}
+
+/// Signs in with the ChatGPT login of the Codex CLI, from `auth.json` in
+/// `$CODEX_HOME` or `~/.codex`. The account active before is kept for
+/// `select_account`.
+#[tauri::command]
+pub async fn import_codex_login(state: State<'_, AppState>) -> Result<(), String> {
+    let path = codex_auth_path()
+        .filter(|path| path.exists())
+        .ok_or_else(|| "No Codex CLI login found".to_string())?;
+    let mut auth_manager = state.auth_manager.write().await;
+    auth_manager.import_codex_login(&path).await.map_err(|e| {
+        let error = MindLinkError::Authentication {
+            message: "Importing the Codex CLI login failed".to_string(),
+            source: Some(e),
+        };
+        log_error!("Auth", error.clone());
+        error.user_message()
+    })
+}
+
+/// Returns up to `limit` (default 50) connection quality reports posted by
+/// companion apps to `/v1/ping/report`, newest first.
+#[tauri::command]
+pub async fn get_connection_reports(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+) -> Result<Vec<ConnectionReport>, String> {
+    let limit = limit.unwrap_or(50);
+    Ok(state.server_manager.read().await.connection_reports(limit))
+}
+
+/// Returns up to `limit` (default 50) captured completion exchanges, newest
+/// first. Captures are only recorded while `capture.enabled` is set.
+#[tauri::command]
+pub async fn get_captures(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+) -> Result<Vec<CaptureSummary>, String> {
+    let limit = limit.unwrap_or(50);
+    state
+        .server_manager
+        .read()
+        .await
+        .captures(limit)
+        .await
+        .map_err(|e| e.user_message())
+}
+
+/// Returns a captured exchange with its sanitized headers and bodies
+#[tauri::command]
+pub async fn get_capture(state: State<'_, AppState>, id: String) -> Result<Capture, String> {
+    state
+        .server_manager
+        .read()
+        .await
+        .capture(&id)
+        .await
+        .map_err(|e| e.user_message())?
+        .ok_or_else(|| format!("There is no capture {}", id))
+}
+
+/// Sends a captured request to the running API server again and returns the
+/// new response, to compare it with the captured one
+#[tauri::command]
+pub async fn replay_capture(
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<ReplayResult, String> {
+    state
+        .server_manager
+        .read()
+        .await
+        .replay_capture(&id)
+        .await
+        .map_err(|e| e.user_message())
+}
+
+/// Returns how the answers of the shadow backend compared with the primary's
+/// so far. Requests are only mirrored while `shadow.enabled` is set.
+#[tauri::command]
+pub async fn get_shadow_report(state: State<'_, AppState>) -> Result<ShadowReport, String> {
+    Ok(state.server_manager.read().await.shadow_report())
+}
+
+/// Returns up to `limit` (default 50) of the latest API requests, newest
+/// first, with the time spent validating, authenticating, waiting for the
+/// upstream's first byte and streaming, for the request inspector
+#[tauri::command]
+pub async fn get_recent_requests(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+) -> Result<Vec<RequestSummary>, String> {
+    let limit = limit.unwrap_or(50);
+    Ok(state.server_manager.read().await.recent_requests(limit))
+}
+
+/// Streaming statistics of the last minutes next to those of every request
+/// the request inspector keeps, so a slowing backend stands out
+#[derive(Debug, Serialize)]
+pub struct StreamStatsReport {
+    pub recent_minutes: i64,
+    pub recent: StreamStats,
+    pub overall: StreamStats,
+}
+
+/// Get the time to first token and generation speed of recent streams
+#[tauri::command]
+pub async fn get_stream_stats(state: State<'_, AppState>) -> Result<StreamStatsReport, String> {
+    const RECENT_MINUTES: i64 = 10;
+    let since = chrono::Utc::now() - chrono::Duration::minutes(RECENT_MINUTES);
+    let server_manager = state.server_manager.read().await;
+    Ok(StreamStatsReport {
+        recent_minutes: RECENT_MINUTES,
+        recent: server_manager.stream_stats(Some(since)),
+        overall: server_manager.stream_stats(None),
+    })
+}
+
+/// Get the requests, bytes and probed round trip of each tunnel traffic went
+/// through since the app started
+#[tauri::command]
+pub async fn get_tunnel_traffic(state: State<'_, AppState>) -> Result<Vec<TunnelTraffic>, String> {
+    Ok(state.server_manager.read().await.tunnel_traffic())
+}
+
+/// Get the API's tunnel and those of the other services, with their URLs
+#[tauri::command]
+pub async fn get_tunnels(state: State<'_, AppState>) -> Result<Vec<ServiceTunnel>, String> {
+    Ok(state.tunnel_manager.read().await.tunnels().await)
+}
+
 /// Get the persistent instance token for this MindLink installation
 #[tauri::command]
 pub async fn get_instance_token(state: State<'_, AppState>) -> Result<String, String> {
     get_or_create_instance_token(state).await
 }
 
+/// Get the token for clients paired through the tunnel, issuing one if needed
+#[tauri::command]
+pub async fn get_tunnel_token(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<TunnelTokenResponse, String> {
+    current_tunnel_token(&app_handle, &state, false).await
+}
+
+/// Issue a new tunnel token and revoke the current one at once, e.g. after
+/// the pairing code was shared too widely
+#[tauri::command]
+pub async fn rotate_tunnel_token(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<TunnelTokenResponse, String> {
+    if let Some(logger) = get_logger() {
+        logger.log_user_action("rotate_tunnel_token", None);
+    }
+    current_tunnel_token(&app_handle, &state, true).await
+}
+
+/// The tunnel token clients should use, rotated first when `revoke` or when
+/// it is due. New tokens are saved and announced to the frontend.
+pub(crate) async fn current_tunnel_token(
+    app_handle: &AppHandle,
+    state: &AppState,
+    revoke: bool,
+) -> Result<TunnelTokenResponse, String> {
+    let lifetime_hours = state
+        .config_manager
+        .read()
+        .await
+        .get_tunnel_config()
+        .await
+        .access_token_lifetime_hours;
+    let now = chrono::Utc::now();
+
+    let mut tokens = state.tunnel_tokens.write().await;
+    if revoke || tokens.needs_rotation(now) {
+        let token = tokens.rotate(chrono::Duration::hours(lifetime_hours as i64), revoke, now);
+        let path = tunnel_tokens::tunnel_tokens_path()
+            .ok_or_else(|| "Cannot determine home directory".to_string())?;
+        tokens
+            .save(&path)
+            .await
+            .map_err(|e| format!("Failed to save the tunnel token: {}", e))?;
+        log_info!(
+            "TunnelTokens",
+            &format!("Issued a tunnel token valid until {}", token.expires_at)
+        );
+        events::emit(
+            app_handle,
+            AppEvent::TunnelTokenRotated(TunnelTokenRotated {
+                expires_at: token.expires_at,
+                manual: revoke,
+            }),
+        );
+    }
+
+    let token = tokens
+        .current(now)
+        .cloned()
+        .ok_or_else(|| "No tunnel token was issued".to_string())?;
+    Ok(TunnelTokenResponse {
+        token,
+        previous_expires_at: tokens.previous(now).map(|previous| previous.expires_at),
+    })
+}
+
 /// Cloudflare tunnel authentication - initiates cloudflared login flow
 #[tauri::command]
 pub async fn oauth_login(state: State<'_, AppState>) -> Result<ServiceResponse, String> {
@@ -1150,11 +2542,14 @@ pub async fn oauth_logout(state: State<'_, AppState>) -> Result<ServiceResponse,
     }
 }
 
-/// Enable tunnel with automatic name generation and permanent setup
+/// Enable the Cloudflare named tunnel `tunnel_name`, serving MindLink at
+/// `hostname` of the user's domain. The tunnel is created and the hostname
+/// routed to it on first use; the config keeps both for later starts.
 #[tauri::command]
 pub async fn start_tunnel(
     state: State<'_, AppState>,
     tunnel_name: String,
+    hostname: String,
 ) -> Result<ServiceResponse, String> {
     println!("🚇 Enabling permanent tunnel: {}", tunnel_name);
     
@@ -1163,17 +2558,45 @@ pub async fn start_tunnel(
     // Save tunnel name to config for persistence
     {
         let config_manager = state.config_manager.write().await;
-        let mut current_config = config_manager.get_config().await;
-        current_config.tunnel.tunnel_type = tunnel_name.clone();
-        current_config.tunnel.enabled = true;
-        
-        if let Err(e) = config_manager.update_config(current_config).await {
-            eprintln!("Warning: Failed to save tunnel config: {}", e);
+        let mut tunnel = config_manager.get_tunnel_config().await;
+        if tunnel.named.name != tunnel_name {
+            tunnel.named.tunnel_id = None;
         }
+        if tunnel.named.hostname != hostname && tunnel.cloudflare_access.app_id.is_some() {
+            // The Access application protects the old hostname
+            log_warn!(
+                "Tunnel",
+                &format!(
+                    "Cloudflare Access is off until {} is protected again",
+                    hostname
+                )
+            );
+            let access = &mut tunnel.cloudflare_access;
+            *access = CloudflareAccessConfig {
+                account_id: access.account_id.clone(),
+                api_token: access.api_token.take(),
+                team_domain: access.team_domain.take(),
+                ..CloudflareAccessConfig::default()
+            };
+        }
+        tunnel.tunnel_type = "named".to_string();
+        tunnel.named.name = tunnel_name.clone();
+        tunnel.named.hostname = hostname.clone();
+        tunnel.enabled = true;
+        
+        config_manager
+            .set_tunnel_config(tunnel.clone())
+            .await
+            .map_err(|e| format!("Invalid tunnel settings: {}", e))?;
+        apply_cloudflare_access(&*state.server_manager.read().await, &tunnel).await;
     }
     
-    match tunnel_manager.create_permanent_tunnel(&tunnel_name).await {
+    match tunnel_manager
+        .create_permanent_tunnel(&tunnel_name, &hostname)
+        .await
+    {
         Ok(tunnel_url) => {
+            remember_named_tunnel(&state, &tunnel_manager).await;
             if let Some(logger) = get_logger() {
                 logger.log(LogEntry::new(
                     LogLevel::Info,
@@ -1204,6 +2627,116 @@ pub async fn start_tunnel(
     }
 }
 
+/// Have the server check the Access JWTs of requests through the named
+/// tunnel when it is protected, and stop checking them otherwise
+async fn apply_cloudflare_access(server_manager: &ServerManager, tunnel: &TunnelConfig) {
+    let verifier = if tunnel.tunnel_type == "named" {
+        AccessVerifier::from_config(&tunnel.named.hostname, &tunnel.cloudflare_access)
+    } else {
+        Ok(None)
+    };
+    match verifier {
+        Ok(verifier) => server_manager.set_cloudflare_access(verifier).await,
+        Err(e) => {
+            if let Some(logger) = get_logger() {
+                logger.log_error("Tunnel", &e, None);
+            }
+        },
+    }
+}
+
+/// Put the hostname of the named tunnel behind Cloudflare Access: create an
+/// Access application, a service token and a policy letting only that token
+/// in, with an API token of the account. Clients then need the returned
+/// service token to reach MindLink through the tunnel.
+#[tauri::command]
+pub async fn protect_tunnel_with_access(
+    state: State<'_, AppState>,
+    account_id: String,
+    api_token: String,
+) -> Result<CloudflareAccessResponse, String> {
+    let config_manager = state.config_manager.read().await;
+    let mut tunnel = config_manager.get_tunnel_config().await;
+    if tunnel.tunnel_type != "named" || tunnel.named.hostname.is_empty() {
+        return Err(
+            "Set up a named tunnel before protecting it with Cloudflare Access".to_string(),
+        );
+    }
+
+    let access = &mut tunnel.cloudflare_access;
+    if access.account_id != account_id {
+        *access = CloudflareAccessConfig {
+            account_id,
+            ..CloudflareAccessConfig::default()
+        };
+    }
+    access.api_token = Some(api_token);
+
+    let client = crate::proxy::apply(reqwest::Client::builder())
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let provisioned = cloudflare_access::provision(
+        &client,
+        cloudflare_access::API_BASE,
+        &tunnel.named.hostname,
+        access,
+    )
+    .await;
+    access.enabled = provisioned.is_ok();
+    let service_token = access.client_id.clone().zip(access.client_secret.clone());
+
+    // Kept even on failure, so trying again resumes where this stopped
+    config_manager
+        .set_tunnel_config(tunnel.clone())
+        .await
+        .map_err(|e| e.user_message())?;
+    drop(config_manager);
+    provisioned.map_err(|e| e.user_message())?;
+
+    apply_cloudflare_access(&*state.server_manager.read().await, &tunnel).await;
+    log_info!(
+        "Tunnel",
+        &format!(
+            "{} is now protected by Cloudflare Access",
+            tunnel.named.hostname
+        )
+    );
+    let (client_id, client_secret) = service_token.unwrap_or_default();
+    Ok(CloudflareAccessResponse {
+        client_id,
+        client_secret,
+    })
+}
+
+/// Start the tunnels of the other configured services next to the API's.
+/// Those failing to start are logged and leave the API's tunnel running.
+async fn create_service_tunnels(tunnel_manager: &mut TunnelManager) {
+    for (service, e) in tunnel_manager.create_service_tunnels().await {
+        log_warn!(
+            "Tunnel",
+            &format!("Failed to start the {} tunnel: {}", service, e)
+        );
+    }
+}
+
+/// Save the ID of the named tunnel once it was created, so the next start
+/// reuses the tunnel instead of creating another
+async fn remember_named_tunnel(state: &AppState, tunnel_manager: &TunnelManager) {
+    let Some(tunnel_id) = tunnel_manager.named_tunnel_id() else {
+        return;
+    };
+
+    let config_manager = state.config_manager.read().await;
+    let mut tunnel = config_manager.get_tunnel_config().await;
+    if tunnel.tunnel_type != "named" || tunnel.named.tunnel_id.as_deref() == Some(tunnel_id) {
+        return;
+    }
+    tunnel.named.tunnel_id = Some(tunnel_id.to_string());
+    if let Err(e) = config_manager.set_tunnel_config(tunnel).await {
+        log_warn!("Tunnel", &format!("Failed to save the tunnel ID: {}", e));
+    }
+}
+
 /// Disable tunnel
 #[tauri::command]
 pub async fn stop_tunnel(state: State<'_, AppState>) -> Result<ServiceResponse, String> {
@@ -1268,58 +2801,138 @@ pub async fn regenerate_token(state: State<'_, AppState>) -> Result<String, Stri
     }
 }
 
-/// Get QR data containing tunnel URL and instance token as JSON
+/// Get QR data containing tunnel URL and instance token as JSON, and its QR
+/// code rendered as PNG and SVG
 #[tauri::command]
-pub async fn get_qr_data(state: State<'_, AppState>) -> Result<QrDataResponse, String> {
-    // Get instance token
-    let token = match get_or_create_instance_token(state.clone()).await {
-        Ok(t) => t,
-        Err(e) => {
-            return Ok(QrDataResponse {
-                success: false,
-                qr_data: None,
-                error: Some(format!("Failed to get token: {}", e)),
-            });
-        }
+pub async fn get_qr_data(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<QrDataResponse, String> {
+    let failed = |error: String| QrDataResponse {
+        success: false,
+        qr_data: None,
+        qr_png: None,
+        qr_svg: None,
+        error: Some(error),
     };
 
-    // Get tunnel URL
-    let tunnel_url = {
-        // First try to detect actual tunnel
-        if let Some(url) = detect_actual_tunnel_url().await {
-            Some(url)
-        } else {
-            // Fallback to managed tunnel state
-            let tunnel_manager = state.tunnel_manager.read().await;
-            tunnel_manager.get_current_url().await
-        }
+    let qr_data = match pairing_data(&app_handle, &state).await {
+        Ok(data) => data,
+        Err(e) => return Ok(failed(e)),
+    };
+    let (png, svg) = match (pairing_qr::png(&qr_data), pairing_qr::svg(&qr_data)) {
+        (Ok(png), Ok(svg)) => (png, svg),
+        (Err(e), _) | (_, Err(e)) => return Ok(failed(e.user_message())),
     };
 
-    // Create QR data
-    let qr_data = if let Some(url) = tunnel_url {
-        let data = serde_json::json!({
+    Ok(QrDataResponse {
+        success: true,
+        qr_png: Some(STANDARD.encode(png)),
+        qr_svg: Some(STANDARD.encode(svg)),
+        qr_data: Some(qr_data),
+        error: None,
+    })
+}
+
+/// Save the pairing QR code to `path` for printing or sharing, as PNG or SVG
+/// depending on its extension
+#[tauri::command]
+pub async fn save_qr_code(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<(), String> {
+    let data = pairing_data(&app_handle, &state).await?;
+    pairing_qr::save(Path::new(&path), &data)
+        .await
+        .map_err(|e| e.user_message())?;
+    log_info!("Pairing", &format!("Saved the pairing QR code to {}", path));
+    Ok(())
+}
+
+/// What paired clients scan: the tunnel URL, the instance and tunnel tokens,
+/// and the Cloudflare Access headers when the tunnel is protected, as JSON
+async fn pairing_data(
+    app_handle: &AppHandle,
+    state: &State<'_, AppState>,
+) -> Result<String, String> {
+    let token = get_or_create_instance_token(state.clone())
+        .await
+        .map_err(|e| format!("Failed to get token: {}", e))?;
+
+    // Paired clients authenticate with the tunnel token, which rotates
+    let access = current_tunnel_token(app_handle, state, false).await?.token;
+
+    let tunnel_url = state.tunnel_manager.read().await.get_current_url().await;
+
+    let data = if let Some(url) = tunnel_url {
+        let mut data = serde_json::json!({
             "url": url,
-            "token": token
+            "token": token,
+            "access_token": access.token,
+            "access_token_expires_at": access.expires_at,
         });
-        Some(data.to_string())
+        // Without the service token, Cloudflare Access turns the client away
+        let tunnel = state.config_manager.read().await.get_tunnel_config().await;
+        let protection = &tunnel.cloudflare_access;
+        if protection.enabled {
+            data["headers"] = serde_json::json!({
+                cloudflare_access::CLIENT_ID_HEADER: protection.client_id,
+                cloudflare_access::CLIENT_SECRET_HEADER: protection.client_secret,
+            });
+        }
+        data
     } else {
         // If no tunnel, return token-only data
-        let data = serde_json::json!({
+        serde_json::json!({
             "token": token,
+            "access_token": access.token,
+            "access_token_expires_at": access.expires_at,
             "status": "No tunnel active"
-        });
-        Some(data.to_string())
+        })
     };
-
-    Ok(QrDataResponse {
-        success: true,
-        qr_data,
-        error: None,
-    })
+    Ok(data.to_string())
 }
 
 // ===== Helper functions for detecting actual running services =====
 
+/// Whether the ChatGPT session is valid: not expired, and not refused by the
+/// backend as far as it answers. Asks the backend at most every
+/// `auth_probe::STATUS_MAX_AGE` per token, unless the auth probe is off.
+async fn session_is_valid(state: &AppState) -> bool {
+    // Copy the token so no auth lock is held during the request
+    let access_token = {
+        let auth_manager = state.auth_manager.read().await;
+        if !auth_manager.is_authenticated().await || auth_manager.reauth_required().is_some() {
+            return false;
+        }
+        auth_manager.get_access_token().map(str::to_string)
+    };
+    let Some(access_token) = access_token else {
+        return false;
+    };
+
+    let probe_interval = state
+        .config_manager
+        .read()
+        .await
+        .get_monitoring_config()
+        .await
+        .auth_probe_interval_secs;
+    if probe_interval == 0 {
+        return true;
+    }
+    let outcome = auth_probe::cached_probe(
+        &state.auth_probe,
+        &auth_probe::probe_client(),
+        auth_probe::AUTH_PROBE_URL,
+        &access_token,
+        auth_probe::STATUS_MAX_AGE,
+    )
+    .await;
+    !matches!(outcome, ProbeOutcome::Rejected { .. })
+}
+
 /// Get or create the persistent instance token
 async fn get_or_create_instance_token(state: State<'_, AppState>) -> Result<String, String> {
     let config_manager = state.config_manager.read().await;
@@ -1358,104 +2971,19 @@ async fn get_or_create_instance_token(state: State<'_, AppState>) -> Result<Stri
     }
 }
 
-/// Check if server is actually running on port 3001
-async fn check_actual_server_running() -> Option<bool> {
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(2))
-        .build()
-        .ok()?;
-
-    match client.get("http://127.0.0.1:3001/health").send().await {
-        Ok(response) => Some(response.status().is_success()),
-        Err(_) => Some(false),
-    }
-}
-
-/// Detect actual tunnel URL by checking running cloudflare processes
-async fn detect_actual_tunnel_url() -> Option<String> {
-    use std::process::Command;
-    
-    // First try to get tunnel URL from cloudflare process
-    if let Ok(output) = Command::new("ps")
-        .args(&["aux"])
-        .output()
-    {
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        for line in output_str.lines() {
-            if line.contains("cloudflared") && line.contains("tunnel") {
-                // Found cloudflare process, now try to extract URL from logs or check the tunnel
-                if let Some(url) = check_tunnel_connectivity().await {
-                    return Some(url);
-                }
-            }
-        }
-    }
-    
-    // If we can't detect from process, try common cloudflare domain patterns
-    check_tunnel_connectivity().await
-}
-
-/// Check tunnel connectivity and return URL if active
-async fn check_tunnel_connectivity() -> Option<String> {
-    use std::process::Command;
-    
-    // Try to get the tunnel URL from systemctl or process command line
-    if let Ok(output) = Command::new("ps")
-        .args(&["aux"])
-        .output()
-    {
-        let output_str = String::from_utf8_lossy(&output.stdout);
-        for line in output_str.lines() {
-            if line.contains("cloudflared") && line.contains("tunnel") && line.contains("http://localhost:") {
-                // Try to identify which port it's tunneling
-                if line.contains("localhost:3001") {
-                    // This is the main server tunnel, let's try to find the URL
-                    if let Some(url) = try_detect_tunnel_from_logs().await {
-                        return Some(url);
-                    }
-                }
-            }
-        }
-    }
-    
-    // Fallback: check known tunnel URL if it still works
-    let potential_urls = vec![
-        "https://raised-hub-cat-barcelona.trycloudflare.com",
-    ];
-    
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(3))
-        .build()
-        .ok()?;
-    
-    for url in potential_urls {
-        if let Ok(response) = client.get(&format!("{}/health", url)).send().await {
-            if response.status().is_success() {
-                return Some(url.to_string());
-            }
-        }
-    }
-    
-    None
-}
-
-/// Try to detect tunnel URL from cloudflare logs or other sources
-async fn try_detect_tunnel_from_logs() -> Option<String> {
-    // Try to check if the known tunnel URL is still working
+/// Check if server is actually running on the configured address
+async fn check_actual_server_running(server_config: &ServerConfig) -> Option<bool> {
     let client = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(2))
+        .danger_accept_invalid_certs(server_config.tls.enabled)
         .build()
         .ok()?;
-    
-    // Check the known working tunnel URL
-    let known_url = "https://raised-hub-cat-barcelona.trycloudflare.com";
-    if let Ok(response) = client.get(&format!("{}/health", known_url)).send().await {
-        if response.status().is_success() {
-            return Some(known_url.to_string());
-        }
+
+    let health_url = format!("{}/health", server_config.local_url());
+    match client.get(&health_url).send().await {
+        Ok(response) => Some(response.status().is_success()),
+        Err(_) => Some(false),
     }
-    
-    None
 }
 
 /// Detect actual Bifrost URL by checking running services
@@ -1499,18 +3027,112 @@ async fn detect_actual_bifrost_url() -> Option<String> {
 
 // Settings Management Commands
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct AuthorizedApp {
-    pub id: String,
-    pub name: String,
-    pub model: String,
-    pub created_at: String,
-}
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Settings {
     pub default_model: Option<String>,
+    #[serde(default)]
     pub authorized_apps: Vec<AuthorizedApp>,
+    /// Keys set through `update_setting`, kept as they are
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Settings {
+    fn new() -> Self {
+        Self {
+            default_model: Some("gpt-4".to_string()),
+            authorized_apps: Vec::new(),
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    /// Copy of the settings as written to disk, with the apps' keys encrypted
+    async fn with_sealed_keys(&self) -> Settings {
+        let mut apps = self.authorized_apps.clone();
+        for app in apps.iter_mut().filter(|app| !app.api_key.is_empty()) {
+            app.api_key = secret_box::seal(&app.api_key).await;
+        }
+        Settings {
+            default_model: self.default_model.clone(),
+            authorized_apps: apps,
+            extra: self.extra.clone(),
+        }
+    }
+
+    /// Decrypt the apps' keys of settings read from disk
+    async fn open_keys(mut self) -> Result<Self, String> {
+        for app in &mut self.authorized_apps {
+            app.api_key = secret_box::open(&app.api_key).await.map_err(|e| {
+                format!("Failed to decrypt the API key of app '{}': {}", app.name, e)
+            })?;
+        }
+        Ok(self)
+    }
+}
+
+pub(crate) fn settings_path() -> Result<PathBuf, String> {
+    Ok(dirs::home_dir()
+        .ok_or_else(|| "Cannot determine home directory".to_string())?
+        .join(".mindlink")
+        .join("settings.json"))
+}
+
+/// The settings as stored, with the apps' keys still encrypted
+async fn read_stored_settings(settings_path: &Path) -> Result<Option<Settings>, String> {
+    let content = match fs::read_to_string(settings_path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read settings file: {}", e)),
+    };
+    serde_json::from_str::<Settings>(&content)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse settings: {}", e))
+}
+
+/// The saved settings, or `None` when there are none
+pub(crate) async fn read_settings(settings_path: &Path) -> Result<Option<Settings>, String> {
+    match read_stored_settings(settings_path).await? {
+        Some(stored) => stored.open_keys().await.map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Encrypt the keys of apps saved in plaintext by earlier versions, once at
+/// startup. Returns whether the settings were rewritten.
+pub(crate) async fn encrypt_app_keys(settings_path: &Path) -> Result<bool, String> {
+    let Some(stored) = read_stored_settings(settings_path).await? else {
+        return Ok(false);
+    };
+    let plaintext = stored
+        .authorized_apps
+        .iter()
+        .any(|app| !app.api_key.is_empty() && !secret_box::is_encrypted(&app.api_key));
+    if !plaintext || secret_box::master_key().await.is_none() {
+        return Ok(false);
+    }
+
+    write_settings(settings_path, &stored.open_keys().await?).await?;
+    log_info!("Settings", "Encrypted the API keys of authorized apps");
+    Ok(true)
+}
+
+/// Save the settings, with the apps' keys encrypted and the file readable by
+/// the user only
+pub(crate) async fn write_settings(
+    settings_path: &Path,
+    settings: &Settings,
+) -> Result<(), String> {
+    if let Some(parent) = settings_path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("Failed to create config directory: {}", e))?;
+    }
+
+    let content = serde_json::to_string_pretty(&settings.with_sealed_keys().await)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    token_store::write_private(settings_path, content.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write settings file: {}", e))
 }
 
 /// Check authentication status with intelligent certificate handling
@@ -1756,25 +3378,10 @@ async fn move_cert_to_cloudflared(downloads_cert_path: &Path) -> Result<(), Stri
 /// Get current application settings
 #[tauri::command]
 pub async fn get_settings(state: State<'_, AppState>) -> Result<Settings, String> {
-    // For now, we'll create a simple settings system using files in the config directory
-    let config_dir = dirs::home_dir()
-        .ok_or_else(|| "Cannot determine home directory".to_string())?
-        .join(".mindlink");
-    
-    let settings_path = config_dir.join("settings.json");
-    
-    // Try to read existing settings file
-    if let Ok(content) = fs::read_to_string(&settings_path).await {
-        if let Ok(settings) = serde_json::from_str::<Settings>(&content) {
-            return Ok(settings);
-        }
-    }
-    
-    // Return default settings if file doesn't exist or is invalid
-    Ok(Settings {
-        default_model: Some("gpt-4".to_string()),
-        authorized_apps: Vec::new(),
-    })
+    // Defaults when there is no settings file yet
+    Ok(read_settings(&settings_path()?)
+        .await?
+        .unwrap_or_else(Settings::new))
 }
 
 /// Update a single setting
@@ -1784,12 +3391,8 @@ pub async fn update_setting(
     key: String,
     value: serde_json::Value,
 ) -> Result<(), String> {
-    let config_dir = dirs::home_dir()
-        .ok_or_else(|| "Cannot determine home directory".to_string())?
-        .join(".mindlink");
-    
-    let settings_path = config_dir.join("settings.json");
-    
+    let settings_path = settings_path()?;
+
     // Read current settings
     let mut settings = if let Ok(content) = fs::read_to_string(&settings_path).await {
         serde_json::from_str::<serde_json::Value>(&content)
@@ -1802,6 +3405,18 @@ pub async fn update_setting(
     if let Some(obj) = settings.as_object_mut() {
         obj.insert(key, value);
     }
+
+    // Keys of apps set this way are stored encrypted like any other
+    if let Some(apps) = settings
+        .get_mut("authorized_apps")
+        .and_then(|apps| apps.as_array_mut())
+    {
+        for api_key in apps.iter_mut().filter_map(|app| app.get_mut("api_key")) {
+            if let Some(plaintext) = api_key.as_str().filter(|key| !key.is_empty()) {
+                *api_key = serde_json::Value::String(secret_box::seal(plaintext).await);
+            }
+        }
+    }
     
     // Ensure config directory exists
     if let Some(parent) = settings_path.parent() {
@@ -1815,84 +3430,71 @@ pub async fn update_setting(
     let content = serde_json::to_string_pretty(&settings)
         .map_err(|e| format!("Failed to serialize settings: {}", e))?;
         
-    fs::write(&settings_path, content).await
-        .map_err(|e| format!("Failed to write settings file: {}", e))?;
-    
-    Ok(())
+    token_store::write_private(&settings_path, content.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write settings file: {}", e))
 }
 
 /// Get all authorized apps
 #[tauri::command]
 pub async fn get_authorized_apps(state: State<'_, AppState>) -> Result<Vec<AuthorizedApp>, String> {
-    let config_dir = dirs::home_dir()
-        .ok_or_else(|| "Cannot determine home directory".to_string())?
-        .join(".mindlink");
-    
-    let settings_path = config_dir.join("settings.json");
-    
-    // Try to read existing settings file
-    if let Ok(content) = fs::read_to_string(&settings_path).await {
-        if let Ok(settings) = serde_json::from_str::<Settings>(&content) {
-            return Ok(settings.authorized_apps);
-        }
-    }
-    
-    // Return empty list if file doesn't exist or is invalid
-    Ok(Vec::new())
+    read_authorized_apps().await
+}
+
+async fn read_authorized_apps() -> Result<Vec<AuthorizedApp>, String> {
+    Ok(read_settings(&settings_path()?)
+        .await?
+        .map(|settings| settings.authorized_apps)
+        .unwrap_or_default())
+}
+
+/// Apply the saved apps' model restrictions to the API server
+async fn sync_authorized_apps(state: &AppState, apps: &[AuthorizedApp]) {
+    state
+        .server_manager
+        .read()
+        .await
+        .set_authorized_apps(apps.to_vec())
+        .await;
 }
 
-/// Add a new authorized app
+/// Add a new authorized app and generate its API key.
+///
+/// Requests made with the key are held to `model`, or to `model` and
+/// `allowed_models` when additional models are listed.
 #[tauri::command]
 pub async fn add_authorized_app(
     state: State<'_, AppState>,
     name: String,
     model: String,
-) -> Result<(), String> {
-    let config_dir = dirs::home_dir()
-        .ok_or_else(|| "Cannot determine home directory".to_string())?
-        .join(".mindlink");
-    
-    let settings_path = config_dir.join("settings.json");
-    
+    allowed_models: Option<Vec<String>>,
+) -> Result<AuthorizedApp, String> {
+    let settings_path = settings_path()?;
+
     // Read current settings
-    let mut settings = if let Ok(content) = fs::read_to_string(&settings_path).await {
-        serde_json::from_str::<Settings>(&content)
-            .unwrap_or_else(|_| Settings {
-                default_model: Some("gpt-4".to_string()),
-                authorized_apps: Vec::new(),
-            })
-    } else {
-        Settings {
-            default_model: Some("gpt-4".to_string()),
-            authorized_apps: Vec::new(),
-        }
-    };
+    let mut settings = read_settings(&settings_path)
+        .await?
+        .unwrap_or_else(Settings::new);
     
     let new_app = AuthorizedApp {
         id: uuid::Uuid::new_v4().to_string(),
         name,
         model,
         created_at: chrono::Utc::now().to_rfc3339(),
+        api_key: generate_api_key(),
+        allowed_models: allowed_models.unwrap_or_default(),
+        system_prompt: None,
+        daily_token_budget: None,
+        monthly_token_budget: None,
     };
     
-    settings.authorized_apps.push(new_app);
-    
-    // Ensure config directory exists
-    if let Some(parent) = settings_path.parent() {
-        if !parent.exists() {
-            fs::create_dir_all(parent).await
-                .map_err(|e| format!("Failed to create config directory: {}", e))?;
-        }
-    }
+    settings.authorized_apps.push(new_app.clone());
     
     // Write back to file
-    let content = serde_json::to_string_pretty(&settings)
-        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
-        
-    fs::write(&settings_path, content).await
-        .map_err(|e| format!("Failed to write settings file: {}", e))?;
+    write_settings(&settings_path, &settings).await?;
     
-    Ok(())
+    sync_authorized_apps(&state, &settings.authorized_apps).await;
+    Ok(new_app)
 }
 
 /// Update an app's model
@@ -1902,19 +3504,12 @@ pub async fn update_app_model(
     app_id: String,
     model: String,
 ) -> Result<(), String> {
-    let config_dir = dirs::home_dir()
-        .ok_or_else(|| "Cannot determine home directory".to_string())?
-        .join(".mindlink");
-    
-    let settings_path = config_dir.join("settings.json");
-    
+    let settings_path = settings_path()?;
+
     // Read current settings
-    let mut settings = if let Ok(content) = fs::read_to_string(&settings_path).await {
-        serde_json::from_str::<Settings>(&content)
-            .map_err(|e| format!("Failed to parse settings: {}", e))?
-    } else {
-        return Err("Settings file not found".to_string());
-    };
+    let mut settings = read_settings(&settings_path)
+        .await?
+        .ok_or_else(|| "Settings file not found".to_string())?;
     
     let app = settings.authorized_apps.iter_mut()
         .find(|app| app.id == app_id)
@@ -1923,44 +3518,134 @@ pub async fn update_app_model(
     app.model = model;
     
     // Write back to file
-    let content = serde_json::to_string_pretty(&settings)
-        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
-        
-    fs::write(&settings_path, content).await
-        .map_err(|e| format!("Failed to write settings file: {}", e))?;
+    write_settings(&settings_path, &settings).await?;
     
+    sync_authorized_apps(&state, &settings.authorized_apps).await;
+    Ok(())
+}
+
+/// Generate a new API key for an app, invalidating the previous one.
+/// Apps added before keys were issued need this before they are enforced.
+#[tauri::command]
+pub async fn regenerate_app_key(
+    state: State<'_, AppState>,
+    app_id: String,
+) -> Result<String, String> {
+    let settings_path = settings_path()?;
+
+    // Read current settings
+    let mut settings = read_settings(&settings_path)
+        .await?
+        .ok_or_else(|| "Settings file not found".to_string())?;
+
+    let app = settings
+        .authorized_apps
+        .iter_mut()
+        .find(|app| app.id == app_id)
+        .ok_or_else(|| "App not found".to_string())?;
+
+    app.api_key = generate_api_key();
+    let api_key = app.api_key.clone();
+
+    // Write back to file
+    write_settings(&settings_path, &settings).await?;
+
+    sync_authorized_apps(&state, &settings.authorized_apps).await;
+    Ok(api_key)
+}
+
+/// Set the system prompt for an app's requests, or clear it with `None` to
+/// fall back to the configured default. The prompt may use template variables.
+#[tauri::command]
+pub async fn set_app_system_prompt(
+    state: State<'_, AppState>,
+    app_id: String,
+    system_prompt: Option<String>,
+) -> Result<(), String> {
+    let settings_path = settings_path()?;
+
+    // Read current settings
+    let mut settings = read_settings(&settings_path)
+        .await?
+        .ok_or_else(|| "Settings file not found".to_string())?;
+
+    let app = settings
+        .authorized_apps
+        .iter_mut()
+        .find(|app| app.id == app_id)
+        .ok_or_else(|| "App not found".to_string())?;
+
+    app.system_prompt = system_prompt.filter(|prompt| !prompt.trim().is_empty());
+
+    // Write back to file
+    write_settings(&settings_path, &settings).await?;
+
+    sync_authorized_apps(&state, &settings.authorized_apps).await;
+    Ok(())
+}
+
+/// Set an app's daily and monthly token budgets. `None` removes a budget.
+/// Requests made with the app's key are refused once a budget is used up.
+#[tauri::command]
+pub async fn set_app_token_budget(
+    state: State<'_, AppState>,
+    app_id: String,
+    daily_tokens: Option<u64>,
+    monthly_tokens: Option<u64>,
+) -> Result<(), String> {
+    if daily_tokens == Some(0) || monthly_tokens == Some(0) {
+        return Err("Token budgets must be greater than zero".to_string());
+    }
+
+    let settings_path = settings_path()?;
+
+    // Read current settings
+    let mut settings = read_settings(&settings_path)
+        .await?
+        .ok_or_else(|| "Settings file not found".to_string())?;
+
+    let app = settings
+        .authorized_apps
+        .iter_mut()
+        .find(|app| app.id == app_id)
+        .ok_or_else(|| "App not found".to_string())?;
+
+    app.daily_token_budget = daily_tokens;
+    app.monthly_token_budget = monthly_tokens;
+
+    // Write back to file
+    write_settings(&settings_path, &settings).await?;
+
+    sync_authorized_apps(&state, &settings.authorized_apps).await;
     Ok(())
 }
 
+/// Returns the token budgets of the apps that have one, with what is used
+/// in the current day and month.
+#[tauri::command]
+pub async fn get_token_budgets(state: State<'_, AppState>) -> Result<Vec<AppBudget>, String> {
+    Ok(state.server_manager.read().await.token_budgets().await)
+}
+
 /// Remove an authorized app
 #[tauri::command]
 pub async fn remove_authorized_app(
     state: State<'_, AppState>,
     app_id: String,
 ) -> Result<(), String> {
-    let config_dir = dirs::home_dir()
-        .ok_or_else(|| "Cannot determine home directory".to_string())?
-        .join(".mindlink");
-    
-    let settings_path = config_dir.join("settings.json");
-    
+    let settings_path = settings_path()?;
+
     // Read current settings
-    let mut settings = if let Ok(content) = fs::read_to_string(&settings_path).await {
-        serde_json::from_str::<Settings>(&content)
-            .map_err(|e| format!("Failed to parse settings: {}", e))?
-    } else {
-        return Err("Settings file not found".to_string());
-    };
+    let mut settings = read_settings(&settings_path)
+        .await?
+        .ok_or_else(|| "Settings file not found".to_string())?;
     
     settings.authorized_apps.retain(|app| app.id != app_id);
     
     // Write back to file
-    let content = serde_json::to_string_pretty(&settings)
-        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
-        
-    fs::write(&settings_path, content).await
-        .map_err(|e| format!("Failed to write settings file: {}", e))?;
+    write_settings(&settings_path, &settings).await?;
     
+    sync_authorized_apps(&state, &settings.authorized_apps).await;
     Ok(())
 }
 
@@ -2584,6 +4269,89 @@ pub async fn delete_ollama_model(model_name: String) -> Result<(), String> {
     }
 }
 
+/// Warm pool state for local models
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WarmPoolResponse {
+    pub capacity: usize,
+    pub models: Vec<WarmModel>,
+}
+
+async fn warm_pool_response(state: &State<'_, AppState>) -> WarmPoolResponse {
+    let local_model_manager = state.local_model_manager.read().await;
+    WarmPoolResponse {
+        capacity: local_model_manager.capacity().await,
+        models: local_model_manager.warm_models().await,
+    }
+}
+
+/// Get the local models currently kept loaded, most recently used first
+#[tauri::command]
+pub async fn get_warm_pool(state: State<'_, AppState>) -> Result<WarmPoolResponse, String> {
+    Ok(warm_pool_response(&state).await)
+}
+
+/// Load a local model into the warm pool, evicting the least recently used one if full
+#[tauri::command]
+pub async fn warm_local_model(
+    state: State<'_, AppState>,
+    model_name: String,
+) -> Result<WarmPoolResponse, String> {
+    {
+        let local_model_manager = state.local_model_manager.read().await;
+        local_model_manager
+            .acquire(&model_name)
+            .await
+            .map_err(|e| e.user_message())?;
+    }
+
+    Ok(warm_pool_response(&state).await)
+}
+
+/// Unload a local model and remove it from the warm pool
+#[tauri::command]
+pub async fn unload_local_model(
+    state: State<'_, AppState>,
+    model_name: String,
+) -> Result<WarmPoolResponse, String> {
+    {
+        let local_model_manager = state.local_model_manager.read().await;
+        local_model_manager
+            .release(&model_name)
+            .await
+            .map_err(|e| e.user_message())?;
+    }
+
+    Ok(warm_pool_response(&state).await)
+}
+
+/// Change how many local models are kept loaded (0 disables the warm pool)
+#[tauri::command]
+pub async fn set_warm_pool_size(
+    state: State<'_, AppState>,
+    size: usize,
+) -> Result<WarmPoolResponse, String> {
+    let local_models_config = {
+        let config_manager = state.config_manager.write().await;
+        let mut config = config_manager.get_config().await;
+        config.local_models.warm_pool_size = size;
+        config_manager
+            .update_config(config.clone())
+            .await
+            .map_err(|e| e.user_message())?;
+        config.local_models
+    };
+
+    {
+        let mut local_model_manager = state.local_model_manager.write().await;
+        local_model_manager
+            .configure(&local_models_config)
+            .await
+            .map_err(|e| e.user_message())?;
+    }
+
+    Ok(warm_pool_response(&state).await)
+}
+
 /// Check if a local LLM provider is configured in Bifrost
 #[tauri::command]
 pub async fn check_bifrost_llm_provider(
@@ -2639,105 +4407,75 @@ pub async fn configure_bifrost_llm_provider(
 
 // ===== Plugin Management Commands =====
 
-/// Plugin manifest structure for external plugins
-#[derive(Debug, Serialize, Deserialize)]
-pub struct PluginManifest {
-    pub id: String,
-    pub name: String,
-    pub version: String,
-    pub description: Option<String>,
-    pub author: Option<String>,
-    pub main: String,
-    pub dependencies: Option<Vec<String>>,
-    pub mindlink_version: Option<String>,
-}
-
 /// Response for plugin discovery operations
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PluginDiscoveryResponse {
     pub success: bool,
     pub manifests: Vec<PluginManifest>,
+    /// Plugins that were found but failed validation
+    pub errors: Vec<PluginLoadError>,
     pub plugins_directory: Option<String>,
     pub error: Option<String>,
 }
 
-/// Get available plugin manifests from the plugins directory
+impl PluginDiscoveryResponse {
+    fn from_registry(result: MindLinkResult<PluginRegistry>) -> Self {
+        match result {
+            Ok(registry) => Self {
+                success: true,
+                manifests: registry.manifests,
+                errors: registry.errors,
+                plugins_directory: Some(registry.plugins_directory),
+                error: None,
+            },
+            Err(e) => Self {
+                success: false,
+                manifests: Vec::new(),
+                errors: Vec::new(),
+                plugins_directory: None,
+                error: Some(e.user_message()),
+            },
+        }
+    }
+}
+
+/// Get available plugin manifests from the plugins directory (cached after the first scan)
 #[tauri::command]
-pub async fn get_plugin_manifests() -> Result<PluginDiscoveryResponse, String> {
-    println!("🔌 Discovering available plugins...");
-    
-    // For now, return built-in manifests since we haven't implemented external plugins yet
-    let built_in_manifests = vec![
-        PluginManifest {
-            id: "openai".to_string(),
-            name: "OpenAI".to_string(),
-            version: "1.0.0".to_string(),
-            description: Some("Connect to OpenAI GPT models via API".to_string()),
-            author: Some("MindLink Team".to_string()),
-            main: "openai.js".to_string(),
-            dependencies: None,
-            mindlink_version: Some("1.0.0".to_string()),
-        },
-        PluginManifest {
-            id: "anthropic".to_string(),
-            name: "Anthropic".to_string(),
-            version: "1.0.0".to_string(),
-            description: Some("Connect to Claude models via Anthropic API".to_string()),
-            author: Some("MindLink Team".to_string()),
-            main: "anthropic.js".to_string(),
-            dependencies: None,
-            mindlink_version: Some("1.0.0".to_string()),
-        },
-        PluginManifest {
-            id: "google".to_string(),
-            name: "Google".to_string(),
-            version: "1.0.0".to_string(),
-            description: Some("Connect to Gemini models via Google AI Studio".to_string()),
-            author: Some("MindLink Team".to_string()),
-            main: "google.js".to_string(),
-            dependencies: None,
-            mindlink_version: Some("1.0.0".to_string()),
-        },
-    ];
-    
-    println!("✅ Found {} plugin manifests", built_in_manifests.len());
-    
-    Ok(PluginDiscoveryResponse {
-        success: true,
-        manifests: built_in_manifests,
-        plugins_directory: Some("Built-in plugins".to_string()),
-        error: None,
-    })
+pub async fn get_plugin_manifests(
+    state: State<'_, AppState>,
+) -> Result<PluginDiscoveryResponse, String> {
+    let result = state.plugin_manager.write().await.registry().await;
+    Ok(PluginDiscoveryResponse::from_registry(result))
+}
+
+/// Rescan the plugins directory, e.g. after installing or editing a plugin
+#[tauri::command]
+pub async fn refresh_plugins(
+    state: State<'_, AppState>,
+) -> Result<PluginDiscoveryResponse, String> {
+    let result = state.plugin_manager.write().await.refresh().await;
+    Ok(PluginDiscoveryResponse::from_registry(result))
 }
 
 /// Get the plugins directory path for external plugins
 #[tauri::command]
 pub async fn get_plugins_directory() -> Result<String, String> {
-    // In production, this would be in the app data directory
-    // For example: ~/.local/share/mindlink/plugins or %APPDATA%/mindlink/plugins
-    let app_data_dir = dirs::data_local_dir()
-        .ok_or_else(|| "Cannot determine app data directory".to_string())?;
-    
-    let plugins_dir = app_data_dir.join("mindlink").join("plugins");
-    
+    let plugins_dir = PluginManager::default_plugins_dir().map_err(|e| e.user_message())?;
     Ok(plugins_dir.to_string_lossy().to_string())
 }
 
 /// Create the plugins directory if it doesn't exist
 #[tauri::command]
 pub async fn ensure_plugins_directory() -> Result<String, String> {
-    let app_data_dir = dirs::data_local_dir()
-        .ok_or_else(|| "Cannot determine app data directory".to_string())?;
-    
-    let plugins_dir = app_data_dir.join("mindlink").join("plugins");
-    
+    let plugins_dir = PluginManager::default_plugins_dir().map_err(|e| e.user_message())?;
+
     // Create directory if it doesn't exist
     if !plugins_dir.exists() {
         println!("📁 Creating plugins directory: {:?}", plugins_dir);
         fs::create_dir_all(&plugins_dir).await
             .map_err(|e| format!("Failed to create plugins directory: {}", e))?;
     }
-    
+
     Ok(plugins_dir.to_string_lossy().to_string())
 }
 
@@ -2746,6 +4484,9 @@ pub async fn ensure_plugins_directory() -> Result<String, String> {
 /// Complete ChatGPT OAuth authentication flow - opens browser and handles callback
 #[tauri::command]
 pub async fn authenticate_chatgpt(state: State<'_, AppState>) -> Result<String, String> {
+    // A second click while the browser login is open waits for it instead of
+    // starting another one
+    let since = Instant::now();
     let mut auth_manager = state.auth_manager.write().await;
     
     if let Some(logger) = get_logger() {
@@ -2757,7 +4498,7 @@ pub async fn authenticate_chatgpt(state: State<'_, AppState>) -> Result<String,
         logger.log(entry);
     }
     
-    match auth_manager.login().await {
+    match auth_manager.login_once(since).await {
         Ok(()) => {
             if let Some(logger) = get_logger() {
                 let entry = LogEntry::new(
@@ -2787,8 +4528,38 @@ pub async fn authenticate_chatgpt(state: State<'_, AppState>) -> Result<String,
 /// Check ChatGPT authentication status
 #[tauri::command]
 pub async fn check_chatgpt_auth_status(state: State<'_, AppState>) -> Result<bool, String> {
-    let auth_manager = state.auth_manager.read().await;
-    Ok(auth_manager.is_authenticated().await)
+    Ok(session_is_valid(&state).await)
+}
+
+/// Signs in the Claude account that serves `claude-*` models, in the browser
+#[tauri::command]
+pub async fn authenticate_anthropic(state: State<'_, AppState>) -> Result<String, String> {
+    let since = Instant::now();
+    let mut auth = state.anthropic_auth.write().await;
+    auth.login_once(since).await.map_err(|e| {
+        let error = MindLinkError::Authentication {
+            message: "Claude login failed".to_string(),
+            source: Some(e),
+        };
+        log_error!("Auth", error.clone());
+        error.user_message()
+    })?;
+    Ok("Claude authentication successful".to_string())
+}
+
+/// Check whether the Claude account is signed in
+#[tauri::command]
+pub async fn check_anthropic_auth_status(state: State<'_, AppState>) -> Result<bool, String> {
+    Ok(state.anthropic_auth.read().await.is_authenticated().await)
+}
+
+/// Signs the Claude account out; `claude-*` models are no longer served
+#[tauri::command]
+pub async fn logout_anthropic(state: State<'_, AppState>) -> Result<(), String> {
+    let mut auth = state.anthropic_auth.write().await;
+    auth.logout()
+        .await
+        .map_err(|e| format!("Claude logout failed: {}", e))
 }
 
 /// Get ChatGPT authentication information
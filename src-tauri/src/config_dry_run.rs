@@ -0,0 +1,145 @@
+// Dry runs of configuration changes
+//
+// A proposed configuration is validated, then recent requests from the
+// analytics journal are replayed through the parts of the request path that
+// depend on configuration: the client access policy, the batch and job API
+// switches, language routing and model aliases. Nothing is sent upstream.
+// The report lists every replayed request whose outcome would change.
+//
+// The journal records the model after language routing was applied, so a
+// removed language route cannot be told apart from a request that named the
+// routed model itself; such requests are reported as unchanged.
+
+use crate::analytics::RequestRecord;
+use crate::managers::config_manager::{ConfigManager, ConfigSchema};
+use crate::middleware::access_control::AccessPolicy;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// What the request path would do with a request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum Outcome {
+    /// Served, with the upstream model for completion routes
+    Accepted {
+        model: Option<String>,
+    },
+    Rejected {
+        reason: String,
+    },
+}
+
+/// A replayed request whose outcome differs under the proposed configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangedRequest {
+    pub request_id: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub method: String,
+    pub route: String,
+    pub client: Option<String>,
+    /// Model as recorded in the journal
+    pub model: Option<String>,
+    pub current: Outcome,
+    pub proposed: Outcome,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DryRunReport {
+    /// Why the proposed configuration would be refused, if it would be
+    pub error: Option<String>,
+    pub replayed: usize,
+    /// Served now, rejected under the proposed configuration
+    pub rejected: Vec<ChangedRequest>,
+    /// Rejected now, served under the proposed configuration
+    pub accepted: Vec<ChangedRequest>,
+    /// Served either way, but by a different upstream model
+    pub rerouted: Vec<ChangedRequest>,
+}
+
+/// Validate `proposed` and replay `records` under both configurations
+pub fn dry_run(
+    current: &ConfigSchema,
+    proposed: &ConfigSchema,
+    records: &[RequestRecord],
+) -> DryRunReport {
+    if let Err(e) = ConfigManager::validate_config(proposed) {
+        return DryRunReport {
+            error: Some(e.user_message()),
+            ..DryRunReport::default()
+        };
+    }
+
+    let mut report = DryRunReport {
+        replayed: records.len(),
+        ..DryRunReport::default()
+    };
+
+    for record in records {
+        let before = simulate(current, record);
+        let after = simulate(proposed, record);
+        let list = match (&before, &after) {
+            (Outcome::Accepted { .. }, Outcome::Rejected { .. }) => &mut report.rejected,
+            (Outcome::Rejected { .. }, Outcome::Accepted { .. }) => &mut report.accepted,
+            (Outcome::Accepted { model: a }, Outcome::Accepted { model: b }) if a != b => {
+                &mut report.rerouted
+            },
+            _ => continue,
+        };
+        list.push(ChangedRequest {
+            request_id: record.request_id.clone(),
+            timestamp: record.timestamp,
+            method: record.method.clone(),
+            route: record.route.clone(),
+            client: record.client.clone(),
+            model: record.model.clone(),
+            current: before,
+            proposed: after,
+        });
+    }
+
+    report
+}
+
+/// Run one journaled request through the configuration-dependent checks
+pub fn simulate(config: &ConfigSchema, record: &RequestRecord) -> Outcome {
+    let client = record
+        .client
+        .as_deref()
+        .and_then(|client| client.parse::<IpAddr>().ok());
+    if let Some(client) = client {
+        // The proposed configuration was validated, so this only fails for a
+        // broken current one, which the running server could not have loaded
+        let allowed = AccessPolicy::from_config(&config.access_control)
+            .map_or(true, |policy| policy.is_allowed(&client));
+        if !allowed {
+            return Outcome::Rejected {
+                reason: format!("Client address {} is not allowed", client),
+            };
+        }
+    }
+
+    let batch_route =
+        record.route.starts_with("/v1/files") || record.route.starts_with("/v1/batches");
+    if batch_route && !config.batches.enabled {
+        return Outcome::Rejected {
+            reason: "The batch API is disabled".to_string(),
+        };
+    }
+    if record.route.starts_with("/v1/jobs") && !config.jobs.enabled {
+        return Outcome::Rejected {
+            reason: "The job API is disabled".to_string(),
+        };
+    }
+
+    let model = record.model.as_deref().map(|model| {
+        let routed = record
+            .language
+            .as_deref()
+            .filter(|_| config.language_detection.enabled)
+            .and_then(|code| config.language_detection.routes.get(code))
+            .map_or(model, String::as_str);
+        config.model_aliases.resolve(routed)
+    });
+    Outcome::Accepted { model }
+}
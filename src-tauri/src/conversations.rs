@@ -0,0 +1,177 @@
+// Conversation continuity with the ChatGPT backend
+//
+// OpenAI clients resend the whole conversation with every request, while the
+// ChatGPT backend keeps conversations itself and expects each request to name
+// the conversation and the message it follows. The store remembers which
+// upstream conversation and message every returned reply belongs to. When a
+// later request starts with exactly that history, only the new messages are
+// sent upstream, continuing the same conversation.
+//
+// Histories are identified by a SHA-256 chain over the client's key and the
+// messages, so no message content is kept.
+
+use crate::managers::config_manager::ConversationConfig;
+use crate::managers::server_manager::Message;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+type HistoryHash = [u8; 32];
+
+/// Where a reply lives upstream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpstreamConversation {
+    pub conversation_id: String,
+    /// The assistant message; the next request's `parent_message_id`
+    pub message_id: String,
+}
+
+/// A request that continues a known upstream conversation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resumption {
+    pub conversation: UpstreamConversation,
+    /// Trailing messages of the request the backend has not seen yet
+    pub new_messages: usize,
+}
+
+#[derive(Debug)]
+struct Entry {
+    conversation: UpstreamConversation,
+    last_used: Instant,
+}
+
+#[derive(Debug)]
+pub struct ConversationStore {
+    config: ConversationConfig,
+    entries: Mutex<HashMap<HistoryHash, Entry>>,
+}
+
+/// A request whose reply is remembered once its upstream ids are known
+#[derive(Debug)]
+pub struct PendingTurn {
+    store: Arc<ConversationStore>,
+    history: HistoryHash,
+}
+
+fn chain(previous: &HistoryHash, role: &str, content: &str) -> HistoryHash {
+    let mut hasher = Sha256::new();
+    hasher.update(previous);
+    hasher.update(role.as_bytes());
+    hasher.update([0]);
+    // Clients commonly trim the replies they store
+    hasher.update(content.trim().as_bytes());
+    hasher.finalize().into()
+}
+
+/// Hash of every prefix of `messages`: entry `i` covers `messages[..=i]`
+fn history_hashes(scope: &str, messages: &[Message]) -> Vec<HistoryHash> {
+    let mut hash: HistoryHash = Sha256::digest(scope.as_bytes()).into();
+    messages
+        .iter()
+        .map(|message| {
+            hash = chain(&hash, &message.role, &message.content);
+            hash
+        })
+        .collect()
+}
+
+impl ConversationStore {
+    pub fn new(config: ConversationConfig) -> Self {
+        Self {
+            config,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(self.config.ttl_minutes * 60)
+    }
+
+    /// Look up the upstream conversation a request continues. `scope` keeps
+    /// clients apart (their API key, or empty). Returns the resumption, if
+    /// any, and the turn to complete once the reply is known.
+    pub fn begin(
+        self: &Arc<Self>,
+        scope: &str,
+        messages: &[Message],
+    ) -> (Option<Resumption>, Option<PendingTurn>) {
+        if !self.config.enabled || messages.is_empty() {
+            return (None, None);
+        }
+
+        let hashes = history_hashes(scope, messages);
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let ttl = self.ttl();
+
+        // Stored histories end with a reply, so the longest matching prefix
+        // is the conversation as the client last saw it
+        let resumption = (1..messages.len()).rev().find_map(|len| {
+            let entry = entries.get_mut(&hashes[len - 1])?;
+            if entry.last_used.elapsed() > ttl {
+                return None;
+            }
+            entry.last_used = Instant::now();
+            Some(Resumption {
+                conversation: entry.conversation.clone(),
+                new_messages: messages.len() - len,
+            })
+        });
+
+        let turn = PendingTurn {
+            store: self.clone(),
+            history: hashes[messages.len() - 1],
+        };
+        (resumption, Some(turn))
+    }
+
+    fn insert(&self, history: HistoryHash, conversation: UpstreamConversation) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let ttl = self.ttl();
+        entries.retain(|_, entry| entry.last_used.elapsed() <= ttl);
+
+        if entries.len() >= self.config.max_conversations {
+            if let Some(oldest) = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(hash, _)| *hash)
+            {
+                entries.remove(&oldest);
+            }
+        }
+
+        entries.insert(
+            history,
+            Entry {
+                conversation,
+                last_used: Instant::now(),
+            },
+        );
+    }
+
+    /// Conversations currently remembered
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl PendingTurn {
+    /// Remember that `reply` is `conversation`'s latest message, so the
+    /// client's next request can continue it
+    pub fn complete(self, reply: &str, conversation: UpstreamConversation) {
+        let history = chain(&self.history, "assistant", reply);
+        self.store.insert(history, conversation);
+    }
+}
+
+/// Conversation and message ids reported in a backend response or stream event
+pub fn upstream_ids(event: &serde_json::Value) -> Option<UpstreamConversation> {
+    Some(UpstreamConversation {
+        conversation_id: event.get("conversation_id")?.as_str()?.to_string(),
+        message_id: event.get("message")?.get("id")?.as_str()?.to_string(),
+    })
+}
@@ -0,0 +1,126 @@
+// Device-code sign-in for machines without a browser
+//
+// The browser login needs a browser on this machine and the callback server on
+// port 1455, neither of which a headless server has. With the device-code flow
+// MindLink prints a URL and a short code instead; the user enters the code on
+// any device, and MindLink polls until it receives the authorization code and
+// PKCE verifier, which are then exchanged for tokens like a browser login.
+// The endpoints are the ones the Codex CLI uses for `codex login --device-auth`.
+
+use anyhow::{anyhow, Result};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Deserializer};
+use std::time::{Duration, Instant};
+
+/// Base of the device authorization endpoints
+pub const DEVICE_AUTH_URL: &str = "https://auth.openai.com/api/accounts/deviceauth";
+/// Page where the user enters the code
+pub const VERIFICATION_URL: &str = "https://auth.openai.com/codex/device";
+/// Redirect URI the authorization code of a device login is bound to
+pub const REDIRECT_URI: &str = "https://auth.openai.com/deviceauth/callback";
+
+/// How long the user has to enter the code
+pub const CODE_LIFETIME: Duration = Duration::from_secs(15 * 60);
+
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 5;
+
+/// A code for the user to enter at [`VERIFICATION_URL`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceCode {
+    pub device_auth_id: String,
+    #[serde(alias = "usercode")]
+    pub user_code: String,
+    /// Seconds to wait between polls
+    #[serde(default = "default_interval", deserialize_with = "interval_secs")]
+    pub interval: u64,
+}
+
+/// Result of a completed device login, to exchange for tokens
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceAuthorization {
+    pub authorization_code: String,
+    pub code_verifier: String,
+}
+
+fn default_interval() -> u64 {
+    DEFAULT_POLL_INTERVAL_SECS
+}
+
+/// The interval arrives as a number or as a string of one
+fn interval_secs<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Interval {
+        Number(u64),
+        Text(String),
+    }
+
+    match Interval::deserialize(deserializer)? {
+        Interval::Number(secs) => Ok(secs),
+        Interval::Text(text) => text.trim().parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// Ask for a code to sign in with on another device
+pub async fn request_code(client: &Client, base_url: &str, client_id: &str) -> Result<DeviceCode> {
+    let response = client
+        .post(format!("{}/usercode", base_url))
+        .json(&serde_json::json!({ "client_id": client_id }))
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(anyhow!(
+            "Device code request failed: {} - {}",
+            status,
+            error_text
+        ));
+    }
+    Ok(response.json().await?)
+}
+
+/// Poll until the user has entered `code`, or until `timeout` has passed
+pub async fn wait_for_authorization(
+    client: &Client,
+    base_url: &str,
+    code: &DeviceCode,
+    timeout: Duration,
+) -> Result<DeviceAuthorization> {
+    let deadline = Instant::now() + timeout;
+    let interval = Duration::from_secs(code.interval.max(1));
+
+    loop {
+        let response = client
+            .post(format!("{}/token", base_url))
+            .json(&serde_json::json!({
+                "device_auth_id": code.device_auth_id,
+                "user_code": code.user_code,
+            }))
+            .send()
+            .await?;
+
+        match response.status() {
+            status if status.is_success() => return Ok(response.json().await?),
+            // Not entered yet
+            StatusCode::FORBIDDEN | StatusCode::NOT_FOUND => {},
+            status => {
+                let error_text = response.text().await.unwrap_or_default();
+                return Err(anyhow!(
+                    "Device authorization failed: {} - {}",
+                    status,
+                    error_text
+                ));
+            },
+        }
+
+        if Instant::now() + interval > deadline {
+            return Err(anyhow!(
+                "The device code was not entered within {} minutes",
+                timeout.as_secs() / 60
+            ));
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
@@ -2,10 +2,11 @@
 
 #![allow(dead_code)]
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Emitter};
+use tauri::AppHandle;
 use tauri_plugin_dialog::DialogExt;
 
-use crate::error::MindLinkError;
+use crate::error::{MindLinkError, RecoveryAction};
+use crate::events::{self, AppEvent, Notification, NotificationKind};
 use crate::logging::{get_logger, LogCategory, LogEntry, LogLevel};
 
 /// Types of dialogs that can be shown to users
@@ -26,6 +27,8 @@ pub struct DialogConfig {
     pub show_details: bool,
     pub technical_details: Option<String>,
     pub suggested_action: Option<String>,
+    /// Recovery the default button starts, for the frontend
+    pub recovery_action: Option<RecoveryAction>,
     pub buttons: Vec<DialogButton>,
 }
 
@@ -38,6 +41,35 @@ pub struct DialogButton {
     pub is_cancel: bool,
 }
 
+impl DialogButton {
+    /// Buttons offering `action`, followed by Cancel
+    pub fn for_recovery(action: &RecoveryAction) -> Vec<DialogButton> {
+        let (id, label) = match action {
+            RecoveryAction::ReAuthenticate => ("re_authenticate", "Sign In Again".to_string()),
+            RecoveryAction::RetryLater => ("retry", "Retry".to_string()),
+            RecoveryAction::ReinstallBinary { binary } => {
+                ("reinstall_binary", format!("Reinstall {}", binary))
+            },
+            RecoveryAction::OpenSettings { .. } => ("open_settings", "Open Settings".to_string()),
+        };
+
+        vec![
+            DialogButton {
+                id: id.to_string(),
+                label,
+                is_default: true,
+                is_cancel: false,
+            },
+            DialogButton {
+                id: "cancel".to_string(),
+                label: "Cancel".to_string(),
+                is_default: false,
+                is_cancel: true,
+            },
+        ]
+    }
+}
+
 impl Default for DialogButton {
     fn default() -> Self {
         Self {
@@ -83,6 +115,7 @@ impl DialogManager {
             None => "Application Error".to_string(),
         };
 
+        let recovery_action = error.recovery_action();
         let config = DialogConfig {
             title,
             message: error.user_message(),
@@ -90,24 +123,11 @@ impl DialogManager {
             show_details: true,
             technical_details: Some(error.technical_details()),
             suggested_action: error.suggested_action(),
-            buttons: if error.is_recoverable() {
-                vec![
-                    DialogButton {
-                        id: "retry".to_string(),
-                        label: "Retry".to_string(),
-                        is_default: true,
-                        is_cancel: false,
-                    },
-                    DialogButton {
-                        id: "cancel".to_string(),
-                        label: "Cancel".to_string(),
-                        is_default: false,
-                        is_cancel: true,
-                    },
-                ]
-            } else {
-                vec![DialogButton::default()]
+            buttons: match &recovery_action {
+                Some(action) => DialogButton::for_recovery(action),
+                None => vec![DialogButton::default()],
             },
+            recovery_action,
         };
 
         Self::show_dialog(app_handle, config).await
@@ -127,6 +147,7 @@ impl DialogManager {
             show_details: details.is_some(),
             technical_details: details.map(|d| d.to_string()),
             suggested_action: None,
+            recovery_action: None,
             buttons: vec![DialogButton::default()],
         };
 
@@ -142,6 +163,7 @@ impl DialogManager {
             show_details: false,
             technical_details: None,
             suggested_action: None,
+            recovery_action: None,
             buttons: vec![DialogButton::default()],
         };
 
@@ -162,6 +184,7 @@ impl DialogManager {
             show_details: false,
             technical_details: None,
             suggested_action: None,
+            recovery_action: None,
             buttons,
         };
 
@@ -242,6 +265,7 @@ impl DialogManager {
             show_details: true,
             technical_details: Some(details.to_string()),
             suggested_action: Some("Check your internet connection and try again. If the problem persists, check your firewall settings.".to_string()),
+            recovery_action: Some(RecoveryAction::RetryLater),
             buttons: vec![
                 DialogButton {
                     id: "retry".to_string(),
@@ -300,6 +324,9 @@ impl DialogManager {
             show_details: true,
             technical_details: Some(details),
             suggested_action: Some(suggested_action.to_string()),
+            recovery_action: Some(RecoveryAction::ReinstallBinary {
+                binary: binary_name.to_string(),
+            }),
             buttons: vec![
                 DialogButton {
                     id: "install".to_string(),
@@ -324,30 +351,28 @@ impl DialogManager {
         app_handle: &AppHandle,
         title: &str,
         message: &str,
-        notification_type: &str,
+        kind: NotificationKind,
     ) {
-        let notification_data = serde_json::json!({
-            "title": title,
-            "message": message,
-            "type": notification_type,
-            "timestamp": chrono::Utc::now().to_rfc3339(),
-        });
-
-        // Emit to frontend for display in notification system
-        if let Err(e) = app_handle.emit("notification", notification_data) {
-            eprintln!("Failed to send notification to frontend: {}", e);
-        }
+        Self::emit_notification(app_handle, Notification::new(kind, title, message));
+    }
 
+    fn emit_notification(app_handle: &AppHandle, notification: Notification) {
         // Log the notification
         if let Some(logger) = get_logger() {
             let entry = LogEntry::new(
                 LogLevel::Info,
                 LogCategory::UserAction,
-                format!("Sent notification: {} - {}", title, message),
+                format!(
+                    "Sent notification: {} - {}",
+                    notification.title, notification.message
+                ),
             )
             .with_component("Dialog");
             logger.log(entry);
         }
+
+        // Emit to frontend for display in notification system
+        events::emit(app_handle, AppEvent::Notification(notification));
     }
 
     /// Send an error notification (non-blocking)
@@ -361,21 +386,24 @@ impl DialogManager {
             None => "Application Error".to_string(),
         };
 
-        Self::send_notification(app_handle, &title, &error.user_message(), "error");
+        // Error notifications carry the recovery the frontend offers as a button
+        let notification = Notification::new(NotificationKind::Error, title, error.user_message())
+            .with_action(error.recovery_action());
+        Self::emit_notification(app_handle, notification);
     }
 
     /// Send a success notification
     pub fn send_success_notification(app_handle: &AppHandle, title: &str, message: &str) {
-        Self::send_notification(app_handle, title, message, "success");
+        Self::send_notification(app_handle, title, message, NotificationKind::Success);
     }
 
     /// Send a warning notification
     pub fn send_warning_notification(app_handle: &AppHandle, title: &str, message: &str) {
-        Self::send_notification(app_handle, title, message, "warning");
+        Self::send_notification(app_handle, title, message, NotificationKind::Warning);
     }
 
     /// Send an info notification
     pub fn send_info_notification(app_handle: &AppHandle, title: &str, message: &str) {
-        Self::send_notification(app_handle, title, message, "info");
+        Self::send_notification(app_handle, title, message, NotificationKind::Info);
     }
 }
@@ -0,0 +1,189 @@
+// Downloads of binaries, with progress and resumption
+//
+// cloudflared and ngrok are tens of megabytes, so their downloads are streamed
+// to a `.partial` file and their progress is published as they go. Every
+// BinaryManager shares one channel of progress for the whole process, which
+// the app forwards to the frontend as `download-progress` events. A download
+// that is interrupted leaves its `.partial` file behind, and the next attempt
+// asks the server for the rest of it rather than starting over.
+
+use crate::proxy;
+use anyhow::{anyhow, Result};
+use futures_util::StreamExt;
+use reqwest::{header, Client, StatusCode};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::broadcast;
+use ts_rs::TS;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How long a download may go without receiving anything
+const STALL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Least time between two progress reports of a download
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+static PROGRESS: LazyLock<broadcast::Sender<DownloadProgress>> =
+    LazyLock::new(|| broadcast::channel(64).0);
+
+/// How far the download of a binary got
+#[derive(Debug, Clone, PartialEq, Serialize, TS)]
+#[ts(export)]
+pub struct DownloadProgress {
+    /// Binary being downloaded, e.g. `cloudflared`
+    pub binary: String,
+    /// Bytes downloaded so far, those of an interrupted attempt included
+    #[ts(type = "number")]
+    pub downloaded: u64,
+    /// Size of the download, when the server tells it
+    #[ts(type = "number | null")]
+    pub total: Option<u64>,
+    /// Share downloaded, from 0 to 100, when the size is known
+    pub percent: Option<f64>,
+    /// Bytes taken over from an interrupted attempt
+    #[ts(type = "number")]
+    pub resumed_from: u64,
+    pub done: bool,
+}
+
+impl DownloadProgress {
+    fn new(binary: &str, downloaded: u64, total: Option<u64>, resumed_from: u64) -> Self {
+        Self {
+            binary: binary.to_string(),
+            downloaded,
+            total,
+            percent: total
+                .filter(|total| *total > 0)
+                .map(|total| (downloaded as f64 * 100.0 / total as f64).min(100.0)),
+            resumed_from,
+            done: false,
+        }
+    }
+}
+
+/// Progress of every download from now on
+pub fn subscribe() -> broadcast::Receiver<DownloadProgress> {
+    PROGRESS.subscribe()
+}
+
+fn publish(progress: DownloadProgress) {
+    // No receivers only means nobody is listening
+    let _ = PROGRESS.send(progress);
+}
+
+/// Client binaries are downloaded with, through the configured proxy. GitHub's
+/// API refuses requests without a user agent.
+pub fn download_client() -> Client {
+    proxy::apply(Client::builder())
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(STALL_TIMEOUT)
+        .user_agent("MindLink/1.0")
+        .build()
+        .unwrap_or_default()
+}
+
+/// Total size told by a `Content-Range` of `bytes <start>-<end>/<total>`
+fn content_range_total(response: &reqwest::Response) -> Option<u64> {
+    response
+        .headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit_once('/')?
+        .1
+        .parse()
+        .ok()
+}
+
+/// Download `url` of `binary` to `path`, continuing what an interrupted
+/// attempt left there. Servers that cannot resume send the whole file
+/// again. The caller checks the result, and removes `path` when it is no
+/// good, so the next attempt starts afresh.
+pub async fn download(client: &Client, url: &str, path: &Path, binary: &str) -> Result<()> {
+    let mut offset = tokio::fs::metadata(path)
+        .await
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+
+    let response = loop {
+        let mut request = client.get(url);
+        if offset > 0 {
+            request = request.header(header::RANGE, format!("bytes={}-", offset));
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| anyhow!("Failed to download {}: {}", binary, e))?;
+        match response.status() {
+            // What was left there is no part of this file
+            StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+                tokio::fs::remove_file(path).await?;
+                offset = 0;
+            },
+            StatusCode::PARTIAL_CONTENT => break response,
+            status if status.is_success() => {
+                // The server ignored the range and sends all of it
+                offset = 0;
+                break response;
+            },
+            status => {
+                return Err(anyhow!("Failed to download {}: HTTP {}", binary, status));
+            },
+        }
+    };
+
+    let total = match offset {
+        0 => response.content_length(),
+        _ => content_range_total(&response)
+            .or_else(|| response.content_length().map(|length| offset + length)),
+    };
+    let mut file = if offset > 0 {
+        println!("Resuming the download of {} at {} bytes", binary, offset);
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(path)
+            .await?
+    } else {
+        tokio::fs::File::create(path).await?
+    };
+
+    let mut downloaded = offset;
+    let mut reported_at = Instant::now();
+    publish(DownloadProgress::new(binary, downloaded, total, offset));
+    let mut chunks = response.bytes_stream();
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk.map_err(|e| {
+            anyhow!(
+                "The download of {} was interrupted at {} bytes: {}",
+                binary,
+                downloaded,
+                e
+            )
+        })?;
+        file.write_all(&chunk).await?;
+        downloaded += chunk.len() as u64;
+        if reported_at.elapsed() >= PROGRESS_INTERVAL {
+            reported_at = Instant::now();
+            publish(DownloadProgress::new(binary, downloaded, total, offset));
+        }
+    }
+    file.flush().await?;
+
+    if let Some(total) = total.filter(|total| downloaded < *total) {
+        return Err(anyhow!(
+            "The download of {} ended at {} of {} bytes",
+            binary,
+            downloaded,
+            total
+        ));
+    }
+    publish(DownloadProgress {
+        done: true,
+        ..DownloadProgress::new(binary, downloaded, total.or(Some(downloaded)), offset)
+    });
+    Ok(())
+}
@@ -3,6 +3,23 @@
 #![allow(missing_docs)]
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use ts_rs::TS;
+
+/// What the user can do to recover from an error. The UI renders it as a
+/// button instead of a generic dialog.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(tag = "action", rename_all = "snake_case")]
+#[ts(export)]
+pub enum RecoveryAction {
+    /// Sign in to ChatGPT again
+    ReAuthenticate,
+    /// The cause is likely temporary; try again in a moment
+    RetryLater,
+    /// Reinstall the binary that failed to run
+    ReinstallBinary { binary: String },
+    /// Fix the settings in `section`, e.g. `server` or `tunnel`
+    OpenSettings { section: String },
+}
 
 /// Main application error type that provides user-friendly messages
 /// and detailed technical information for logging
@@ -271,6 +288,22 @@ impl MindLinkError {
         }
     }
 
+    /// Name of the error variant, for logs and the error feed
+    pub fn kind(&self) -> &'static str {
+        match self {
+            MindLinkError::Authentication { .. } => "Authentication",
+            MindLinkError::Network { .. } => "Network",
+            MindLinkError::BinaryExecution { .. } => "BinaryExecution",
+            MindLinkError::Configuration { .. } => "Configuration",
+            MindLinkError::FileSystem { .. } => "FileSystem",
+            MindLinkError::ProcessMonitoring { .. } => "ProcessMonitoring",
+            MindLinkError::HealthCheck { .. } => "HealthCheck",
+            MindLinkError::Tunnel { .. } => "Tunnel",
+            MindLinkError::SystemResource { .. } => "SystemResource",
+            MindLinkError::Internal { .. } => "Internal",
+        }
+    }
+
     /// Get technical details for logging (includes source error chain)
     pub fn technical_details(&self) -> String {
         let base_message = self.to_string();
@@ -312,6 +345,36 @@ impl MindLinkError {
         }
     }
 
+    /// Machine-readable counterpart of `suggested_action`, or `None` when only
+    /// a restart or a bug report helps
+    pub fn recovery_action(&self) -> Option<RecoveryAction> {
+        match self {
+            MindLinkError::Authentication { .. } => Some(RecoveryAction::ReAuthenticate),
+            MindLinkError::Network { .. } => match crate::api_error::upstream_status(self) {
+                Some(upstream) if upstream.status == 401 => Some(RecoveryAction::ReAuthenticate),
+                _ => Some(RecoveryAction::RetryLater),
+            },
+            MindLinkError::BinaryExecution { binary_name, .. } => {
+                Some(RecoveryAction::ReinstallBinary {
+                    binary: binary_name.clone(),
+                })
+            },
+            MindLinkError::Configuration { config_key, .. } => {
+                // Keys are `section` or `section.field`
+                let section = config_key
+                    .as_deref()
+                    .and_then(|key| key.split('.').next())
+                    .filter(|section| !section.is_empty())
+                    .unwrap_or("general");
+                Some(RecoveryAction::OpenSettings {
+                    section: section.to_string(),
+                })
+            },
+            MindLinkError::Internal { .. } => None,
+            _ => Some(RecoveryAction::RetryLater),
+        }
+    }
+
     /// Get suggested action for the user
     pub fn suggested_action(&self) -> Option<String> {
         match self {
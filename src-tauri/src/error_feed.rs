@@ -0,0 +1,83 @@
+// Recent errors, newest first, for the UI
+//
+// Every error logged through `LogManager::log_error` is kept here with the
+// component that raised it and what the user can do about it, so the UI can
+// show a history instead of the single most recent message.
+
+use crate::error::{MindLinkError, RecoveryAction};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Errors kept before the oldest are dropped
+pub const ERROR_FEED_CAPACITY: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorFeedEntry {
+    pub timestamp: DateTime<Utc>,
+    pub component: String,
+    /// Error variant, e.g. `Network`
+    pub kind: String,
+    pub user_message: String,
+    pub technical_details: String,
+    /// What the UI should offer to fix it
+    pub action: Option<RecoveryAction>,
+    /// Longer advice to show next to the action
+    pub hint: Option<String>,
+    pub correlation_id: Option<String>,
+}
+
+/// Bounded history of logged errors
+#[derive(Debug)]
+pub struct ErrorFeed {
+    entries: Mutex<VecDeque<ErrorFeedEntry>>,
+    capacity: usize,
+}
+
+impl Default for ErrorFeed {
+    fn default() -> Self {
+        Self::new(ERROR_FEED_CAPACITY)
+    }
+}
+
+impl ErrorFeed {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+        }
+    }
+
+    pub fn record(&self, component: &str, error: &MindLinkError, correlation_id: Option<&str>) {
+        let entry = ErrorFeedEntry {
+            timestamp: Utc::now(),
+            component: component.to_string(),
+            kind: error.kind().to_string(),
+            user_message: error.user_message(),
+            technical_details: error.technical_details(),
+            action: error.recovery_action(),
+            hint: error.suggested_action(),
+            correlation_id: correlation_id.map(str::to_string),
+        };
+
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= self.capacity {
+            entries.pop_back();
+        }
+        entries.push_front(entry);
+    }
+
+    /// Up to `limit` entries, newest first
+    pub fn recent(&self, limit: usize) -> Vec<ErrorFeedEntry> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.iter().take(limit).cloned().collect()
+    }
+
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
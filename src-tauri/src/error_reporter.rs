@@ -8,7 +8,7 @@ use std::collections::HashMap;
 use tauri::AppHandle;
 
 use crate::dialog::DialogManager;
-use crate::error::MindLinkError;
+use crate::error::{MindLinkError, RecoveryAction};
 use crate::logging::{get_logger, LogCategory, LogEntry, LogLevel};
 
 /// Error context information
@@ -28,6 +28,8 @@ pub struct ErrorReport {
     pub timestamp: DateTime<Utc>,
     pub error: MindLinkError,
     pub context: ErrorContext,
+    /// Recovery offered to the user, if any
+    pub recovery_action: Option<RecoveryAction>,
     pub user_notified: bool,
     pub resolved: bool,
     pub resolution_notes: Option<String>,
@@ -85,6 +87,7 @@ impl ErrorReporter {
             timestamp: Utc::now(),
             error: error.clone(),
             context: context.clone(),
+            recovery_action: error.recovery_action(),
             user_notified: false,
             resolved: false,
             resolution_notes: None,
@@ -130,7 +133,17 @@ impl ErrorReporter {
         let should_show_dialog =
             self.config.show_user_dialogs && self.should_show_dialog(&report.error, context);
 
-        if should_show_dialog {
+        if should_show_dialog && report.recovery_action.is_some() {
+            // The frontend renders the recovery as a button on the
+            // notification, which a native dialog cannot do
+            DialogManager::send_error_notification(
+                app_handle,
+                &report.error,
+                Some(&context.component),
+            );
+
+            report.user_notified = true;
+        } else if should_show_dialog {
             // Show blocking dialog for critical errors
             let dialog_result =
                 DialogManager::show_error(app_handle, &report.error, Some(&context.component))
@@ -0,0 +1,150 @@
+// Typed events sent from the backend to the frontend
+//
+// Every event the frontend can receive is a variant of `AppEvent`, and all
+// emissions go through `emit`. Payloads are wrapped in an `EventEnvelope`
+// carrying `EVENT_VERSION`, so the frontend can tell when it is talking to a
+// backend with a different contract. The TypeScript types in
+// `src/types/generated` are produced from these definitions by ts-rs when the
+// tests run.
+
+use crate::downloads::DownloadProgress;
+use crate::error::RecoveryAction;
+use crate::health::HealthReport;
+use crate::log_warn;
+use crate::managers::auth_manager::AuthEvent;
+use crate::managers::tunnel_manager::TunnelUrlChanged;
+use crate::middleware::backpressure::OverloadEvent;
+use crate::power::PowerStatus;
+use crate::tunnel_limits::TunnelLimitWarning;
+use crate::tunnel_tokens::TunnelTokenRotated;
+use crate::TrayState;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use ts_rs::TS;
+
+/// Bumped whenever an event or its payload changes incompatibly
+pub const EVENT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum NotificationKind {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+/// Non-blocking notification shown by the frontend
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct Notification {
+    pub title: String,
+    pub message: String,
+    #[serde(rename = "type")]
+    pub kind: NotificationKind,
+    pub timestamp: DateTime<Utc>,
+    /// Recovery the frontend offers as a button on error notifications
+    pub action: Option<RecoveryAction>,
+}
+
+impl Notification {
+    pub fn new(
+        kind: NotificationKind,
+        title: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            title: title.into(),
+            message: message.into(),
+            kind,
+            timestamp: Utc::now(),
+            action: None,
+        }
+    }
+
+    pub fn with_action(mut self, action: Option<RecoveryAction>) -> Self {
+        self.action = action;
+        self
+    }
+}
+
+/// Every event the backend emits. The Tauri event name equals `kind`.
+#[derive(Debug, Clone, Serialize, TS)]
+#[serde(tag = "kind", content = "data", rename_all = "kebab-case")]
+#[ts(export)]
+pub enum AppEvent {
+    Notification(Notification),
+    TrayStateChanged(TrayState),
+    HealthChanged(HealthReport),
+    PowerSaverChanged(PowerStatus),
+    /// Completion requests are being turned away with 429
+    Overloaded(OverloadEvent),
+    /// Progress of a ChatGPT login, or a failed token refresh
+    Auth(AuthEvent),
+    TunnelTokenRotated(TunnelTokenRotated),
+    /// The tunnel's public URL changed; pairing data shown before is outdated
+    TunnelUrlChanged(TunnelUrlChanged),
+    /// Traffic through the tunnel is nearing or reached a configured limit
+    TunnelLimit(TunnelLimitWarning),
+    /// A binary such as cloudflared is being downloaded
+    DownloadProgress(DownloadProgress),
+}
+
+impl AppEvent {
+    /// Name the event is emitted under
+    pub fn name(&self) -> &'static str {
+        match self {
+            AppEvent::Notification(_) => "notification",
+            AppEvent::TrayStateChanged(_) => "tray-state-changed",
+            AppEvent::HealthChanged(_) => "health-changed",
+            AppEvent::PowerSaverChanged(_) => "power-saver-changed",
+            AppEvent::Overloaded(_) => "overloaded",
+            AppEvent::Auth(_) => "auth",
+            AppEvent::TunnelTokenRotated(_) => "tunnel-token-rotated",
+            AppEvent::TunnelUrlChanged(_) => "tunnel-url-changed",
+            AppEvent::TunnelLimit(_) => "tunnel-limit",
+            AppEvent::DownloadProgress(_) => "download-progress",
+        }
+    }
+}
+
+/// Payload of every emitted event
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct EventEnvelope {
+    pub version: u32,
+    pub event: AppEvent,
+}
+
+impl EventEnvelope {
+    pub fn new(event: AppEvent) -> Self {
+        Self {
+            version: EVENT_VERSION,
+            event,
+        }
+    }
+}
+
+/// Emit `event` to all windows. Failures are logged, not returned: a missing
+/// window must never interrupt the task that produced the event.
+pub fn emit(app_handle: &AppHandle, event: AppEvent) {
+    let name = event.name();
+    if let Err(e) = app_handle.emit(name, EventEnvelope::new(event)) {
+        log_warn!("Events", &format!("Failed to emit {}: {}", name, e));
+    }
+}
+
+/// Emit a notification
+pub fn notify(
+    app_handle: &AppHandle,
+    kind: NotificationKind,
+    title: impl Into<String>,
+    message: impl Into<String>,
+) {
+    emit(
+        app_handle,
+        AppEvent::Notification(Notification::new(kind, title, message)),
+    );
+}
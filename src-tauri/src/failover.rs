@@ -0,0 +1,187 @@
+// Failover of chat completions to other OpenAI-compatible providers
+//
+// When the ChatGPT backend cannot serve a chat completion, because it is
+// unreachable, the session expired, the account is rate limited or the
+// backend fails, the request is sent to the providers of the configured
+// fallback chain in order: an OpenAI API key, a local model behind Bifrost,
+// or anything else that speaks the OpenAI API. Errors the client caused, such
+// as a malformed request or an unknown model, are returned as they are since
+// every provider would reject them too.
+//
+// Providers served by the configured Ollama go through its warm pool: the
+// model is loaded, and the least recently used ones unloaded, before the
+// request is sent, and a model the provider fails on is released again.
+
+use crate::api_error::upstream_status;
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::log_warn;
+use crate::managers::config_manager::{FailoverConfig, FallbackProvider};
+use crate::managers::local_model_manager::LocalModelManager;
+use crate::managers::server_manager::ChatCompletionRequest;
+use reqwest::Client;
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::sync::mpsc::Sender;
+
+/// Whether a failure of the ChatGPT backend should be retried elsewhere
+pub fn should_fail_over(error: &MindLinkError) -> bool {
+    match error {
+        MindLinkError::Authentication { .. } => true,
+        MindLinkError::Network { .. } => match upstream_status(error) {
+            Some(upstream) => matches!(upstream.status, 401 | 403 | 429 | 500..=599),
+            // Unreachable
+            None => true,
+        },
+        _ => false,
+    }
+}
+
+/// `request` as sent to `provider`, with its model override applied
+pub fn provider_request(
+    provider: &FallbackProvider,
+    request: &ChatCompletionRequest,
+    stream: bool,
+) -> ChatCompletionRequest {
+    let mut request = request.clone();
+    if let Some(model) = &provider.model {
+        request.model = model.clone();
+    }
+    request.stream = Some(stream);
+    request
+}
+
+/// Chat completions URL of `provider`, or `None` for Bifrost while it is stopped
+pub fn completions_url(provider: &FallbackProvider, bifrost_url: Option<&str>) -> Option<String> {
+    let base = match &provider.base_url {
+        Some(base) => base.trim_end_matches('/').to_string(),
+        None => format!("{}/v1", bifrost_url?.trim_end_matches('/')),
+    };
+    Some(format!("{}/chat/completions", base))
+}
+
+/// A successful response of a fallback provider
+#[derive(Debug)]
+pub struct FallbackResponse {
+    /// Name of the provider that answered
+    pub provider: String,
+    pub response: reqwest::Response,
+}
+
+#[derive(Debug, Default)]
+pub struct FallbackChain {
+    config: FailoverConfig,
+}
+
+impl FallbackChain {
+    pub fn new(config: FailoverConfig) -> Self {
+        Self { config }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled && !self.config.providers.is_empty()
+    }
+
+    /// Send `request` to each provider in turn until one accepts it. Each
+    /// provider gets `timeout` to answer; a stream only to start. Models of
+    /// providers served by `local_models` are warmed first.
+    pub async fn send(
+        &self,
+        client: &Client,
+        request: &ChatCompletionRequest,
+        stream: bool,
+        bifrost_url: Option<&str>,
+        local_models: Option<&LocalModelManager>,
+        timeout: Duration,
+    ) -> MindLinkResult<FallbackResponse> {
+        let mut last_error = None;
+        for provider in &self.config.providers {
+            let Some(url) = completions_url(provider, bifrost_url) else {
+                continue;
+            };
+            let provider_request = provider_request(provider, request, stream);
+
+            let local_models = local_models.filter(|local_models| local_models.serves(&url));
+            if let Some(local_models) = local_models {
+                if let Err(e) = local_models.acquire(&provider_request.model).await {
+                    log_warn!(
+                        "Failover",
+                        &format!(
+                            "Fallback provider '{}' failed to load {}: {}",
+                            provider.name, provider_request.model, e
+                        )
+                    );
+                    last_error = Some(e);
+                    continue;
+                }
+            }
+
+            let mut builder = client.post(&url).json(&provider_request);
+            if let Some(api_key) = &provider.api_key {
+                builder = builder.bearer_auth(api_key);
+            }
+            if !stream {
+                builder = builder.timeout(timeout);
+            }
+
+            let error = match tokio::time::timeout(timeout, builder.send()).await {
+                Ok(Ok(response)) if response.status().is_success() => {
+                    return Ok(FallbackResponse {
+                        provider: provider.name.clone(),
+                        response,
+                    });
+                },
+                Ok(Ok(response)) => format!("returned status {}", response.status()),
+                Ok(Err(e)) => e.to_string(),
+                Err(_) => format!("did not answer within {}s", timeout.as_secs()),
+            };
+            log_warn!(
+                "Failover",
+                &format!("Fallback provider '{}' failed: {}", provider.name, error)
+            );
+            if let Some(local_models) = local_models {
+                // Not worth holding memory for
+                if let Err(e) = local_models.release(&provider_request.model).await {
+                    log_warn!(
+                        "Failover",
+                        &format!("Failed to release {}: {}", provider_request.model, e)
+                    );
+                }
+            }
+            last_error = Some(MindLinkError::Network {
+                message: format!("Fallback provider '{}' {}", provider.name, error),
+                url: Some(url),
+                source: None,
+            });
+        }
+
+        Err(last_error.unwrap_or_else(|| MindLinkError::Network {
+            message: "No fallback provider is available".to_string(),
+            url: None,
+            source: None,
+        }))
+    }
+}
+
+/// Forward the SSE events of a streamed fallback response, one event per item,
+/// until the stream ends or goes quiet for `idle_timeout`. Returns `false` once
+/// the client is gone.
+pub async fn forward_stream(
+    mut response: reqwest::Response,
+    tx: &Sender<Result<String, Infallible>>,
+    idle_timeout: Duration,
+) -> bool {
+    // Bytes, since a chunk may end inside a multi-byte character
+    let mut buffer: Vec<u8> = Vec::new();
+    while let Ok(Ok(Some(bytes))) = tokio::time::timeout(idle_timeout, response.chunk()).await {
+        buffer.extend_from_slice(&bytes);
+        while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end();
+            if line.starts_with("data:") && tx.send(Ok(format!("{}\n\n", line))).await.is_err() {
+                return false;
+            }
+        }
+    }
+    true
+}
@@ -0,0 +1,242 @@
+// Health model shared by the health monitor, the API server and the tray
+//
+// Components report one of three levels instead of a plain boolean so that
+// dependent clients can tell "tunnel down but local API fine" apart from
+// "authentication broken, nothing will work". Components also carry details
+// such as when the token expires or how many requests are queued, so external
+// monitors polling `/health` can alert on the specific failure.
+
+#![allow(static_mut_refs)]
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use ts_rs::TS;
+
+/// Health level of a single component or of the application as a whole
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthLevel {
+    Ok,
+    Degraded,
+    Down,
+}
+
+impl std::fmt::Display for HealthLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HealthLevel::Ok => write!(f, "ok"),
+            HealthLevel::Degraded => write!(f, "degraded"),
+            HealthLevel::Down => write!(f, "down"),
+        }
+    }
+}
+
+/// Health of a single component (server, tunnel, auth, ...)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+pub struct ComponentHealth {
+    pub name: String,
+    pub level: HealthLevel,
+    pub reason: Option<String>,
+    /// Critical components take the whole application down when they fail;
+    /// non-critical ones only degrade it.
+    pub critical: bool,
+    pub checked_at: DateTime<Utc>,
+    /// Component-specific state, e.g. `expires_at` for `auth`
+    #[serde(default)]
+    #[ts(type = "Record<string, unknown>")]
+    pub details: BTreeMap<String, Value>,
+}
+
+impl ComponentHealth {
+    pub fn ok(name: &str, critical: bool) -> Self {
+        Self::new(name, HealthLevel::Ok, None, critical)
+    }
+
+    pub fn degraded(name: &str, reason: impl Into<String>, critical: bool) -> Self {
+        Self::new(name, HealthLevel::Degraded, Some(reason.into()), critical)
+    }
+
+    pub fn down(name: &str, reason: impl Into<String>, critical: bool) -> Self {
+        Self::new(name, HealthLevel::Down, Some(reason.into()), critical)
+    }
+
+    fn new(name: &str, level: HealthLevel, reason: Option<String>, critical: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            level,
+            reason,
+            critical,
+            checked_at: Utc::now(),
+            details: BTreeMap::new(),
+        }
+    }
+
+    /// Add a detail; values that fail to serialize are left out
+    pub fn with_detail(mut self, key: &str, value: impl Serialize) -> Self {
+        if let Ok(value) = serde_json::to_value(value) {
+            self.details.insert(key.to_string(), value);
+        }
+        self
+    }
+
+    /// The level this component contributes to the overall health
+    fn overall_contribution(&self) -> HealthLevel {
+        match (self.level, self.critical) {
+            (HealthLevel::Down, false) => HealthLevel::Degraded,
+            (level, _) => level,
+        }
+    }
+}
+
+/// Aggregated health of all components
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+pub struct HealthReport {
+    pub overall: HealthLevel,
+    pub reason: Option<String>,
+    pub components: Vec<ComponentHealth>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Default for HealthReport {
+    fn default() -> Self {
+        Self::from_components(Vec::new())
+    }
+}
+
+impl HealthReport {
+    /// Build a report, deriving the overall level from the components
+    pub fn from_components(components: Vec<ComponentHealth>) -> Self {
+        let overall = components
+            .iter()
+            .map(ComponentHealth::overall_contribution)
+            .max()
+            .unwrap_or(HealthLevel::Ok);
+
+        let reasons: Vec<String> = components
+            .iter()
+            .filter(|c| c.level != HealthLevel::Ok)
+            .map(|c| match &c.reason {
+                Some(reason) => format!("{} {}: {}", c.name, c.level, reason),
+                None => format!("{} {}", c.name, c.level),
+            })
+            .collect();
+
+        Self {
+            overall,
+            reason: if reasons.is_empty() {
+                None
+            } else {
+                Some(reasons.join("; "))
+            },
+            components,
+            timestamp: Utc::now(),
+        }
+    }
+
+    /// Look up a component by name
+    pub fn component(&self, name: &str) -> Option<&ComponentHealth> {
+        self.components.iter().find(|c| c.name == name)
+    }
+
+    /// Return a copy of this report with the given component inserted or replaced
+    pub fn with_component(&self, component: ComponentHealth) -> Self {
+        let mut components: Vec<ComponentHealth> = self
+            .components
+            .iter()
+            .filter(|c| c.name != component.name)
+            .cloned()
+            .collect();
+        components.push(component);
+        Self::from_components(components)
+    }
+
+    /// Whether any level changed compared to a previous report
+    pub fn levels_differ(&self, other: &HealthReport) -> bool {
+        if self.overall != other.overall || self.components.len() != other.components.len() {
+            return true;
+        }
+
+        self.components.iter().any(|c| {
+            other
+                .component(&c.name)
+                .map_or(true, |prev| prev.level != c.level)
+        })
+    }
+
+    /// Short human-readable summary, suitable for tray tooltips
+    pub fn summary(&self) -> String {
+        match (&self.overall, &self.reason) {
+            (HealthLevel::Ok, _) => "All systems operational".to_string(),
+            (level, Some(reason)) => format!("{}: {}", capitalize(&level.to_string()), reason),
+            (level, None) => capitalize(&level.to_string()),
+        }
+    }
+}
+
+/// Health of the completion queue. At `max_in_flight` further requests are
+/// turned away with 429.
+pub fn queue_health(
+    in_flight: usize,
+    active_streams: i64,
+    queued_jobs: usize,
+    max_in_flight: Option<usize>,
+) -> ComponentHealth {
+    let health = match max_in_flight {
+        Some(max) if in_flight >= max => ComponentHealth::degraded(
+            "queue",
+            format!("{} requests in flight, new ones are turned away", in_flight),
+            false,
+        ),
+        _ => ComponentHealth::ok("queue", false),
+    };
+    health
+        .with_detail("in_flight", in_flight)
+        .with_detail("max_in_flight", max_in_flight)
+        .with_detail("active_streams", active_streams)
+        .with_detail("queued_jobs", queued_jobs)
+}
+
+fn capitalize(value: &str) -> String {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+        None => String::new(),
+    }
+}
+
+/// Global store for the most recent health report
+static mut HEALTH_REPORT: Option<Arc<RwLock<HealthReport>>> = None;
+static HEALTH_REPORT_INIT: std::sync::Once = std::sync::Once::new();
+
+/// Get the shared health report store, creating it on first use
+pub fn health_store() -> Arc<RwLock<HealthReport>> {
+    HEALTH_REPORT_INIT.call_once(|| {
+        #[allow(unsafe_code)]
+        unsafe {
+            HEALTH_REPORT = Some(Arc::new(RwLock::new(HealthReport::default())));
+        }
+    });
+
+    #[allow(unsafe_code)]
+    unsafe {
+        HEALTH_REPORT
+            .as_ref()
+            .expect("HealthReport store should be initialized by call_once")
+            .clone()
+    }
+}
+
+/// Get a snapshot of the latest health report
+pub async fn current_health() -> HealthReport {
+    health_store().read().await.clone()
+}
+
+/// Replace the latest health report, returning the previous one
+pub async fn publish_health(report: HealthReport) -> HealthReport {
+    let store = health_store();
+    let mut guard = store.write().await;
+    std::mem::replace(&mut *guard, report)
+}
@@ -0,0 +1,243 @@
+// Long-running chat completion jobs
+//
+// A generation that takes ten minutes rarely survives an HTTP connection held
+// open through a tunnel. `POST /v1/jobs` accepts a chat completion request and
+// answers at once with a job ID; the request then runs in the background, at
+// most `jobs.max_concurrent` at a time, through the regular chat completion
+// handler. Clients poll `GET /v1/jobs/{id}` or follow its progress as
+// server-sent events on `GET /v1/jobs/{id}/events`.
+//
+// Jobs are kept in memory only: finished jobs are dropped after
+// `jobs.retention_secs`, and every job is lost when the server stops.
+
+use crate::managers::config_manager::JobConfig;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, watch, Semaphore};
+use tokio::task::AbortHandle;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for a free slot
+    Queued,
+    InProgress,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            JobStatus::Completed | JobStatus::Failed | JobStatus::Cancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: String,
+    pub object: String,
+    pub status: JobStatus,
+    pub model: String,
+    pub created_at: i64,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+    /// HTTP status the chat completion ended with
+    pub status_code: Option<u16>,
+    /// The `chat.completion` of a completed job
+    pub result: Option<Value>,
+    /// The error body of a failed job
+    pub error: Option<Value>,
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+#[derive(Debug)]
+struct JobEntry {
+    updates: watch::Sender<Job>,
+    task: Option<AbortHandle>,
+}
+
+/// The jobs of a running server
+#[derive(Debug)]
+pub struct JobStore {
+    jobs: Mutex<HashMap<String, JobEntry>>,
+    slots: Arc<Semaphore>,
+    retention_secs: i64,
+}
+
+impl JobStore {
+    pub fn new(config: &JobConfig) -> Self {
+        Self {
+            jobs: Mutex::new(HashMap::new()),
+            slots: Arc::new(Semaphore::new(config.max_concurrent.max(1))),
+            retention_secs: i64::try_from(config.retention_secs).unwrap_or(i64::MAX),
+        }
+    }
+
+    fn jobs(&self) -> std::sync::MutexGuard<'_, HashMap<String, JobEntry>> {
+        self.jobs.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Queue `execute`, which returns the HTTP status and JSON body of the
+    /// chat completion, and return the new job
+    pub fn submit<Fut>(self: &Arc<Self>, model: &str, execute: Fut) -> Job
+    where
+        Fut: Future<Output = (u16, Value)> + Send + 'static,
+    {
+        self.prune();
+
+        let job = Job {
+            id: format!("job_{}", Uuid::new_v4().simple()),
+            object: "job".to_string(),
+            status: JobStatus::Queued,
+            model: model.to_string(),
+            created_at: now(),
+            started_at: None,
+            finished_at: None,
+            status_code: None,
+            result: None,
+            error: None,
+        };
+        let (updates, _) = watch::channel(job.clone());
+        self.jobs().insert(
+            job.id.clone(),
+            JobEntry {
+                updates,
+                task: None,
+            },
+        );
+
+        let store = self.clone();
+        let id = job.id.clone();
+        let task = tokio::spawn(async move {
+            let Ok(_slot) = store.slots.clone().acquire_owned().await else {
+                return;
+            };
+            store.update(&id, |job| {
+                job.status = JobStatus::InProgress;
+                job.started_at = Some(now());
+            });
+
+            let (status, body) = execute.await;
+            store.update(&id, |job| {
+                let succeeded = (200..300).contains(&status);
+                job.status = if succeeded {
+                    JobStatus::Completed
+                } else {
+                    JobStatus::Failed
+                };
+                job.finished_at = Some(now());
+                job.status_code = Some(status);
+                if succeeded {
+                    job.result = Some(body);
+                } else {
+                    job.error = Some(body);
+                }
+            });
+        });
+
+        if let Some(entry) = self.jobs().get_mut(&job.id) {
+            entry.task = Some(task.abort_handle());
+        }
+        job
+    }
+
+    pub fn job(&self, id: &str) -> Option<Job> {
+        self.jobs()
+            .get(id)
+            .map(|entry| entry.updates.borrow().clone())
+    }
+
+    /// Jobs waiting for a free slot
+    pub fn queued(&self) -> usize {
+        self.jobs()
+            .values()
+            .filter(|entry| entry.updates.borrow().status == JobStatus::Queued)
+            .count()
+    }
+
+    /// Follow the changes of a job
+    pub fn subscribe(&self, id: &str) -> Option<watch::Receiver<Job>> {
+        self.jobs().get(id).map(|entry| entry.updates.subscribe())
+    }
+
+    /// Stop a job that has not finished yet. Returns `None` for unknown jobs.
+    pub fn cancel(&self, id: &str) -> Option<Job> {
+        let task = self.jobs().get_mut(id)?.task.take();
+        let cancelled = self.update(id, |job| {
+            if !job.status.is_terminal() {
+                job.status = JobStatus::Cancelled;
+                job.finished_at = Some(now());
+            }
+        });
+        if let Some(task) = task {
+            task.abort();
+        }
+        cancelled
+    }
+
+    /// Apply `change` unless the job already finished. Returns the job.
+    fn update(&self, id: &str, change: impl FnOnce(&mut Job)) -> Option<Job> {
+        let jobs = self.jobs();
+        let entry = jobs.get(id)?;
+        entry.updates.send_if_modified(|job| {
+            if job.status.is_terminal() {
+                return false;
+            }
+            change(job);
+            true
+        });
+        let job = entry.updates.borrow().clone();
+        Some(job)
+    }
+
+    /// Forget jobs that finished longer than the retention period ago
+    fn prune(&self) {
+        let cutoff = now().saturating_sub(self.retention_secs);
+        self.jobs().retain(|_, entry| {
+            entry
+                .updates
+                .borrow()
+                .finished_at
+                .map_or(true, |finished| finished >= cutoff)
+        });
+    }
+}
+
+/// SSE events for `updates`: a `status` event for the current state and each
+/// change, then a `done` event once the job has finished
+pub fn events(mut updates: watch::Receiver<Job>) -> mpsc::Receiver<Result<String, Infallible>> {
+    let (tx, rx) = mpsc::channel(16);
+    tokio::spawn(async move {
+        loop {
+            let job = updates.borrow_and_update().clone();
+            let event = if job.status.is_terminal() {
+                "done"
+            } else {
+                "status"
+            };
+            let data = serde_json::to_string(&job).unwrap_or_default();
+            if tx
+                .send(Ok(format!("event: {}\ndata: {}\n\n", event, data)))
+                .await
+                .is_err()
+                || job.status.is_terminal()
+                || updates.changed().await.is_err()
+            {
+                return;
+            }
+        }
+    });
+    rx
+}
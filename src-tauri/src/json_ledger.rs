@@ -0,0 +1,150 @@
+// Counters persisted as JSON files
+//
+// Token budgets and tunnel egress are counted as requests are served and kept
+// in `~/.mindlink` so that restarting MindLink does not lift their caps. They
+// are saved after every request, so a save is written to a temporary file and
+// renamed over the ledger: a crash or kill in the middle of a save leaves the
+// previous copy in place instead of a truncated one.
+//
+// A ledger whose file exists but cannot be read is not taken for zero usage.
+// It fails closed: its owner treats the counts as used up, and nothing is
+// saved over the file until it is repaired or removed. The file is read again
+// whenever the ledger is used, so a repair counts without a restart.
+
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::{log_error, log_info};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Default location of the ledger `file`: `~/.mindlink/<file>`
+pub fn default_path(file: &str) -> MindLinkResult<PathBuf> {
+    dirs::home_dir()
+        .map(|home| home.join(".mindlink").join(file))
+        .ok_or_else(|| MindLinkError::SystemResource {
+            message: "Cannot determine home directory".to_string(),
+            resource_type: "home directory".to_string(),
+            source: None,
+        })
+}
+
+/// A value saved as JSON at `path`. Without a path nothing is persisted.
+#[derive(Debug, Default)]
+pub struct JsonLedger<T> {
+    path: Option<PathBuf>,
+    value: Mutex<T>,
+    unreadable: AtomicBool,
+}
+
+/// The ledger saved at `path`, `None` when there is none
+fn read<T: DeserializeOwned>(path: &Path) -> std::io::Result<Option<T>> {
+    match std::fs::read_to_string(path) {
+        Ok(json) => Ok(Some(serde_json::from_str(&json)?)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+impl<T: Default + Serialize + DeserializeOwned> JsonLedger<T> {
+    /// The ledger saved at `path`; empty when there is none yet
+    pub fn open(path: &Path) -> Self {
+        let (value, unreadable) = match read(path) {
+            Ok(value) => (value.unwrap_or_default(), false),
+            Err(e) => {
+                log_error!(
+                    "JsonLedger",
+                    MindLinkError::FileSystem {
+                        message: format!(
+                            "{} cannot be read; its limits count as used up until it is \
+                             repaired or removed",
+                            path.display()
+                        ),
+                        path: Some(path.to_string_lossy().to_string()),
+                        operation: "read".to_string(),
+                        source: Some(e.into()),
+                    }
+                );
+                (T::default(), true)
+            },
+        };
+        Self {
+            path: Some(path.to_path_buf()),
+            value: Mutex::new(value),
+            unreadable: AtomicBool::new(unreadable),
+        }
+    }
+
+    /// Whether the saved ledger could not be read, in which case its owner
+    /// fails closed
+    pub fn is_unreadable(&self) -> bool {
+        self.unreadable.load(Ordering::Acquire)
+    }
+
+    /// The value, taken from the file again while it could not be read so a
+    /// repaired or removed file counts without a restart
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        let mut value = self.value.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(path) = self.path.as_deref().filter(|_| self.is_unreadable()) {
+            if let Ok(saved) = read(path) {
+                *value = saved.unwrap_or_default();
+                self.unreadable.store(false, Ordering::Release);
+                log_info!(
+                    "JsonLedger",
+                    &format!("{} can be read again", path.display())
+                );
+            }
+        }
+        value
+    }
+
+    /// Write the ledger to its file. Blocks, so async callers go through
+    /// [`JsonLedger::save_in_background`].
+    pub fn save(&self) -> MindLinkResult<()> {
+        let Some(path) = self.path.as_deref().filter(|_| !self.is_unreadable()) else {
+            return Ok(());
+        };
+        let fs_error = |operation: &str, e: std::io::Error| MindLinkError::FileSystem {
+            message: format!("Failed to save {}", path.display()),
+            path: Some(path.to_string_lossy().to_string()),
+            operation: operation.to_string(),
+            source: Some(e.into()),
+        };
+
+        // Written under the lock, so a later snapshot is never overwritten by
+        // an earlier one
+        let value = self.lock();
+        let json =
+            serde_json::to_string_pretty(&*value).map_err(|e| fs_error("serialize", e.into()))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| fs_error("create", e))?;
+        }
+
+        let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+        temp_name.push(".tmp");
+        let temp_path = path.with_file_name(temp_name);
+        let mut file = std::fs::File::create(&temp_path).map_err(|e| fs_error("create", e))?;
+        file.write_all(json.as_bytes())
+            .and_then(|()| file.sync_all())
+            .map_err(|e| fs_error("write", e))?;
+        std::fs::rename(&temp_path, path).map_err(|e| fs_error("rename", e))
+    }
+}
+
+impl<T: Default + Serialize + DeserializeOwned + Send + 'static> JsonLedger<T> {
+    /// Save the ledger on the blocking pool. Outside a runtime nothing is
+    /// saved.
+    pub fn save_in_background(self: &Arc<Self>) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let ledger = self.clone();
+        runtime.spawn_blocking(move || {
+            if let Err(e) = ledger.save() {
+                log_error!("JsonLedger", e);
+            }
+        });
+    }
+}
@@ -0,0 +1,57 @@
+// Prompt language detection
+//
+// Classifies the latest user message so multilingual traffic can be traced in
+// the analytics journal and, when configured, routed to a model that handles
+// the language well.
+
+use crate::managers::config_manager::LanguageDetectionConfig;
+use crate::managers::server_manager::Message;
+use serde::{Deserialize, Serialize};
+
+/// Language detected for a request's prompt. Also attached to the response as
+/// an extension so the analytics layer can record it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DetectedLanguage {
+    /// ISO 639-3 code, e.g. `jpn`
+    pub code: String,
+    /// English name, e.g. `Japanese`
+    pub name: String,
+    pub confidence: f64,
+    pub reliable: bool,
+}
+
+/// Text of the most recent user message, which is what the client is asking in
+pub fn prompt_text(messages: &[Message]) -> Option<&str> {
+    messages
+        .iter()
+        .rev()
+        .find(|m| m.role == "user" && !m.content.trim().is_empty())
+        .map(|m| m.content.as_str())
+}
+
+/// Detect the language of `text`; short texts are too ambiguous to classify
+pub fn detect_language(text: &str, min_chars: usize) -> Option<DetectedLanguage> {
+    if text.trim().chars().count() < min_chars {
+        return None;
+    }
+
+    let info = whatlang::detect(text)?;
+    Some(DetectedLanguage {
+        code: info.lang().code().to_string(),
+        name: info.lang().eng_name().to_string(),
+        confidence: info.confidence(),
+        reliable: info.is_reliable(),
+    })
+}
+
+/// Model configured for the detected language, if the detection is confident enough
+pub fn route_for_language<'a>(
+    config: &'a LanguageDetectionConfig,
+    detected: &DetectedLanguage,
+) -> Option<&'a str> {
+    if !detected.reliable || detected.confidence < config.min_confidence {
+        return None;
+    }
+
+    config.routes.get(&detected.code).map(String::as_str)
+}
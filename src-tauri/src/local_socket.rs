@@ -0,0 +1,190 @@
+// Local socket listener of the API server
+//
+// Besides its TCP port, the server can listen on a Unix domain socket, or a
+// named pipe on Windows, set as `server.socket_path`. Tools on this machine
+// reach the API there without going through a port, e.g.
+// `curl --unix-socket ~/.mindlink/api.sock http://localhost/v1/models`.
+// Requests through the socket pass the same middleware as those on the port,
+// and the access policy sees them as coming from loopback. The socket speaks
+// plain HTTP, also when the port is served over TLS.
+//
+// A Unix socket is only accessible to the user running MindLink. A socket
+// file left behind by a run that did not stop cleanly is replaced, one that
+// another server still answers on is not. Named pipes refuse remote clients.
+
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::{log_debug, log_info, log_warn};
+use axum::{extract::ConnectInfo, Extension, Router};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto::Builder;
+use hyper_util::server::graceful::GracefulShutdown;
+use hyper_util::service::TowerToHyperService;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+/// Peer address of connections through the socket
+pub const LOCAL_PEER: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
+
+#[cfg(unix)]
+type Connection = tokio::net::UnixStream;
+#[cfg(windows)]
+type Connection = tokio::net::windows::named_pipe::NamedPipeServer;
+
+/// A bound local socket, ready to serve
+pub struct LocalSocket {
+    path: String,
+    #[cfg(unix)]
+    listener: tokio::net::UnixListener,
+    #[cfg(windows)]
+    pipe: tokio::net::windows::named_pipe::NamedPipeServer,
+}
+
+fn bind_error(path: &str, message: String, e: io::Error) -> MindLinkError {
+    MindLinkError::Network {
+        message,
+        url: Some(path.to_string()),
+        source: Some(e.into()),
+    }
+}
+
+impl LocalSocket {
+    /// Bind the Unix socket at `path`, replacing a stale socket file
+    #[cfg(unix)]
+    pub async fn bind(path: &str) -> MindLinkResult<Self> {
+        use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                return Err(MindLinkError::Configuration {
+                    message: format!("{} exists and is not a socket", path),
+                    config_key: Some("server.socket_path".to_string()),
+                    source: None,
+                });
+            }
+            if tokio::net::UnixStream::connect(path).await.is_ok() {
+                return Err(MindLinkError::Network {
+                    message: format!("Another server is listening on {}", path),
+                    url: Some(path.to_string()),
+                    source: None,
+                });
+            }
+            std::fs::remove_file(path).map_err(|e| {
+                bind_error(path, format!("Failed to remove stale socket {}", path), e)
+            })?;
+        }
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            std::fs::create_dir_all(dir).map_err(|e| {
+                bind_error(path, format!("Failed to create directory of {}", path), e)
+            })?;
+        }
+
+        let listener = tokio::net::UnixListener::bind(path)
+            .map_err(|e| bind_error(path, format!("Failed to bind to {}", path), e))?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| bind_error(path, format!("Failed to restrict access to {}", path), e))?;
+
+        Ok(Self {
+            path: path.to_string(),
+            listener,
+        })
+    }
+
+    /// Create the named pipe `path`, failing if another server has it
+    #[cfg(windows)]
+    pub async fn bind(path: &str) -> MindLinkResult<Self> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        let pipe = ServerOptions::new()
+            .first_pipe_instance(true)
+            .reject_remote_clients(true)
+            .create(path)
+            .map_err(|e| bind_error(path, format!("Failed to create pipe {}", path), e))?;
+
+        Ok(Self {
+            path: path.to_string(),
+            pipe,
+        })
+    }
+
+    #[cfg(unix)]
+    async fn accept(&mut self) -> io::Result<Connection> {
+        self.listener.accept().await.map(|(stream, _)| stream)
+    }
+
+    /// Wait for a client on the current pipe instance and open the next one
+    /// for the client after it
+    #[cfg(windows)]
+    async fn accept(&mut self) -> io::Result<Connection> {
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        self.pipe.connect().await?;
+        let next = ServerOptions::new()
+            .reject_remote_clients(true)
+            .create(&self.path)?;
+        Ok(std::mem::replace(&mut self.pipe, next))
+    }
+
+    /// Serve `app` on the socket until `shutdown` resolves, then let the open
+    /// connections finish their requests. Connections still open when the
+    /// returned future is dropped are cut off.
+    pub async fn serve<F>(mut self, app: Router, builder: Builder<TokioExecutor>, shutdown: F)
+    where
+        F: Future<Output = ()> + Send,
+    {
+        let service = TowerToHyperService::new(app.layer(Extension(ConnectInfo(LOCAL_PEER))));
+        let graceful = GracefulShutdown::new();
+        let mut connections = JoinSet::new();
+        tokio::pin!(shutdown);
+
+        log_info!(
+            "ServerManager",
+            &format!("Listening on local socket {}", self.path)
+        );
+        loop {
+            let accepted = tokio::select! {
+                accepted = self.accept() => accepted,
+                Some(_) = connections.join_next(), if !connections.is_empty() => continue,
+                _ = &mut shutdown => break,
+            };
+            match accepted {
+                Ok(stream) => {
+                    let connection = builder
+                        .serve_connection_with_upgrades(TokioIo::new(stream), service.clone())
+                        .into_owned();
+                    let connection = graceful.watch(connection);
+                    connections.spawn(async move {
+                        if let Err(e) = connection.await {
+                            log_debug!(
+                                "ServerManager",
+                                &format!("Local socket connection ended: {}", e)
+                            );
+                        }
+                    });
+                },
+                Err(e) => {
+                    log_warn!(
+                        "ServerManager",
+                        &format!("Failed to accept on local socket: {}", e)
+                    );
+                    // Don't spin while e.g. out of file descriptors
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                },
+            }
+        }
+
+        // New clients are refused while the open connections drain
+        #[cfg(unix)]
+        {
+            drop(self.listener);
+            let _ = std::fs::remove_file(&self.path);
+        }
+        #[cfg(windows)]
+        drop(self.pipe);
+
+        graceful.shutdown().await;
+        while connections.join_next().await.is_some() {}
+    }
+}
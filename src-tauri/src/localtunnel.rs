@@ -0,0 +1,114 @@
+// localtunnel client, the fallback of quick tunnels
+//
+// When cloudflared cannot start a quick tunnel, the API is served through a
+// localtunnel server instead, which needs no binary: the server leases a
+// public URL and a TCP port, and every connection MindLink opens to that port
+// carries requests for the URL. Those connections are relayed to the local
+// port, a few at a time so requests are served in parallel. A connection that
+// is used up is replaced; when the server stops accepting them, the tunnel is
+// over.
+
+use crate::proxy;
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::task::{JoinHandle, JoinSet};
+
+/// Connections kept open when the server does not say how many it takes
+const DEFAULT_CONNECTIONS: usize = 10;
+
+/// Failed connections in a row after which a relay gives up
+const MAX_CONNECT_FAILURES: u32 = 10;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+const LEASE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// What the server leased to this client
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Lease {
+    pub id: String,
+    /// Public URL of the tunnel
+    pub url: String,
+    /// Port of the server the relay connections go to
+    pub port: u16,
+    pub max_conn_count: Option<usize>,
+    /// Address to connect to instead of the server's hostname
+    pub ip: Option<String>,
+}
+
+/// Client leases are requested with, through the configured proxy
+pub fn lease_client() -> Client {
+    proxy::apply(Client::builder())
+        .timeout(LEASE_TIMEOUT)
+        .user_agent("MindLink/1.0")
+        .build()
+        .unwrap_or_default()
+}
+
+/// Lease a tunnel with a random URL from `server`
+pub async fn request_lease(client: &Client, server: &str) -> Result<Lease> {
+    let response = client
+        .get(format!("{}/?new", server.trim_end_matches('/')))
+        .send()
+        .await
+        .map_err(|e| anyhow!("localtunnel server {} is unreachable: {}", server, e))?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "localtunnel server {} refused the tunnel: {}",
+            server,
+            response.status()
+        ));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| anyhow!("localtunnel server {} sent no lease: {}", server, e))
+}
+
+/// Relay the connections of `lease` from `server` to `local_port`. The task
+/// ends once the server accepts no more connections; abort it to close the
+/// tunnel.
+pub fn serve(server: &str, lease: &Lease, local_port: u16) -> Result<JoinHandle<()>> {
+    let host = match &lease.ip {
+        Some(ip) => ip.clone(),
+        None => url::Url::parse(server)?
+            .host_str()
+            .ok_or_else(|| anyhow!("localtunnel server {} has no host", server))?
+            .to_string(),
+    };
+    let remote = (host, lease.port);
+    let connections = lease.max_conn_count.unwrap_or(DEFAULT_CONNECTIONS).max(1);
+
+    Ok(tokio::spawn(async move {
+        let mut relays = JoinSet::new();
+        for _ in 0..connections {
+            relays.spawn(relay(remote.clone(), local_port));
+        }
+        // Aborting this task drops the set, which aborts the relays
+        while relays.join_next().await.is_some() {}
+    }))
+}
+
+/// Relay one connection after the other from `remote` to the local port
+async fn relay(remote: (String, u16), local_port: u16) {
+    let mut failures = 0;
+    while failures < MAX_CONNECT_FAILURES {
+        let Ok(mut inbound) = TcpStream::connect((remote.0.as_str(), remote.1)).await else {
+            failures += 1;
+            tokio::time::sleep(RECONNECT_DELAY).await;
+            continue;
+        };
+        failures = 0;
+
+        match TcpStream::connect(("127.0.0.1", local_port)).await {
+            Ok(mut local) => {
+                let _ = tokio::io::copy_bidirectional(&mut inbound, &mut local).await;
+            },
+            // The request is dropped; the next one may find the server up
+            Err(_) => tokio::time::sleep(RECONNECT_DELAY).await,
+        }
+    }
+}
@@ -2,15 +2,40 @@
 
 #![allow(static_mut_refs)]
 use crate::error::MindLinkError;
+use crate::error_feed::ErrorFeed;
 use chrono::{DateTime, Utc};
 use log::{debug, error, info, trace, warn};
 use serde::{Deserialize, Serialize};
 use std::fs::{File, OpenOptions};
+use std::future::Future;
 use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
 
+tokio::task_local! {
+    /// Correlation ID (e.g. the API request ID) of the task currently running
+    static CORRELATION_ID: String;
+}
+
+/// Correlation ID of the current task, if it runs inside [`with_correlation_id`]
+pub fn current_correlation_id() -> Option<String> {
+    CORRELATION_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Run a future with a correlation ID attached to every log entry it creates.
+/// Spawned tasks don't inherit it, so capture [`current_correlation_id`] and
+/// pass it along when spawning.
+pub async fn with_correlation_id<F: Future>(
+    correlation_id: Option<String>,
+    future: F,
+) -> F::Output {
+    match correlation_id {
+        Some(id) => CORRELATION_ID.scope(id, future).await,
+        None => future.await,
+    }
+}
+
 /// Log levels for the application
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum LogLevel {
@@ -84,7 +109,7 @@ impl LogEntry {
             component: None,
             message,
             details: None,
-            correlation_id: None,
+            correlation_id: current_correlation_id(),
         }
     }
 
@@ -113,7 +138,7 @@ impl LogEntry {
         };
 
         let correlation_str = match &self.correlation_id {
-            Some(id) => format!(" [{}]", &id[..8]), // Short correlation ID
+            Some(id) => format!(" [{}]", id),
             None => String::new(),
         };
 
@@ -166,6 +191,7 @@ pub struct LogManager {
     max_file_size: u64,
     max_files: usize,
     console_enabled: bool,
+    error_feed: ErrorFeed,
 }
 
 impl LogManager {
@@ -202,6 +228,7 @@ impl LogManager {
             max_file_size: 10 * 1024 * 1024, // 10MB
             max_files: 5,
             console_enabled: true,
+            error_feed: ErrorFeed::default(),
         })
     }
 
@@ -373,6 +400,17 @@ impl LogManager {
         &self.log_file_path
     }
 
+    /// Write out everything still buffered for the log file
+    pub fn flush(&self) -> Result<(), MindLinkError> {
+        let mut writer = self.file_writer.lock().unwrap_or_else(|e| e.into_inner());
+        writer.flush().map_err(|e| MindLinkError::FileSystem {
+            message: "Failed to flush log buffer".to_string(),
+            path: Some(self.log_file_path.to_string_lossy().to_string()),
+            operation: "flush".to_string(),
+            source: Some(e.into()),
+        })
+    }
+
     /// Log an error with full details
     pub fn log_error(&self, component: &str, error: &MindLinkError, correlation_id: Option<&str>) {
         let mut entry = LogEntry::new(LogLevel::Error, LogCategory::Error, error.user_message())
@@ -383,28 +421,22 @@ impl LogManager {
         }
 
         // Add technical details
-        let error_type = match error {
-            crate::error::MindLinkError::Authentication { .. } => "Authentication",
-            crate::error::MindLinkError::Network { .. } => "Network",
-            crate::error::MindLinkError::BinaryExecution { .. } => "BinaryExecution",
-            crate::error::MindLinkError::Configuration { .. } => "Configuration",
-            crate::error::MindLinkError::FileSystem { .. } => "FileSystem",
-            crate::error::MindLinkError::ProcessMonitoring { .. } => "ProcessMonitoring",
-            crate::error::MindLinkError::HealthCheck { .. } => "HealthCheck",
-            crate::error::MindLinkError::Tunnel { .. } => "Tunnel",
-            crate::error::MindLinkError::SystemResource { .. } => "SystemResource",
-            crate::error::MindLinkError::Internal { .. } => "Internal",
-        };
-
         let details = serde_json::json!({
-            "error_type": error_type,
+            "error_type": error.kind(),
             "technical_details": error.technical_details(),
             "recoverable": error.is_recoverable(),
             "suggested_action": error.suggested_action(),
+            "recovery_action": error.recovery_action(),
         });
         entry = entry.with_details(&details);
 
         self.log(entry);
+        self.error_feed.record(component, error, correlation_id);
+    }
+
+    /// Errors logged so far, for the UI
+    pub fn error_feed(&self) -> &ErrorFeed {
+        &self.error_feed
     }
 
     /// Log process stdout/stderr output
@@ -43,45 +43,118 @@ use tauri::{
     image::Image,
     menu::{MenuBuilder, MenuEvent, MenuItemBuilder},
     tray::{TrayIconBuilder, TrayIconEvent},
-    AppHandle, Emitter, Manager, WebviewUrl, WebviewWindowBuilder,
+    AppHandle, Manager, RunEvent, WebviewUrl, WebviewWindowBuilder,
 };
 // Shell functionality now handled by tauri-plugin-opener
 use std::sync::Arc;
 use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_notification::NotificationExt;
 use tokio::sync::RwLock;
 // Path utilities will be needed later for tray icons
 
+mod accounts;
+mod analytics;
+mod anthropic;
+mod api_error;
+mod auth_probe;
+mod authorized_apps;
+mod batches;
+mod binary_drift;
+mod budgets;
+mod bundles;
+mod canary;
+mod capture;
+mod cloudflare_access;
+mod cloudflared_release;
 mod command_helpers;
 mod commands;
+mod config_dry_run;
+mod conversations;
+mod device_auth;
 mod dialog;
+mod downloads;
 mod error;
+mod error_feed;
 mod error_reporter;
+mod events;
+mod failover;
+mod health;
+mod jobs;
+mod json_ledger;
+mod language;
+mod local_socket;
+mod localtunnel;
 mod logging;
 mod managers;
+mod middleware;
+mod model_catalog;
+mod moderation;
+mod ollama;
+mod openapi;
+mod pairing_qr;
+mod ping;
+mod plan;
+mod playground;
+mod post_processing;
+mod power;
 mod process_monitor;
+mod prompt_templates;
+mod proxy;
+mod reasoning;
+mod redaction;
+mod request_log;
+mod request_transforms;
+mod secret_box;
+mod security_report;
+mod self_healing;
+mod session_expiry;
+mod shadow;
+mod shutdown;
+mod startup_summary;
+mod stream_continuation;
+mod token_store;
+mod tool_emulation;
+mod tunnel_limits;
+mod tunnel_pids;
+mod tunnel_schedule;
+mod tunnel_tokens;
+mod tunnel_webhooks;
+mod websocket;
 // mod tray_manager; // Temporarily disabled for step-by-step implementation
 
 #[cfg(test)]
 mod tests;
 
-use error::{MindLinkError, MindLinkResult};
+use error::{MindLinkError, MindLinkResult, RecoveryAction};
 use error_reporter::{init_error_reporter, ErrorReportingConfig};
+use events::{AppEvent, Notification, NotificationKind};
+use health::{ComponentHealth, HealthLevel, HealthReport};
 use logging::{get_logger, init_logging, LogCategory, LogEntry, LogLevel};
+use power::PowerStatus;
 use process_monitor::init_process_monitor;
+use self_healing::{health_check_actions, SelfHealingPolicy, HEALTH_ERROR_PREFIX};
+use shutdown::{ShutdownStep, StepOutcome};
+use startup_summary::StartupSummary;
+use tunnel_tokens::TunnelTokens;
 
 use managers::{
     auth_manager::AuthManager, bifrost_manager::BifrostManager, binary_manager::BinaryManager,
     config_manager::ConfigManager, dashboard_manager::DashboardManager,
-    server_manager::ServerManager, tunnel_manager::TunnelManager,
+    local_model_manager::LocalModelManager, plugin_manager::PluginManager,
+    server_manager::ServerManager,
+    tunnel_manager::{PublicCheck, ScheduleChange, TunnelManager},
 };
 
 /// Application states for tray icon management
-#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, ts_rs::TS)]
 pub enum TrayState {
     Disconnected,
     Connecting,
     Connected,
+    Degraded,
     Error,
+    /// The ChatGPT session ended, so the user has to log in again
+    ReauthRequired,
 }
 
 impl TrayState {
@@ -91,7 +164,8 @@ impl TrayState {
             TrayState::Disconnected => "icon-disconnected.png",
             TrayState::Connecting => "icon-connecting.png",
             TrayState::Connected => "icon-connected.png",
-            TrayState::Error => "icon-error.png",
+            TrayState::Degraded => "icon-connecting.png",
+            TrayState::Error | TrayState::ReauthRequired => "icon-error.png",
         }
     }
 
@@ -101,30 +175,70 @@ impl TrayState {
             TrayState::Disconnected => "MindLink - Disconnected",
             TrayState::Connecting => "MindLink - Connecting...",
             TrayState::Connected => "MindLink - Connected",
+            TrayState::Degraded => "MindLink - Degraded",
             TrayState::Error => "MindLink - Error",
+            TrayState::ReauthRequired => "MindLink - Log in again",
+        }
+    }
+
+    /// Get the tooltip text including the reasons from the latest health report
+    fn tooltip_with_health(&self, report: &HealthReport) -> String {
+        match self {
+            TrayState::Degraded | TrayState::Error if report.overall != HealthLevel::Ok => {
+                format!("{}\n{}", self.tooltip_text(), report.summary())
+            },
+            _ => self.tooltip_text().to_string(),
         }
     }
 }
 
+/// Identifier of the system tray icon
+const TRAY_ID: &str = "mindlink-tray";
+
 /// Determine the appropriate tray state based on application state
 async fn determine_tray_state(app_state: &AppState) -> TrayState {
     let is_serving = *app_state.is_serving.read().await;
-    let has_error = app_state.last_error.read().await.is_some();
+    let has_error = app_state
+        .last_error
+        .read()
+        .await
+        .as_deref()
+        .is_some_and(|e| !e.starts_with(HEALTH_ERROR_PREFIX));
 
     if has_error {
         return TrayState::Error;
     }
 
+    let reauth_required = app_state
+        .auth_manager
+        .read()
+        .await
+        .reauth_required()
+        .is_some();
+    if reauth_required {
+        return TrayState::ReauthRequired;
+    }
+
     if is_serving {
+        match health::current_health().await.overall {
+            HealthLevel::Down => return TrayState::Error,
+            HealthLevel::Degraded => return TrayState::Degraded,
+            HealthLevel::Ok => {},
+        }
+
         // Check if services are actually healthy
         let server_healthy = {
             let server_manager = app_state.server_manager.read().await;
             server_manager.is_running().await
         };
 
+        // A tunnel the schedule keeps closed is as intended
         let tunnel_healthy = {
             let tunnel_manager = app_state.tunnel_manager.read().await;
             tunnel_manager.is_connected().await
+                || tunnel_manager
+                    .closed_by_schedule(chrono::Local::now().naive_local())
+                    .await
         };
 
         if server_healthy && tunnel_healthy {
@@ -140,6 +254,19 @@ async fn determine_tray_state(app_state: &AppState) -> TrayState {
 /// Update tray menu items based on current application state
 async fn update_tray_menu_for_state(app_handle: &AppHandle, app_state: &AppState) {
     let current_state = determine_tray_state(app_state).await;
+
+    // Reasons can change without the state changing, so always refresh the tooltip
+    if let Some(tray) = app_handle.tray_by_id(TRAY_ID) {
+        let mut tooltip = current_state.tooltip_with_health(&health::current_health().await);
+        let now = chrono::Local::now().naive_local();
+        if let Some(status) = app_state.tunnel_manager.read().await.schedule_status(now) {
+            tooltip = format!("{}\n{}", tooltip, status.describe(now));
+        }
+        if let Err(e) = tray.set_tooltip(Some(tooltip)) {
+            eprintln!("Failed to update tray tooltip: {}", e);
+        }
+    }
+
     let mut stored_state = app_state.current_tray_state.write().await;
 
     if *stored_state != current_state {
@@ -150,9 +277,10 @@ async fn update_tray_menu_for_state(app_handle: &AppHandle, app_state: &AppState
         *stored_state = current_state.clone();
 
         // Emit event to frontend that tray state changed
-        if let Err(e) = app_handle.emit("tray-state-changed", &current_state) {
-            eprintln!("Failed to emit tray state change: {}", e);
-        }
+        events::emit(
+            app_handle,
+            AppEvent::TrayStateChanged(current_state.clone()),
+        );
 
         // For now, we'll log the state change. In a full implementation,
         // we would update the actual tray icon and menu here.
@@ -201,6 +329,10 @@ pub struct AppState {
     /// token exchange, automatic refresh, and secure credential storage.
     pub auth_manager: Arc<RwLock<AuthManager>>,
 
+    /// Claude account serving `claude-*` models when the `anthropic` config
+    /// section enables them.
+    pub anthropic_auth: Arc<RwLock<AuthManager>>,
+
     /// HTTP server providing OpenAI-compatible API endpoints.
     ///
     /// Runs an async web server (Axum) that translates OpenAI API requests
@@ -238,6 +370,30 @@ pub struct AppState {
     /// for tunnel creation and other system functionality.
     pub binary_manager: Arc<RwLock<BinaryManager>>,
 
+    /// Warm pool of loaded models on local backends (Ollama).
+    ///
+    /// Keeps up to a configured number of models loaded, evicting the least
+    /// recently used one, so switching models avoids a full reload.
+    pub local_model_manager: Arc<RwLock<LocalModelManager>>,
+
+    /// Provider plugin discovery from the plugins directory.
+    ///
+    /// Validates each plugin's manifest and caches the resulting registry
+    /// until the frontend asks for a refresh.
+    pub plugin_manager: Arc<RwLock<PluginManager>>,
+
+    /// Restart decisions and budgets for the health monitor.
+    ///
+    /// Tracks recent restarts per component so that a service which keeps
+    /// failing is left alone instead of being restarted on every check.
+    pub healing_policy: Arc<RwLock<SelfHealingPolicy>>,
+
+    /// Power source and whether the battery power saver is active.
+    ///
+    /// Read by the health monitor to pick its interval and to tell a Bifrost
+    /// stopped to save power apart from one that crashed.
+    pub power_status: Arc<RwLock<PowerStatus>>,
+
     /// Current API service status flag.
     ///
     /// Indicates whether the main API service is running and accepting requests.
@@ -256,13 +412,45 @@ pub struct AppState {
     /// Cached authentication status to avoid expensive cloudflared calls
     /// Format: (is_authenticated, last_check_time)
     pub auth_cache: Arc<RwLock<Option<(bool, std::time::Instant)>>>,
+
+    /// Latest result of the periodic ChatGPT session probe.
+    ///
+    /// Written by the probe task only, so the serving path never waits on it.
+    /// Read by the health check, which ignores results for an older token.
+    pub auth_probe: Arc<RwLock<Option<auth_probe::ProbeResult>>>,
+
+    /// Why the user has to log in again soon, if they do.
+    ///
+    /// Kept by the session expiry monitor and shown by the health check.
+    pub session_warning: Arc<RwLock<Option<session_expiry::SessionWarning>>>,
+
+    /// Tokens of clients paired through the tunnel, rotated ahead of expiry.
+    pub tunnel_tokens: Arc<RwLock<TunnelTokens>>,
 }
 
 impl AppState {
     /// Create new application state with all managers initialized
     pub async fn new() -> MindLinkResult<Self> {
         let config_manager = Arc::new(RwLock::new(ConfigManager::new().await?));
-        let auth_manager = Arc::new(RwLock::new(AuthManager::new().await?));
+
+        proxy::configure(config_manager.read().await.get_proxy_config().await);
+
+        let mut auth_config = config_manager.read().await.get_auth_config().await;
+        // `--device-code` signs in without a browser regardless of the config
+        if std::env::args().any(|arg| arg == "--device-code") {
+            auth_config.login_flow = managers::config_manager::LoginFlow::DeviceCode;
+        }
+        let auth_manager = Arc::new(RwLock::new(
+            AuthManager::new().await?.with_config(auth_config),
+        ));
+        let anthropic_config = config_manager.read().await.get_anthropic_config().await;
+        let anthropic_auth = Arc::new(RwLock::new(
+            AuthManager::open_anthropic(
+                managers::auth_manager::anthropic_auth_path()?,
+                anthropic_config.auth_config(),
+            )
+            .await?,
+        ));
         let server_manager = Arc::new(RwLock::new(ServerManager::new().await));
 
         let tunnel_manager = Arc::new(RwLock::new(TunnelManager::new().await.map_err(|e| {
@@ -272,34 +460,124 @@ impl AppState {
                 source: Some(e.into()),
             }
         })?));
-
-        let binary_manager = Arc::new(RwLock::new(BinaryManager::new().await.map_err(|e| {
-            MindLinkError::Internal {
-                message: "Failed to initialize binary manager".to_string(),
-                component: Some("AppState".to_string()),
-                source: Some(e.into()),
+        // Tunnel processes a crash left running would hold on to the tunnels
+        // about to be started
+        if let Some(dir) = tunnel_pids::pid_files_dir() {
+            for pid in tunnel_pids::reap_orphans(&dir).await {
+                crate::log_warn!(
+                    "AppState",
+                    &format!("Stopped orphaned tunnel process {}", pid)
+                );
             }
-        })?));
+        }
+
+        // Keys of authorized apps saved in plaintext by earlier versions
+        let encrypted = match commands::settings_path() {
+            Ok(path) => commands::encrypt_app_keys(&path).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = encrypted {
+            crate::log_warn!("AppState", &format!("Failed to migrate the settings: {}", e));
+        }
+
+        // Requests through the tunnel are told apart by its current hostname
+        {
+            let tunnel_manager = tunnel_manager.read().await;
+            server_manager.write().await.follow_tunnel(
+                tunnel_manager.url_handle(),
+                tunnel_manager.cloudflared_url_handle(),
+            );
+        }
+
+        let mut binary_manager =
+            BinaryManager::new()
+                .await
+                .map_err(|e| MindLinkError::Internal {
+                    message: "Failed to initialize binary manager".to_string(),
+                    component: Some("AppState".to_string()),
+                    source: Some(e.into()),
+                })?;
+        let cloudflared_updates = config_manager
+            .read()
+            .await
+            .get_tunnel_config()
+            .await
+            .cloudflared_updates;
+        binary_manager.pin_cloudflared(cloudflared_updates.pinned_version);
+        let binary_manager = Arc::new(RwLock::new(binary_manager));
 
         let bifrost_manager = Arc::new(RwLock::new(BifrostManager::new().await));
         let dashboard_manager = Arc::new(RwLock::new(DashboardManager::new().await));
 
+        let local_models_config = config_manager.read().await.get_local_models_config().await;
+        let local_model_manager =
+            Arc::new(RwLock::new(LocalModelManager::new(&local_models_config)));
+        // Fallback providers served by Ollama go through its warm pool
+        server_manager
+            .write()
+            .await
+            .use_local_models(local_model_manager.clone());
+
+        let plugin_manager = Arc::new(RwLock::new(PluginManager::new(
+            PluginManager::default_plugins_dir()?,
+        )));
+
+        // Unreadable tunnel tokens are replaced; paired clients pair again
+        let tunnel_tokens = match tunnel_tokens::tunnel_tokens_path() {
+            Some(path) => TunnelTokens::load(&path).await.unwrap_or_else(|e| {
+                crate::log_warn!("AppState", &format!("Discarding the tunnel tokens: {}", e));
+                TunnelTokens::default()
+            }),
+            None => TunnelTokens::default(),
+        };
+        let tunnel_tokens = Arc::new(RwLock::new(tunnel_tokens));
+        // Clients through the tunnel are let in with them
+        server_manager
+            .write()
+            .await
+            .accept_tunnel_tokens(tunnel_tokens.clone());
+
         Ok(Self {
             auth_manager,
+            anthropic_auth,
             server_manager,
             tunnel_manager,
             config_manager,
             bifrost_manager,
             dashboard_manager,
             binary_manager,
+            local_model_manager,
+            plugin_manager,
+            healing_policy: Arc::new(RwLock::new(SelfHealingPolicy::default())),
+            power_status: Arc::new(RwLock::new(PowerStatus::default())),
             is_serving: Arc::new(RwLock::new(false)),
             last_error: Arc::new(RwLock::new(None)),
             current_tray_state: Arc::new(RwLock::new(TrayState::Disconnected)),
             auth_cache: Arc::new(RwLock::new(None)),
+            auth_probe: Arc::new(RwLock::new(None)),
+            session_warning: Arc::new(RwLock::new(None)),
+            tunnel_tokens,
         })
     }
 }
 
+/// Log the effective configuration as one structured entry, secrets redacted
+async fn log_startup_summary(state: &AppState) {
+    let config = state.config_manager.read().await.get_config().await;
+    let authenticated = state.auth_manager.read().await.is_authenticated().await;
+
+    if let Some(logger) = get_logger() {
+        let entry = LogEntry::new(
+            LogLevel::Info,
+            LogCategory::System,
+            "Effective configuration".to_string(),
+        )
+        .with_component("Main")
+        .with_details(&StartupSummary::new(&config, authenticated));
+        logger.log(entry);
+    }
+}
+
 /// Application entry point and Tauri runtime initialization.
 ///
 /// Initializes the complete MindLink application including:
@@ -378,12 +656,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             return Err(e.into());
         },
     };
+    log_startup_summary(&app_state).await;
 
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
         .manage(app_state)
         .setup(move |app| {
             // Create system tray menu
@@ -427,7 +707,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .item(&quit)
                 .build()?;
 
-            let _tray = TrayIconBuilder::new()
+            let _tray = TrayIconBuilder::with_id(TRAY_ID)
                 .menu(&tray_menu)
                 .icon(app.default_window_icon().unwrap().clone())
                 .tooltip("MindLink - Local LLM Router")
@@ -488,15 +768,143 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 start_health_monitoring(app_handle).await;
             });
 
+            // Follow the power source for the battery power saver
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                start_power_monitoring(app_handle).await;
+            });
+
+            // Verify the ChatGPT session upstream on its own schedule
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                start_auth_probe(app_handle).await;
+            });
+
+            // Refresh the tokens ahead of expiry and warn when that fails
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                start_session_expiry_monitor(app_handle).await;
+            });
+
+            // Measure the round trip through the tunnel for the dashboard
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                start_tunnel_probe(app_handle).await;
+            });
+
+            // Rotate the token of tunnel clients before it expires
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                start_tunnel_token_rotation(app_handle).await;
+            });
+
+            // Tell the dashboard when the API server turns requests away
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                forward_overload_events(app_handle).await;
+            });
+
+            // Open and close the tunnel on its schedule
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                start_tunnel_schedule(app_handle).await;
+            });
+
+            // Warn when traffic through the tunnel nears its limits
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                forward_tunnel_limit_warnings(app_handle).await;
+            });
+
+            // Show the progress of logins in the dashboard
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                forward_auth_events(app_handle).await;
+            });
+
+            // Tell the dashboard and the webhooks when the tunnel URL changes
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                forward_tunnel_url_changes(app_handle).await;
+            });
+
+            // Warn about managed binaries that changed since they were installed
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                check_binary_drift(app_handle).await;
+            });
+
+            // Show the progress of binary downloads, e.g. of cloudflared
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                forward_download_progress(app_handle).await;
+            });
+
+            // Keep the downloaded cloudflared at its latest or pinned release
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                start_cloudflared_updates(app_handle).await;
+            });
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::get_status,
+            commands::get_health_report,
+            commands::get_analytics_stats,
+            commands::get_quota_attribution,
+            commands::start_canary,
+            commands::get_canary_status,
+            commands::rollback_canary,
+            commands::get_security_report,
+            commands::get_power_status,
+            commands::set_power_saver_mode,
+            commands::get_request_record,
+            commands::get_error_feed,
+            commands::clear_error_feed,
+            commands::dry_run_config,
+            commands::refresh_models,
+            commands::get_model_aliases,
+            commands::set_model_aliases,
+            commands::get_prompt_config,
+            commands::set_prompt_config,
+            commands::get_post_processing_config,
+            commands::set_post_processing_config,
+            commands::get_request_transform_config,
+            commands::set_request_transform_config,
+            commands::get_auth_config,
+            commands::set_auth_config,
+            commands::get_proxy_config,
+            commands::set_proxy_config,
+            commands::get_anthropic_config,
+            commands::set_anthropic_config,
+            commands::get_bundle_public_key,
+            commands::export_bundle,
+            commands::import_bundle,
             commands::login_and_serve,
             commands::stop_serving,
             commands::logout,
+            commands::add_chatgpt_account,
+            commands::remove_chatgpt_account,
+            commands::get_account_status,
+            commands::list_accounts,
+            commands::add_account,
+            commands::select_account,
+            commands::remove_account,
+            commands::import_codex_login,
+            commands::get_connection_reports,
+            commands::get_captures,
+            commands::get_capture,
+            commands::replay_capture,
+            commands::get_shadow_report,
+            commands::get_recent_requests,
+            commands::get_stream_stats,
+            commands::get_tunnel_traffic,
+            commands::get_tunnels,
             commands::get_config,
             commands::save_config,
+            commands::get_server_bind_address,
+            commands::set_server_bind_address,
             commands::show_notification,
             commands::open_bifrost_dashboard,
             commands::copy_api_url,
@@ -510,15 +918,21 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             commands::close_tunnel,
             commands::get_tunnel_status,
             commands::install_cloudflared_binary,
+            commands::get_binary_drift,
+            commands::update_cloudflared,
             commands::get_instance_token,
             commands::regenerate_token,
             commands::get_qr_data,
+            commands::save_qr_code,
+            commands::get_tunnel_token,
+            commands::rotate_tunnel_token,
             commands::show_main_window,
             commands::test_show_main_window,
             commands::oauth_login,
             commands::oauth_logout,
             commands::check_auth_status,
             commands::start_tunnel,
+            commands::protect_tunnel_with_access,
             commands::stop_tunnel,
             commands::simple_test,
             commands::get_settings,
@@ -526,12 +940,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             commands::get_authorized_apps,
             commands::add_authorized_app,
             commands::update_app_model,
+            commands::regenerate_app_key,
+            commands::set_app_system_prompt,
+            commands::set_app_token_budget,
+            commands::get_token_budgets,
             commands::remove_authorized_app,
             commands::open_external_url,
             commands::get_certificate_instructions,
             commands::check_certificate_status,
             commands::test_certificate_handling,
             commands::get_plugin_manifests,
+            commands::refresh_plugins,
             commands::get_plugins_directory,
             commands::ensure_plugins_directory,
             // Local LLM Management Commands
@@ -544,6 +963,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             commands::get_ollama_models,
             commands::download_ollama_model,
             commands::delete_ollama_model,
+            commands::get_warm_pool,
+            commands::warm_local_model,
+            commands::unload_local_model,
+            commands::set_warm_pool_size,
             commands::check_bifrost_llm_provider,
             commands::configure_bifrost_llm_provider,
             commands::get_bifrost_models,
@@ -551,10 +974,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             commands::authenticate_chatgpt,
             commands::check_chatgpt_auth_status,
             commands::get_chatgpt_auth_info,
+            commands::authenticate_anthropic,
+            commands::check_anthropic_auth_status,
+            commands::logout_anthropic,
             commands::configure_chatgpt_provider,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Every way of quitting goes through the coordinated shutdown,
+            // which ends with an exit of its own that is let through
+            if let RunEvent::ExitRequested { api, .. } = event {
+                if !shutdown::is_finished() {
+                    api.prevent_exit();
+                    request_shutdown(app);
+                }
+            }
+        });
 
     Ok(())
 }
@@ -587,9 +1023,11 @@ async fn start_dashboard(app_handle: AppHandle) -> MindLinkResult<()> {
             }
 
             // Show user-friendly notification
-            let _ = app_handle.emit(
-                "notification",
-                format!("Dashboard Warning: {}", mindlink_error.user_message()),
+            events::notify(
+                &app_handle,
+                NotificationKind::Warning,
+                "Dashboard Warning",
+                mindlink_error.user_message(),
             );
 
             return Err(mindlink_error);
@@ -631,7 +1069,12 @@ async fn start_bifrost_service(app_handle: AppHandle) -> MindLinkResult<()> {
                 .with_component("Bifrost");
                 logger.log(entry);
             }
-            let _ = app_handle.emit("notification", "Bifrost LLM Router started successfully");
+            events::notify(
+                &app_handle,
+                NotificationKind::Success,
+                "Bifrost",
+                "Bifrost LLM Router started successfully",
+            );
         },
         Err(e) => {
             let mindlink_error = MindLinkError::BinaryExecution {
@@ -649,9 +1092,11 @@ async fn start_bifrost_service(app_handle: AppHandle) -> MindLinkResult<()> {
             }
 
             // Show user-friendly notification
-            let _ = app_handle.emit(
-                "notification",
-                format!("Bifrost Warning: {}", mindlink_error.user_message()),
+            events::notify(
+                &app_handle,
+                NotificationKind::Warning,
+                "Bifrost Warning",
+                mindlink_error.user_message(),
             );
 
             // Don't return error for auto-start failures - they're non-critical
@@ -662,15 +1107,633 @@ async fn start_bifrost_service(app_handle: AppHandle) -> MindLinkResult<()> {
 }
 
 async fn start_health_monitoring(app_handle: AppHandle) {
-    let mut interval = tokio::time::interval(std::time::Duration::from_secs(30));
-
     loop {
-        interval.tick().await;
-
         // Perform health check
         if let Err(e) = perform_health_check(&app_handle).await {
             eprintln!("Health check failed: {}", e);
         }
+
+        // Checks are spaced further apart while saving power
+        let state = app_handle.state::<AppState>();
+        let config = state
+            .config_manager
+            .read()
+            .await
+            .get_power_saver_config()
+            .await;
+        let interval = power::health_check_interval(&*state.power_status.read().await, &config);
+        tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+    }
+}
+
+async fn start_power_monitoring(app_handle: AppHandle) {
+    let mut interval = tokio::time::interval(std::time::Duration::from_secs(60));
+
+    loop {
+        interval.tick().await;
+        power::refresh_power_saver(&app_handle).await;
+    }
+}
+
+async fn forward_overload_events(app_handle: AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let mut overloads = state.server_manager.read().await.subscribe_overload();
+    loop {
+        match overloads.recv().await {
+            Ok(overload) => {
+                crate::log_warn!(
+                    "Server",
+                    format!(
+                        "Overloaded ({:?}), answering 429 with Retry-After {}s",
+                        overload.reason, overload.retry_after_secs
+                    )
+                );
+                events::emit(&app_handle, AppEvent::Overloaded(overload));
+            },
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+async fn forward_tunnel_limit_warnings(app_handle: AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let mut warnings = state.server_manager.read().await.subscribe_tunnel_limits();
+    loop {
+        match warnings.recv().await {
+            Ok(warning) => {
+                let message = warning.message();
+                crate::log_warn!("Tunnel", message.clone());
+                events::emit(
+                    &app_handle,
+                    AppEvent::Notification(Notification::new(
+                        NotificationKind::Warning,
+                        "Tunnel Limit",
+                        message,
+                    )),
+                );
+                events::emit(&app_handle, AppEvent::TunnelLimit(warning));
+            },
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+async fn forward_download_progress(app_handle: AppHandle) {
+    let mut progress = downloads::subscribe();
+    loop {
+        match progress.recv().await {
+            Ok(progress) => events::emit(&app_handle, AppEvent::DownloadProgress(progress)),
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+async fn forward_auth_events(app_handle: AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let mut auth_events = state.auth_manager.read().await.subscribe_events();
+    loop {
+        match auth_events.recv().await {
+            Ok(event) => events::emit(&app_handle, AppEvent::Auth(event)),
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+async fn forward_tunnel_url_changes(app_handle: AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let mut url_changes = state.tunnel_manager.read().await.subscribe_url_changes();
+    let client = tunnel_webhooks::webhook_client();
+    loop {
+        match url_changes.recv().await {
+            Ok(change) => {
+                crate::log_info!(
+                    "Tunnel",
+                    &format!(
+                        "Public URL changed to {}",
+                        change.url.as_deref().unwrap_or("none")
+                    )
+                );
+                events::emit(&app_handle, AppEvent::TunnelUrlChanged(change.clone()));
+
+                let webhooks = state
+                    .config_manager
+                    .read()
+                    .await
+                    .get_tunnel_config()
+                    .await
+                    .webhooks;
+                if !webhooks.is_empty() {
+                    let client = client.clone();
+                    tauri::async_runtime::spawn(async move {
+                        tunnel_webhooks::notify_all(&client, &webhooks, &change).await;
+                    });
+                }
+            },
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+        }
+    }
+}
+
+/// Stop the services and exit, once, however often quitting is requested
+fn request_shutdown(app: &AppHandle) {
+    if !shutdown::begin() {
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let exit_code = shutdown_services(&app).await;
+        shutdown::finish();
+        app.exit(exit_code);
+    });
+}
+
+async fn shutdown_services(app_handle: &AppHandle) -> i32 {
+    crate::log_info!("Main", "Shutting down...");
+
+    let report = shutdown::run(&ShutdownStep::ALL, shutdown::HARD_TIMEOUT, |step| {
+        run_shutdown_step(app_handle.clone(), step)
+    })
+    .await;
+
+    for step in &report.steps {
+        if step.outcome == StepOutcome::Completed {
+            crate::log_info!(
+                "Main",
+                &format!("Shutdown: {} done in {} ms", step.step, step.elapsed_ms)
+            );
+        }
+    }
+    for problem in report.problems() {
+        crate::log_warn!("Main", format!("Shutdown: {}", problem));
+    }
+
+    let exit_code = report.exit_code();
+    crate::log_info!(
+        "Main",
+        &format!(
+            "Shutdown finished in {} ms, exiting with code {}",
+            report.elapsed_ms, exit_code
+        )
+    );
+    if let Some(logger) = get_logger() {
+        if let Err(e) = logger.flush() {
+            eprintln!("Failed to flush logs: {}", e);
+        }
+    }
+
+    exit_code
+}
+
+async fn run_shutdown_step(app_handle: AppHandle, step: ShutdownStep) -> MindLinkResult<()> {
+    let state = app_handle.state::<AppState>();
+    match step {
+        ShutdownStep::DrainServer => {
+            let drained = state
+                .server_manager
+                .write()
+                .await
+                .drain(shutdown::DRAIN_TIMEOUT)
+                .await;
+            *state.is_serving.write().await = false;
+            if drained {
+                Ok(())
+            } else {
+                Err(MindLinkError::Internal {
+                    message: format!(
+                        "Requests still running after {} seconds were cut off",
+                        shutdown::DRAIN_TIMEOUT.as_secs()
+                    ),
+                    component: Some("ServerManager".to_string()),
+                    source: None,
+                })
+            }
+        },
+        ShutdownStep::CloseTunnel => state
+            .tunnel_manager
+            .write()
+            .await
+            .close_tunnel()
+            .await
+            .map_err(|e| MindLinkError::Tunnel {
+                message: "Failed to close tunnel".to_string(),
+                tunnel_type: None,
+                local_port: None,
+                source: Some(e),
+            }),
+        ShutdownStep::StopChildren => {
+            let Some(monitor) = process_monitor::get_process_monitor() else {
+                return Ok(());
+            };
+            let failed = monitor.stop_all().await;
+            if failed.is_empty() {
+                Ok(())
+            } else {
+                Err(MindLinkError::ProcessMonitoring {
+                    message: format!("Failed to stop {}", failed.join(", ")),
+                    process_name: failed.join(", "),
+                    pid: None,
+                    source: None,
+                })
+            }
+        },
+        ShutdownStep::FlushAnalytics => {
+            state.server_manager.read().await.flush_analytics().await;
+            Ok(())
+        },
+    }
+}
+
+async fn check_binary_drift(app_handle: AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let findings = state.binary_manager.read().await.check_drift().await;
+    for finding in findings {
+        crate::log_warn!(
+            "BinaryManager",
+            format!(
+                "{}. To fix it, run: {}",
+                finding.summary(),
+                finding.remediation
+            )
+        );
+    }
+}
+
+async fn start_cloudflared_updates(app_handle: AppHandle) {
+    loop {
+        let state = app_handle.state::<AppState>();
+        let config = state
+            .config_manager
+            .read()
+            .await
+            .get_tunnel_config()
+            .await
+            .cloudflared_updates;
+        state
+            .binary_manager
+            .write()
+            .await
+            .pin_cloudflared(config.pinned_version.clone());
+        check_cloudflared_update(&app_handle, config.auto_update).await;
+
+        tokio::time::sleep(std::time::Duration::from_secs(
+            config.check_interval_hours.max(1) * 3600,
+        ))
+        .await;
+    }
+}
+
+/// Look for a new release of the downloaded cloudflared, installing it when
+/// `auto_update` is on and telling the user about it otherwise
+async fn check_cloudflared_update(app_handle: &AppHandle, auto_update: bool) {
+    let state = app_handle.state::<AppState>();
+    let binary_manager = state.binary_manager.read().await;
+    let release = match binary_manager.pending_cloudflared_update().await {
+        Ok(Some(release)) => release,
+        Ok(None) => return,
+        Err(e) => {
+            crate::log_warn!(
+                "BinaryManager",
+                format!("Failed to check for a cloudflared update: {}", e)
+            );
+            return;
+        },
+    };
+
+    let (title, message) = if !auto_update {
+        (
+            "cloudflared Update Available",
+            format!("cloudflared {} is available", release.tag_name),
+        )
+    } else {
+        match binary_manager.update_cloudflared().await {
+            Ok(update) => (
+                "cloudflared Updated",
+                format!(
+                    "cloudflared was updated from {} to {}; the tunnel uses it once it restarts",
+                    update.previous.as_deref().unwrap_or("an unknown release"),
+                    update.installed
+                ),
+            ),
+            Err(e) => {
+                crate::log_warn!(
+                    "BinaryManager",
+                    format!(
+                        "Failed to update cloudflared to {}: {}",
+                        release.tag_name, e
+                    )
+                );
+                return;
+            },
+        }
+    };
+
+    crate::log_info!("BinaryManager", message.clone());
+    events::emit(
+        app_handle,
+        AppEvent::Notification(Notification::new(NotificationKind::Info, title, message)),
+    );
+}
+
+async fn start_auth_probe(app_handle: AppHandle) {
+    let client = auth_probe::probe_client();
+
+    loop {
+        let state = app_handle.state::<AppState>();
+        let interval = state
+            .config_manager
+            .read()
+            .await
+            .get_monitoring_config()
+            .await
+            .auth_probe_interval_secs;
+        if interval == 0 {
+            // Disabled; look again later in case it gets turned on
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            continue;
+        }
+
+        if *state.is_serving.read().await {
+            // Copy the token so no auth lock is held during the request
+            let access_token = {
+                let auth_manager = state.auth_manager.read().await;
+                if auth_manager.is_authenticated().await {
+                    auth_manager
+                        .get_tokens()
+                        .map(|tokens| tokens.access_token.clone())
+                } else {
+                    None
+                }
+            };
+
+            if let Some(access_token) = access_token {
+                let result =
+                    auth_probe::probe(&client, auth_probe::AUTH_PROBE_URL, &access_token).await;
+                let changed = {
+                    let mut latest = state.auth_probe.write().await;
+                    let changed = latest
+                        .as_ref()
+                        .map_or(true, |previous| previous.outcome != result.outcome);
+                    *latest = Some(result.clone());
+                    changed
+                };
+
+                if changed {
+                    crate::log_info!(
+                        "AuthProbe",
+                        format!("ChatGPT session probe: {:?}", result.outcome)
+                    );
+                    if let Err(e) = perform_health_check(&app_handle).await {
+                        eprintln!("Health check failed: {}", e);
+                    }
+                }
+            }
+        }
+
+        tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+    }
+}
+
+/// Time requests through the tunnel's public URL while a tunnel is up, and
+/// keep whether they got through for the health check. The probe is answered
+/// by this server, so it covers the whole way out through DNS, TLS and the
+/// tunnel provider and back.
+async fn start_tunnel_probe(app_handle: AppHandle) {
+    let client = auth_probe::probe_client();
+
+    loop {
+        let state = app_handle.state::<AppState>();
+        let interval = state
+            .config_manager
+            .read()
+            .await
+            .get_monitoring_config()
+            .await
+            .tunnel_probe_interval_secs;
+        if interval == 0 {
+            // Disabled; look again later in case it gets turned on
+            tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+            continue;
+        }
+
+        let url = state.tunnel_manager.read().await.get_current_url().await;
+        if let Some((url, tunnel)) =
+            url.and_then(|url| middleware::metrics::tunnel_host(&url).map(|tunnel| (url, tunnel)))
+        {
+            let protection = state
+                .config_manager
+                .read()
+                .await
+                .get_tunnel_config()
+                .await
+                .cloudflare_access;
+            let headers: Vec<(&str, &str)> = match (
+                protection.enabled,
+                &protection.client_id,
+                &protection.client_secret,
+            ) {
+                (true, Some(client_id), Some(client_secret)) => vec![
+                    (cloudflare_access::CLIENT_ID_HEADER, client_id),
+                    (cloudflare_access::CLIENT_SECRET_HEADER, client_secret),
+                ],
+                _ => Vec::new(),
+            };
+
+            let result = managers::tunnel_manager::probe_round_trip(&client, &url, &headers).await;
+            if let Err(failure) = &result {
+                crate::log_warn!(
+                    "Tunnel",
+                    format!("Probe through {} failed: {}", url, failure)
+                );
+            }
+            state
+                .tunnel_manager
+                .read()
+                .await
+                .record_public_check(PublicCheck::new(&url, &result))
+                .await;
+            state
+                .server_manager
+                .read()
+                .await
+                .record_tunnel_probe(&tunnel, result.ok());
+        }
+
+        tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
+    }
+}
+
+/// Open and close the tunnel with the windows of its schedule while serving
+async fn start_tunnel_schedule(app_handle: AppHandle) {
+    loop {
+        apply_tunnel_schedule(&app_handle).await;
+        tokio::time::sleep(tunnel_schedule::CHECK_INTERVAL).await;
+    }
+}
+
+/// Apply the tunnel's schedule, telling the user when a window opened or
+/// closed the tunnel
+async fn apply_tunnel_schedule(app_handle: &AppHandle) {
+    let state = app_handle.state::<AppState>();
+    if !*state.is_serving.read().await {
+        return;
+    }
+
+    let now = chrono::Local::now().naive_local();
+    let change = state.tunnel_manager.write().await.apply_schedule(now).await;
+    let (title, message) = match change {
+        Ok(Some(ScheduleChange::Opened(url))) => (
+            "Tunnel Opened",
+            format!("A scheduled window began; the API is reachable at {}", url),
+        ),
+        Ok(Some(ScheduleChange::Closed)) => (
+            "Tunnel Closed",
+            "The scheduled window ended; the API is served locally only".to_string(),
+        ),
+        Ok(None) => return,
+        Err(e) => {
+            crate::log_warn!(
+                "Tunnel",
+                format!("Failed to open the tunnel for its scheduled window: {}", e)
+            );
+            return;
+        },
+    };
+
+    crate::log_info!("Tunnel", message.clone());
+    events::emit(
+        app_handle,
+        AppEvent::Notification(Notification::new(NotificationKind::Info, title, message)),
+    );
+    update_tray_menu_for_state(app_handle, &state).await;
+}
+
+/// Keep the token of paired tunnel clients valid, so the pairing QR code never
+/// carries an expired one. Nothing is issued before a client was paired.
+async fn start_tunnel_token_rotation(app_handle: AppHandle) {
+    loop {
+        let state = app_handle.state::<AppState>();
+        if state.tunnel_tokens.read().await.issued() {
+            if let Err(e) = commands::current_tunnel_token(&app_handle, &state, false).await {
+                crate::log_warn!("TunnelTokens", format!("Token rotation failed: {}", e));
+            }
+        }
+
+        tokio::time::sleep(std::time::Duration::from_secs(
+            tunnel_tokens::CHECK_INTERVAL_SECS,
+        ))
+        .await;
+    }
+}
+
+async fn start_session_expiry_monitor(app_handle: AppHandle) {
+    // Error of the last failed refresh, with the access token it was for
+    let mut refresh_error: Option<(String, String)> = None;
+
+    loop {
+        let state = app_handle.state::<AppState>();
+
+        let since = std::time::Instant::now();
+        let due = {
+            let auth_manager = state.auth_manager.read().await;
+            auth_manager.reauth_required().is_none()
+                && auth_manager.get_tokens().is_some_and(|tokens| {
+                    !tokens.refresh_token.is_empty()
+                        && session_expiry::needs_refresh(tokens.expires_at, chrono::Utc::now())
+                })
+        };
+        if due {
+            let mut auth_manager = state.auth_manager.write().await;
+            let access_token = auth_manager
+                .get_tokens()
+                .map(|tokens| tokens.access_token.clone())
+                .unwrap_or_default();
+            // A request may have refreshed the tokens while the lock was awaited
+            match auth_manager.refresh_once(since).await {
+                Ok(()) => refresh_error = None,
+                Err(e) => {
+                    crate::log_warn!("SessionExpiry", format!("Token refresh failed: {}", e));
+                    refresh_error = Some((access_token, e.to_string()));
+                },
+            }
+        }
+
+        let now = chrono::Utc::now();
+        let warning = {
+            let auth_manager = state.auth_manager.read().await;
+            match auth_manager.reauth_required() {
+                Some(reason) => Some(session_expiry::SessionWarning::ReauthRequired {
+                    reason: reason.to_string(),
+                }),
+                None => auth_manager.get_tokens().and_then(|tokens| {
+                    let error = refresh_error
+                        .as_ref()
+                        .filter(|(access_token, _)| *access_token == tokens.access_token)
+                        .map(|(_, reason)| reason.as_str());
+                    session_expiry::session_warning(
+                        tokens.expires_at,
+                        !tokens.refresh_token.is_empty(),
+                        error,
+                        now,
+                    )
+                }),
+            }
+        };
+        let previous =
+            std::mem::replace(&mut *state.session_warning.write().await, warning.clone());
+
+        if previous != warning {
+            let is_new = warning.as_ref().filter(|warning| {
+                !previous
+                    .as_ref()
+                    .is_some_and(|previous| previous.same_kind(warning))
+            });
+            if let Some(warning) = is_new {
+                let message = warning.message(now);
+                crate::log_warn!("SessionExpiry", &message);
+                // An ended session is an error whose banner offers the login
+                let (kind, action) = match warning {
+                    session_expiry::SessionWarning::ReauthRequired { .. } => (
+                        NotificationKind::Error,
+                        Some(RecoveryAction::ReAuthenticate),
+                    ),
+                    _ => (NotificationKind::Warning, None),
+                };
+                events::emit(
+                    &app_handle,
+                    AppEvent::Notification(
+                        Notification::new(kind, "ChatGPT Login Needed", &message)
+                            .with_action(action),
+                    ),
+                );
+                if let Err(e) = app_handle
+                    .notification()
+                    .builder()
+                    .title("MindLink: ChatGPT login needed")
+                    .body(&message)
+                    .show()
+                {
+                    crate::log_warn!(
+                        "SessionExpiry",
+                        format!("Desktop notification failed: {}", e)
+                    );
+                }
+            }
+            if let Err(e) = perform_health_check(&app_handle).await {
+                eprintln!("Health check failed: {}", e);
+            }
+            update_tray_menu_for_state(&app_handle, &state).await;
+        }
+
+        tokio::time::sleep(std::time::Duration::from_secs(
+            session_expiry::CHECK_INTERVAL_SECS,
+        ))
+        .await;
     }
 }
 
@@ -683,10 +1746,11 @@ async fn perform_health_check(app_handle: &AppHandle) -> MindLinkResult<()> {
     }
 
     // Check all managers' health with proper error handling
-    let server_healthy = {
+    let server_health = {
         let server_manager = state.server_manager.read().await;
         match server_manager.check_health().await {
-            Ok(healthy) => healthy,
+            Ok(true) => ComponentHealth::ok("server", true),
+            Ok(false) => ComponentHealth::down("server", "API server is not responding", true),
             Err(e) => {
                 if let Some(logger) = get_logger() {
                     logger.log_health_check("Server", false, None, None);
@@ -698,15 +1762,100 @@ async fn perform_health_check(app_handle: &AppHandle) -> MindLinkResult<()> {
                     .with_component("HealthMonitor");
                     logger.log(entry);
                 }
-                false
+                ComponentHealth::down("server", e.user_message(), true)
             },
         }
     };
 
-    let tunnel_healthy = {
+    let (auth_health, upstream_health) = {
+        let auth_manager = state.auth_manager.read().await;
+        let authenticated = auth_manager.is_authenticated().await;
+        let latest_probe = state.auth_probe.read().await;
+        let probe = auth_manager.get_tokens().and_then(|tokens| {
+            latest_probe
+                .as_ref()
+                .filter(|result| result.applies_to(&tokens.access_token))
+                .map(|result| result.outcome.clone())
+        });
+        let mut auth_health = auth_probe::auth_health(
+            auth_manager.get_tokens().is_some(),
+            authenticated,
+            probe.as_ref(),
+        )
+        .with_detail("probe", &probe);
+        if let Some(tokens) = auth_manager.get_tokens() {
+            auth_health =
+                auth_probe::with_token_expiry(auth_health, tokens.expires_at, chrono::Utc::now());
+        }
+        let session_warning = state.session_warning.read().await;
+        auth_health =
+            session_expiry::with_warning(auth_health, session_warning.as_ref(), chrono::Utc::now());
+        // Reachability does not depend on which token was probed
+        let upstream_health =
+            auth_probe::upstream_health(latest_probe.as_ref().map(|result| &result.outcome));
+        (auth_health, upstream_health)
+    };
+
+    // Before the health check, which would only report the tunnel as down
+    {
+        let mut tunnel_manager = state.tunnel_manager.write().await;
+        match tunnel_manager.restart_if_crashed().await {
+            Ok(Some(url)) => crate::log_info!(
+                "HealthMonitor",
+                format!("Restarted the tunnel after its process exited: {}", url)
+            ),
+            Ok(None) => {},
+            Err(e) => crate::log_warn!(
+                "HealthMonitor",
+                format!("Failed to restart the tunnel after its process exited: {}", e)
+            ),
+        }
+        for (service, result) in tunnel_manager.restart_crashed_services().await {
+            match result {
+                Ok(url) => crate::log_info!(
+                    "HealthMonitor",
+                    format!("Restarted the {} tunnel after its process exited: {}", service, url)
+                ),
+                Err(e) => crate::log_warn!(
+                    "HealthMonitor",
+                    format!("Failed to restart the {} tunnel: {}", service, e)
+                ),
+            }
+        }
+    }
+
+    let tunnel_health = {
         let tunnel_manager = state.tunnel_manager.read().await;
-        match tunnel_manager.check_health().await {
-            Ok(healthy) => healthy,
+        let now = chrono::Local::now().naive_local();
+        let public_check = tunnel_manager.last_public_check().await;
+        let health = match tunnel_manager.check_health().await {
+            Ok(true) => ComponentHealth::ok("tunnel", false),
+            // check_health clears the connected flag when the process has exited,
+            // so a still-connected tunnel that failed means the public URL is unreachable
+            Ok(false) if tunnel_manager.is_connected().await => {
+                let failure = public_check.as_ref().and_then(|check| check.failure.as_ref());
+                ComponentHealth::degraded(
+                    "tunnel",
+                    match failure {
+                        Some(failure) => format!(
+                            "Tunnel process is running but the public URL is unreachable: {}",
+                            failure
+                        ),
+                        None => "Tunnel process is running but the public URL is unreachable"
+                            .to_string(),
+                    },
+                    false,
+                )
+            },
+            // Not a failure, and must not be restarted by the self-healing policy
+            Ok(false) if tunnel_manager.closed_by_schedule(now).await => {
+                let mut health = ComponentHealth::ok("tunnel", false);
+                health.reason = tunnel_manager
+                    .schedule_status(now)
+                    .map(|status| status.describe(now));
+                health
+            },
+            Ok(false) => ComponentHealth::down("tunnel", "Tunnel is not connected", false),
             Err(e) => {
                 if let Some(logger) = get_logger() {
                     logger.log_health_check("Tunnel", false, None, None);
@@ -718,14 +1867,27 @@ async fn perform_health_check(app_handle: &AppHandle) -> MindLinkResult<()> {
                     .with_component("HealthMonitor");
                     logger.log(entry);
                 }
-                false
+                ComponentHealth::down("tunnel", e.to_string(), false)
             },
-        }
+        };
+        health
+            .with_detail("connected", tunnel_manager.is_connected().await)
+            .with_detail("url", tunnel_manager.get_current_url().await)
+            .with_detail(
+                "publicly_reachable",
+                public_check.as_ref().map(PublicCheck::reachable),
+            )
+            .with_detail("public_check", &public_check)
     };
 
-    let bifrost_healthy = {
+    let bifrost_health = if state.power_status.read().await.bifrost_stopped {
+        // Not a failure, and must not be restarted by the self-healing policy
+        let mut health = ComponentHealth::ok("bifrost", false);
+        health.reason = Some("Stopped to save power".to_string());
+        health.with_detail("state", "stopped_for_power")
+    } else {
         let bifrost_manager = state.bifrost_manager.read().await;
-        match bifrost_manager.check_health().await {
+        let health = match bifrost_manager.check_health().await {
             Ok(healthy) => {
                 if let Some(logger) = get_logger() {
                     logger.log_health_check(
@@ -735,7 +1897,11 @@ async fn perform_health_check(app_handle: &AppHandle) -> MindLinkResult<()> {
                         None,
                     );
                 }
-                healthy
+                if healthy {
+                    ComponentHealth::ok("bifrost", false)
+                } else {
+                    ComponentHealth::down("bifrost", "Bifrost router is not responding", false)
+                }
             },
             Err(e) => {
                 if let Some(logger) = get_logger() {
@@ -748,12 +1914,20 @@ async fn perform_health_check(app_handle: &AppHandle) -> MindLinkResult<()> {
                     .with_component("HealthMonitor");
                     logger.log(entry);
                 }
-                false
+                ComponentHealth::down("bifrost", e.to_string(), false)
             },
-        }
+        };
+        let bifrost_state = match (bifrost_manager.is_running().await, health.level) {
+            (false, _) => "stopped",
+            (true, HealthLevel::Ok) => "running",
+            (true, _) => "not_responding",
+        };
+        health
+            .with_detail("state", bifrost_state)
+            .with_detail("url", bifrost_manager.get_local_url().await)
     };
 
-    let dashboard_healthy = {
+    let dashboard_health = {
         let dashboard_manager = state.dashboard_manager.read().await;
         match dashboard_manager.check_health().await {
             Ok(healthy) => {
@@ -765,7 +1939,11 @@ async fn perform_health_check(app_handle: &AppHandle) -> MindLinkResult<()> {
                         None,
                     );
                 }
-                healthy
+                if healthy {
+                    ComponentHealth::ok("dashboard", false)
+                } else {
+                    ComponentHealth::down("dashboard", "Dashboard is not responding", false)
+                }
             },
             Err(e) => {
                 if let Some(logger) = get_logger() {
@@ -778,60 +1956,98 @@ async fn perform_health_check(app_handle: &AppHandle) -> MindLinkResult<()> {
                     .with_component("HealthMonitor");
                     logger.log(entry);
                 }
-                false
+                ComponentHealth::down("dashboard", e.to_string(), false)
             },
         }
     };
 
-    if !server_healthy || !tunnel_healthy || !bifrost_healthy || !dashboard_healthy {
-        let error_msg = format!(
-            "Health check failed - Server: {}, Tunnel: {}, Bifrost: {}, Dashboard: {}",
-            server_healthy, tunnel_healthy, bifrost_healthy, dashboard_healthy
+    let report = HealthReport::from_components(vec![
+        server_health,
+        auth_health,
+        upstream_health,
+        tunnel_health,
+        bifrost_health,
+        dashboard_health,
+    ]);
+
+    let previous = health::publish_health(report.clone()).await;
+    let actions = {
+        let mut policy = state.healing_policy.write().await;
+        let mut last_error = state.last_error.write().await;
+        let actions = health_check_actions(
+            &mut policy,
+            &previous,
+            &report,
+            last_error.as_deref(),
+            chrono::Utc::now(),
         );
+        last_error.clone_from(&actions.last_error);
+        actions
+    };
 
-        *state.last_error.write().await = Some(error_msg.clone());
+    if let Some(report) = actions.notify.clone() {
+        events::emit(app_handle, AppEvent::HealthChanged(report));
+    }
 
-        if let Some(logger) = get_logger() {
-            let entry = LogEntry::new(LogLevel::Error, LogCategory::HealthCheck, error_msg.clone())
-                .with_component("HealthMonitor");
+    if report.overall != HealthLevel::Ok {
+        if let (Some(logger), Some(error_msg)) = (get_logger(), &actions.last_error) {
+            let level = if report.overall == HealthLevel::Down {
+                LogLevel::Error
+            } else {
+                LogLevel::Warn
+            };
+            let entry = LogEntry::new(level, LogCategory::HealthCheck, error_msg.clone())
+                .with_component("HealthMonitor")
+                .with_details(&report);
             logger.log(entry);
         }
+    }
 
-        // Try to restart Bifrost if it's unhealthy
-        if !bifrost_healthy {
-            let mut bifrost_manager = state.bifrost_manager.write().await;
-            if let Err(e) = bifrost_manager.restart().await {
-                let restart_error = MindLinkError::ProcessMonitoring {
-                    message: "Failed to restart Bifrost service".to_string(),
-                    process_name: "Bifrost".to_string(),
-                    pid: None,
-                    source: Some(e.into()),
-                };
+    for component in &actions.suppressed {
+        crate::log_warn!(
+            "HealthMonitor",
+            format!(
+                "Not restarting {}: restart budget exhausted, waiting for it to recover",
+                component
+            )
+        );
+    }
 
-                if let Some(logger) = get_logger() {
-                    logger.log_error("HealthMonitor", &restart_error, None);
-                }
-            }
-        }
+    for component in &actions.restart {
+        let result = match component.as_str() {
+            "bifrost" => state
+                .bifrost_manager
+                .write()
+                .await
+                .restart()
+                .await
+                .map_err(|e| ("Bifrost", anyhow::Error::from(e))),
+            "dashboard" => state
+                .dashboard_manager
+                .write()
+                .await
+                .start()
+                .await
+                .map_err(|e| ("Dashboard", e)),
+            _ => Ok(()),
+        };
 
-        // Try to restart dashboard if it's unhealthy
-        if !dashboard_healthy {
-            let mut dashboard_manager = state.dashboard_manager.write().await;
-            if let Err(e) = dashboard_manager.start().await {
-                let restart_error = MindLinkError::ProcessMonitoring {
-                    message: "Failed to restart Dashboard service".to_string(),
-                    process_name: "Dashboard".to_string(),
-                    pid: None,
-                    source: Some(e.into()),
-                };
+        if let Err((process_name, e)) = result {
+            let restart_error = MindLinkError::ProcessMonitoring {
+                message: format!("Failed to restart {} service", process_name),
+                process_name: process_name.to_string(),
+                pid: None,
+                source: Some(e),
+            };
 
-                if let Some(logger) = get_logger() {
-                    logger.log_error("HealthMonitor", &restart_error, None);
-                }
+            if let Some(logger) = get_logger() {
+                logger.log_error("HealthMonitor", &restart_error, None);
             }
         }
     }
 
+    update_tray_menu_for_state(app_handle, &*state).await;
+
     Ok(())
 }
 
@@ -940,7 +2156,7 @@ fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
             }
         },
         "quit" => {
-            app.exit(0);
+            request_shutdown(app);
         },
         _ => {
             println!("Unhandled menu item: {}", event.id.as_ref());
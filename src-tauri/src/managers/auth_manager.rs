@@ -7,17 +7,25 @@ use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::fs;
 use tokio::net::TcpListener;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, oneshot, RwLock};
+use tokio::task::JoinHandle;
+use ts_rs::TS;
 use url::Url;
 use jsonwebtoken::{decode, decode_header, DecodingKey, Validation, Algorithm};
 use serde_json::Value;
 
+use crate::device_auth;
 use crate::error::{MindLinkError, MindLinkResult};
-use crate::{auth_error, log_error, log_info};
+use crate::managers::config_manager::{AuthConfig, LoginFlow};
+use crate::plan::PlanQuota;
+use crate::proxy;
+use crate::token_store;
+use crate::{auth_error, log_error, log_info, log_warn};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthTokens {
@@ -29,14 +37,122 @@ pub struct AuthTokens {
     pub account_id: String,
 }
 
+/// A ChatGPT account whose tokens are kept, for switching between accounts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedAccount {
+    pub account_id: String,
+    /// Whether requests currently use this account
+    pub active: bool,
+    /// Whether the access token is still valid; expired ones are refreshed
+    /// when the account is selected
+    pub authenticated: bool,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl SavedAccount {
+    fn new(tokens: &AuthTokens, active: bool) -> Self {
+        Self {
+            account_id: tokens.account_id.clone(),
+            active,
+            authenticated: tokens.expires_at > Utc::now() + Duration::minutes(5),
+            expires_at: tokens.expires_at,
+        }
+    }
+}
+
+/// Progress of a login and other changes of the ChatGPT session, for the
+/// frontend to show
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+#[ts(export)]
+pub enum AuthEvent {
+    LoginStarted,
+    /// The login page was opened in the browser, or has to be opened at
+    /// `url` by hand
+    BrowserOpened {
+        url: String,
+    },
+    /// A device login waits for `user_code` to be entered at
+    /// `verification_url`
+    DeviceCodeIssued {
+        verification_url: String,
+        user_code: String,
+    },
+    /// The browser came back with an authorization code
+    CallbackReceived,
+    TokensSaved {
+        account_id: String,
+    },
+    LoginFailed {
+        reason: String,
+    },
+    RefreshFailed {
+        reason: String,
+    },
+    /// The refresh token was rejected for good, so requests fail until the
+    /// user logs in again
+    ReauthRequired {
+        reason: String,
+    },
+}
+
+/// Who is signed in, as the claims of the ID token tell
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UserIdentity {
+    pub email: Option<String>,
+    /// ChatGPT plan, e.g. `plus` or `pro`
+    pub plan: Option<String>,
+    pub account_id: String,
+    /// When the ChatGPT subscription ends
+    pub subscription_until: Option<DateTime<Utc>>,
+}
+
+impl UserIdentity {
+    /// Identity from the ID token of `tokens`. Claims the token lacks, or all
+    /// of them when it cannot be decoded, are left out.
+    pub fn from_tokens(tokens: &AuthTokens) -> Self {
+        let claims = AuthManager::id_token_claims(&tokens.id_token).unwrap_or(Value::Null);
+        let auth_claim = |name: &str| {
+            claims
+                .get("https://api.openai.com/auth")
+                .and_then(|auth| auth.get(name))
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+
+        Self {
+            email: claims
+                .get("email")
+                .and_then(Value::as_str)
+                .map(str::to_string),
+            plan: auth_claim("chatgpt_plan_type"),
+            account_id: auth_claim("chatgpt_account_id")
+                .unwrap_or_else(|| tokens.account_id.clone()),
+            subscription_until: auth_claim("chatgpt_subscription_active_until")
+                .and_then(|until| DateTime::parse_from_rfc3339(&until).ok())
+                .map(|until| until.with_timezone(&Utc)),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TokenResponse {
     pub access_token: String,
+    /// Only ChatGPT logins have one
+    #[serde(default)]
     pub id_token: String,
     pub token_type: String,
     pub expires_in: Option<u64>,
     pub refresh_token: Option<String>,
     pub scope: Option<String>,
+    /// Account of Claude logins, which carry no ID token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<TokenAccount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenAccount {
+    pub uuid: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,67 +177,504 @@ struct AuthCallbackQuery {
 }
 
 struct OAuthState {
-    #[allow(dead_code)]
-    code_verifier: String,
-    #[allow(dead_code)]
     state: String,
     auth_result: Arc<RwLock<Option<MindLinkResult<String>>>>,
 }
 
-// Allow dead_code for OAuthState fields that might not be used in all tests
-#[allow(dead_code)]
 impl OAuthState {
-    fn new(code_verifier: String, state: String) -> Self {
+    fn new(state: String) -> Self {
         Self {
-            code_verifier,
             state,
             auth_result: Arc::new(RwLock::new(None)),
         }
     }
 }
 
-// ChatGPT OAuth configuration using Codex CLI client ID
+/// Callback server of a browser login. Dropping it stops the server and
+/// frees its port, however the login ends.
+struct CallbackServer {
+    shutdown: Option<oneshot::Sender<()>>,
+    task: JoinHandle<()>,
+}
+
+impl CallbackServer {
+    fn spawn(listener: TcpListener, app: Router) -> Self {
+        let (shutdown, signal) = oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            let server = axum::serve(listener, app).with_graceful_shutdown(async {
+                let _ = signal.await;
+            });
+            if let Err(e) = server.await {
+                log_warn!(
+                    "AuthManager",
+                    &format!("OAuth callback server failed: {}", e)
+                );
+            }
+        });
+        Self {
+            shutdown: Some(shutdown),
+            task,
+        }
+    }
+
+    /// Stop the server, giving it a moment to finish the page it is sending
+    async fn close(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        let _ = tokio::time::timeout(CALLBACK_SHUTDOWN_GRACE, &mut self.task).await;
+    }
+}
+
+impl Drop for CallbackServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// ChatGPT OAuth endpoints; the client ID, scopes and redirect port are in AuthConfig
 const CHATGPT_AUTH_URL: &str = "https://auth.openai.com/oauth/authorize";
 const CHATGPT_TOKEN_URL: &str = "https://auth.openai.com/oauth/token";
-const CLIENT_ID: &str = "app_EMoamEEZ73f0CkXaXp7hrann"; // Codex CLI's client ID for ChatGPT access
-const SCOPE: &str = "openid profile email offline_access";
-const REDIRECT_PORT: u16 = 1455; // Required port for Codex CLI flow
+const CHATGPT_REVOKE_URL: &str = "https://auth.openai.com/oauth/revoke";
 const CHATGPT_API_URL: &str = "https://chatgpt.com/backend-api/codex/responses";
 
+// Claude OAuth endpoints; the client ID, scopes and redirect port are in AnthropicConfig
+const ANTHROPIC_AUTH_URL: &str = "https://claude.ai/oauth/authorize";
+const ANTHROPIC_TOKEN_URL: &str = "https://console.anthropic.com/v1/oauth/token";
+
+/// How long a browser login waits for the OAuth callback
+const CALLBACK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
+/// How long the callback server may take to finish its last page once the
+/// login is over
+const CALLBACK_SHUTDOWN_GRACE: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Ports tried for the callback server, from the redirect port on, before
+/// any free port
+const CALLBACK_PORT_ATTEMPTS: u16 = 5;
+
+/// Whose subscription an AuthManager signs in to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AuthProvider {
+    /// ChatGPT, through OpenAI's OAuth server
+    #[default]
+    ChatGpt,
+    /// Claude, through Anthropic's OAuth server
+    Anthropic,
+}
+
+impl AuthProvider {
+    pub fn name(self) -> &'static str {
+        match self {
+            AuthProvider::ChatGpt => "ChatGPT",
+            AuthProvider::Anthropic => "Claude",
+        }
+    }
+
+    fn authorize_url(self) -> &'static str {
+        match self {
+            AuthProvider::ChatGpt => CHATGPT_AUTH_URL,
+            AuthProvider::Anthropic => ANTHROPIC_AUTH_URL,
+        }
+    }
+
+    fn token_url(self) -> &'static str {
+        match self {
+            AuthProvider::ChatGpt => CHATGPT_TOKEN_URL,
+            AuthProvider::Anthropic => ANTHROPIC_TOKEN_URL,
+        }
+    }
+
+    /// Token revocation endpoint, if the provider has one
+    fn revoke_url(self) -> Option<&'static str> {
+        match self {
+            AuthProvider::ChatGpt => Some(CHATGPT_REVOKE_URL),
+            AuthProvider::Anthropic => None,
+        }
+    }
+}
+
+/// How long logging out waits for the revocation of the tokens
+const REVOKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Revoke `token` of `client_id` at the RFC 7009 endpoint `url`.
+/// `token_type_hint` is `refresh_token` or `access_token`.
+pub async fn revoke_token(
+    client: &reqwest::Client,
+    url: &str,
+    client_id: &str,
+    token: &str,
+    token_type_hint: &str,
+) -> Result<()> {
+    let response = client
+        .post(url)
+        .form(&[
+            ("token", token),
+            ("token_type_hint", token_type_hint),
+            ("client_id", client_id),
+        ])
+        .timeout(REVOKE_TIMEOUT)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Token revocation failed: {}", response.status()));
+    }
+    Ok(())
+}
+
+/// Why the token endpoint rejected a refresh with `body`, if the refresh token
+/// itself is no good: expired, revoked or used already. No retry fixes that.
+pub fn rejected_refresh_token(body: &str) -> Option<String> {
+    let body: Value = serde_json::from_str(body).ok()?;
+    let (code, description) = match body.get("error")? {
+        Value::String(code) => (code.as_str(), body.get("error_description")),
+        // OpenAI nests the reason: {"error": {"code": "refresh_token_expired", "message": ..}}
+        Value::Object(error) => (
+            error.get("code").and_then(Value::as_str)?,
+            error.get("message"),
+        ),
+        _ => return None,
+    };
+    if code != "invalid_grant" && !code.starts_with("refresh_token_") {
+        return None;
+    }
+    let reason = description.and_then(Value::as_str).unwrap_or(code);
+    Some(reason.to_string())
+}
+
+/// `~/.mindlink`, where the tokens are kept
+fn mindlink_dir() -> MindLinkResult<PathBuf> {
+    dirs::home_dir()
+        .map(|home| home.join(".mindlink"))
+        .ok_or_else(|| MindLinkError::SystemResource {
+            message: "Cannot determine home directory".to_string(),
+            resource_type: "home directory".to_string(),
+            source: None,
+        })
+}
+
+/// Where the tokens of the Claude account are kept
+pub fn anthropic_auth_path() -> MindLinkResult<PathBuf> {
+    Ok(mindlink_dir()?.join("anthropic_auth.json"))
+}
+
+/// `auth.json` of the Codex CLI, in `$CODEX_HOME` or `~/.codex`
+pub fn codex_auth_path() -> Option<PathBuf> {
+    std::env::var_os("CODEX_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".codex")))
+        .map(|dir| dir.join("auth.json"))
+}
+
+/// Listen for the OAuth callback on `port`. When another program has it,
+/// the next `CALLBACK_PORT_ATTEMPTS - 1` ports are tried, so the redirect URI
+/// stays predictable, and then any free port.
+pub async fn bind_callback_listener(port: u16) -> Result<TcpListener> {
+    let candidates = (0..CALLBACK_PORT_ATTEMPTS).filter_map(|offset| port.checked_add(offset));
+    for candidate in candidates {
+        match TcpListener::bind(("127.0.0.1", candidate)).await {
+            Ok(listener) => {
+                if candidate != port {
+                    log_warn!(
+                        "AuthManager",
+                        &format!(
+                            "OAuth redirect port {} is in use, using port {}",
+                            port, candidate
+                        )
+                    );
+                }
+                return Ok(listener);
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {},
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    log_warn!(
+        "AuthManager",
+        &format!(
+            "OAuth redirect ports {} to {} are in use, using a free port",
+            port,
+            port.saturating_add(CALLBACK_PORT_ATTEMPTS - 1)
+        )
+    );
+    Ok(TcpListener::bind(("127.0.0.1", 0)).await?)
+}
+
+/// Path the OAuth callback is served at. With a `path_token`, only the login
+/// it was made for knows the path, so nothing else on this machine can
+/// deliver a callback to it.
+pub fn callback_path(path_token: Option<&str>) -> String {
+    match path_token {
+        Some(token) => format!("/auth/callback/{}", token),
+        None => "/auth/callback".to_string(),
+    }
+}
+
+/// Serve the OAuth callback at `path` until one with `state` arrives or
+/// `timeout` passes, and return its authorization code. Callbacks with
+/// another state, or arriving after the one that counted, are answered with
+/// a page saying so and otherwise ignored. The listener is closed before
+/// this returns, whatever the outcome.
+pub async fn await_callback(
+    listener: TcpListener,
+    path: &str,
+    state: &str,
+    timeout: std::time::Duration,
+) -> Result<String> {
+    let oauth_state = Arc::new(OAuthState::new(state.to_string()));
+    let app = Router::new().route(
+        path,
+        get({
+            let oauth_state = oauth_state.clone();
+            move |query: Query<AuthCallbackQuery>| handle_callback(query, oauth_state)
+        }),
+    );
+    let server = CallbackServer::spawn(listener, app);
+
+    let result = tokio::select! {
+        result = wait_for_callback(&oauth_state) => result,
+        _ = tokio::time::sleep(timeout) => Err(anyhow!(
+            "Authentication timed out after {} seconds",
+            timeout.as_secs()
+        )),
+    };
+    server.close().await;
+    result
+}
+
+async fn wait_for_callback(oauth_state: &OAuthState) -> Result<String> {
+    loop {
+        // Check if we received the auth result
+        if let Some(result) = oauth_state.auth_result.read().await.as_ref() {
+            return match result {
+                Ok(code) => Ok(code.clone()),
+                Err(e) => Err(anyhow!("{}", e)),
+            };
+        }
+
+        // Sleep for a short time before checking again
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+}
+
+async fn handle_callback(
+    Query(query): Query<AuthCallbackQuery>,
+    oauth_state: Arc<OAuthState>,
+) -> Html<&'static str> {
+    println!("📨 Received OAuth callback");
+
+    let mut auth_result = oauth_state.auth_result.write().await;
+
+    // Only the first callback of the login counts
+    if auth_result.is_some() {
+        log_warn!(
+            "AuthManager",
+            "Ignored an OAuth callback for a login that already received one"
+        );
+        return Html(CALLBACK_PAGE_REUSED);
+    }
+
+    // A callback of another login, e.g. from an old tab, leaves this one waiting
+    if query.state.as_deref() != Some(oauth_state.state.as_str()) {
+        log_warn!(
+            "AuthManager",
+            "Ignored an OAuth callback whose state does not match the login in progress"
+        );
+        return Html(CALLBACK_PAGE_STATE_MISMATCH);
+    }
+
+    // Check for errors
+    if let Some(error) = &query.error {
+        let error_desc = query
+            .error_description
+            .as_deref()
+            .unwrap_or("Unknown error");
+        *auth_result = Some(Err(
+            anyhow!("OAuth error: {} - {}", error, error_desc).into()
+        ));
+        return Html(SUCCESS_PAGE_ERROR);
+    }
+
+    // Extract authorization code
+    if let Some(code) = &query.code {
+        *auth_result = Some(Ok(code.clone()));
+        Html(SUCCESS_PAGE_SUCCESS)
+    } else {
+        *auth_result = Some(Err(anyhow!("Missing authorization code").into()));
+        Html(SUCCESS_PAGE_ERROR)
+    }
+}
+
+/// How a login or refresh ended, and when
+#[derive(Debug, Clone)]
+struct Landed {
+    at: Instant,
+    outcome: std::result::Result<(), String>,
+}
+
+impl Landed {
+    fn now(result: &Result<()>) -> Self {
+        Self {
+            at: Instant::now(),
+            outcome: result.as_ref().map(|_| ()).map_err(ToString::to_string),
+        }
+    }
+
+    /// The outcome of `landed` if it finished after `since`
+    fn after(landed: Option<&Landed>, since: Instant) -> Option<Result<()>> {
+        landed
+            .filter(|landed| landed.at > since)
+            .map(|landed| landed.outcome.clone().map_err(|e| anyhow!(e)))
+    }
+}
+
 #[derive(Debug)]
 pub struct AuthManager {
     auth_path: PathBuf,
     tokens: Option<AuthTokens>,
+    /// Tokens of the signed-in accounts other than the active one, one file
+    /// per account ID. Only the primary login keeps other accounts.
+    accounts_dir: Option<PathBuf>,
+    provider: AuthProvider,
+    config: AuthConfig,
+    events: broadcast::Sender<AuthEvent>,
+    last_login: Option<Landed>,
+    last_refresh: Option<Landed>,
+    /// Why the refresh token was rejected, until the next login
+    reauth_reason: Option<String>,
 }
 
 impl AuthManager {
     /// Create a new AuthManager with proper error handling and token validation
     pub async fn new() -> MindLinkResult<Self> {
-        let auth_dir = dirs::home_dir()
-            .ok_or_else(|| MindLinkError::SystemResource {
-                message: "Cannot determine home directory".to_string(),
-                resource_type: "home directory".to_string(),
-                source: None,
-            })?
-            .join(".mindlink");
+        let auth_dir = mindlink_dir()?;
+
+        let mut manager = Self::open(auth_dir.join("auth.json"))
+            .await?
+            .with_saved_accounts(auth_dir.join("chatgpt_accounts"));
+
+        // Users of the Codex CLI are signed in already
+        if manager.tokens.is_none() {
+            if let Some(path) = codex_auth_path().filter(|path| path.exists()) {
+                if let Err(e) = manager.import_codex_login(&path).await {
+                    log_warn!(
+                        "AuthManager",
+                        &format!("Could not import the Codex CLI login: {}", e)
+                    );
+                }
+            }
+        }
+        Ok(manager)
+    }
+
+    /// Keep the tokens of accounts switched away from in `accounts_dir`
+    pub fn with_saved_accounts(mut self, accounts_dir: PathBuf) -> Self {
+        self.accounts_dir = Some(accounts_dir);
+        self
+    }
+
+    /// Sign in as configured by `config`, e.g. with a device code on a
+    /// headless machine or as another OAuth client
+    pub fn with_config(mut self, config: AuthConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    pub fn provider(&self) -> AuthProvider {
+        self.provider
+    }
+
+    pub fn config(&self) -> &AuthConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: AuthConfig) {
+        self.config = config;
+    }
+
+    /// Receive the progress of logins and failed refreshes from now on
+    pub fn subscribe_events(&self) -> broadcast::Receiver<AuthEvent> {
+        self.events.subscribe()
+    }
+
+    fn emit(&self, event: AuthEvent) {
+        // Nobody listening is fine
+        let _ = self.events.send(event);
+    }
+
+    /// Why the user has to log in again, if the refresh token was rejected.
+    /// Requests are not sent upstream meanwhile.
+    pub fn reauth_required(&self) -> Option<&str> {
+        self.reauth_reason.as_deref()
+    }
+
+    fn require_reauth(&mut self, reason: String) {
+        log_warn!(
+            "AuthManager",
+            &format!(
+                "The {} refresh token was rejected, log in again: {}",
+                self.provider.name(),
+                reason
+            )
+        );
+        self.emit(AuthEvent::ReauthRequired {
+            reason: reason.clone(),
+        });
+        self.reauth_reason = Some(reason);
+    }
+
+    fn reauth_error(&self) -> Option<anyhow::Error> {
+        self.reauth_reason.as_ref().map(|reason| {
+            anyhow!(
+                "The {} session ended ({}), log in again",
+                self.provider.name(),
+                reason
+            )
+        })
+    }
+
+    /// Open the account whose tokens are stored at `auth_path`, such as an
+    /// additional account of the load-balancing pool
+    pub async fn open(auth_path: PathBuf) -> MindLinkResult<Self> {
+        Self::open_as(auth_path, AuthProvider::ChatGpt, AuthConfig::default()).await
+    }
 
-        let auth_path = auth_dir.join("auth.json");
+    /// Open the Claude account whose tokens are stored at `auth_path`,
+    /// signing in as `config` says
+    pub async fn open_anthropic(auth_path: PathBuf, config: AuthConfig) -> MindLinkResult<Self> {
+        Self::open_as(auth_path, AuthProvider::Anthropic, config).await
+    }
 
+    async fn open_as(
+        auth_path: PathBuf,
+        provider: AuthProvider,
+        config: AuthConfig,
+    ) -> MindLinkResult<Self> {
         // Ensure directory exists
-        fs::create_dir_all(&auth_dir)
-            .await
-            .map_err(|e| MindLinkError::FileSystem {
-                message: "Failed to create auth directory".to_string(),
-                path: Some(auth_dir.to_string_lossy().to_string()),
-                operation: "create directory".to_string(),
-                source: Some(e.into()),
-            })?;
+        if let Some(auth_dir) = auth_path.parent() {
+            fs::create_dir_all(auth_dir)
+                .await
+                .map_err(|e| MindLinkError::FileSystem {
+                    message: "Failed to create auth directory".to_string(),
+                    path: Some(auth_dir.to_string_lossy().to_string()),
+                    operation: "create directory".to_string(),
+                    source: Some(e.into()),
+                })?;
+        }
 
         log_info!("AuthManager", "Initializing authentication system");
 
         let mut manager = Self {
             auth_path,
             tokens: None,
+            accounts_dir: None,
+            provider,
+            config,
+            events: broadcast::channel(16).0,
+            last_login: None,
+            last_refresh: None,
+            reauth_reason: None,
         };
 
         // Load and validate existing tokens
@@ -168,6 +721,9 @@ impl AuthManager {
                     },
                     Err(refresh_err) => {
                         log_error!("AuthManager", refresh_err.clone());
+                        self.emit(AuthEvent::RefreshFailed {
+                            reason: refresh_err.to_string(),
+                        });
                         Err(refresh_err)
                     },
                 }
@@ -193,22 +749,24 @@ impl AuthManager {
 
         log_info!("AuthManager", "Attempting silent token refresh");
 
-        let client = reqwest::Client::new();
+        let client = proxy::client();
 
         let mut refresh_params = HashMap::new();
         refresh_params.insert("grant_type", "refresh_token");
         refresh_params.insert("refresh_token", &current_tokens.refresh_token);
-        refresh_params.insert("client_id", CLIENT_ID);
+        refresh_params.insert("client_id", &self.config.client_id);
 
-        let response = client
-            .post(CHATGPT_TOKEN_URL)
-            .form(&refresh_params)
+        let response = self
+            .token_request(&client, &refresh_params)
             .send()
             .await
             .map_err(|e| auth_error!("Failed to send refresh token request", e))?;
 
         if !response.status().is_success() {
             let error_text = response.text().await.unwrap_or_default();
+            if let Some(reason) = rejected_refresh_token(&error_text) {
+                self.require_reauth(reason);
+            }
             return Err(auth_error!(format!("Token refresh failed: {}", error_text)));
         }
 
@@ -249,33 +807,73 @@ impl AuthManager {
     }
 
     pub async fn login(&mut self) -> Result<()> {
-        println!("🔐 Starting ChatGPT OAuth2 PKCE authentication flow...");
+        let result = self.sign_in().await;
+        self.last_login = Some(Landed::now(&result));
+        result
+    }
+
+    /// Log in, unless a login finished after `since`, whose outcome is
+    /// returned instead
+    ///
+    /// Only the holder of the write lock on the manager can log in, so
+    /// callers take `since` before waiting for the lock. Whoever waited out
+    /// a login started by someone else shares its result rather than
+    /// running a second OAuth flow.
+    pub async fn login_once(&mut self, since: Instant) -> Result<()> {
+        match Landed::after(self.last_login.as_ref(), since) {
+            Some(outcome) => outcome,
+            None => self.login().await,
+        }
+    }
+
+    async fn sign_in(&mut self) -> Result<()> {
+        self.emit(AuthEvent::LoginStarted);
+        let result = match (self.config.login_flow, self.provider) {
+            (LoginFlow::Browser, _) | (_, AuthProvider::Anthropic) => self.browser_login().await,
+            (LoginFlow::DeviceCode, AuthProvider::ChatGpt) => self.device_code_login().await,
+        };
+        let tokens = result.inspect_err(|e| {
+            self.emit(AuthEvent::LoginFailed {
+                reason: e.to_string(),
+            });
+        })?;
+
+        // Store tokens
+        self.tokens = Some(tokens);
+        self.reauth_reason = None;
+        self.save_tokens().await?;
+
+        println!("✅ {} authentication successful!", self.provider.name());
+        Ok(())
+    }
+
+    /// Sign in in the browser of this machine, which redirects to a local
+    /// callback server
+    async fn browser_login(&self) -> Result<AuthTokens> {
+        println!(
+            "🔐 Starting {} OAuth2 PKCE authentication flow...",
+            self.provider.name()
+        );
 
         // Generate PKCE parameters
         let code_verifier = Self::generate_code_verifier();
         let code_challenge = Self::generate_code_challenge(&code_verifier)?;
         let state = Self::generate_state();
 
-        // Use fixed port for Codex CLI compatibility
-        let redirect_uri = format!("http://localhost:{}/auth/callback", REDIRECT_PORT);
-        let listener = TcpListener::bind(format!("127.0.0.1:{}", REDIRECT_PORT)).await?;
+        let listener = bind_callback_listener(self.config.redirect_port).await?;
+        let port = listener.local_addr()?.port();
+        let path_token = self.config.callback_path_token.then(Self::generate_state);
+        let path = callback_path(path_token.as_deref());
+        let redirect_uri = format!("http://localhost:{}{}", port, path);
+
+        println!("📡 Starting local callback server on port {}", port);
 
+        let auth_url = self.build_auth_url(&redirect_uri, &code_challenge, &state)?;
         println!(
-            "📡 Starting local callback server on port {}",
-            REDIRECT_PORT
+            "🌐 Opening browser for {} authentication...",
+            self.provider.name()
         );
 
-        // Prepare OAuth state
-        let oauth_state = Arc::new(OAuthState {
-            code_verifier: code_verifier.clone(),
-            state: state.clone(),
-            auth_result: Arc::new(RwLock::new(None)),
-        });
-
-        // Build authorization URL for ChatGPT
-        let auth_url = Self::build_chatgpt_auth_url(&redirect_uri, &code_challenge, &state)?;
-        println!("🌐 Opening browser for ChatGPT authentication...");
-
         // Open browser using system command
         if let Err(e) = Self::open_browser(&auth_url).await {
             println!(
@@ -284,24 +882,93 @@ impl AuthManager {
             );
             println!("    {}", auth_url);
         }
+        self.emit(AuthEvent::BrowserOpened {
+            url: auth_url.clone(),
+        });
 
         // Start callback server and wait for response
-        let auth_code = self.handle_callback_server(listener, oauth_state).await?;
+        println!("⏳ Waiting for authentication callback...");
+        let auth_code = await_callback(listener, &path, &state, CALLBACK_TIMEOUT).await?;
+        self.emit(AuthEvent::CallbackReceived);
 
         // Exchange authorization code for tokens
-        let tokens = self
-            .exchange_code_for_chatgpt_tokens(&auth_code, &code_verifier, &redirect_uri)
-            .await?;
+        self.exchange_code_for_tokens(&auth_code, &code_verifier, &redirect_uri, &state)
+            .await
+    }
 
-        // Store tokens
-        self.tokens = Some(tokens);
-        self.save_tokens().await?;
+    /// Sign in on another device with a code printed here, for machines
+    /// without a browser
+    async fn device_code_login(&self) -> Result<AuthTokens> {
+        println!("🔐 Starting ChatGPT device code authentication flow...");
 
-        println!("✅ ChatGPT authentication successful!");
-        Ok(())
+        let client = proxy::client();
+        let code = device_auth::request_code(
+            &client,
+            device_auth::DEVICE_AUTH_URL,
+            &self.config.client_id,
+        )
+        .await?;
+
+        println!("🌐 On any device, open {}", device_auth::VERIFICATION_URL);
+        println!("    and enter the code: {}", code.user_code);
+        self.emit(AuthEvent::DeviceCodeIssued {
+            verification_url: device_auth::VERIFICATION_URL.to_string(),
+            user_code: code.user_code.clone(),
+        });
+        log_info!(
+            "AuthManager",
+            &format!(
+                "Waiting for device code {} to be entered at {}",
+                code.user_code,
+                device_auth::VERIFICATION_URL
+            )
+        );
+
+        let authorization = device_auth::wait_for_authorization(
+            &client,
+            device_auth::DEVICE_AUTH_URL,
+            &code,
+            device_auth::CODE_LIFETIME,
+        )
+        .await?;
+
+        self.exchange_code_for_tokens(
+            &authorization.authorization_code,
+            &authorization.code_verifier,
+            device_auth::REDIRECT_URI,
+            "",
+        )
+        .await
     }
 
     pub async fn refresh_tokens(&mut self) -> Result<()> {
+        // A rejected refresh token is not sent again
+        if let Some(e) = self.reauth_error() {
+            return Err(e);
+        }
+        let result = self.request_refreshed_tokens().await;
+        if let Err(e) = &result {
+            self.emit(AuthEvent::RefreshFailed {
+                reason: e.to_string(),
+            });
+        }
+        self.last_refresh = Some(Landed::now(&result));
+        result
+    }
+
+    /// Refresh the tokens, unless a refresh or login finished after `since`,
+    /// whose outcome is returned instead. See [`Self::login_once`].
+    pub async fn refresh_once(&mut self, since: Instant) -> Result<()> {
+        if let Some(outcome) = Landed::after(self.last_refresh.as_ref(), since) {
+            return outcome;
+        }
+        if matches!(Landed::after(self.last_login.as_ref(), since), Some(Ok(()))) {
+            return Ok(());
+        }
+        self.refresh_tokens().await
+    }
+
+    async fn request_refreshed_tokens(&mut self) -> Result<()> {
         let tokens = self
             .tokens
             .as_ref()
@@ -309,18 +976,13 @@ impl AuthManager {
 
         println!("🔄 Refreshing authentication tokens...");
 
-        let client = reqwest::Client::new();
+        let client = proxy::client();
         let mut form_params = HashMap::new();
         form_params.insert("grant_type", "refresh_token");
         form_params.insert("refresh_token", &tokens.refresh_token);
-        form_params.insert("client_id", CLIENT_ID);
+        form_params.insert("client_id", &self.config.client_id);
 
-        let response = client
-            .post(CHATGPT_TOKEN_URL)
-            .form(&form_params)
-            .header("Content-Type", "application/x-www-form-urlencoded")
-            .send()
-            .await?;
+        let response = self.token_request(&client, &form_params).send().await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -328,6 +990,9 @@ impl AuthManager {
                 .text()
                 .await
                 .unwrap_or_else(|_| "Unknown error".to_string());
+            if let Some(reason) = rejected_refresh_token(&error_text) {
+                self.require_reauth(reason);
+            }
             return Err(anyhow!("Token refresh failed: {} - {}", status, error_text));
         }
 
@@ -358,17 +1023,47 @@ impl AuthManager {
     }
 
     pub async fn logout(&mut self) -> Result<()> {
-        self.tokens = None;
-
-        // Remove auth file
-        if self.auth_path.exists() {
-            fs::remove_file(&self.auth_path).await?;
+        if let Some(tokens) = self.tokens.take() {
+            self.revoke(&tokens).await;
         }
+        // Outcomes of earlier logins are not shared past a logout
+        self.last_login = None;
+        self.last_refresh = None;
+        self.reauth_reason = None;
+
+        // Remove the tokens, from the keyring as well
+        token_store::remove(&self.auth_path).await?;
 
         println!("Logged out successfully");
         Ok(())
     }
 
+    /// Revoke `tokens` upstream. Best-effort: logging out goes on when the
+    /// OAuth server cannot be reached or refuses.
+    async fn revoke(&self, tokens: &AuthTokens) {
+        let Some(url) = self.provider.revoke_url() else {
+            return;
+        };
+        // Revoking the refresh token ends the whole grant, access token included
+        let (token, hint) = if tokens.refresh_token.is_empty() {
+            (&tokens.access_token, "access_token")
+        } else {
+            (&tokens.refresh_token, "refresh_token")
+        };
+        let client = proxy::client();
+        match revoke_token(&client, url, &self.config.client_id, token, hint).await {
+            Ok(()) => log_info!("AuthManager", "Revoked the tokens on logout"),
+            Err(e) => log_warn!(
+                "AuthManager",
+                &format!(
+                    "Could not revoke the {} tokens on logout: {}",
+                    self.provider.name(),
+                    e
+                )
+            ),
+        }
+    }
+
     pub fn get_access_token(&self) -> Option<&str> {
         self.tokens.as_ref().map(|t| t.access_token.as_str())
     }
@@ -379,7 +1074,9 @@ impl AuthManager {
     }
 
     async fn load_tokens(&mut self) -> Result<()> {
-        let content = fs::read_to_string(&self.auth_path).await?;
+        let content = token_store::read(&self.auth_path)
+            .await?
+            .ok_or_else(|| anyhow!("No tokens saved"))?;
 
         // First try to deserialize with the new format (with token_type field)
         match serde_json::from_str::<AuthTokens>(&content) {
@@ -421,23 +1118,265 @@ impl AuthManager {
     async fn save_tokens(&self) -> Result<()> {
         if let Some(tokens) = &self.tokens {
             let json = serde_json::to_string_pretty(tokens)?;
-            fs::write(&self.auth_path, json).await?;
+            token_store::write(&self.auth_path, &json).await?;
+            self.emit(AuthEvent::TokensSaved {
+                account_id: tokens.account_id.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// File keeping the tokens of `account_id` while it is not active
+    fn saved_account_path(&self, account_id: &str) -> Result<PathBuf> {
+        let accounts_dir = self
+            .accounts_dir
+            .as_ref()
+            .ok_or_else(|| anyhow!("This login does not keep other accounts"))?;
+        let valid = !account_id.is_empty()
+            && account_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(anyhow!("Invalid account ID '{}'", account_id));
+        }
+        Ok(accounts_dir.join(format!("{}.json", account_id)))
+    }
+
+    /// Keep the tokens of the active account, before switching away from it
+    async fn save_active_account(&self) -> Result<()> {
+        let Some(tokens) = &self.tokens else {
+            return Ok(());
+        };
+        if tokens.account_id.is_empty() {
+            return Err(anyhow!(
+                "The signed-in account has no account ID; sign in again before switching accounts"
+            ));
+        }
+        let path = self.saved_account_path(&tokens.account_id)?;
+        token_store::write(&path, &serde_json::to_string_pretty(tokens)?).await
+    }
+
+    /// Forget the kept tokens of `account_id`. Returns whether there were any.
+    async fn forget_saved_account(&self, account_id: &str) -> Result<bool> {
+        token_store::remove(&self.saved_account_path(account_id)?).await
+    }
+
+    /// The active account followed by the other signed-in accounts
+    pub async fn accounts(&self) -> Result<Vec<SavedAccount>> {
+        let mut accounts: Vec<SavedAccount> = self
+            .tokens
+            .iter()
+            .map(|tokens| SavedAccount::new(tokens, true))
+            .collect();
+        let Some(accounts_dir) = &self.accounts_dir else {
+            return Ok(accounts);
+        };
+        let mut entries = match fs::read_dir(accounts_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(accounts),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut others = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let Ok(Some(content)) = token_store::read(&path).await else {
+                continue;
+            };
+            match serde_json::from_str::<AuthTokens>(&content) {
+                Ok(tokens) if accounts.iter().all(|a| a.account_id != tokens.account_id) => {
+                    others.push(SavedAccount::new(&tokens, false));
+                },
+                Ok(_) => {},
+                Err(e) => {
+                    log_warn!(
+                        "AuthManager",
+                        &format!(
+                            "Skipping unreadable saved account {}: {}",
+                            path.display(),
+                            e
+                        )
+                    );
+                },
+            }
+        }
+        others.sort_by(|a, b| a.account_id.cmp(&b.account_id));
+        accounts.extend(others);
+        Ok(accounts)
+    }
+
+    /// Sign in another account and make it the active one. The account
+    /// signed in before is kept and can be selected again. Returns the ID of
+    /// the new account.
+    pub async fn add_account(&mut self) -> Result<String> {
+        self.save_active_account().await?;
+        let previous = self.tokens.take();
+
+        if let Err(e) = self.login().await {
+            if let Some(previous) = &previous {
+                let _ = self.forget_saved_account(&previous.account_id).await;
+            }
+            self.tokens = previous;
+            return Err(e);
+        }
+
+        let account_id = self
+            .tokens
+            .as_ref()
+            .map(|tokens| tokens.account_id.clone())
+            .unwrap_or_default();
+        // Signing in to an account that was kept makes its fresh tokens the
+        // only ones
+        self.forget_saved_account(&account_id).await?;
+        log_info!("AuthManager", &format!("Added account {}", account_id));
+        Ok(account_id)
+    }
+
+    /// Make the kept account `account_id` the active one
+    pub async fn select_account(&mut self, account_id: &str) -> Result<()> {
+        if self
+            .tokens
+            .as_ref()
+            .is_some_and(|tokens| tokens.account_id == account_id)
+        {
+            return Ok(());
+        }
+
+        let path = self.saved_account_path(account_id)?;
+        let content = token_store::read(&path)
+            .await?
+            .ok_or_else(|| anyhow!("No saved account '{}'", account_id))?;
+        let tokens: AuthTokens = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Saved account '{}' is unreadable: {}", account_id, e))?;
+
+        self.save_active_account().await?;
+        self.tokens = Some(tokens);
+        self.reauth_reason = None;
+        self.save_tokens().await?;
+        self.forget_saved_account(account_id).await?;
+        log_info!(
+            "AuthManager",
+            &format!("Switched to account {}", account_id)
+        );
+
+        // Refreshes tokens that expired while the account was not in use
+        if let Err(e) = self.validate_tokens_on_startup().await {
+            log_error!("AuthManager", e);
+        }
+        Ok(())
+    }
+
+    /// Sign out of `account_id`. Removing the active account leaves no
+    /// account active until another is selected or signed in.
+    pub async fn remove_account(&mut self, account_id: &str) -> Result<()> {
+        if self
+            .tokens
+            .as_ref()
+            .is_some_and(|tokens| tokens.account_id == account_id)
+        {
+            return self.logout().await;
+        }
+        if !self.forget_saved_account(account_id).await? {
+            return Err(anyhow!("No saved account '{}'", account_id));
         }
+        log_info!("AuthManager", &format!("Removed account {}", account_id));
         Ok(())
     }
 
-    pub async fn ensure_valid_tokens(&mut self) -> Result<()> {
+    /// Take over the ChatGPT login of the Codex CLI from its `auth.json`, so
+    /// users of both do not sign in twice. The account active before is kept
+    /// for `select_account`. Both apps refresh the same session afterwards,
+    /// so the Codex CLI may ask to sign in again once MindLink refreshed it.
+    pub async fn import_codex_login(&mut self, path: &Path) -> Result<()> {
+        #[derive(Deserialize)]
+        struct CodexAuth {
+            tokens: Option<CodexTokens>,
+        }
+
+        #[derive(Deserialize)]
+        struct CodexTokens {
+            id_token: String,
+            access_token: String,
+            refresh_token: String,
+            account_id: Option<String>,
+        }
+
+        let content = fs::read_to_string(path).await?;
+        let codex: CodexAuth = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Unreadable Codex CLI login {}: {}", path.display(), e))?;
+        let tokens = codex.tokens.ok_or_else(|| {
+            anyhow!("The Codex CLI is signed in with an API key, not a ChatGPT account")
+        })?;
+
+        // The access token is a JWT too; one of unknown age is refreshed
+        let expires_at = Self::id_token_claims(&tokens.access_token)
+            .ok()
+            .and_then(|claims| claims.get("exp")?.as_i64())
+            .and_then(|exp| DateTime::from_timestamp(exp, 0))
+            .unwrap_or_else(Utc::now);
+        let account_id = match tokens.account_id.filter(|id| !id.is_empty()) {
+            Some(account_id) => account_id,
+            None => Self::extract_account_id_from_id_token(&tokens.id_token).unwrap_or_default(),
+        };
+
+        if self.accounts_dir.is_some() {
+            self.save_active_account().await?;
+            if !account_id.is_empty() {
+                self.forget_saved_account(&account_id).await?;
+            }
+        }
+        self.tokens = Some(AuthTokens {
+            access_token: tokens.access_token,
+            refresh_token: tokens.refresh_token,
+            id_token: tokens.id_token,
+            expires_at,
+            token_type: "Bearer".to_string(),
+            account_id,
+        });
+        self.reauth_reason = None;
+        self.save_tokens().await?;
+        log_info!(
+            "AuthManager",
+            &format!("Imported the Codex CLI login from {}", path.display())
+        );
+
+        if let Err(e) = self.validate_tokens_on_startup().await {
+            log_error!("AuthManager", e);
+        }
+        Ok(())
+    }
+
+    /// Refresh or log in unless the tokens are valid. Callers that waited
+    /// for the lock since `since` share the outcome of the refresh or login
+    /// that ran meanwhile, see [`Self::login_once`]. Once the refresh token
+    /// was rejected, fails without either, see [`Self::reauth_required`].
+    pub async fn ensure_valid_tokens(&mut self, since: Instant) -> Result<()> {
+        // Requests neither use the ended session nor open a login; the user
+        // is asked to log in
+        if let Some(e) = self.reauth_error() {
+            return Err(e);
+        }
         if !self.is_authenticated().await {
+            // A login that just failed is not retried for every waiting request
+            if let Some(Err(e)) = Landed::after(self.last_login.as_ref(), since) {
+                return Err(e);
+            }
             if self.tokens.is_some() {
                 // Try to refresh first
-                if let Err(e) = self.refresh_tokens().await {
+                if let Err(e) = self.refresh_once(since).await {
                     println!("⚠️ Token refresh failed: {}", e);
+                    if self.reauth_reason.is_some() {
+                        return Err(e);
+                    }
                     // If refresh fails, need to login again
-                    self.login().await?;
+                    self.login_once(since).await?;
                 }
             } else {
                 // No tokens, need to login
-                self.login().await?;
+                self.login_once(since).await?;
             }
         }
         Ok(())
@@ -465,21 +1404,50 @@ impl AuthManager {
         URL_SAFE_NO_PAD.encode(&bytes)
     }
 
-    fn build_chatgpt_auth_url(redirect_uri: &str, code_challenge: &str, state: &str) -> Result<String> {
-        let mut url = Url::parse(CHATGPT_AUTH_URL)?;
+    fn build_auth_url(
+        &self,
+        redirect_uri: &str,
+        code_challenge: &str,
+        state: &str,
+    ) -> Result<String> {
+        if self.config.client_id.is_empty() {
+            return Err(anyhow!(
+                "No OAuth client ID is configured for {} logins",
+                self.provider.name()
+            ));
+        }
+
+        let mut url = Url::parse(self.provider.authorize_url())?;
         url.query_pairs_mut()
             .append_pair("response_type", "code")
-            .append_pair("client_id", CLIENT_ID)
+            .append_pair("client_id", &self.config.client_id)
             .append_pair("redirect_uri", redirect_uri)
-            .append_pair("scope", SCOPE)
+            .append_pair("scope", &self.config.scopes.join(" "))
             .append_pair("state", state)
             .append_pair("code_challenge", code_challenge)
-            .append_pair("code_challenge_method", "S256")
-            .append_pair("id_token_add_organizations", "true")
-            .append_pair("codex_cli_simplified_flow", "true"); // Critical for Codex CLI access
+            .append_pair("code_challenge_method", "S256");
+        if self.provider == AuthProvider::ChatGpt {
+            url.query_pairs_mut()
+                .append_pair("id_token_add_organizations", "true")
+                .append_pair("codex_cli_simplified_flow", "true"); // Critical for Codex CLI access
+        }
         Ok(url.to_string())
     }
 
+    /// POST of `params` to the token endpoint, as a form for OpenAI and as
+    /// JSON for Anthropic
+    fn token_request(
+        &self,
+        client: &reqwest::Client,
+        params: &HashMap<&str, &str>,
+    ) -> reqwest::RequestBuilder {
+        let request = client.post(self.provider.token_url());
+        match self.provider {
+            AuthProvider::ChatGpt => request.form(params),
+            AuthProvider::Anthropic => request.json(params),
+        }
+    }
+
     async fn open_browser(url: &str) -> Result<()> {
         // Use Tauri's opener plugin for better compatibility
         println!("🌐 Opening OAuth URL in default browser: {}", url);
@@ -491,125 +1459,32 @@ impl AuthManager {
         Ok(())
     }
 
-    async fn handle_callback_server(
-        &self,
-        listener: TcpListener,
-        oauth_state: Arc<OAuthState>,
-    ) -> Result<String> {
-        println!("⏳ Waiting for authentication callback...");
-
-        // Create the callback router
-        let app = Router::new().route(
-            "/auth/callback",
-            get({
-                let oauth_state = oauth_state.clone();
-                move |query: Query<AuthCallbackQuery>| Self::handle_callback(query, oauth_state)
-            }),
-        );
-
-        // Use axum's serve function with our listener
-        let server = axum::serve(listener, app);
-
-        // Set a timeout for the authentication process
-        let timeout_duration = std::time::Duration::from_secs(300); // 5 minutes
-
-        let oauth_state_clone = oauth_state.clone();
-
-        // Start the server in the background
-        tokio::spawn(async move {
-            if let Err(e) = server.await {
-                println!("Server error: {}", e);
-            }
-        });
-
-        // Wait for the callback result
-        tokio::select! {
-            result = self.wait_for_callback(oauth_state_clone) => {
-                result
-            }
-            _ = tokio::time::sleep(timeout_duration) => {
-                Err(anyhow!("Authentication timed out after 5 minutes"))
-            }
-        }
-    }
-
-    async fn wait_for_callback(&self, oauth_state: Arc<OAuthState>) -> Result<String> {
-        loop {
-            // Check if we received the auth result
-            if let Some(result) = oauth_state.auth_result.read().await.as_ref() {
-                return result
-                    .as_ref()
-                    .map(|s| s.clone())
-                    .map_err(|e| anyhow!("{}", e));
-            }
-
-            // Sleep for a short time before checking again
-            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
-        }
-    }
-
-    async fn handle_callback(
-        Query(query): Query<AuthCallbackQuery>,
-        oauth_state: Arc<OAuthState>,
-    ) -> Html<&'static str> {
-        println!("📨 Received ChatGPT authentication callback");
-
-        let mut auth_result = oauth_state.auth_result.write().await;
-
-        // Verify state parameter
-        if let Some(state) = &query.state {
-            if state != &oauth_state.state {
-                *auth_result = Some(Err(anyhow!("Invalid state parameter").into()));
-                return Html(SUCCESS_PAGE_ERROR);
-            }
-        } else {
-            *auth_result = Some(Err(anyhow!("Missing state parameter").into()));
-            return Html(SUCCESS_PAGE_ERROR);
-        }
-
-        // Check for errors
-        if let Some(error) = &query.error {
-            let error_desc = query
-                .error_description
-                .as_deref()
-                .unwrap_or("Unknown error");
-            *auth_result = Some(Err(
-                anyhow!("ChatGPT OAuth error: {} - {}", error, error_desc).into()
-            ));
-            return Html(SUCCESS_PAGE_ERROR);
-        }
-
-        // Extract authorization code
-        if let Some(code) = &query.code {
-            *auth_result = Some(Ok(code.clone()));
-            Html(SUCCESS_PAGE_SUCCESS)
-        } else {
-            *auth_result = Some(Err(anyhow!("Missing authorization code").into()));
-            Html(SUCCESS_PAGE_ERROR)
-        }
-    }
-
-    async fn exchange_code_for_chatgpt_tokens(
+    /// Tokens for `auth_code`. Anthropic also wants the `state` of the
+    /// login, which device code logins have none of.
+    async fn exchange_code_for_tokens(
         &self,
         auth_code: &str,
         code_verifier: &str,
         redirect_uri: &str,
+        state: &str,
     ) -> Result<AuthTokens> {
-        println!("🔄 Exchanging authorization code for ChatGPT tokens...");
+        println!(
+            "🔄 Exchanging authorization code for {} tokens...",
+            self.provider.name()
+        );
 
-        let client = reqwest::Client::new();
+        let client = proxy::client();
         let mut form_params = HashMap::new();
         form_params.insert("grant_type", "authorization_code");
-        form_params.insert("client_id", CLIENT_ID);
+        form_params.insert("client_id", &self.config.client_id);
         form_params.insert("code", auth_code);
         form_params.insert("redirect_uri", redirect_uri);
         form_params.insert("code_verifier", code_verifier);
+        if self.provider == AuthProvider::Anthropic {
+            form_params.insert("state", state);
+        }
 
-        let response = client
-            .post(CHATGPT_TOKEN_URL)
-            .form(&form_params)
-            .send()
-            .await?;
+        let response = self.token_request(&client, &form_params).send().await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -618,7 +1493,8 @@ impl AuthManager {
                 .await
                 .unwrap_or_else(|_| "Unknown error".to_string());
             return Err(anyhow!(
-                "ChatGPT token exchange failed: {} - {}",
+                "{} token exchange failed: {} - {}",
+                self.provider.name(),
                 status,
                 error_text
             ));
@@ -626,8 +1502,17 @@ impl AuthManager {
 
         let token_response: TokenResponse = response.json().await?;
 
-        // Extract account ID from ID token
-        let account_id = Self::extract_account_id_from_id_token(&token_response.id_token)?;
+        let account_id = match self.provider {
+            // Extract account ID from ID token
+            AuthProvider::ChatGpt => {
+                Self::extract_account_id_from_id_token(&token_response.id_token)?
+            },
+            AuthProvider::Anthropic => token_response
+                .account
+                .as_ref()
+                .map(|account| account.uuid.clone())
+                .unwrap_or_default(),
+        };
         
         let expires_at = if let Some(expires_in) = token_response.expires_in {
             Utc::now() + Duration::seconds(expires_in as i64)
@@ -645,8 +1530,8 @@ impl AuthManager {
         })
     }
 
-    /// Extract chatgpt_account_id from JWT ID token
-    fn extract_account_id_from_id_token(id_token: &str) -> Result<String> {
+    /// Claims of a JWT ID token
+    fn id_token_claims(id_token: &str) -> Result<Value> {
         // Decode JWT without verification (we trust the source since it came from OAuth)
         let header = decode_header(id_token)
             .map_err(|e| anyhow!("Failed to decode JWT header: {}", e))?;
@@ -665,8 +1550,15 @@ impl AuthManager {
         )
         .map_err(|e| anyhow!("Failed to decode JWT: {}", e))?;
 
+        Ok(token_data.claims)
+    }
+
+    /// Extract chatgpt_account_id from JWT ID token
+    fn extract_account_id_from_id_token(id_token: &str) -> Result<String> {
+        let claims = Self::id_token_claims(id_token)?;
+
         // Extract chatgpt_account_id from auth claims
-        let auth_claims = token_data.claims
+        let auth_claims = claims
             .get("https://api.openai.com/auth")
             .and_then(|v| v.as_object())
             .ok_or_else(|| anyhow!("Missing auth claims in ID token"))?;
@@ -685,7 +1577,7 @@ impl AuthManager {
         let tokens = self.tokens.as_ref()
             .ok_or_else(|| anyhow!("No authentication tokens available"))?;
 
-        let client = reqwest::Client::new();
+        let client = proxy::client();
         let session_id = uuid::Uuid::new_v4().to_string();
         
         let request_body = serde_json::json!({
@@ -743,16 +1635,19 @@ impl AuthManager {
         Ok(auth_url)
     }
 
+    /// The signed-in user, whether or not their tokens are still valid
+    pub fn identity(&self) -> Option<UserIdentity> {
+        self.tokens.as_ref().map(UserIdentity::from_tokens)
+    }
+
+    /// Plan of the signed-in account and the caps that come with it
+    pub fn quota(&self) -> Option<PlanQuota> {
+        self.identity().as_ref().map(PlanQuota::from_identity)
+    }
+
     /// Get current authentication status and user info
-    pub async fn get_auth_status(&self) -> (bool, Option<String>) {
-        let is_authenticated = self.is_authenticated().await;
-        let user_email = if is_authenticated {
-            // In a real implementation, you'd decode the JWT token to get user info
-            Some("user@example.com".to_string()) // Mock email for now
-        } else {
-            None
-        };
-        (is_authenticated, user_email)
+    pub async fn get_auth_status(&self) -> (bool, Option<UserIdentity>) {
+        (self.is_authenticated().await, self.identity())
     }
 }
 
@@ -805,3 +1700,51 @@ const SUCCESS_PAGE_ERROR: &str = r#"
 </body>
 </html>
 "#;
+
+const CALLBACK_PAGE_STATE_MISMATCH: &str = r#"
+<!DOCTYPE html>
+<html>
+<head>
+    <title>MindLink - Login Link Not Recognized</title>
+    <style>
+        body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, sans-serif; text-align: center; padding: 50px; background: #f0f2f5; }
+        .container { max-width: 400px; margin: 0 auto; background: white; padding: 40px; border-radius: 12px; box-shadow: 0 4px 12px rgba(0,0,0,0.1); }
+        .error { color: #d97706; font-size: 24px; margin-bottom: 16px; }
+        .message { color: #374151; margin-bottom: 24px; }
+        .retry-button { background: #d97706; color: white; border: none; padding: 12px 24px; border-radius: 8px; font-size: 16px; cursor: pointer; }
+        .retry-button:hover { background: #b45309; }
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="error">⚠️ Login Link Not Recognized</div>
+        <div class="message">This page does not belong to the login MindLink is waiting for, for example because it comes from an earlier attempt. Nothing was signed in. Finish the login in the tab MindLink opened last, or start a new one.</div>
+        <button class="retry-button" onclick="window.close()">Close This Tab</button>
+    </div>
+</body>
+</html>
+"#;
+
+const CALLBACK_PAGE_REUSED: &str = r#"
+<!DOCTYPE html>
+<html>
+<head>
+    <title>MindLink - Login Link Already Used</title>
+    <style>
+        body { font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, sans-serif; text-align: center; padding: 50px; background: #f0f2f5; }
+        .container { max-width: 400px; margin: 0 auto; background: white; padding: 40px; border-radius: 12px; box-shadow: 0 4px 12px rgba(0,0,0,0.1); }
+        .error { color: #d97706; font-size: 24px; margin-bottom: 16px; }
+        .message { color: #374151; margin-bottom: 24px; }
+        .retry-button { background: #d97706; color: white; border: none; padding: 12px 24px; border-radius: 8px; font-size: 16px; cursor: pointer; }
+        .retry-button:hover { background: #b45309; }
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="error">⚠️ Login Link Already Used</div>
+        <div class="message">MindLink already received the answer to this login. A login link only works once; return to MindLink to see how it went.</div>
+        <button class="retry-button" onclick="window.close()">Close This Tab</button>
+    </div>
+</body>
+</html>
+"#;
@@ -5,9 +5,12 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use tokio::process::Command as TokioCommand;
 
+use crate::binary_drift::{self, BinaryManifest, BinaryRecord, DriftFinding};
+use crate::cloudflared_release::{self, CloudflaredUpdate, Release};
+use crate::downloads;
 use crate::error::{MindLinkError, MindLinkResult};
 use crate::logging::get_logger;
-use crate::{log_error, log_info};
+use crate::{log_error, log_info, log_warn};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BinaryInfo {
@@ -23,6 +26,8 @@ pub struct BinaryManager {
     #[allow(dead_code)]
     data_dir: PathBuf,
     binaries_dir: PathBuf,
+    /// Release of cloudflared to run instead of the latest
+    pinned_cloudflared: Option<String>,
 }
 
 impl BinaryManager {
@@ -39,6 +44,7 @@ impl BinaryManager {
         Ok(Self {
             data_dir,
             binaries_dir,
+            pinned_cloudflared: None,
         })
     }
 
@@ -239,7 +245,9 @@ impl BinaryManager {
                 })?;
 
         // Verify binary is executable and calculate checksum
-        self.verify_binary_integrity(&binary_path).await?;
+        let checksum = self.verify_binary_integrity(&binary_path).await?;
+        self.record_binary("bifrost-http", &binary_path, checksum, false, None)
+            .await;
 
         log_info!(
             "BinaryManager",
@@ -630,28 +638,63 @@ exit 1
         Ok(())
     }
 
-    /// Ensure cloudflared is available (check PATH first, then download)
+    /// Run the cloudflared release `version` instead of the latest, or
+    /// follow the latest again with `None`
+    pub fn pin_cloudflared(&mut self, version: Option<String>) {
+        self.pinned_cloudflared = version;
+    }
+
+    pub fn pinned_cloudflared(&self) -> Option<&str> {
+        self.pinned_cloudflared.as_deref()
+    }
+
+    /// Ensure cloudflared is available (check PATH first, then download).
+    /// A pinned release only runs from the local installation.
     pub async fn ensure_cloudflared(&self) -> Result<PathBuf> {
-        // First check if cloudflared is available in PATH
-        if let Ok(output) = TokioCommand::new("cloudflared")
-            .arg("--version")
-            .output()
-            .await
-        {
-            if output.status.success() {
-                println!("Using cloudflared from PATH");
-                return Ok(PathBuf::from("cloudflared"));
+        if let Some(version) = &self.pinned_cloudflared {
+            println!(
+                "cloudflared {} is pinned, checking local installation...",
+                version
+            );
+        } else {
+            // First check if cloudflared is available in PATH
+            if let Ok(output) = TokioCommand::new("cloudflared")
+                .arg("--version")
+                .output()
+                .await
+            {
+                if output.status.success() {
+                    println!("Using cloudflared from PATH");
+                    return Ok(PathBuf::from("cloudflared"));
+                }
             }
-        }
 
-        println!("cloudflared not found in PATH, checking local installation...");
+            println!("cloudflared not found in PATH, checking local installation...");
+        }
 
-        // Check if we already have it downloaded
-        if let Some(local_path) = self.get_cloudflared_path() {
-            if self.verify_binary(&local_path).await? {
-                println!("Using local cloudflared at: {:?}", local_path);
-                return Ok(local_path);
-            }
+        // Check if we already have it downloaded. Copies that were not
+        // checked against a published checksum are never run.
+        match self.installed_cloudflared() {
+            None => {
+                if let Some(local_path) = self.get_cloudflared_path() {
+                    println!(
+                        "Local cloudflared at {:?} is not verified, downloading it again",
+                        local_path
+                    );
+                }
+            },
+            Some(record) if !self.is_pinned_release(&record) => {
+                println!(
+                    "Local cloudflared is {}, not the pinned release",
+                    record.release.as_deref().unwrap_or("an unknown release")
+                );
+            },
+            Some(record) => {
+                if self.verify_binary(&record.path).await? {
+                    println!("Using local cloudflared at: {:?}", record.path);
+                    return Ok(record.path);
+                }
+            },
         }
 
         // Download cloudflared
@@ -675,72 +718,270 @@ exit 1
         }
     }
 
-    /// Download cloudflared binary from GitHub releases
+    /// Download cloudflared from its pinned or latest GitHub release
     async fn download_cloudflared(&self) -> Result<PathBuf> {
+        let client = downloads::download_client();
+        let release = cloudflared_release::release(&client, self.pinned_cloudflared()).await?;
+        self.install_cloudflared(&client, &release).await
+    }
+
+    /// Install cloudflared from `release`. The download is written to a
+    /// `.partial` file, which is checked against the SHA-256 published with
+    /// the release before it is made executable and moved into place. It is
+    /// deleted when it does not match, and nothing is downloaded when no
+    /// checksum is found.
+    async fn install_cloudflared(
+        &self,
+        client: &reqwest::Client,
+        release: &Release,
+    ) -> Result<PathBuf> {
         let os = std::env::consts::OS;
         let arch = std::env::consts::ARCH;
 
-        // Determine download URL and filename based on platform
-        let (download_url, filename) = match (os, arch) {
-            ("linux", "x86_64") => (
-                "https://github.com/cloudflare/cloudflared/releases/latest/download/cloudflared-linux-amd64",
-                "cloudflared"
-            ),
-            ("linux", "aarch64") => (
-                "https://github.com/cloudflare/cloudflared/releases/latest/download/cloudflared-linux-arm64",
-                "cloudflared"
-            ),
-            ("macos", "x86_64") => (
-                "https://github.com/cloudflare/cloudflared/releases/latest/download/cloudflared-darwin-amd64.tgz",
-                "cloudflared"
-            ),
-            ("macos", "aarch64") => (
-                "https://github.com/cloudflare/cloudflared/releases/latest/download/cloudflared-darwin-amd64.tgz",
-                "cloudflared"
-            ),
-            ("windows", "x86_64") => (
-                "https://github.com/cloudflare/cloudflared/releases/latest/download/cloudflared-windows-amd64.exe",
-                "cloudflared.exe"
-            ),
-            ("windows", "aarch64") => (
-                "https://github.com/cloudflare/cloudflared/releases/latest/download/cloudflared-windows-386.exe",
-                "cloudflared.exe"
-            ),
-            _ => return Err(anyhow!("Unsupported platform: {}-{}", os, arch)),
+        let asset_name = cloudflared_release::asset_name(os, arch)
+            .ok_or_else(|| anyhow!("Unsupported platform: {}-{}", os, arch))?;
+        let filename = if cfg!(windows) {
+            "cloudflared.exe"
+        } else {
+            "cloudflared"
         };
 
+        // Handle compressed files (macOS uses .tgz)
+        if asset_name.ends_with(".tgz") {
+            return Err(anyhow!(
+                "Compressed downloads not yet supported. Please install cloudflared manually."
+            ));
+        }
+
+        let asset = release.asset(asset_name)?;
+        let expected = release.checksum(asset_name).ok_or_else(|| {
+            anyhow!(
+                "cloudflared {} publishes no checksum for {}; refusing to install it",
+                release.tag_name,
+                asset_name
+            )
+        })?;
+
         // Create cloudflared directory
         let cloudflared_dir = self.binaries_dir.join("cloudflared");
         fs::create_dir_all(&cloudflared_dir)?;
 
         let binary_path = cloudflared_dir.join(filename);
 
-        // Download the binary
-        println!("Downloading cloudflared from: {}", download_url);
-        let response = reqwest::get(download_url).await?;
+        // Download the binary aside and move it into place once verified, so
+        // an interrupted download can be resumed and never runs. It is named
+        // after its release, so only the same release is resumed.
+        println!(
+            "Downloading cloudflared {} from: {}",
+            release.tag_name, asset.browser_download_url
+        );
+        let partial_path =
+            cloudflared_dir.join(format!("cloudflared-{}.partial", release.tag_name));
+        downloads::download(
+            client,
+            &asset.browser_download_url,
+            &partial_path,
+            "cloudflared",
+        )
+        .await?;
+
+        let checksum = match cloudflared_release::verify(&partial_path, &expected) {
+            Ok(checksum) => checksum,
+            Err(e) => {
+                let _ = fs::remove_file(&partial_path);
+                return Err(e);
+            },
+        };
 
-        if !response.status().is_success() {
-            return Err(anyhow!(
-                "Failed to download cloudflared: HTTP {}",
-                response.status()
-            ));
+        // Make executable on Unix systems
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mut perms = fs::metadata(&partial_path)?.permissions();
+            perms.set_mode(0o755);
+            fs::set_permissions(&partial_path, perms)?;
         }
+        fs::rename(&partial_path, &binary_path)?;
 
-        let bytes = response.bytes().await?;
+        // Verify the binary works
+        if !self.verify_binary(&binary_path).await? {
+            return Err(anyhow!("Downloaded cloudflared binary is not working"));
+        }
 
-        // Handle compressed files (macOS uses .tgz)
-        if download_url.ends_with(".tgz") {
-            // For macOS, we'd need to extract the tar.gz
-            // For simplicity, let's create a direct binary download approach
-            return Err(anyhow!(
-                "Compressed downloads not yet supported. Please install cloudflared manually."
-            ));
+        println!(
+            "cloudflared {} downloaded and verified successfully (SHA-256 {})",
+            release.tag_name, checksum
+        );
+        self.record_binary(
+            "cloudflared",
+            &binary_path,
+            checksum,
+            true,
+            Some(&release.tag_name),
+        )
+        .await;
+        Ok(binary_path)
+    }
+
+    /// Whether `installed` is at least as current as `release`: the same
+    /// release when one is pinned, else no older
+    fn is_current(&self, installed: &BinaryRecord, release: &Release) -> bool {
+        let installed = installed.release.as_deref().unwrap_or_default();
+        if self.pinned_cloudflared.is_some() {
+            installed == release.tag_name
+        } else {
+            !cloudflared_release::is_newer(&release.tag_name, installed)
+        }
+    }
+
+    fn is_pinned_release(&self, installed: &BinaryRecord) -> bool {
+        self.pinned_cloudflared
+            .as_deref()
+            .is_none_or(|pinned| installed.release.as_deref() == Some(pinned))
+    }
+
+    /// The release the local cloudflared should be updated to: the pinned
+    /// one, or a newer latest one. `None` when it is current or there is no
+    /// verified local cloudflared to update.
+    pub async fn pending_cloudflared_update(&self) -> Result<Option<Release>> {
+        let Some(installed) = self.installed_cloudflared() else {
+            return Ok(None);
+        };
+        let client = downloads::download_client();
+        let release = cloudflared_release::release(&client, self.pinned_cloudflared()).await?;
+        Ok((!self.is_current(&installed, &release)).then_some(release))
+    }
+
+    /// Bring the local cloudflared to the pinned or latest release,
+    /// installing it when there is none. The copy on PATH, which runs
+    /// instead unless a release is pinned, is left to whatever installed it.
+    pub async fn update_cloudflared(&self) -> Result<CloudflaredUpdate> {
+        if self.pinned_cloudflared.is_none() {
+            let on_path = std::env::var_os("PATH")
+                .and_then(|search_path| binary_drift::find_in_path("cloudflared", &search_path));
+            if let Some(path) = on_path {
+                return Err(anyhow!(
+                    "cloudflared at {} runs instead of MindLink's copy; update it with whatever \
+                     installed it, or pin a release",
+                    path.display()
+                ));
+            }
+        }
+
+        let installed = self.installed_cloudflared();
+        let client = downloads::download_client();
+        let release = cloudflared_release::release(&client, self.pinned_cloudflared()).await?;
+        let previous = installed.as_ref().and_then(|record| record.release.clone());
+        let updated = !installed.is_some_and(|record| self.is_current(&record, &release));
+        if updated {
+            self.install_cloudflared(&client, &release).await?;
+        }
+
+        Ok(CloudflaredUpdate {
+            installed: match (&previous, updated) {
+                (Some(previous), false) => previous.clone(),
+                _ => release.tag_name,
+            },
+            previous,
+            updated,
+            pinned: self.pinned_cloudflared.is_some(),
+        })
+    }
+
+    /// Ensure ngrok is available (check PATH first, then download)
+    pub async fn ensure_ngrok(&self) -> Result<PathBuf> {
+        if let Ok(output) = TokioCommand::new("ngrok").arg("--version").output().await {
+            if output.status.success() {
+                println!("Using ngrok from PATH");
+                return Ok(PathBuf::from("ngrok"));
+            }
+        }
+
+        if let Some(local_path) = self.get_ngrok_path() {
+            if self.verify_binary(&local_path).await? {
+                println!("Using local ngrok at: {:?}", local_path);
+                return Ok(local_path);
+            }
+        }
+
+        println!("Downloading ngrok...");
+        self.download_ngrok().await
+    }
+
+    /// Get the path to the local ngrok binary
+    pub fn get_ngrok_path(&self) -> Option<PathBuf> {
+        let binary_name = if cfg!(windows) { "ngrok.exe" } else { "ngrok" };
+        let ngrok_path = self.binaries_dir.join("ngrok").join(binary_name);
+
+        if ngrok_path.exists() {
+            Some(ngrok_path)
         } else {
-            // Direct binary download
-            fs::write(&binary_path, bytes)?;
+            None
         }
+    }
+
+    /// Download the ngrok agent. It is only published as an archive, which is
+    /// unpacked with the system's `tar`: bsdtar on macOS and Windows reads
+    /// zip, and the Linux builds come as tgz.
+    async fn download_ngrok(&self) -> Result<PathBuf> {
+        let os = std::env::consts::OS;
+        let arch = std::env::consts::ARCH;
+
+        let archive = match (os, arch) {
+            ("linux", "x86_64") => "ngrok-v3-stable-linux-amd64.tgz",
+            ("linux", "aarch64") => "ngrok-v3-stable-linux-arm64.tgz",
+            ("macos", "x86_64") => "ngrok-v3-stable-darwin-amd64.zip",
+            ("macos", "aarch64") => "ngrok-v3-stable-darwin-arm64.zip",
+            ("windows", "x86_64") => "ngrok-v3-stable-windows-amd64.zip",
+            ("windows", "aarch64") => "ngrok-v3-stable-windows-arm64.zip",
+            _ => return Err(anyhow!("Unsupported platform: {}-{}", os, arch)),
+        };
+        let download_url = format!("https://bin.equinox.io/c/bNyj1mQVY4c/{}", archive);
+
+        let ngrok_dir = self.binaries_dir.join("ngrok");
+        fs::create_dir_all(&ngrok_dir)?;
+
+        println!("Downloading ngrok from: {}", download_url);
+        let partial_path = ngrok_dir.join(format!("{}.partial", archive));
+        downloads::download(
+            &downloads::download_client(),
+            &download_url,
+            &partial_path,
+            "ngrok",
+        )
+        .await?;
+
+        let archive_path = ngrok_dir.join(archive);
+        fs::rename(&partial_path, &archive_path)?;
+
+        let extracted = TokioCommand::new("tar")
+            .arg("-xf")
+            .arg(&archive_path)
+            .arg("-C")
+            .arg(&ngrok_dir)
+            .output()
+            .await;
+        let _ = fs::remove_file(&archive_path);
+        match extracted {
+            Ok(output) if output.status.success() => {},
+            Ok(output) => {
+                return Err(anyhow!(
+                    "Failed to unpack ngrok: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ))
+            },
+            Err(e) => {
+                return Err(anyhow!(
+                    "Failed to unpack ngrok, please install it manually: {}",
+                    e
+                ))
+            },
+        }
+
+        let binary_path = self
+            .get_ngrok_path()
+            .ok_or_else(|| anyhow!("The ngrok archive did not contain the ngrok binary"))?;
 
-        // Make executable on Unix systems
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
@@ -749,12 +990,87 @@ exit 1
             fs::set_permissions(&binary_path, perms)?;
         }
 
-        // Verify the binary works
         if !self.verify_binary(&binary_path).await? {
-            return Err(anyhow!("Downloaded cloudflared binary is not working"));
+            return Err(anyhow!("Downloaded ngrok binary is not working"));
         }
 
-        println!("cloudflared downloaded and verified successfully");
+        println!("ngrok downloaded and verified successfully");
+        let checksum = binary_drift::sha256_file(&binary_path)?;
+        self.record_binary("ngrok", &binary_path, checksum, false, None)
+            .await;
         Ok(binary_path)
     }
+
+    fn manifest_path(&self) -> PathBuf {
+        self.binaries_dir.join(binary_drift::MANIFEST_FILE)
+    }
+
+    /// Remember what was installed so later drift can be detected.
+    /// `verified` tells whether it matched its publisher's checksum, and
+    /// `release` what it was downloaded from.
+    async fn record_binary(
+        &self,
+        name: &str,
+        path: &Path,
+        sha256: String,
+        verified: bool,
+        release: Option<&str>,
+    ) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let record = BinaryRecord {
+            version: binary_drift::binary_version(&path).await,
+            path,
+            sha256,
+            recorded_at: chrono::Utc::now(),
+            verified,
+            release: release.map(str::to_string),
+        };
+
+        let manifest_path = self.manifest_path();
+        let mut manifest = BinaryManifest::load(&manifest_path);
+        manifest.binaries.insert(name.to_string(), record);
+        if let Err(e) = manifest.save(&manifest_path) {
+            log_warn!(
+                "BinaryManager",
+                format!("Failed to record {} in the binary manifest: {}", name, e)
+            );
+        }
+    }
+
+    /// Record of the local cloudflared, if it was verified when installed
+    /// and is unchanged since
+    pub fn installed_cloudflared(&self) -> Option<BinaryRecord> {
+        let path = self.get_cloudflared_path()?;
+        let path = path.canonicalize().unwrap_or(path);
+        let mut manifest = BinaryManifest::load(&self.manifest_path());
+        let record = manifest.binaries.remove("cloudflared")?;
+        let unchanged = record.path == path
+            && binary_drift::sha256_file(&path).is_ok_and(|sha256| sha256 == record.sha256);
+        (record.verified && unchanged).then_some(record)
+    }
+
+    /// Compare the recorded binaries with the disk and with the copies that
+    /// would run now
+    pub async fn check_drift(&self) -> Vec<DriftFinding> {
+        let manifest = BinaryManifest::load(&self.manifest_path());
+        let mut resolved = std::collections::BTreeMap::new();
+        // ensure_cloudflared prefers cloudflared from PATH, unless a
+        // release is pinned
+        let on_path = std::env::var_os("PATH")
+            .and_then(|search_path| binary_drift::find_in_path("cloudflared", &search_path));
+        if let Some(path) = on_path.filter(|_| self.pinned_cloudflared.is_none()) {
+            resolved.insert("cloudflared".to_string(), path);
+        }
+        // ensure_ngrok does the same
+        let on_path = std::env::var_os("PATH")
+            .and_then(|search_path| binary_drift::find_in_path("ngrok", &search_path));
+        if let Some(path) = on_path {
+            resolved.insert("ngrok".to_string(), path);
+        }
+        if let Some(path) = self.get_local_bifrost_path() {
+            resolved.insert("bifrost-http".to_string(), path);
+        }
+
+        binary_drift::check(&manifest, &resolved).await
+    }
 }
@@ -1,15 +1,26 @@
 // Configuration Manager - Rust implementation with enterprise-grade error handling
+use axum::http::{HeaderName, HeaderValue};
+use base64::{engine::general_purpose::STANDARD, Engine as _};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::time::Duration;
 use tokio::fs;
 use tokio::sync::RwLock;
+use ts_rs::TS;
 
 use crate::error::{MindLinkError, MindLinkResult};
+use crate::middleware::access_control::{AccessPolicy, TrustedProxies};
+use crate::secret_box;
 use crate::{log_error, log_info};
 
 /// Current configuration schema version for migration support
 const CONFIG_VERSION: u32 = 1;
 
+/// Bounds of every request body limit
+const MIN_BODY_LIMIT: usize = 1024;
+const MAX_BODY_LIMIT: usize = 1024 * 1024 * 1024;
+
 /// Configuration schema with version and validation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConfigSchema {
@@ -19,12 +30,153 @@ pub struct ConfigSchema {
     pub tunnel: TunnelConfig,
     pub features: FeatureConfig,
     pub monitoring: MonitoringConfig,
+    #[serde(default)]
+    pub local_models: LocalModelsConfig,
+    #[serde(default)]
+    pub access_control: AccessControlConfig,
+    #[serde(default)]
+    pub tool_emulation: ToolEmulationConfig,
+    #[serde(default)]
+    pub analytics: AnalyticsConfig,
+    #[serde(default)]
+    pub language_detection: LanguageDetectionConfig,
+    #[serde(default)]
+    pub model_aliases: ModelAliasConfig,
+    #[serde(default)]
+    pub stream_continuation: StreamContinuationConfig,
+    #[serde(default)]
+    pub power_saver: PowerSaverConfig,
+    #[serde(default)]
+    pub prompts: PromptConfig,
+    #[serde(default)]
+    pub conversations: ConversationConfig,
+    #[serde(default)]
+    pub batches: BatchConfig,
+    #[serde(default)]
+    pub accounts: AccountsConfig,
+    #[serde(default)]
+    pub failover: FailoverConfig,
+    #[serde(default)]
+    pub bundles: BundleConfig,
+    #[serde(default)]
+    pub jobs: JobConfig,
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    #[serde(default)]
+    pub backpressure: BackpressureConfig,
+    #[serde(default)]
+    pub capture: CaptureConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub shadow: ShadowConfig,
+    #[serde(default)]
+    pub http: HttpConfig,
+    #[serde(default)]
+    pub moderation: ModerationConfig,
+    #[serde(default)]
+    pub post_processing: PostProcessingConfig,
+    #[serde(default)]
+    pub request_transforms: RequestTransformConfig,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub proxy: ProxyConfig,
+    #[serde(default)]
+    pub anthropic: AnthropicConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
     pub port: u16,
     pub host: String,
+    #[serde(default)]
+    pub tls: TlsConfig,
+    /// Unix socket (or `\\.\pipe\...` named pipe on Windows) to listen on
+    /// besides the port, for tools on this machine
+    #[serde(default)]
+    pub socket_path: Option<String>,
+    /// Further addresses served with the same state, e.g. a legacy port
+    #[serde(default)]
+    pub additional_binds: Vec<BindAddress>,
+}
+
+/// An address the API server listens on
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BindAddress {
+    pub host: String,
+    pub port: u16,
+}
+
+impl BindAddress {
+    /// Whether the address is reachable from other devices on the network
+    pub fn is_lan_exposed(&self) -> bool {
+        match self.host.parse::<std::net::IpAddr>() {
+            Ok(ip) => !ip.is_loopback(),
+            Err(_) => self.host != "localhost",
+        }
+    }
+}
+
+impl std::fmt::Display for BindAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.host.contains(':') {
+            write!(f, "[{}]:{}", self.host, self.port)
+        } else {
+            write!(f, "{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// Host to use when connecting to a server bound to `host` from this machine.
+/// Wildcard binds (0.0.0.0 / ::) are not connectable everywhere, so use loopback.
+fn connect_host(host: &str) -> &str {
+    match host {
+        "0.0.0.0" => "127.0.0.1",
+        "::" => "::1",
+        host => host,
+    }
+}
+
+impl ServerConfig {
+    /// Base URL for reaching the server from this machine
+    pub fn local_url(&self) -> String {
+        let scheme = if self.tls.enabled { "https" } else { "http" };
+        let host = connect_host(&self.host);
+        if host.contains(':') {
+            format!("{}://[{}]:{}", scheme, host, self.port)
+        } else {
+            format!("{}://{}:{}", scheme, host, self.port)
+        }
+    }
+
+    /// Every address the server listens on, the primary one first
+    pub fn bind_addresses(&self) -> Vec<BindAddress> {
+        let primary = BindAddress {
+            host: self.host.clone(),
+            port: self.port,
+        };
+        std::iter::once(primary)
+            .chain(self.additional_binds.iter().cloned())
+            .collect()
+    }
+
+    /// Whether the server is reachable from other devices on the network
+    pub fn is_lan_exposed(&self) -> bool {
+        self.bind_addresses()
+            .iter()
+            .any(BindAddress::is_lan_exposed)
+    }
+}
+
+/// HTTPS settings for the local API server. When enabled without a
+/// certificate/key pair, a self-signed certificate is generated and reused
+/// for as long as the server's hosts stay the same.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TlsConfig {
+    pub enabled: bool,
+    pub cert_path: Option<String>,
+    pub key_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +190,286 @@ pub struct BifrostConfig {
 pub struct TunnelConfig {
     pub enabled: bool,
     pub tunnel_type: String,
+    /// Hours a token of clients paired through the tunnel is valid before
+    /// it is rotated
+    #[serde(default = "default_access_token_lifetime")]
+    pub access_token_lifetime_hours: u64,
+    /// Refuse requests through the tunnel that carry neither the instance
+    /// token, a tunnel token nor the API key of an authorized app
+    #[serde(default = "default_require_token")]
+    pub require_token: bool,
+    /// The Cloudflare tunnel used when `tunnel_type` is `named`
+    #[serde(default)]
+    pub named: NamedTunnelConfig,
+    /// The ngrok tunnel used when `tunnel_type` is `ngrok`
+    #[serde(default)]
+    pub ngrok: NgrokConfig,
+    /// How cloudflared connects to Cloudflare, for quick and named tunnels
+    #[serde(default)]
+    pub cloudflared: CloudflaredOptions,
+    /// Which release of cloudflared is installed and kept up to date
+    #[serde(default)]
+    pub cloudflared_updates: CloudflaredUpdatesConfig,
+    /// Tunnel used when a quick tunnel cannot be started
+    #[serde(default)]
+    pub fallback: FallbackTunnelConfig,
+    /// Caps on the traffic through the tunnel
+    #[serde(default)]
+    pub limits: TunnelLimitsConfig,
+    /// Times of the week the tunnel is up
+    #[serde(default)]
+    pub schedule: TunnelScheduleConfig,
+    /// Cloudflare Access application in front of the named tunnel
+    #[serde(default)]
+    pub cloudflare_access: CloudflareAccessConfig,
+    /// Called with the new public URL whenever it changes
+    #[serde(default)]
+    pub webhooks: Vec<TunnelWebhook>,
+    /// Further services, each served by a tunnel of its own
+    #[serde(default)]
+    pub services: Vec<ServiceTunnelConfig>,
+}
+
+/// Local service a tunnel serves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TunnelService {
+    /// The MindLink API, served by the main tunnel
+    Api,
+    Bifrost,
+    Dashboard,
+}
+
+impl std::fmt::Display for TunnelService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TunnelService::Api => write!(f, "api"),
+            TunnelService::Bifrost => write!(f, "bifrost"),
+            TunnelService::Dashboard => write!(f, "dashboard"),
+        }
+    }
+}
+
+/// Tunnel of a service besides the API, e.g. to reach the Bifrost dashboard
+/// from outside. It is of the same type as the API's tunnel, but nothing
+/// checks the requests coming through it: whoever knows the URL reaches the
+/// service.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceTunnelConfig {
+    pub service: TunnelService,
+    /// Port the service listens on locally
+    pub local_port: u16,
+    /// Hostname of the service's named tunnel, or the domain of its ngrok
+    /// tunnel. Quick tunnels get a random one.
+    #[serde(default)]
+    pub hostname: String,
+}
+
+/// Endpoint told about changes of the tunnel's public URL, so scripts and
+/// paired devices can follow a quick tunnel to its new URL
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TunnelWebhook {
+    /// `http` or `https` URL the change is posted to as JSON
+    pub url: String,
+    /// Key the payload is signed with (HMAC-SHA256, in the
+    /// `X-MindLink-Signature` header), stored encrypted
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+/// Cloudflare named tunnel serving MindLink at a hostname of the user's own
+/// domain. `cloudflared tunnel login` has to have been run once, which
+/// authorizes cloudflared for the domain.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NamedTunnelConfig {
+    /// Name of the tunnel in the Cloudflare account
+    pub name: String,
+    /// Public hostname routed to the tunnel, e.g. `mindlink.example.com`
+    pub hostname: String,
+    /// ID of the tunnel once it was created, so later starts reuse it
+    pub tunnel_id: Option<String>,
+}
+
+/// ngrok tunnel, for networks that block Cloudflare. ngrok needs the
+/// authtoken of an account; free accounts get a random URL on every start
+/// unless they claimed their static domain.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NgrokConfig {
+    /// Authtoken from the ngrok dashboard, stored encrypted
+    pub authtoken: Option<String>,
+    /// Domain reserved in the ngrok account, e.g. `mindlink.ngrok.app`
+    pub domain: Option<String>,
+}
+
+/// Options of cloudflared for networks where its defaults do not connect,
+/// e.g. ISPs that block UDP or have broken IPv6. Unset options are left to
+/// cloudflared.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CloudflaredOptions {
+    /// Region of Cloudflare's edge to connect to; `us` keeps the tunnel in
+    /// the United States, unset uses the global edge
+    pub region: Option<String>,
+    /// IP version the edge is reached with: `auto`, `4` or `6`
+    pub edge_ip_version: Option<String>,
+    /// Transport to the edge: `auto`, `quic` or `http2`, which also works
+    /// where UDP is blocked
+    pub protocol: Option<String>,
+}
+
+/// Updates of the cloudflared MindLink downloads. A cloudflared found on PATH
+/// is left to whatever installed it, unless a version is pinned.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CloudflaredUpdatesConfig {
+    /// Install new releases as they come out, rather than only telling the
+    /// user about them
+    pub auto_update: bool,
+    /// Hours between checks for a new release
+    pub check_interval_hours: u64,
+    /// Release to stay on, e.g. `2024.6.1`. MindLink's copy of that release
+    /// runs even when cloudflared is on PATH.
+    pub pinned_version: Option<String>,
+}
+
+impl Default for CloudflaredUpdatesConfig {
+    fn default() -> Self {
+        Self {
+            auto_update: true,
+            check_interval_hours: 24,
+            pinned_version: None,
+        }
+    }
+}
+
+/// localtunnel server that serves the tunnel when cloudflared cannot start a
+/// quick tunnel, e.g. while trycloudflare.com is down or blocked. Browsers
+/// are shown a reminder page before they reach the API; API clients are not.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FallbackTunnelConfig {
+    pub enabled: bool,
+    /// Base URL of the localtunnel server
+    pub server: String,
+}
+
+impl Default for FallbackTunnelConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            server: "https://localtunnel.me".to_string(),
+        }
+    }
+}
+
+/// Caps on the traffic through the tunnel, so a shared tunnel cannot be
+/// abused by whoever got hold of its URL. Local requests do not count, and
+/// unset limits do not apply.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TunnelLimitsConfig {
+    /// Requests served through the tunnel at once, streams included
+    pub max_connections: Option<u32>,
+    /// Megabytes sent through the tunnel per calendar month (UTC)
+    pub max_monthly_egress_mb: Option<u64>,
+    /// Share of a limit, in percent, from which a warning is shown
+    pub warn_at_percent: u8,
+}
+
+impl Default for TunnelLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: None,
+            max_monthly_egress_mb: None,
+            warn_at_percent: 80,
+        }
+    }
+}
+
+impl TunnelLimitsConfig {
+    pub fn is_limited(&self) -> bool {
+        self.max_connections.is_some() || self.max_monthly_egress_mb.is_some()
+    }
+}
+
+/// Times of the week the tunnel is up, e.g. work hours. Outside its windows
+/// the tunnel is closed, and local clients are still served. Without windows,
+/// or while disabled, the tunnel is up whenever MindLink serves.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TunnelScheduleConfig {
+    pub enabled: bool,
+    pub windows: Vec<TunnelWindow>,
+}
+
+/// A window of the tunnel schedule, in local time. One that ends before it
+/// starts runs past midnight into the next day.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TunnelWindow {
+    /// Days the window starts on: `mon`, `tue`, ... `sun`; every day when empty
+    #[serde(default)]
+    pub days: Vec<String>,
+    /// `HH:MM`
+    pub start: String,
+    /// `HH:MM`
+    pub end: String,
+}
+
+const CLOUDFLARED_REGIONS: &[&str] = &["us"];
+const CLOUDFLARED_IP_VERSIONS: &[&str] = &["auto", "4", "6"];
+const CLOUDFLARED_PROTOCOLS: &[&str] = &["auto", "quic", "http2"];
+
+/// Cloudflare Access application protecting the hostname of the named
+/// tunnel. Only clients with its service token get through Cloudflare, and
+/// the server checks the JWT Access adds to their requests. Everything but
+/// the account and API token is filled in when the application is created.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CloudflareAccessConfig {
+    pub enabled: bool,
+    /// Cloudflare account of the tunnel's zone
+    pub account_id: String,
+    /// API token allowed to edit Access applications, policies and service
+    /// tokens, stored encrypted
+    pub api_token: Option<String>,
+    /// Team domain of the account, e.g. `myteam.cloudflareaccess.com`
+    pub team_domain: Option<String>,
+    /// ID of the Access application
+    pub app_id: Option<String>,
+    /// Audience tag of the Access application, carried by its JWTs
+    pub audience: Option<String>,
+    /// Client ID of the service token paired clients send
+    pub client_id: Option<String>,
+    /// Client secret of the service token, stored encrypted
+    pub client_secret: Option<String>,
+}
+
+fn default_access_token_lifetime() -> u64 {
+    24
+}
+
+fn default_require_token() -> bool {
+    true
+}
+
+/// Tunnel tokens live a week at most
+const MAX_ACCESS_TOKEN_LIFETIME_HOURS: u64 = 24 * 7;
+
+/// Whether `hostname` is a DNS name with at least two labels, each of
+/// letters, digits and inner hyphens
+fn is_valid_hostname(hostname: &str) -> bool {
+    let labels: Vec<&str> = hostname.split('.').collect();
+    hostname.len() <= 253
+        && labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,6 +484,808 @@ pub struct MonitoringConfig {
     pub health_check_interval: u64,
     pub error_threshold: u32,
     pub notifications: bool,
+    /// How often the ChatGPT session is verified upstream; 0 disables the probe
+    #[serde(default = "default_auth_probe_interval")]
+    pub auth_probe_interval_secs: u64,
+    /// How often the tunnel's public URL is requested from outside, to
+    /// measure the round trip and tell whether clients can reach it; 0
+    /// disables the probe
+    #[serde(default = "default_tunnel_probe_interval")]
+    pub tunnel_probe_interval_secs: u64,
+}
+
+fn default_auth_probe_interval() -> u64 {
+    300
+}
+
+fn default_tunnel_probe_interval() -> u64 {
+    60
+}
+
+/// Local model backends (Ollama) and how many models are kept loaded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalModelsConfig {
+    pub ollama_url: String,
+    /// Maximum number of models kept loaded at once; 0 disables the warm pool
+    pub warm_pool_size: usize,
+}
+
+impl Default for LocalModelsConfig {
+    fn default() -> Self {
+        Self {
+            ollama_url: "http://127.0.0.1:11434".to_string(),
+            warm_pool_size: 2,
+        }
+    }
+}
+
+/// Client IP allow/deny lists (CIDR notation or bare addresses) for the API server.
+/// Deny entries take precedence; an empty allow list admits every address.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessControlConfig {
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+    /// Peers whose `CF-Connecting-IP`/`X-Forwarded-For` headers are believed.
+    /// Loopback by default, where the tunnel connects from.
+    #[serde(default = "default_trusted_proxies")]
+    pub trusted_proxies: Vec<String>,
+}
+
+fn default_trusted_proxies() -> Vec<String> {
+    vec!["127.0.0.0/8".to_string(), "::1".to_string()]
+}
+
+impl Default for AccessControlConfig {
+    fn default() -> Self {
+        Self {
+            allow: Vec::new(),
+            deny: Vec::new(),
+            trusted_proxies: default_trusted_proxies(),
+        }
+    }
+}
+
+/// Request analytics written to SQLite through a write-behind buffer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalyticsConfig {
+    pub enabled: bool,
+    /// Maximum time a record waits in memory before being written
+    pub flush_interval_ms: u64,
+    /// Records written per transaction
+    pub batch_size: usize,
+    /// Records buffered in memory before new ones are dropped
+    pub buffer_capacity: usize,
+    /// Keep one in this many records once the buffer is half full
+    pub pressure_sample_rate: u32,
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            flush_interval_ms: 1000,
+            batch_size: 200,
+            buffer_capacity: 5000,
+            pressure_sample_rate: 10,
+        }
+    }
+}
+
+/// Prompt language detection. Detected languages are recorded in analytics and
+/// can route prompts to a model: `routes` maps an ISO 639-3 code (`jpn`, `deu`,
+/// ...) to the model those prompts are sent to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageDetectionConfig {
+    pub enabled: bool,
+    /// Prompts shorter than this are not classified
+    pub min_chars: usize,
+    /// Detections below this confidence are recorded but never route
+    pub min_confidence: f64,
+    #[serde(default)]
+    pub routes: HashMap<String, String>,
+}
+
+impl Default for LanguageDetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_chars: 20,
+            min_confidence: 0.5,
+            routes: HashMap::new(),
+        }
+    }
+}
+
+/// Automatic continuation of streamed responses that end before the upstream
+/// finished them. The follow-up is stitched into the same client stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamContinuationConfig {
+    pub enabled: bool,
+    /// Follow-up requests allowed for a single response
+    pub max_continuations: u32,
+}
+
+impl Default for StreamContinuationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_continuations: 2,
+        }
+    }
+}
+
+/// Continuing upstream ChatGPT conversations across requests instead of
+/// replaying the whole history each time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationConfig {
+    pub enabled: bool,
+    /// Conversations idle for longer start over upstream
+    pub ttl_minutes: u64,
+    pub max_conversations: usize,
+}
+
+impl Default for ConversationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ttl_minutes: 360,
+            max_conversations: 1000,
+        }
+    }
+}
+
+/// Pacing of `/v1/batches` jobs. Batch requests run one at a time so they
+/// never compete with interactive traffic for the account's rate limit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchConfig {
+    pub enabled: bool,
+    /// Minimum gap between two upstream requests of a batch
+    pub min_interval_ms: u64,
+    /// Retries of a request that was rate limited or failed upstream
+    pub max_retries: u32,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_interval_ms: 1000,
+            max_retries: 5,
+        }
+    }
+}
+
+/// Background chat completions submitted through `/v1/jobs`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobConfig {
+    pub enabled: bool,
+    /// Jobs running at the same time; the rest wait in line
+    pub max_concurrent: usize,
+    /// How long finished jobs can still be fetched
+    pub retention_secs: u64,
+}
+
+impl Default for JobConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_concurrent: 2,
+            retention_secs: 3600,
+        }
+    }
+}
+
+/// Admission control for completion requests. Requests beyond
+/// `max_in_flight`, and all requests while every account is rate limited, are
+/// answered with 429 and a Retry-After header.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackpressureConfig {
+    pub enabled: bool,
+    pub max_in_flight: usize,
+    /// Lower `max_in_flight` to what the plans of the signed-in accounts are
+    /// expected to sustain
+    #[serde(default = "default_adapt_to_plan")]
+    pub adapt_to_plan: bool,
+}
+
+fn default_adapt_to_plan() -> bool {
+    true
+}
+
+impl Default for BackpressureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_in_flight: 16,
+            adapt_to_plan: true,
+        }
+    }
+}
+
+/// Recording of completion requests and their responses to disk, for
+/// debugging. Credentials and API keys are masked in what is written.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureConfig {
+    pub enabled: bool,
+    /// Captures kept; the oldest are deleted first
+    pub max_captures: usize,
+}
+
+impl Default for CaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_captures: 100,
+        }
+    }
+}
+
+/// Request body limits of the API server and timeouts of its upstream requests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LimitsConfig {
+    /// Largest request body, in bytes, of routes without a limit of their own
+    pub max_body_bytes: usize,
+    /// Largest batch input file accepted by `/v1/files`
+    pub max_upload_bytes: usize,
+    /// Limits for single routes, e.g. `"/v1/chat/completions": 8388608`
+    pub route_body_limits: HashMap<String, usize>,
+    /// Longest a non-streaming upstream request may take, and a streaming one
+    /// until its response starts
+    pub upstream_timeout_secs: u64,
+    /// Longest a stream may go without new data before it is given up
+    pub stream_idle_timeout_secs: u64,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 2 * 1024 * 1024,
+            max_upload_bytes: crate::batches::MAX_FILE_BYTES,
+            route_body_limits: HashMap::new(),
+            upstream_timeout_secs: 120,
+            stream_idle_timeout_secs: 60,
+        }
+    }
+}
+
+impl LimitsConfig {
+    /// Body limit of `route`, `default` unless the route has its own
+    pub fn body_limit(&self, route: &str, default: usize) -> usize {
+        self.route_body_limits
+            .get(route)
+            .copied()
+            .unwrap_or(default)
+    }
+
+    pub fn upstream_timeout(&self) -> Duration {
+        Duration::from_secs(self.upstream_timeout_secs)
+    }
+
+    pub fn stream_idle_timeout(&self) -> Duration {
+        Duration::from_secs(self.stream_idle_timeout_secs)
+    }
+}
+
+/// Connection handling of the API server and of its upstream requests. The
+/// server speaks HTTP/1.1 and HTTP/2 on the same port: h2c in the clear, and
+/// h2 negotiated through ALPN over TLS.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpConfig {
+    /// Keep HTTP/1.1 connections open for further requests
+    pub keep_alive: bool,
+    /// Longest a client may take to send the headers of a request
+    pub header_read_timeout_secs: u64,
+    /// How often idle HTTP/2 connections are pinged; 0 disables the pings
+    pub http2_keep_alive_interval_secs: u64,
+    /// Longest a ping may go unanswered before the connection is closed
+    pub http2_keep_alive_timeout_secs: u64,
+    /// Requests one HTTP/2 connection may have in flight
+    pub http2_max_concurrent_streams: u32,
+    /// Idle connections kept open to each upstream host
+    pub upstream_pool_max_idle: usize,
+    /// How long an idle upstream connection is kept
+    pub upstream_pool_idle_timeout_secs: u64,
+    /// Compress responses with gzip or brotli for clients that accept it
+    #[serde(default = "default_compression")]
+    pub compression: bool,
+    /// Smallest response body compressed
+    #[serde(default = "default_compression_min_bytes")]
+    pub compression_min_bytes: u16,
+    /// Also compress streamed completions (SSE and NDJSON). The encoder
+    /// holds chunks back until it has enough to compress, so tokens arrive
+    /// in bursts.
+    #[serde(default)]
+    pub compress_streams: bool,
+}
+
+fn default_compression() -> bool {
+    true
+}
+
+fn default_compression_min_bytes() -> u16 {
+    1024
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            keep_alive: true,
+            header_read_timeout_secs: 30,
+            http2_keep_alive_interval_secs: 20,
+            http2_keep_alive_timeout_secs: 20,
+            http2_max_concurrent_streams: 256,
+            upstream_pool_max_idle: 32,
+            upstream_pool_idle_timeout_secs: 90,
+            compression: default_compression(),
+            compression_min_bytes: default_compression_min_bytes(),
+            compress_streams: false,
+        }
+    }
+}
+
+/// Mirroring of a share of chat completions to a secondary backend, whose
+/// answers are compared with the primary's and then discarded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowConfig {
+    pub enabled: bool,
+    /// Share of requests (0-100) mirrored
+    pub percentage: u8,
+    /// Backend the requests are mirrored to; by default the local models
+    /// behind Bifrost
+    pub target: FallbackProvider,
+}
+
+impl Default for ShadowConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            percentage: 10,
+            target: FallbackProvider {
+                name: "shadow".to_string(),
+                base_url: None,
+                api_key: None,
+                model: None,
+            },
+        }
+    }
+}
+
+/// Content moderation for `/v1/moderations` and, optionally, for every prompt
+/// before it is answered
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModerationConfig {
+    /// Refuse prompts that are flagged instead of answering them
+    pub preflight: bool,
+    /// OpenAI-compatible moderation API, e.g. `https://api.openai.com/v1`
+    /// with `omni-moderation-latest`. Without one only the rules apply.
+    #[serde(default)]
+    pub provider: Option<FallbackProvider>,
+    /// Local rules, checked in addition to the provider
+    #[serde(default)]
+    pub rules: Vec<ModerationRule>,
+    /// Refuse prompts while the provider cannot be reached, rather than let
+    /// them through unchecked
+    #[serde(default)]
+    pub fail_closed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModerationRule {
+    /// Category flagged on a match, e.g. `harassment` or `internal-project`
+    pub category: String,
+    /// Regular expression matched against each prompt
+    pub pattern: String,
+}
+
+/// Rewrites of the text of completions before they are returned, applied in
+/// order; see `post_processing`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostProcessingConfig {
+    pub rules: Vec<PostProcessingRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PostProcessingRule {
+    /// Models the rule applies to, as clients request them; all when empty
+    #[serde(default)]
+    pub models: Vec<String>,
+    #[serde(flatten)]
+    pub action: PostProcessingAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PostProcessingAction {
+    /// Replace every match of `pattern`; `$1` in `replacement` refers to the
+    /// first group
+    RegexReplace {
+        pattern: String,
+        #[serde(default)]
+        replacement: String,
+    },
+    /// Cut the text off where the first of `sequences` starts
+    StopSequences { sequences: Vec<String> },
+    /// Reduce Markdown to plain text
+    StripMarkdown,
+    /// Keep only the first JSON object or array in the text
+    ExtractJson,
+}
+
+/// Rewrites of incoming completion requests, applied in order, for clients
+/// that send parameters the backend rejects or ask for models under another
+/// name; see `request_transforms`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestTransformConfig {
+    pub rules: Vec<RequestTransformRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RequestTransformRule {
+    /// Regular expression matched against the client's `User-Agent`; all
+    /// clients when unset
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// Models the rule applies to, as the request names them when the rule
+    /// runs; all when empty
+    #[serde(default)]
+    pub models: Vec<String>,
+    #[serde(flatten)]
+    pub action: RequestTransformAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RequestTransformAction {
+    /// Set a top-level field of the body, e.g. force `temperature`
+    SetField {
+        field: String,
+        value: serde_json::Value,
+    },
+    /// Remove top-level fields of the body, e.g. parameters the backend
+    /// doesn't support
+    RemoveFields {
+        fields: Vec<String>,
+    },
+    /// Ask for another model
+    RenameModel {
+        to: String,
+    },
+    SetHeader {
+        name: String,
+        value: String,
+    },
+    RemoveHeader {
+        name: String,
+    },
+}
+
+/// Masking of personal data and secrets in prompts before they are sent to the
+/// ChatGPT backend. The built-in rules can be switched off one by one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    pub enabled: bool,
+    pub emails: bool,
+    pub phone_numbers: bool,
+    /// OpenAI, GitHub, AWS and similar keys and tokens
+    pub api_keys: bool,
+    #[serde(default)]
+    pub custom_rules: Vec<RedactionRule>,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            emails: true,
+            phone_numbers: true,
+            api_keys: true,
+            custom_rules: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionRule {
+    /// Shown in the mask, e.g. `employee_id` masks as `[REDACTED_EMPLOYEE_ID]`
+    pub name: String,
+    /// Regular expression; every match is masked
+    pub pattern: String,
+    pub enabled: bool,
+}
+
+/// How ChatGPT accounts are signed in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LoginFlow {
+    /// Open the browser of this machine, which redirects to a local callback
+    /// server on the redirect port
+    #[default]
+    Browser,
+    /// Print a URL and a code to enter on another device, for headless
+    /// machines. Also selected by starting MindLink with `--device-code`.
+    DeviceCode,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuthConfig {
+    pub login_flow: LoginFlow,
+    /// OAuth client MindLink signs in as. The default is the Codex CLI's,
+    /// which ChatGPT plans are authorized for.
+    pub client_id: String,
+    /// Port of the callback server of the browser login. The next few ports,
+    /// then any free port, are used instead when it is taken, which the
+    /// client has to allow.
+    pub redirect_port: u16,
+    pub scopes: Vec<String>,
+    /// Append a random segment, new for every login, to the callback path.
+    /// The client has to allow any path under `/auth/callback`, which the
+    /// Codex CLI's does not.
+    pub callback_path_token: bool,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            login_flow: LoginFlow::default(),
+            client_id: "app_EMoamEEZ73f0CkXaXp7hrann".to_string(),
+            redirect_port: 1455,
+            scopes: ["openid", "profile", "email", "offline_access"]
+                .map(String::from)
+                .to_vec(),
+            callback_path_token: false,
+        }
+    }
+}
+
+/// Proxy for the traffic to OpenAI and ChatGPT: logins, token refreshes and
+/// upstream requests
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxyConfig {
+    /// `http://`, `https://`, `socks5://` or `socks5h://` URL, with the
+    /// credentials in it if the proxy needs them. Without one, the
+    /// `HTTPS_PROXY` and `ALL_PROXY` environment variables apply.
+    pub url: Option<String>,
+    /// Hosts reached without the proxy, in the format of `NO_PROXY`
+    pub no_proxy: Vec<String>,
+}
+
+/// Claude subscription served next to ChatGPT: requests for `claude-*`
+/// models go to Anthropic's Messages API with the signed-in Claude account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnthropicConfig {
+    pub enabled: bool,
+    /// OAuth client registered with Anthropic that MindLink signs in as.
+    /// There is none by default, so Claude logins need one set.
+    pub client_id: String,
+    /// Port of the callback server of the Claude login
+    pub redirect_port: u16,
+    pub scopes: Vec<String>,
+}
+
+impl Default for AnthropicConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            client_id: String::new(),
+            redirect_port: 1456,
+            scopes: ["user:profile", "user:inference"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
+impl AnthropicConfig {
+    /// How the Claude account signs in; there is only the browser login
+    pub fn auth_config(&self) -> AuthConfig {
+        AuthConfig {
+            login_flow: LoginFlow::Browser,
+            client_id: self.client_id.clone(),
+            redirect_port: self.redirect_port,
+            scopes: self.scopes.clone(),
+            callback_path_token: false,
+        }
+    }
+}
+
+/// How upstream requests are spread across signed-in ChatGPT accounts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BalancingStrategy {
+    /// Take turns
+    #[default]
+    RoundRobin,
+    /// The account with the fewest requests in flight
+    LeastLoaded,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountsConfig {
+    pub strategy: BalancingStrategy,
+    /// How long an account that hit a rate limit is passed over
+    pub rate_limit_cooldown_secs: u64,
+    /// Keep requests naming the same session on the same account
+    #[serde(default = "default_sticky_sessions")]
+    pub sticky_sessions: bool,
+    /// Sessions idle for longer may move to another account
+    #[serde(default = "default_session_ttl")]
+    pub session_ttl_minutes: u64,
+}
+
+fn default_sticky_sessions() -> bool {
+    true
+}
+
+fn default_session_ttl() -> u64 {
+    360
+}
+
+impl Default for AccountsConfig {
+    fn default() -> Self {
+        Self {
+            strategy: BalancingStrategy::RoundRobin,
+            rate_limit_cooldown_secs: 60,
+            sticky_sessions: default_sticky_sessions(),
+            session_ttl_minutes: default_session_ttl(),
+        }
+    }
+}
+
+/// An OpenAI-compatible provider chat completions fail over to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FallbackProvider {
+    pub name: String,
+    /// Base URL including the API version, e.g. `https://api.openai.com/v1`.
+    /// `None` uses the running Bifrost instance.
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Model to request instead of the client's, e.g. a local model
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// Providers tried in order when the ChatGPT backend cannot serve a chat
+/// completion
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FailoverConfig {
+    pub enabled: bool,
+    pub providers: Vec<FallbackProvider>,
+}
+
+/// A teammate whose export bundles may be imported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedSigner {
+    pub name: String,
+    /// Base64 Ed25519 public key, as shown by the teammate's MindLink
+    pub public_key: String,
+}
+
+/// Sharing of prompt templates and routes through signed export bundles
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BundleConfig {
+    /// Signers besides this instance whose bundles are accepted
+    pub trusted_signers: Vec<TrustedSigner>,
+}
+
+/// When the power saver is active
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+pub enum PowerSaverMode {
+    /// Active while running on battery
+    #[default]
+    Auto,
+    On,
+    Off,
+}
+
+/// Reduced background activity for laptops on battery: slower health checks,
+/// analytics written only in full batches and, optionally, Bifrost stopped
+/// until AC power returns.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerSaverConfig {
+    pub mode: PowerSaverMode,
+    /// Health check interval while the power saver is active
+    pub health_check_interval_secs: u64,
+    pub stop_bifrost: bool,
+}
+
+impl Default for PowerSaverConfig {
+    fn default() -> Self {
+        Self {
+            mode: PowerSaverMode::Auto,
+            health_check_interval_secs: 120,
+            stop_bifrost: false,
+        }
+    }
+}
+
+/// Maps the model names clients request to upstream ChatGPT models. `*` applies
+/// to names without their own entry; without a `*` entry, unknown names are
+/// sent upstream unchanged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelAliasConfig {
+    pub aliases: HashMap<String, String>,
+}
+
+impl Default for ModelAliasConfig {
+    fn default() -> Self {
+        let aliases = [
+            ("gpt-5", "gpt-4"),
+            ("codex-mini", "gpt-3.5-turbo"),
+            ("*", "gpt-4"),
+        ]
+        .into_iter()
+        .map(|(alias, model)| (alias.to_string(), model.to_string()))
+        .collect();
+        Self { aliases }
+    }
+}
+
+impl ModelAliasConfig {
+    /// Upstream model for a requested model name
+    pub fn resolve(&self, model: &str) -> String {
+        self.aliases
+            .get(model)
+            .or_else(|| self.aliases.get("*"))
+            .cloned()
+            .unwrap_or_else(|| model.to_string())
+    }
+}
+
+/// System prompt prepended to requests and named templates clients can select.
+/// Prompts may use `{{variable}}` placeholders; see `prompt_templates`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PromptConfig {
+    /// Used for requests without an app-specific prompt or a selected template
+    pub system_prompt: Option<String>,
+    pub templates: HashMap<String, String>,
+    /// Values for placeholders, overridable per request
+    pub variables: HashMap<String, String>,
+}
+
+/// How tolerant the function-calling emulation is of malformed model output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolCallStrictness {
+    /// Extract JSON from surrounding prose, skip unknown tools, fall back to plain text
+    #[default]
+    Lenient,
+    /// Require an exact tool-call envelope and fail the request otherwise
+    Strict,
+}
+
+/// Function-calling emulation for a single model route
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolEmulationRoute {
+    pub enabled: bool,
+    #[serde(default)]
+    pub strictness: ToolCallStrictness,
+}
+
+/// Per-route function-calling emulation for backends without native tool support.
+/// Routes are keyed by model name; `*` applies to every model without its own entry.
+/// Without routes emulation is off, so models opt in one by one.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolEmulationConfig {
+    pub routes: HashMap<String, ToolEmulationRoute>,
+}
+
+impl ToolEmulationConfig {
+    /// Emulation settings for a model, if emulation is enabled for it
+    pub fn route_for(&self, model: &str) -> Option<&ToolEmulationRoute> {
+        self.routes
+            .get(model)
+            .or_else(|| self.routes.get("*"))
+            .filter(|route| route.enabled)
+    }
 }
 
 /// Enterprise-grade configuration manager with validation and migration support
@@ -115,6 +1349,7 @@ impl ConfigManager {
 
                 match serde_json::from_str::<ConfigSchema>(&content) {
                     Ok(config) => {
+                        let config = Self::open_secrets(config).await?;
                         Self::validate_config(&config)?;
                         Self::migrate_config_if_needed(config, config_path, backup_path).await
                     },
@@ -150,112 +1385,992 @@ impl ConfigManager {
                 Self::create_default_config(config_path).await
             },
         }
-    }
+    }
+
+    /// Create default configuration with validation
+    async fn create_default_config(config_path: &PathBuf) -> MindLinkResult<ConfigSchema> {
+        let default_config = ConfigSchema {
+            version: CONFIG_VERSION,
+            server: ServerConfig {
+                port: 3001,
+                host: "127.0.0.1".to_string(),
+                tls: TlsConfig::default(),
+                socket_path: None,
+                additional_binds: Vec::new(),
+            },
+            bifrost: BifrostConfig {
+                port: 3002,
+                host: "127.0.0.1".to_string(),
+                enabled: true,
+            },
+            tunnel: TunnelConfig {
+                enabled: true,
+                tunnel_type: "quick".to_string(),
+                access_token_lifetime_hours: default_access_token_lifetime(),
+                require_token: true,
+                named: NamedTunnelConfig::default(),
+                ngrok: NgrokConfig::default(),
+                cloudflared: CloudflaredOptions::default(),
+                cloudflared_updates: CloudflaredUpdatesConfig::default(),
+                fallback: FallbackTunnelConfig::default(),
+                limits: TunnelLimitsConfig::default(),
+                schedule: TunnelScheduleConfig::default(),
+                cloudflare_access: CloudflareAccessConfig::default(),
+                webhooks: Vec::new(),
+                services: Vec::new(),
+            },
+            features: FeatureConfig {
+                reasoning_effort: "medium".to_string(),
+                reasoning_summaries: "auto".to_string(),
+                reasoning_compatibility: "think-tags".to_string(),
+            },
+            monitoring: MonitoringConfig {
+                health_check_interval: 30,
+                error_threshold: 5,
+                notifications: true,
+                auth_probe_interval_secs: default_auth_probe_interval(),
+                tunnel_probe_interval_secs: default_tunnel_probe_interval(),
+            },
+            local_models: LocalModelsConfig::default(),
+            access_control: AccessControlConfig::default(),
+            tool_emulation: ToolEmulationConfig::default(),
+            analytics: AnalyticsConfig::default(),
+            language_detection: LanguageDetectionConfig::default(),
+            model_aliases: ModelAliasConfig::default(),
+            stream_continuation: StreamContinuationConfig::default(),
+            power_saver: PowerSaverConfig::default(),
+            prompts: PromptConfig::default(),
+            conversations: ConversationConfig::default(),
+            batches: BatchConfig::default(),
+            accounts: AccountsConfig::default(),
+            failover: FailoverConfig::default(),
+            bundles: BundleConfig::default(),
+            jobs: JobConfig::default(),
+            redaction: RedactionConfig::default(),
+            backpressure: BackpressureConfig::default(),
+            capture: CaptureConfig::default(),
+            limits: LimitsConfig::default(),
+            shadow: ShadowConfig::default(),
+            http: HttpConfig::default(),
+            moderation: ModerationConfig::default(),
+            post_processing: PostProcessingConfig::default(),
+            request_transforms: RequestTransformConfig::default(),
+            auth: AuthConfig::default(),
+            proxy: ProxyConfig::default(),
+            anthropic: AnthropicConfig::default(),
+        };
+
+        Self::validate_config(&default_config)?;
+
+        // Save default config
+        let json = serde_json::to_string_pretty(&default_config).map_err(|e| {
+            MindLinkError::Configuration {
+                message: "Failed to serialize default configuration".to_string(),
+                config_key: None,
+                source: Some(e.into()),
+            }
+        })?;
+
+        fs::write(config_path, json)
+            .await
+            .map_err(|e| MindLinkError::FileSystem {
+                message: "Failed to save default configuration".to_string(),
+                path: Some(config_path.to_string_lossy().to_string()),
+                operation: "write".to_string(),
+                source: Some(e.into()),
+            })?;
+
+        log_info!("ConfigManager", "Default configuration created and saved");
+
+        Ok(default_config)
+    }
+
+    /// Validate configuration values
+    pub fn validate_config(config: &ConfigSchema) -> MindLinkResult<()> {
+        // Validate server config
+        if config.server.port == 0 {
+            return Err(MindLinkError::Configuration {
+                message: "Server port cannot be 0".to_string(),
+                config_key: Some("server.port".to_string()),
+                source: None,
+            });
+        }
+
+        if config.server.host.is_empty() {
+            return Err(MindLinkError::Configuration {
+                message: "Server host cannot be empty".to_string(),
+                config_key: Some("server.host".to_string()),
+                source: None,
+            });
+        }
+
+        if config.server.host != "localhost"
+            && config.server.host.parse::<std::net::IpAddr>().is_err()
+        {
+            return Err(MindLinkError::Configuration {
+                message: format!(
+                    "Invalid server host: {}. Must be an IP address or localhost",
+                    config.server.host
+                ),
+                config_key: Some("server.host".to_string()),
+                source: None,
+            });
+        }
+
+        if config.server.tls.cert_path.is_some() != config.server.tls.key_path.is_some() {
+            return Err(MindLinkError::Configuration {
+                message: "TLS certificate and key paths must be set together".to_string(),
+                config_key: Some("server.tls".to_string()),
+                source: None,
+            });
+        }
+
+        for bind in &config.server.additional_binds {
+            let valid_host =
+                bind.host == "localhost" || bind.host.parse::<std::net::IpAddr>().is_ok();
+            if bind.port == 0 || !valid_host {
+                return Err(MindLinkError::Configuration {
+                    message: format!(
+                        "Invalid additional bind address: {}. Must have a port and an IP address or localhost",
+                        bind
+                    ),
+                    config_key: Some("server.additional_binds".to_string()),
+                    source: None,
+                });
+            }
+        }
+
+        let mut bound = HashSet::new();
+        if let Some(bind) = config
+            .server
+            .bind_addresses()
+            .into_iter()
+            .find(|bind| !bound.insert(bind.clone()))
+        {
+            return Err(MindLinkError::Configuration {
+                message: format!("The server is bound to {} more than once", bind),
+                config_key: Some("server.additional_binds".to_string()),
+                source: None,
+            });
+        }
+
+        if let Some(socket_path) = &config.server.socket_path {
+            const PIPE_PREFIX: &str = r"\\.\pipe\";
+            let problem = if cfg!(windows) {
+                (!socket_path.starts_with(PIPE_PREFIX) || socket_path.len() == PIPE_PREFIX.len())
+                    .then(|| format!("Must be a named pipe such as {}mindlink", PIPE_PREFIX))
+            } else if !std::path::Path::new(socket_path).is_absolute() {
+                Some("Must be an absolute path".to_string())
+            } else {
+                // The path has to fit in `sun_path` of the socket address
+                (socket_path.len() >= 104).then(|| "Must be shorter than 104 bytes".to_string())
+            };
+            if let Some(problem) = problem {
+                return Err(MindLinkError::Configuration {
+                    message: format!("Invalid server socket path: {}. {}", socket_path, problem),
+                    config_key: Some("server.socket_path".to_string()),
+                    source: None,
+                });
+            }
+        }
+
+        // Validate bifrost config
+        if config.bifrost.port == 0 {
+            return Err(MindLinkError::Configuration {
+                message: "Bifrost port cannot be 0".to_string(),
+                config_key: Some("bifrost.port".to_string()),
+                source: None,
+            });
+        }
+
+        // Validate reasoning effort values
+        let valid_efforts = ["low", "medium", "high"];
+        if !valid_efforts.contains(&config.features.reasoning_effort.as_str()) {
+            return Err(MindLinkError::Configuration {
+                message: format!(
+                    "Invalid reasoning effort: {}. Must be one of: {:?}",
+                    config.features.reasoning_effort, valid_efforts
+                ),
+                config_key: Some("features.reasoning_effort".to_string()),
+                source: None,
+            });
+        }
+
+        // Validate tunnel type
+        let valid_types = ["quick", "named", "ngrok"];
+        if !valid_types.contains(&config.tunnel.tunnel_type.as_str()) {
+            return Err(MindLinkError::Configuration {
+                message: format!(
+                    "Invalid tunnel type: {}. Must be one of: {:?}",
+                    config.tunnel.tunnel_type, valid_types
+                ),
+                config_key: Some("tunnel.tunnel_type".to_string()),
+                source: None,
+            });
+        }
+
+        if config.tunnel.tunnel_type == "named" {
+            let named = &config.tunnel.named;
+            let valid_name = !named.name.is_empty()
+                && named
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid_name {
+                return Err(MindLinkError::Configuration {
+                    message: format!(
+                        "Invalid tunnel name '{}': use letters, digits, '-' and '_'",
+                        named.name
+                    ),
+                    config_key: Some("tunnel.named.name".to_string()),
+                    source: None,
+                });
+            }
+            if !is_valid_hostname(&named.hostname) {
+                return Err(MindLinkError::Configuration {
+                    message: format!(
+                        "Invalid tunnel hostname '{}': expected a name like mindlink.example.com",
+                        named.hostname
+                    ),
+                    config_key: Some("tunnel.named.hostname".to_string()),
+                    source: None,
+                });
+            }
+        }
+
+        if config.tunnel.tunnel_type == "ngrok" {
+            let ngrok = &config.tunnel.ngrok;
+            if ngrok.authtoken.as_deref().is_none_or(str::is_empty) {
+                return Err(MindLinkError::Configuration {
+                    message: "ngrok tunnels need the authtoken of an ngrok account".to_string(),
+                    config_key: Some("tunnel.ngrok.authtoken".to_string()),
+                    source: None,
+                });
+            }
+            if let Some(domain) = ngrok.domain.as_deref() {
+                if !is_valid_hostname(domain) {
+                    return Err(MindLinkError::Configuration {
+                        message: format!(
+                            "Invalid ngrok domain '{}': expected a name like mindlink.ngrok.app",
+                            domain
+                        ),
+                        config_key: Some("tunnel.ngrok.domain".to_string()),
+                        source: None,
+                    });
+                }
+            }
+        }
+
+        let cloudflared = &config.tunnel.cloudflared;
+        let options = [
+            ("region", &cloudflared.region, CLOUDFLARED_REGIONS),
+            (
+                "edge_ip_version",
+                &cloudflared.edge_ip_version,
+                CLOUDFLARED_IP_VERSIONS,
+            ),
+            ("protocol", &cloudflared.protocol, CLOUDFLARED_PROTOCOLS),
+        ];
+        for (name, value, valid) in options {
+            if let Some(value) = value {
+                if !valid.contains(&value.as_str()) {
+                    return Err(MindLinkError::Configuration {
+                        message: format!(
+                            "Invalid cloudflared {} '{}'. Must be one of: {:?}",
+                            name, value, valid
+                        ),
+                        config_key: Some(format!("tunnel.cloudflared.{}", name)),
+                        source: None,
+                    });
+                }
+            }
+        }
+
+        let updates = &config.tunnel.cloudflared_updates;
+        if updates.check_interval_hours == 0 {
+            return Err(MindLinkError::Configuration {
+                message: "cloudflared update checks must be at least 1 hour apart".to_string(),
+                config_key: Some("tunnel.cloudflared_updates.check_interval_hours".to_string()),
+                source: None,
+            });
+        }
+        if let Some(version) = &updates.pinned_version {
+            if crate::cloudflared_release::version_parts(version).is_none() {
+                return Err(MindLinkError::Configuration {
+                    message: format!(
+                        "Invalid cloudflared version '{}': expected a release such as 2024.6.1",
+                        version
+                    ),
+                    config_key: Some("tunnel.cloudflared_updates.pinned_version".to_string()),
+                    source: None,
+                });
+            }
+        }
+
+        let fallback = &config.tunnel.fallback;
+        let valid_server = url::Url::parse(&fallback.server)
+            .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host().is_some());
+        if fallback.enabled && !valid_server {
+            return Err(MindLinkError::Configuration {
+                message: format!(
+                    "Invalid fallback tunnel server '{}': expected an http(s) URL",
+                    fallback.server
+                ),
+                config_key: Some("tunnel.fallback.server".to_string()),
+                source: None,
+            });
+        }
+
+        let limits = &config.tunnel.limits;
+        if limits.max_connections == Some(0) || limits.max_monthly_egress_mb == Some(0) {
+            return Err(MindLinkError::Configuration {
+                message: "Tunnel limits must be at least 1; leave a limit unset to lift it"
+                    .to_string(),
+                config_key: Some("tunnel.limits".to_string()),
+                source: None,
+            });
+        }
+        if !(1..=99).contains(&limits.warn_at_percent) {
+            return Err(MindLinkError::Configuration {
+                message: format!(
+                    "Invalid tunnel limit warning threshold {}%. Must be between 1 and 99",
+                    limits.warn_at_percent
+                ),
+                config_key: Some("tunnel.limits.warn_at_percent".to_string()),
+                source: None,
+            });
+        }
+
+        let schedule = &config.tunnel.schedule;
+        if schedule.enabled && schedule.windows.is_empty() {
+            return Err(MindLinkError::Configuration {
+                message: "The tunnel schedule needs at least one window".to_string(),
+                config_key: Some("tunnel.schedule.windows".to_string()),
+                source: None,
+            });
+        }
+        for window in &schedule.windows {
+            if let Err(message) = crate::tunnel_schedule::Window::parse(window) {
+                return Err(MindLinkError::Configuration {
+                    message,
+                    config_key: Some("tunnel.schedule.windows".to_string()),
+                    source: None,
+                });
+            }
+        }
+
+        let access = &config.tunnel.cloudflare_access;
+        if access.enabled {
+            if config.tunnel.tunnel_type != "named" {
+                return Err(MindLinkError::Configuration {
+                    message: "Cloudflare Access can only protect a named tunnel".to_string(),
+                    config_key: Some("tunnel.cloudflare_access.enabled".to_string()),
+                    source: None,
+                });
+            }
+            let valid_account = access.account_id.len() == 32
+                && access.account_id.chars().all(|c| c.is_ascii_hexdigit());
+            if !valid_account {
+                return Err(MindLinkError::Configuration {
+                    message: format!(
+                        "Invalid Cloudflare account ID '{}': expected 32 hex digits",
+                        access.account_id
+                    ),
+                    config_key: Some("tunnel.cloudflare_access.account_id".to_string()),
+                    source: None,
+                });
+            }
+            if access.api_token.as_deref().is_none_or(str::is_empty) {
+                return Err(MindLinkError::Configuration {
+                    message: "Cloudflare Access needs an API token of the account".to_string(),
+                    config_key: Some("tunnel.cloudflare_access.api_token".to_string()),
+                    source: None,
+                });
+            }
+        }
+
+        for webhook in &config.tunnel.webhooks {
+            let valid = url::Url::parse(&webhook.url)
+                .is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+            if !valid {
+                return Err(MindLinkError::Configuration {
+                    message: format!("Invalid tunnel webhook URL '{}'", webhook.url),
+                    config_key: Some("tunnel.webhooks.url".to_string()),
+                    source: None,
+                });
+            }
+        }
+
+        let mut services = HashSet::new();
+        for service in &config.tunnel.services {
+            if service.service == TunnelService::Api {
+                return Err(MindLinkError::Configuration {
+                    message: "The API is served by the main tunnel".to_string(),
+                    config_key: Some("tunnel.services.service".to_string()),
+                    source: None,
+                });
+            }
+            if !services.insert(service.service) {
+                return Err(MindLinkError::Configuration {
+                    message: format!("The {} service has more than one tunnel", service.service),
+                    config_key: Some("tunnel.services.service".to_string()),
+                    source: None,
+                });
+            }
+            if service.local_port == 0 {
+                return Err(MindLinkError::Configuration {
+                    message: format!("The {} tunnel needs the service's port", service.service),
+                    config_key: Some("tunnel.services.local_port".to_string()),
+                    source: None,
+                });
+            }
+            let needs_hostname = config.tunnel.tunnel_type == "named";
+            if (needs_hostname || !service.hostname.is_empty())
+                && !is_valid_hostname(&service.hostname)
+            {
+                return Err(MindLinkError::Configuration {
+                    message: format!(
+                        "Invalid hostname '{}' of the {} tunnel",
+                        service.hostname, service.service
+                    ),
+                    config_key: Some("tunnel.services.hostname".to_string()),
+                    source: None,
+                });
+            }
+        }
+
+        // Tunnel tokens are rotated ahead of expiry, so a lifetime shorter than
+        // the lead would rotate them on every check
+        let lifetime = config.tunnel.access_token_lifetime_hours;
+        if !(1..=MAX_ACCESS_TOKEN_LIFETIME_HOURS).contains(&lifetime) {
+            return Err(MindLinkError::Configuration {
+                message: format!(
+                    "Tunnel access tokens must be valid for 1 to {} hours",
+                    MAX_ACCESS_TOKEN_LIFETIME_HOURS
+                ),
+                config_key: Some("tunnel.access_token_lifetime_hours".to_string()),
+                source: None,
+            });
+        }
+
+        if config.local_models.warm_pool_size > 16 {
+            return Err(MindLinkError::Configuration {
+                message: "Warm pool size cannot exceed 16 models".to_string(),
+                config_key: Some("local_models.warm_pool_size".to_string()),
+                source: None,
+            });
+        }
+
+        if url::Url::parse(&config.local_models.ollama_url).is_err() {
+            return Err(MindLinkError::Configuration {
+                message: format!("Invalid Ollama URL: {}", config.local_models.ollama_url),
+                config_key: Some("local_models.ollama_url".to_string()),
+                source: None,
+            });
+        }
+
+        AccessPolicy::from_config(&config.access_control)?;
+        TrustedProxies::from_config(&config.access_control)?;
+
+        let analytics = &config.analytics;
+        if analytics.batch_size == 0 || analytics.buffer_capacity < analytics.batch_size {
+            return Err(MindLinkError::Configuration {
+                message: "Analytics batch size must be positive and fit in the buffer".to_string(),
+                config_key: Some("analytics.batch_size".to_string()),
+                source: None,
+            });
+        }
+
+        if analytics.flush_interval_ms < 50 || analytics.pressure_sample_rate == 0 {
+            return Err(MindLinkError::Configuration {
+                message: "Analytics flush interval must be >= 50ms and sample rate >= 1"
+                    .to_string(),
+                config_key: Some("analytics".to_string()),
+                source: None,
+            });
+        }
+
+        let language_detection = &config.language_detection;
+        if !(0.0..=1.0).contains(&language_detection.min_confidence) {
+            return Err(MindLinkError::Configuration {
+                message: "Language detection confidence must be between 0 and 1".to_string(),
+                config_key: Some("language_detection.min_confidence".to_string()),
+                source: None,
+            });
+        }
+
+        for (code, model) in &language_detection.routes {
+            if whatlang::Lang::from_code(code).is_none() || model.trim().is_empty() {
+                return Err(MindLinkError::Configuration {
+                    message: format!(
+                        "Invalid language route '{}': expected an ISO 639-3 code and a model",
+                        code
+                    ),
+                    config_key: Some("language_detection.routes".to_string()),
+                    source: None,
+                });
+            }
+        }
+
+        for (alias, model) in &config.model_aliases.aliases {
+            if alias.trim().is_empty() || model.trim().is_empty() {
+                return Err(MindLinkError::Configuration {
+                    message: format!(
+                        "Invalid model alias '{}' -> '{}': both names are required",
+                        alias, model
+                    ),
+                    config_key: Some("model_aliases".to_string()),
+                    source: None,
+                });
+            }
+        }
+
+        if config.stream_continuation.max_continuations > 10 {
+            return Err(MindLinkError::Configuration {
+                message: "Stream continuation allows at most 10 follow-up requests".to_string(),
+                config_key: Some("stream_continuation.max_continuations".to_string()),
+                source: None,
+            });
+        }
+
+        for (name, template) in &config.prompts.templates {
+            if name.trim().is_empty() || template.trim().is_empty() {
+                return Err(MindLinkError::Configuration {
+                    message: format!("Prompt template '{}' needs a name and a prompt", name),
+                    config_key: Some("prompts.templates".to_string()),
+                    source: None,
+                });
+            }
+        }
+
+        if config.conversations.ttl_minutes == 0 || config.conversations.max_conversations == 0 {
+            return Err(MindLinkError::Configuration {
+                message: "Conversation TTL and capacity must be greater than zero".to_string(),
+                config_key: Some("conversations".to_string()),
+                source: None,
+            });
+        }
+
+        if config.batches.max_retries > 10 {
+            return Err(MindLinkError::Configuration {
+                message: "Batch requests can be retried at most 10 times".to_string(),
+                config_key: Some("batches.max_retries".to_string()),
+                source: None,
+            });
+        }
+
+        if !(1..=3600).contains(&config.accounts.rate_limit_cooldown_secs) {
+            return Err(MindLinkError::Configuration {
+                message: "Rate limit cooldown must be between 1 and 3600 seconds".to_string(),
+                config_key: Some("accounts.rate_limit_cooldown_secs".to_string()),
+                source: None,
+            });
+        }
+        if !(1..=10080).contains(&config.accounts.session_ttl_minutes) {
+            return Err(MindLinkError::Configuration {
+                message: "Session pinning must last between 1 minute and 7 days".to_string(),
+                config_key: Some("accounts.session_ttl_minutes".to_string()),
+                source: None,
+            });
+        }
 
-    /// Create default configuration with validation
-    async fn create_default_config(config_path: &PathBuf) -> MindLinkResult<ConfigSchema> {
-        let default_config = ConfigSchema {
-            version: CONFIG_VERSION,
-            server: ServerConfig {
-                port: 3001,
-                host: "127.0.0.1".to_string(),
-            },
-            bifrost: BifrostConfig {
-                port: 3002,
-                host: "127.0.0.1".to_string(),
-                enabled: true,
-            },
-            tunnel: TunnelConfig {
-                enabled: true,
-                tunnel_type: "quick".to_string(),
-            },
-            features: FeatureConfig {
-                reasoning_effort: "medium".to_string(),
-                reasoning_summaries: "auto".to_string(),
-                reasoning_compatibility: "think-tags".to_string(),
-            },
-            monitoring: MonitoringConfig {
-                health_check_interval: 30,
-                error_threshold: 5,
-                notifications: true,
-            },
-        };
+        for provider in &config.failover.providers {
+            if provider.name.trim().is_empty() {
+                return Err(MindLinkError::Configuration {
+                    message: "Every fallback provider needs a name".to_string(),
+                    config_key: Some("failover.providers".to_string()),
+                    source: None,
+                });
+            }
+            let valid_url = provider.base_url.as_deref().map_or(true, |url| {
+                url::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+            });
+            if !valid_url {
+                return Err(MindLinkError::Configuration {
+                    message: format!("Invalid base URL for fallback provider '{}'", provider.name),
+                    config_key: Some("failover.providers".to_string()),
+                    source: None,
+                });
+            }
+        }
 
-        Self::validate_config(&default_config)?;
+        if !(1..=16).contains(&config.jobs.max_concurrent) {
+            return Err(MindLinkError::Configuration {
+                message: "Concurrent jobs must be between 1 and 16".to_string(),
+                config_key: Some("jobs.max_concurrent".to_string()),
+                source: None,
+            });
+        }
 
-        // Save default config
-        let json = serde_json::to_string_pretty(&default_config).map_err(|e| {
-            MindLinkError::Configuration {
-                message: "Failed to serialize default configuration".to_string(),
-                config_key: None,
-                source: Some(e.into()),
+        for signer in &config.bundles.trusted_signers {
+            let valid_key = STANDARD
+                .decode(signer.public_key.trim())
+                .is_ok_and(|key| key.len() == 32);
+            if signer.name.trim().is_empty() || !valid_key {
+                return Err(MindLinkError::Configuration {
+                    message: format!(
+                        "Trusted signer '{}' needs a name and a valid public key",
+                        signer.name
+                    ),
+                    config_key: Some("bundles.trusted_signers".to_string()),
+                    source: None,
+                });
             }
-        })?;
-
-        fs::write(config_path, json)
-            .await
-            .map_err(|e| MindLinkError::FileSystem {
-                message: "Failed to save default configuration".to_string(),
-                path: Some(config_path.to_string_lossy().to_string()),
-                operation: "write".to_string(),
-                source: Some(e.into()),
-            })?;
+        }
 
-        log_info!("ConfigManager", "Default configuration created and saved");
+        if !(1..=256).contains(&config.backpressure.max_in_flight) {
+            return Err(MindLinkError::Configuration {
+                message: "Maximum in-flight requests must be between 1 and 256".to_string(),
+                config_key: Some("backpressure.max_in_flight".to_string()),
+                source: None,
+            });
+        }
 
-        Ok(default_config)
-    }
+        if !(1..=1000).contains(&config.capture.max_captures) {
+            return Err(MindLinkError::Configuration {
+                message: "Kept captures must be between 1 and 1000".to_string(),
+                config_key: Some("capture.max_captures".to_string()),
+                source: None,
+            });
+        }
 
-    /// Validate configuration values
-    fn validate_config(config: &ConfigSchema) -> MindLinkResult<()> {
-        // Validate server config
-        if config.server.port == 0 {
+        let limits = &config.limits;
+        let body_limits = [
+            ("limits.max_body_bytes", limits.max_body_bytes),
+            ("limits.max_upload_bytes", limits.max_upload_bytes),
+        ];
+        for (key, bytes) in body_limits.into_iter().chain(
+            limits
+                .route_body_limits
+                .values()
+                .map(|bytes| ("limits.route_body_limits", *bytes)),
+        ) {
+            if !(MIN_BODY_LIMIT..=MAX_BODY_LIMIT).contains(&bytes) {
+                return Err(MindLinkError::Configuration {
+                    message: "Body limits must be between 1 KiB and 1 GiB".to_string(),
+                    config_key: Some(key.to_string()),
+                    source: None,
+                });
+            }
+        }
+        if limits
+            .route_body_limits
+            .keys()
+            .any(|route| !route.starts_with('/'))
+        {
             return Err(MindLinkError::Configuration {
-                message: "Server port cannot be 0".to_string(),
-                config_key: Some("server.port".to_string()),
+                message: "Routes with a body limit must start with '/'".to_string(),
+                config_key: Some("limits.route_body_limits".to_string()),
                 source: None,
             });
         }
+        for (key, secs) in [
+            ("limits.upstream_timeout_secs", limits.upstream_timeout_secs),
+            (
+                "limits.stream_idle_timeout_secs",
+                limits.stream_idle_timeout_secs,
+            ),
+        ] {
+            if !(1..=3600).contains(&secs) {
+                return Err(MindLinkError::Configuration {
+                    message: "Timeouts must be between 1 and 3600 seconds".to_string(),
+                    config_key: Some(key.to_string()),
+                    source: None,
+                });
+            }
+        }
 
-        if config.server.host.is_empty() {
+        if config.shadow.percentage > 100 {
             return Err(MindLinkError::Configuration {
-                message: "Server host cannot be empty".to_string(),
-                config_key: Some("server.host".to_string()),
+                message: "Mirrored share must be between 0 and 100 percent".to_string(),
+                config_key: Some("shadow.percentage".to_string()),
+                source: None,
+            });
+        }
+        let http = &config.http;
+        for (key, secs) in [
+            (
+                "http.header_read_timeout_secs",
+                http.header_read_timeout_secs,
+            ),
+            (
+                "http.http2_keep_alive_timeout_secs",
+                http.http2_keep_alive_timeout_secs,
+            ),
+            (
+                "http.upstream_pool_idle_timeout_secs",
+                http.upstream_pool_idle_timeout_secs,
+            ),
+        ] {
+            if !(1..=3600).contains(&secs) {
+                return Err(MindLinkError::Configuration {
+                    message: "Timeouts must be between 1 and 3600 seconds".to_string(),
+                    config_key: Some(key.to_string()),
+                    source: None,
+                });
+            }
+        }
+        if http.http2_keep_alive_interval_secs > 3600 {
+            return Err(MindLinkError::Configuration {
+                message: "HTTP/2 pings must be at most 3600 seconds apart".to_string(),
+                config_key: Some("http.http2_keep_alive_interval_secs".to_string()),
+                source: None,
+            });
+        }
+        if !(1..=10_000).contains(&http.http2_max_concurrent_streams) {
+            return Err(MindLinkError::Configuration {
+                message: "Concurrent HTTP/2 streams must be between 1 and 10000".to_string(),
+                config_key: Some("http.http2_max_concurrent_streams".to_string()),
+                source: None,
+            });
+        }
+        if http.upstream_pool_max_idle > 1024 {
+            return Err(MindLinkError::Configuration {
+                message: "At most 1024 idle upstream connections can be kept".to_string(),
+                config_key: Some("http.upstream_pool_max_idle".to_string()),
                 source: None,
             });
         }
 
-        // Validate bifrost config
-        if config.bifrost.port == 0 {
+        let target = &config.shadow.target;
+        let valid_url = target.base_url.as_deref().map_or(true, |url| {
+            url::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+        });
+        if !valid_url {
             return Err(MindLinkError::Configuration {
-                message: "Bifrost port cannot be 0".to_string(),
-                config_key: Some("bifrost.port".to_string()),
+                message: "Invalid base URL for the shadow backend".to_string(),
+                config_key: Some("shadow.target.base_url".to_string()),
                 source: None,
             });
         }
 
-        // Validate reasoning effort values
-        let valid_efforts = ["low", "medium", "high"];
-        if !valid_efforts.contains(&config.features.reasoning_effort.as_str()) {
+        for rule in &config.redaction.custom_rules {
+            let valid_name = !rule.name.is_empty()
+                && rule.name.len() <= 32
+                && rule
+                    .name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+            if !valid_name {
+                return Err(MindLinkError::Configuration {
+                    message: format!(
+                        "Redaction rule name '{}' must be 1 to 32 letters, digits, '_' or '-'",
+                        rule.name
+                    ),
+                    config_key: Some("redaction.custom_rules".to_string()),
+                    source: None,
+                });
+            }
+            if let Err(e) = regex::Regex::new(&rule.pattern) {
+                return Err(MindLinkError::Configuration {
+                    message: format!("Invalid pattern for redaction rule '{}'", rule.name),
+                    config_key: Some("redaction.custom_rules".to_string()),
+                    source: Some(e.into()),
+                });
+            }
+        }
+
+        if let Some(provider) = &config.moderation.provider {
+            let valid_url = provider.base_url.as_deref().is_some_and(|url| {
+                url::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"))
+            });
+            if !valid_url {
+                return Err(MindLinkError::Configuration {
+                    message: "The moderation provider needs an http(s) base URL".to_string(),
+                    config_key: Some("moderation.provider.base_url".to_string()),
+                    source: None,
+                });
+            }
+        }
+        for rule in &config.moderation.rules {
+            if rule.category.trim().is_empty() {
+                return Err(MindLinkError::Configuration {
+                    message: "Moderation rules need a category".to_string(),
+                    config_key: Some("moderation.rules".to_string()),
+                    source: None,
+                });
+            }
+            if let Err(e) = regex::Regex::new(&rule.pattern) {
+                return Err(MindLinkError::Configuration {
+                    message: format!("Invalid pattern for moderation rule '{}'", rule.category),
+                    config_key: Some("moderation.rules".to_string()),
+                    source: Some(e.into()),
+                });
+            }
+        }
+
+        for rule in &config.post_processing.rules {
+            match &rule.action {
+                PostProcessingAction::RegexReplace { pattern, .. } => {
+                    if let Err(e) = regex::Regex::new(pattern) {
+                        return Err(MindLinkError::Configuration {
+                            message: format!("Invalid post-processing pattern '{}'", pattern),
+                            config_key: Some("post_processing.rules".to_string()),
+                            source: Some(e.into()),
+                        });
+                    }
+                },
+                PostProcessingAction::StopSequences { sequences } => {
+                    if sequences.is_empty() || sequences.iter().any(String::is_empty) {
+                        return Err(MindLinkError::Configuration {
+                            message: "Stop sequence rules need non-empty sequences".to_string(),
+                            config_key: Some("post_processing.rules".to_string()),
+                            source: None,
+                        });
+                    }
+                },
+                PostProcessingAction::StripMarkdown | PostProcessingAction::ExtractJson => {},
+            }
+        }
+
+        for rule in &config.request_transforms.rules {
+            let invalid = |message: String| MindLinkError::Configuration {
+                message,
+                config_key: Some("request_transforms.rules".to_string()),
+                source: None,
+            };
+            if let Some(user_agent) = &rule.user_agent {
+                if regex::Regex::new(user_agent).is_err() {
+                    return Err(invalid(format!(
+                        "Invalid User-Agent pattern '{}'",
+                        user_agent
+                    )));
+                }
+            }
+            match &rule.action {
+                RequestTransformAction::SetField { field, .. } if field.is_empty() => {
+                    return Err(invalid("Set field rules need a field name".to_string()));
+                },
+                RequestTransformAction::RemoveFields { fields }
+                    if fields.is_empty() || fields.iter().any(String::is_empty) =>
+                {
+                    return Err(invalid(
+                        "Remove fields rules need non-empty field names".to_string(),
+                    ));
+                },
+                RequestTransformAction::RenameModel { to } if to.is_empty() => {
+                    return Err(invalid("Rename model rules need a model".to_string()));
+                },
+                RequestTransformAction::SetHeader { name, value }
+                    if HeaderName::from_bytes(name.as_bytes()).is_err()
+                        || HeaderValue::from_str(value).is_err() =>
+                {
+                    return Err(invalid(format!("Invalid header '{}: {}'", name, value)));
+                },
+                RequestTransformAction::RemoveHeader { name }
+                    if HeaderName::from_bytes(name.as_bytes()).is_err() =>
+                {
+                    return Err(invalid(format!("Invalid header name '{}'", name)));
+                },
+                _ => {},
+            }
+        }
+
+        let auth = &config.auth;
+        if auth.client_id.is_empty() || auth.client_id.contains(char::is_whitespace) {
+            return Err(MindLinkError::Configuration {
+                message: "OAuth client ID must be non-empty and without spaces".to_string(),
+                config_key: Some("auth.client_id".to_string()),
+                source: None,
+            });
+        }
+        if auth.redirect_port == 0 || auth.redirect_port == config.server.port {
             return Err(MindLinkError::Configuration {
                 message: format!(
-                    "Invalid reasoning effort: {}. Must be one of: {:?}",
-                    config.features.reasoning_effort, valid_efforts
+                    "Invalid OAuth redirect port: {}. Must be set and differ from the server port",
+                    auth.redirect_port
                 ),
-                config_key: Some("features.reasoning_effort".to_string()),
+                config_key: Some("auth.redirect_port".to_string()),
+                source: None,
+            });
+        }
+        if auth.scopes.is_empty()
+            || auth
+                .scopes
+                .iter()
+                .any(|scope| scope.is_empty() || scope.contains(char::is_whitespace))
+        {
+            return Err(MindLinkError::Configuration {
+                message: "OAuth scopes must be a non-empty list of single words".to_string(),
+                config_key: Some("auth.scopes".to_string()),
                 source: None,
             });
         }
 
-        // Validate tunnel type
-        let valid_types = ["quick", "named"];
-        if !valid_types.contains(&config.tunnel.tunnel_type.as_str()) {
+        let anthropic = &config.anthropic;
+        if anthropic.enabled {
+            if anthropic.client_id.is_empty() || anthropic.client_id.contains(char::is_whitespace) {
+                return Err(MindLinkError::Configuration {
+                    message: "Claude logins need an OAuth client ID without spaces".to_string(),
+                    config_key: Some("anthropic.client_id".to_string()),
+                    source: None,
+                });
+            }
+            if anthropic.redirect_port == 0
+                || anthropic.redirect_port == config.server.port
+                || anthropic.redirect_port == auth.redirect_port
+            {
+                return Err(MindLinkError::Configuration {
+                    message: format!(
+                        "Invalid Claude redirect port: {}. Must be set and differ from the server and ChatGPT redirect ports",
+                        anthropic.redirect_port
+                    ),
+                    config_key: Some("anthropic.redirect_port".to_string()),
+                    source: None,
+                });
+            }
+            if anthropic
+                .scopes
+                .iter()
+                .any(|scope| scope.is_empty() || scope.contains(char::is_whitespace))
+            {
+                return Err(MindLinkError::Configuration {
+                    message: "Claude OAuth scopes must be single words".to_string(),
+                    config_key: Some("anthropic.scopes".to_string()),
+                    source: None,
+                });
+            }
+        }
+
+        if let Some(proxy_url) = &config.proxy.url {
+            let valid = url::Url::parse(proxy_url).is_ok_and(|url| {
+                crate::proxy::SCHEMES.contains(&url.scheme()) && url.host_str().is_some()
+            });
+            if !valid {
+                return Err(MindLinkError::Configuration {
+                    message: format!(
+                        "Invalid proxy URL. Must be an {} URL with a host",
+                        crate::proxy::SCHEMES.join(", ")
+                    ),
+                    config_key: Some("proxy.url".to_string()),
+                    source: None,
+                });
+            }
+        }
+
+        let probe_interval = config.monitoring.auth_probe_interval_secs;
+        if probe_interval != 0 && probe_interval < 60 {
             return Err(MindLinkError::Configuration {
-                message: format!(
-                    "Invalid tunnel type: {}. Must be one of: {:?}",
-                    config.tunnel.tunnel_type, valid_types
-                ),
-                config_key: Some("tunnel.tunnel_type".to_string()),
+                message: "Auth probe interval must be 0 (off) or at least 60 seconds".to_string(),
+                config_key: Some("monitoring.auth_probe_interval_secs".to_string()),
+                source: None,
+            });
+        }
+
+        let tunnel_probe_interval = config.monitoring.tunnel_probe_interval_secs;
+        if tunnel_probe_interval != 0 && tunnel_probe_interval < 10 {
+            return Err(MindLinkError::Configuration {
+                message: "Tunnel probe interval must be 0 (off) or at least 10 seconds".to_string(),
+                config_key: Some("monitoring.tunnel_probe_interval_secs".to_string()),
+                source: None,
+            });
+        }
+
+        if config.power_saver.health_check_interval_secs < 30 {
+            return Err(MindLinkError::Configuration {
+                message: "Power saver health check interval must be at least 30 seconds"
+                    .to_string(),
+                config_key: Some("power_saver.health_check_interval_secs".to_string()),
                 source: None,
             });
         }
@@ -279,7 +2394,8 @@ impl ConfigManager {
             );
 
             // Backup current config before migration
-            let backup_content = serde_json::to_string_pretty(&config).map_err(|e| {
+            let stored = Self::with_sealed_secrets(&config).await;
+            let backup_content = serde_json::to_string_pretty(&stored).map_err(|e| {
                 MindLinkError::Configuration {
                     message: "Failed to serialize config for backup".to_string(),
                     config_key: None,
@@ -301,7 +2417,8 @@ impl ConfigManager {
             config.version = CONFIG_VERSION;
 
             // Save migrated config
-            let json = serde_json::to_string_pretty(&config).map_err(|e| {
+            let stored = Self::with_sealed_secrets(&config).await;
+            let json = serde_json::to_string_pretty(&stored).map_err(|e| {
                 MindLinkError::Configuration {
                     message: "Failed to serialize migrated configuration".to_string(),
                     config_key: None,
@@ -327,6 +2444,104 @@ impl ConfigManager {
         Ok(config)
     }
 
+    /// Copy of `config` as written to disk, with its secrets encrypted
+    async fn with_sealed_secrets(config: &ConfigSchema) -> ConfigSchema {
+        let mut stored = config.clone();
+        for provider in &mut stored.failover.providers {
+            if let Some(api_key) = &provider.api_key {
+                provider.api_key = Some(secret_box::seal(api_key).await);
+            }
+        }
+        if let Some(authtoken) = &stored.tunnel.ngrok.authtoken {
+            stored.tunnel.ngrok.authtoken = Some(secret_box::seal(authtoken).await);
+        }
+        let access = &mut stored.tunnel.cloudflare_access;
+        if let Some(api_token) = &access.api_token {
+            access.api_token = Some(secret_box::seal(api_token).await);
+        }
+        if let Some(client_secret) = &access.client_secret {
+            access.client_secret = Some(secret_box::seal(client_secret).await);
+        }
+        for webhook in &mut stored.tunnel.webhooks {
+            if let Some(secret) = &webhook.secret {
+                webhook.secret = Some(secret_box::seal(secret).await);
+            }
+        }
+        stored
+    }
+
+    /// Decrypt the secrets of a configuration read from disk
+    async fn open_secrets(mut config: ConfigSchema) -> MindLinkResult<ConfigSchema> {
+        for provider in &mut config.failover.providers {
+            let Some(api_key) = &provider.api_key else {
+                continue;
+            };
+            match secret_box::open(api_key).await {
+                Ok(api_key) => provider.api_key = Some(api_key),
+                Err(e) => {
+                    return Err(MindLinkError::Configuration {
+                        message: format!(
+                            "Failed to decrypt the API key of fallback provider '{}'",
+                            provider.name
+                        ),
+                        config_key: Some("failover.providers.api_key".to_string()),
+                        source: Some(e),
+                    })
+                },
+            }
+        }
+        if let Some(authtoken) = &config.tunnel.ngrok.authtoken {
+            match secret_box::open(authtoken).await {
+                Ok(authtoken) => config.tunnel.ngrok.authtoken = Some(authtoken),
+                Err(e) => {
+                    return Err(MindLinkError::Configuration {
+                        message: "Failed to decrypt the ngrok authtoken".to_string(),
+                        config_key: Some("tunnel.ngrok.authtoken".to_string()),
+                        source: Some(e),
+                    })
+                },
+            }
+        }
+        let access = &mut config.tunnel.cloudflare_access;
+        for (key, secret) in [
+            ("api_token", &mut access.api_token),
+            ("client_secret", &mut access.client_secret),
+        ] {
+            let Some(sealed) = secret.as_deref() else {
+                continue;
+            };
+            match secret_box::open(sealed).await {
+                Ok(opened) => *secret = Some(opened),
+                Err(e) => {
+                    return Err(MindLinkError::Configuration {
+                        message: "Failed to decrypt a Cloudflare Access credential".to_string(),
+                        config_key: Some(format!("tunnel.cloudflare_access.{}", key)),
+                        source: Some(e),
+                    })
+                },
+            }
+        }
+        for webhook in &mut config.tunnel.webhooks {
+            let Some(secret) = &webhook.secret else {
+                continue;
+            };
+            match secret_box::open(secret).await {
+                Ok(secret) => webhook.secret = Some(secret),
+                Err(e) => {
+                    return Err(MindLinkError::Configuration {
+                        message: format!(
+                            "Failed to decrypt the secret of tunnel webhook '{}'",
+                            webhook.url
+                        ),
+                        config_key: Some("tunnel.webhooks.secret".to_string()),
+                        source: Some(e),
+                    })
+                },
+            }
+        }
+        Ok(config)
+    }
+
     /// Migrate configuration between versions
     fn migrate_config(config: ConfigSchema) -> MindLinkResult<ConfigSchema> {
         // For now, no migration logic needed since this is version 1
@@ -345,6 +2560,7 @@ impl ConfigManager {
 
         // Create backup before update
         let current_config = self.config.read().await.clone();
+        let current_config = Self::with_sealed_secrets(&current_config).await;
         let backup_content = serde_json::to_string_pretty(&current_config).map_err(|e| {
             MindLinkError::Configuration {
                 message: "Failed to serialize current config for backup".to_string(),
@@ -363,7 +2579,8 @@ impl ConfigManager {
             })?;
 
         // Save new config
-        let json = serde_json::to_string_pretty(&new_config).map_err(|e| {
+        let sealed_config = Self::with_sealed_secrets(&new_config).await;
+        let json = serde_json::to_string_pretty(&sealed_config).map_err(|e| {
             MindLinkError::Configuration {
                 message: "Failed to serialize new configuration".to_string(),
                 config_key: None,
@@ -401,10 +2618,183 @@ impl ConfigManager {
         self.config.read().await.tunnel.clone()
     }
 
+    pub async fn set_tunnel_config(&self, tunnel: TunnelConfig) -> MindLinkResult<()> {
+        let mut config = self.get_config().await;
+        config.tunnel = tunnel;
+        self.update_config(config).await
+    }
+
     pub async fn get_feature_config(&self) -> FeatureConfig {
         self.config.read().await.features.clone()
     }
 
+    pub async fn get_local_models_config(&self) -> LocalModelsConfig {
+        self.config.read().await.local_models.clone()
+    }
+
+    pub async fn get_access_control_config(&self) -> AccessControlConfig {
+        self.config.read().await.access_control.clone()
+    }
+
+    pub async fn get_tool_emulation_config(&self) -> ToolEmulationConfig {
+        self.config.read().await.tool_emulation.clone()
+    }
+
+    pub async fn get_analytics_config(&self) -> AnalyticsConfig {
+        self.config.read().await.analytics.clone()
+    }
+
+    pub async fn get_language_detection_config(&self) -> LanguageDetectionConfig {
+        self.config.read().await.language_detection.clone()
+    }
+
+    pub async fn get_stream_continuation_config(&self) -> StreamContinuationConfig {
+        self.config.read().await.stream_continuation.clone()
+    }
+
+    pub async fn get_power_saver_config(&self) -> PowerSaverConfig {
+        self.config.read().await.power_saver.clone()
+    }
+
+    /// Validate and persist a new power saver mode
+    pub async fn set_power_saver_mode(&self, mode: PowerSaverMode) -> MindLinkResult<()> {
+        let mut config = self.get_config().await;
+        config.power_saver.mode = mode;
+        self.update_config(config).await
+    }
+
+    pub async fn get_model_aliases(&self) -> ModelAliasConfig {
+        self.config.read().await.model_aliases.clone()
+    }
+
+    /// Validate and persist a new model alias map
+    pub async fn set_model_aliases(&self, model_aliases: ModelAliasConfig) -> MindLinkResult<()> {
+        let mut config = self.get_config().await;
+        config.model_aliases = model_aliases;
+        self.update_config(config).await
+    }
+
+    pub async fn get_prompt_config(&self) -> PromptConfig {
+        self.config.read().await.prompts.clone()
+    }
+
+    /// Validate and persist new system prompt and template settings
+    pub async fn set_prompt_config(&self, prompts: PromptConfig) -> MindLinkResult<()> {
+        let mut config = self.get_config().await;
+        config.prompts = prompts;
+        self.update_config(config).await
+    }
+
+    pub async fn get_conversation_config(&self) -> ConversationConfig {
+        self.config.read().await.conversations.clone()
+    }
+
+    pub async fn get_batch_config(&self) -> BatchConfig {
+        self.config.read().await.batches.clone()
+    }
+
+    pub async fn get_accounts_config(&self) -> AccountsConfig {
+        self.config.read().await.accounts.clone()
+    }
+
+    pub async fn get_failover_config(&self) -> FailoverConfig {
+        self.config.read().await.failover.clone()
+    }
+
+    pub async fn get_bundle_config(&self) -> BundleConfig {
+        self.config.read().await.bundles.clone()
+    }
+
+    pub async fn get_job_config(&self) -> JobConfig {
+        self.config.read().await.jobs.clone()
+    }
+
+    pub async fn get_redaction_config(&self) -> RedactionConfig {
+        self.config.read().await.redaction.clone()
+    }
+
+    pub async fn get_backpressure_config(&self) -> BackpressureConfig {
+        self.config.read().await.backpressure.clone()
+    }
+
+    pub async fn get_capture_config(&self) -> CaptureConfig {
+        self.config.read().await.capture.clone()
+    }
+
+    pub async fn get_limits_config(&self) -> LimitsConfig {
+        self.config.read().await.limits.clone()
+    }
+
+    pub async fn get_shadow_config(&self) -> ShadowConfig {
+        self.config.read().await.shadow.clone()
+    }
+
+    pub async fn get_http_config(&self) -> HttpConfig {
+        self.config.read().await.http.clone()
+    }
+
+    pub async fn get_moderation_config(&self) -> ModerationConfig {
+        self.config.read().await.moderation.clone()
+    }
+
+    pub async fn get_post_processing_config(&self) -> PostProcessingConfig {
+        self.config.read().await.post_processing.clone()
+    }
+
+    /// Validate and persist new post-processing rules
+    pub async fn set_post_processing_config(
+        &self,
+        post_processing: PostProcessingConfig,
+    ) -> MindLinkResult<()> {
+        let mut config = self.get_config().await;
+        config.post_processing = post_processing;
+        self.update_config(config).await
+    }
+
+    pub async fn get_request_transform_config(&self) -> RequestTransformConfig {
+        self.config.read().await.request_transforms.clone()
+    }
+
+    /// Validate and persist new request transformation rules
+    pub async fn set_request_transform_config(
+        &self,
+        request_transforms: RequestTransformConfig,
+    ) -> MindLinkResult<()> {
+        let mut config = self.get_config().await;
+        config.request_transforms = request_transforms;
+        self.update_config(config).await
+    }
+
+    pub async fn get_auth_config(&self) -> AuthConfig {
+        self.config.read().await.auth.clone()
+    }
+
+    pub async fn set_auth_config(&self, auth: AuthConfig) -> MindLinkResult<()> {
+        let mut config = self.get_config().await;
+        config.auth = auth;
+        self.update_config(config).await
+    }
+
+    pub async fn get_anthropic_config(&self) -> AnthropicConfig {
+        self.config.read().await.anthropic.clone()
+    }
+
+    pub async fn set_anthropic_config(&self, anthropic: AnthropicConfig) -> MindLinkResult<()> {
+        let mut config = self.get_config().await;
+        config.anthropic = anthropic;
+        self.update_config(config).await
+    }
+
+    pub async fn get_proxy_config(&self) -> ProxyConfig {
+        self.config.read().await.proxy.clone()
+    }
+
+    pub async fn set_proxy_config(&self, proxy: ProxyConfig) -> MindLinkResult<()> {
+        let mut config = self.get_config().await;
+        config.proxy = proxy;
+        self.update_config(config).await
+    }
+
     pub async fn get_monitoring_config(&self) -> MonitoringConfig {
         self.config.read().await.monitoring.clone()
     }
@@ -436,11 +2826,13 @@ impl ConfigManager {
                 config_key: None,
                 source: Some(e.into()),
             })?;
+        let backup_config = Self::open_secrets(backup_config).await?;
 
         Self::validate_config(&backup_config)?;
 
         // Save restored config
-        let json = serde_json::to_string_pretty(&backup_config).map_err(|e| {
+        let sealed_config = Self::with_sealed_secrets(&backup_config).await;
+        let json = serde_json::to_string_pretty(&sealed_config).map_err(|e| {
             MindLinkError::Configuration {
                 message: "Failed to serialize restored configuration".to_string(),
                 config_key: None,
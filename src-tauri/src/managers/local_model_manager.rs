@@ -0,0 +1,215 @@
+// Local Model Manager - Keeps a warm pool of loaded models on local backends (Ollama)
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::managers::config_manager::LocalModelsConfig;
+use crate::{log_debug, log_info, network_error};
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// A model currently held in the warm pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmModel {
+    pub name: String,
+    pub loaded_at: DateTime<Utc>,
+    pub last_used: DateTime<Utc>,
+    pub use_count: u64,
+}
+
+/// Least-recently-used bookkeeping for loaded models, independent of the backend
+#[derive(Debug, Default)]
+pub struct WarmPool {
+    capacity: usize,
+    /// Most recently used model first
+    models: VecDeque<WarmModel>,
+}
+
+impl WarmPool {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            models: VecDeque::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.models.iter().any(|m| m.name == name)
+    }
+
+    /// Snapshot of the pool, most recently used first
+    pub fn models(&self) -> Vec<WarmModel> {
+        self.models.iter().cloned().collect()
+    }
+
+    /// Mark a model as used, inserting it if needed. Returns the models
+    /// evicted to stay within capacity, least recently used first.
+    pub fn touch(&mut self, name: &str) -> Vec<String> {
+        let now = Utc::now();
+        let existing = self
+            .models
+            .iter()
+            .position(|m| m.name == name)
+            .and_then(|index| self.models.remove(index));
+        let mut model = existing.unwrap_or_else(|| WarmModel {
+            name: name.to_string(),
+            loaded_at: now,
+            last_used: now,
+            use_count: 0,
+        });
+        model.last_used = now;
+        model.use_count += 1;
+        self.models.push_front(model);
+
+        self.evict_to(self.capacity)
+    }
+
+    /// Change the capacity, returning the models evicted as a result
+    pub fn resize(&mut self, capacity: usize) -> Vec<String> {
+        self.capacity = capacity;
+        self.evict_to(capacity)
+    }
+
+    /// Remove a model from the pool, returning whether it was present
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.models.len();
+        self.models.retain(|m| m.name != name);
+        self.models.len() != before
+    }
+
+    fn evict_to(&mut self, capacity: usize) -> Vec<String> {
+        let mut evicted = Vec::new();
+        while self.models.len() > capacity {
+            if let Some(model) = self.models.pop_back() {
+                evicted.push(model.name);
+            }
+        }
+        evicted
+    }
+}
+
+/// Manages which local models stay loaded so switching between them
+/// doesn't pay the full load time on every request. Clones share the pool.
+#[derive(Debug, Clone)]
+pub struct LocalModelManager {
+    ollama_url: String,
+    pool: Arc<RwLock<WarmPool>>,
+    http_client: Client,
+}
+
+impl LocalModelManager {
+    pub fn new(config: &LocalModelsConfig) -> Self {
+        Self {
+            ollama_url: config.ollama_url.trim_end_matches('/').to_string(),
+            pool: Arc::new(RwLock::new(WarmPool::new(config.warm_pool_size))),
+            // Loading a large model can take minutes
+            http_client: Client::builder()
+                .timeout(Duration::from_secs(300))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Apply a changed configuration, unloading models that no longer fit
+    pub async fn configure(&mut self, config: &LocalModelsConfig) -> MindLinkResult<()> {
+        self.ollama_url = config.ollama_url.trim_end_matches('/').to_string();
+        let evicted = self.pool.write().await.resize(config.warm_pool_size);
+        self.unload_all(&evicted).await
+    }
+
+    /// Ensure a model is loaded before it is used, evicting the least
+    /// recently used models when the pool is full
+    pub async fn acquire(&self, model: &str) -> MindLinkResult<()> {
+        let (already_warm, capacity) = {
+            let pool = self.pool.read().await;
+            (pool.contains(model), pool.capacity())
+        };
+
+        if capacity == 0 {
+            return Ok(());
+        }
+
+        if !already_warm {
+            log_info!("LocalModelManager", format!("Warming model {}", model));
+            self.set_keep_alive(model, -1).await?;
+        }
+
+        let evicted = self.pool.write().await.touch(model);
+        self.unload_all(&evicted).await
+    }
+
+    /// Explicitly unload a model and drop it from the pool
+    pub async fn release(&self, model: &str) -> MindLinkResult<()> {
+        self.pool.write().await.remove(model);
+        self.set_keep_alive(model, 0).await
+    }
+
+    /// Whether `url` is served by the configured Ollama, e.g. a fallback
+    /// provider at `http://127.0.0.1:11434/v1`
+    pub fn serves(&self, url: &str) -> bool {
+        match (url::Url::parse(url), url::Url::parse(&self.ollama_url)) {
+            (Ok(url), Ok(ollama)) => url.origin() == ollama.origin(),
+            _ => false,
+        }
+    }
+
+    /// Models currently held warm, most recently used first
+    pub async fn warm_models(&self) -> Vec<WarmModel> {
+        self.pool.read().await.models()
+    }
+
+    pub async fn capacity(&self) -> usize {
+        self.pool.read().await.capacity()
+    }
+
+    async fn unload_all(&self, models: &[String]) -> MindLinkResult<()> {
+        for model in models {
+            log_info!("LocalModelManager", format!("Evicting model {}", model));
+            self.set_keep_alive(model, 0).await?;
+        }
+        Ok(())
+    }
+
+    /// Load (`-1` keeps it loaded indefinitely) or unload (`0`) a model.
+    /// Ollama loads a model without generating when the prompt is omitted.
+    async fn set_keep_alive(&self, model: &str, keep_alive: i64) -> MindLinkResult<()> {
+        let url = format!("{}/api/generate", self.ollama_url);
+        let response = self
+            .http_client
+            .post(&url)
+            .json(&serde_json::json!({ "model": model, "keep_alive": keep_alive }))
+            .send()
+            .await
+            .map_err(|e| network_error!("Failed to reach Ollama", &url, e))?;
+
+        if !response.status().is_success() {
+            let action = if keep_alive == 0 {
+                "unloading"
+            } else {
+                "loading"
+            };
+            return Err(MindLinkError::Network {
+                message: format!(
+                    "Ollama returned {} while {} model {}",
+                    response.status(),
+                    action,
+                    model
+                ),
+                url: Some(url),
+                source: None,
+            });
+        }
+
+        log_debug!(
+            "LocalModelManager",
+            format!("Set keep_alive={} for model {}", keep_alive, model)
+        );
+        Ok(())
+    }
+}
@@ -16,6 +16,8 @@
 //! - **Binary**: External binary management and execution
 //! - **Bifrost**: Dashboard and monitoring interface
 //! - **Dashboard**: Web interface for system management
+//! - **Local Models**: Warm pool of loaded models on local backends
+//! - **Plugins**: Discovery and validation of provider plugin manifests
 //!
 //! ## Usage Pattern
 //!
@@ -48,5 +50,7 @@ pub mod bifrost_manager;
 pub mod binary_manager;
 pub mod config_manager;
 pub mod dashboard_manager;
+pub mod local_model_manager;
+pub mod plugin_manager;
 pub mod server_manager;
 pub mod tunnel_manager;
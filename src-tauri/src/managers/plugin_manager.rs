@@ -0,0 +1,285 @@
+// Plugin Manager - Discovers and validates provider plugin manifests on disk
+//
+// Each plugin lives in its own directory under the plugins directory and
+// describes itself with a `manifest.json`. Manifests are validated before they
+// are exposed to the frontend; plugins that fail validation are reported with
+// the reason instead of being silently dropped.
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::{log_info, log_warn};
+use chrono::{DateTime, Utc};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Component, Path, PathBuf};
+use tokio::fs;
+
+/// Name of the manifest file inside each plugin directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Plugin manifest structure for external plugins
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub main: String,
+    pub dependencies: Option<Vec<String>>,
+    /// Compatible MindLink versions as a semver requirement (e.g. `^1.0`)
+    #[serde(alias = "mindlinkVersion")]
+    pub mindlink_version: Option<String>,
+}
+
+/// A plugin directory that could not be loaded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginLoadError {
+    pub directory: String,
+    pub plugin_id: Option<String>,
+    pub message: String,
+}
+
+/// Result of scanning the plugins directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginRegistry {
+    pub plugins_directory: String,
+    pub manifests: Vec<PluginManifest>,
+    pub errors: Vec<PluginLoadError>,
+    pub scanned_at: DateTime<Utc>,
+}
+
+/// Discovers plugins and caches the validated registry until refreshed
+#[derive(Debug)]
+pub struct PluginManager {
+    plugins_dir: PathBuf,
+    registry: Option<PluginRegistry>,
+}
+
+impl PluginManager {
+    pub fn new(plugins_dir: PathBuf) -> Self {
+        Self {
+            plugins_dir,
+            registry: None,
+        }
+    }
+
+    /// Default location: `<local data dir>/mindlink/plugins`
+    pub fn default_plugins_dir() -> MindLinkResult<PathBuf> {
+        dirs::data_local_dir()
+            .map(|dir| dir.join("mindlink").join("plugins"))
+            .ok_or_else(|| MindLinkError::SystemResource {
+                message: "Cannot determine app data directory".to_string(),
+                resource_type: "data directory".to_string(),
+                source: None,
+            })
+    }
+
+    pub fn plugins_dir(&self) -> &Path {
+        &self.plugins_dir
+    }
+
+    /// Cached registry, scanning the plugins directory on first use
+    pub async fn registry(&mut self) -> MindLinkResult<PluginRegistry> {
+        match &self.registry {
+            Some(registry) => Ok(registry.clone()),
+            None => self.refresh().await,
+        }
+    }
+
+    /// Rescan the plugins directory and replace the cached registry
+    pub async fn refresh(&mut self) -> MindLinkResult<PluginRegistry> {
+        let registry = scan_plugins_dir(&self.plugins_dir).await?;
+
+        log_info!(
+            "PluginManager",
+            format!(
+                "Discovered {} plugin(s), {} failed to load",
+                registry.manifests.len(),
+                registry.errors.len()
+            )
+        );
+
+        self.registry = Some(registry.clone());
+        Ok(registry)
+    }
+}
+
+/// Scan every subdirectory of `plugins_dir` for a valid manifest.
+/// A missing plugins directory is an empty registry, not an error.
+pub async fn scan_plugins_dir(plugins_dir: &Path) -> MindLinkResult<PluginRegistry> {
+    let mut manifests: Vec<PluginManifest> = Vec::new();
+    let mut errors = Vec::new();
+
+    let mut directories = Vec::new();
+    if fs::try_exists(plugins_dir).await.unwrap_or(false) {
+        let mut entries =
+            fs::read_dir(plugins_dir)
+                .await
+                .map_err(|e| MindLinkError::FileSystem {
+                    message: "Failed to read plugins directory".to_string(),
+                    path: Some(plugins_dir.to_string_lossy().to_string()),
+                    operation: "read directory".to_string(),
+                    source: Some(e.into()),
+                })?;
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if entry.file_type().await.map(|t| t.is_dir()).unwrap_or(false) {
+                directories.push(entry.path());
+            }
+        }
+    }
+    // Stable ordering regardless of the filesystem
+    directories.sort();
+
+    for directory in directories {
+        let display = directory.to_string_lossy().to_string();
+        let load_error = |plugin_id: Option<String>, message: String| PluginLoadError {
+            directory: display.clone(),
+            plugin_id,
+            message,
+        };
+
+        let content = match fs::read_to_string(directory.join(MANIFEST_FILE)).await {
+            Ok(content) => content,
+            Err(e) => {
+                errors.push(load_error(
+                    None,
+                    format!("Cannot read {}: {}", MANIFEST_FILE, e),
+                ));
+                continue;
+            },
+        };
+
+        let value: Value = match serde_json::from_str(&content) {
+            Ok(value) => value,
+            Err(e) => {
+                errors.push(load_error(None, format!("Invalid JSON: {}", e)));
+                continue;
+            },
+        };
+
+        let plugin_id = value
+            .get("id")
+            .and_then(|id| id.as_str())
+            .map(str::to_string);
+        match validate_manifest(&value, &directory, env!("CARGO_PKG_VERSION")) {
+            Ok(manifest) if manifests.iter().any(|m| m.id == manifest.id) => {
+                errors.push(load_error(
+                    plugin_id,
+                    format!("Duplicate plugin id '{}'", manifest.id),
+                ));
+            },
+            Ok(manifest) => manifests.push(manifest),
+            Err(message) => errors.push(load_error(plugin_id, message)),
+        }
+    }
+
+    for error in &errors {
+        log_warn!(
+            "PluginManager",
+            format!("Skipping plugin in {}: {}", error.directory, error.message)
+        );
+    }
+
+    Ok(PluginRegistry {
+        plugins_directory: plugins_dir.to_string_lossy().to_string(),
+        manifests,
+        errors,
+        scanned_at: Utc::now(),
+    })
+}
+
+/// Validate a manifest against the manifest schema and the running MindLink version.
+/// Returns a human-readable reason on failure.
+pub fn validate_manifest(
+    value: &Value,
+    plugin_dir: &Path,
+    mindlink_version: &str,
+) -> Result<PluginManifest, String> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| "Manifest must be a JSON object".to_string())?;
+
+    for field in ["id", "name", "version", "main"] {
+        match object.get(field) {
+            Some(Value::String(s)) if !s.trim().is_empty() => {},
+            Some(Value::String(_)) => return Err(format!("Field '{}' must not be empty", field)),
+            Some(_) => return Err(format!("Field '{}' must be a string", field)),
+            None => return Err(format!("Missing required field '{}'", field)),
+        }
+    }
+
+    for field in [
+        "description",
+        "author",
+        "mindlink_version",
+        "mindlinkVersion",
+    ] {
+        if let Some(v) = object.get(field) {
+            if !v.is_string() && !v.is_null() {
+                return Err(format!("Field '{}' must be a string", field));
+            }
+        }
+    }
+
+    if let Some(deps) = object.get("dependencies") {
+        let valid = deps.is_null()
+            || deps
+                .as_array()
+                .is_some_and(|items| items.iter().all(Value::is_string));
+        if !valid {
+            return Err("Field 'dependencies' must be an array of strings".to_string());
+        }
+    }
+
+    let manifest: PluginManifest =
+        serde_json::from_value(value.clone()).map_err(|e| format!("Invalid manifest: {}", e))?;
+
+    let id_is_valid = manifest
+        .id
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if !id_is_valid {
+        return Err(format!(
+            "Plugin id '{}' may only contain lowercase letters, digits, '-' and '_'",
+            manifest.id
+        ));
+    }
+
+    Version::parse(&manifest.version)
+        .map_err(|e| format!("Version '{}' is not valid semver: {}", manifest.version, e))?;
+
+    // The entry point must stay inside the plugin directory
+    let main_path = Path::new(&manifest.main);
+    let escapes = main_path
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir));
+    if escapes {
+        return Err(format!(
+            "Entry point '{}' must be a relative path inside the plugin directory",
+            manifest.main
+        ));
+    }
+    if !plugin_dir.join(main_path).is_file() {
+        return Err(format!("Entry point '{}' does not exist", manifest.main));
+    }
+
+    if let Some(requirement) = &manifest.mindlink_version {
+        let requirement = VersionReq::parse(requirement).map_err(|e| {
+            format!(
+                "mindlink_version '{}' is not a valid requirement: {}",
+                requirement, e
+            )
+        })?;
+        let current = Version::parse(mindlink_version)
+            .map_err(|e| format!("Invalid MindLink version '{}': {}", mindlink_version, e))?;
+        if !requirement.matches(&current) {
+            return Err(format!(
+                "Requires MindLink {}, but this is {}",
+                requirement, current
+            ));
+        }
+    }
+
+    Ok(manifest)
+}
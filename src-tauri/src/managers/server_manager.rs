@@ -13,6 +13,8 @@
 //! - **CORS Support**: Cross-origin request handling for web applications
 //! - **Request Validation**: Input validation and sanitization
 //! - **Structured Logging**: Detailed request/response logging for debugging
+//! - **Local Socket**: Optionally also served on a Unix socket, or a named pipe on Windows
+//! - **Multiple Listeners**: Further bind addresses served with the same state
 //!
 //! ## Architecture
 //!
@@ -25,10 +27,20 @@
 //!
 //! ## Endpoints
 //!
-//! - `GET /v1/models` - List available models
+//! - `GET /v1/models` - Models of the ChatGPT account plus any served by Bifrost
 //! - `POST /v1/chat/completions` - Chat completions (streaming and non-streaming)
-//! - `GET /health` - Server health check
-//! - `GET /dashboard` - Management dashboard (served by BifrostManager)
+//! - `GET /v1/ws` - Chat completions streamed over a WebSocket
+//! - `POST /v1/files`, `GET /v1/files/{id}[/content]` - Batch input and result files
+//! - `POST /v1/batches`, `GET /v1/batches[/{id}]`, `POST /v1/batches/{id}/cancel` - Batch API
+//! - `POST /v1/jobs`, `GET /v1/jobs/{id}[/events]`, `POST /v1/jobs/{id}/cancel` - Background chat completions
+//! - `GET /v1/ping`, `POST /v1/ping/report` - Connection quality heartbeats of companion apps
+//! - `POST /v1/moderations` - Moderation of text by the configured provider and rules
+//! - `POST /api/chat`, `POST /api/generate`, `GET /api/tags` - Ollama-compatible API
+//! - `GET /health` - Health levels (ok/degraded/down) per component and overall
+//! - `GET /playground` - Chat playground on this API; `GET /dashboard` redirects to it
+//! - `GET /metrics` - Prometheus metrics (requests, latency, upstream errors, active streams,
+//!   tunnel traffic)
+//! - `GET /openapi.json`, `GET /docs` - OpenAPI 3.1 document of these endpoints and Swagger UI for it
 //!
 //! ## Performance
 //!
@@ -36,37 +48,212 @@
 //! - **Connection Pooling**: Reused HTTP connections to upstream services
 //! - **Resource Limits**: Configurable request size and timeout limits
 //! - **Graceful Shutdown**: Clean connection termination on service stop
+use crate::accounts::{AccountLease, AccountPool, AccountStatus, PRIMARY_ACCOUNT, SESSION_HEADERS};
+use crate::analytics::{
+    AnalyticsRecorder, AnalyticsStats, AnalyticsStatsSnapshot, AnalyticsStore, QuotaAttribution,
+    RequestRecord,
+};
+use crate::anthropic;
+use crate::api_error::{ApiError, UpstreamStatus};
+use crate::auth_probe;
+use crate::authorized_apps::{self, AuthorizedApp};
+use crate::batches::{self, BatchStore, CreateBatchRequest};
+use crate::budgets::{AppBudget, BudgetLedger};
+use crate::canary::{Canary, CanaryArm, CanarySpec, CanaryState, CanaryStatus, Verdict};
+use crate::capture::{
+    replay_headers, Capture, CaptureStore, CaptureSummary, ReplayResult, Sanitizer, REPLAY_HEADER,
+};
+use crate::cloudflare_access::{AccessVerifier, SharedAccessVerifier};
+use crate::conversations::{self, ConversationStore, PendingTurn, UpstreamConversation};
 use crate::error::{MindLinkError, MindLinkResult};
+use crate::failover::{self, FallbackChain, FallbackResponse};
+use crate::health::{self, ComponentHealth, HealthLevel};
+use crate::jobs::{self, JobStore};
+use crate::language::{self, DetectedLanguage};
+use crate::local_socket::LocalSocket;
+use crate::logging::{current_correlation_id, with_correlation_id};
 use crate::managers::auth_manager::AuthManager;
-use crate::{log_debug, log_error, log_info, network_error};
+use crate::managers::config_manager::{
+    AccessControlConfig, AccountsConfig, AnalyticsConfig, BackpressureConfig, BatchConfig,
+    BindAddress, CaptureConfig, ConversationConfig, FailoverConfig, HttpConfig, JobConfig,
+    LanguageDetectionConfig, LimitsConfig, ModelAliasConfig, ModerationConfig,
+    PostProcessingConfig, PromptConfig, RedactionConfig, RequestTransformConfig, ServerConfig,
+    ShadowConfig, StreamContinuationConfig, TlsConfig, ToolEmulationConfig, TunnelLimitsConfig,
+};
+use crate::managers::local_model_manager::LocalModelManager;
+use crate::middleware::access_control::{
+    enforce_access_policy, resolve_client_ip, AccessPolicy, ClientIpResolver, TrustedProxies,
+};
+use crate::middleware::analytics::{
+    fingerprint, key_fingerprint, record_analytics, FailureReason, TokenUsage,
+};
+use crate::middleware::azure::{deployment_as_model, DEPLOYMENT_COMPLETIONS_PATH};
+use crate::middleware::backpressure::{apply_backpressure, Backpressure, OverloadEvent};
+use crate::middleware::budget::{enforce_budget, TokenBudgets};
+use crate::middleware::capture::{capture_exchange, Capturer};
+use crate::middleware::cloudflare_access::require_access_jwt;
+use crate::middleware::metrics::{
+    track_metrics, track_tunnel_traffic, Metrics, TunnelTracking, TunnelTraffic,
+};
+use crate::middleware::moderation::screen_prompts;
+use crate::middleware::post_processing::post_process;
+use crate::middleware::request_id::{assign_request_id, REQUEST_ID_HEADER};
+use crate::middleware::request_log::log_requests;
+use crate::middleware::request_transforms::transform_requests;
+use crate::middleware::shadow::{mirror_traffic, Shadow};
+use crate::middleware::tunnel_auth::{require_tunnel_token, TunnelAuth, TunnelTokenPolicy};
+use crate::middleware::tunnel_limits::{limit_tunnel_traffic, TunnelLimiter};
+use crate::model_catalog::ModelCatalog;
+use crate::moderation::{self, ModerationResponse, Moderator};
+use crate::ollama::{
+    self, DoneStats, OllamaChatRequest, OllamaEndpoint, OllamaGenerateRequest, StreamEvent,
+};
+use crate::openapi;
+use crate::ping::{ConnectionReport, PingTracker, QueueDepth};
+use crate::playground;
+use crate::post_processing::PostProcessor;
+use crate::prompt_templates;
+use crate::proxy;
+use crate::reasoning::{self, ReasoningStream};
+use crate::redaction::Redactor;
+use crate::request_log::{self, with_timer, Phase, RequestLog, RequestSummary, StreamStats};
+use crate::request_transforms::RequestTransformer;
+use crate::shadow::{ShadowReport, ShadowStats};
+use crate::stream_continuation::{ContinuationStitcher, CONTINUE_PROMPT};
+use crate::token_store;
+use crate::tool_emulation::{flatten_tool_messages, EmulatedOutput, ToolEmulation};
+use crate::tunnel_limits::{EgressLedger, TunnelLimitWarning};
+use crate::tunnel_tokens::TunnelTokens;
+use crate::websocket::{self, CompletionRoute};
+use crate::{log_debug, log_error, log_info, log_warn, network_error};
 
 use axum::{
     body::Body,
-    extract::State,
-    http::{Request, StatusCode},
-    response::{Html, IntoResponse, Json, Response},
-    routing::{get, post},
-    Router,
+    extract::{ws::WebSocketUpgrade, DefaultBodyLimit, Multipart, Path, Query, State},
+    http::{header, Extensions, HeaderMap, HeaderValue, Request, StatusCode, Version},
+    response::{Html, IntoResponse, Json, Redirect, Response},
+    routing::{get, post, MethodRouter},
+    Extension, Router,
 };
-use tower_http::services::ServeDir;
+use axum_server::tls_rustls::RustlsConfig;
+use futures_util::future::join_all;
 use futures_util::stream::StreamExt;
+use futures_util::FutureExt;
+use hyper_util::rt::{TokioExecutor, TokioTimer};
+use hyper_util::server::conn::auto::Builder;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::net::TcpListener;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, oneshot, watch, RwLock};
 use tokio_stream;
 use tower::ServiceBuilder;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::{Any, CorsLayer};
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::services::ServeDir;
 use uuid::Uuid;
 
+/// How long `stop` waits for requests in flight, e.g. on a restart
+const STOP_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A replayed completion may take as long as the original
+const REPLAY_TIMEOUT: Duration = Duration::from_secs(300);
+
 // ===== OpenAI API Request/Response Types =====
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
+    /// `system`, `developer`, `user`, `assistant`, `tool` or the legacy `function`
     pub role: String,
+    /// Text of the message. Requests may send a string, a list of content
+    /// parts or, for assistant messages that only carry tool calls, `null`.
+    #[serde(default, deserialize_with = "deserialize_content")]
     pub content: String,
+    /// Participant name, or the function name of a legacy `function` message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    /// Reasoning summary of an o-series reply
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_content: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub call_type: String,
+    pub function: FunctionCall,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionCall {
+    pub name: String,
+    /// JSON-encoded arguments, as in the OpenAI API
+    pub arguments: String,
+}
+
+/// Message content as sent by clients
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text { text: String },
+    Refusal { refusal: String },
+    ImageUrl { image_url: serde_json::Value },
+    InputAudio { input_audio: serde_json::Value },
+    File { file: serde_json::Value },
+}
+
+impl MessageContent {
+    /// The text of the message, its text parts joined by newlines. The
+    /// ChatGPT backend only takes text, so other parts are an error.
+    pub fn into_text(self) -> Result<String, String> {
+        let parts = match self {
+            MessageContent::Text(text) => return Ok(text),
+            MessageContent::Parts(parts) => parts,
+        };
+
+        let mut texts = Vec::with_capacity(parts.len());
+        for part in parts {
+            match part {
+                ContentPart::Text { text } => texts.push(text),
+                ContentPart::Refusal { refusal } => texts.push(refusal),
+                ContentPart::ImageUrl { .. } => return Err(unsupported_part("image_url")),
+                ContentPart::InputAudio { .. } => return Err(unsupported_part("input_audio")),
+                ContentPart::File { .. } => return Err(unsupported_part("file")),
+            }
+        }
+        Ok(texts.join("\n"))
+    }
+}
+
+fn unsupported_part(kind: &str) -> String {
+    format!("{} content parts are not supported, only text", kind)
+}
+
+fn deserialize_content<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    match Option::<MessageContent>::deserialize(deserializer)? {
+        Some(content) => content.into_text().map_err(serde::de::Error::custom),
+        None => Ok(String::new()),
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -154,6 +341,9 @@ pub struct ChatGptRequest {
     pub action: String,
     pub messages: Vec<ChatGptMessage>,
     pub parent_message_id: String,
+    /// Set when resuming an existing upstream conversation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_id: Option<String>,
     pub model: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
@@ -167,6 +357,10 @@ pub struct ChatGptRequest {
     pub frequency_penalty: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub presence_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_completion_tokens: Option<u32>,
 }
 
 // ===== Application State =====
@@ -174,17 +368,153 @@ pub struct ChatGptRequest {
 #[derive(Clone)]
 pub struct AppState {
     auth_manager: Arc<RwLock<AuthManager>>,
+    /// Accounts upstream requests are spread across, the primary one included
+    accounts: Arc<AccountPool>,
+    /// Claude account serving `claude-*` models, `None` when they are not
+    /// served
+    anthropic_auth: Option<Arc<RwLock<AuthManager>>>,
     http_client: Client,
+    tool_emulation: Arc<ToolEmulationConfig>,
+    language_detection: Arc<LanguageDetectionConfig>,
+    model_aliases: Arc<RwLock<ModelAliasConfig>>,
+    canary: Arc<RwLock<Option<Canary>>>,
+    authorized_apps: Arc<RwLock<Vec<AuthorizedApp>>>,
+    prompts: Arc<RwLock<PromptConfig>>,
+    stream_continuation: Arc<StreamContinuationConfig>,
+    conversations: Arc<ConversationStore>,
+    metrics: Arc<Metrics>,
+    ping: Arc<PingTracker>,
+    models: Arc<ModelCatalog>,
+    /// `None` when the batch API is disabled or its storage is unavailable
+    batches: Option<Arc<BatchStore>>,
+    batch_config: Arc<BatchConfig>,
+    /// `None` when the job API is disabled
+    jobs: Option<Arc<JobStore>>,
+    /// `None` when prompts are sent as they are
+    redactor: Option<Arc<Redactor>>,
+    /// `None` when moderation has neither a provider nor rules
+    moderator: Option<Arc<Moderator>>,
+    failover: Arc<FallbackChain>,
+    /// Warm pool of the local models fallback providers may be served by
+    local_models: Option<Arc<RwLock<LocalModelManager>>>,
+    /// Backpressure limit, `None` when backpressure is disabled
+    max_in_flight: Option<usize>,
+    upstream_timeout: Duration,
+    stream_idle_timeout: Duration,
 }
 
 // ===== Server Manager =====
 
+/// Run a read-only query against the analytics database on the blocking pool.
+/// The write-behind flusher keeps its own connection; WAL lets both coexist.
+async fn query_analytics<T, F>(query: F) -> MindLinkResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&AnalyticsStore) -> MindLinkResult<T> + Send + 'static,
+{
+    let path = AnalyticsStore::default_path()?;
+    tokio::task::spawn_blocking(move || query(&AnalyticsStore::open(&path)?))
+        .await
+        .map_err(|e| MindLinkError::Internal {
+            message: "Analytics query failed".to_string(),
+            component: Some("ServerManager".to_string()),
+            source: Some(e.into()),
+        })?
+}
+
+/// Run `query` against the capture store on the blocking pool
+async fn query_captures<T, F>(max_captures: usize, query: F) -> MindLinkResult<T>
+where
+    T: Send + 'static,
+    F: FnOnce(&CaptureStore) -> MindLinkResult<T> + Send + 'static,
+{
+    let dir = CaptureStore::default_dir()?;
+    tokio::task::spawn_blocking(move || query(&CaptureStore::open(&dir, max_captures)?))
+        .await
+        .map_err(|e| MindLinkError::Internal {
+            message: "Capture query failed".to_string(),
+            component: Some("ServerManager".to_string()),
+            source: Some(e.into()),
+        })?
+}
+
 #[derive(Debug)]
 pub struct ServerManager {
     port: u16,
     host: String,
+    tls: TlsConfig,
+    /// Served besides the port when set
+    socket_path: Option<String>,
+    /// Served with the same state as the primary address
+    additional_binds: Vec<BindAddress>,
+    access_policy: Arc<AccessPolicy>,
+    trusted_proxies: Arc<TrustedProxies>,
+    /// Shared with the running server so protecting the tunnel applies
+    /// without a restart
+    cloudflare_access: SharedAccessVerifier,
+    tool_emulation: Arc<ToolEmulationConfig>,
+    language_detection: Arc<LanguageDetectionConfig>,
+    /// Shared with the running server so alias edits apply without a restart
+    model_aliases: Arc<RwLock<ModelAliasConfig>>,
+    /// Routing change on trial, shared with the running server
+    canary: Arc<RwLock<Option<Canary>>>,
+    /// Shared with the running server so app edits apply without a restart
+    authorized_apps: Arc<RwLock<Vec<AuthorizedApp>>>,
+    /// Token consumption of the apps with a budget
+    budget_ledger: Arc<BudgetLedger>,
+    /// Shared with the running server so prompt edits apply without a restart
+    prompts: Arc<RwLock<PromptConfig>>,
+    /// Shared with the running server so rule edits apply without a restart
+    post_processor: Arc<RwLock<PostProcessor>>,
+    /// Shared with the running server so rule edits apply without a restart
+    request_transformer: Arc<RwLock<RequestTransformer>>,
+    stream_continuation: Arc<StreamContinuationConfig>,
+    /// Kept across restarts so clients can keep their conversations
+    conversations: Arc<ConversationStore>,
+    metrics: Arc<Metrics>,
+    /// Public URL of the tunnel, shared with the tunnel manager so requests
+    /// through it are counted per tunnel
+    tunnel_url: Arc<RwLock<Option<String>>>,
+    cloudflared_url: Arc<RwLock<Option<String>>>,
+    /// Shared with the running server so token edits apply without a restart
+    tunnel_token_policy: Arc<RwLock<TunnelTokenPolicy>>,
+    /// Shared with the app, which issues and rotates them
+    tunnel_tokens: Arc<RwLock<TunnelTokens>>,
+    tunnel_limits_config: TunnelLimitsConfig,
+    /// Bytes sent through the tunnel this month
+    tunnel_egress: Arc<EgressLedger>,
+    /// Outlives restarts so the app stays subscribed
+    tunnel_limit_events: broadcast::Sender<TunnelLimitWarning>,
+    /// Kept across restarts so the ping sequence keeps growing
+    ping: Arc<PingTracker>,
+    models: Arc<ModelCatalog>,
+    /// Kept across restarts with the load of each account
+    accounts: Arc<AccountPool>,
+    anthropic_auth: Option<Arc<RwLock<AuthManager>>>,
+    batch_config: Arc<BatchConfig>,
+    job_config: JobConfig,
+    redactor: Option<Arc<Redactor>>,
+    backpressure_config: BackpressureConfig,
+    capture_config: CaptureConfig,
+    limits_config: LimitsConfig,
+    http_config: HttpConfig,
+    shadow_config: ShadowConfig,
+    moderation_config: ModerationConfig,
+    /// Kept across restarts with the comparisons made so far
+    shadow_stats: Arc<ShadowStats>,
+    /// Kept across restarts for the request inspector
+    request_log: Arc<RequestLog>,
+    /// Outlives restarts so the app stays subscribed
+    overload_events: broadcast::Sender<OverloadEvent>,
+    failover: Arc<FallbackChain>,
+    /// Shared with the app, which configures it and shows its pool
+    local_models: Option<Arc<RwLock<LocalModelManager>>>,
+    analytics_config: AnalyticsConfig,
+    analytics_stats: Arc<AnalyticsStats>,
     is_running: Arc<RwLock<bool>>,
     server_handle: Arc<RwLock<Option<tokio::task::JoinHandle<()>>>>,
+    /// Tells the running server to stop accepting connections and drain
+    shutdown_signal: Option<oneshot::Sender<()>>,
 }
 
 impl ServerManager {
@@ -195,8 +525,66 @@ impl ServerManager {
         Self {
             port: 3001,
             host: "127.0.0.1".to_string(),
+            tls: TlsConfig::default(),
+            socket_path: None,
+            additional_binds: Vec::new(),
+            access_policy: Arc::new(AccessPolicy::default()),
+            trusted_proxies: Arc::new(TrustedProxies::default()),
+            cloudflare_access: Arc::new(RwLock::new(None)),
+            tool_emulation: Arc::new(ToolEmulationConfig::default()),
+            language_detection: Arc::new(LanguageDetectionConfig::default()),
+            model_aliases: Arc::new(RwLock::new(ModelAliasConfig::default())),
+            canary: Arc::new(RwLock::new(None)),
+            authorized_apps: Arc::new(RwLock::new(Vec::new())),
+            budget_ledger: Arc::new(match BudgetLedger::default_path() {
+                Ok(path) => BudgetLedger::open(&path),
+                Err(e) => {
+                    log_error!("ServerManager", e);
+                    BudgetLedger::default()
+                },
+            }),
+            prompts: Arc::new(RwLock::new(PromptConfig::default())),
+            post_processor: Arc::new(RwLock::new(PostProcessor::default())),
+            request_transformer: Arc::new(RwLock::new(RequestTransformer::default())),
+            stream_continuation: Arc::new(StreamContinuationConfig::default()),
+            conversations: Arc::new(ConversationStore::new(ConversationConfig::default())),
+            metrics: Arc::new(Metrics::new()),
+            tunnel_url: Arc::new(RwLock::new(None)),
+            cloudflared_url: Arc::new(RwLock::new(None)),
+            tunnel_token_policy: Arc::new(RwLock::new(TunnelTokenPolicy::default())),
+            tunnel_tokens: Arc::new(RwLock::new(TunnelTokens::default())),
+            tunnel_limits_config: TunnelLimitsConfig::default(),
+            tunnel_egress: Arc::new(match EgressLedger::default_path() {
+                Ok(path) => EgressLedger::open(&path),
+                Err(e) => {
+                    log_error!("ServerManager", e);
+                    EgressLedger::default()
+                },
+            }),
+            tunnel_limit_events: broadcast::channel(16).0,
+            ping: Arc::new(PingTracker::default()),
+            models: Arc::new(ModelCatalog::default()),
+            accounts: Arc::new(AccountPool::new(AccountsConfig::default())),
+            anthropic_auth: None,
+            batch_config: Arc::new(BatchConfig::default()),
+            job_config: JobConfig::default(),
+            redactor: None,
+            backpressure_config: BackpressureConfig::default(),
+            capture_config: CaptureConfig::default(),
+            limits_config: LimitsConfig::default(),
+            http_config: HttpConfig::default(),
+            shadow_config: ShadowConfig::default(),
+            moderation_config: ModerationConfig::default(),
+            shadow_stats: Arc::new(ShadowStats::default()),
+            request_log: Arc::new(RequestLog::default()),
+            overload_events: broadcast::channel(16).0,
+            failover: Arc::new(FallbackChain::default()),
+            local_models: None,
+            analytics_config: AnalyticsConfig::default(),
+            analytics_stats: Arc::new(AnalyticsStats::default()),
             is_running: Arc::new(RwLock::new(false)),
             server_handle: Arc::new(RwLock::new(None)),
+            shutdown_signal: None,
         }
     }
 
@@ -219,57 +607,308 @@ impl ServerManager {
             &format!("Starting API server on {}:{}", self.host, self.port)
         );
 
-        // Create HTTP client with proper timeouts
-        let http_client = Client::builder()
-            .timeout(Duration::from_secs(30))
+        // Without its certificate the server does not start at all rather
+        // than serving plain HTTP where HTTPS is expected
+        let rustls_config = if self.tls.enabled {
+            Some(self.load_rustls_config().await?)
+        } else {
+            None
+        };
+
+        // No overall timeout: it would cut long streams off. Requests get the
+        // upstream timeout, streams the idle timeout.
+        let http_client = proxy::apply(Client::builder())
             .connect_timeout(Duration::from_secs(10))
+            .pool_max_idle_per_host(self.http_config.upstream_pool_max_idle)
+            .pool_idle_timeout(Duration::from_secs(
+                self.http_config.upstream_pool_idle_timeout_secs,
+            ))
             .user_agent("MindLink/1.0")
             .build()
             .map_err(|e| network_error!("Failed to create HTTP client", "", e))?;
 
+        self.accounts.set_primary(auth_manager.clone()).await;
+        if let Err(e) = self.accounts.load_saved().await {
+            log_error!("ServerManager", e);
+        }
+
+        let batches = if self.batch_config.enabled {
+            let store = match BatchStore::default_dir() {
+                Ok(dir) => BatchStore::open(&dir).await,
+                Err(e) => Err(e),
+            };
+            match store {
+                Ok(store) => Some(Arc::new(store)),
+                Err(e) => {
+                    log_error!("ServerManager", e);
+                    None
+                },
+            }
+        } else {
+            None
+        };
+
+        let shadow = self.shadow_config.enabled.then(|| {
+            Arc::new(Shadow::new(
+                self.shadow_config.clone(),
+                http_client.clone(),
+                self.limits_config.upstream_timeout(),
+                self.redactor.clone(),
+                self.models.clone(),
+                self.shadow_stats.clone(),
+                self.metrics.clone(),
+            ))
+        });
+
+        let moderator = Moderator::from_config(
+            &self.moderation_config,
+            http_client.clone(),
+            self.limits_config.upstream_timeout(),
+        )?
+        .map(Arc::new);
+        let preflight = moderator
+            .clone()
+            .filter(|_| self.moderation_config.preflight);
+
+        let max_in_flight = self.max_in_flight().await;
+
         let app_state = AppState {
             auth_manager: auth_manager.clone(),
+            accounts: self.accounts.clone(),
+            anthropic_auth: self.anthropic_auth.clone(),
             http_client,
+            tool_emulation: self.tool_emulation.clone(),
+            language_detection: self.language_detection.clone(),
+            model_aliases: self.model_aliases.clone(),
+            canary: self.canary.clone(),
+            authorized_apps: self.authorized_apps.clone(),
+            prompts: self.prompts.clone(),
+            stream_continuation: self.stream_continuation.clone(),
+            conversations: self.conversations.clone(),
+            metrics: self.metrics.clone(),
+            ping: self.ping.clone(),
+            models: self.models.clone(),
+            batches,
+            batch_config: self.batch_config.clone(),
+            jobs: self
+                .job_config
+                .enabled
+                .then(|| Arc::new(JobStore::new(&self.job_config))),
+            redactor: self.redactor.clone(),
+            moderator,
+            failover: self.failover.clone(),
+            local_models: self.local_models.clone(),
+            max_in_flight,
+            upstream_timeout: self.limits_config.upstream_timeout(),
+            stream_idle_timeout: self.limits_config.stream_idle_timeout(),
         };
 
-        // Create the router with middleware
-        let app = create_router(app_state);
+        // Pick up batches that were still running when the server stopped
+        if let Some(store) = &app_state.batches {
+            for id in store.unfinished().await {
+                spawn_batch(app_state.clone(), id);
+            }
+        }
 
-        // Bind to the configured address
-        let bind_address = format!("{}:{}", self.host, self.port);
-        let listener =
-            TcpListener::bind(&bind_address)
-                .await
-                .map_err(|e| MindLinkError::Network {
-                    message: format!("Failed to bind to {}", bind_address),
-                    url: Some(bind_address.clone()),
-                    source: Some(e.into()),
-                })?;
+        // The recorder's flusher stops once the router (and its handle) is dropped
+        let analytics = if self.analytics_config.enabled {
+            match AnalyticsStore::default_path().and_then(|path| {
+                AnalyticsRecorder::spawn(
+                    &path,
+                    &self.analytics_config,
+                    self.analytics_stats.clone(),
+                )
+            }) {
+                Ok(recorder) => Some(recorder),
+                Err(e) => {
+                    log_error!("ServerManager", e);
+                    None
+                },
+            }
+        } else {
+            None
+        };
 
-        log_info!(
-            "ServerManager",
-            &format!("Server bound to {}", bind_address)
+        let backpressure = max_in_flight.map(|max_in_flight| {
+            Arc::new(Backpressure::new(
+                max_in_flight,
+                self.accounts.clone(),
+                self.overload_events.clone(),
+            ))
+        });
+
+        let capture = if self.capture_config.enabled {
+            match CaptureStore::default_dir()
+                .and_then(|dir| CaptureStore::open(&dir, self.capture_config.max_captures))
+            {
+                Ok(store) => Some(Arc::new(Capturer::new(
+                    store,
+                    Sanitizer::new(self.redactor.clone()),
+                ))),
+                Err(e) => {
+                    log_error!("ServerManager", e);
+                    None
+                },
+            }
+        } else {
+            None
+        };
+
+        let budgets = Arc::new(TokenBudgets::new(
+            self.budget_ledger.clone(),
+            self.authorized_apps.clone(),
+        ));
+
+        let tunnel_limiter = self.tunnel_limits_config.is_limited().then(|| {
+            Arc::new(TunnelLimiter::new(
+                &self.tunnel_limits_config,
+                self.tunnel_egress.clone(),
+                self.tunnel_url.clone(),
+                self.tunnel_limit_events.clone(),
+            ))
+        });
+
+        // Create the router with middleware
+        let app = create_router(
+            app_state,
+            self.access_policy.clone(),
+            ClientIpResolver {
+                proxies: self.trusted_proxies.clone(),
+                cloudflared_url: self.cloudflared_url.clone(),
+            },
+            self.cloudflare_access.clone(),
+            TunnelTracking {
+                metrics: self.metrics.clone(),
+                tunnel_url: self.tunnel_url.clone(),
+            },
+            TunnelAuth {
+                policy: self.tunnel_token_policy.clone(),
+                tunnel_url: self.tunnel_url.clone(),
+                tunnel_tokens: self.tunnel_tokens.clone(),
+                authorized_apps: self.authorized_apps.clone(),
+            },
+            tunnel_limiter,
+            analytics,
+            backpressure,
+            capture,
+            budgets,
+            shadow,
+            preflight,
+            self.post_processor.clone(),
+            self.request_transformer.clone(),
+            self.request_log.clone(),
+            &self.limits_config,
+            &self.http_config,
         );
 
-        // Start the server in a background task
-        let server_task = tokio::spawn(async move {
-            log_info!("ServerManager", "Axum server starting...");
-            if let Err(e) = axum::serve(listener, app).await {
-                log_error!(
-                    "ServerManager",
-                    MindLinkError::Network {
-                        message: "Server error occurred".to_string(),
-                        url: None,
+        // Bind to the configured addresses, the primary one first
+        let mut std_listeners = Vec::new();
+        for bind in self.server_config().bind_addresses() {
+            let bind_address = bind.to_string();
+            let listener =
+                TcpListener::bind(&bind_address)
+                    .await
+                    .map_err(|e| MindLinkError::Network {
+                        message: format!("Failed to bind to {}", bind_address),
+                        url: Some(bind_address.clone()),
                         source: Some(e.into()),
-                    }
-                );
+                    })?;
+
+            log_info!(
+                "ServerManager",
+                &format!("Server bound to {}", bind_address)
+            );
+
+            std_listeners.push(listener.into_std().map_err(|e| MindLinkError::Network {
+                message: "Failed to prepare listener".to_string(),
+                url: Some(bind_address.clone()),
+                source: Some(e.into()),
+            })?);
+        }
+
+        let local_socket = match &self.socket_path {
+            Some(path) => Some(LocalSocket::bind(path).await?),
+            None => None,
+        };
+
+        // Every listener stops accepting on the same signal
+        let (shutdown_signal, shutdown) = oneshot::channel::<()>();
+        let (stop_socket, mut socket_stopped) = watch::channel(());
+        let handle = axum_server::Handle::new();
+        let drain = handle.clone();
+        tokio::spawn(async move {
+            if shutdown.await.is_ok() {
+                drain.graceful_shutdown(None);
+                let _ = stop_socket.send(());
             }
         });
 
+        let mut servers = Vec::new();
+        if let Some(socket) = local_socket {
+            let mut builder = Builder::new(TokioExecutor::new());
+            tune_connections(&mut builder, &self.http_config);
+            let stopped = async move {
+                let _ = socket_stopped.changed().await;
+            };
+            servers.push(socket.serve(app.clone(), builder, stopped).boxed());
+        }
+
+        let service = app.into_make_service_with_connect_info::<SocketAddr>();
+        for std_listener in std_listeners {
+            let handle = handle.clone();
+            let service = service.clone();
+            let serve_port = if let Some(rustls_config) = &rustls_config {
+                let server = tune_http(
+                    axum_server::from_tcp_rustls(std_listener, rustls_config.clone()),
+                    &self.http_config,
+                );
+
+                async move {
+                    log_info!("ServerManager", "Axum server starting with TLS...");
+                    if let Err(e) = server.handle(handle).serve(service).await {
+                        log_error!(
+                            "ServerManager",
+                            MindLinkError::Network {
+                                message: "Server error occurred".to_string(),
+                                url: None,
+                                source: Some(e.into()),
+                            }
+                        );
+                    }
+                }
+                .boxed()
+            } else {
+                let server = tune_http(axum_server::from_tcp(std_listener), &self.http_config);
+
+                async move {
+                    log_info!("ServerManager", "Axum server starting...");
+                    if let Err(e) = server.handle(handle).serve(service).await {
+                        log_error!(
+                            "ServerManager",
+                            MindLinkError::Network {
+                                message: "Server error occurred".to_string(),
+                                url: None,
+                                source: Some(e.into()),
+                            }
+                        );
+                    }
+                }
+                .boxed()
+            };
+            servers.push(serve_port);
+        }
+
+        // One task serves every listener, so a drain waits for all of them
+        // and cuts them off together
+        let server_task = tokio::spawn(async move {
+            join_all(servers).await;
+        });
+
         *self.server_handle.write().await = Some(server_task);
+        self.shutdown_signal = Some(shutdown_signal);
         *self.is_running.write().await = true;
 
-        let url = format!("http://{}:{}", self.host, self.port);
+        let url = self.base_url();
         log_info!(
             "ServerManager",
             &format!("API server started successfully at {}", url)
@@ -280,24 +919,50 @@ impl ServerManager {
 
     /// Stop the server gracefully
     pub async fn stop(&mut self) -> MindLinkResult<()> {
+        if !self.drain(STOP_DRAIN_TIMEOUT).await {
+            log_warn!(
+                "ServerManager",
+                "Requests still running after the drain timeout were cut off"
+            );
+        }
+        Ok(())
+    }
+
+    /// Stop accepting connections and wait up to `timeout` for the requests in
+    /// flight, streams included, before cutting them off. Returns whether
+    /// every request finished in time.
+    pub async fn drain(&mut self, timeout: Duration) -> bool {
         if !*self.is_running.read().await {
             log_debug!("ServerManager", "Server is not running, no action needed");
-            return Ok(());
+            return true;
         }
 
         log_info!("ServerManager", "Stopping API server...");
 
-        // Cancel the server task
-        if let Some(handle) = self.server_handle.write().await.take() {
-            handle.abort();
-            // Give it a moment to clean up
-            tokio::time::sleep(Duration::from_millis(500)).await;
+        if let Some(signal) = self.shutdown_signal.take() {
+            let _ = signal.send(());
+        }
+
+        let mut drained = true;
+        if let Some(mut handle) = self.server_handle.write().await.take() {
+            if tokio::time::timeout(timeout, &mut handle).await.is_err() {
+                drained = false;
+                handle.abort();
+                let _ = handle.await;
+            }
         }
 
         *self.is_running.write().await = false;
         log_info!("ServerManager", "API server stopped successfully");
 
-        Ok(())
+        drained
+    }
+
+    /// Wait until the analytics records of the stopped server are written
+    pub async fn flush_analytics(&self) {
+        while self.analytics_stats.snapshot().pending > 0 {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
     }
 
     /// Check if the server is responding to requests
@@ -306,10 +971,12 @@ impl ServerManager {
             return Ok(false);
         }
 
-        let health_url = format!("http://{}:{}/health", self.host, self.port);
+        let health_url = format!("{}/health", self.base_url());
 
+        // The local probe has to accept our own self-signed certificate
         let client = Client::builder()
             .timeout(Duration::from_secs(5))
+            .danger_accept_invalid_certs(self.tls.enabled)
             .build()
             .map_err(|e| network_error!("Failed to create health check client", &health_url, e))?;
 
@@ -332,7 +999,7 @@ impl ServerManager {
     /// Get the local server URL if running
     pub async fn get_local_url(&self) -> Option<String> {
         if *self.is_running.read().await {
-            Some(format!("http://{}:{}", self.host, self.port))
+            Some(self.base_url())
         } else {
             None
         }
@@ -373,268 +1040,1997 @@ impl ServerManager {
 
         Ok(())
     }
-}
-
-// ===== Router Configuration =====
 
-fn create_router(state: AppState) -> Router {
-    Router::new()
-        // OpenAI-compatible API endpoints
-        .route("/v1/models", get(get_models))
-        .route("/v1/chat/completions", post(chat_completions))
-        // Test route to debug routing
-        .route("/test", get(test_handler))
-        // Static file routes - must come BEFORE catch-all routes
-        .route("/app.js", get(serve_static_file))
-        .route("/styles.css", get(serve_static_file))
-        .route("/settings.js", get(serve_static_file))
-        .route("/settings.html", get(serve_static_file))
-        // Health and status endpoints
-        .route("/health", get(health_check))
-        .route("/dashboard", get(dashboard))
-        .with_state(state)
-        .layer(
-            ServiceBuilder::new()
-                .layer(
-                    CorsLayer::new()
-                        .allow_origin(Any)
-                        .allow_methods(Any)
-                        .allow_headers(Any),
-                )
-                .into_inner(),
-        )
-}
+    /// Configure HTTPS for the API server (only when stopped, unless it is
+    /// unchanged)
+    pub async fn configure_tls(&mut self, tls: TlsConfig) -> MindLinkResult<()> {
+        if tls == self.tls {
+            return Ok(());
+        }
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change TLS configuration while running".to_string(),
+                config_key: Some("server.tls".to_string()),
+                source: None,
+            });
+        }
 
-// ===== Route Handlers =====
+        self.tls = tls;
+        Ok(())
+    }
 
-/// Health check endpoint
-async fn health_check() -> impl IntoResponse {
-    Json(serde_json::json!({
-        "status": "healthy",
-        "timestamp": chrono::Utc::now().timestamp(),
-        "service": "MindLink API Server"
-    }))
-}
+    /// Configure the local socket served besides the port (only when stopped)
+    pub async fn configure_socket(&mut self, socket_path: Option<String>) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change the local socket while running".to_string(),
+                config_key: Some("server.socket_path".to_string()),
+                source: None,
+            });
+        }
 
-/// Root endpoint - redirects to serve index.html
-async fn root_handler() -> impl IntoResponse {
-    log_info!("ServerManager", "Root handler called");
-    // This will be handled by the fallback static file service
-    // But let's serve index.html directly here for the root route
-    let file_path = std::path::Path::new("../dist/index.html");
-    match tokio::fs::read_to_string(file_path).await {
-        Ok(content) => Html(content),
-        Err(_) => Html("<h1>MindLink Dashboard</h1><p>Frontend files not found</p>".to_string()),
+        self.socket_path = socket_path;
+        Ok(())
     }
-}
 
-/// Dashboard HTML page
-async fn dashboard() -> impl IntoResponse {
-    let html = r#"
-<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>MindLink API Dashboard</title>
-    <style>
-        body {
-            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif;
-            margin: 0;
-            padding: 20px;
-            background: linear-gradient(135deg, #667eea 0%, #764ba2 100%);
-            min-height: 100vh;
-            color: white;
-        }
-        .container {
-            max-width: 800px;
-            margin: 0 auto;
-            background: rgba(255, 255, 255, 0.1);
-            backdrop-filter: blur(10px);
-            border-radius: 15px;
-            padding: 30px;
-            box-shadow: 0 8px 32px rgba(0, 0, 0, 0.3);
-        }
-        .header {
-            text-align: center;
-            margin-bottom: 40px;
-        }
-        .status {
-            display: flex;
-            align-items: center;
-            justify-content: center;
-            margin: 20px 0;
-        }
-        .status-dot {
-            width: 12px;
-            height: 12px;
-            background: #4ade80;
-            border-radius: 50%;
-            margin-right: 8px;
-            animation: pulse 2s infinite;
-        }
-        @keyframes pulse {
-            0%, 100% { opacity: 1; }
-            50% { opacity: 0.5; }
-        }
-        .endpoints {
-            display: grid;
-            gap: 15px;
-            margin-top: 30px;
-        }
-        .endpoint {
-            background: rgba(255, 255, 255, 0.1);
-            padding: 15px;
-            border-radius: 10px;
-            border: 1px solid rgba(255, 255, 255, 0.2);
-        }
-        .endpoint h3 {
-            margin: 0 0 10px 0;
-            color: #fbbf24;
-        }
-        .endpoint code {
-            background: rgba(0, 0, 0, 0.3);
-            padding: 4px 8px;
-            border-radius: 4px;
-            font-family: 'SF Mono', Monaco, monospace;
-        }
-        .footer {
-            text-align: center;
-            margin-top: 30px;
-            opacity: 0.8;
-            font-size: 14px;
-        }
-    </style>
-</head>
-<body>
-    <div class="container">
-        <div class="header">
-            <h1>🚀 MindLink API Server</h1>
-            <div class="status">
-                <div class="status-dot"></div>
-                <span>Server is running</span>
-            </div>
-        </div>
-        
-        <div class="endpoints">
-            <div class="endpoint">
-                <h3>📋 Models</h3>
-                <p>Get available models</p>
-                <code>GET /v1/models</code>
-            </div>
-            
-            <div class="endpoint">
-                <h3>💬 Chat Completions</h3>
-                <p>OpenAI-compatible chat completions endpoint</p>
-                <code>POST /v1/chat/completions</code>
-            </div>
-            
-            <div class="endpoint">
-                <h3>❤️ Health Check</h3>
-                <p>Server health status</p>
-                <code>GET /health</code>
-            </div>
-        </div>
-        
-        <div class="footer">
-            <p>Built with ❤️ using Rust + Axum</p>
-        </div>
-    </div>
-    
-    <script>
-        // Auto-refresh status every 30 seconds
-        setInterval(async () => {
-            try {
-                const response = await fetch('/health');
-                const data = await response.json();
-                console.log('Health check:', data);
-            } catch (error) {
-                console.error('Health check failed:', error);
-            }
-        }, 30000);
-    </script>
-</body>
-</html>
-    "#;
+    /// Configure the addresses served besides the primary one (only when
+    /// stopped)
+    pub async fn configure_additional_binds(
+        &mut self,
+        binds: Vec<BindAddress>,
+    ) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change server configuration while running".to_string(),
+                config_key: Some("server.additional_binds".to_string()),
+                source: None,
+            });
+        }
 
-    Html(html)
-}
+        self.additional_binds = binds;
+        Ok(())
+    }
 
-/// Get supported models endpoint
-async fn get_models() -> impl IntoResponse {
-    log_debug!("ServerManager", "Models endpoint requested");
+    /// Configure the client IP allow/deny lists and trusted proxies (only when stopped)
+    pub async fn configure_access_control(
+        &mut self,
+        config: &AccessControlConfig,
+    ) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change access control while running".to_string(),
+                config_key: Some("access_control".to_string()),
+                source: None,
+            });
+        }
 
-    let models = ModelList {
-        object: "list".to_string(),
-        data: vec![
-            Model {
-                id: "gpt-5".to_string(),
-                object: "model".to_string(),
-                created: chrono::Utc::now().timestamp() as u64,
-                owned_by: "mindlink".to_string(),
-            },
-            Model {
-                id: "codex-mini".to_string(),
-                object: "model".to_string(),
-                created: chrono::Utc::now().timestamp() as u64,
-                owned_by: "mindlink".to_string(),
-            },
-        ],
-    };
+        self.access_policy = Arc::new(AccessPolicy::from_config(config)?);
+        self.trusted_proxies = Arc::new(TrustedProxies::from_config(config)?);
+        Ok(())
+    }
 
-    Json(models)
-}
+    /// Check the Access JWTs of requests through the named tunnel with
+    /// `verifier`, or stop checking them
+    pub async fn set_cloudflare_access(&self, verifier: Option<AccessVerifier>) {
+        *self.cloudflare_access.write().await = verifier.map(Arc::new);
+    }
 
-/// Chat completions endpoint with streaming support
-async fn chat_completions(
-    State(state): State<AppState>,
-    Json(request): Json<ChatCompletionRequest>,
-) -> impl IntoResponse {
-    log_info!(
-        "ServerManager",
-        &format!("Chat completion request for model: {}", request.model)
-    );
+    /// Configure the batch API (only when stopped)
+    pub async fn configure_batches(&mut self, config: BatchConfig) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change batch settings while running".to_string(),
+                config_key: Some("batches".to_string()),
+                source: None,
+            });
+        }
 
-    // Validate request
-    if request.messages.is_empty() {
-        return create_error_response(StatusCode::BAD_REQUEST, "messages array cannot be empty");
+        self.batch_config = Arc::new(config);
+        Ok(())
     }
 
-    // Get valid access token
-    let access_token = match get_valid_access_token(&state.auth_manager).await {
-        Ok(token) => token,
-        Err(e) => {
-            log_error!("ServerManager", e.clone());
-            return create_error_response(StatusCode::UNAUTHORIZED, &e.user_message());
-        },
-    };
+    /// Configure the job API (only when stopped)
+    pub async fn configure_jobs(&mut self, config: JobConfig) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change job settings while running".to_string(),
+                config_key: Some("jobs".to_string()),
+                source: None,
+            });
+        }
 
-    // Convert OpenAI request to ChatGPT format
-    let chatgpt_request = match convert_to_chatgpt_format(&request) {
-        Ok(req) => req,
+        self.job_config = config;
+        Ok(())
+    }
+
+    /// Configure admission control for completion requests (only when stopped)
+    pub async fn configure_backpressure(
+        &mut self,
+        config: BackpressureConfig,
+    ) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change backpressure settings while running".to_string(),
+                config_key: Some("backpressure".to_string()),
+                source: None,
+            });
+        }
+
+        self.backpressure_config = config;
+        Ok(())
+    }
+
+    /// Backpressure limit for the next start, `None` when backpressure is
+    /// disabled. Lowered to what the plans of the accounts sustain, when
+    /// those are known and `adapt_to_plan` is on.
+    async fn max_in_flight(&self) -> Option<usize> {
+        let config = &self.backpressure_config;
+        if !config.enabled {
+            return None;
+        }
+
+        let plan_limit = if config.adapt_to_plan {
+            self.accounts.plan_max_in_flight().await
+        } else {
+            None
+        };
+        match plan_limit {
+            Some(limit) if limit < config.max_in_flight => {
+                log_info!(
+                    "ServerManager",
+                    &format!(
+                        "Limiting completion requests in flight to {} for the account plans",
+                        limit
+                    )
+                );
+                Some(limit)
+            },
+            _ => Some(config.max_in_flight),
+        }
+    }
+
+    /// Configure connection handling of the server and its upstream requests
+    /// (only when stopped)
+    pub async fn configure_http(&mut self, config: HttpConfig) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change HTTP settings while running".to_string(),
+                config_key: Some("http".to_string()),
+                source: None,
+            });
+        }
+
+        self.http_config = config;
+        Ok(())
+    }
+
+    /// Configure mirroring of chat completions to a shadow backend (only when
+    /// stopped)
+    pub async fn configure_shadow(&mut self, config: ShadowConfig) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change shadow traffic while running".to_string(),
+                config_key: Some("shadow".to_string()),
+                source: None,
+            });
+        }
+
+        self.shadow_config = config;
+        Ok(())
+    }
+
+    /// Configure content moderation (only when stopped)
+    pub async fn configure_moderation(&mut self, config: ModerationConfig) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change content moderation while running".to_string(),
+                config_key: Some("moderation".to_string()),
+                source: None,
+            });
+        }
+
+        self.moderation_config = config;
+        Ok(())
+    }
+
+    /// How the answers of the shadow backend compared with the primary's
+    pub fn shadow_report(&self) -> ShadowReport {
+        self.shadow_stats.report()
+    }
+
+    /// Up to `limit` of the latest API requests with their latency breakdown,
+    /// newest first
+    pub fn recent_requests(&self, limit: usize) -> Vec<RequestSummary> {
+        self.request_log.recent(limit)
+    }
+
+    /// Count requests for the hostname of the tunnel whose URL `url` holds
+    /// as tunnel traffic, and believe the client address cloudflared reports
+    /// for requests to `cloudflared_url`
+    pub fn follow_tunnel(
+        &mut self,
+        url: Arc<RwLock<Option<String>>>,
+        cloudflared_url: Arc<RwLock<Option<String>>>,
+    ) {
+        self.tunnel_url = url;
+        self.cloudflared_url = cloudflared_url;
+    }
+
+    /// Let clients through the tunnel in with the tokens in `tokens`
+    pub fn accept_tunnel_tokens(&mut self, tokens: Arc<RwLock<TunnelTokens>>) {
+        self.tunnel_tokens = tokens;
+    }
+
+    /// Warm the models of fallback providers served by the local backend in
+    /// `local_models`, from the next start of the server
+    pub fn use_local_models(&mut self, local_models: Arc<RwLock<LocalModelManager>>) {
+        self.local_models = Some(local_models);
+    }
+
+    /// Whether requests through the tunnel need a token, and the instance
+    /// token they may present. Applies to the running server immediately.
+    pub async fn set_tunnel_token_policy(&self, policy: TunnelTokenPolicy) {
+        *self.tunnel_token_policy.write().await = policy;
+    }
+
+    /// Observe the round trip of a probe through the tunnel serving `tunnel`
+    pub fn record_tunnel_probe(&self, tunnel: &str, round_trip: Option<Duration>) {
+        self.metrics.record_tunnel_probe(tunnel, round_trip);
+    }
+
+    /// Requests, bytes and probe round trips of each tunnel
+    pub fn tunnel_traffic(&self) -> Vec<TunnelTraffic> {
+        self.metrics.tunnel_traffic()
+    }
+
+    /// Time to first token and generation speed of the streamed replies among
+    /// the recent requests, of those since `since` when given
+    pub fn stream_stats(&self, since: Option<chrono::DateTime<chrono::Utc>>) -> StreamStats {
+        self.request_log.stream_stats(since)
+    }
+
+    /// Configure capture of completion exchanges (only when stopped)
+    pub async fn configure_capture(&mut self, config: CaptureConfig) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change request capture while running".to_string(),
+                config_key: Some("capture".to_string()),
+                source: None,
+            });
+        }
+
+        self.capture_config = config;
+        Ok(())
+    }
+
+    /// Set body limits and upstream timeouts (only when stopped)
+    pub async fn configure_limits(&mut self, config: LimitsConfig) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change limits while running".to_string(),
+                config_key: Some("limits".to_string()),
+                source: None,
+            });
+        }
+
+        self.limits_config = config;
+        Ok(())
+    }
+
+    /// The `limit` most recent captures, newest first
+    pub async fn captures(&self, limit: usize) -> MindLinkResult<Vec<CaptureSummary>> {
+        query_captures(self.capture_config.max_captures, move |store| {
+            store.list(limit)
+        })
+        .await
+    }
+
+    pub async fn capture(&self, id: &str) -> MindLinkResult<Option<Capture>> {
+        let id = id.to_string();
+        query_captures(self.capture_config.max_captures, move |store| {
+            store.load(&id)
+        })
+        .await
+    }
+
+    /// Send a captured request to the running server again. The replay goes
+    /// out without the original credentials, which were never captured.
+    pub async fn replay_capture(&self, id: &str) -> MindLinkResult<ReplayResult> {
+        if !*self.is_running.read().await {
+            return Err(network_error!("The API server is not running"));
+        }
+
+        let capture = self
+            .capture(id)
+            .await?
+            .ok_or_else(|| MindLinkError::Internal {
+                message: format!("There is no capture {}", id),
+                component: Some("ServerManager".to_string()),
+                source: None,
+            })?;
+        if capture.request.truncated {
+            return Err(MindLinkError::Internal {
+                message: format!("The request body of capture {} was cut off", id),
+                component: Some("ServerManager".to_string()),
+                source: None,
+            });
+        }
+
+        let url = format!("{}{}", self.base_url(), capture.path);
+        let method = reqwest::Method::from_bytes(capture.method.as_bytes())
+            .map_err(|e| network_error!("Captured request has an invalid method", &url, e))?;
+        let client = Client::builder()
+            .timeout(REPLAY_TIMEOUT)
+            .danger_accept_invalid_certs(self.tls.enabled)
+            .build()
+            .map_err(|e| network_error!("Failed to create replay client", &url, e))?;
+
+        let mut request = client
+            .request(method, &url)
+            .header(REPLAY_HEADER, &capture.id)
+            .body(capture.request.body.clone());
+        for (name, value) in replay_headers(&capture) {
+            request = request.header(name, value);
+        }
+
+        let started = Instant::now();
+        let response = request
+            .send()
+            .await
+            .map_err(|e| network_error!("Replayed request failed", &url, e))?;
+        let status = response.status().as_u16();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| network_error!("Failed to read the replayed response", &url, e))?;
+
+        Ok(ReplayResult {
+            capture_id: capture.id,
+            original_status: capture.status,
+            status,
+            duration_ms: started.elapsed().as_millis() as u64,
+            body,
+        })
+    }
+
+    /// Overloads of the running server, as they happen
+    pub fn subscribe_overload(&self) -> broadcast::Receiver<OverloadEvent> {
+        self.overload_events.subscribe()
+    }
+
+    /// Configure the limits of traffic through the tunnel (only when stopped)
+    pub async fn configure_tunnel_limits(
+        &mut self,
+        config: TunnelLimitsConfig,
+    ) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change tunnel limits while running".to_string(),
+                config_key: Some("tunnel.limits".to_string()),
+                source: None,
+            });
+        }
+
+        self.tunnel_limits_config = config;
+        Ok(())
+    }
+
+    /// Tunnel traffic nearing or reaching its limits, as it happens
+    pub fn subscribe_tunnel_limits(&self) -> broadcast::Receiver<TunnelLimitWarning> {
+        self.tunnel_limit_events.subscribe()
+    }
+
+    /// Configure masking of personal data in prompts (only when stopped)
+    pub async fn configure_redaction(&mut self, config: &RedactionConfig) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change prompt redaction while running".to_string(),
+                config_key: Some("redaction".to_string()),
+                source: None,
+            });
+        }
+
+        self.redactor = Redactor::from_config(config)?.map(Arc::new);
+        Ok(())
+    }
+
+    /// Configure the providers chat completions fail over to (only when stopped)
+    pub async fn configure_failover(&mut self, config: FailoverConfig) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change failover providers while running".to_string(),
+                config_key: Some("failover".to_string()),
+                source: None,
+            });
+        }
+
+        self.failover = Arc::new(FallbackChain::new(config));
+        Ok(())
+    }
+
+    /// Serve `claude-*` models from the Claude account of `auth`, or stop
+    /// serving them with `None` (only when stopped)
+    pub async fn configure_anthropic(
+        &mut self,
+        auth: Option<Arc<RwLock<AuthManager>>>,
+    ) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change the Claude account while running".to_string(),
+                config_key: Some("anthropic".to_string()),
+                source: None,
+            });
+        }
+
+        self.anthropic_auth = auth;
+        Ok(())
+    }
+
+    /// Configure how requests are spread across accounts (only when stopped)
+    pub async fn configure_accounts(&mut self, config: AccountsConfig) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change account balancing while running".to_string(),
+                config_key: Some("accounts".to_string()),
+                source: None,
+            });
+        }
+
+        self.accounts.configure(config).await;
+        Ok(())
+    }
+
+    /// Add a signed-in account to the pool; it takes requests right away
+    pub async fn add_account(&self, id: &str, auth: Arc<RwLock<AuthManager>>) {
+        self.accounts.add(id, auth).await;
+    }
+
+    /// Take an extra account out of the pool, returning it for sign-out
+    pub async fn remove_account(&self, id: &str) -> Option<Arc<RwLock<AuthManager>>> {
+        if id == PRIMARY_ACCOUNT {
+            return None;
+        }
+        self.accounts
+            .remove(id)
+            .await
+            .map(|account| account.auth.clone())
+    }
+
+    /// Load and rate of every account in the pool
+    pub async fn account_status(&self) -> Vec<AccountStatus> {
+        self.accounts.status().await
+    }
+
+    /// Connection quality reported by companion apps, newest first
+    pub fn connection_reports(&self, limit: usize) -> Vec<ConnectionReport> {
+        self.ping.recent(limit)
+    }
+
+    /// Configure request analytics (only when stopped)
+    pub async fn configure_analytics(&mut self, config: AnalyticsConfig) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change analytics settings while running".to_string(),
+                config_key: Some("analytics".to_string()),
+                source: None,
+            });
+        }
+
+        self.analytics_config = config;
+        Ok(())
+    }
+
+    /// Write-behind recorder counters since the application started
+    pub fn analytics_stats(&self) -> AnalyticsStatsSnapshot {
+        self.analytics_stats.snapshot()
+    }
+
+    /// Only write analytics in full batches while paused. Also applies to a
+    /// running server, and to servers started later.
+    pub fn set_analytics_paused(&self, paused: bool) {
+        self.analytics_stats.set_paused(paused);
+    }
+
+    /// Replace the model alias map. Applies to the running server immediately.
+    pub async fn set_model_aliases(&self, config: ModelAliasConfig) {
+        *self.model_aliases.write().await = config;
+    }
+
+    /// Start trying a routing change. Needs analytics, which records the side
+    /// every request ran on, and no other canary running.
+    pub async fn start_canary(&self, spec: CanarySpec) -> MindLinkResult<Canary> {
+        if !self.analytics_config.enabled {
+            return Err(MindLinkError::Configuration {
+                message: "Canary releases need analytics to be enabled".to_string(),
+                config_key: Some("analytics.enabled".to_string()),
+                source: None,
+            });
+        }
+
+        let mut current = self.canary.write().await;
+        if current.as_ref().is_some_and(Canary::is_running) {
+            return Err(MindLinkError::Configuration {
+                message: "Another canary is already running".to_string(),
+                config_key: None,
+                source: None,
+            });
+        }
+
+        let canary =
+            Canary::start(spec, fingerprint).map_err(|message| MindLinkError::Configuration {
+                message,
+                config_key: None,
+                source: None,
+            })?;
+        let key = if canary.api_key.is_some() {
+            " plus one API key"
+        } else {
+            ""
+        };
+        log_info!(
+            "ServerManager",
+            &format!(
+                "Canary started: {}% of traffic{} for {} minutes",
+                canary.spec.percentage, key, canary.spec.trial_minutes
+            )
+        );
+        *current = Some(canary.clone());
+        Ok(canary)
+    }
+
+    /// The current or last canary, with the traffic of both sides since it started
+    pub async fn canary_status(&self) -> MindLinkResult<Option<CanaryStatus>> {
+        let Some(canary) = self.canary.read().await.clone() else {
+            return Ok(None);
+        };
+
+        let since = canary.started_at;
+        let mut stats = query_analytics(move |store| store.variant_stats(since)).await?;
+        Ok(Some(CanaryStatus {
+            stable: stats.remove(CanaryArm::Stable.as_str()).unwrap_or_default(),
+            candidate: stats.remove(CanaryArm::Canary.as_str()).unwrap_or_default(),
+            canary,
+        }))
+    }
+
+    /// Judge the running canary, promoting or rolling it back as needed.
+    /// Returns `None` when no canary is running.
+    pub async fn check_canary(&self) -> MindLinkResult<Option<(Verdict, Canary)>> {
+        let Some(status) = self.canary_status().await? else {
+            return Ok(None);
+        };
+        if !status.canary.is_running() {
+            return Ok(None);
+        }
+
+        let verdict = status
+            .canary
+            .evaluate(&status.stable, &status.candidate, chrono::Utc::now());
+        let mut current = self.canary.write().await;
+        let Some(canary) = current.as_mut().filter(|canary| canary.is_running()) else {
+            return Ok(None);
+        };
+
+        match &verdict {
+            Verdict::Continue => {},
+            Verdict::Promote => {
+                *self.model_aliases.write().await = canary.aliases();
+                canary.finish(CanaryState::Promoted, None);
+                log_info!("ServerManager", "Canary promoted to the stable routing");
+            },
+            Verdict::Rollback(reason) => {
+                canary.finish(CanaryState::RolledBack, Some(reason.clone()));
+                log_warn!("ServerManager", &format!("Canary rolled back: {}", reason));
+            },
+        }
+        Ok(Some((verdict, canary.clone())))
+    }
+
+    /// Stop the running canary, keeping the stable routing
+    pub async fn rollback_canary(&self, reason: &str) -> Option<Canary> {
+        let mut current = self.canary.write().await;
+        let canary = current.as_mut().filter(|canary| canary.is_running())?;
+        canary.finish(CanaryState::RolledBack, Some(reason.to_string()));
+        log_warn!("ServerManager", &format!("Canary rolled back: {}", reason));
+        Some(canary.clone())
+    }
+
+    /// Replace the authorized apps whose keys restrict models. Applies to the
+    /// running server immediately.
+    pub async fn set_authorized_apps(&self, apps: Vec<AuthorizedApp>) {
+        *self.authorized_apps.write().await = apps;
+    }
+
+    /// Token budget use of the apps that have a budget
+    pub async fn token_budgets(&self) -> Vec<AppBudget> {
+        let now = chrono::Utc::now();
+        self.authorized_apps
+            .read()
+            .await
+            .iter()
+            .filter(|app| app.has_token_budget())
+            .map(|app| self.budget_ledger.budget(app, now))
+            .collect()
+    }
+
+    /// Replace the system prompt and templates. Applies to the running server
+    /// immediately.
+    pub async fn set_prompt_config(&self, config: PromptConfig) {
+        *self.prompts.write().await = config;
+    }
+
+    /// Replace the post-processing rules. Takes effect immediately, also
+    /// while the server is running.
+    pub async fn set_post_processing(&self, config: &PostProcessingConfig) -> MindLinkResult<()> {
+        *self.post_processor.write().await = PostProcessor::from_config(config)?;
+        Ok(())
+    }
+
+    /// Replace the request transformation rules. Takes effect immediately,
+    /// also while the server is running.
+    pub async fn set_request_transforms(
+        &self,
+        config: &RequestTransformConfig,
+    ) -> MindLinkResult<()> {
+        *self.request_transformer.write().await = RequestTransformer::from_config(config)?;
+        Ok(())
+    }
+
+    /// Tell the model catalog where Bifrost is listening (`None` when stopped).
+    /// Takes effect immediately, also while the server is running.
+    pub async fn set_bifrost_url(&self, url: Option<String>) {
+        self.models.set_bifrost_url(url).await;
+    }
+
+    /// Fetch the model list again, bypassing the catalog cache
+    pub async fn refresh_models(&self, auth_manager: &Arc<RwLock<AuthManager>>) -> Vec<Model> {
+        let access_token = get_valid_access_token(auth_manager).await.ok();
+        self.models.refresh(access_token.as_deref()).await
+    }
+
+    /// Token usage over the last `window` grouped by key, device and model,
+    /// with the token budgets of the keys' apps, together with the streams
+    /// currently in flight
+    pub async fn quota_attribution(
+        &self,
+        window: chrono::Duration,
+    ) -> MindLinkResult<QuotaAttribution> {
+        if !self.analytics_config.enabled {
+            return Err(MindLinkError::Configuration {
+                message: "Quota attribution needs analytics to be enabled".to_string(),
+                config_key: Some("analytics.enabled".to_string()),
+                source: None,
+            });
+        }
+
+        let now = chrono::Utc::now();
+        let since = now - window;
+        let mut entries = query_analytics(move |store| store.quota_attribution(since)).await?;
+
+        // Analytics only know keys by their fingerprint
+        let apps = self.authorized_apps.read().await;
+        for entry in &mut entries {
+            let Some(key) = entry.api_key.as_deref() else {
+                continue;
+            };
+            if let Some(app) = apps.iter().find(|app| {
+                app.has_token_budget()
+                    && !app.api_key.is_empty()
+                    && fingerprint(&app.api_key) == key
+            }) {
+                entry.budgets = self.budget_ledger.budget(app, now).budgets;
+            }
+        }
+
+        Ok(QuotaAttribution::new(
+            since,
+            entries,
+            self.metrics.active_streams(),
+        ))
+    }
+
+    /// Stored analytics record of a request, looked up by its `x-request-id`
+    pub async fn request_record(
+        &self,
+        request_id: String,
+    ) -> MindLinkResult<Option<RequestRecord>> {
+        query_analytics(move |store| store.find_by_request_id(&request_id)).await
+    }
+
+    /// The `limit` most recent analytics records, newest first
+    pub async fn recent_requests(&self, limit: usize) -> MindLinkResult<Vec<RequestRecord>> {
+        query_analytics(move |store| store.recent(limit)).await
+    }
+
+    /// Configure per-route function-calling emulation (only when stopped)
+    pub async fn configure_tool_emulation(
+        &mut self,
+        config: ToolEmulationConfig,
+    ) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change tool emulation while running".to_string(),
+                config_key: Some("tool_emulation".to_string()),
+                source: None,
+            });
+        }
+
+        self.tool_emulation = Arc::new(config);
+        Ok(())
+    }
+
+    /// Configure prompt language detection and routing (only when stopped)
+    pub async fn configure_language_detection(
+        &mut self,
+        config: LanguageDetectionConfig,
+    ) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change language detection while running".to_string(),
+                config_key: Some("language_detection".to_string()),
+                source: None,
+            });
+        }
+
+        self.language_detection = Arc::new(config);
+        Ok(())
+    }
+
+    /// Configure continuation of truncated streams (only when stopped)
+    pub async fn configure_stream_continuation(
+        &mut self,
+        config: StreamContinuationConfig,
+    ) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change stream continuation while running".to_string(),
+                config_key: Some("stream_continuation".to_string()),
+                source: None,
+            });
+        }
+
+        self.stream_continuation = Arc::new(config);
+        Ok(())
+    }
+
+    /// Configure upstream conversation continuity (only when stopped). Known
+    /// conversations are forgotten.
+    pub async fn configure_conversations(
+        &mut self,
+        config: ConversationConfig,
+    ) -> MindLinkResult<()> {
+        if *self.is_running.read().await {
+            return Err(MindLinkError::Configuration {
+                message: "Cannot change conversation settings while running".to_string(),
+                config_key: Some("conversations".to_string()),
+                source: None,
+            });
+        }
+
+        self.conversations = Arc::new(ConversationStore::new(config));
+        Ok(())
+    }
+
+    /// Get the configured bind address
+    pub fn bind_address(&self) -> (String, u16) {
+        (self.host.clone(), self.port)
+    }
+
+    /// Get the bind addresses served besides the primary one
+    pub fn additional_binds(&self) -> &[BindAddress] {
+        &self.additional_binds
+    }
+
+    /// The server settings in effect
+    fn server_config(&self) -> ServerConfig {
+        ServerConfig {
+            host: self.host.clone(),
+            port: self.port,
+            tls: self.tls.clone(),
+            socket_path: self.socket_path.clone(),
+            additional_binds: self.additional_binds.clone(),
+        }
+    }
+
+    /// Base URL for reaching the server from this machine
+    fn base_url(&self) -> String {
+        self.server_config().local_url()
+    }
+
+    /// Build the rustls configuration from the user-supplied certificate, or
+    /// from a self-signed certificate generated on first use
+    async fn load_rustls_config(&self) -> MindLinkResult<RustlsConfig> {
+        let (cert_path, key_path) = match (&self.tls.cert_path, &self.tls.key_path) {
+            (Some(cert), Some(key)) => (PathBuf::from(cert), PathBuf::from(key)),
+            (None, None) => self.ensure_self_signed_certificate().await?,
+            _ => {
+                return Err(MindLinkError::Configuration {
+                    message: "TLS needs both a certificate and a key, or neither for a \
+                              self-signed certificate"
+                        .to_string(),
+                    config_key: Some("server.tls".to_string()),
+                    source: None,
+                })
+            },
+        };
+
+        log_info!(
+            "ServerManager",
+            &format!("Loading TLS certificate from {}", cert_path.display())
+        );
+
+        RustlsConfig::from_pem_file(&cert_path, &key_path)
+            .await
+            .map_err(|e| MindLinkError::Configuration {
+                message: format!(
+                    "Failed to load TLS certificate {} / key {}",
+                    cert_path.display(),
+                    key_path.display()
+                ),
+                config_key: Some("server.tls".to_string()),
+                source: Some(e.into()),
+            })
+    }
+
+    /// Return the paths of the self-signed certificate, generating it if
+    /// missing or issued for other hosts than those now served
+    async fn ensure_self_signed_certificate(&self) -> MindLinkResult<(PathBuf, PathBuf)> {
+        let tls_dir = dirs::home_dir()
+            .ok_or_else(|| MindLinkError::SystemResource {
+                message: "Cannot determine home directory".to_string(),
+                resource_type: "home directory".to_string(),
+                source: None,
+            })?
+            .join(".mindlink")
+            .join("tls");
+        let cert_path = tls_dir.join("cert.pem");
+        let key_path = tls_dir.join("key.pem");
+        // The names the certificate was issued for, one per line
+        let names_path = tls_dir.join("cert.names");
+
+        let subject_alt_names = self_signed_names(
+            std::iter::once(self.host.as_str())
+                .chain(self.additional_binds.iter().map(|bind| bind.host.as_str())),
+        );
+        let names = subject_alt_names.join("\n");
+        let issued_for = tokio::fs::read_to_string(&names_path)
+            .await
+            .unwrap_or_default();
+        if cert_path.exists() && key_path.exists() && issued_for == names {
+            return Ok((cert_path, key_path));
+        }
+
+        log_info!(
+            "ServerManager",
+            &format!(
+                "Generating self-signed TLS certificate for {} in {}",
+                subject_alt_names.join(", "),
+                tls_dir.display()
+            )
+        );
+
+        let certified = rcgen::generate_simple_self_signed(subject_alt_names).map_err(|e| {
+            MindLinkError::Internal {
+                message: "Failed to generate self-signed certificate".to_string(),
+                component: Some("ServerManager".to_string()),
+                source: Some(e.into()),
+            }
+        })?;
+
+        tokio::fs::create_dir_all(&tls_dir)
+            .await
+            .map_err(|e| MindLinkError::FileSystem {
+                message: "Failed to create TLS directory".to_string(),
+                path: Some(tls_dir.to_string_lossy().to_string()),
+                operation: "create directory".to_string(),
+                source: Some(e.into()),
+            })?;
+
+        // The private key is only ever readable by its owner
+        token_store::write_private(&key_path, certified.key_pair.serialize_pem().as_bytes())
+            .await
+            .map_err(|e| MindLinkError::FileSystem {
+                message: "Failed to write the TLS private key".to_string(),
+                path: Some(key_path.to_string_lossy().to_string()),
+                operation: "write".to_string(),
+                source: Some(e),
+            })?;
+
+        // The names last, so an interrupted write is redone on the next start
+        for (path, contents) in [(&cert_path, certified.cert.pem()), (&names_path, names)] {
+            tokio::fs::write(path, contents)
+                .await
+                .map_err(|e| MindLinkError::FileSystem {
+                    message: "Failed to write TLS certificate".to_string(),
+                    path: Some(path.to_string_lossy().to_string()),
+                    operation: "write".to_string(),
+                    source: Some(e.into()),
+                })?;
+        }
+
+        Ok((cert_path, key_path))
+    }
+}
+
+/// Names a self-signed certificate is issued for: loopback and every served
+/// host but the wildcard addresses, which clients never connect to by name
+pub fn self_signed_names<'a>(hosts: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut names = vec!["localhost".to_string(), "127.0.0.1".to_string()];
+    for host in hosts {
+        let wildcard = host
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_unspecified());
+        if !wildcard && !names.iter().any(|name| name == host) {
+            names.push(host.to_string());
+        }
+    }
+    names
+}
+
+// ===== Router Configuration =====
+
+/// Apply the keep-alive settings to a server
+fn tune_http<A>(mut server: axum_server::Server<A>, config: &HttpConfig) -> axum_server::Server<A> {
+    tune_connections(server.http_builder(), config);
+    server
+}
+
+/// Apply the keep-alive settings to the connections of a server. Connections
+/// are served as HTTP/2 when they start with its preface or negotiated it
+/// through ALPN.
+fn tune_connections(builder: &mut Builder<TokioExecutor>, config: &HttpConfig) {
+    builder
+        .http1()
+        .timer(TokioTimer::new())
+        .keep_alive(config.keep_alive)
+        .header_read_timeout(Duration::from_secs(config.header_read_timeout_secs));
+    let interval = config.http2_keep_alive_interval_secs;
+    builder
+        .http2()
+        .timer(TokioTimer::new())
+        .keep_alive_interval((interval > 0).then(|| Duration::from_secs(interval)))
+        .keep_alive_timeout(Duration::from_secs(config.http2_keep_alive_timeout_secs))
+        .max_concurrent_streams(config.http2_max_concurrent_streams);
+}
+
+/// Compress responses for clients that accept gzip or brotli. Streams are
+/// left alone unless configured otherwise, as the encoder holds their chunks
+/// back.
+fn compression(config: &HttpConfig) -> CompressionLayer<impl Predicate> {
+    let compress_streams = config.compress_streams;
+    let predicate = SizeAbove::new(config.compression_min_bytes)
+        .and(NotForContentType::GRPC)
+        .and(NotForContentType::IMAGES)
+        .and(
+            move |status: StatusCode, _: Version, headers: &HeaderMap, _: &Extensions| {
+                let streamed = headers
+                    .get(header::CONTENT_TYPE)
+                    .and_then(|value| value.to_str().ok())
+                    .is_some_and(|value| {
+                        value.starts_with("text/event-stream")
+                            || value.starts_with("application/x-ndjson")
+                    });
+                // Not the switching protocols response of a WebSocket upgrade
+                !status.is_informational() && (compress_streams || !streamed)
+            },
+        );
+    CompressionLayer::new().compress_when(predicate)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_router(
+    state: AppState,
+    access_policy: Arc<AccessPolicy>,
+    client_ip: ClientIpResolver,
+    cloudflare_access: SharedAccessVerifier,
+    tunnel_tracking: TunnelTracking,
+    tunnel_auth: TunnelAuth,
+    tunnel_limiter: Option<Arc<TunnelLimiter>>,
+    analytics: Option<AnalyticsRecorder>,
+    backpressure: Option<Arc<Backpressure>>,
+    capture: Option<Arc<Capturer>>,
+    budgets: Arc<TokenBudgets>,
+    shadow: Option<Arc<Shadow>>,
+    preflight: Option<Arc<Moderator>>,
+    post_processor: Arc<RwLock<PostProcessor>>,
+    request_transformer: Arc<RwLock<RequestTransformer>>,
+    request_log: Arc<RequestLog>,
+    limits: &LimitsConfig,
+    http: &HttpConfig,
+) -> Router {
+    let metrics = state.metrics.clone();
+
+    // Limits the body itself rather than what extractors read, so buffering
+    // middleware such as capture is bound as well
+    let limited =
+        |path: &str, default: usize, route: MethodRouter<AppState>| -> MethodRouter<AppState> {
+            let route: MethodRouter<AppState> = route.layer(DefaultBodyLimit::disable());
+            route.layer(RequestBodyLimitLayer::new(limits.body_limit(path, default)))
+        };
+    let body_limit = limits.max_body_bytes;
+
+    // A share of chat completions is mirrored to the shadow backend, once the
+    // request has been admitted
+    let mirrored = |route: MethodRouter<AppState>| match &shadow {
+        Some(shadow) => route.layer(axum::middleware::from_fn_with_state(
+            shadow.clone(),
+            mirror_traffic,
+        )),
+        None => route,
+    };
+
+    // Completion routes are captured for debugging when enabled, refuse
+    // prompts flagged by pre-flight moderation before they go anywhere, and
+    // answer 429 while the server or upstream is saturated or the app's token
+    // budget is used up. Their requests are transformed before any of that,
+    // their replies post-processed before capture sees them.
+    let completion = |route: MethodRouter<AppState>| {
+        let route = route.layer(axum::middleware::from_fn_with_state(
+            post_processor.clone(),
+            post_process,
+        ));
+        let route = match &preflight {
+            Some(moderator) => route.layer(axum::middleware::from_fn_with_state(
+                moderator.clone(),
+                screen_prompts,
+            )),
+            None => route,
+        };
+        let route = match &capture {
+            Some(capturer) => route.layer(axum::middleware::from_fn_with_state(
+                capturer.clone(),
+                capture_exchange,
+            )),
+            None => route,
+        };
+        let route = match &backpressure {
+            Some(backpressure) => route.layer(axum::middleware::from_fn_with_state(
+                backpressure.clone(),
+                apply_backpressure,
+            )),
+            None => route,
+        };
+        let route = route.layer(axum::middleware::from_fn_with_state(
+            budgets.clone(),
+            enforce_budget,
+        ));
+        route.layer(axum::middleware::from_fn_with_state(
+            request_transformer.clone(),
+            transform_requests,
+        ))
+    };
+
+    // Requests over `/v1/ws` are served by the same route, minus the layers
+    // the upgrade request already passed through
+    let completion_route = Router::new()
+        .route(
+            websocket::COMPLETIONS_PATH,
+            limited(
+                websocket::COMPLETIONS_PATH,
+                body_limit,
+                completion(mirrored(post(chat_completions))),
+            ),
+        )
+        .with_state(state.clone())
+        .layer(axum::middleware::from_fn_with_state(
+            metrics.clone(),
+            track_metrics,
+        ));
+
+    let router = Router::new()
+        // OpenAI-compatible API endpoints
+        .route("/v1/models", get(get_models))
+        .route(
+            "/v1/chat/completions",
+            limited(
+                "/v1/chat/completions",
+                body_limit,
+                completion(mirrored(post(chat_completions))),
+            ),
+        )
+        .route(
+            "/v1/ws",
+            get(chat_websocket).layer(Extension(CompletionRoute {
+                route: completion_route,
+                max_message_bytes: limits.body_limit(websocket::COMPLETIONS_PATH, body_limit),
+            })),
+        )
+        .route(
+            "/v1/files",
+            limited("/v1/files", limits.max_upload_bytes, post(upload_file)),
+        )
+        .route("/v1/files/:id", get(get_file))
+        .route("/v1/files/:id/content", get(get_file_content))
+        .route(
+            "/v1/batches",
+            limited("/v1/batches", body_limit, post(create_batch)).get(list_batches),
+        )
+        .route("/v1/batches/:id", get(get_batch))
+        .route("/v1/batches/:id/cancel", post(cancel_batch))
+        .route(
+            "/v1/jobs",
+            limited("/v1/jobs", body_limit, post(create_job)),
+        )
+        .route("/v1/jobs/:id", get(get_job))
+        .route("/v1/jobs/:id/events", get(job_events))
+        .route("/v1/jobs/:id/cancel", post(cancel_job))
+        .route("/v1/ping", get(ping))
+        .route(
+            "/v1/ping/report",
+            limited("/v1/ping/report", body_limit, post(report_ping)),
+        )
+        .route(
+            "/v1/moderations",
+            limited("/v1/moderations", body_limit, post(create_moderation)),
+        )
+        // Ollama-compatible endpoints
+        .route(
+            "/api/chat",
+            limited("/api/chat", body_limit, completion(post(ollama_chat))),
+        )
+        .route(
+            "/api/generate",
+            limited(
+                "/api/generate",
+                body_limit,
+                completion(post(ollama_generate)),
+            ),
+        )
+        .route("/api/tags", get(ollama_tags))
+        // Azure OpenAI-compatible endpoint
+        .route(
+            DEPLOYMENT_COMPLETIONS_PATH,
+            limited(
+                DEPLOYMENT_COMPLETIONS_PATH,
+                body_limit,
+                completion(mirrored(post(chat_completions)))
+                    .layer(axum::middleware::from_fn(deployment_as_model)),
+            ),
+        )
+        // Test route to debug routing
+        .route("/test", get(test_handler))
+        // Static file routes - must come BEFORE catch-all routes
+        .route("/app.js", get(serve_static_file))
+        .route("/styles.css", get(serve_static_file))
+        .route("/settings.js", get(serve_static_file))
+        .route("/settings.html", get(serve_static_file))
+        // Health and status endpoints
+        .route("/health", get(health_check))
+        .route("/playground", get(playground_page))
+        .route(
+            "/dashboard",
+            get(|| async { Redirect::permanent("/playground") }),
+        )
+        .route("/metrics", get(metrics_handler))
+        .route("/openapi.json", get(openapi_document))
+        .route("/docs", get(api_docs))
+        .with_state(state)
+        .layer(axum::middleware::from_fn_with_state(
+            access_policy,
+            enforce_access_policy,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            tunnel_auth,
+            require_tunnel_token,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            cloudflare_access,
+            require_access_jwt,
+        ))
+        .layer(axum::middleware::from_fn_with_state(metrics, track_metrics))
+        .layer(axum::middleware::from_fn_with_state(
+            tunnel_tracking,
+            track_tunnel_traffic,
+        ));
+
+    let router = match analytics {
+        Some(recorder) => router.layer(axum::middleware::from_fn_with_state(
+            recorder,
+            record_analytics,
+        )),
+        None => router,
+    };
+
+    // Times everything the request goes through once it is routed
+    let router = router.layer(axum::middleware::from_fn_with_state(
+        request_log,
+        log_requests,
+    ));
+
+    // Outside the layers that read bodies or their length
+    let router = if http.compression {
+        router.layer(compression(http))
+    } else {
+        router
+    };
+
+    // Outside compression, so the bytes sent over the wire are counted
+    let router = match tunnel_limiter {
+        Some(limiter) => router.layer(axum::middleware::from_fn_with_state(
+            limiter,
+            limit_tunnel_traffic,
+        )),
+        None => router,
+    };
+
+    router
+        // Outside access control and analytics, which both use the client IP
+        .layer(axum::middleware::from_fn_with_state(
+            client_ip,
+            resolve_client_ip,
+        ))
+        .layer(
+            ServiceBuilder::new()
+                .layer(
+                    CorsLayer::new()
+                        .allow_origin(Any)
+                        .allow_methods(Any)
+                        .allow_headers(Any)
+                        .expose_headers([REQUEST_ID_HEADER.clone()]),
+                )
+                .into_inner(),
+        )
+        // Outermost, so every other layer and handler logs with the request ID
+        .layer(axum::middleware::from_fn(assign_request_id))
+}
+
+// ===== Route Handlers =====
+
+/// Health check endpoint
+///
+/// Reports the overall level (`ok`, `degraded`, `down`) together with the
+/// per-component levels and reasons from the last health monitor run. The
+/// server and auth components are evaluated live. Always answers 200 so that
+/// reachability probes (e.g. through the tunnel) are not confused with the
+/// reported health level.
+/// The latest report of the health monitor, with the components this server
+/// knows first-hand checked live. Always answers 200 so the monitor's own
+/// probe of the server keeps working; the levels are in the body.
+async fn health_check(State(state): State<AppState>) -> impl IntoResponse {
+    let published = health::current_health().await;
+    let now = chrono::Utc::now();
+
+    let auth_health = {
+        let auth_manager = state.auth_manager.read().await;
+        let live = if auth_manager.is_authenticated().await {
+            ComponentHealth::ok("auth", true)
+        } else {
+            ComponentHealth::down("auth", "Not authenticated with ChatGPT", true)
+        };
+        // The monitor also knows what the latest session probe said
+        let health = match published.component("auth") {
+            Some(monitored) if monitored.level > live.level => monitored.clone(),
+            _ => live,
+        };
+        match auth_manager.get_tokens() {
+            Some(tokens) => auth_probe::with_token_expiry(health, tokens.expires_at, now),
+            None => health,
+        }
+    };
+
+    let upstream_health = {
+        let health = published
+            .component("upstream")
+            .cloned()
+            .unwrap_or_else(|| auth_probe::upstream_health(None));
+        let rate_limited_for = state.accounts.saturated_for().await;
+        let health = match rate_limited_for {
+            Some(_) if health.level == HealthLevel::Ok => ComponentHealth {
+                details: health.details,
+                ..ComponentHealth::degraded(
+                    "upstream",
+                    "Every account is cooling down after a rate limit",
+                    false,
+                )
+            },
+            _ => health,
+        };
+        health.with_detail(
+            "rate_limited_for_secs",
+            rate_limited_for.map(|cooldown| cooldown.as_secs()),
+        )
+    };
+
+    let queue_health = health::queue_health(
+        state.accounts.in_flight().await,
+        state.metrics.active_streams(),
+        state.jobs.as_ref().map_or(0, |jobs| jobs.queued()),
+        state.max_in_flight,
+    );
+
+    let report = published
+        .with_component(ComponentHealth::ok("server", true))
+        .with_component(auth_health)
+        .with_component(upstream_health)
+        .with_component(queue_health);
+
+    Json(serde_json::json!({
+        "status": report.overall,
+        "reason": report.reason,
+        "components": report.components,
+        "timestamp": chrono::Utc::now().timestamp(),
+        "service": "MindLink API Server"
+    }))
+}
+
+/// Root endpoint - redirects to serve index.html
+async fn root_handler() -> impl IntoResponse {
+    log_info!("ServerManager", "Root handler called");
+    // This will be handled by the fallback static file service
+    // But let's serve index.html directly here for the root route
+    let file_path = std::path::Path::new("../dist/index.html");
+    match tokio::fs::read_to_string(file_path).await {
+        Ok(content) => Html(content),
+        Err(_) => Html("<h1>MindLink Dashboard</h1><p>Frontend files not found</p>".to_string()),
+    }
+}
+
+/// `GET /v1/ping`: heartbeat for measuring round trip time and jitter
+async fn ping(State(state): State<AppState>) -> impl IntoResponse {
+    let queue = QueueDepth {
+        in_flight: state.accounts.in_flight().await,
+        active_streams: state.metrics.active_streams(),
+        queued_jobs: state.jobs.as_ref().map_or(0, |jobs| jobs.queued()),
+    };
+    Json(state.ping.pong(queue))
+}
+
+/// `POST /v1/ping/report`: store an app's connection measurements
+async fn report_ping(
+    State(state): State<AppState>,
+    Json(report): Json<ConnectionReport>,
+) -> Response<Body> {
+    if let Err(message) = report.validate() {
+        return create_error_response(StatusCode::BAD_REQUEST, &message);
+    }
+    state.ping.report(report);
+    StatusCode::NO_CONTENT.into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct ModerationRequest {
+    input: serde_json::Value,
+    #[serde(default)]
+    model: Option<String>,
+}
+
+/// `POST /v1/moderations`: check text against the moderation provider and rules
+async fn create_moderation(
+    State(state): State<AppState>,
+    Json(request): Json<ModerationRequest>,
+) -> Response<Body> {
+    let Some(moderator) = &state.moderator else {
+        return create_error_response(StatusCode::NOT_FOUND, "Moderation is not configured");
+    };
+    let Some(inputs) = moderation::moderation_inputs(&request.input) else {
+        return ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "input must be a string, an array of strings or an array of content parts",
+        )
+        .with_param("input")
+        .into_response();
+    };
+
+    let model = request.model.as_deref();
+    match moderator.moderate(&inputs, model).await {
+        Ok(results) => Json(ModerationResponse {
+            id: format!("modr-{}", Uuid::new_v4()),
+            model: moderator.model(model),
+            results,
+        })
+        .into_response(),
+        Err(e) => {
+            log_error!("ServerManager", e);
+            ApiError::from_error(&e).into_response()
+        },
+    }
+}
+
+/// Prometheus scrape endpoint
+async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        state.metrics.render(),
+    )
+}
+
+/// Chat playground on the API of this server
+async fn playground_page() -> impl IntoResponse {
+    Html(playground::page())
+}
+
+/// OpenAPI document of this server
+async fn openapi_document() -> impl IntoResponse {
+    // Relative, so the document works through the tunnel as well as locally
+    Json(openapi::document("/"))
+}
+
+async fn api_docs() -> impl IntoResponse {
+    Html(openapi::docs_page())
+}
+
+/// Chat completions over a WebSocket, for clients that cannot read SSE
+async fn chat_websocket(
+    upgrade: WebSocketUpgrade,
+    Extension(route): Extension<CompletionRoute>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    upgrade
+        .max_message_size(route.max_message_bytes)
+        .on_upgrade(move |socket| websocket::serve(socket, route, headers))
+}
+
+/// Get supported models endpoint
+async fn get_models(State(state): State<AppState>) -> impl IntoResponse {
+    log_debug!("ServerManager", "Models endpoint requested");
+
+    Json(ModelList {
+        object: "list".to_string(),
+        data: available_models(&state).await,
+    })
+}
+
+/// Models of the ChatGPT account merged with those of a running Bifrost,
+/// served from the catalog cache while it is fresh, followed by the Claude
+/// models while a Claude account is signed in
+async fn available_models(state: &AppState) -> Vec<Model> {
+    let mut models = match state.models.cached().await {
+        Some(models) => models,
+        None => {
+            let access_token = get_valid_access_token(&state.auth_manager).await.ok();
+            state.models.refresh(access_token.as_deref()).await
+        },
+    };
+
+    if let Some(auth) = &state.anthropic_auth {
+        if auth.read().await.get_tokens().is_some() {
+            let created = chrono::Utc::now().timestamp() as u64;
+            models.extend(anthropic::MODELS.map(|id| Model {
+                id: id.to_string(),
+                object: "model".to_string(),
+                created,
+                owned_by: "anthropic".to_string(),
+            }));
+        }
+    }
+    models
+}
+
+/// Chat completions endpoint with streaming support
+async fn chat_completions(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(mut request): Json<ChatCompletionRequest>,
+) -> impl IntoResponse {
+    log_info!(
+        "ServerManager",
+        &format!("Chat completion request for model: {}", request.model)
+    );
+
+    // Validate request
+    if request.messages.is_empty() {
+        return create_error_response(StatusCode::BAD_REQUEST, "messages array cannot be empty");
+    }
+
+    // The app's policy has the last word on the model language routing picked
+    let language = detect_request_language(&state.language_detection, &mut request);
+    if let Err(message) = apply_app_model_policy(&state, &headers, &mut request).await {
+        log_warn!("ServerManager", &message);
+        return ApiError::new(StatusCode::FORBIDDEN, message)
+            .with_code("model_not_allowed")
+            .with_param("model")
+            .into_response();
+    }
+
+    let system_prompt = match request_system_prompt(&state, &headers, &request).await {
+        Ok(prompt) => prompt,
+        Err(message) => return create_error_response(StatusCode::BAD_REQUEST, &message),
+    };
+    request_log::mark(Phase::Validated);
+
+    let arm = canary_arm(&state, &headers).await;
+    let upstream_model = resolve_model(&state, arm, &request.model).await;
+
+    if let Some(auth) = state
+        .anthropic_auth
+        .clone()
+        .filter(|_| anthropic::is_claude_model(&request.model))
+    {
+        let mut response = handle_anthropic_request(
+            &state,
+            &auth,
+            request,
+            &upstream_model,
+            system_prompt.as_deref(),
+        )
+        .await;
+        record_routing(&mut response, language, arm);
+        return response;
+    }
+
+    if let Some(bifrost_url) = bifrost_url_for(&state, &upstream_model).await {
+        let mut response = handle_bifrost_request(
+            &state,
+            &bifrost_url,
+            request,
+            &upstream_model,
+            system_prompt.as_deref(),
+        )
+        .await;
+        record_routing(&mut response, language, arm);
+        return response;
+    }
+
+    // Borrow an account with a valid access token, the session's if it has one
+    let account = match acquire_account(&state, session_id(&headers)).await {
+        Ok(account) => account,
         Err(e) => {
             log_error!("ServerManager", e.clone());
-            return create_error_response(StatusCode::BAD_REQUEST, &e.user_message());
+            let stream = request.stream.unwrap_or(false);
+            if let Some(fallback) = fail_over(&state, &request, &e, stream).await {
+                return fallback_response(fallback, state.stream_idle_timeout);
+            }
+            return create_error_response(StatusCode::UNAUTHORIZED, &e.user_message());
         },
     };
+    request_log::mark(Phase::Authenticated);
+
+    // The ChatGPT backend has no native tools, so describe them in the prompt
+    // instead. Tool history is flattened to text even without tools on offer.
+    flatten_tool_messages(&mut request.messages);
+    redact_prompt(&state, &mut request);
+    let emulation = resolve_tool_emulation(&state.tool_emulation, &request);
+    if let Some(emulation) = &emulation {
+        emulation.inject_prompt(&mut request.messages);
+    }
+
+    // Convert OpenAI request to ChatGPT format
+    let mut chatgpt_request =
+        match convert_to_chatgpt_format(&request, &upstream_model, system_prompt.as_deref()) {
+            Ok(req) => req,
+            Err(e) => {
+                log_error!("ServerManager", e.clone());
+                return create_error_response(StatusCode::BAD_REQUEST, &e.user_message());
+            },
+        };
 
     // Handle streaming vs non-streaming
     let is_streaming = request.stream.unwrap_or(false);
+    let model = request.model.clone();
+    let prompt_tokens = estimate_tokens(&request.messages);
 
-    if is_streaming {
-        handle_streaming_request(state, chatgpt_request, access_token, request).await
+    let mut response = if let Some(emulation) = emulation {
+        handle_tool_emulation_request(state, chatgpt_request, account, request, emulation).await
     } else {
-        handle_non_streaming_request(state, chatgpt_request, access_token, request).await
+        let turn = resume_conversation(&state, &headers, &account, &request, &mut chatgpt_request);
+        if is_streaming {
+            handle_streaming_request(state, chatgpt_request, account, request, turn).await
+        } else {
+            handle_non_streaming_request(state, chatgpt_request, account, request, turn).await
+        }
+    };
+
+    // Streams only know their prompt size by the time headers are sent
+    if response.status().is_success() && response.extensions().get::<TokenUsage>().is_none() {
+        response.extensions_mut().insert(TokenUsage {
+            model,
+            total_tokens: u64::from(prompt_tokens),
+        });
+    }
+    record_routing(&mut response, language, arm);
+
+    response
+}
+
+/// Record on `response` the language its request was routed by and the side
+/// of the canary it went through
+fn record_routing(
+    response: &mut Response<Body>,
+    language: Option<DetectedLanguage>,
+    arm: Option<CanaryArm>,
+) {
+    if let Some(language) = language {
+        response.extensions_mut().insert(language);
+    }
+    if let Some(arm) = arm {
+        response.extensions_mut().insert(arm);
+    }
+}
+
+/// The side of the running canary a request is routed through, if any
+async fn canary_arm(state: &AppState, headers: &HeaderMap) -> Option<CanaryArm> {
+    state
+        .canary
+        .read()
+        .await
+        .as_ref()?
+        .arm(key_fingerprint(headers).as_deref())
+}
+
+/// Upstream model for `model` under the routing of `arm`
+async fn resolve_model(state: &AppState, arm: Option<CanaryArm>, model: &str) -> String {
+    if arm == Some(CanaryArm::Canary) {
+        if let Some(canary) = state.canary.read().await.as_ref() {
+            return canary.aliases().resolve(model);
+        }
+    }
+    state.model_aliases.read().await.resolve(model)
+}
+
+/// Hold requests made with an authorized app's key to that app's models
+async fn apply_app_model_policy(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: &mut ChatCompletionRequest,
+) -> Result<(), String> {
+    let Some(api_key) = authorized_apps::bearer_token(headers) else {
+        return Ok(());
+    };
+    let apps = state.authorized_apps.read().await;
+    let Some(app) = authorized_apps::find_app(&apps, api_key) else {
+        return Ok(());
+    };
+
+    let model = app.model_for(&request.model)?;
+    if model != request.model {
+        log_info!(
+            "ServerManager",
+            &format!(
+                "App '{}' requested {}, using its model {}",
+                app.name, request.model, model
+            )
+        );
+        request.model = model;
+    }
+    Ok(())
+}
+
+/// The system prompt to prepend to a request, rendered for its model
+async fn request_system_prompt(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: &ChatCompletionRequest,
+) -> Result<Option<String>, String> {
+    let apps = state.authorized_apps.read().await;
+    let app_prompt = authorized_apps::bearer_token(headers)
+        .and_then(|api_key| authorized_apps::find_app(&apps, api_key))
+        .and_then(|app| app.system_prompt.as_deref());
+
+    prompt_templates::system_prompt(
+        &*state.prompts.read().await,
+        app_prompt,
+        &request.model,
+        &request.other,
+    )
+}
+
+/// Continue the upstream conversation the request's history belongs to,
+/// sending only the messages the backend has not seen. Returns the turn to
+/// complete once the reply's ids are known.
+fn resume_conversation(
+    state: &AppState,
+    headers: &HeaderMap,
+    account: &AccountLease,
+    request: &ChatCompletionRequest,
+    chatgpt_request: &mut ChatGptRequest,
+) -> Option<PendingTurn> {
+    // Conversations only exist on the account that started them
+    let scope = format!(
+        "{}\n{}",
+        account.id(),
+        authorized_apps::bearer_token(headers).unwrap_or_default()
+    );
+    let (resumption, turn) = state.conversations.begin(&scope, &request.messages);
+
+    if let Some(resumption) = resumption {
+        let seen = chatgpt_request
+            .messages
+            .len()
+            .saturating_sub(resumption.new_messages);
+        chatgpt_request.messages.drain(..seen);
+        chatgpt_request.conversation_id = Some(resumption.conversation.conversation_id);
+        chatgpt_request.parent_message_id = resumption.conversation.message_id;
+        log_debug!(
+            "ServerManager",
+            &format!(
+                "Continuing upstream conversation with {} new message(s)",
+                resumption.new_messages
+            )
+        );
+    }
+
+    turn
+}
+
+// ===== Ollama-compatible API =====
+
+/// `GET /api/tags` - the models MindLink serves, in Ollama's listing format.
+/// Claude and Bifrost models are left out, as the Ollama API reaches neither.
+async fn ollama_tags(State(state): State<AppState>) -> impl IntoResponse {
+    let model_ids: Vec<String> = available_models(&state)
+        .await
+        .into_iter()
+        .filter(|m| m.owned_by != "anthropic" && m.owned_by != "bifrost")
+        .map(|m| m.id)
+        .collect();
+    Json(ollama::tags_response(&model_ids))
+}
+
+/// `POST /api/chat`
+async fn ollama_chat(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<OllamaChatRequest>,
+) -> Response<Body> {
+    handle_ollama_request(state, &headers, request.to_openai(), OllamaEndpoint::Chat).await
+}
+
+/// `POST /api/generate`
+async fn ollama_generate(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<OllamaGenerateRequest>,
+) -> Response<Body> {
+    handle_ollama_request(
+        state,
+        &headers,
+        request.to_openai(),
+        OllamaEndpoint::Generate,
+    )
+    .await
+}
+
+/// Run a translated Ollama request through the chat completion pipeline and
+/// shape the reply for `endpoint`. Streams are sent as newline-delimited JSON.
+async fn handle_ollama_request(
+    state: AppState,
+    headers: &HeaderMap,
+    mut request: ChatCompletionRequest,
+    endpoint: OllamaEndpoint,
+) -> Response<Body> {
+    let language = detect_request_language(&state.language_detection, &mut request);
+    if let Err(message) = apply_app_model_policy(&state, headers, &mut request).await {
+        log_warn!("ServerManager", &message);
+        return create_ollama_error_response(StatusCode::FORBIDDEN, &message);
+    }
+    let arm = canary_arm(&state, headers).await;
+
+    let mut response = run_ollama_request(state, headers, request, endpoint, arm).await;
+    if let Some(language) = language {
+        response.extensions_mut().insert(language);
+    }
+    if let Some(arm) = arm {
+        response.extensions_mut().insert(arm);
+    }
+    response
+}
+
+async fn run_ollama_request(
+    state: AppState,
+    headers: &HeaderMap,
+    mut request: ChatCompletionRequest,
+    endpoint: OllamaEndpoint,
+    arm: Option<CanaryArm>,
+) -> Response<Body> {
+    log_info!(
+        "ServerManager",
+        &format!("Ollama {:?} request for model: {}", endpoint, request.model)
+    );
+
+    if request.messages.is_empty() {
+        return create_ollama_error_response(
+            StatusCode::BAD_REQUEST,
+            "no messages or prompt provided",
+        );
+    }
+    request_log::mark(Phase::Validated);
+
+    let account = match acquire_account(&state, None).await {
+        Ok(account) => account,
+        Err(e) => {
+            log_error!("ServerManager", e.clone());
+            return create_ollama_error_response(StatusCode::UNAUTHORIZED, &e.user_message());
+        },
+    };
+    request_log::mark(Phase::Authenticated);
+
+    let system_prompt = match request_system_prompt(&state, headers, &request).await {
+        Ok(prompt) => prompt,
+        Err(message) => return create_ollama_error_response(StatusCode::BAD_REQUEST, &message),
+    };
+    redact_prompt(&state, &mut request);
+
+    let upstream_model = resolve_model(&state, arm, &request.model).await;
+    let chatgpt_request =
+        match convert_to_chatgpt_format(&request, &upstream_model, system_prompt.as_deref()) {
+            Ok(req) => req,
+            Err(e) => {
+                log_error!("ServerManager", e.clone());
+                return create_ollama_error_response(StatusCode::BAD_REQUEST, &e.user_message());
+            },
+        };
+
+    let started = Instant::now();
+    let model = request.model.clone();
+    let prompt_tokens = estimate_tokens(&request.messages);
+
+    if !request.stream.unwrap_or(true) {
+        let result = make_chatgpt_request(
+            &state.http_client,
+            &chatgpt_request,
+            account.access_token(),
+            state.upstream_timeout,
+        )
+        .await;
+        account.record(&result);
+        let openai_response = match result {
+            Ok(resp) => create_openai_response(&request, &resp),
+            Err(e) => {
+                log_error!("ServerManager", e.clone());
+                state.metrics.record_upstream_error("chatgpt");
+                let fallback = match fail_over(&state, &request, &e, false).await {
+                    Some(fallback) => fallback.response.json::<ChatCompletionResponse>().await,
+                    None => {
+                        return create_ollama_error_response(
+                            StatusCode::BAD_GATEWAY,
+                            &e.user_message(),
+                        )
+                    },
+                };
+                match fallback {
+                    Ok(response) => response,
+                    Err(e) => {
+                        return create_ollama_error_response(
+                            StatusCode::BAD_GATEWAY,
+                            &format!("Invalid response from fallback provider: {}", e),
+                        )
+                    },
+                }
+            },
+        };
+
+        let usage = token_usage(&openai_response);
+        let content = openai_response
+            .choices
+            .first()
+            .and_then(|choice| choice.message.as_ref())
+            .map(|message| message.content.clone())
+            .unwrap_or_default();
+        let stats = DoneStats {
+            done_reason: "stop".to_string(),
+            total_duration_ns: started.elapsed().as_nanos() as u64,
+            prompt_eval_count: prompt_tokens,
+            eval_count: openai_response
+                .usage
+                .as_ref()
+                .map_or(0, |usage| usage.completion_tokens),
+        };
+
+        let object = ollama::response_object(endpoint, &model, &content, Some(&stats));
+        let mut response = Json(object).into_response();
+        response.extensions_mut().insert(usage);
+        return response;
     }
+
+    let rx = spawn_chat_stream(&state, chatgpt_request, account, request, None, None);
+    let usage = TokenUsage {
+        model: model.clone(),
+        total_tokens: u64::from(prompt_tokens),
+    };
+
+    // Re-encode the OpenAI chunks as Ollama objects, one per line
+    let mut completion_chars = 0;
+    let stream = tokio_stream::wrappers::ReceiverStream::new(rx).filter_map(move |chunk| {
+        let object = match chunk.ok().and_then(|c| ollama::parse_openai_chunk(&c)) {
+            Some(StreamEvent::Content(content)) => {
+                completion_chars += content.len();
+                Some(ollama::response_object(endpoint, &model, &content, None))
+            },
+            Some(StreamEvent::Finished(done_reason)) => {
+                let stats = DoneStats {
+                    done_reason,
+                    total_duration_ns: started.elapsed().as_nanos() as u64,
+                    prompt_eval_count: prompt_tokens,
+                    eval_count: (completion_chars as f32 / 4.0).ceil() as u32,
+                };
+                Some(ollama::response_object(endpoint, &model, "", Some(&stats)))
+            },
+            Some(StreamEvent::Error(message)) => Some(serde_json::json!({ "error": message })),
+            None => None,
+        };
+        std::future::ready(
+            object.map(|object| Ok::<_, std::convert::Infallible>(format!("{}\n", object))),
+        )
+    });
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "application/x-ndjson")
+        .header("Cache-Control", "no-cache")
+        .extension(usage)
+        .body(Body::from_stream(stream))
+        .unwrap()
+}
+
+/// Error body in Ollama's format: `{"error": "..."}`
+fn create_ollama_error_response(status: StatusCode, message: &str) -> Response<Body> {
+    let mut response = (status, Json(serde_json::json!({ "error": message }))).into_response();
+    response
+        .extensions_mut()
+        .insert(FailureReason(message.to_string()));
+    response
 }
 
 // ===== Helper Functions =====
 
+/// Emulation settings for a request that declares tools, if its route has emulation enabled
+fn resolve_tool_emulation(
+    config: &ToolEmulationConfig,
+    request: &ChatCompletionRequest,
+) -> Option<ToolEmulation> {
+    let tools = request.other.get("tools")?.as_array()?.clone();
+    let tool_choice = request.other.get("tool_choice").cloned();
+
+    if tools.is_empty() || tool_choice.as_ref().and_then(|c| c.as_str()) == Some("none") {
+        return None;
+    }
+
+    let route = config.route_for(&request.model)?;
+    Some(ToolEmulation {
+        tools,
+        tool_choice,
+        strictness: route.strictness,
+    })
+}
+
+/// Detect the prompt language when enabled, routing the request to the model
+/// configured for that language
+fn detect_request_language(
+    config: &LanguageDetectionConfig,
+    request: &mut ChatCompletionRequest,
+) -> Option<DetectedLanguage> {
+    if !config.enabled {
+        return None;
+    }
+
+    let detected =
+        language::detect_language(language::prompt_text(&request.messages)?, config.min_chars)?;
+
+    if let Some(model) = language::route_for_language(config, &detected) {
+        if model != request.model {
+            log_info!(
+                "ServerManager",
+                format!(
+                    "Routing {} prompt from {} to {}",
+                    detected.name, request.model, model
+                )
+            );
+            request.model = model.to_string();
+        }
+    }
+
+    Some(detected)
+}
+
 async fn get_valid_access_token(auth_manager: &Arc<RwLock<AuthManager>>) -> MindLinkResult<String> {
+    let since = Instant::now();
+    {
+        // Valid tokens are shared without waiting for one another
+        let auth = auth_manager.read().await;
+        if auth.reauth_required().is_none() && auth.is_authenticated().await {
+            if let Some(token) = auth.get_access_token() {
+                return Ok(token.to_string());
+            }
+        }
+    }
     let mut auth = auth_manager.write().await;
 
-    // Ensure we have valid tokens (handles refresh automatically)
-    auth.ensure_valid_tokens().await.map_err(|e| {
+    // Ensure we have valid tokens (handles refresh automatically); requests
+    // that waited for another one to refresh or log in share its outcome
+    auth.ensure_valid_tokens(since).await.map_err(|e| {
         let error: MindLinkError = e.into();
         log_error!("ServerManager", error);
         MindLinkError::Authentication {
@@ -652,15 +3048,166 @@ async fn get_valid_access_token(auth_manager: &Arc<RwLock<AuthManager>>) -> Mind
         })
 }
 
-fn convert_to_chatgpt_format(request: &ChatCompletionRequest) -> MindLinkResult<ChatGptRequest> {
+/// Send `request` down the fallback chain after the ChatGPT backend failed
+/// with `error`. `None` when failover is off, does not apply to the error, or
+/// no fallback provider answered either.
+async fn fail_over(
+    state: &AppState,
+    request: &ChatCompletionRequest,
+    error: &MindLinkError,
+    stream: bool,
+) -> Option<FallbackResponse> {
+    if !state.failover.is_enabled() || !failover::should_fail_over(error) {
+        return None;
+    }
+
+    let bifrost_url = state.models.bifrost_url().await;
+    // Snapshot the manager so a slow fallback doesn't block reconfiguration
+    let local_models = match &state.local_models {
+        Some(local_models) => Some(local_models.read().await.clone()),
+        None => None,
+    };
+    let result = state
+        .failover
+        .send(
+            &state.http_client,
+            request,
+            stream,
+            bifrost_url.as_deref(),
+            local_models.as_ref(),
+            state.upstream_timeout,
+        )
+        .await;
+    match result {
+        Ok(fallback) => {
+            log_warn!(
+                "ServerManager",
+                &format!(
+                    "ChatGPT backend unavailable, served by fallback provider '{}'",
+                    fallback.provider
+                )
+            );
+            Some(fallback)
+        },
+        Err(e) => {
+            log_error!("ServerManager", e);
+            state.metrics.record_upstream_error("fallback");
+            None
+        },
+    }
+}
+
+/// Pass a fallback provider's response through, naming the provider in the
+/// `x-mindlink-provider` header. The body ends once it goes quiet for
+/// `idle_timeout`.
+fn fallback_response(fallback: FallbackResponse, idle_timeout: Duration) -> Response<Body> {
+    let mut builder = Response::builder().status(fallback.response.status());
+    if let Some(content_type) = fallback.response.headers().get(header::CONTENT_TYPE) {
+        builder = builder.header(header::CONTENT_TYPE, content_type.clone());
+    }
+    if let Ok(provider) = HeaderValue::from_str(&fallback.provider) {
+        builder = builder.header("x-mindlink-provider", provider);
+    }
+    let body = tokio_stream::StreamExt::timeout(fallback.response.bytes_stream(), idle_timeout);
+    let body = tokio_stream::StreamExt::map_while(body, Result::ok);
+    builder.body(Body::from_stream(body)).unwrap()
+}
+
+/// Session a request belongs to, from the first session header it carries
+fn session_id(headers: &HeaderMap) -> Option<&str> {
+    SESSION_HEADERS.iter().find_map(|name| {
+        headers
+            .get(*name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|session| !session.is_empty())
+    })
+}
+
+/// Borrow an account of the pool with a valid access token, trying the
+/// accounts in balancing order. Requests of a `session` stay on its account
+/// while it can serve them.
+async fn acquire_account(state: &AppState, session: Option<&str>) -> MindLinkResult<AccountLease> {
+    let mut last_error = None;
+    for account in state.accounts.candidates(session).await {
+        // Only the primary account may fall back to an interactive login
+        if account.id != PRIMARY_ACCOUNT && account.auth.read().await.get_tokens().is_none() {
+            continue;
+        }
+        match get_valid_access_token(&account.auth).await {
+            Ok(token) => {
+                if let Some(session) = session {
+                    if let Some(previous) = state.accounts.pin(session, &account.id).await {
+                        log_info!(
+                            "ServerManager",
+                            &format!(
+                                "Session moved from account '{}' to '{}'",
+                                previous, account.id
+                            )
+                        );
+                    }
+                }
+                return Ok(state.accounts.lease(account, token).await);
+            },
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| MindLinkError::Authentication {
+        message: "No ChatGPT account is signed in".to_string(),
+        source: None,
+    }))
+}
+
+/// Mask personal data in the request's messages if redaction is configured
+fn redact_prompt(state: &AppState, request: &mut ChatCompletionRequest) {
+    let Some(redactor) = &state.redactor else {
+        return;
+    };
+    let masked = redactor.redact_messages(&mut request.messages);
+    if masked > 0 {
+        log_info!(
+            "ServerManager",
+            &format!("Redacted {} value(s) from the prompt", masked)
+        );
+    }
+}
+
+/// Convert an OpenAI request for the ChatGPT backend. `upstream_model` is the
+/// requested model after alias resolution.
+fn convert_to_chatgpt_format(
+    request: &ChatCompletionRequest,
+    upstream_model: &str,
+    system_prompt: Option<&str>,
+) -> MindLinkResult<ChatGptRequest> {
     let mut chatgpt_messages = Vec::new();
 
+    if let Some(system_prompt) = system_prompt {
+        chatgpt_messages.push(ChatGptMessage {
+            id: Uuid::new_v4().to_string(),
+            author: ChatGptAuthor {
+                role: "system".to_string(),
+                name: None,
+            },
+            content: ChatGptContent {
+                content_type: "text".to_string(),
+                parts: vec![system_prompt.to_string()],
+            },
+            metadata: None,
+        });
+    }
+
     for (_index, message) in request.messages.iter().enumerate() {
+        // Developer messages are the system messages of newer models
+        let role = match message.role.as_str() {
+            "developer" => "system",
+            role => role,
+        };
         let chatgpt_message = ChatGptMessage {
             id: Uuid::new_v4().to_string(),
             author: ChatGptAuthor {
-                role: message.role.clone(),
-                name: None,
+                role: role.to_string(),
+                name: message.name.clone(),
             },
             content: ChatGptContent {
                 content_type: "text".to_string(),
@@ -671,18 +3218,16 @@ fn convert_to_chatgpt_format(request: &ChatCompletionRequest) -> MindLinkResult<
         chatgpt_messages.push(chatgpt_message);
     }
 
-    // Add a parent message ID (required by ChatGPT API)
-    let parent_message_id = if chatgpt_messages.len() > 1 {
-        chatgpt_messages[chatgpt_messages.len() - 2].id.clone()
-    } else {
-        Uuid::new_v4().to_string()
-    };
+    // New conversations hang off a fresh root; resumed ones replace this with
+    // the id of the previous reply
+    let parent_message_id = Uuid::new_v4().to_string();
 
     Ok(ChatGptRequest {
         action: "next".to_string(),
         messages: chatgpt_messages,
         parent_message_id,
-        model: map_model_name(&request.model),
+        conversation_id: None,
+        model: upstream_model.to_string(),
         stream: request.stream,
         temperature: request.temperature,
         max_tokens: request.max_tokens,
@@ -698,49 +3243,266 @@ fn convert_to_chatgpt_format(request: &ChatCompletionRequest) -> MindLinkResult<
             .other
             .get("presence_penalty")
             .and_then(|v| v.as_f64().map(|f| f as f32)),
+        reasoning_effort: reasoning::reasoning_effort(&request.other),
+        max_completion_tokens: reasoning::max_completion_tokens(&request.other),
     })
 }
 
-fn map_model_name(model: &str) -> String {
-    match model {
-        "gpt-5" => "gpt-4".to_string(), // Map to actual ChatGPT model
-        "codex-mini" => "gpt-3.5-turbo".to_string(),
-        _ => "gpt-4".to_string(), // Default fallback
+async fn handle_non_streaming_request(
+    state: AppState,
+    chatgpt_request: ChatGptRequest,
+    account: AccountLease,
+    original_request: ChatCompletionRequest,
+    turn: Option<PendingTurn>,
+) -> Response<Body> {
+    log_debug!("ServerManager", "Processing non-streaming request");
+
+    // Make request to ChatGPT API
+    let result = make_chatgpt_request(
+        &state.http_client,
+        &chatgpt_request,
+        account.access_token(),
+        state.upstream_timeout,
+    )
+    .await;
+    account.record(&result);
+    let response = match result {
+        Ok(resp) => resp,
+        Err(e) => {
+            log_error!("ServerManager", e.clone());
+            state.metrics.record_upstream_error("chatgpt");
+            if let Some(fallback) = fail_over(&state, &original_request, &e, false).await {
+                return fallback_response(fallback, state.stream_idle_timeout);
+            }
+            return ApiError::from_error(&e).into_response();
+        },
+    };
+
+    // Convert response back to OpenAI format
+    let openai_response = create_openai_response(&original_request, &response);
+    let usage = token_usage(&openai_response);
+
+    if let (Some(turn), Some(conversation)) = (turn, conversations::upstream_ids(&response)) {
+        if let Some(message) = openai_response
+            .choices
+            .first()
+            .and_then(|c| c.message.as_ref())
+        {
+            turn.complete(&message.content, conversation);
+        }
     }
+
+    let mut response = Json(openai_response).into_response();
+    response.extensions_mut().insert(usage);
+    response
 }
 
-async fn handle_non_streaming_request(
+/// Run a request with emulated tools. The full model output is needed to parse
+/// tool calls, so streaming clients receive the result as a single SSE burst.
+async fn handle_tool_emulation_request(
     state: AppState,
-    chatgpt_request: ChatGptRequest,
-    access_token: String,
+    mut chatgpt_request: ChatGptRequest,
+    account: AccountLease,
     original_request: ChatCompletionRequest,
+    emulation: ToolEmulation,
 ) -> Response<Body> {
-    log_debug!("ServerManager", "Processing non-streaming request");
+    log_debug!(
+        "ServerManager",
+        "Processing request with emulated tool calls"
+    );
 
-    // Make request to ChatGPT API
-    let response =
-        match make_chatgpt_request(&state.http_client, &chatgpt_request, &access_token).await {
-            Ok(resp) => resp,
-            Err(e) => {
-                log_error!("ServerManager", e.clone());
-                return create_error_response(StatusCode::BAD_GATEWAY, &e.user_message());
-            },
-        };
+    chatgpt_request.stream = Some(false);
 
-    // Convert response back to OpenAI format
-    let openai_response = create_openai_response(&original_request, &response);
+    let result = make_chatgpt_request(
+        &state.http_client,
+        &chatgpt_request,
+        account.access_token(),
+        state.upstream_timeout,
+    )
+    .await;
+    account.record(&result);
+    let response = match result {
+        Ok(resp) => resp,
+        Err(e) => {
+            log_error!("ServerManager", e.clone());
+            state.metrics.record_upstream_error("chatgpt");
+            return ApiError::from_error(&e).into_response();
+        },
+    };
+
+    let mut openai_response = create_openai_response(&original_request, &response);
+    let content = extract_content_from_response(&response).unwrap_or_default();
+
+    let output = match emulation.parse_output(&content) {
+        Ok(output) => output,
+        Err(e) => {
+            log_error!("ServerManager", e.clone());
+            return create_error_response(StatusCode::BAD_GATEWAY, &e.to_string());
+        },
+    };
+
+    if let EmulatedOutput::ToolCalls(tool_calls) = &output {
+        if let Some(choice) = openai_response.choices.first_mut() {
+            choice.message = Some(Message {
+                role: "assistant".to_string(),
+                content: String::new(),
+                tool_calls: Some(tool_calls.clone()),
+                tool_call_id: None,
+                name: None,
+                reasoning_content: None,
+            });
+            choice.finish_reason = Some("tool_calls".to_string());
+        }
+    }
+
+    let usage = token_usage(&openai_response);
+    if !original_request.stream.unwrap_or(false) {
+        let mut response = Json(openai_response).into_response();
+        response.extensions_mut().insert(usage);
+        return response;
+    }
+
+    let (delta, finish_reason) = match output {
+        EmulatedOutput::ToolCalls(tool_calls) => {
+            let calls: Vec<serde_json::Value> = tool_calls
+                .iter()
+                .enumerate()
+                .map(|(index, call)| {
+                    serde_json::json!({
+                        "index": index,
+                        "id": call.id,
+                        "type": call.call_type,
+                        "function": {
+                            "name": call.function.name,
+                            "arguments": call.function.arguments,
+                        }
+                    })
+                })
+                .collect();
+            (
+                serde_json::json!({ "role": "assistant", "tool_calls": calls }),
+                "tool_calls",
+            )
+        },
+        EmulatedOutput::Text(text) => (
+            serde_json::json!({ "role": "assistant", "content": text }),
+            "stop",
+        ),
+    };
+
+    let chunk = |delta: serde_json::Value, finish_reason: Option<&str>| {
+        format!(
+            "data: {}\n\n",
+            serde_json::json!({
+                "id": openai_response.id,
+                "object": "chat.completion.chunk",
+                "created": openai_response.created,
+                "model": openai_response.model,
+                "choices": [{
+                    "index": 0,
+                    "delta": delta,
+                    "finish_reason": finish_reason
+                }]
+            })
+        )
+    };
+
+    let mut body = [
+        chunk(delta, None),
+        chunk(serde_json::json!({}), Some(finish_reason)),
+    ]
+    .concat();
+    if let Some(usage) = openai_response
+        .usage
+        .as_ref()
+        .filter(|_| stream_usage_requested(&original_request))
+    {
+        body.push_str(&usage_chunk(
+            &openai_response.id,
+            &openai_response.model,
+            openai_response.created as i64,
+            usage,
+        ));
+    }
+    body.push_str("data: [DONE]\n\n");
 
-    Json(openai_response).into_response()
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/plain; charset=utf-8")
+        .header("Cache-Control", "no-cache")
+        .header("Access-Control-Allow-Origin", "*")
+        .extension(usage)
+        .body(Body::from(body))
+        .unwrap()
 }
 
 async fn handle_streaming_request(
     state: AppState,
-    mut chatgpt_request: ChatGptRequest,
-    access_token: String,
+    chatgpt_request: ChatGptRequest,
+    account: AccountLease,
     original_request: ChatCompletionRequest,
+    turn: Option<PendingTurn>,
 ) -> Response<Body> {
     log_debug!("ServerManager", "Processing streaming request with SSE");
 
+    let usage_prompt_tokens = stream_usage_requested(&original_request)
+        .then(|| estimate_tokens(&original_request.messages));
+    let rx = spawn_chat_stream(
+        &state,
+        chatgpt_request,
+        account,
+        original_request,
+        turn,
+        usage_prompt_tokens,
+    );
+
+    sse_response(rx)
+}
+
+/// Response streaming the chunks received on `rx`
+fn sse_response(
+    rx: tokio::sync::mpsc::Receiver<Result<String, std::convert::Infallible>>,
+) -> Response<Body> {
+    // Convert receiver to stream
+    let stream = tokio_stream::wrappers::ReceiverStream::new(rx);
+
+    // Create SSE response
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/plain; charset=utf-8")
+        .header("Cache-Control", "no-cache")
+        .header("Connection", "keep-alive")
+        .header("Access-Control-Allow-Origin", "*")
+        .header("Access-Control-Allow-Methods", "GET, POST, OPTIONS")
+        .header(
+            "Access-Control-Allow-Headers",
+            "Content-Type, Authorization",
+        )
+        .body(Body::from_stream(stream))
+        .unwrap()
+}
+
+/// Run the upstream stream on a background task. Each received item is one
+/// OpenAI `chat.completion.chunk` in SSE framing, ending with `[DONE]`.
+///
+/// With stream continuation enabled, an upstream stream that ends before the
+/// response is finished is resumed with a follow-up request and stitched into
+/// the same client stream. The final chunk then carries a `mindlink` object
+/// recording how many continuations were needed.
+///
+/// `turn` is completed once the whole reply reached the client. With
+/// `usage_prompt_tokens` set, a usage chunk is sent before `[DONE]`.
+///
+/// When the upstream request fails before anything was sent, `request` is
+/// streamed from the fallback chain instead.
+fn spawn_chat_stream(
+    state: &AppState,
+    mut chatgpt_request: ChatGptRequest,
+    account: AccountLease,
+    request: ChatCompletionRequest,
+    turn: Option<PendingTurn>,
+    usage_prompt_tokens: Option<u32>,
+) -> tokio::sync::mpsc::Receiver<Result<String, std::convert::Infallible>> {
     // Ensure streaming is enabled for ChatGPT request
     chatgpt_request.stream = Some(true);
 
@@ -748,118 +3510,623 @@ async fn handle_streaming_request(
     let (tx, rx) = tokio::sync::mpsc::channel::<Result<String, std::convert::Infallible>>(100);
 
     // Spawn task to handle ChatGPT streaming response
+    let state = state.clone();
+    let model = request.model.clone();
     let client = state.http_client.clone();
     let request_id = format!("chatcmpl-{}", Uuid::new_v4());
-    let model = original_request.model.clone();
+    let metrics = state.metrics.clone();
+    let continuation = state.stream_continuation.clone();
+    let timeouts = (state.upstream_timeout, state.stream_idle_timeout);
+    let correlation_id = current_correlation_id();
+    let timer = request_log::current_timer();
 
-    tokio::spawn(async move {
-        match make_chatgpt_streaming_request(
-            &client,
-            &chatgpt_request,
-            &access_token,
-            &request_id,
-            &model,
-            tx.clone(),
-        )
-        .await
-        {
+    let stream = async move {
+        let _stream_guard = metrics.stream_started();
+
+        let mut progress = StreamProgress::default();
+        let mut stitcher = ContinuationStitcher::default();
+        let mut upstream_request = chatgpt_request.clone();
+        let mut continuations = 0;
+        let mut truncated = false;
+
+        let result = loop {
+            let attempt = make_chatgpt_streaming_request(
+                &client,
+                &upstream_request,
+                account.access_token(),
+                timeouts,
+                &request_id,
+                &model,
+                &tx,
+                &mut stitcher,
+                &mut progress,
+            )
+            .await;
+            account.record(&attempt);
+
+            if let Some(rest) = stitcher.finish() {
+                if !send_content(&tx, &request_id, &model, &rest).await {
+                    progress.client_gone = true;
+                }
+                progress.text.push_str(&rest);
+            }
+
+            if progress.client_gone || progress.finished {
+                break attempt;
+            }
+            // Nothing was received, so there is nothing to continue from
+            if progress.text.is_empty() {
+                break attempt;
+            }
+
+            // Without a continuation the client learns the reply is incomplete:
+            // an upstream error is passed on, a stream that was cut off ends
+            // with `length`
+            if !continuation.enabled || continuations >= continuation.max_continuations {
+                log_warn!(
+                    "ServerManager",
+                    "Upstream stream ended before the response was finished"
+                );
+                truncated = true;
+                break attempt;
+            }
+            if let Err(e) = &attempt {
+                log_error!("ServerManager", e);
+            }
+
+            continuations += 1;
+            log_info!(
+                "ServerManager",
+                &format!(
+                    "Continuing truncated stream {} (attempt {}/{})",
+                    request_id, continuations, continuation.max_continuations
+                )
+            );
+            upstream_request = continuation_request(&chatgpt_request, &progress);
+            stitcher = ContinuationStitcher::new(&progress.text);
+        };
+
+        match result {
+            Ok(_) if progress.client_gone => {
+                log_debug!("ServerManager", "Client disconnected during streaming");
+            },
             Ok(_) => {
+                // A truncated reply is not part of the conversation to resume
+                if !truncated {
+                    if let (Some(turn), Some(conversation)) = (turn, progress.upstream()) {
+                        turn.complete(&progress.text, conversation);
+                    }
+                }
+
+                // Send final chunk with finish_reason
+                let mut final_chunk = create_streaming_chunk(&request_id, &model, "", 0, true);
+                if truncated {
+                    final_chunk["choices"][0]["finish_reason"] = serde_json::json!("length");
+                }
+                if continuations > 0 {
+                    final_chunk["mindlink"] = serde_json::json!({
+                        "continued": true,
+                        "continuations": continuations
+                    });
+                }
+                let final_line = format!(
+                    "data: {}\n\n",
+                    serde_json::to_string(&final_chunk).unwrap_or_default()
+                );
+                let _ = tx.send(Ok(final_line)).await;
+
+                let completion_tokens = estimate_text_tokens(&progress.text);
+                request_log::set_completion_tokens(u64::from(completion_tokens));
+                if let Some(throughput) =
+                    request_log::current_timer().and_then(|timer| timer.throughput())
+                {
+                    metrics.record_stream(&throughput);
+                }
+
+                if let Some(prompt_tokens) = usage_prompt_tokens {
+                    let usage = Usage {
+                        prompt_tokens,
+                        completion_tokens,
+                        total_tokens: prompt_tokens + completion_tokens,
+                    };
+                    let created = chrono::Utc::now().timestamp();
+                    let _ = tx
+                        .send(Ok(usage_chunk(&request_id, &model, created, &usage)))
+                        .await;
+                }
+
                 // Send final [DONE] message
                 let done_chunk = "data: [DONE]\n\n";
                 let _ = tx.send(Ok(done_chunk.to_string())).await;
             },
             Err(e) => {
                 log_error!("ServerManager", &e);
+                metrics.record_upstream_error("chatgpt");
+                // Nothing reached the client yet, so another provider can still answer
+                if progress.text.is_empty() {
+                    if let Some(fallback) = fail_over(&state, &request, &e, true).await {
+                        failover::forward_stream(fallback.response, &tx, timeouts.1).await;
+                        return;
+                    }
+                }
                 // Send error in SSE format
-                let error_chunk = format!(
-                    "data: {}\n\n",
-                    serde_json::json!({
-                        "error": {
-                            "message": e.to_string(),
-                            "type": "server_error"
-                        }
-                    })
-                );
+                let error_chunk = format!("data: {}\n\n", ApiError::from_error(&e).body());
                 let _ = tx.send(Ok(error_chunk)).await;
             },
         }
-    });
+    };
+    tokio::spawn(with_correlation_id(
+        correlation_id,
+        with_timer(timer, stream),
+    ));
 
-    // Convert receiver to stream
-    let stream = tokio_stream::wrappers::ReceiverStream::new(rx);
+    rx
+}
 
-    // Create SSE response
-    Response::builder()
-        .status(StatusCode::OK)
-        .header("Content-Type", "text/plain; charset=utf-8")
-        .header("Cache-Control", "no-cache")
-        .header("Connection", "keep-alive")
-        .header("Access-Control-Allow-Origin", "*")
-        .header("Access-Control-Allow-Methods", "GET, POST, OPTIONS")
-        .header(
-            "Access-Control-Allow-Headers",
-            "Content-Type, Authorization",
+/// What one upstream stream delivered, kept across continuation attempts
+#[derive(Debug, Default)]
+struct StreamProgress {
+    /// Content forwarded to the client so far
+    text: String,
+    /// The upstream sent `[DONE]`
+    finished: bool,
+    client_gone: bool,
+    conversation_id: Option<String>,
+    message_id: Option<String>,
+    reasoning: ReasoningStream,
+}
+
+impl StreamProgress {
+    /// Where the streamed reply lives upstream, once both ids were reported
+    fn upstream(&self) -> Option<UpstreamConversation> {
+        Some(UpstreamConversation {
+            conversation_id: self.conversation_id.clone()?,
+            message_id: self.message_id.clone()?,
+        })
+    }
+}
+
+/// Follow-up request resuming a truncated response. When the backend reported
+/// its conversation and message ids it is asked to continue that message, the
+/// same as ChatGPT's "Continue generating", so nothing is sent again.
+/// Otherwise the partial reply is replayed with a request to carry on.
+fn continuation_request(base: &ChatGptRequest, progress: &StreamProgress) -> ChatGptRequest {
+    let mut request = base.clone();
+
+    if let (Some(conversation_id), Some(message_id)) =
+        (&progress.conversation_id, &progress.message_id)
+    {
+        request.action = "continue".to_string();
+        request.conversation_id = Some(conversation_id.clone());
+        request.parent_message_id = message_id.clone();
+        request.messages = Vec::new();
+        return request;
+    }
+
+    for (role, content) in [
+        ("assistant", progress.text.as_str()),
+        ("user", CONTINUE_PROMPT),
+    ] {
+        let message = ChatGptMessage {
+            id: Uuid::new_v4().to_string(),
+            author: ChatGptAuthor {
+                role: role.to_string(),
+                name: None,
+            },
+            content: ChatGptContent {
+                content_type: "text".to_string(),
+                parts: vec![content.to_string()],
+            },
+            metadata: None,
+        };
+        if let Some(previous) = request.messages.last() {
+            request.parent_message_id = previous.id.clone();
+        }
+        request.messages.push(message);
+    }
+
+    request
+}
+
+/// Forward one content delta; returns false once the client has gone away
+async fn send_content(
+    tx: &tokio::sync::mpsc::Sender<Result<String, std::convert::Infallible>>,
+    request_id: &str,
+    model: &str,
+    content: &str,
+) -> bool {
+    let openai_chunk = create_streaming_chunk(request_id, model, content, 0, false);
+    let chunk_line = format!(
+        "data: {}\n\n",
+        serde_json::to_string(&openai_chunk).unwrap_or_default()
+    );
+    let sent = tx.send(Ok(chunk_line)).await.is_ok();
+    if sent && !content.is_empty() {
+        request_log::mark(Phase::FirstToken);
+    }
+    sent
+}
+
+async fn make_chatgpt_request(
+    client: &Client,
+    request: &ChatGptRequest,
+    access_token: &str,
+    timeout: Duration,
+) -> MindLinkResult<serde_json::Value> {
+    log_debug!("ServerManager", "Making request to ChatGPT backend");
+
+    request_log::mark(Phase::UpstreamSent);
+    let response = client
+        .post("https://chatgpt.com/backend-api/conversation")
+        .header("Authorization", format!("Bearer {}", access_token))
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .timeout(timeout)
+        .json(request)
+        .send()
+        .await
+        .map_err(|e| network_error!("ChatGPT API request failed", "https://chatgpt.com", e))?;
+    request_log::mark(Phase::FirstByte);
+
+    if !response.status().is_success() {
+        return Err(upstream_error(response).await);
+    }
+
+    let json_response = response
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| network_error!("Failed to parse ChatGPT response", "", e))?;
+
+    log_debug!("ServerManager", "ChatGPT request completed successfully");
+
+    Ok(json_response)
+}
+
+/// Serve `request` from the Claude account of `auth` through Anthropic's
+/// Messages API, as `upstream_model`
+/// Base URL of the running Bifrost when it serves `model` rather than the
+/// ChatGPT account
+async fn bifrost_url_for(state: &AppState, model: &str) -> Option<String> {
+    let bifrost_url = state.models.bifrost_url().await?;
+    if state.models.cached().await.is_none() {
+        let access_token = get_valid_access_token(&state.auth_manager).await.ok();
+        state.models.refresh(access_token.as_deref()).await;
+    }
+    state
+        .models
+        .is_bifrost_model(model)
+        .await
+        .then_some(bifrost_url)
+}
+
+/// Send a request for one of Bifrost's models to Bifrost, which speaks the
+/// OpenAI API, and pass its reply through
+async fn handle_bifrost_request(
+    state: &AppState,
+    bifrost_url: &str,
+    mut request: ChatCompletionRequest,
+    upstream_model: &str,
+    system_prompt: Option<&str>,
+) -> Response<Body> {
+    redact_prompt(state, &mut request);
+    let model = std::mem::replace(&mut request.model, upstream_model.to_string());
+    if let Some(system_prompt) = system_prompt {
+        request.messages.insert(
+            0,
+            Message {
+                role: "system".to_string(),
+                content: system_prompt.to_string(),
+                tool_calls: None,
+                tool_call_id: None,
+                name: None,
+                reasoning_content: None,
+            },
+        );
+    }
+    let prompt_tokens = estimate_tokens(&request.messages);
+
+    let url = format!("{}/v1/chat/completions", bifrost_url.trim_end_matches('/'));
+    request_log::mark(Phase::UpstreamSent);
+    let send = state.http_client.post(&url).json(&request).send();
+    let response = match tokio::time::timeout(state.upstream_timeout, send).await {
+        Ok(Ok(response)) if response.status().is_success() => response,
+        Ok(Ok(response)) => {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            let error = MindLinkError::Network {
+                message: format!("Bifrost returned status: {}", status),
+                url: Some(url),
+                source: Some(UpstreamStatus::new(status.as_u16(), &body).into()),
+            };
+            log_error!("ServerManager", error.clone());
+            state.metrics.record_upstream_error("bifrost");
+            return ApiError::from_error(&error).into_response();
+        },
+        Ok(Err(e)) => {
+            let error = network_error!("Bifrost request failed", &url, e);
+            log_error!("ServerManager", error.clone());
+            state.metrics.record_upstream_error("bifrost");
+            return ApiError::from_error(&error).into_response();
+        },
+        Err(_) => {
+            state.metrics.record_upstream_error("bifrost");
+            return create_error_response(
+                StatusCode::GATEWAY_TIMEOUT,
+                "Bifrost did not answer in time",
+            );
+        },
+    };
+    request_log::mark(Phase::FirstByte);
+
+    let fallback = FallbackResponse {
+        provider: "bifrost".to_string(),
+        response,
+    };
+    let mut response = fallback_response(fallback, state.stream_idle_timeout);
+    response.extensions_mut().insert(TokenUsage {
+        model,
+        total_tokens: u64::from(prompt_tokens),
+    });
+    response
+}
+
+async fn handle_anthropic_request(
+    state: &AppState,
+    auth: &Arc<RwLock<AuthManager>>,
+    mut request: ChatCompletionRequest,
+    upstream_model: &str,
+    system_prompt: Option<&str>,
+) -> Response<Body> {
+    if anthropic::uses_tools(&request) {
+        return ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "Tools are not supported for Claude models",
         )
-        .body(Body::from_stream(stream))
-        .unwrap()
+        .with_param("tools")
+        .into_response();
+    }
+
+    // Requests never start the browser login of an account
+    if auth.read().await.get_tokens().is_none() {
+        return create_error_response(StatusCode::UNAUTHORIZED, "No Claude account is signed in");
+    }
+    let access_token = match get_valid_access_token(auth).await {
+        Ok(token) => token,
+        Err(e) => return create_error_response(StatusCode::UNAUTHORIZED, &e.user_message()),
+    };
+    request_log::mark(Phase::Authenticated);
+
+    flatten_tool_messages(&mut request.messages);
+    redact_prompt(state, &mut request);
+    let stream = request.stream.unwrap_or(false);
+    let body = anthropic::messages_request(&request, upstream_model, system_prompt, stream);
+
+    request_log::mark(Phase::UpstreamSent);
+    let send = state
+        .http_client
+        .post(anthropic::MESSAGES_URL)
+        .header("Authorization", format!("Bearer {}", access_token))
+        .header("anthropic-version", anthropic::API_VERSION)
+        .header("anthropic-beta", anthropic::OAUTH_BETA)
+        .json(&body)
+        .send();
+    let response = match tokio::time::timeout(state.upstream_timeout, send).await {
+        Ok(Ok(response)) if response.status().is_success() => response,
+        Ok(Ok(response)) => {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            let error = MindLinkError::Network {
+                message: format!("Anthropic API returned status: {}", status),
+                url: Some(anthropic::MESSAGES_URL.to_string()),
+                source: Some(UpstreamStatus::new(status.as_u16(), &body).into()),
+            };
+            log_error!("ServerManager", error.clone());
+            state.metrics.record_upstream_error("anthropic");
+            return ApiError::from_error(&error).into_response();
+        },
+        Ok(Err(e)) => {
+            let error = network_error!("Anthropic API request failed", anthropic::MESSAGES_URL, e);
+            log_error!("ServerManager", error.clone());
+            state.metrics.record_upstream_error("anthropic");
+            return ApiError::from_error(&error).into_response();
+        },
+        Err(_) => {
+            state.metrics.record_upstream_error("anthropic");
+            return create_error_response(
+                StatusCode::GATEWAY_TIMEOUT,
+                "Anthropic did not answer in time",
+            );
+        },
+    };
+    request_log::mark(Phase::FirstByte);
+
+    if stream {
+        let prompt_tokens = estimate_tokens(&request.messages);
+        let usage_prompt_tokens = stream_usage_requested(&request).then_some(prompt_tokens);
+        let mut response = sse_response(spawn_anthropic_stream(
+            response,
+            request.model.clone(),
+            state.stream_idle_timeout,
+            usage_prompt_tokens,
+        ));
+        response.extensions_mut().insert(TokenUsage {
+            model: request.model.clone(),
+            total_tokens: u64::from(prompt_tokens),
+        });
+        return response;
+    }
+
+    let reply = match response.json::<serde_json::Value>().await {
+        Ok(reply) => reply,
+        Err(e) => {
+            let error = network_error!("Failed to parse Anthropic response", "", e);
+            log_error!("ServerManager", error.clone());
+            return ApiError::from_error(&error).into_response();
+        },
+    };
+    let content = anthropic::response_text(&reply);
+    let usage =
+        anthropic::response_usage(&reply).unwrap_or_else(|| usage_for(&request.messages, &content));
+    let stop_reason = reply.get("stop_reason").and_then(|reason| reason.as_str());
+    let openai_response = ChatCompletionResponse {
+        id: format!("chatcmpl-{}", Uuid::new_v4()),
+        object: "chat.completion".to_string(),
+        created: chrono::Utc::now().timestamp() as u64,
+        model: request.model.clone(),
+        choices: vec![Choice {
+            index: 0,
+            message: Some(Message {
+                role: "assistant".to_string(),
+                content,
+                tool_calls: None,
+                tool_call_id: None,
+                name: None,
+                reasoning_content: None,
+            }),
+            delta: None,
+            finish_reason: Some(anthropic::finish_reason(stop_reason).to_string()),
+        }],
+        usage: Some(usage),
+    };
+
+    let usage = token_usage(&openai_response);
+    let mut response = Json(openai_response).into_response();
+    response.extensions_mut().insert(usage);
+    response
 }
 
-async fn make_chatgpt_request(
-    client: &Client,
-    request: &ChatGptRequest,
-    access_token: &str,
-) -> MindLinkResult<serde_json::Value> {
-    log_debug!("ServerManager", "Making request to ChatGPT backend");
+/// Relay the Messages API stream of `response` as chat completion chunks,
+/// ending with `[DONE]`. The stream ends once it goes quiet for
+/// `idle_timeout`. With `usage_prompt_tokens` set, a usage chunk is sent
+/// before `[DONE]`, with Anthropic's counts where the stream reports them.
+fn spawn_anthropic_stream(
+    response: reqwest::Response,
+    model: String,
+    idle_timeout: Duration,
+    usage_prompt_tokens: Option<u32>,
+) -> tokio::sync::mpsc::Receiver<Result<String, std::convert::Infallible>> {
+    let (tx, rx) = tokio::sync::mpsc::channel::<Result<String, std::convert::Infallible>>(100);
+    let request_id = format!("chatcmpl-{}", Uuid::new_v4());
 
-    let response = client
-        .post("https://chatgpt.com/backend-api/conversation")
-        .header("Authorization", format!("Bearer {}", access_token))
-        .header("Content-Type", "application/json")
-        .header("Accept", "application/json")
-        .json(request)
-        .send()
-        .await
-        .map_err(|e| network_error!("ChatGPT API request failed", "https://chatgpt.com", e))?;
+    // The stream outlives the request; it keeps logging with its request ID
+    tokio::spawn(with_correlation_id(current_correlation_id(), async move {
+        let mut stream = response.bytes_stream();
+        let mut buffer = String::new();
+        let mut finish_reason = "stop";
+        let mut text = String::new();
+        let mut usage = Usage {
+            prompt_tokens: usage_prompt_tokens.unwrap_or(0),
+            completion_tokens: 0,
+            total_tokens: 0,
+        };
 
-    if !response.status().is_success() {
-        return Err(MindLinkError::Network {
-            message: format!("ChatGPT API returned status: {}", response.status()),
-            url: Some("https://chatgpt.com/backend-api/conversation".to_string()),
-            source: None,
-        });
-    }
+        'stream: while let Ok(Some(Ok(chunk))) =
+            tokio::time::timeout(idle_timeout, stream.next()).await
+        {
+            buffer.push_str(&String::from_utf8_lossy(&chunk));
+            // Events can be split across chunks, so only complete lines are read
+            while let Some(end) = buffer.find('\n') {
+                let line: String = buffer.drain(..=end).collect();
+                let Some(event) = line
+                    .trim_end()
+                    .strip_prefix("data: ")
+                    .and_then(|data| serde_json::from_str(data).ok())
+                else {
+                    continue;
+                };
+                anthropic::count_stream_usage(&event, &mut usage);
+                let Some(event) = anthropic::stream_event(&event) else {
+                    continue;
+                };
+                match event {
+                    anthropic::StreamEvent::Text(delta) => {
+                        if !send_content(&tx, &request_id, &model, &delta).await {
+                            return;
+                        }
+                        text.push_str(&delta);
+                    },
+                    anthropic::StreamEvent::Finished(reason) => finish_reason = reason,
+                    anthropic::StreamEvent::Error(message) => {
+                        log_warn!(
+                            "ServerManager",
+                            &format!("Claude stream failed: {}", message)
+                        );
+                        break 'stream;
+                    },
+                }
+            }
+        }
 
-    let json_response = response
-        .json::<serde_json::Value>()
-        .await
-        .map_err(|e| network_error!("Failed to parse ChatGPT response", "", e))?;
+        let mut last = create_streaming_chunk(&request_id, &model, "", 0, true);
+        last["choices"][0]["finish_reason"] = serde_json::json!(finish_reason);
+        let _ = tx.send(Ok(format!("data: {}\n\n", last))).await;
+        if usage_prompt_tokens.is_some() {
+            if usage.completion_tokens == 0 {
+                usage.completion_tokens = estimate_text_tokens(&text);
+            }
+            usage.total_tokens = usage.prompt_tokens + usage.completion_tokens;
+            let created = chrono::Utc::now().timestamp();
+            let _ = tx
+                .send(Ok(usage_chunk(&request_id, &model, created, &usage)))
+                .await;
+        }
+        let _ = tx.send(Ok("data: [DONE]\n\n".to_string())).await;
+    }));
 
-    log_debug!("ServerManager", "ChatGPT request completed successfully");
+    rx
+}
 
-    Ok(json_response)
+/// Turn a non-success ChatGPT response into an error that keeps its status,
+/// so handlers can answer with the matching OpenAI error
+async fn upstream_error(response: reqwest::Response) -> MindLinkError {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    MindLinkError::Network {
+        message: format!("ChatGPT API returned status: {}", status),
+        url: Some("https://chatgpt.com/backend-api/conversation".to_string()),
+        source: Some(UpstreamStatus::new(status.as_u16(), &body).into()),
+    }
 }
 
+/// Stream one upstream response into `tx`. Progress is recorded as it goes so
+/// the caller still knows what was delivered when the stream fails midway.
+/// The response must start within `upstream_timeout`, and the stream fails
+/// once it goes quiet for `idle_timeout`.
+#[allow(clippy::too_many_arguments)]
 async fn make_chatgpt_streaming_request(
     client: &Client,
     request: &ChatGptRequest,
     access_token: &str,
+    (upstream_timeout, idle_timeout): (Duration, Duration),
     request_id: &str,
     model: &str,
-    tx: tokio::sync::mpsc::Sender<Result<String, std::convert::Infallible>>,
+    tx: &tokio::sync::mpsc::Sender<Result<String, std::convert::Infallible>>,
+    stitcher: &mut ContinuationStitcher,
+    progress: &mut StreamProgress,
 ) -> MindLinkResult<()> {
     log_debug!(
         "ServerManager",
         "Making streaming request to ChatGPT backend"
     );
 
-    let response = client
+    request_log::mark(Phase::UpstreamSent);
+    let send = client
         .post("https://chatgpt.com/backend-api/conversation")
         .header("Authorization", format!("Bearer {}", access_token))
         .header("Content-Type", "application/json")
         .header("Accept", "text/event-stream")
         .json(request)
-        .send()
+        .send();
+    let response = tokio::time::timeout(upstream_timeout, send)
         .await
+        .map_err(|_| MindLinkError::Network {
+            message: format!(
+                "ChatGPT did not start streaming within {}s",
+                upstream_timeout.as_secs()
+            ),
+            url: Some("https://chatgpt.com".to_string()),
+            source: None,
+        })?
         .map_err(|e| {
             network_error!(
                 "ChatGPT streaming API request failed",
@@ -867,83 +4134,88 @@ async fn make_chatgpt_streaming_request(
                 e
             )
         })?;
+    request_log::mark(Phase::FirstByte);
 
     if !response.status().is_success() {
-        return Err(MindLinkError::Network {
-            message: format!("ChatGPT API returned status: {}", response.status()),
-            url: Some("https://chatgpt.com/backend-api/conversation".to_string()),
-            source: None,
-        });
+        return Err(upstream_error(response).await);
     }
 
     // Process the streaming response
     let mut stream = response.bytes_stream();
-    let mut chunk_index = 0;
-
-    while let Some(chunk_result) = stream.next().await {
-        match chunk_result {
-            Ok(chunk) => {
-                // Parse the chunk as text
-                if let Ok(text) = std::str::from_utf8(&chunk) {
-                    // Process each line in the chunk (SSE format)
-                    for line in text.lines() {
-                        if line.starts_with("data: ") {
-                            let data = &line[6..]; // Remove "data: " prefix
-                            if data == "[DONE]" {
-                                break;
-                            }
 
-                            // Try to parse as JSON and extract content
-                            if let Ok(json_data) = serde_json::from_str::<serde_json::Value>(data) {
-                                if let Some(content) = extract_streaming_content(&json_data) {
-                                    // Create OpenAI-compatible streaming chunk
-                                    let openai_chunk = create_streaming_chunk(
-                                        request_id,
-                                        model,
-                                        &content,
-                                        chunk_index,
-                                        false,
-                                    );
-                                    let chunk_line = format!(
-                                        "data: {}\n\n",
-                                        serde_json::to_string(&openai_chunk).unwrap_or_default()
-                                    );
-
-                                    if tx.send(Ok(chunk_line)).await.is_err() {
-                                        log_debug!(
-                                            "ServerManager",
-                                            "Client disconnected during streaming"
-                                        );
-                                        return Ok(());
-                                    }
-
-                                    chunk_index += 1;
-                                }
-                            }
-                        }
+    loop {
+        let Ok(next) = tokio::time::timeout(idle_timeout, stream.next()).await else {
+            return Err(MindLinkError::Network {
+                message: format!("ChatGPT stream stalled for {}s", idle_timeout.as_secs()),
+                url: Some("streaming".to_string()),
+                source: None,
+            });
+        };
+        let Some(chunk_result) = next else {
+            break;
+        };
+        let chunk = chunk_result.map_err(|e| MindLinkError::Network {
+            message: format!("Error reading stream chunk: {}", e),
+            url: Some("streaming".to_string()),
+            source: Some(e.into()),
+        })?;
+
+        // Parse the chunk as text
+        let Ok(text) = std::str::from_utf8(&chunk) else {
+            continue;
+        };
+
+        // Process each line in the chunk (SSE format)
+        for line in text.lines() {
+            let Some(data) = line.strip_prefix("data: ") else {
+                continue;
+            };
+            if data == "[DONE]" {
+                progress.finished = true;
+                return Ok(());
+            }
+
+            // Try to parse as JSON and extract content
+            let Ok(json_data) = serde_json::from_str::<serde_json::Value>(data) else {
+                continue;
+            };
+            if let Some(id) = json_data.get("conversation_id").and_then(|v| v.as_str()) {
+                progress.conversation_id = Some(id.to_string());
+            }
+            if let Some(id) = json_data
+                .get("message")
+                .and_then(|m| m.get("id"))
+                .and_then(|v| v.as_str())
+            {
+                progress.message_id = Some(id.to_string());
+            }
+
+            if let Some(thoughts) = reasoning::reasoning_text(&json_data) {
+                if let Some(piece) = progress.reasoning.push(thoughts) {
+                    let chunk = reasoning::reasoning_chunk(request_id, model, &piece);
+                    if tx.send(Ok(chunk)).await.is_err() {
+                        progress.client_gone = true;
+                        return Ok(());
                     }
                 }
-            },
-            Err(e) => {
-                let error = MindLinkError::Network {
-                    message: format!("Error reading stream chunk: {}", e),
-                    url: Some("streaming".to_string()),
-                    source: Some(e.into()),
-                };
-                log_error!("ServerManager", &error);
-                break;
-            },
+                continue;
+            }
+
+            let Some(content) =
+                extract_streaming_content(&json_data).and_then(|content| stitcher.push(&content))
+            else {
+                continue;
+            };
+
+            // Create OpenAI-compatible streaming chunk
+            if !send_content(tx, request_id, model, &content).await {
+                progress.client_gone = true;
+                return Ok(());
+            }
+            progress.text.push_str(&content);
         }
     }
 
-    // Send final chunk with finish_reason
-    let final_chunk = create_streaming_chunk(request_id, model, "", chunk_index, true);
-    let final_line = format!(
-        "data: {}\n\n",
-        serde_json::to_string(&final_chunk).unwrap_or_default()
-    );
-    let _ = tx.send(Ok(final_line)).await;
-
     Ok(())
 }
 
@@ -953,6 +4225,7 @@ fn create_openai_response(
 ) -> ChatCompletionResponse {
     // Extract content from ChatGPT response (this is simplified)
     let content = extract_content_from_response(chatgpt_response).unwrap_or_default();
+    let usage = usage_for(&request.messages, &content);
 
     ChatCompletionResponse {
         id: format!("chatcmpl-{}", Uuid::new_v4()),
@@ -964,15 +4237,15 @@ fn create_openai_response(
             message: Some(Message {
                 role: "assistant".to_string(),
                 content,
+                tool_calls: None,
+                tool_call_id: None,
+                name: None,
+                reasoning_content: reasoning::reasoning_text(chatgpt_response),
             }),
             delta: None,
             finish_reason: Some("stop".to_string()),
         }],
-        usage: Some(Usage {
-            prompt_tokens: estimate_tokens(&request.messages),
-            completion_tokens: 100, // Simplified
-            total_tokens: estimate_tokens(&request.messages) + 100,
-        }),
+        usage: Some(usage),
     }
 }
 
@@ -1019,14 +4292,65 @@ fn extract_content_from_response(response: &serde_json::Value) -> Option<String>
         })
 }
 
+fn token_usage(response: &ChatCompletionResponse) -> TokenUsage {
+    TokenUsage {
+        model: response.model.clone(),
+        total_tokens: response
+            .usage
+            .as_ref()
+            .map_or(0, |usage| u64::from(usage.total_tokens)),
+    }
+}
+
 fn estimate_tokens(messages: &[Message]) -> u32 {
-    // Simple token estimation - in production, use a proper tokenizer
     messages
         .iter()
-        .map(|m| (m.content.len() as f32 / 4.0).ceil() as u32)
+        .map(|m| estimate_text_tokens(&m.content))
         .sum()
 }
 
+fn estimate_text_tokens(text: &str) -> u32 {
+    // Simple token estimation - in production, use a proper tokenizer
+    (text.len() as f32 / 4.0).ceil() as u32
+}
+
+fn usage_for(messages: &[Message], completion: &str) -> Usage {
+    let prompt_tokens = estimate_tokens(messages);
+    let completion_tokens = estimate_text_tokens(completion);
+    Usage {
+        prompt_tokens,
+        completion_tokens,
+        total_tokens: prompt_tokens + completion_tokens,
+    }
+}
+
+/// Whether a streaming client asked for a usage chunk with
+/// `stream_options: {"include_usage": true}`
+fn stream_usage_requested(request: &ChatCompletionRequest) -> bool {
+    request
+        .other
+        .get("stream_options")
+        .and_then(|options| options.get("include_usage"))
+        .and_then(|include| include.as_bool())
+        .unwrap_or(false)
+}
+
+/// The chunk sent after the finish chunk when usage was requested: no
+/// choices, only the token counts of the whole response
+fn usage_chunk(id: &str, model: &str, created: i64, usage: &Usage) -> String {
+    format!(
+        "data: {}\n\n",
+        serde_json::json!({
+            "id": id,
+            "object": "chat.completion.chunk",
+            "created": created,
+            "model": model,
+            "choices": [],
+            "usage": usage,
+        })
+    )
+}
+
 fn extract_streaming_content(response: &serde_json::Value) -> Option<String> {
     // Extract content from ChatGPT streaming response
     // Try different possible structures based on ChatGPT's actual response format
@@ -1100,16 +4424,381 @@ fn create_streaming_chunk(
     }
 }
 
-fn create_error_response(status: StatusCode, message: &str) -> Response<Body> {
-    let error_json = serde_json::json!({
-        "error": {
-            "message": message,
-            "type": "invalid_request_error",
-            "code": status.as_u16()
+// ===== Batch API =====
+
+fn batch_store(state: &AppState) -> Result<&Arc<BatchStore>, Response<Body>> {
+    state
+        .batches
+        .as_ref()
+        .ok_or_else(|| create_error_response(StatusCode::NOT_FOUND, "The batch API is disabled"))
+}
+
+/// Run a batch in the background until it finishes
+fn spawn_batch(state: AppState, id: String) {
+    let Some(store) = state.batches.clone() else {
+        return;
+    };
+    // Logs with the ID of the request that created the batch, if any
+    tokio::spawn(with_correlation_id(current_correlation_id(), async move {
+        let app_id = store.app_id(&id).await;
+        let config = (*state.batch_config).clone();
+        log_info!("ServerManager", &format!("Running batch {}", id));
+
+        let result = batches::run_batch(store, id.clone(), config, |body| {
+            execute_as_app(state.clone(), app_id.clone(), body)
+        })
+        .await;
+        match result {
+            Ok(()) => log_info!("ServerManager", &format!("Batch {} finished", id)),
+            Err(e) => log_error!("ServerManager", e),
         }
-    });
+    }));
+}
+
+/// Run a batch request or job through the regular chat completion handler, as
+/// the app that submitted it. Returns the response status and JSON body.
+async fn execute_as_app(
+    state: AppState,
+    app_id: Option<String>,
+    body: serde_json::Value,
+) -> (u16, serde_json::Value) {
+    let mut headers = HeaderMap::new();
+    if let Some(app_id) = app_id {
+        let apps = state.authorized_apps.read().await;
+        let api_key = apps
+            .iter()
+            .find(|app| app.id == app_id)
+            .and_then(|app| HeaderValue::from_str(&format!("Bearer {}", app.api_key)).ok());
+        match api_key {
+            Some(api_key) => {
+                headers.insert(header::AUTHORIZATION, api_key);
+            },
+            None => {
+                let error = ApiError::new(
+                    StatusCode::FORBIDDEN,
+                    "The app that submitted this request is no longer authorized",
+                );
+                return (error.status.as_u16(), error.body());
+            },
+        }
+    }
+
+    let response = match serde_json::from_value::<ChatCompletionRequest>(body) {
+        Ok(mut request) => {
+            request.stream = Some(false);
+            chat_completions(State(state), headers, Json(request))
+                .await
+                .into_response()
+        },
+        Err(e) => create_error_response(StatusCode::BAD_REQUEST, &e.to_string()),
+    };
+
+    let status = response.status().as_u16();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or(serde_json::Value::Null);
+    (status, body)
+}
+
+/// `POST /v1/files`: upload a batch input file (multipart `file` and `purpose`)
+async fn upload_file(State(state): State<AppState>, mut multipart: Multipart) -> Response<Body> {
+    let store = match batch_store(&state) {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+
+    let mut purpose = None;
+    let mut upload = None;
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return create_error_response(StatusCode::BAD_REQUEST, &e.body_text()),
+        };
+        let name = field.name().unwrap_or_default().to_string();
+        match name.as_str() {
+            "purpose" => purpose = field.text().await.ok(),
+            "file" => {
+                let filename = field.file_name().unwrap_or("input.jsonl").to_string();
+                match field.bytes().await {
+                    Ok(bytes) => upload = Some((filename, bytes)),
+                    Err(e) => {
+                        return create_error_response(StatusCode::BAD_REQUEST, &e.body_text())
+                    },
+                }
+            },
+            _ => {},
+        }
+    }
+
+    if purpose.as_deref() != Some(batches::BATCH_PURPOSE) {
+        return create_error_response(
+            StatusCode::BAD_REQUEST,
+            "Only files with purpose 'batch' are supported",
+        );
+    }
+    let Some((filename, bytes)) = upload else {
+        return create_error_response(StatusCode::BAD_REQUEST, "Missing 'file' field");
+    };
+
+    match store
+        .create_file(&filename, batches::BATCH_PURPOSE, &bytes)
+        .await
+    {
+        Ok(file) => Json(file).into_response(),
+        Err(e) => {
+            log_error!("ServerManager", e.clone());
+            create_error_response(StatusCode::INTERNAL_SERVER_ERROR, &e.user_message())
+        },
+    }
+}
+
+async fn get_file(State(state): State<AppState>, Path(id): Path<String>) -> Response<Body> {
+    let store = match batch_store(&state) {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+    match store.file(&id).await {
+        Some(file) => Json(file).into_response(),
+        None => create_error_response(StatusCode::NOT_FOUND, &format!("No file with ID {}", id)),
+    }
+}
+
+/// `GET /v1/files/{id}/content`: the raw JSONL of an input, output or error file
+async fn get_file_content(State(state): State<AppState>, Path(id): Path<String>) -> Response<Body> {
+    let store = match batch_store(&state) {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+    match store.file_content(&id).await {
+        Ok(Some(content)) => (
+            [(header::CONTENT_TYPE, "application/octet-stream")],
+            content,
+        )
+            .into_response(),
+        Ok(None) => {
+            create_error_response(StatusCode::NOT_FOUND, &format!("No file with ID {}", id))
+        },
+        Err(e) => {
+            log_error!("ServerManager", e.clone());
+            create_error_response(StatusCode::INTERNAL_SERVER_ERROR, &e.user_message())
+        },
+    }
+}
+
+async fn create_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CreateBatchRequest>,
+) -> Response<Body> {
+    let store = match batch_store(&state) {
+        Ok(store) => store.clone(),
+        Err(response) => return response,
+    };
+
+    if request.endpoint != batches::CHAT_COMPLETIONS_ENDPOINT {
+        return create_error_response(
+            StatusCode::BAD_REQUEST,
+            &format!(
+                "Only the {} endpoint is supported",
+                batches::CHAT_COMPLETIONS_ENDPOINT
+            ),
+        );
+    }
+    if request.completion_window != batches::COMPLETION_WINDOW {
+        return create_error_response(
+            StatusCode::BAD_REQUEST,
+            &format!("completion_window must be {}", batches::COMPLETION_WINDOW),
+        );
+    }
+    match store.file(&request.input_file_id).await {
+        Some(file) if file.purpose == batches::BATCH_PURPOSE => {},
+        Some(_) => {
+            return create_error_response(
+                StatusCode::BAD_REQUEST,
+                "The input file must have purpose 'batch'",
+            )
+        },
+        None => {
+            return create_error_response(
+                StatusCode::NOT_FOUND,
+                &format!("No file with ID {}", request.input_file_id),
+            )
+        },
+    }
+
+    // Remember the creating app, so its model policy applies to every request
+    let app_id = {
+        let apps = state.authorized_apps.read().await;
+        authorized_apps::bearer_token(&headers)
+            .and_then(|api_key| authorized_apps::find_app(&apps, api_key))
+            .map(|app| app.id.clone())
+    };
+
+    match store.create_batch(request, app_id).await {
+        Ok(batch) => {
+            if !batch.status.is_terminal() {
+                spawn_batch(state.clone(), batch.id.clone());
+            }
+            Json(batch).into_response()
+        },
+        Err(e) => {
+            log_error!("ServerManager", e.clone());
+            create_error_response(StatusCode::INTERNAL_SERVER_ERROR, &e.user_message())
+        },
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListBatchesQuery {
+    after: Option<String>,
+    limit: Option<usize>,
+}
+
+async fn list_batches(
+    State(state): State<AppState>,
+    Query(query): Query<ListBatchesQuery>,
+) -> Response<Body> {
+    let store = match batch_store(&state) {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
+    // One extra to learn whether there are more
+    let mut data = store.list(query.after.as_deref(), limit + 1).await;
+    let has_more = data.len() > limit;
+    data.truncate(limit);
+
+    Json(serde_json::json!({
+        "object": "list",
+        "first_id": data.first().map(|batch| batch.id.clone()),
+        "last_id": data.last().map(|batch| batch.id.clone()),
+        "has_more": has_more,
+        "data": data,
+    }))
+    .into_response()
+}
+
+async fn get_batch(State(state): State<AppState>, Path(id): Path<String>) -> Response<Body> {
+    let store = match batch_store(&state) {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+    match store.batch(&id).await {
+        Some(batch) => Json(batch).into_response(),
+        None => create_error_response(StatusCode::NOT_FOUND, &format!("No batch with ID {}", id)),
+    }
+}
+
+async fn cancel_batch(State(state): State<AppState>, Path(id): Path<String>) -> Response<Body> {
+    let store = match batch_store(&state) {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+    match store.cancel(&id).await {
+        Ok(Some(batch)) => Json(batch).into_response(),
+        Ok(None) => {
+            create_error_response(StatusCode::NOT_FOUND, &format!("No batch with ID {}", id))
+        },
+        Err(e) => {
+            log_error!("ServerManager", e.clone());
+            create_error_response(StatusCode::INTERNAL_SERVER_ERROR, &e.user_message())
+        },
+    }
+}
+
+// ===== Job API =====
+
+fn job_store(state: &AppState) -> Result<&Arc<JobStore>, Response<Body>> {
+    state
+        .jobs
+        .as_ref()
+        .ok_or_else(|| create_error_response(StatusCode::NOT_FOUND, "The job API is disabled"))
+}
+
+fn job_not_found(id: &str) -> Response<Body> {
+    create_error_response(StatusCode::NOT_FOUND, &format!("No job with ID {}", id))
+}
+
+/// `POST /v1/jobs`: queue a chat completion and return the job right away
+async fn create_job(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(body): Json<serde_json::Value>,
+) -> Response<Body> {
+    let store = match job_store(&state) {
+        Ok(store) => store.clone(),
+        Err(response) => return response,
+    };
+
+    let model = match serde_json::from_value::<ChatCompletionRequest>(body.clone()) {
+        Ok(request) => request.model,
+        Err(e) => return create_error_response(StatusCode::BAD_REQUEST, &e.to_string()),
+    };
+
+    // The job runs as the submitting app, so its model policy still applies
+    let app_id = {
+        let apps = state.authorized_apps.read().await;
+        authorized_apps::bearer_token(&headers)
+            .and_then(|api_key| authorized_apps::find_app(&apps, api_key))
+            .map(|app| app.id.clone())
+    };
 
-    (status, Json(error_json)).into_response()
+    let job = store.submit(&model, execute_as_app(state.clone(), app_id, body));
+    log_info!("ServerManager", &format!("Queued job {}", job.id));
+    (StatusCode::ACCEPTED, Json(job)).into_response()
+}
+
+async fn get_job(State(state): State<AppState>, Path(id): Path<String>) -> Response<Body> {
+    let store = match job_store(&state) {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+    match store.job(&id) {
+        Some(job) => Json(job).into_response(),
+        None => job_not_found(&id),
+    }
+}
+
+/// `GET /v1/jobs/{id}/events`: the job's status changes as server-sent
+/// events, ending with a `done` event that carries the result
+async fn job_events(State(state): State<AppState>, Path(id): Path<String>) -> Response<Body> {
+    let store = match job_store(&state) {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+    let Some(updates) = store.subscribe(&id) else {
+        return job_not_found(&id);
+    };
+
+    let stream = tokio_stream::wrappers::ReceiverStream::new(jobs::events(updates));
+    Response::builder()
+        .status(StatusCode::OK)
+        .header("Content-Type", "text/event-stream")
+        .header("Cache-Control", "no-cache")
+        .header("Connection", "keep-alive")
+        .body(Body::from_stream(stream))
+        .unwrap_or_else(|_| {
+            create_error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to follow job")
+        })
+}
+
+async fn cancel_job(State(state): State<AppState>, Path(id): Path<String>) -> Response<Body> {
+    let store = match job_store(&state) {
+        Ok(store) => store,
+        Err(response) => return response,
+    };
+    match store.cancel(&id) {
+        Some(job) => Json(job).into_response(),
+        None => job_not_found(&id),
+    }
+}
+
+pub(crate) fn create_error_response(status: StatusCode, message: &str) -> Response<Body> {
+    ApiError::new(status, message).into_response()
 }
 
 /// Test handler to debug routing
@@ -1123,9 +4812,12 @@ async fn serve_static_file(request: Request<Body>) -> impl IntoResponse {
     let path = request.uri().path();
     let file_name = path.trim_start_matches('/');
     let file_path = format!("../dist/{}", file_name);
-    
-    log_info!("ServerManager", &format!("Serving static file: {} -> {}", path, file_path));
-    
+
+    log_info!(
+        "ServerManager",
+        &format!("Serving static file: {} -> {}", path, file_path)
+    );
+
     match tokio::fs::read_to_string(&file_path).await {
         Ok(content) => {
             let content_type = match file_name {
@@ -1134,18 +4826,13 @@ async fn serve_static_file(request: Request<Body>) -> impl IntoResponse {
                 name if name.ends_with(".html") => "text/html",
                 _ => "text/plain",
             };
-            
-            (
-                StatusCode::OK,
-                [("content-type", content_type)],
-                content
-            )
-        }
+
+            (StatusCode::OK, [("content-type", content_type)], content)
+        },
         Err(_) => (
             StatusCode::NOT_FOUND,
             [("content-type", "text/plain")],
-            "File not found".to_string()
+            "File not found".to_string(),
         ),
     }
 }
-
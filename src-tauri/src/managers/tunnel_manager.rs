@@ -1,31 +1,367 @@
 // Tunnel Manager - Real Cloudflare tunnel implementation
+//
+// Quick tunnels get a random trycloudflare.com URL on every start, which
+// cloudflared announces in its output. Named tunnels serve a hostname of the
+// user's own domain: the tunnel is created once in their Cloudflare account,
+// its credentials and a cloudflared config are kept in `~/.mindlink/tunnels`,
+// and a DNS record routes the hostname to it. The tunnel ID is saved in the
+// config so later starts reuse the tunnel. Where Cloudflare is blocked, ngrok
+// can serve the tunnel instead, and where only quick tunnels fail, a
+// localtunnel server takes over. Subscribers hear of every change of the public
+// URL, so clients can follow a quick tunnel that came back under a new one.
+// The tunnel process runs under the process monitor, and one that crashes is
+// started again as often as the monitor's restart limit allows. Other local
+// services, like the Bifrost dashboard, can be served by tunnels of their own
+// next to the API's. A running process does not mean clients can get through,
+// so the health endpoint is also requested through the public URL, and what
+// stopped the request (DNS, TLS, routing) is kept for the health report. The
+// PID of the tunnel process is kept in a file while it runs, so a process
+// left behind by a crash can be stopped on the next start. A schedule may keep
+// the tunnel up during some windows of the week only; it is applied here.
 use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use regex::Regex;
-use std::path::PathBuf;
+use serde::Serialize;
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::io::{AsyncBufReadExt, BufReader};
+use std::time::{Duration, Instant};
 use tokio::process::{Child, Command};
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, mpsc, RwLock};
+use tokio::task::JoinHandle;
 use tokio::time::timeout;
+use ts_rs::TS;
 
 use super::binary_manager::BinaryManager;
+use super::config_manager::{
+    CloudflaredOptions, FallbackTunnelConfig, NamedTunnelConfig, NgrokConfig, ServiceTunnelConfig,
+    TunnelConfig, TunnelService,
+};
+use crate::localtunnel;
+use crate::process_monitor::{init_process_monitor, MonitorConfig};
+use crate::tunnel_pids;
+use crate::tunnel_schedule::{Schedule, ScheduleStatus};
+
+/// How long a cloudflared management command (create, route, ...) may take
+const CLOUDFLARED_COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long a named tunnel may take to register its first connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// URL of the quick tunnel, if `line` of cloudflared's output announces it.
+/// Requests to the trycloudflare.com API itself, which show up in its
+/// errors, are not tunnel URLs.
+pub fn parse_quick_tunnel_url(line: &str) -> Option<String> {
+    let url_regex = Regex::new(r"https://[a-zA-Z0-9\-]+\.trycloudflare\.com").ok()?;
+    let url = url_regex
+        .find_iter(line)
+        .map(|found| found.as_str())
+        .find(|url| *url != "https://api.trycloudflare.com")?;
+    Some(url.to_string())
+}
+
+/// What a line of ngrok's output says about the tunnel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NgrokEvent {
+    /// The tunnel is up at this URL
+    Started(String),
+    /// ngrok reported an error
+    Failed(String),
+}
+
+/// Parse a line ngrok writes with `--log stdout --log-format json`. Errors
+/// that stop ngrok before it logs anything are printed as plain text.
+pub fn parse_ngrok_log(line: &str) -> Option<NgrokEvent> {
+    if let Some(error) = line.strip_prefix("ERROR:") {
+        return Some(NgrokEvent::Failed(error.trim().to_string()));
+    }
+
+    let entry: serde_json::Value = serde_json::from_str(line).ok()?;
+    let field = |name: &str| entry.get(name).and_then(serde_json::Value::as_str);
+    if field("msg") == Some("started tunnel") {
+        return field("url").map(|url| NgrokEvent::Started(url.to_string()));
+    }
+    match (field("lvl"), field("err")) {
+        (Some("eror" | "crit"), Some(error)) => Some(NgrokEvent::Failed(error.to_string())),
+        _ => None,
+    }
+}
+
+/// Route the output of a tunnel process through the process monitor, which
+/// logs it under `process_id`, and hand its lines back for parsing
+async fn capture_output(process_id: &str, child: &mut Child) -> mpsc::UnboundedReceiver<String> {
+    let monitor = init_process_monitor();
+    let (sender, lines) = mpsc::unbounded_channel();
+    if let Some(stdout) = child.stdout.take() {
+        monitor
+            .capture_output(process_id, "stdout", stdout, sender.clone())
+            .await;
+    }
+    if let Some(stderr) = child.stderr.take() {
+        monitor
+            .capture_output(process_id, "stderr", stderr, sender)
+            .await;
+    }
+    lines
+}
+
+/// How the process monitor watches a tunnel process and how often it may be
+/// restarted, the same as for Bifrost
+fn monitor_config() -> MonitorConfig {
+    MonitorConfig {
+        capture_stdout: true,
+        capture_stderr: true,
+        max_restart_attempts: 3,
+        restart_delay: Duration::from_secs(5),
+        output_buffer_size: 1024 * 1024,
+        health_check_interval: Duration::from_secs(30),
+        process_timeout: Some(Duration::from_secs(300)),
+    }
+}
+
+/// What stopped a request through the public URL
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "stage", content = "detail", rename_all = "snake_case")]
+pub enum ReachFailure {
+    /// The hostname did not resolve
+    Dns(String),
+    /// The TLS handshake failed, e.g. over the certificate
+    Tls(String),
+    /// No connection or no answer in time
+    Connect(String),
+    /// The edge answered with this status instead of MindLink's health
+    /// endpoint, so it does not route the hostname to the tunnel
+    Routing(u16),
+}
+
+impl ReachFailure {
+    /// Tell from the causes of a failed request where it failed
+    pub fn from_request_error(error: &reqwest::Error) -> Self {
+        let mut causes = vec![error.to_string()];
+        let mut source = std::error::Error::source(error);
+        while let Some(cause) = source {
+            causes.push(cause.to_string());
+            source = cause.source();
+        }
+        let detail = causes.last().cloned().unwrap_or_default();
+
+        let chain = causes.join(": ").to_lowercase();
+        if chain.contains("dns error") || chain.contains("failed to lookup address") {
+            ReachFailure::Dns(detail)
+        } else if ["certificate", "tls", "ssl", "handshake"]
+            .iter()
+            .any(|word| chain.contains(word))
+        {
+            ReachFailure::Tls(detail)
+        } else {
+            ReachFailure::Connect(detail)
+        }
+    }
+}
+
+impl std::fmt::Display for ReachFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReachFailure::Dns(detail) => write!(f, "DNS lookup failed: {}", detail),
+            ReachFailure::Tls(detail) => write!(f, "TLS failed: {}", detail),
+            ReachFailure::Connect(detail) => write!(f, "Connection failed: {}", detail),
+            ReachFailure::Routing(status) => write!(f, "Not routed to MindLink (HTTP {})", status),
+        }
+    }
+}
+
+/// Outcome of a request through the public URL
+#[derive(Debug, Clone, Serialize)]
+pub struct PublicCheck {
+    pub url: String,
+    pub checked_at: DateTime<Utc>,
+    pub round_trip_ms: Option<f64>,
+    pub failure: Option<ReachFailure>,
+}
+
+impl PublicCheck {
+    pub fn new(url: &str, result: &std::result::Result<Duration, ReachFailure>) -> Self {
+        Self {
+            url: url.to_string(),
+            checked_at: Utc::now(),
+            round_trip_ms: result.as_ref().ok().map(|d| d.as_secs_f64() * 1000.0),
+            failure: result.as_ref().err().cloned(),
+        }
+    }
+
+    pub fn reachable(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Time a request to the health endpoint through the tunnel's public `url`,
+/// sending `headers` along to get past Cloudflare Access
+pub async fn probe_round_trip(
+    client: &reqwest::Client,
+    url: &str,
+    headers: &[(&str, &str)],
+) -> std::result::Result<Duration, ReachFailure> {
+    let started = Instant::now();
+    let mut request = client.get(format!("{}/health", url.trim_end_matches('/')));
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| ReachFailure::from_request_error(&e))?;
+    if !response.status().is_success() {
+        return Err(ReachFailure::Routing(response.status().as_u16()));
+    }
+    Ok(started.elapsed())
+}
+
+/// Directory of the credentials and cloudflared configs of named tunnels
+pub fn tunnels_dir() -> Result<PathBuf> {
+    dirs::home_dir()
+        .map(|home| home.join(".mindlink").join("tunnels"))
+        .ok_or_else(|| anyhow!("Cannot determine home directory"))
+}
+
+/// ID of the tunnel `cloudflared tunnel create` reports it created
+pub fn parse_created_tunnel_id(output: &str) -> Option<String> {
+    let id_regex = Regex::new(r"with id ([0-9a-fA-F-]{36})").ok()?;
+    let captures = id_regex.captures(output)?;
+    Some(captures[1].to_string())
+}
+
+/// ID of the tunnel called `name` in the JSON of `cloudflared tunnel list`
+pub fn parse_listed_tunnel_id(output: &str, name: &str) -> Option<String> {
+    let tunnels: Vec<serde_json::Value> = serde_json::from_str(output).ok()?;
+    tunnels
+        .iter()
+        .find(|tunnel| tunnel.get("name").and_then(|n| n.as_str()) == Some(name))
+        .and_then(|tunnel| tunnel.get("id")?.as_str())
+        .map(str::to_string)
+}
+
+/// cloudflared config routing `hostname` through the tunnel to MindLink's
+/// server on `local_port`, and answering everything else with 404
+pub fn named_tunnel_config(
+    tunnel_id: &str,
+    credentials_file: &Path,
+    hostname: &str,
+    local_port: u16,
+) -> String {
+    format!(
+        "tunnel: {}\ncredentials-file: {}\ningress:\n  - hostname: {}\n    service: http://localhost:{}\n  - service: http_status:404\n",
+        tunnel_id,
+        credentials_file.display(),
+        hostname,
+        local_port
+    )
+}
+
+/// Flags of `cloudflared tunnel` for the set `options`
+pub fn cloudflared_args(options: &CloudflaredOptions) -> Vec<String> {
+    let flags = [
+        ("--region", &options.region),
+        ("--edge-ip-version", &options.edge_ip_version),
+        ("--protocol", &options.protocol),
+    ];
+    let mut args = Vec::new();
+    for (flag, value) in flags {
+        if let Some(value) = value {
+            args.push(flag.to_string());
+            args.push(value.clone());
+        }
+    }
+    args
+}
+
+/// The public URL changed: a tunnel came up, moved or closed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+pub struct TunnelUrlChanged {
+    /// `None` once the tunnel is closed
+    pub url: Option<String>,
+    pub previous_url: Option<String>,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// Set the public URL to `url`, telling subscribers when that changed it
+async fn publish_url(
+    current_url: &RwLock<Option<String>>,
+    url_changes: &broadcast::Sender<TunnelUrlChanged>,
+    url: Option<String>,
+) {
+    let mut current_url = current_url.write().await;
+    if *current_url == url {
+        return;
+    }
+    let previous_url = std::mem::replace(&mut *current_url, url.clone());
+    // Nobody may be subscribed
+    let _ = url_changes.send(TunnelUrlChanged {
+        url,
+        previous_url,
+        changed_at: Utc::now(),
+    });
+}
 
 #[derive(Debug, Clone)]
 pub enum TunnelType {
     Quick,
     Named(String),
+    Ngrok,
+}
+
+/// What applying the schedule did to the tunnel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleChange {
+    /// A window began; the tunnel is up at this URL
+    Opened(String),
+    /// The window ended
+    Closed,
+}
+
+/// A tunnel and where it is reachable
+#[derive(Debug, Clone, Serialize)]
+pub struct ServiceTunnel {
+    pub service: TunnelService,
+    pub local_port: u16,
+    pub url: Option<String>,
+    pub connected: bool,
 }
 
 #[derive(Debug)]
 pub struct TunnelManager {
-    process: Arc<RwLock<Option<Child>>>,
     current_url: Arc<RwLock<Option<String>>>,
+    /// The public URL while cloudflared serves it, rather than ngrok or the
+    /// fallback tunnel
+    cloudflared_url: Arc<RwLock<Option<String>>>,
+    /// Latest request through the public URL
+    public_check: Arc<RwLock<Option<PublicCheck>>>,
+    url_changes: broadcast::Sender<TunnelUrlChanged>,
     tunnel_type: TunnelType,
     local_port: u16,
     is_connected: Arc<RwLock<bool>>,
     binary_manager: BinaryManager,
     cloudflared_path: Arc<RwLock<Option<PathBuf>>>,
+    /// Hostname and ID of the named tunnel
+    named: NamedTunnelConfig,
+    /// Authtoken and domain of the ngrok tunnel
+    ngrok: NgrokConfig,
+    /// Region and protocol cloudflared connects with
+    cloudflared: CloudflaredOptions,
+    /// localtunnel server taking over when no quick tunnel starts
+    fallback: FallbackTunnelConfig,
+    /// Relays of the fallback tunnel, while it serves instead of cloudflared
+    fallback_relays: Option<JoinHandle<()>>,
+    /// The service this tunnel serves
+    service: TunnelService,
+    /// Tunnels of the other services and their configuration
+    services: BTreeMap<TunnelService, TunnelManager>,
+    service_configs: Vec<ServiceTunnelConfig>,
+    /// Windows the tunnel is up in, when it is scheduled
+    schedule: Option<Schedule>,
+    /// Whether the tunnel was last opened (or kept closed) for a window
+    schedule_applied: Option<bool>,
 }
 
 impl TunnelManager {
@@ -33,13 +369,25 @@ impl TunnelManager {
         let binary_manager = BinaryManager::new().await?;
 
         Ok(Self {
-            process: Arc::new(RwLock::new(None)),
             current_url: Arc::new(RwLock::new(None)),
+            cloudflared_url: Arc::new(RwLock::new(None)),
+            public_check: Arc::new(RwLock::new(None)),
+            url_changes: broadcast::channel(16).0,
             tunnel_type: TunnelType::Quick,
             local_port: 3001,
             is_connected: Arc::new(RwLock::new(false)),
             binary_manager,
             cloudflared_path: Arc::new(RwLock::new(None)),
+            named: NamedTunnelConfig::default(),
+            ngrok: NgrokConfig::default(),
+            cloudflared: CloudflaredOptions::default(),
+            fallback: FallbackTunnelConfig::default(),
+            fallback_relays: None,
+            service: TunnelService::Api,
+            services: BTreeMap::new(),
+            service_configs: Vec::new(),
+            schedule: None,
+            schedule_applied: None,
         })
     }
 
@@ -64,15 +412,40 @@ impl TunnelManager {
             }
         }
 
-        println!("Creating Cloudflare tunnel...");
-
         let tunnel_type = self.tunnel_type.clone();
         match tunnel_type {
-            TunnelType::Quick => self.create_quick_tunnel().await,
+            TunnelType::Quick => match self.create_quick_tunnel().await {
+                Err(e) if self.fallback.enabled => {
+                    eprintln!("Quick tunnel failed, falling back to localtunnel: {}", e);
+                    self.create_fallback_tunnel().await.map_err(|fallback| {
+                        anyhow!("{}; the fallback tunnel failed too: {}", e, fallback)
+                    })
+                },
+                result => result,
+            },
             TunnelType::Named(name) => self.create_named_tunnel(&name).await,
+            TunnelType::Ngrok => self.create_ngrok_tunnel().await,
         }
     }
 
+    /// Serve the local port through the configured localtunnel server
+    async fn create_fallback_tunnel(&mut self) -> Result<String> {
+        // cloudflared is not restarted while the fallback serves
+        init_process_monitor()
+            .unregister_process(&self.process_id())
+            .await?;
+        self.forget_pid().await;
+
+        let server = self.fallback.server.clone();
+        let lease = localtunnel::request_lease(&localtunnel::lease_client(), &server).await?;
+        self.fallback_relays = Some(localtunnel::serve(&server, &lease, self.local_port)?);
+        self.set_url(Some(lease.url.clone())).await;
+        *self.is_connected.write().await = true;
+
+        println!("Fallback tunnel created: {}", lease.url);
+        Ok(lease.url)
+    }
+
     async fn create_quick_tunnel(&mut self) -> Result<String> {
         println!("Creating Cloudflare quick tunnel...");
 
@@ -87,181 +460,562 @@ impl TunnelManager {
                 &format!("http://localhost:{}", self.local_port),
                 "--no-autoupdate",
             ])
+            .args(cloudflared_args(&self.cloudflared))
             .stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped())
             .kill_on_drop(true)
             .spawn()
             .map_err(|e| anyhow!("Failed to spawn cloudflared process: {}", e))?;
 
-        // Parse tunnel URL from stdout
-        let stdout = child
-            .stdout
-            .take()
-            .ok_or_else(|| anyhow!("Failed to capture cloudflared stdout"))?;
+        let mut lines = capture_output(&self.process_id(), &mut child).await;
+        let tunnel_url = Self::wait_for_url(&mut lines).await?;
 
-        let stderr = child
-            .stderr
-            .take()
-            .ok_or_else(|| anyhow!("Failed to capture cloudflared stderr"))?;
-
-        let tunnel_url = self.parse_tunnel_output(stdout, stderr).await?;
-
-        // Store the process and update state
-        *self.process.write().await = Some(child);
-        *self.current_url.write().await = Some(tunnel_url.clone());
+        self.monitor_process(child).await?;
+        self.set_url(Some(tunnel_url.clone())).await;
         *self.is_connected.write().await = true;
+        Self::follow_quick_tunnel(
+            lines,
+            self.current_url.clone(),
+            self.cloudflared_url.clone(),
+            self.url_changes.clone(),
+        );
 
         println!("Quick tunnel created successfully: {}", tunnel_url);
         Ok(tunnel_url)
     }
 
-    /// Parse cloudflared output to extract tunnel URL
-    async fn parse_tunnel_output(
-        &self,
-        stdout: tokio::process::ChildStdout,
-        stderr: tokio::process::ChildStderr,
-    ) -> Result<String> {
-        let url_regex = Regex::new(r"https://[a-zA-Z0-9\-]+\.trycloudflare\.com")
-            .map_err(|e| anyhow!("Failed to compile regex: {}", e))?;
-
-        let stdout_reader = BufReader::new(stdout);
-        let stderr_reader = BufReader::new(stderr);
-
-        let mut stdout_lines = stdout_reader.lines();
-        let mut stderr_lines = stderr_reader.lines();
-
-        // Use timeout to prevent hanging indefinitely
-        let parse_result = timeout(Duration::from_secs(30), async {
-            loop {
-                tokio::select! {
-                    stdout_line = stdout_lines.next_line() => {
-                        if let Ok(Some(line)) = stdout_line {
-                            println!("cloudflared stdout: {}", line);
-
-                            // Look for tunnel URL in stdout
-                            if let Some(captures) = url_regex.find(&line) {
-                                return Ok(captures.as_str().to_string());
-                            }
-
-                            // Check for connection success indicators
-                            if line.contains("Connection") && line.contains("registered") {
-                                println!("Tunnel connection registered");
-                            }
-                        }
-                    }
-                    stderr_line = stderr_lines.next_line() => {
-                        if let Ok(Some(line)) = stderr_line {
-                            println!("cloudflared stderr: {}", line);
-
-                            // Look for tunnel URL in stderr (cloudflared outputs tunnel info to stderr)
-                            if let Some(captures) = url_regex.find(&line) {
-                                println!("🎯 Found tunnel URL in stderr: {}", captures.as_str());
-                                return Ok(captures.as_str().to_string());
-                            }
-
-                            // Also check for the boxed format specifically
-                            if line.contains("Visit it at") || line.contains("trycloudflare.com") {
-                                if let Some(captures) = url_regex.find(&line) {
-                                    println!("🎯 Found tunnel URL in boxed format: {}", captures.as_str());
-                                    return Ok(captures.as_str().to_string());
-                                }
-                            }
-
-                            // Check for connection success indicators in stderr
-                            if line.contains("Registered tunnel connection") {
-                                println!("✅ Tunnel connection registered successfully");
-                            }
-
-                            // Check for specific error conditions
-                            if line.contains("connection refused") || line.contains("no such host") {
-                                return Err(anyhow!("Local server not accessible: {}", line));
-                            }
-
-                            if line.contains("authentication") || line.contains("login") {
-                                return Err(anyhow!("Cloudflare authentication required: {}", line));
-                            }
-
-                            if line.contains("failed") && line.contains("tunnel") && !line.contains("connection") {
-                                return Err(anyhow!("Tunnel creation failed: {}", line));
-                            }
-                        }
-                    }
+    /// Wait for cloudflared to announce the URL of the quick tunnel
+    async fn wait_for_url(lines: &mut mpsc::UnboundedReceiver<String>) -> Result<String> {
+        let parse_result = timeout(CONNECT_TIMEOUT, async {
+            while let Some(line) = lines.recv().await {
+                if let Some(url) = parse_quick_tunnel_url(&line) {
+                    return Ok(url);
+                }
+
+                // Check for specific error conditions
+                if line.contains("connection refused") || line.contains("no such host") {
+                    return Err(anyhow!("Local server not accessible: {}", line));
+                }
+
+                if line.contains("authentication") || line.contains("login") {
+                    return Err(anyhow!("Cloudflare authentication required: {}", line));
+                }
+
+                if line.contains("failed")
+                    && line.contains("tunnel")
+                    && !line.contains("connection")
+                {
+                    return Err(anyhow!("Tunnel creation failed: {}", line));
                 }
             }
-        }).await;
+            Err(anyhow!(
+                "cloudflared exited before it assigned a tunnel URL"
+            ))
+        })
+        .await;
 
         match parse_result {
-            Ok(Ok(url)) => Ok(url),
-            Ok(Err(e)) => Err(e),
-            Err(_) => Err(anyhow!("Timeout waiting for tunnel URL (30 seconds)")),
+            Ok(result) => result,
+            Err(_) => Err(anyhow!(
+                "Timeout waiting for tunnel URL ({} seconds)",
+                CONNECT_TIMEOUT.as_secs()
+            )),
         }
     }
 
+    /// Keep reading cloudflared's output once the quick tunnel is up, so the
+    /// pipes never fill, and pick up the URL should it announce a new one
+    fn follow_quick_tunnel(
+        mut lines: mpsc::UnboundedReceiver<String>,
+        current_url: Arc<RwLock<Option<String>>>,
+        cloudflared_url: Arc<RwLock<Option<String>>>,
+        url_changes: broadcast::Sender<TunnelUrlChanged>,
+    ) {
+        tokio::spawn(async move {
+            while let Some(line) = lines.recv().await {
+                let Some(url) = parse_quick_tunnel_url(&line) else {
+                    continue;
+                };
+                if current_url.read().await.as_deref() != Some(url.as_str()) {
+                    println!("Quick tunnel moved to {}", url);
+                    *cloudflared_url.write().await = Some(url.clone());
+                    publish_url(&current_url, &url_changes, Some(url)).await;
+                }
+            }
+        });
+    }
+
+    /// Run the named tunnel `name`, creating it and routing the hostname to
+    /// it first when needed
     async fn create_named_tunnel(&mut self, name: &str) -> Result<String> {
-        // In a real implementation, this would create a named tunnel
-        let tunnel_url = format!("https://{}.yourdomain.com", name);
+        if self.named.hostname.is_empty() {
+            return Err(anyhow!("No hostname is configured for tunnel '{}'", name));
+        }
+        let hostname = self.named.hostname.clone();
+        println!("Starting named tunnel '{}' for {}...", name, hostname);
 
-        println!("Named tunnel created: {}", tunnel_url);
+        let cloudflared_path = self.ensure_cloudflared().await?;
+        let dir = tunnels_dir()?;
+        tokio::fs::create_dir_all(&dir).await?;
+        let credentials_file = dir.join(format!("{}.json", name));
+
+        let tunnel_id = match &self.named.tunnel_id {
+            Some(id) if credentials_file.exists() => id.clone(),
+            _ => {
+                self.provision_named_tunnel(&cloudflared_path, name, &credentials_file)
+                    .await?
+            },
+        };
+
+        // Idempotent while the record already points at this tunnel
+        Self::run_cloudflared(
+            &cloudflared_path,
+            &["tunnel", "route", "dns", &tunnel_id, &hostname],
+        )
+        .await?;
 
-        *self.current_url.write().await = Some(tunnel_url.clone());
+        let config_file = dir.join(format!("{}.yml", name));
+        let config = named_tunnel_config(&tunnel_id, &credentials_file, &hostname, self.local_port);
+        tokio::fs::write(&config_file, config).await?;
+
+        let mut child = Command::new(&cloudflared_path)
+            .args([
+                "tunnel",
+                "--no-autoupdate",
+                "--config",
+                &config_file.to_string_lossy(),
+            ])
+            .args(cloudflared_args(&self.cloudflared))
+            .args(["run", &tunnel_id])
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow!("Failed to spawn cloudflared process: {}", e))?;
+        let lines = capture_output(&self.process_id(), &mut child).await;
+        Self::wait_for_connection(lines).await?;
+
+        let tunnel_url = format!("https://{}", hostname);
+        self.monitor_process(child).await?;
+        self.set_url(Some(tunnel_url.clone())).await;
         *self.is_connected.write().await = true;
+        self.named.tunnel_id = Some(tunnel_id);
 
+        println!("Named tunnel '{}' connected: {}", name, tunnel_url);
         Ok(tunnel_url)
     }
 
+    /// Run an ngrok tunnel to the local port
+    async fn create_ngrok_tunnel(&mut self) -> Result<String> {
+        let Some(authtoken) = self.ngrok.authtoken.clone().filter(|t| !t.is_empty()) else {
+            return Err(anyhow!(
+                "ngrok tunnels need the authtoken of an ngrok account"
+            ));
+        };
+        println!("Creating ngrok tunnel...");
+
+        let ngrok_path = self.binary_manager.ensure_ngrok().await?;
+        let mut command = Command::new(&ngrok_path);
+        command.args([
+            "http",
+            &self.local_port.to_string(),
+            "--log",
+            "stdout",
+            "--log-format",
+            "json",
+        ]);
+        if let Some(domain) = &self.ngrok.domain {
+            command.args(["--domain", domain]);
+        }
+        // Kept off the command line, where other users could read it
+        let mut child = command
+            .env("NGROK_AUTHTOKEN", authtoken)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| anyhow!("Failed to spawn ngrok process: {}", e))?;
+
+        let mut lines = capture_output(&self.process_id(), &mut child).await;
+        let mut last_error = None;
+        let started = timeout(CONNECT_TIMEOUT, async {
+            while let Some(line) = lines.recv().await {
+                match parse_ngrok_log(&line) {
+                    Some(NgrokEvent::Started(url)) => return Some(url),
+                    Some(NgrokEvent::Failed(error)) => last_error = Some(error),
+                    None => {},
+                }
+            }
+            None
+        })
+        .await;
+
+        let tunnel_url = match started {
+            Ok(Some(url)) => url,
+            Ok(None) => {
+                return Err(anyhow!(
+                    "ngrok exited before the tunnel started: {}",
+                    last_error.unwrap_or_default()
+                ))
+            },
+            Err(_) => {
+                return Err(anyhow!(
+                    "Timeout waiting for the ngrok tunnel ({} seconds){}",
+                    CONNECT_TIMEOUT.as_secs(),
+                    last_error.map(|e| format!(": {}", e)).unwrap_or_default()
+                ))
+            },
+        };
+        tokio::spawn(async move { while lines.recv().await.is_some() {} });
+
+        self.monitor_process(child).await?;
+        self.set_url(Some(tunnel_url.clone())).await;
+        *self.is_connected.write().await = true;
+
+        println!("ngrok tunnel created successfully: {}", tunnel_url);
+        Ok(tunnel_url)
+    }
+
+    /// Create the tunnel `name` with its credentials in `credentials_file`.
+    /// A tunnel of that name created before, e.g. on another machine, is
+    /// reused and only its credentials are fetched. Returns the tunnel ID.
+    async fn provision_named_tunnel(
+        &self,
+        cloudflared_path: &Path,
+        name: &str,
+        credentials_file: &Path,
+    ) -> Result<String> {
+        let credentials = credentials_file.to_string_lossy();
+        let created = Self::run_cloudflared(
+            cloudflared_path,
+            &["tunnel", "create", "--credentials-file", &credentials, name],
+        )
+        .await;
+
+        match created {
+            Ok(output) => parse_created_tunnel_id(&output)
+                .ok_or_else(|| anyhow!("cloudflared did not report the ID of tunnel '{}'", name)),
+            Err(e) if e.to_string().contains("already exists") => {
+                let tunnel_id = match &self.named.tunnel_id {
+                    Some(id) => id.clone(),
+                    None => {
+                        let listed = Self::run_cloudflared(
+                            cloudflared_path,
+                            &["tunnel", "list", "--output", "json", "--name", name],
+                        )
+                        .await?;
+                        parse_listed_tunnel_id(&listed, name)
+                            .ok_or_else(|| anyhow!("Tunnel '{}' was not found", name))?
+                    },
+                };
+                Self::run_cloudflared(
+                    cloudflared_path,
+                    &["tunnel", "token", "--cred-file", &credentials, &tunnel_id],
+                )
+                .await?;
+                Ok(tunnel_id)
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Run a cloudflared management command and return what it printed
+    async fn run_cloudflared(cloudflared_path: &Path, args: &[&str]) -> Result<String> {
+        let output = timeout(
+            CLOUDFLARED_COMMAND_TIMEOUT,
+            Command::new(cloudflared_path).args(args).output(),
+        )
+        .await
+        .map_err(|_| anyhow!("cloudflared {} timed out", args[..2].join(" ")))??;
+
+        let printed = format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        if output.status.success() {
+            return Ok(printed);
+        }
+
+        // Everything but running a tunnel needs the certificate of the login
+        if printed.contains("cert.pem") || printed.contains("origin certificate") {
+            return Err(anyhow!(
+                "cloudflared is not logged in to Cloudflare. Run `cloudflared tunnel login` once to authorize it for your domain."
+            ));
+        }
+        Err(anyhow!(
+            "cloudflared {} failed: {}",
+            args[..2].join(" "),
+            printed.trim()
+        ))
+    }
+
+    /// Wait until the tunnel registered a connection with Cloudflare. The
+    /// rest of cloudflared's log is drained in the background, so it never
+    /// blocks on a full pipe.
+    async fn wait_for_connection(mut lines: mpsc::UnboundedReceiver<String>) -> Result<()> {
+        let mut last_error = None;
+
+        let connected = timeout(CONNECT_TIMEOUT, async {
+            while let Some(line) = lines.recv().await {
+                if line.contains("Registered tunnel connection") {
+                    return true;
+                }
+                if line.contains(" ERR ") {
+                    last_error = Some(line);
+                }
+            }
+            false
+        })
+        .await;
+
+        match connected {
+            Ok(true) => {
+                tokio::spawn(async move { while lines.recv().await.is_some() {} });
+                Ok(())
+            },
+            Ok(false) => Err(anyhow!(
+                "cloudflared exited before the tunnel connected: {}",
+                last_error.unwrap_or_default()
+            )),
+            Err(_) => Err(anyhow!(
+                "Timeout waiting for the tunnel to connect ({} seconds){}",
+                CONNECT_TIMEOUT.as_secs(),
+                last_error.map(|e| format!(": {}", e)).unwrap_or_default()
+            )),
+        }
+    }
+
+    /// Close the tunnel and those of the other services
     pub async fn close_tunnel(&mut self) -> Result<()> {
+        for tunnel in self.services.values_mut() {
+            if let Err(e) = tunnel.close_process().await {
+                eprintln!("Failed to close the {} tunnel: {}", tunnel.service, e);
+            }
+        }
+        self.close_process().await
+    }
+
+    /// Stop the tunnel process
+    async fn close_process(&mut self) -> Result<()> {
+        // Also done when the process is gone, so it is not restarted
+        init_process_monitor()
+            .unregister_process(&self.process_id())
+            .await?;
+        self.forget_pid().await;
+        if let Some(relays) = self.fallback_relays.take() {
+            relays.abort();
+        }
         if !*self.is_connected.read().await {
             return Ok(());
         }
 
         println!("Closing tunnel...");
+        self.set_url(None).await;
+        *self.is_connected.write().await = false;
 
-        // Gracefully terminate the cloudflared process
-        if let Some(mut child) = self.process.write().await.take() {
-            // First try graceful termination
-            #[cfg(unix)]
-            {
-                if let Some(id) = child.id() {
-                    // Send SIGTERM for graceful shutdown
-                    #[allow(unsafe_code)]
-                    unsafe {
-                        libc::kill(id as i32, libc::SIGTERM);
-                    }
-
-                    // Wait up to 5 seconds for graceful shutdown
-                    let graceful_shutdown = timeout(Duration::from_secs(5), child.wait()).await;
-
-                    if graceful_shutdown.is_err() {
-                        // If graceful shutdown failed, force kill
-                        println!("Graceful shutdown timed out, force killing process");
-                        if let Err(e) = child.kill().await {
-                            eprintln!("Failed to force kill tunnel process: {}", e);
-                        }
-                    } else {
-                        println!("Tunnel process terminated gracefully");
-                    }
-                } else {
-                    // Process already finished
-                    let _ = child.wait().await;
-                }
+        println!("Tunnel closed");
+        Ok(())
+    }
+
+    /// Name the tunnel process is monitored under
+    fn process_id(&self) -> String {
+        let program = match self.tunnel_type {
+            TunnelType::Ngrok => "ngrok",
+            TunnelType::Quick | TunnelType::Named(_) => "cloudflared",
+        };
+        match self.service {
+            TunnelService::Api => program.to_string(),
+            service => format!("{}-{}", program, service),
+        }
+    }
+
+    /// Hand the tunnel process to the process monitor, which notices when it
+    /// exits. It is registered once, so its restarts count against the limit
+    /// until the tunnel is closed.
+    async fn monitor_process(&self, child: Child) -> Result<()> {
+        let monitor = init_process_monitor();
+        let process_id = self.process_id();
+        if monitor.get_process_info(&process_id).await.is_none() {
+            let name = match self.tunnel_type {
+                TunnelType::Ngrok => "ngrok Tunnel",
+                TunnelType::Quick | TunnelType::Named(_) => "Cloudflare Tunnel",
+            };
+            let name = match self.service {
+                TunnelService::Api => name.to_string(),
+                service => format!("{} ({})", name, service),
+            };
+            monitor
+                .register_process(process_id.clone(), name, monitor_config())
+                .await?;
+        }
+
+        // Stopped on the next start should MindLink go away without closing it
+        if let (Some(dir), Some(pid)) = (tunnel_pids::pid_files_dir(), child.id()) {
+            if let Err(e) = tunnel_pids::record(&dir, &process_id, pid).await {
+                eprintln!("Failed to record the PID of {}: {}", process_id, e);
             }
+        }
+        monitor.start_monitoring(process_id, child).await?;
+        Ok(())
+    }
 
-            #[cfg(windows)]
-            {
-                // On Windows, just kill the process
-                if let Err(e) = child.kill().await {
-                    eprintln!("Failed to kill tunnel process: {}", e);
-                } else {
-                    println!("Tunnel process terminated");
-                }
+    /// Drop the PID file of the tunnel process, which was stopped
+    async fn forget_pid(&self) {
+        let Some(dir) = tunnel_pids::pid_files_dir() else {
+            return;
+        };
+        let process_id = self.process_id();
+        if let Err(e) = tunnel_pids::forget(&dir, &process_id).await {
+            eprintln!("Failed to remove the PID file of {}: {}", process_id, e);
+        }
+    }
+
+    /// Start the tunnel again when its process exited without being closed.
+    /// A failed restart is tried again on the next call, until the restart
+    /// limit is reached. Returns the URL of the restarted tunnel.
+    pub async fn restart_if_crashed(&mut self) -> Result<Option<String>> {
+        // A fallback tunnel that ended makes way for cloudflared again
+        if self
+            .fallback_relays
+            .as_ref()
+            .is_some_and(JoinHandle::is_finished)
+        {
+            println!("Fallback tunnel closed, restarting the tunnel...");
+            self.fallback_relays = None;
+            *self.is_connected.write().await = false;
+            let restarted = self.create_tunnel().await;
+            if restarted.is_err() {
+                self.set_url(None).await;
             }
+            return restarted.map(Some);
+        }
+
+        let monitor = init_process_monitor();
+        let process_id = self.process_id();
+        if monitor.get_process_info(&process_id).await.is_none()
+            || monitor.is_process_running(&process_id).await
+        {
+            return Ok(None);
         }
 
-        *self.current_url.write().await = None;
+        println!("{} exited, restarting the tunnel...", process_id);
         *self.is_connected.write().await = false;
+        let restarted = match monitor.restart_process(&process_id).await {
+            Ok(()) => self.create_tunnel().await,
+            Err(e) => {
+                // Given up on until the tunnel is started again
+                let _ = monitor.unregister_process(&process_id).await;
+                self.forget_pid().await;
+                Err(e.into())
+            },
+        };
+        if restarted.is_err() {
+            self.set_url(None).await;
+        }
+        restarted.map(Some)
+    }
 
-        println!("Tunnel closed");
-        Ok(())
+    /// Start the tunnels of the other configured services that are not
+    /// running yet, and close those no longer configured. Returns the
+    /// services whose tunnel failed to start.
+    pub async fn create_service_tunnels(&mut self) -> Vec<(TunnelService, anyhow::Error)> {
+        let configs = self.service_configs.clone();
+        let removed: Vec<TunnelService> = self
+            .services
+            .keys()
+            .filter(|service| !configs.iter().any(|config| config.service == **service))
+            .copied()
+            .collect();
+        for service in removed {
+            if let Some(mut tunnel) = self.services.remove(&service) {
+                if let Err(e) = tunnel.close_process().await {
+                    eprintln!("Failed to close the {} tunnel: {}", service, e);
+                }
+            }
+        }
+
+        let mut failed = Vec::new();
+        for config in &configs {
+            if let Err(e) = self.create_service_tunnel(config).await {
+                failed.push((config.service, e));
+            }
+        }
+        failed
+    }
+
+    /// Start the tunnel of another service, of the same type as this one
+    async fn create_service_tunnel(&mut self, config: &ServiceTunnelConfig) -> Result<String> {
+        let tunnel = match self.services.entry(config.service) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let mut tunnel = TunnelManager::new().await?;
+                tunnel.service = config.service;
+                entry.insert(tunnel)
+            },
+        };
+
+        tunnel.set_local_port(config.local_port).await;
+        tunnel.cloudflared = self.cloudflared.clone();
+        tunnel.fallback = self.fallback.clone();
+        match &self.tunnel_type {
+            TunnelType::Quick => tunnel.set_tunnel_type(TunnelType::Quick).await,
+            TunnelType::Named(name) => {
+                let name = format!("{}-{}", name, config.service);
+                // The ID only still applies to the same tunnel
+                let tunnel_id = tunnel
+                    .named
+                    .tunnel_id
+                    .clone()
+                    .filter(|_| tunnel.named.name == name);
+                tunnel
+                    .set_named_tunnel(NamedTunnelConfig {
+                        name,
+                        hostname: config.hostname.clone(),
+                        tunnel_id,
+                    })
+                    .await;
+            },
+            TunnelType::Ngrok => {
+                tunnel
+                    .set_ngrok_tunnel(NgrokConfig {
+                        authtoken: self.ngrok.authtoken.clone(),
+                        domain: Some(config.hostname.clone()).filter(|d| !d.is_empty()),
+                    })
+                    .await;
+            },
+        }
+        tunnel.create_tunnel().await
+    }
+
+    /// Restart the tunnels of other services whose process exited, returning
+    /// the result for each one restarted
+    pub async fn restart_crashed_services(&mut self) -> Vec<(TunnelService, Result<String>)> {
+        let mut restarted = Vec::new();
+        for (service, tunnel) in &mut self.services {
+            if let Some(result) = tunnel.restart_if_crashed().await.transpose() {
+                restarted.push((*service, result));
+            }
+        }
+        restarted
+    }
+
+    /// This tunnel and those of the other services
+    pub async fn tunnels(&self) -> Vec<ServiceTunnel> {
+        let mut tunnels = vec![self.status().await];
+        for tunnel in self.services.values() {
+            tunnels.push(tunnel.status().await);
+        }
+        tunnels
+    }
+
+    async fn status(&self) -> ServiceTunnel {
+        ServiceTunnel {
+            service: self.service,
+            local_port: self.local_port,
+            url: self.get_current_url().await,
+            connected: self.is_connected().await,
+        }
     }
 
     pub async fn check_health(&self) -> Result<bool> {
@@ -270,13 +1024,13 @@ impl TunnelManager {
         }
 
         // First check if the process is still running
-        let process_running = {
-            let process_guard = self.process.read().await;
-            if let Some(child) = process_guard.as_ref() {
-                child.id().is_some()
-            } else {
-                false
-            }
+        let process_running = match &self.fallback_relays {
+            Some(relays) => !relays.is_finished(),
+            None => {
+                init_process_monitor()
+                    .is_process_running(&self.process_id())
+                    .await
+            },
         };
 
         if !process_running {
@@ -286,45 +1040,62 @@ impl TunnelManager {
             return Ok(false);
         }
 
-        // Then check HTTP connectivity through the tunnel
-        if let Some(url) = &*self.current_url.read().await {
-            let health_url = format!("{}/health", url);
+        // Reachability from outside is checked by the tunnel probe. A URL
+        // not checked yet counts as reachable while its process runs.
+        Ok(self
+            .last_public_check()
+            .await
+            .is_none_or(|check| check.reachable()))
+    }
 
-            // Use a short timeout for health checks
-            let client = reqwest::Client::builder()
-                .timeout(Duration::from_secs(10))
-                .build()
-                .map_err(|e| anyhow!("Failed to create HTTP client: {}", e))?;
+    /// Remember the outcome of a request through the public URL
+    pub async fn record_public_check(&self, check: PublicCheck) {
+        *self.public_check.write().await = Some(check);
+    }
 
-            match client.get(&health_url).send().await {
-                Ok(response) => {
-                    let is_healthy = response.status().is_success();
-                    if !is_healthy {
-                        println!("Tunnel HTTP health check failed: {}", response.status());
-                    }
-                    Ok(is_healthy)
-                },
-                Err(e) => {
-                    println!("Tunnel health check request failed: {}", e);
-                    Ok(false)
-                },
-            }
-        } else {
-            Ok(false)
-        }
+    /// Latest check of the current public URL
+    pub async fn last_public_check(&self) -> Option<PublicCheck> {
+        let url = self.get_current_url().await?;
+        self.public_check
+            .read()
+            .await
+            .clone()
+            .filter(|check| check.url == url)
     }
 
     pub async fn get_current_url(&self) -> Option<String> {
         self.current_url.read().await.clone()
     }
 
+    async fn set_url(&self, url: Option<String>) {
+        let by_cloudflared =
+            self.fallback_relays.is_none() && !matches!(self.tunnel_type, TunnelType::Ngrok);
+        *self.cloudflared_url.write().await = url.clone().filter(|_| by_cloudflared);
+        publish_url(&self.current_url, &self.url_changes, url).await;
+    }
+
+    /// Hear of every change of the public URL
+    pub fn subscribe_url_changes(&self) -> broadcast::Receiver<TunnelUrlChanged> {
+        self.url_changes.subscribe()
+    }
+
+    /// The public URL as it changes, for following it elsewhere
+    pub fn url_handle(&self) -> Arc<RwLock<Option<String>>> {
+        self.current_url.clone()
+    }
+
+    /// The public URL while cloudflared serves it, as it changes
+    pub fn cloudflared_url_handle(&self) -> Arc<RwLock<Option<String>>> {
+        self.cloudflared_url.clone()
+    }
+
     pub async fn is_connected(&self) -> bool {
         *self.is_connected.read().await
     }
 
     pub async fn recreate_tunnel(&mut self) -> Result<String> {
         println!("Recreating tunnel...");
-        self.close_tunnel().await?;
+        self.close_process().await?;
         tokio::time::sleep(Duration::from_secs(3)).await;
         self.create_tunnel().await
     }
@@ -338,6 +1109,112 @@ impl TunnelManager {
         self.tunnel_type = tunnel_type;
     }
 
+    /// Use the tunnel `config` describes: the named tunnel when its type is
+    /// `named`, ngrok when it is `ngrok`, otherwise a quick tunnel. The
+    /// tunnels of its other services are started by `create_service_tunnels`.
+    pub async fn configure(&mut self, config: &TunnelConfig) {
+        match config.tunnel_type.as_str() {
+            "named" => self.set_named_tunnel(config.named.clone()).await,
+            "ngrok" => self.set_ngrok_tunnel(config.ngrok.clone()).await,
+            _ => self.set_tunnel_type(TunnelType::Quick).await,
+        }
+        self.cloudflared = config.cloudflared.clone();
+        let pinned = config.cloudflared_updates.pinned_version.clone();
+        if self.binary_manager.pinned_cloudflared() != pinned.as_deref() {
+            // Found again, e.g. the pinned release instead of the one on PATH
+            *self.cloudflared_path.write().await = None;
+            self.binary_manager.pin_cloudflared(pinned);
+        }
+        self.fallback = config.fallback.clone();
+        self.service_configs = config.services.clone();
+        self.schedule = Schedule::from_config(&config.schedule);
+        self.schedule_applied = None;
+    }
+
+    /// Whether the schedule has the tunnel up at `now`, and until when;
+    /// `None` when the tunnel is not scheduled
+    pub fn schedule_status(&self, now: NaiveDateTime) -> Option<ScheduleStatus> {
+        self.schedule.as_ref().map(|schedule| schedule.status(now))
+    }
+
+    /// Whether the tunnel is down because the schedule has it closed at `now`
+    pub async fn closed_by_schedule(&self, now: NaiveDateTime) -> bool {
+        self.schedule
+            .as_ref()
+            .is_some_and(|schedule| !schedule.is_open(now))
+            && !self.is_connected().await
+    }
+
+    /// Start the tunnel unless the schedule has it closed at `now`. Returns
+    /// its public URL, or `None` while it is closed.
+    pub async fn create_scheduled_tunnel(&mut self, now: NaiveDateTime) -> Result<Option<String>> {
+        let open = self
+            .schedule
+            .as_ref()
+            .is_none_or(|schedule| schedule.is_open(now));
+        if !open {
+            self.schedule_applied = Some(false);
+            return Ok(None);
+        }
+
+        let url = self.create_tunnel().await?;
+        self.schedule_applied = Some(true);
+        Ok(Some(url))
+    }
+
+    /// Open the tunnel when a window of the schedule began by `now`, and
+    /// close it when the window ended. A failed start is tried again on the
+    /// next call.
+    pub async fn apply_schedule(&mut self, now: NaiveDateTime) -> Result<Option<ScheduleChange>> {
+        let Some(open) = self.schedule.as_ref().map(|schedule| schedule.is_open(now)) else {
+            return Ok(None);
+        };
+        if self.schedule_applied == Some(open) {
+            return Ok(None);
+        }
+
+        let connected = self.is_connected().await;
+        let change = if open && !connected {
+            let url = self.create_tunnel().await?;
+            for (service, e) in self.create_service_tunnels().await {
+                eprintln!("Failed to start the {} tunnel: {}", service, e);
+            }
+            Some(ScheduleChange::Opened(url))
+        } else if !open && connected {
+            self.close_tunnel().await?;
+            Some(ScheduleChange::Closed)
+        } else {
+            None
+        };
+        self.schedule_applied = Some(open);
+        Ok(change)
+    }
+
+    pub async fn set_named_tunnel(&mut self, named: NamedTunnelConfig) {
+        if *self.is_connected.read().await {
+            eprintln!("Cannot change tunnel type while connected");
+            return;
+        }
+
+        self.tunnel_type = TunnelType::Named(named.name.clone());
+        self.named = named;
+    }
+
+    pub async fn set_ngrok_tunnel(&mut self, ngrok: NgrokConfig) {
+        if *self.is_connected.read().await {
+            eprintln!("Cannot change tunnel type while connected");
+            return;
+        }
+
+        self.tunnel_type = TunnelType::Ngrok;
+        self.ngrok = ngrok;
+    }
+
+    /// ID of the named tunnel, once it was created or reused
+    pub fn named_tunnel_id(&self) -> Option<&str> {
+        self.named.tunnel_id.as_deref()
+    }
+
     pub async fn set_local_port(&mut self, port: u16) {
         if *self.is_connected.read().await {
             eprintln!("Cannot change local port while tunnel is active");
@@ -347,8 +1224,13 @@ impl TunnelManager {
         self.local_port = port;
     }
 
-    /// Create a permanent named tunnel that persists across restarts
-    pub async fn create_permanent_tunnel(&mut self, tunnel_name: &str) -> Result<String> {
+    /// Switch to the named tunnel `tunnel_name` serving `hostname`, which
+    /// persists across restarts, and start it
+    pub async fn create_permanent_tunnel(
+        &mut self,
+        tunnel_name: &str,
+        hostname: &str,
+    ) -> Result<String> {
         println!("🚇 Creating permanent tunnel: {}", tunnel_name);
 
         // Close existing tunnel if any
@@ -357,70 +1239,19 @@ impl TunnelManager {
             tokio::time::sleep(Duration::from_secs(2)).await;
         }
 
-        // Create tunnel with specific name
-        let cloudflared_path = self.ensure_cloudflared().await?;
-        
-        let mut child = Command::new(&cloudflared_path)
-            .args(&[
-                "tunnel",
-                "--url", &format!("http://127.0.0.1:{}", self.local_port),
-                "--name", tunnel_name,
-                "--no-autoupdate",
-            ])
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
-            .kill_on_drop(true)
-            .spawn()
-            .map_err(|e| anyhow!("Failed to spawn cloudflared process: {}", e))?;
-
-        // Wait for tunnel to establish and extract URL
-        let mut attempts = 0;
-        let max_attempts = 30; // 30 seconds max
-        
-        while attempts < max_attempts {
-            if let Some(stderr) = child.stderr.as_mut() {
-                use tokio::io::{AsyncBufReadExt, BufReader};
-                let mut reader = BufReader::new(stderr);
-                let mut line = String::new();
-                
-                match tokio::time::timeout(Duration::from_secs(1), reader.read_line(&mut line)).await {
-                    Ok(Ok(_)) => {
-                        if line.contains("https://") && line.contains(".trycloudflare.com") {
-                            // Extract URL from line
-                            if let Some(start) = line.find("https://") {
-                                if let Some(end) = line[start..].find(" ") {
-                                    let url = &line[start..start + end];
-                                    println!("✅ Permanent tunnel established: {}", url);
-                                    
-                                    // Store tunnel info
-                                    *self.current_url.write().await = Some(url.to_string());
-                                    *self.is_connected.write().await = true;
-                                    *self.process.write().await = Some(child);
-                                    
-                                    return Ok(url.to_string());
-                                } else {
-                                    // URL goes to end of line
-                                    let url = line[start..].trim();
-                                    println!("✅ Permanent tunnel established: {}", url);
-                                    
-                                    *self.current_url.write().await = Some(url.to_string());
-                                    *self.is_connected.write().await = true;
-                                    *self.process.write().await = Some(child);
-                                    
-                                    return Ok(url.to_string());
-                                }
-                            }
-                        }
-                    }
-                    _ => {
-                        attempts += 1;
-                        tokio::time::sleep(Duration::from_millis(1000)).await;
-                    }
-                }
-            }
-        }
-
-        Err(anyhow::anyhow!("Failed to establish permanent tunnel after {} attempts", max_attempts))
+        // The ID only still applies to the same tunnel
+        let tunnel_id = self
+            .named
+            .tunnel_id
+            .clone()
+            .filter(|_| self.named.name == tunnel_name);
+        self.set_named_tunnel(NamedTunnelConfig {
+            name: tunnel_name.to_string(),
+            hostname: hostname.to_string(),
+            tunnel_id,
+        })
+        .await;
+        self.create_tunnel().await
     }
 }
 
@@ -0,0 +1,294 @@
+// Client IP resolution and allow/deny lists for the API server
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::log_warn;
+use crate::managers::config_manager::AccessControlConfig;
+use crate::managers::server_manager::create_error_response;
+use crate::middleware::metrics::tunnel_host;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// An IPv4 or IPv6 network in CIDR notation. A bare address is a single-host network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IpCidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpCidr {
+    pub fn parse(value: &str) -> MindLinkResult<Self> {
+        let invalid = |reason: &str| MindLinkError::Configuration {
+            message: format!("Invalid CIDR '{}': {}", value, reason),
+            config_key: Some("access_control".to_string()),
+            source: None,
+        };
+
+        let (addr, prefix) = match value.trim().split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (value.trim(), None),
+        };
+
+        let network: IpAddr = addr.parse().map_err(|_| invalid("not an IP address"))?;
+        let max_len = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|len| *len <= max_len)
+                .ok_or_else(|| invalid("prefix length out of range"))?,
+            None => max_len,
+        };
+
+        Ok(Self {
+            network,
+            prefix_len,
+        })
+    }
+
+    pub fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.network, normalize(ip)) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                prefix_matches(&net.octets(), &ip.octets(), self.prefix_len)
+            },
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                prefix_matches(&net.octets(), &ip.octets(), self.prefix_len)
+            },
+            _ => false,
+        }
+    }
+}
+
+/// Treat IPv4-mapped IPv6 addresses (dual-stack sockets) as plain IPv4
+fn normalize(ip: &IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6
+            .to_ipv4_mapped()
+            .map(IpAddr::V4)
+            .unwrap_or(IpAddr::V6(*v6)),
+        IpAddr::V4(_) => *ip,
+    }
+}
+
+fn prefix_matches(network: &[u8], ip: &[u8], prefix_len: u8) -> bool {
+    let full_bytes = usize::from(prefix_len / 8);
+    let remaining_bits = prefix_len % 8;
+
+    if network[..full_bytes] != ip[..full_bytes] {
+        return false;
+    }
+
+    if remaining_bits == 0 {
+        return true;
+    }
+
+    let mask = 0xffu8 << (8 - remaining_bits);
+    network[full_bytes] & mask == ip[full_bytes] & mask
+}
+
+/// Compiled allow/deny lists. Deny entries win; an empty allow list allows everyone.
+#[derive(Debug, Clone, Default)]
+pub struct AccessPolicy {
+    allow: Vec<IpCidr>,
+    deny: Vec<IpCidr>,
+}
+
+impl AccessPolicy {
+    pub fn from_config(config: &AccessControlConfig) -> MindLinkResult<Self> {
+        Ok(Self {
+            allow: config
+                .allow
+                .iter()
+                .map(|entry| IpCidr::parse(entry))
+                .collect::<MindLinkResult<_>>()?,
+            deny: config
+                .deny
+                .iter()
+                .map(|entry| IpCidr::parse(entry))
+                .collect::<MindLinkResult<_>>()?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    pub fn is_allowed(&self, ip: &IpAddr) -> bool {
+        if self.deny.iter().any(|cidr| cidr.contains(ip)) {
+            return false;
+        }
+
+        self.allow.is_empty() || self.allow.iter().any(|cidr| cidr.contains(ip))
+    }
+}
+
+/// Proxies whose forwarding headers are believed. By default only loopback,
+/// where the tunnels deliver their traffic; other peers cannot spoof their
+/// address with headers.
+#[derive(Debug, Clone)]
+pub struct TrustedProxies {
+    proxies: Vec<IpCidr>,
+}
+
+impl Default for TrustedProxies {
+    fn default() -> Self {
+        Self {
+            proxies: vec![
+                IpCidr {
+                    network: IpAddr::from([127, 0, 0, 0]),
+                    prefix_len: 8,
+                },
+                IpCidr {
+                    network: IpAddr::from([0u16, 0, 0, 0, 0, 0, 0, 1]),
+                    prefix_len: 128,
+                },
+            ],
+        }
+    }
+}
+
+impl TrustedProxies {
+    pub fn from_config(config: &AccessControlConfig) -> MindLinkResult<Self> {
+        Ok(Self {
+            proxies: config
+                .trusted_proxies
+                .iter()
+                .map(|entry| IpCidr::parse(entry))
+                .collect::<MindLinkResult<_>>()?,
+        })
+    }
+
+    fn trusts(&self, ip: &IpAddr) -> bool {
+        self.proxies.iter().any(|proxy| proxy.contains(ip))
+    }
+
+    /// Determine the client IP for a request. Forwarding headers only count
+    /// when the direct peer is a trusted proxy: `CF-Connecting-IP` when the
+    /// request came `via_cloudflared`, else `X-Forwarded-For`, walked from the
+    /// right up to the first hop that is not a trusted proxy. Entries left of
+    /// that hop were written by the client itself. Without a peer address,
+    /// nothing is believed.
+    pub fn client_ip(
+        &self,
+        peer: Option<IpAddr>,
+        headers: &HeaderMap,
+        via_cloudflared: bool,
+    ) -> Option<IpAddr> {
+        let peer = normalize(&peer?);
+        if !self.trusts(&peer) {
+            return Some(peer);
+        }
+
+        if via_cloudflared {
+            let connecting = headers
+                .get("cf-connecting-ip")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse().ok());
+            if let Some(ip) = connecting {
+                return Some(normalize(&ip));
+            }
+        }
+
+        let forwarded: Vec<&str> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .collect();
+        let mut client = peer;
+        for hop in forwarded.iter().rev() {
+            if !self.trusts(&client) {
+                break;
+            }
+            match hop.trim().parse() {
+                Ok(ip) => client = normalize(&ip),
+                Err(_) => break,
+            }
+        }
+        Some(client)
+    }
+}
+
+/// Everything resolving the client IP needs
+#[derive(Debug, Clone)]
+pub struct ClientIpResolver {
+    pub proxies: Arc<TrustedProxies>,
+    /// Public URL of the tunnel while cloudflared serves it, the only source
+    /// whose `CF-Connecting-IP` is believed
+    pub cloudflared_url: Arc<RwLock<Option<String>>>,
+}
+
+/// Client IP of the current request, resolved once by [`resolve_client_ip`]
+/// and available as a request extension to every later layer and handler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+/// Resolve the real client IP behind trusted proxies
+pub async fn resolve_client_ip(
+    State(resolver): State<ClientIpResolver>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    // Other tunnels reach the server from loopback too, but only requests for
+    // cloudflared's hostname were delivered by it
+    let cloudflared = resolver
+        .cloudflared_url
+        .read()
+        .await
+        .as_deref()
+        .and_then(tunnel_host);
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok())
+        .map(|host| host.rsplit_once(':').map_or(host, |(name, _)| name));
+    let via_cloudflared = cloudflared
+        .zip(host)
+        .is_some_and(|(tunnel, host)| host.eq_ignore_ascii_case(&tunnel));
+    let client = resolver
+        .proxies
+        .client_ip(peer, request.headers(), via_cloudflared);
+    request.extensions_mut().insert(ClientIp(client));
+
+    next.run(request).await
+}
+
+/// Reject requests whose client IP is denied or not on the allow list
+pub async fn enforce_access_policy(
+    State(policy): State<Arc<AccessPolicy>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if policy.is_empty() {
+        return next.run(request).await;
+    }
+
+    let client = request
+        .extensions()
+        .get::<ClientIp>()
+        .and_then(|ClientIp(ip)| *ip);
+
+    match client {
+        Some(ip) if policy.is_allowed(&ip) => next.run(request).await,
+        Some(ip) => {
+            log_warn!(
+                "AccessControl",
+                format!("Rejected request from {} to {}", ip, request.uri().path())
+            );
+            create_error_response(StatusCode::FORBIDDEN, "Access denied for client address")
+        },
+        None => create_error_response(
+            StatusCode::FORBIDDEN,
+            "Access denied: client address unknown",
+        ),
+    }
+}
@@ -0,0 +1,114 @@
+// Per-request analytics recording for the API server
+use crate::analytics::{AnalyticsRecorder, RequestRecord};
+use crate::authorized_apps::bearer_token;
+use crate::canary::CanaryArm;
+use crate::language::DetectedLanguage;
+use crate::middleware::access_control::ClientIp;
+use crate::middleware::request_id::RequestId;
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::Response,
+};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use std::time::Instant;
+
+/// Token usage of a completion, attached by handlers as a response extension
+/// so the analytics layer can attribute it
+#[derive(Debug, Clone)]
+pub struct TokenUsage {
+    pub model: String,
+    pub total_tokens: u64,
+}
+
+/// Why a request failed, attached by error responses as a response extension
+/// so the analytics record of the request says what went wrong
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailureReason(pub String);
+
+/// Stable label for the bearer key a client presented. Only a short hash is
+/// kept, so the key itself never reaches the analytics database.
+pub fn key_fingerprint(headers: &HeaderMap) -> Option<String> {
+    bearer_token(headers).map(fingerprint)
+}
+
+/// Fingerprint of an API key, as recorded by [`key_fingerprint`]
+pub fn fingerprint(api_key: &str) -> String {
+    let digest = Sha256::digest(api_key.as_bytes());
+    let hex: String = digest
+        .iter()
+        .take(6)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("key_{}", hex)
+}
+
+/// Record API requests (`/v1/...` and the Ollama-compatible `/api/...`)
+/// through the write-behind recorder. Only a channel send happens on the
+/// request path.
+pub async fn record_analytics(
+    State(recorder): State<AnalyticsRecorder>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string());
+
+    let is_api_route = |route: &String| {
+        route.starts_with("/v1/") || route.starts_with("/api/") || route.starts_with("/openai/")
+    };
+    let Some(route) = route.filter(is_api_route) else {
+        return next.run(request).await;
+    };
+
+    let started = Instant::now();
+    let timestamp = Utc::now();
+    let method = request.method().to_string();
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .map(|RequestId(id)| id.clone());
+    let client = request
+        .extensions()
+        .get::<ClientIp>()
+        .and_then(|ClientIp(ip)| ip.map(|ip| ip.to_string()));
+    let api_key = key_fingerprint(request.headers());
+
+    let response = next.run(request).await;
+    let usage = response.extensions().get::<TokenUsage>().cloned();
+    let language = response
+        .extensions()
+        .get::<DetectedLanguage>()
+        .map(|language| language.code.clone());
+    let variant = response
+        .extensions()
+        .get::<CanaryArm>()
+        .map(|arm| arm.as_str().to_string());
+    let error = response
+        .extensions()
+        .get::<FailureReason>()
+        .map(|FailureReason(reason)| reason.clone());
+
+    recorder.record(RequestRecord {
+        timestamp,
+        method,
+        route,
+        status: response.status().as_u16(),
+        latency_ms: started.elapsed().as_millis() as u64,
+        sample_weight: 1,
+        request_id,
+        model: usage.as_ref().map(|usage| usage.model.clone()),
+        tokens: usage.map_or(0, |usage| usage.total_tokens),
+        client,
+        api_key,
+        language,
+        variant,
+        error,
+    });
+
+    response
+}
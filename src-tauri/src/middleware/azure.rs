@@ -0,0 +1,45 @@
+// Azure OpenAI-compatible chat completions
+//
+// Tools made for Azure OpenAI address a deployment rather than a model,
+// `/openai/deployments/{deployment}/chat/completions?api-version=...`, and
+// send their key as an `api-key` header, which `authorized_apps` accepts. The
+// deployment becomes the model of the request, so model aliases can map
+// deployment names onto models, and the request continues as an ordinary chat
+// completion. The `api-version` parameter is accepted and ignored.
+use crate::middleware::buffer_body;
+use axum::{
+    body::Body,
+    extract::{Path, Request},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use serde_json::Value;
+
+/// Path of chat completions on a deployment
+pub const DEPLOYMENT_COMPLETIONS_PATH: &str = "/openai/deployments/:deployment/chat/completions";
+
+/// Ask for the deployment in the path as the model
+pub async fn deployment_as_model(
+    Path(deployment): Path<String>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (mut parts, bytes) = match buffer_body(request).await {
+        Ok(buffered) => buffered,
+        Err(response) => return response,
+    };
+    let body = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(Value::Object(mut object)) => {
+            object.insert("model".to_string(), Value::String(deployment));
+            let bytes = Value::Object(object).to_string();
+            parts
+                .headers
+                .insert(header::CONTENT_LENGTH, HeaderValue::from(bytes.len()));
+            Body::from(bytes)
+        },
+        // Malformed, which the handler answers
+        _ => Body::from(bytes),
+    };
+    next.run(Request::from_parts(parts, body)).await
+}
@@ -0,0 +1,206 @@
+// Backpressure for completion requests
+//
+// Completion requests beyond `backpressure.max_in_flight` are turned away at
+// once with `429 Too Many Requests` instead of piling up until they time out,
+// and so is every completion request while all ChatGPT accounts are cooling
+// down after a rate limit. `Retry-After` tells clients when to come back: when
+// the first account's cooldown ends, or after the average duration of recent
+// requests when the server is full. Overloads are published as events for
+// the dashboard, at most one per `EVENT_INTERVAL`.
+use crate::accounts::AccountPool;
+use crate::api_error::ApiError;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, Semaphore};
+use ts_rs::TS;
+
+/// Minimum time between two overload events
+pub const EVENT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Retry-After bounds in seconds
+const MIN_RETRY_AFTER: u64 = 1;
+const MAX_RETRY_AFTER: u64 = 300;
+
+fn clamp_retry_after(secs: u64) -> u32 {
+    secs.clamp(MIN_RETRY_AFTER, MAX_RETRY_AFTER) as u32
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum OverloadReason {
+    /// `max_in_flight` completion requests are already running
+    QueueFull,
+    /// Every ChatGPT account is cooling down after a rate limit
+    UpstreamRateLimited,
+}
+
+/// Requests are being turned away with 429
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export)]
+pub struct OverloadEvent {
+    pub reason: OverloadReason,
+    pub retry_after_secs: u32,
+    pub in_flight: usize,
+    pub max_in_flight: usize,
+    pub at: DateTime<Utc>,
+}
+
+/// Admission control shared by the completion routes of a running server
+#[derive(Debug)]
+pub struct Backpressure {
+    max_in_flight: usize,
+    slots: Arc<Semaphore>,
+    accounts: Arc<AccountPool>,
+    /// Moving average of completion request durations in milliseconds
+    average_ms: AtomicU64,
+    events: broadcast::Sender<OverloadEvent>,
+    last_event: Mutex<Option<Instant>>,
+}
+
+impl Backpressure {
+    pub fn new(
+        max_in_flight: usize,
+        accounts: Arc<AccountPool>,
+        events: broadcast::Sender<OverloadEvent>,
+    ) -> Self {
+        let max_in_flight = max_in_flight.max(1);
+        Self {
+            max_in_flight,
+            slots: Arc::new(Semaphore::new(max_in_flight)),
+            accounts,
+            average_ms: AtomicU64::new(0),
+            events,
+            last_event: Mutex::new(None),
+        }
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.max_in_flight - self.slots.available_permits()
+    }
+
+    /// Fold a finished request into the average duration
+    pub fn record_duration(&self, duration: Duration) {
+        let sample = duration.as_millis().min(u64::MAX as u128) as u64;
+        let _ = self
+            .average_ms
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |average| {
+                Some(if average == 0 {
+                    sample
+                } else {
+                    (average * 4 + sample) / 5
+                })
+            });
+    }
+
+    /// Seconds until a slot is likely free: the average request duration
+    pub fn queue_retry_after(&self) -> u32 {
+        clamp_retry_after(self.average_ms.load(Ordering::Relaxed).div_ceil(1000))
+    }
+
+    /// Publish an overload unless one was published within `EVENT_INTERVAL`
+    pub fn publish(&self, reason: OverloadReason, retry_after_secs: u32) {
+        let now = Instant::now();
+        {
+            let mut last_event = self.last_event.lock().unwrap_or_else(|e| e.into_inner());
+            if last_event.is_some_and(|last| now.duration_since(last) < EVENT_INTERVAL) {
+                return;
+            }
+            *last_event = Some(now);
+        }
+
+        // No receivers only means nobody is listening
+        let _ = self.events.send(OverloadEvent {
+            reason,
+            retry_after_secs,
+            in_flight: self.in_flight(),
+            max_in_flight: self.max_in_flight,
+            at: Utc::now(),
+        });
+    }
+}
+
+fn overloaded(path: &str, reason: OverloadReason, retry_after_secs: u32) -> Response {
+    let message = match reason {
+        OverloadReason::QueueFull => "The server is busy with other requests",
+        OverloadReason::UpstreamRateLimited => "Every ChatGPT account is rate limited",
+    };
+    let message = format!("{}. Retry in {} seconds.", message, retry_after_secs);
+
+    let mut response = if path.starts_with("/api/") {
+        // Ollama clients expect a plain error string
+        (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(serde_json::json!({ "error": message })),
+        )
+            .into_response()
+    } else {
+        ApiError::new(StatusCode::TOO_MANY_REQUESTS, message).into_response()
+    };
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+    response
+}
+
+/// Turn completion requests away while the server or the upstream is
+/// saturated. Admitted requests hold their slot until the response body,
+/// streams included, has been sent.
+pub async fn apply_backpressure(
+    State(backpressure): State<Arc<Backpressure>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_string();
+
+    if let Some(cooldown) = backpressure.accounts.saturated_for().await {
+        let retry_after = clamp_retry_after((cooldown.as_millis() as u64).div_ceil(1000));
+        backpressure.publish(OverloadReason::UpstreamRateLimited, retry_after);
+        return overloaded(&path, OverloadReason::UpstreamRateLimited, retry_after);
+    }
+
+    let Ok(slot) = backpressure.slots.clone().try_acquire_owned() else {
+        let retry_after = backpressure.queue_retry_after();
+        backpressure.publish(OverloadReason::QueueFull, retry_after);
+        return overloaded(&path, OverloadReason::QueueFull, retry_after);
+    };
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+    let (parts, body) = response.into_parts();
+
+    // Release the slot once the body is done, whether sent or dropped
+    let admission = Admission {
+        backpressure,
+        started,
+        _slot: slot,
+    };
+    let body = body.into_data_stream().map(move |chunk| {
+        let _ = &admission;
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
+struct Admission {
+    backpressure: Arc<Backpressure>,
+    started: Instant,
+    _slot: tokio::sync::OwnedSemaphorePermit,
+}
+
+impl Drop for Admission {
+    fn drop(&mut self) {
+        self.backpressure.record_duration(self.started.elapsed());
+    }
+}
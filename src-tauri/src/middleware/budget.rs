@@ -0,0 +1,153 @@
+// Token budget enforcement for completion routes
+//
+// Requests made with the key of an app that has a token budget are counted
+// against it: the usage the handler reports right away, and for streams the
+// text of the completion as it is sent. Once a budget is used up the app's
+// requests are refused with `429 insufficient_quota` until the budget resets.
+// Every answered request carries the tokens left in `x-mindlink-budget-remaining`.
+use crate::api_error::ApiError;
+use crate::authorized_apps::{bearer_token, find_app, AuthorizedApp};
+use crate::budgets::{BudgetLedger, PeriodBudget, StreamTally};
+use crate::middleware::analytics::TokenUsage;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use chrono::Utc;
+use futures_util::StreamExt;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub const REMAINING_HEADER: &str = "x-mindlink-budget-remaining";
+
+/// The ledger together with the apps whose budgets it tracks
+#[derive(Debug)]
+pub struct TokenBudgets {
+    ledger: Arc<BudgetLedger>,
+    apps: Arc<RwLock<Vec<AuthorizedApp>>>,
+}
+
+impl TokenBudgets {
+    pub fn new(ledger: Arc<BudgetLedger>, apps: Arc<RwLock<Vec<AuthorizedApp>>>) -> Self {
+        Self { ledger, apps }
+    }
+
+    fn record(&self, app_id: &str, tokens: u64) {
+        self.ledger.record(app_id, tokens, Utc::now());
+        self.ledger.save_in_background();
+    }
+}
+
+fn exhausted(path: &str, app: &AuthorizedApp, budget: &PeriodBudget) -> Response {
+    let message = format!(
+        "App '{}' has used its {} token budget of {} tokens. It resets at {}.",
+        app.name,
+        budget.period.as_str(),
+        budget.limit,
+        budget.resets_at.to_rfc3339()
+    );
+
+    let mut response = if path.starts_with("/api/") {
+        // Ollama clients expect a plain error string
+        (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(serde_json::json!({ "error": message })),
+        )
+            .into_response()
+    } else {
+        ApiError {
+            error_type: "insufficient_quota",
+            code: Some("insufficient_quota"),
+            ..ApiError::new(StatusCode::TOO_MANY_REQUESTS, message)
+        }
+        .into_response()
+    };
+    let retry_after = (budget.resets_at - Utc::now()).num_seconds().max(1);
+    let headers = response.headers_mut();
+    headers.insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+    headers.insert(REMAINING_HEADER, HeaderValue::from(0));
+    response
+}
+
+fn is_stream(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| {
+            value.starts_with("text/event-stream") || value.starts_with("application/x-ndjson")
+        })
+}
+
+/// Refuse requests of apps that used up a token budget, and count what the
+/// others consume
+pub async fn enforce_budget(
+    State(budgets): State<Arc<TokenBudgets>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let app = {
+        let apps = budgets.apps.read().await;
+        bearer_token(request.headers())
+            .and_then(|api_key| find_app(&apps, api_key))
+            .filter(|app| app.has_token_budget())
+            .cloned()
+    };
+    let Some(app) = app else {
+        return next.run(request).await;
+    };
+
+    let path = request.uri().path().to_string();
+    if let Some(budget) = budgets.ledger.budget(&app, Utc::now()).exhausted() {
+        return exhausted(&path, &app, budget);
+    }
+
+    let response = next.run(request).await;
+    // Streams only know their prompt size by now
+    let tokens = response
+        .extensions()
+        .get::<TokenUsage>()
+        .map_or(0, |usage| usage.total_tokens);
+    budgets.record(&app.id, tokens);
+
+    let (mut parts, body) = response.into_parts();
+    if let Some(remaining) = budgets.ledger.budget(&app, Utc::now()).remaining() {
+        parts
+            .headers
+            .insert(REMAINING_HEADER, HeaderValue::from(remaining));
+    }
+    if !is_stream(&parts.headers) {
+        return Response::from_parts(parts, body);
+    }
+
+    let mut completion = Completion {
+        budgets,
+        app_id: app.id,
+        tally: StreamTally::default(),
+    };
+    let body = body.into_data_stream().map(move |chunk| {
+        if let Ok(chunk) = &chunk {
+            completion.tally.push(chunk);
+        }
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
+/// A streamed completion, counted against the budgets when it ends
+struct Completion {
+    budgets: Arc<TokenBudgets>,
+    app_id: String,
+    tally: StreamTally,
+}
+
+impl Drop for Completion {
+    fn drop(&mut self) {
+        let tokens = self.tally.tokens();
+        if tokens > 0 {
+            self.budgets.record(&self.app_id, tokens);
+        }
+    }
+}
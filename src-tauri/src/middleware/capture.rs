@@ -0,0 +1,153 @@
+// Request/response capture for debugging
+//
+// Buffers the request body, lets the request through and records the response
+// body as it is sent. The exchange is written once the response body is done,
+// so streamed completions are captured in full.
+use crate::capture::{
+    new_capture_id, Capture, CaptureStore, Sanitizer, MAX_BODY_BYTES, REPLAY_HEADER,
+};
+use crate::log_error;
+use crate::middleware::buffer_body;
+use crate::middleware::request_id::RequestId;
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use std::sync::Arc;
+use std::time::Instant;
+
+#[derive(Debug)]
+pub struct Capturer {
+    store: CaptureStore,
+    sanitizer: Sanitizer,
+}
+
+impl Capturer {
+    pub fn new(store: CaptureStore, sanitizer: Sanitizer) -> Self {
+        Self { store, sanitizer }
+    }
+}
+
+pub async fn capture_exchange(
+    State(capturer): State<Arc<Capturer>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let started = Instant::now();
+    let captured_at = Utc::now();
+
+    let (parts, body) = match buffer_body(request).await {
+        Ok(buffered) => buffered,
+        Err(response) => return response,
+    };
+
+    let request_id = parts
+        .extensions
+        .get::<RequestId>()
+        .map(|RequestId(id)| id.clone());
+    let replay_of = parts
+        .headers
+        .get(REPLAY_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let method = parts.method.to_string();
+    let path = parts
+        .uri
+        .path_and_query()
+        .map_or_else(|| parts.uri.path().to_string(), |path| path.to_string());
+    let request_headers = parts.headers.clone();
+
+    let response = next
+        .run(Request::from_parts(parts, Body::from(body.clone())))
+        .await;
+    let (parts, response_body) = response.into_parts();
+
+    let mut recording = Recording {
+        capturer,
+        started,
+        captured_at,
+        request_id,
+        replay_of,
+        method,
+        path,
+        request_headers,
+        request_body: body,
+        status: parts.status.as_u16(),
+        response_headers: parts.headers.clone(),
+        response_body: Vec::new(),
+    };
+    let response_body = response_body.into_data_stream().map(move |chunk| {
+        if let Ok(chunk) = &chunk {
+            recording.push(chunk);
+        }
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(response_body))
+}
+
+/// An exchange in progress, written to the store when dropped
+struct Recording {
+    capturer: Arc<Capturer>,
+    started: Instant,
+    captured_at: DateTime<Utc>,
+    request_id: Option<String>,
+    replay_of: Option<String>,
+    method: String,
+    path: String,
+    request_headers: HeaderMap,
+    request_body: Bytes,
+    status: u16,
+    response_headers: HeaderMap,
+    response_body: Vec<u8>,
+}
+
+impl Recording {
+    fn push(&mut self, chunk: &Bytes) {
+        // One byte past the limit marks the body as cut off
+        let room = (MAX_BODY_BYTES + 1).saturating_sub(self.response_body.len());
+        self.response_body
+            .extend_from_slice(&chunk[..chunk.len().min(room)]);
+    }
+}
+
+impl Drop for Recording {
+    fn drop(&mut self) {
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+
+        let capturer = self.capturer.clone();
+        let request_headers = std::mem::take(&mut self.request_headers);
+        let request_body = std::mem::take(&mut self.request_body);
+        let response_headers = std::mem::take(&mut self.response_headers);
+        let response_body = std::mem::take(&mut self.response_body);
+        let mut capture = Capture {
+            id: new_capture_id(self.captured_at),
+            request_id: self.request_id.take(),
+            replay_of: self.replay_of.take(),
+            captured_at: self.captured_at,
+            method: std::mem::take(&mut self.method),
+            path: std::mem::take(&mut self.path),
+            status: self.status,
+            duration_ms: self.started.elapsed().as_millis() as u64,
+            request: Default::default(),
+            response: Default::default(),
+        };
+
+        // Masking runs regular expressions over up to a megabyte per body
+        runtime.spawn_blocking(move || {
+            capture.request = capturer.sanitizer.message(&request_headers, &request_body);
+            capture.response = capturer
+                .sanitizer
+                .message(&response_headers, &response_body);
+            if let Err(e) = capturer.store.save(&capture) {
+                log_error!("Capture", e);
+            }
+        });
+    }
+}
@@ -0,0 +1,54 @@
+// Cloudflare Access check of requests through the named tunnel
+//
+// Requests for the tunnel's hostname have to carry a valid Access JWT once
+// the tunnel is protected; the others are refused with 403. Local clients
+// use other hostnames and are not affected.
+use crate::cloudflare_access::{SharedAccessVerifier, JWT_HEADER};
+use crate::log_warn;
+use crate::managers::server_manager::create_error_response;
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+
+/// Refuse requests for the protected hostname without a valid Access JWT
+pub async fn require_access_jwt(
+    State(verifier): State<SharedAccessVerifier>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let verifier = verifier.read().await.clone();
+    let Some(verifier) = verifier else {
+        return next.run(request).await;
+    };
+
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok())
+        .or_else(|| request.uri().host())
+        .unwrap_or_default();
+    if !verifier.protects(host) {
+        return next.run(request).await;
+    }
+
+    let token = request
+        .headers()
+        .get(JWT_HEADER)
+        .and_then(|value| value.to_str().ok());
+    let Some(token) = token else {
+        return create_error_response(StatusCode::FORBIDDEN, "Cloudflare Access token missing");
+    };
+    match verifier.verify(token).await {
+        Ok(()) => next.run(request).await,
+        Err(e) => {
+            log_warn!(
+                "CloudflareAccess",
+                format!("Refused request to {}: {}", request.uri().path(), e)
+            );
+            create_error_response(StatusCode::FORBIDDEN, "Cloudflare Access token invalid")
+        },
+    }
+}
@@ -0,0 +1,566 @@
+// Prometheus metrics for the API server
+use crate::request_log::StreamThroughput;
+use axum::{
+    body::{Body, HttpBody},
+    extract::{MatchedPath, Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use futures_util::StreamExt;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// Upper bounds (seconds) of the request latency histogram buckets
+const LATENCY_BUCKETS: [f64; 12] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+#[derive(Debug, Default, Clone)]
+struct Histogram {
+    /// Non-cumulative count per bucket; the last slot is `+Inf`
+    buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        let index = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.buckets[index] += 1;
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+/// Traffic of one tunnel and its round trip time as measured by probes
+#[derive(Debug, Default, Clone)]
+struct TunnelCounters {
+    requests: u64,
+    bytes_received: u64,
+    bytes_sent: u64,
+    probe_failures: u64,
+    round_trip: Histogram,
+    last_round_trip: Option<Duration>,
+}
+
+/// Traffic through a tunnel, for the dashboard
+#[derive(Debug, Clone, Serialize)]
+pub struct TunnelTraffic {
+    /// Public hostname of the tunnel
+    pub tunnel: String,
+    pub requests: u64,
+    /// Request body bytes as declared by clients
+    pub bytes_received: u64,
+    /// Response body bytes sent back through the tunnel
+    pub bytes_sent: u64,
+    pub probes: u64,
+    pub probe_failures: u64,
+    pub last_round_trip_ms: Option<f64>,
+    pub mean_round_trip_ms: Option<f64>,
+}
+
+/// Request, latency, upstream error, stream and tunnel metrics, rendered in the
+/// Prometheus text exposition format. Counters live for the lifetime of the
+/// [`crate::managers::server_manager::ServerManager`] so restarts don't reset them.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// (method, route, status) -> count
+    requests: Mutex<BTreeMap<(String, String, u16), u64>>,
+    /// route -> latency histogram
+    latency: Mutex<BTreeMap<String, Histogram>>,
+    /// upstream -> failed request count
+    upstream_errors: Mutex<BTreeMap<String, u64>>,
+    /// outcome -> requests mirrored to the shadow backend
+    shadow_requests: Mutex<BTreeMap<&'static str, u64>>,
+    /// (sum, count) of the similarity of compared shadow answers
+    shadow_similarity: Mutex<(f64, u64)>,
+    active_streams: AtomicI64,
+    /// Time to first token of streamed replies
+    stream_ttft: Mutex<Histogram>,
+    /// (tokens, seconds) streamed after the first token
+    stream_generation: Mutex<(u64, f64)>,
+    /// Public hostname -> traffic through that tunnel
+    tunnels: Mutex<BTreeMap<String, TunnelCounters>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_request(&self, method: &str, route: &str, status: u16, seconds: f64) {
+        if let Ok(mut requests) = self.requests.lock() {
+            *requests
+                .entry((method.to_string(), route.to_string(), status))
+                .or_insert(0) += 1;
+        }
+        if let Ok(mut latency) = self.latency.lock() {
+            latency
+                .entry(route.to_string())
+                .or_default()
+                .observe(seconds);
+        }
+    }
+
+    pub fn record_upstream_error(&self, upstream: &str) {
+        if let Ok(mut errors) = self.upstream_errors.lock() {
+            *errors.entry(upstream.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    /// Count a mirrored request, with the similarity of the two answers when
+    /// the shadow backend answered
+    pub fn record_shadow(&self, similarity: Option<f64>) {
+        let outcome = if similarity.is_some() {
+            "compared"
+        } else {
+            "failed"
+        };
+        if let Ok(mut requests) = self.shadow_requests.lock() {
+            *requests.entry(outcome).or_insert(0) += 1;
+        }
+        if let (Some(similarity), Ok(mut summary)) = (similarity, self.shadow_similarity.lock()) {
+            summary.0 += similarity;
+            summary.1 += 1;
+        }
+    }
+
+    /// Count a stream as active until the returned guard is dropped
+    pub fn stream_started(self: &Arc<Self>) -> ActiveStreamGuard {
+        self.active_streams.fetch_add(1, Ordering::Relaxed);
+        ActiveStreamGuard {
+            metrics: self.clone(),
+        }
+    }
+
+    pub fn active_streams(&self) -> i64 {
+        self.active_streams.load(Ordering::Relaxed)
+    }
+
+    /// Observe how fast a finished stream started and went on
+    pub fn record_stream(&self, throughput: &StreamThroughput) {
+        if let Ok(mut ttft) = self.stream_ttft.lock() {
+            ttft.observe(throughput.ttft_ms / 1000.0);
+        }
+        if let (Some(tokens), Ok(mut generation)) =
+            (throughput.completion_tokens, self.stream_generation.lock())
+        {
+            generation.0 += tokens;
+            generation.1 += throughput.generation_ms / 1000.0;
+        }
+    }
+
+    /// Count a request that came through the tunnel serving `tunnel`
+    pub fn record_tunnel_request(&self, tunnel: &str, bytes_received: u64) {
+        if let Ok(mut tunnels) = self.tunnels.lock() {
+            let counters = tunnels.entry(tunnel.to_string()).or_default();
+            counters.requests += 1;
+            counters.bytes_received += bytes_received;
+        }
+    }
+
+    pub fn record_tunnel_bytes_sent(&self, tunnel: &str, bytes: u64) {
+        if let Ok(mut tunnels) = self.tunnels.lock() {
+            tunnels.entry(tunnel.to_string()).or_default().bytes_sent += bytes;
+        }
+    }
+
+    /// Observe the round trip of a probe through the tunnel serving
+    /// `tunnel`, `None` when the probe got no answer
+    pub fn record_tunnel_probe(&self, tunnel: &str, round_trip: Option<Duration>) {
+        if let Ok(mut tunnels) = self.tunnels.lock() {
+            let counters = tunnels.entry(tunnel.to_string()).or_default();
+            match round_trip {
+                Some(round_trip) => {
+                    counters.round_trip.observe(round_trip.as_secs_f64());
+                    counters.last_round_trip = Some(round_trip);
+                },
+                None => counters.probe_failures += 1,
+            }
+        }
+    }
+
+    /// Traffic of every tunnel requests or probes went through
+    pub fn tunnel_traffic(&self) -> Vec<TunnelTraffic> {
+        let Ok(tunnels) = self.tunnels.lock() else {
+            return Vec::new();
+        };
+        tunnels
+            .iter()
+            .map(|(tunnel, counters)| TunnelTraffic {
+                tunnel: tunnel.clone(),
+                requests: counters.requests,
+                bytes_received: counters.bytes_received,
+                bytes_sent: counters.bytes_sent,
+                probes: counters.round_trip.count + counters.probe_failures,
+                probe_failures: counters.probe_failures,
+                last_round_trip_ms: counters
+                    .last_round_trip
+                    .map(|round_trip| round_trip.as_secs_f64() * 1000.0),
+                mean_round_trip_ms: (counters.round_trip.count > 0)
+                    .then(|| counters.round_trip.sum * 1000.0 / counters.round_trip.count as f64),
+            })
+            .collect()
+    }
+
+    /// Render all metrics in the Prometheus text format (version 0.0.4)
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str("# HELP mindlink_http_requests_total Total HTTP requests handled.\n");
+        out.push_str("# TYPE mindlink_http_requests_total counter\n");
+        if let Ok(requests) = self.requests.lock() {
+            for ((method, route, status), count) in requests.iter() {
+                let _ = writeln!(
+                    out,
+                    "mindlink_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+                    escape(method),
+                    escape(route),
+                    status,
+                    count
+                );
+            }
+        }
+
+        out.push_str("# HELP mindlink_http_request_duration_seconds Time to response headers.\n");
+        out.push_str("# TYPE mindlink_http_request_duration_seconds histogram\n");
+        if let Ok(latency) = self.latency.lock() {
+            for (route, histogram) in latency.iter() {
+                let route = escape(route);
+                let mut cumulative = 0;
+                for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets.iter()) {
+                    cumulative += count;
+                    let _ = writeln!(
+                        out,
+                        "mindlink_http_request_duration_seconds_bucket{{route=\"{}\",le=\"{}\"}} {}",
+                        route, bound, cumulative
+                    );
+                }
+                let _ = writeln!(
+                    out,
+                    "mindlink_http_request_duration_seconds_bucket{{route=\"{}\",le=\"+Inf\"}} {}",
+                    route, histogram.count
+                );
+                let _ = writeln!(
+                    out,
+                    "mindlink_http_request_duration_seconds_sum{{route=\"{}\"}} {}",
+                    route, histogram.sum
+                );
+                let _ = writeln!(
+                    out,
+                    "mindlink_http_request_duration_seconds_count{{route=\"{}\"}} {}",
+                    route, histogram.count
+                );
+            }
+        }
+
+        out.push_str(
+            "# HELP mindlink_upstream_errors_total Failed requests to upstream providers.\n",
+        );
+        out.push_str("# TYPE mindlink_upstream_errors_total counter\n");
+        if let Ok(errors) = self.upstream_errors.lock() {
+            for (upstream, count) in errors.iter() {
+                let _ = writeln!(
+                    out,
+                    "mindlink_upstream_errors_total{{upstream=\"{}\"}} {}",
+                    escape(upstream),
+                    count
+                );
+            }
+        }
+
+        out.push_str(
+            "# HELP mindlink_shadow_requests_total Requests mirrored to the shadow backend.\n",
+        );
+        out.push_str("# TYPE mindlink_shadow_requests_total counter\n");
+        if let Ok(requests) = self.shadow_requests.lock() {
+            for (outcome, count) in requests.iter() {
+                let _ = writeln!(
+                    out,
+                    "mindlink_shadow_requests_total{{outcome=\"{}\"}} {}",
+                    outcome, count
+                );
+            }
+        }
+
+        out.push_str(
+            "# HELP mindlink_shadow_similarity Word overlap of primary and shadow answers.\n",
+        );
+        out.push_str("# TYPE mindlink_shadow_similarity summary\n");
+        if let Ok(summary) = self.shadow_similarity.lock() {
+            let _ = writeln!(out, "mindlink_shadow_similarity_sum {}", summary.0);
+            let _ = writeln!(out, "mindlink_shadow_similarity_count {}", summary.1);
+        }
+
+        out.push_str("# HELP mindlink_active_streams Streaming responses currently in flight.\n");
+        out.push_str("# TYPE mindlink_active_streams gauge\n");
+        let _ = writeln!(out, "mindlink_active_streams {}", self.active_streams());
+
+        out.push_str(
+            "# HELP mindlink_stream_first_token_seconds Time from request to the first streamed token.\n",
+        );
+        out.push_str("# TYPE mindlink_stream_first_token_seconds histogram\n");
+        if let Ok(ttft) = self.stream_ttft.lock() {
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(ttft.buckets.iter()) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "mindlink_stream_first_token_seconds_bucket{{le=\"{}\"}} {}",
+                    bound, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "mindlink_stream_first_token_seconds_bucket{{le=\"+Inf\"}} {}",
+                ttft.count
+            );
+            let _ = writeln!(out, "mindlink_stream_first_token_seconds_sum {}", ttft.sum);
+            let _ = writeln!(
+                out,
+                "mindlink_stream_first_token_seconds_count {}",
+                ttft.count
+            );
+        }
+
+        // Their rates divide into tokens per second
+        if let Ok(generation) = self.stream_generation.lock() {
+            out.push_str(
+                "# HELP mindlink_stream_tokens_total Tokens of streamed replies, estimated.\n",
+            );
+            out.push_str("# TYPE mindlink_stream_tokens_total counter\n");
+            let _ = writeln!(out, "mindlink_stream_tokens_total {}", generation.0);
+            out.push_str(
+                "# HELP mindlink_stream_generation_seconds_total Time from the first to the last token of streamed replies.\n",
+            );
+            out.push_str("# TYPE mindlink_stream_generation_seconds_total counter\n");
+            let _ = writeln!(
+                out,
+                "mindlink_stream_generation_seconds_total {}",
+                generation.1
+            );
+        }
+
+        if let Ok(tunnels) = self.tunnels.lock() {
+            render_tunnels(&mut out, &tunnels);
+        }
+
+        out
+    }
+}
+
+fn render_tunnels(out: &mut String, tunnels: &BTreeMap<String, TunnelCounters>) {
+    out.push_str("# HELP mindlink_tunnel_requests_total Requests that came through a tunnel.\n");
+    out.push_str("# TYPE mindlink_tunnel_requests_total counter\n");
+    for (tunnel, counters) in tunnels {
+        let _ = writeln!(
+            out,
+            "mindlink_tunnel_requests_total{{tunnel=\"{}\"}} {}",
+            escape(tunnel),
+            counters.requests
+        );
+    }
+
+    out.push_str("# HELP mindlink_tunnel_bytes_total Body bytes transferred through a tunnel.\n");
+    out.push_str("# TYPE mindlink_tunnel_bytes_total counter\n");
+    for (tunnel, counters) in tunnels {
+        let tunnel = escape(tunnel);
+        let _ = writeln!(
+            out,
+            "mindlink_tunnel_bytes_total{{tunnel=\"{}\",direction=\"received\"}} {}",
+            tunnel, counters.bytes_received
+        );
+        let _ = writeln!(
+            out,
+            "mindlink_tunnel_bytes_total{{tunnel=\"{}\",direction=\"sent\"}} {}",
+            tunnel, counters.bytes_sent
+        );
+    }
+
+    out.push_str(
+        "# HELP mindlink_tunnel_round_trip_seconds Round trip of probes through the public URL.\n",
+    );
+    out.push_str("# TYPE mindlink_tunnel_round_trip_seconds histogram\n");
+    for (tunnel, counters) in tunnels {
+        let tunnel = escape(tunnel);
+        let histogram = &counters.round_trip;
+        let mut cumulative = 0;
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets.iter()) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "mindlink_tunnel_round_trip_seconds_bucket{{tunnel=\"{}\",le=\"{}\"}} {}",
+                tunnel, bound, cumulative
+            );
+        }
+        let _ = writeln!(
+            out,
+            "mindlink_tunnel_round_trip_seconds_bucket{{tunnel=\"{}\",le=\"+Inf\"}} {}",
+            tunnel, histogram.count
+        );
+        let _ = writeln!(
+            out,
+            "mindlink_tunnel_round_trip_seconds_sum{{tunnel=\"{}\"}} {}",
+            tunnel, histogram.sum
+        );
+        let _ = writeln!(
+            out,
+            "mindlink_tunnel_round_trip_seconds_count{{tunnel=\"{}\"}} {}",
+            tunnel, histogram.count
+        );
+    }
+
+    out.push_str(
+        "# HELP mindlink_tunnel_probe_failures_total Probes through the public URL that got no answer.\n",
+    );
+    out.push_str("# TYPE mindlink_tunnel_probe_failures_total counter\n");
+    for (tunnel, counters) in tunnels {
+        let _ = writeln!(
+            out,
+            "mindlink_tunnel_probe_failures_total{{tunnel=\"{}\"}} {}",
+            escape(tunnel),
+            counters.probe_failures
+        );
+    }
+}
+
+/// Decrements the active stream gauge when the stream finishes
+#[derive(Debug)]
+pub struct ActiveStreamGuard {
+    metrics: Arc<Metrics>,
+}
+
+impl Drop for ActiveStreamGuard {
+    fn drop(&mut self) {
+        self.metrics.active_streams.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Count requests and observe their latency. Routes are labelled with the
+/// matched route template rather than the raw path to keep cardinality bounded.
+pub async fn track_metrics(
+    State(metrics): State<Arc<Metrics>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(request).await;
+
+    metrics.record_request(
+        &method,
+        &route,
+        response.status().as_u16(),
+        started.elapsed().as_secs_f64(),
+    );
+
+    response
+}
+
+/// State of [`track_tunnel_traffic`]
+#[derive(Debug, Clone)]
+pub struct TunnelTracking {
+    pub metrics: Arc<Metrics>,
+    /// Public URL of the tunnel, kept current by the tunnel manager
+    pub tunnel_url: Arc<RwLock<Option<String>>>,
+}
+
+/// Hostname of the tunnel `url`, as requests through it carry in `Host`
+pub fn tunnel_host(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    url.host_str().map(str::to_ascii_lowercase)
+}
+
+/// Adds the bytes of the response body it is moved into once it ends
+struct SentBytes {
+    metrics: Arc<Metrics>,
+    tunnel: String,
+    bytes: u64,
+}
+
+impl Drop for SentBytes {
+    fn drop(&mut self) {
+        self.metrics
+            .record_tunnel_bytes_sent(&self.tunnel, self.bytes);
+    }
+}
+
+/// Count the requests that came through the tunnel, told apart from local
+/// ones by the tunnel's hostname, and the bytes they transferred
+pub async fn track_tunnel_traffic(
+    State(tracking): State<TunnelTracking>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let tunnel = tracking
+        .tunnel_url
+        .read()
+        .await
+        .as_deref()
+        .and_then(tunnel_host);
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok())
+        .map(|host| host.rsplit_once(':').map_or(host, |(name, _)| name));
+    let Some(tunnel) =
+        tunnel.filter(|tunnel| host.is_some_and(|host| host.eq_ignore_ascii_case(tunnel)))
+    else {
+        return next.run(request).await;
+    };
+
+    let bytes_received = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0);
+    tracking
+        .metrics
+        .record_tunnel_request(&tunnel, bytes_received);
+
+    let response = next.run(request).await;
+
+    // Bodies of known length are counted right away, streams as they go
+    if let Some(length) = response.body().size_hint().exact() {
+        tracking.metrics.record_tunnel_bytes_sent(&tunnel, length);
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let mut sent = SentBytes {
+        metrics: tracking.metrics,
+        tunnel,
+        bytes: 0,
+    };
+    let body = body.into_data_stream().map(move |chunk| {
+        // Moves the whole counter in, so it is dropped with the body
+        let sent = &mut sent;
+        if let Ok(chunk) = &chunk {
+            sent.bytes += chunk.len() as u64;
+        }
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(body))
+}
@@ -0,0 +1,58 @@
+//! # HTTP Middleware
+//!
+//! Request-level layers applied by the API server router in
+//! [`crate::managers::server_manager`] before requests reach the handlers.
+//!
+//! - [`access_control`] - Client IP allow/deny lists
+//! - [`analytics`] - Request records for the SQLite analytics store
+//! - [`azure`] - Deployment routes of Azure OpenAI clients
+//! - [`backpressure`] - 429 with Retry-After for completions under overload
+//! - [`budget`] - Daily and monthly token budgets of authorized apps
+//! - [`capture`] - Sanitized request/response capture for debugging
+//! - [`cloudflare_access`] - Access JWTs of requests through the named tunnel
+//! - [`metrics`] - Prometheus request, latency and stream metrics
+//! - [`moderation`] - Pre-flight moderation of prompts
+//! - [`post_processing`] - Rewriting of completion text by configured rules
+//! - [`request_id`] - `x-request-id` assignment and log correlation
+//! - [`request_log`] - Per-request logging with a latency breakdown
+//! - [`request_transforms`] - Rewriting of requests by configured rules
+//! - [`shadow`] - Mirroring of chat completions to a shadow backend
+//! - [`tunnel_auth`] - Tokens required of requests through the tunnel
+//! - [`tunnel_limits`] - Connection and monthly egress caps of the tunnel
+
+pub mod access_control;
+pub mod analytics;
+pub mod azure;
+pub mod backpressure;
+pub mod budget;
+pub mod capture;
+pub mod cloudflare_access;
+pub mod metrics;
+pub mod moderation;
+pub mod post_processing;
+pub mod request_id;
+pub mod request_log;
+pub mod request_transforms;
+pub mod shadow;
+pub mod tunnel_auth;
+pub mod tunnel_limits;
+
+use crate::api_error::ApiError;
+use axum::body::Bytes;
+use axum::extract::Request;
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+
+/// Read the whole body of `request` for a layer that inspects or rewrites it.
+/// The body limit of the route bounds what is buffered here.
+pub async fn buffer_body(request: Request) -> Result<(Parts, Bytes), Response> {
+    let (parts, body) = request.into_parts();
+    match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => Ok((parts, bytes)),
+        Err(_) => Err(
+            ApiError::new(StatusCode::PAYLOAD_TOO_LARGE, "Request body is too large")
+                .into_response(),
+        ),
+    }
+}
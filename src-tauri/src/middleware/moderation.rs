@@ -0,0 +1,99 @@
+// Pre-flight moderation of prompts on the completion routes
+//
+// The request body is buffered and what the client wrote is checked by the
+// moderator before the handler sees it. Flagged prompts are refused with
+// `400 content_policy_violation`, naming the categories but never the text.
+// When the moderation provider fails, the prompt is let through unless
+// `moderation.fail_closed` is set.
+use crate::api_error::ApiError;
+use crate::log_warn;
+use crate::middleware::buffer_body;
+use crate::moderation::{prompt_texts, Moderator};
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use std::sync::Arc;
+
+fn refuse(path: &str, status: StatusCode, message: String, code: &'static str) -> Response {
+    if path.starts_with("/api/") {
+        // Ollama clients expect a plain error string
+        (status, Json(serde_json::json!({ "error": message }))).into_response()
+    } else {
+        ApiError::new(status, message)
+            .with_code(code)
+            .into_response()
+    }
+}
+
+/// Refuse requests whose prompt the moderator flags
+pub async fn screen_prompts(
+    State(moderator): State<Arc<Moderator>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (parts, body) = match buffer_body(request).await {
+        Ok(buffered) => buffered,
+        Err(response) => return response,
+    };
+    let texts = serde_json::from_slice(&body)
+        .map(|body| prompt_texts(&body))
+        .unwrap_or_default();
+    let request = Request::from_parts(parts, Body::from(body));
+    if texts.is_empty() {
+        // Nothing to check, or malformed, which the handler answers
+        return next.run(request).await;
+    }
+
+    let path = request.uri().path().to_string();
+    let mut categories = match moderator.moderate(&texts, None).await {
+        Ok(results) => results
+            .iter()
+            .flat_map(|result| result.flagged_categories())
+            .map(str::to_string)
+            .collect::<Vec<_>>(),
+        Err(e) if moderator.fail_closed() => {
+            log_warn!(
+                "Moderation",
+                &format!("Refusing prompt that could not be checked: {}", e)
+            );
+            return refuse(
+                &path,
+                StatusCode::SERVICE_UNAVAILABLE,
+                "The prompt could not be checked against the moderation policy. Try again later."
+                    .to_string(),
+                "moderation_unavailable",
+            );
+        },
+        Err(e) => {
+            log_warn!(
+                "Moderation",
+                &format!("Letting prompt through unchecked: {}", e)
+            );
+            Vec::new()
+        },
+    };
+    if categories.is_empty() {
+        return next.run(request).await;
+    }
+
+    categories.sort();
+    categories.dedup();
+    let categories = categories.join(", ");
+    log_warn!(
+        "Moderation",
+        &format!("Refused prompt to {} flagged for {}", path, categories)
+    );
+    refuse(
+        &path,
+        StatusCode::BAD_REQUEST,
+        format!(
+            "This prompt was flagged by the moderation policy of this server ({}).",
+            categories
+        ),
+        "content_policy_violation",
+    )
+}
@@ -0,0 +1,51 @@
+// Post-processing of completions on the completion routes
+//
+// Successful JSON responses are buffered and their completion text rewritten
+// by the post-processing rules in effect. Streams, error responses and
+// requests made while there are no rules pass through untouched.
+use crate::api_error::ApiError;
+use crate::log_debug;
+use crate::post_processing::PostProcessor;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Apply the post-processing rules to non-streamed completions
+pub async fn post_process(
+    State(processor): State<Arc<RwLock<PostProcessor>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let response = next.run(request).await;
+
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !response.status().is_success() || !is_json || processor.read().await.is_empty() {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let Ok(bytes) = axum::body::to_bytes(body, usize::MAX).await else {
+        return ApiError::new(StatusCode::BAD_GATEWAY, "The completion could not be read")
+            .into_response();
+    };
+    let Ok(mut completion) = serde_json::from_slice::<serde_json::Value>(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    if !processor.read().await.process_response(&mut completion) {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+
+    log_debug!("PostProcessing", "Rewrote completion text");
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(completion.to_string()))
+}
@@ -0,0 +1,54 @@
+// Request ID generation and correlation for the API server
+use crate::logging::with_correlation_id;
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use uuid::Uuid;
+
+pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Request ID assigned to the current request, available as a request extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+/// Accept a client-supplied ID only if it is short and made of safe characters,
+/// so it can be echoed in headers and logs verbatim
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= 128
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
+
+/// Reuse the caller's `x-request-id` when valid, otherwise generate one
+pub fn request_id_for(headers: &axum::http::HeaderMap) -> String {
+    headers
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("req_{}", Uuid::new_v4().simple()))
+}
+
+/// Assign a request ID, run the rest of the stack with it as the logging
+/// correlation ID, and return it in the `x-request-id` response header
+pub async fn assign_request_id(mut request: Request, next: Next) -> Response {
+    let request_id = request_id_for(request.headers());
+    request
+        .extensions_mut()
+        .insert(RequestId(request_id.clone()));
+
+    let mut response = with_correlation_id(Some(request_id.clone()), next.run(request)).await;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response
+            .headers_mut()
+            .insert(REQUEST_ID_HEADER.clone(), value);
+    }
+
+    response
+}
@@ -0,0 +1,128 @@
+// Per-request structured logging with a latency breakdown
+//
+// API requests run with a request timer the handlers mark phases on. Once the
+// response is complete, which for streams is when the body ends, the phases
+// are logged as one entry with the breakdown as its details, and the summary
+// is kept for the request inspector.
+use crate::logging::{get_logger, LogCategory, LogEntry, LogLevel};
+use crate::middleware::analytics::TokenUsage;
+use crate::middleware::request_id::RequestId;
+use crate::request_log::{with_timer, RequestLog, RequestSummary, RequestTimer};
+use axum::{
+    body::{Body, HttpBody},
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use chrono::Utc;
+use futures_util::StreamExt;
+use std::sync::Arc;
+
+/// A request whose summary is logged once its response is complete
+struct Pending {
+    log: Arc<RequestLog>,
+    timer: Arc<RequestTimer>,
+    summary: RequestSummary,
+}
+
+impl Pending {
+    fn finish(mut self) {
+        self.summary.phases = self.timer.phases(self.summary.streamed);
+        if self.summary.streamed {
+            self.summary.throughput = self.timer.throughput();
+        }
+        let summary = self.summary;
+
+        if let Some(logger) = get_logger() {
+            let mut entry = LogEntry::new(
+                LogLevel::Info,
+                LogCategory::Network,
+                format!(
+                    "{} {} {} in {} ms",
+                    summary.method, summary.route, summary.status, summary.phases.total_ms
+                ),
+            )
+            .with_component("API")
+            .with_details(&summary);
+            if let Some(request_id) = &summary.request_id {
+                entry = entry.with_correlation_id(request_id);
+            }
+            logger.log(entry);
+        }
+        self.log.record(summary);
+    }
+}
+
+/// Ends with the streamed body it is moved into
+struct Stream(Option<Pending>);
+
+impl Drop for Stream {
+    fn drop(&mut self) {
+        if let Some(pending) = self.0.take() {
+            pending.finish();
+        }
+    }
+}
+
+/// Time API requests (`/v1/...` and the Ollama-compatible `/api/...`) by
+/// phase and log each one
+pub async fn log_requests(
+    State(log): State<Arc<RequestLog>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string());
+    let is_api_route = |route: &String| {
+        route.starts_with("/v1/") || route.starts_with("/api/") || route.starts_with("/openai/")
+    };
+    let Some(route) = route.filter(is_api_route) else {
+        return next.run(request).await;
+    };
+
+    let timer = Arc::new(RequestTimer::new());
+    let at = Utc::now();
+    let method = request.method().to_string();
+    let request_id = request
+        .extensions()
+        .get::<RequestId>()
+        .map(|RequestId(id)| id.clone());
+
+    let response = with_timer(Some(timer.clone()), next.run(request)).await;
+
+    // Bodies of unknown length are streamed, and done when they end
+    let streamed =
+        !response.body().is_end_stream() && response.body().size_hint().exact().is_none();
+    let pending = Pending {
+        log,
+        timer,
+        summary: RequestSummary {
+            request_id,
+            at,
+            method,
+            route,
+            status: response.status().as_u16(),
+            model: response
+                .extensions()
+                .get::<TokenUsage>()
+                .map(|usage| usage.model.clone()),
+            streamed,
+            phases: Default::default(),
+            throughput: None,
+        },
+    };
+    if !streamed {
+        pending.finish();
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let stream = Stream(Some(pending));
+    let body = body.into_data_stream().map(move |chunk| {
+        let _ = &stream;
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(body))
+}
@@ -0,0 +1,51 @@
+// Transformation of requests on the completion routes
+//
+// The request body is buffered and, together with the headers, rewritten by
+// the transformation rules in effect before any other layer of the route sees
+// it. Requests made while there are no rules pass through untouched.
+use crate::log_debug;
+use crate::middleware::buffer_body;
+use crate::request_transforms::RequestTransformer;
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Rewrite requests by the transformation rules
+pub async fn transform_requests(
+    State(transformer): State<Arc<RwLock<RequestTransformer>>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if transformer.read().await.is_empty() {
+        return next.run(request).await;
+    }
+
+    let (mut parts, bytes) = match buffer_body(request).await {
+        Ok(buffered) => buffered,
+        Err(response) => return response,
+    };
+    let mut json = serde_json::from_slice::<serde_json::Value>(&bytes).ok();
+    let changed = transformer
+        .read()
+        .await
+        .apply(&mut parts.headers, json.as_mut());
+
+    let body = match json {
+        Some(json) if changed => {
+            log_debug!("RequestTransforms", "Rewrote request");
+            let bytes = json.to_string();
+            parts
+                .headers
+                .insert(header::CONTENT_LENGTH, HeaderValue::from(bytes.len()));
+            Body::from(bytes)
+        },
+        _ => Body::from(bytes),
+    };
+    next.run(Request::from_parts(parts, body)).await
+}
@@ -0,0 +1,215 @@
+// Shadow traffic mirroring for chat completions
+//
+// A sampled request is sent to the shadow backend as soon as it arrives, with
+// the prompt redacted as it would be for ChatGPT, while the primary handles it
+// as usual. The primary's response is passed through untouched and read along
+// the way; once it has been sent in full, the two answers are compared in the
+// background. Requests the primary refuses, and responses the client stops
+// reading, are not compared.
+use crate::failover::{completions_url, provider_request};
+use crate::managers::config_manager::ShadowConfig;
+use crate::managers::server_manager::ChatCompletionRequest;
+use crate::middleware::buffer_body;
+use crate::middleware::metrics::Metrics;
+use crate::model_catalog::ModelCatalog;
+use crate::redaction::Redactor;
+use crate::shadow::{
+    completion_text, length_ratio, similarity, ShadowComparison, ShadowStats, MAX_RESPONSE_BYTES,
+};
+use axum::{
+    body::{Body, Bytes},
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use chrono::Utc;
+use futures_util::StreamExt;
+use reqwest::Client;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// The shadow backend's answer and how long it took, or why there is none
+type ShadowAnswer = Result<(String, Duration), String>;
+
+#[derive(Debug)]
+pub struct Shadow {
+    config: ShadowConfig,
+    client: Client,
+    timeout: Duration,
+    redactor: Option<Arc<Redactor>>,
+    models: Arc<ModelCatalog>,
+    stats: Arc<ShadowStats>,
+    metrics: Arc<Metrics>,
+}
+
+impl Shadow {
+    pub fn new(
+        config: ShadowConfig,
+        client: Client,
+        timeout: Duration,
+        redactor: Option<Arc<Redactor>>,
+        models: Arc<ModelCatalog>,
+        stats: Arc<ShadowStats>,
+        metrics: Arc<Metrics>,
+    ) -> Self {
+        Self {
+            config,
+            client,
+            timeout,
+            redactor,
+            models,
+            stats,
+            metrics,
+        }
+    }
+
+    fn sampled(&self) -> bool {
+        ((Uuid::new_v4().as_u128() % 100) as u8) < self.config.percentage
+    }
+
+    /// Ask the shadow backend for a complete, non-streamed answer
+    async fn ask(self: Arc<Self>, mut request: ChatCompletionRequest) -> ShadowAnswer {
+        let started = Instant::now();
+        let target = &self.config.target;
+        let bifrost_url = self.models.bifrost_url().await;
+        let url = completions_url(target, bifrost_url.as_deref())
+            .ok_or_else(|| "Bifrost is not running".to_string())?;
+        if let Some(redactor) = &self.redactor {
+            redactor.redact_messages(&mut request.messages);
+        }
+
+        let mut builder = self
+            .client
+            .post(&url)
+            .json(&provider_request(target, &request, false))
+            .timeout(self.timeout);
+        if let Some(api_key) = &target.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+        let response = builder.send().await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("returned status {}", response.status()));
+        }
+        let body = response.bytes().await.map_err(|e| e.to_string())?;
+        let text = completion_text(&body).ok_or_else(|| "returned no completion".to_string())?;
+        Ok((text, started.elapsed()))
+    }
+
+    fn record(
+        &self,
+        model: String,
+        primary: &str,
+        primary_latency: Duration,
+        shadow: ShadowAnswer,
+    ) {
+        let mut comparison = ShadowComparison {
+            at: Utc::now(),
+            model,
+            primary_latency_ms: primary_latency.as_millis() as u64,
+            shadow_latency_ms: None,
+            similarity: None,
+            length_ratio: None,
+            error: None,
+        };
+        match shadow {
+            Ok((text, latency)) => {
+                comparison.shadow_latency_ms = Some(latency.as_millis() as u64);
+                comparison.similarity = Some(similarity(primary, &text));
+                comparison.length_ratio = Some(length_ratio(primary, &text));
+            },
+            Err(error) => comparison.error = Some(error),
+        }
+        self.metrics.record_shadow(comparison.similarity);
+        self.stats.record(comparison);
+    }
+}
+
+/// Mirror a share of chat completions to the shadow backend
+pub async fn mirror_traffic(
+    State(shadow): State<Arc<Shadow>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !shadow.sampled() {
+        return next.run(request).await;
+    }
+
+    let (parts, body) = match buffer_body(request).await {
+        Ok(buffered) => buffered,
+        Err(response) => return response,
+    };
+    let Ok(mirrored) = serde_json::from_slice::<ChatCompletionRequest>(&body) else {
+        // The handler answers malformed requests
+        return next.run(Request::from_parts(parts, Body::from(body))).await;
+    };
+
+    let started = Instant::now();
+    let model = mirrored.model.clone();
+    let answer = tokio::spawn(shadow.clone().ask(mirrored));
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if !response.status().is_success() {
+        answer.abort();
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let mut primary = Primary {
+        shadow,
+        model,
+        started,
+        body: Vec::new(),
+        answer: Some(answer),
+    };
+    let body = body.into_data_stream().map(move |chunk| {
+        if let Ok(chunk) = &chunk {
+            primary.push(chunk);
+        }
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
+/// The primary's response as it is sent, compared with the shadow's answer
+/// when dropped
+struct Primary {
+    shadow: Arc<Shadow>,
+    model: String,
+    started: Instant,
+    body: Vec<u8>,
+    answer: Option<JoinHandle<ShadowAnswer>>,
+}
+
+impl Primary {
+    fn push(&mut self, chunk: &Bytes) {
+        // One byte past the limit marks the response as too long to compare
+        let room = (MAX_RESPONSE_BYTES + 1).saturating_sub(self.body.len());
+        self.body.extend_from_slice(&chunk[..chunk.len().min(room)]);
+    }
+}
+
+impl Drop for Primary {
+    fn drop(&mut self) {
+        let Some(answer) = self.answer.take() else {
+            return;
+        };
+        let text = (self.body.len() <= MAX_RESPONSE_BYTES)
+            .then(|| completion_text(&self.body))
+            .flatten();
+        let (Some(text), Ok(runtime)) = (text, tokio::runtime::Handle::try_current()) else {
+            answer.abort();
+            return;
+        };
+
+        let latency = self.started.elapsed();
+        let shadow = self.shadow.clone();
+        let model = std::mem::take(&mut self.model);
+        runtime.spawn(async move {
+            let answer = answer
+                .await
+                .unwrap_or_else(|e| Err(format!("was interrupted: {}", e)));
+            shadow.record(model, &text, latency, answer);
+        });
+    }
+}
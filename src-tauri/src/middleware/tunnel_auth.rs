@@ -0,0 +1,140 @@
+// Token check of requests through the tunnel
+//
+// Whoever learns the tunnel's URL reaches the API, so requests coming through
+// it have to present the instance token, a tunnel token or the API key of an
+// authorized app as their bearer token; the others are refused with 401.
+// Requests count as tunneled when they are for the tunnel's hostname, or when
+// a local proxy forwarded them for a remote client. Local clients are not
+// affected.
+use crate::authorized_apps::{self, AuthorizedApp};
+use crate::log_warn;
+use crate::managers::server_manager::create_error_response;
+use crate::middleware::access_control::ClientIp;
+use crate::middleware::metrics::tunnel_host;
+use crate::tunnel_tokens::TunnelTokens;
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Paths served without a token, so the tunnel can be probed
+pub const OPEN_PATHS: &[&str] = &["/health"];
+
+/// Whether tokens are enforced, and the instance token accepted besides the
+/// tunnel tokens and app keys
+#[derive(Debug, Clone, Default)]
+pub struct TunnelTokenPolicy {
+    pub required: bool,
+    pub instance_token: Option<String>,
+}
+
+/// Everything the check needs, all shared with the running server so
+/// rotations and edits apply without a restart
+#[derive(Clone)]
+pub struct TunnelAuth {
+    pub policy: Arc<RwLock<TunnelTokenPolicy>>,
+    /// Public URL of the tunnel, kept current by the tunnel manager
+    pub tunnel_url: Arc<RwLock<Option<String>>>,
+    pub tunnel_tokens: Arc<RwLock<TunnelTokens>>,
+    pub authorized_apps: Arc<RwLock<Vec<AuthorizedApp>>>,
+}
+
+impl TunnelAuth {
+    /// Whether `token` lets a request through the tunnel in
+    pub async fn accepts(&self, token: &str) -> bool {
+        let policy = self.policy.read().await;
+        if policy.instance_token.as_deref() == Some(token) {
+            return true;
+        }
+
+        // The replaced token keeps working until it expires
+        let now = chrono::Utc::now();
+        let tokens = self.tunnel_tokens.read().await;
+        if [tokens.current(now), tokens.previous(now)]
+            .into_iter()
+            .flatten()
+            .any(|tunnel_token| tunnel_token.token == token)
+        {
+            return true;
+        }
+
+        authorized_apps::find_app(&self.authorized_apps.read().await, token).is_some()
+    }
+}
+
+/// Whether a request reached the server through the tunnel: it is for the
+/// tunnel's hostname, or the loopback peer forwarded it for a remote client
+pub fn is_tunneled(
+    tunnel_host: Option<&str>,
+    host: Option<&str>,
+    peer: Option<IpAddr>,
+    client: Option<IpAddr>,
+) -> bool {
+    let host = host.map(|host| host.rsplit_once(':').map_or(host, |(name, _)| name));
+    if let (Some(tunnel), Some(host)) = (tunnel_host, host) {
+        if host.eq_ignore_ascii_case(tunnel) {
+            return true;
+        }
+    }
+
+    let forwarded_by_local_proxy = peer.is_none_or(|peer| peer.to_canonical().is_loopback());
+    forwarded_by_local_proxy && client.is_some_and(|client| !client.to_canonical().is_loopback())
+}
+
+/// Whether `request` reached the server through the tunnel at `tunnel_url`
+pub fn is_tunneled_request(tunnel_url: Option<&str>, request: &Request) -> bool {
+    let tunnel = tunnel_url.and_then(tunnel_host);
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok());
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let client = request
+        .extensions()
+        .get::<ClientIp>()
+        .and_then(|ClientIp(ip)| *ip);
+    is_tunneled(tunnel.as_deref(), host, peer, client)
+}
+
+/// Refuse requests through the tunnel without an accepted token
+pub async fn require_tunnel_token(
+    State(auth): State<TunnelAuth>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !auth.policy.read().await.required || OPEN_PATHS.contains(&request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    let tunnel_url = auth.tunnel_url.read().await.clone();
+    if !is_tunneled_request(tunnel_url.as_deref(), &request) {
+        return next.run(request).await;
+    }
+
+    let Some(token) = authorized_apps::bearer_token(request.headers()) else {
+        return create_error_response(
+            StatusCode::UNAUTHORIZED,
+            "A token is required for requests through the tunnel",
+        );
+    };
+    if auth.accepts(token).await {
+        return next.run(request).await;
+    }
+
+    log_warn!(
+        "TunnelAuth",
+        format!(
+            "Refused request through the tunnel to {}: unknown token",
+            request.uri().path()
+        )
+    );
+    create_error_response(StatusCode::UNAUTHORIZED, "Invalid token")
+}
@@ -0,0 +1,244 @@
+// Connection and egress limits of requests through the tunnel
+//
+// Tunneled requests beyond the connection limit are refused with 503, and once
+// the month's egress is used up with 429 until the month is over, both with
+// Retry-After. A request holds its connection until the response, streams
+// included, has been sent, and what it sent is counted then. Nearing a limit
+// and reaching it are each published once, and again once traffic fell back.
+// Local requests are neither limited nor counted.
+use crate::budgets::BudgetPeriod;
+use crate::managers::config_manager::TunnelLimitsConfig;
+use crate::managers::server_manager::create_error_response;
+use crate::middleware::tunnel_auth::{is_tunneled_request, OPEN_PATHS};
+use crate::tunnel_limits::{EgressLedger, TunnelLimit, TunnelLimitWarning};
+use axum::{
+    body::{Body, HttpBody},
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use chrono::Utc;
+use futures_util::StreamExt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use tokio::sync::{broadcast, OwnedSemaphorePermit, RwLock, Semaphore};
+
+/// Seconds a client refused for too many connections is asked to wait
+const CONNECTIONS_RETRY_AFTER: u32 = 5;
+
+/// How close traffic is to a limit, so each step is warned about once
+const BELOW: u8 = 0;
+const NEAR: u8 = 1;
+const REACHED: u8 = 2;
+
+/// Limits of the tunnel shared by the requests of a running server
+#[derive(Debug)]
+pub struct TunnelLimiter {
+    /// Public URL of the tunnel, kept current by the tunnel manager
+    tunnel_url: Arc<RwLock<Option<String>>>,
+    /// Slots of the requests through the tunnel, when they are limited
+    connections: Option<(u64, Arc<Semaphore>)>,
+    max_egress_bytes: Option<u64>,
+    warn_at_percent: u8,
+    ledger: Arc<EgressLedger>,
+    events: broadcast::Sender<TunnelLimitWarning>,
+    connections_level: AtomicU8,
+    egress_level: AtomicU8,
+}
+
+impl TunnelLimiter {
+    pub fn new(
+        config: &TunnelLimitsConfig,
+        ledger: Arc<EgressLedger>,
+        tunnel_url: Arc<RwLock<Option<String>>>,
+        events: broadcast::Sender<TunnelLimitWarning>,
+    ) -> Self {
+        Self {
+            tunnel_url,
+            connections: config.max_connections.map(|max| {
+                let max = max.max(1);
+                (max as u64, Arc::new(Semaphore::new(max as usize)))
+            }),
+            max_egress_bytes: config
+                .max_monthly_egress_mb
+                .map(|mb| mb.saturating_mul(1024 * 1024)),
+            warn_at_percent: config.warn_at_percent,
+            ledger,
+            events,
+            connections_level: AtomicU8::new(BELOW),
+            egress_level: AtomicU8::new(BELOW),
+        }
+    }
+
+    fn warn_threshold(&self, max: u64) -> u64 {
+        max.saturating_mul(self.warn_at_percent as u64)
+            .div_ceil(100)
+    }
+
+    /// Publish a warning when traffic moved up to `level`
+    fn escalate(&self, limit: TunnelLimit, level: u8, used: u64, max: u64) {
+        let current = match limit {
+            TunnelLimit::Connections => &self.connections_level,
+            TunnelLimit::MonthlyEgress => &self.egress_level,
+        };
+        if level == BELOW {
+            current.store(BELOW, Ordering::Relaxed);
+            return;
+        }
+        if current.fetch_max(level, Ordering::Relaxed) >= level {
+            return;
+        }
+
+        // No receivers only means nobody is listening
+        let _ = self.events.send(TunnelLimitWarning {
+            limit,
+            used,
+            max,
+            reached: level == REACHED,
+            at: Utc::now(),
+        });
+    }
+
+    fn check_connections(&self, refused: bool) {
+        let Some((max, slots)) = &self.connections else {
+            return;
+        };
+        let (max, used) = (*max, *max - slots.available_permits() as u64);
+        let warn_at = self.warn_threshold(max);
+        let level = if refused {
+            REACHED
+        } else if used >= warn_at {
+            NEAR
+        } else if used <= warn_at / 2 {
+            BELOW
+        } else {
+            // Keeps a warning from repeating while traffic hovers near it
+            return;
+        };
+        self.escalate(TunnelLimit::Connections, level, used, max);
+    }
+
+    fn check_egress(&self, used: u64) {
+        let Some(max) = self.max_egress_bytes else {
+            return;
+        };
+        let level = if used >= max {
+            REACHED
+        } else if used >= self.warn_threshold(max) {
+            NEAR
+        } else {
+            BELOW
+        };
+        self.escalate(TunnelLimit::MonthlyEgress, level, used, max);
+    }
+
+    /// Count `bytes` sent through the tunnel
+    fn record_egress(&self, bytes: u64) {
+        if bytes == 0 {
+            return;
+        }
+        let used = self.ledger.record(bytes, Utc::now());
+        self.check_egress(used);
+        self.ledger.save_in_background();
+    }
+}
+
+fn refused(status: StatusCode, message: &str, retry_after: i64) -> Response {
+    let mut response = create_error_response(status, message);
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(retry_after.max(1)));
+    response
+}
+
+/// Refuse requests through the tunnel beyond its limits, and count the bytes
+/// the others send
+pub async fn limit_tunnel_traffic(
+    State(limiter): State<Arc<TunnelLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let tunnel_url = limiter.tunnel_url.read().await.clone();
+    if OPEN_PATHS.contains(&request.uri().path())
+        || !is_tunneled_request(tunnel_url.as_deref(), &request)
+    {
+        return next.run(request).await;
+    }
+
+    let now = Utc::now();
+    if let Some(max) = limiter.max_egress_bytes {
+        let used = limiter.ledger.used(now);
+        if used >= max {
+            limiter.check_egress(used);
+            let resets_at = BudgetPeriod::Monthly.resets_at(now);
+            return refused(
+                StatusCode::TOO_MANY_REQUESTS,
+                &format!(
+                    "The tunnel's monthly traffic limit is used up. It resets at {}.",
+                    resets_at.to_rfc3339()
+                ),
+                (resets_at - now).num_seconds(),
+            );
+        }
+    }
+
+    let connection = match &limiter.connections {
+        Some((_, slots)) => match slots.clone().try_acquire_owned() {
+            Ok(permit) => {
+                limiter.check_connections(false);
+                Some(permit)
+            },
+            Err(_) => {
+                limiter.check_connections(true);
+                return refused(
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "Too many requests through the tunnel at once",
+                    CONNECTIONS_RETRY_AFTER.into(),
+                );
+            },
+        },
+        None => None,
+    };
+
+    let response = next.run(request).await;
+    let mut egress = Egress {
+        limiter,
+        bytes: 0,
+        connection,
+    };
+
+    // Bodies of known length are done once handed on, streams once sent
+    if let Some(length) = response.body().size_hint().exact() {
+        egress.bytes = length;
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let body = body.into_data_stream().map(move |chunk| {
+        // Moves the whole counter in, so it is dropped with the body
+        let egress = &mut egress;
+        if let Ok(chunk) = &chunk {
+            egress.bytes += chunk.len() as u64;
+        }
+        chunk
+    });
+    Response::from_parts(parts, Body::from_stream(body))
+}
+
+/// A response through the tunnel, which holds its connection and is counted
+/// against the egress limit once it is done
+struct Egress {
+    limiter: Arc<TunnelLimiter>,
+    bytes: u64,
+    connection: Option<OwnedSemaphorePermit>,
+}
+
+impl Drop for Egress {
+    fn drop(&mut self) {
+        self.limiter.record_egress(self.bytes);
+        if let Some(connection) = self.connection.take() {
+            drop(connection);
+            self.limiter.check_connections(false);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+// Model catalog for `/v1/models` and `/api/tags`
+//
+// Models come from two places: the authenticated ChatGPT account (what the
+// plan can actually use) and a running Bifrost instance (whatever providers
+// it has configured). Both lists are merged and cached; a failed fetch keeps
+// serving the last good list, and the built-in defaults are only used when
+// nothing has been fetched yet. Requests for a model only Bifrost lists are
+// sent to Bifrost.
+
+use crate::managers::server_manager::Model;
+use crate::{log_warn, proxy};
+use reqwest::Client;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+/// ChatGPT endpoint listing the models available to the account
+pub const UPSTREAM_MODELS_URL: &str = "https://chatgpt.com/backend-api/models";
+
+/// How long a fetched list is served before it is fetched again
+pub const MODEL_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Served until the account's models have been fetched once
+const DEFAULT_MODELS: [&str; 2] = ["gpt-5", "codex-mini"];
+
+#[derive(Debug, Clone)]
+struct CachedModels {
+    upstream: Vec<String>,
+    bifrost: Vec<String>,
+    fetched_at: Instant,
+}
+
+#[derive(Debug)]
+pub struct ModelCatalog {
+    client: Client,
+    ttl: Duration,
+    bifrost_url: RwLock<Option<String>>,
+    cache: RwLock<Option<CachedModels>>,
+}
+
+impl Default for ModelCatalog {
+    fn default() -> Self {
+        Self::new(MODEL_CACHE_TTL)
+    }
+}
+
+impl ModelCatalog {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            client: proxy::apply(Client::builder())
+                .timeout(Duration::from_secs(10))
+                .user_agent("MindLink/1.0")
+                .build()
+                .unwrap_or_default(),
+            ttl,
+            bifrost_url: RwLock::new(None),
+            cache: RwLock::new(None),
+        }
+    }
+
+    /// Base URL of the running Bifrost instance, or `None` when it is stopped.
+    /// Invalidates the cache so the next listing picks up the change.
+    pub async fn set_bifrost_url(&self, url: Option<String>) {
+        let mut bifrost_url = self.bifrost_url.write().await;
+        if *bifrost_url != url {
+            *bifrost_url = url;
+            *self.cache.write().await = None;
+        }
+    }
+
+    pub async fn bifrost_url(&self) -> Option<String> {
+        self.bifrost_url.read().await.clone()
+    }
+
+    /// The cached list, if it is younger than the TTL
+    pub async fn cached(&self) -> Option<Vec<Model>> {
+        self.cache
+            .read()
+            .await
+            .as_ref()
+            .filter(|cache| cache.fetched_at.elapsed() < self.ttl)
+            .map(|cache| merge_models(&cache.upstream, &cache.bifrost))
+    }
+
+    /// Whether `model` is one of Bifrost's, as of the last listing. A model
+    /// the account lists as well is served by the account.
+    pub async fn is_bifrost_model(&self, model: &str) -> bool {
+        self.cache.read().await.as_ref().is_some_and(|cache| {
+            cache.bifrost.iter().any(|id| id == model)
+                && !cache.upstream.iter().any(|id| id == model)
+        })
+    }
+
+    /// Fetch both sources and replace the cache. Without an access token, or
+    /// when the account listing fails, the previous upstream list is kept.
+    pub async fn refresh(&self, access_token: Option<&str>) -> Vec<Model> {
+        let previous = self.cache.read().await.clone();
+        let previous_upstream = || {
+            previous
+                .as_ref()
+                .map(|cache| cache.upstream.clone())
+                .unwrap_or_else(|| DEFAULT_MODELS.iter().map(|m| m.to_string()).collect())
+        };
+
+        let upstream = match access_token {
+            Some(token) => match self.fetch_upstream(token).await {
+                Ok(models) if !models.is_empty() => models,
+                Ok(_) => previous_upstream(),
+                Err(e) => {
+                    log_warn!(
+                        "ModelCatalog",
+                        format!("Failed to list account models: {}", e)
+                    );
+                    previous_upstream()
+                },
+            },
+            None => previous_upstream(),
+        };
+
+        let bifrost_url = self.bifrost_url.read().await.clone();
+        let bifrost = match bifrost_url {
+            Some(url) => self.fetch_bifrost(&url).await.unwrap_or_else(|e| {
+                log_warn!(
+                    "ModelCatalog",
+                    format!("Failed to list Bifrost models: {}", e)
+                );
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+
+        let models = merge_models(&upstream, &bifrost);
+        *self.cache.write().await = Some(CachedModels {
+            upstream,
+            bifrost,
+            fetched_at: Instant::now(),
+        });
+        models
+    }
+
+    async fn fetch_upstream(&self, access_token: &str) -> Result<Vec<String>, String> {
+        let value = self
+            .fetch_json(
+                self.client
+                    .get(UPSTREAM_MODELS_URL)
+                    .header("Authorization", format!("Bearer {}", access_token)),
+            )
+            .await?;
+        Ok(parse_upstream_models(&value))
+    }
+
+    async fn fetch_bifrost(&self, base_url: &str) -> Result<Vec<String>, String> {
+        let url = format!("{}/v1/models", base_url.trim_end_matches('/'));
+        let value = self.fetch_json(self.client.get(&url)).await?;
+        Ok(parse_model_ids(&value))
+    }
+
+    async fn fetch_json(&self, request: reqwest::RequestBuilder) -> Result<Value, String> {
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("HTTP {}", response.status()));
+        }
+        response.json::<Value>().await.map_err(|e| e.to_string())
+    }
+}
+
+/// Model slugs from the ChatGPT account listing (`{"models": [{"slug": ...}]}`)
+pub fn parse_upstream_models(value: &Value) -> Vec<String> {
+    value
+        .get("models")
+        .and_then(Value::as_array)
+        .map(|models| {
+            models
+                .iter()
+                .filter_map(|m| m.get("slug").or_else(|| m.get("id"))?.as_str())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Model ids from an OpenAI-style listing (`{"data": [{"id": ...}]}`)
+pub fn parse_model_ids(value: &Value) -> Vec<String> {
+    value
+        .get("data")
+        .and_then(Value::as_array)
+        .map(|models| {
+            models
+                .iter()
+                .filter_map(|m| m.get("id")?.as_str())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Account models first, then Bifrost models not already listed
+pub fn merge_models(upstream: &[String], bifrost: &[String]) -> Vec<Model> {
+    let created = chrono::Utc::now().timestamp() as u64;
+    let mut models: Vec<Model> = Vec::new();
+
+    let sources = upstream
+        .iter()
+        .map(|id| (id, "mindlink"))
+        .chain(bifrost.iter().map(|id| (id, "bifrost")));
+    for (id, owned_by) in sources {
+        if models.iter().any(|m| &m.id == id) {
+            continue;
+        }
+        models.push(Model {
+            id: id.clone(),
+            object: "model".to_string(),
+            created,
+            owned_by: owned_by.to_string(),
+        });
+    }
+
+    models
+}
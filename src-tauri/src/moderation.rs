@@ -0,0 +1,242 @@
+// Content moderation
+//
+// `/v1/moderations` answers in OpenAI's format. Inputs are checked by an
+// OpenAI-compatible moderation API when one is configured, and by the local
+// rules in any case: a rule flags its category whenever its pattern matches.
+// With `moderation.preflight` set, every prompt sent to the completion routes
+// goes through the same check first, so a server shared with teammates through
+// the tunnel can refuse content before it reaches ChatGPT.
+
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::managers::config_manager::{FallbackProvider, ModerationConfig};
+use crate::network_error;
+use regex::Regex;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Reported as the model when only the local rules apply
+pub const RULES_MODEL: &str = "mindlink-rules";
+
+/// Verdict on one input, in OpenAI's format
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModerationResult {
+    pub flagged: bool,
+    #[serde(default)]
+    pub categories: BTreeMap<String, bool>,
+    #[serde(default)]
+    pub category_scores: BTreeMap<String, f64>,
+}
+
+impl ModerationResult {
+    /// Categories the input was flagged for
+    pub fn flagged_categories(&self) -> Vec<&str> {
+        self.categories
+            .iter()
+            .filter(|(_, flagged)| **flagged)
+            .map(|(category, _)| category.as_str())
+            .collect()
+    }
+
+    fn flag(&mut self, category: &str) {
+        self.flagged = true;
+        self.categories.insert(category.to_string(), true);
+        self.category_scores.insert(category.to_string(), 1.0);
+    }
+}
+
+/// Body of a `/v1/moderations` response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModerationResponse {
+    pub id: String,
+    pub model: String,
+    pub results: Vec<ModerationResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProviderResponse {
+    results: Vec<ModerationResult>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    category: String,
+    pattern: Regex,
+}
+
+#[derive(Debug)]
+pub struct Moderator {
+    provider: Option<FallbackProvider>,
+    rules: Vec<Rule>,
+    client: Client,
+    timeout: Duration,
+    fail_closed: bool,
+}
+
+impl Moderator {
+    /// `None` when there is neither a provider nor a rule to check against
+    pub fn from_config(
+        config: &ModerationConfig,
+        client: Client,
+        timeout: Duration,
+    ) -> MindLinkResult<Option<Self>> {
+        let mut rules = Vec::new();
+        for rule in &config.rules {
+            let pattern = Regex::new(&rule.pattern).map_err(|e| MindLinkError::Configuration {
+                message: format!("Invalid pattern for moderation rule '{}'", rule.category),
+                config_key: Some("moderation.rules".to_string()),
+                source: Some(e.into()),
+            })?;
+            rules.push(Rule {
+                category: rule.category.clone(),
+                pattern,
+            });
+        }
+        if config.provider.is_none() && rules.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self {
+            provider: config.provider.clone(),
+            rules,
+            client,
+            timeout,
+            fail_closed: config.fail_closed,
+        }))
+    }
+
+    /// Whether prompts are refused while the provider cannot be reached
+    pub fn fail_closed(&self) -> bool {
+        self.fail_closed
+    }
+
+    /// Model named in responses: the provider's, the client's, or the rules
+    pub fn model(&self, requested: Option<&str>) -> String {
+        match &self.provider {
+            Some(provider) => provider
+                .model
+                .as_deref()
+                .or(requested)
+                .unwrap_or("omni-moderation-latest")
+                .to_string(),
+            None => RULES_MODEL.to_string(),
+        }
+    }
+
+    /// One result per input, in order
+    pub async fn moderate(
+        &self,
+        inputs: &[String],
+        model: Option<&str>,
+    ) -> MindLinkResult<Vec<ModerationResult>> {
+        let mut results = match &self.provider {
+            Some(provider) => self.ask(provider, inputs, model).await?,
+            None => vec![ModerationResult::default(); inputs.len()],
+        };
+
+        for (input, result) in inputs.iter().zip(&mut results) {
+            for rule in &self.rules {
+                if rule.pattern.is_match(input) {
+                    result.flag(&rule.category);
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    async fn ask(
+        &self,
+        provider: &FallbackProvider,
+        inputs: &[String],
+        model: Option<&str>,
+    ) -> MindLinkResult<Vec<ModerationResult>> {
+        // Validation makes sure the provider has a base URL
+        let base_url = provider.base_url.as_deref().unwrap_or_default();
+        let url = format!("{}/moderations", base_url.trim_end_matches('/'));
+        let mut body = serde_json::json!({ "input": inputs });
+        if let Some(model) = provider.model.as_deref().or(model) {
+            body["model"] = Value::from(model);
+        }
+
+        let mut builder = self.client.post(&url).json(&body).timeout(self.timeout);
+        if let Some(api_key) = &provider.api_key {
+            builder = builder.bearer_auth(api_key);
+        }
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| network_error!("Moderation provider is unreachable", &url, e))?;
+        if !response.status().is_success() {
+            return Err(network_error!(
+                format!("Moderation provider returned status {}", response.status()),
+                &url
+            ));
+        }
+        let response: ProviderResponse = response.json().await.map_err(|e| {
+            network_error!("Invalid response from the moderation provider", &url, e)
+        })?;
+        if response.results.len() != inputs.len() {
+            return Err(network_error!(
+                "Moderation provider did not answer for every input",
+                &url
+            ));
+        }
+        Ok(response.results)
+    }
+}
+
+/// Inputs of a `/v1/moderations` request: a string, a list of strings, or a
+/// list of content parts, which is one input made of its text parts. `None` if
+/// malformed.
+pub fn moderation_inputs(input: &Value) -> Option<Vec<String>> {
+    match input {
+        Value::String(text) => Some(vec![text.clone()]),
+        Value::Array(items) if items.is_empty() => None,
+        Value::Array(items) if items.iter().all(Value::is_string) => items
+            .iter()
+            .map(|item| item.as_str().map(str::to_string))
+            .collect(),
+        Value::Array(parts) if parts.iter().all(Value::is_object) => {
+            let texts: Vec<&str> = parts
+                .iter()
+                .filter(|part| part.get("type").and_then(Value::as_str) == Some("text"))
+                .map(|part| part.get("text").and_then(Value::as_str))
+                .collect::<Option<_>>()?;
+            Some(vec![texts.join("\n")])
+        },
+        _ => None,
+    }
+}
+
+/// What a client wrote in a chat completion or Ollama request: the system
+/// prompt, the `prompt` of `/api/generate`, and the text of every message not
+/// written by the assistant
+pub fn prompt_texts(body: &Value) -> Vec<String> {
+    let mut texts = Vec::new();
+    for key in ["system", "prompt"] {
+        if let Some(text) = body.get(key).and_then(Value::as_str) {
+            texts.push(text.to_string());
+        }
+    }
+
+    let messages = body.get("messages").and_then(Value::as_array);
+    for message in messages.into_iter().flatten() {
+        if message.get("role").and_then(Value::as_str) == Some("assistant") {
+            continue;
+        }
+        match message.get("content") {
+            Some(Value::String(text)) => texts.push(text.clone()),
+            Some(Value::Array(parts)) => texts.extend(
+                parts
+                    .iter()
+                    .filter_map(|part| part.get("text").and_then(Value::as_str))
+                    .map(str::to_string),
+            ),
+            _ => {},
+        }
+    }
+    texts.retain(|text| !text.trim().is_empty());
+    texts
+}
@@ -0,0 +1,251 @@
+// Ollama-compatible API surface
+//
+// `/api/chat` and `/api/generate` requests are translated into OpenAI chat
+// completion requests and run through the same upstream pipeline as
+// `/v1/chat/completions`. Replies are shaped like Ollama's: a single JSON
+// object, or newline-delimited JSON objects when streaming (Ollama's default).
+
+use crate::managers::server_manager::{ChatCompletionRequest, Message};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// Which Ollama endpoint a response is shaped for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OllamaEndpoint {
+    Chat,
+    Generate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaMessage {
+    pub role: String,
+    #[serde(default)]
+    pub content: String,
+}
+
+/// Model options; unknown options (`num_ctx`, `mirostat`, ...) are ignored
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OllamaOptions {
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub num_predict: Option<i64>,
+    pub seed: Option<i64>,
+    pub stop: Option<Vec<String>>,
+}
+
+/// Body of `POST /api/chat`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaChatRequest {
+    pub model: String,
+    #[serde(default)]
+    pub messages: Vec<OllamaMessage>,
+    pub stream: Option<bool>,
+    pub format: Option<Value>,
+    pub options: Option<OllamaOptions>,
+}
+
+/// Body of `POST /api/generate`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaGenerateRequest {
+    pub model: String,
+    #[serde(default)]
+    pub prompt: String,
+    pub system: Option<String>,
+    pub stream: Option<bool>,
+    pub format: Option<Value>,
+    pub options: Option<OllamaOptions>,
+}
+
+impl OllamaChatRequest {
+    pub fn to_openai(&self) -> ChatCompletionRequest {
+        let messages = self
+            .messages
+            .iter()
+            .map(|m| message(&m.role, &m.content))
+            .collect();
+        openai_request(
+            &self.model,
+            messages,
+            self.stream,
+            self.format.as_ref(),
+            self.options.as_ref(),
+        )
+    }
+}
+
+impl OllamaGenerateRequest {
+    pub fn to_openai(&self) -> ChatCompletionRequest {
+        let mut messages = Vec::new();
+        if let Some(system) = self.system.as_deref().filter(|s| !s.is_empty()) {
+            messages.push(message("system", system));
+        }
+        if !self.prompt.is_empty() {
+            messages.push(message("user", &self.prompt));
+        }
+        openai_request(
+            &self.model,
+            messages,
+            self.stream,
+            self.format.as_ref(),
+            self.options.as_ref(),
+        )
+    }
+}
+
+fn message(role: &str, content: &str) -> Message {
+    Message {
+        role: role.to_string(),
+        content: content.to_string(),
+        tool_calls: None,
+        tool_call_id: None,
+        name: None,
+        reasoning_content: None,
+    }
+}
+
+fn openai_request(
+    model: &str,
+    messages: Vec<Message>,
+    stream: Option<bool>,
+    format: Option<&Value>,
+    options: Option<&OllamaOptions>,
+) -> ChatCompletionRequest {
+    let options = options.cloned().unwrap_or_default();
+
+    let mut other = Map::new();
+    if let Some(top_p) = options.top_p {
+        other.insert("top_p".to_string(), json!(top_p));
+    }
+    if let Some(seed) = options.seed {
+        other.insert("seed".to_string(), json!(seed));
+    }
+    if let Some(stop) = options.stop {
+        other.insert("stop".to_string(), json!(stop));
+    }
+    // `"format": "json"` asks for a JSON reply; schema formats are treated the same way
+    if format.is_some_and(|f| !f.is_null()) {
+        other.insert(
+            "response_format".to_string(),
+            json!({ "type": "json_object" }),
+        );
+    }
+
+    ChatCompletionRequest {
+        model: model.to_string(),
+        messages,
+        temperature: options.temperature,
+        // Ollama uses -1 and -2 for "unlimited" and "fill context"
+        max_tokens: options
+            .num_predict
+            .filter(|n| *n > 0)
+            .map(|n| n.min(i64::from(u32::MAX)) as u32),
+        // Ollama streams unless told otherwise
+        stream: Some(stream.unwrap_or(true)),
+        other,
+    }
+}
+
+/// One reply object. Content objects have `done: false`; the final object
+/// carries timing and token counts.
+pub fn response_object(
+    endpoint: OllamaEndpoint,
+    model: &str,
+    content: &str,
+    done: Option<&DoneStats>,
+) -> Value {
+    let mut object = json!({
+        "model": model,
+        "created_at": Utc::now().to_rfc3339(),
+        "done": done.is_some(),
+    });
+
+    match endpoint {
+        OllamaEndpoint::Chat => {
+            object["message"] = json!({ "role": "assistant", "content": content });
+        },
+        OllamaEndpoint::Generate => {
+            object["response"] = json!(content);
+        },
+    }
+
+    if let Some(stats) = done {
+        object["done_reason"] = json!(stats.done_reason);
+        object["total_duration"] = json!(stats.total_duration_ns);
+        object["load_duration"] = json!(0);
+        object["prompt_eval_count"] = json!(stats.prompt_eval_count);
+        object["eval_count"] = json!(stats.eval_count);
+    }
+
+    object
+}
+
+/// Fields of the final reply object
+#[derive(Debug, Clone)]
+pub struct DoneStats {
+    pub done_reason: String,
+    pub total_duration_ns: u64,
+    pub prompt_eval_count: u32,
+    pub eval_count: u32,
+}
+
+/// Content or end of stream extracted from an OpenAI SSE chunk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamEvent {
+    Content(String),
+    Finished(String),
+    Error(String),
+}
+
+/// Parse one `data: {...}` chunk produced for `/v1/chat/completions` streams.
+/// Returns `None` for `[DONE]`, empty deltas and anything that isn't a chunk.
+pub fn parse_openai_chunk(chunk: &str) -> Option<StreamEvent> {
+    let data = chunk.trim().strip_prefix("data: ")?;
+    let value = serde_json::from_str::<Value>(data).ok()?;
+
+    if let Some(error) = value.get("error") {
+        let message = error
+            .get("message")
+            .and_then(Value::as_str)
+            .unwrap_or("Upstream error");
+        return Some(StreamEvent::Error(message.to_string()));
+    }
+
+    let choice = value.get("choices")?.get(0)?;
+    if let Some(reason) = choice.get("finish_reason").and_then(Value::as_str) {
+        return Some(StreamEvent::Finished(reason.to_string()));
+    }
+
+    choice
+        .get("delta")?
+        .get("content")?
+        .as_str()
+        .filter(|content| !content.is_empty())
+        .map(|content| StreamEvent::Content(content.to_string()))
+}
+
+/// Body of `GET /api/tags`
+pub fn tags_response(model_ids: &[String]) -> Value {
+    let modified_at = Utc::now().to_rfc3339();
+    let models: Vec<Value> = model_ids
+        .iter()
+        .map(|id| {
+            json!({
+                "name": id,
+                "model": id,
+                "modified_at": modified_at,
+                "size": 0,
+                "digest": "",
+                "details": {
+                    "format": "remote",
+                    "family": "mindlink",
+                    "families": ["mindlink"],
+                    "parameter_size": "",
+                    "quantization_level": ""
+                }
+            })
+        })
+        .collect();
+
+    json!({ "models": models })
+}
@@ -0,0 +1,825 @@
+// OpenAPI description of the API server
+//
+// `GET /openapi.json` serves an OpenAPI 3.1 document built from `ENDPOINTS`,
+// and `GET /docs` renders it with Swagger UI. The playground lists the same
+// table, so a route added to the router needs an entry here to be documented
+// anywhere. Schemas are written by hand and describe what clients send and
+// receive, not every field MindLink tolerates.
+
+use serde_json::{json, Map, Value};
+
+pub const OPENAPI_VERSION: &str = "3.1.0";
+
+/// Swagger UI is loaded from a CDN, so `/docs` needs internet access; the
+/// spec itself does not
+const SWAGGER_UI: &str = "https://unpkg.com/swagger-ui-dist@5";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Content {
+    /// JSON matching the named schema in `components.schemas`
+    Json(&'static str),
+    /// Server-sent events
+    EventStream,
+    /// Newline-delimited JSON, as Ollama streams
+    NdJson,
+    Multipart,
+    Binary,
+    Text,
+    Html,
+    /// No body
+    Empty,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Endpoint {
+    pub method: &'static str,
+    /// In OpenAPI form, with `{id}` for path parameters
+    pub path: &'static str,
+    pub operation_id: &'static str,
+    pub tag: &'static str,
+    pub summary: &'static str,
+    pub request: Option<Content>,
+    /// Status and body of the successful response
+    pub status: u16,
+    pub response: Content,
+    /// Set `stream: true` in the request to get this instead
+    pub stream: Option<Content>,
+    /// Query parameters: name, JSON type and description
+    pub query: &'static [(&'static str, &'static str, &'static str)],
+}
+
+impl Endpoint {
+    const fn new(
+        method: &'static str,
+        path: &'static str,
+        operation_id: &'static str,
+        tag: &'static str,
+        summary: &'static str,
+    ) -> Self {
+        Self {
+            method,
+            path,
+            operation_id,
+            tag,
+            summary,
+            request: None,
+            status: 200,
+            response: Content::Empty,
+            stream: None,
+            query: &[],
+        }
+    }
+
+    const fn request(mut self, content: Content) -> Self {
+        self.request = Some(content);
+        self
+    }
+
+    const fn returns(mut self, status: u16, content: Content) -> Self {
+        self.status = status;
+        self.response = content;
+        self
+    }
+
+    const fn streams(mut self, content: Content) -> Self {
+        self.stream = Some(content);
+        self
+    }
+
+    const fn query(mut self, query: &'static [(&'static str, &'static str, &'static str)]) -> Self {
+        self.query = query;
+        self
+    }
+
+    /// Names of the `{...}` segments of the path
+    pub fn path_params(&self) -> Vec<&'static str> {
+        self.path
+            .split('/')
+            .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+            .collect()
+    }
+
+    fn operation(&self) -> Value {
+        let mut parameters: Vec<Value> = self
+            .path_params()
+            .into_iter()
+            .map(|name| {
+                json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } })
+            })
+            .collect();
+        parameters.extend(self.query.iter().map(|(name, kind, description)| {
+            json!({ "name": name, "in": "query", "description": description, "schema": { "type": kind } })
+        }));
+
+        let mut success = json!({ "description": self.summary });
+        if let Some(content) = media_content(self.response, self.stream) {
+            success["content"] = content;
+        }
+
+        // Ollama clients get Ollama's error shape, everyone else OpenAI's
+        let error_schema = if self.path.starts_with("/api/") {
+            "OllamaError"
+        } else {
+            "Error"
+        };
+
+        let mut operation = json!({
+            "operationId": self.operation_id,
+            "tags": [self.tag],
+            "summary": self.summary,
+            "responses": {
+                self.status.to_string(): success,
+                "default": {
+                    "description": "Error",
+                    "content": { "application/json": { "schema": schema_ref(error_schema) } }
+                }
+            }
+        });
+        if !parameters.is_empty() {
+            operation["parameters"] = Value::Array(parameters);
+        }
+        if let Some(content) = self
+            .request
+            .and_then(|request| media_content(request, None))
+        {
+            operation["requestBody"] = json!({ "required": true, "content": content });
+        }
+        operation
+    }
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn media_type(content: Content) -> Option<(&'static str, Value)> {
+    let string = || json!({ "schema": { "type": "string" } });
+    Some(match content {
+        Content::Json(schema) => ("application/json", json!({ "schema": schema_ref(schema) })),
+        Content::EventStream => ("text/event-stream", string()),
+        Content::NdJson => ("application/x-ndjson", string()),
+        Content::Multipart => (
+            "multipart/form-data",
+            json!({ "schema": schema_ref("FileUpload") }),
+        ),
+        Content::Binary => (
+            "application/octet-stream",
+            json!({ "schema": { "type": "string", "contentMediaType": "application/octet-stream" } }),
+        ),
+        Content::Text => ("text/plain", string()),
+        Content::Html => ("text/html", string()),
+        Content::Empty => return None,
+    })
+}
+
+fn media_content(content: Content, stream: Option<Content>) -> Option<Value> {
+    let mut media = Map::new();
+    for (name, schema) in std::iter::once(content)
+        .chain(stream)
+        .filter_map(media_type)
+    {
+        media.insert(name.to_string(), schema);
+    }
+    (!media.is_empty()).then_some(Value::Object(media))
+}
+
+/// Every route the API server answers, apart from static files and the
+/// `/dashboard` redirect
+pub const ENDPOINTS: &[Endpoint] = &[
+    Endpoint::new(
+        "get",
+        "/v1/models",
+        "listModels",
+        "OpenAI",
+        "List available models",
+    )
+    .returns(200, Content::Json("ModelList")),
+    Endpoint::new(
+        "post",
+        "/v1/chat/completions",
+        "createChatCompletion",
+        "OpenAI",
+        "Create a chat completion",
+    )
+    .request(Content::Json("ChatCompletionRequest"))
+    .returns(200, Content::Json("ChatCompletion"))
+    .streams(Content::EventStream),
+    Endpoint::new(
+        "get",
+        "/v1/ws",
+        "chatWebSocket",
+        "OpenAI",
+        "Stream chat completions over a WebSocket",
+    )
+    .returns(101, Content::Empty),
+    Endpoint::new(
+        "post",
+        "/v1/files",
+        "uploadFile",
+        "Batches",
+        "Upload a batch input file",
+    )
+    .request(Content::Multipart)
+    .returns(200, Content::Json("File")),
+    Endpoint::new("get", "/v1/files/{id}", "getFile", "Batches", "Get a file")
+        .returns(200, Content::Json("File")),
+    Endpoint::new(
+        "get",
+        "/v1/files/{id}/content",
+        "getFileContent",
+        "Batches",
+        "Download the content of a file",
+    )
+    .returns(200, Content::Binary),
+    Endpoint::new(
+        "post",
+        "/v1/batches",
+        "createBatch",
+        "Batches",
+        "Create a batch",
+    )
+    .request(Content::Json("CreateBatchRequest"))
+    .returns(200, Content::Json("Batch")),
+    Endpoint::new(
+        "get",
+        "/v1/batches",
+        "listBatches",
+        "Batches",
+        "List batches",
+    )
+    .returns(200, Content::Json("BatchList"))
+    .query(&[
+        (
+            "after",
+            "string",
+            "ID of the last batch of the previous page",
+        ),
+        (
+            "limit",
+            "integer",
+            "Batches per page, 1 to 100 (default 20)",
+        ),
+    ]),
+    Endpoint::new(
+        "get",
+        "/v1/batches/{id}",
+        "getBatch",
+        "Batches",
+        "Get a batch",
+    )
+    .returns(200, Content::Json("Batch")),
+    Endpoint::new(
+        "post",
+        "/v1/batches/{id}/cancel",
+        "cancelBatch",
+        "Batches",
+        "Cancel a batch",
+    )
+    .returns(200, Content::Json("Batch")),
+    Endpoint::new(
+        "post",
+        "/v1/jobs",
+        "createJob",
+        "Jobs",
+        "Run a chat completion in the background",
+    )
+    .request(Content::Json("ChatCompletionRequest"))
+    .returns(202, Content::Json("Job")),
+    Endpoint::new("get", "/v1/jobs/{id}", "getJob", "Jobs", "Get a job")
+        .returns(200, Content::Json("Job")),
+    Endpoint::new(
+        "get",
+        "/v1/jobs/{id}/events",
+        "followJob",
+        "Jobs",
+        "Follow the progress of a job",
+    )
+    .returns(200, Content::EventStream),
+    Endpoint::new(
+        "post",
+        "/v1/jobs/{id}/cancel",
+        "cancelJob",
+        "Jobs",
+        "Cancel a job",
+    )
+    .returns(200, Content::Json("Job")),
+    Endpoint::new(
+        "get",
+        "/v1/ping",
+        "ping",
+        "Connection",
+        "Heartbeat with server time and queue depth",
+    )
+    .returns(200, Content::Json("Pong")),
+    Endpoint::new(
+        "post",
+        "/v1/ping/report",
+        "reportConnection",
+        "Connection",
+        "Report measured connection quality",
+    )
+    .request(Content::Json("ConnectionReport"))
+    .returns(204, Content::Empty),
+    Endpoint::new(
+        "post",
+        "/v1/moderations",
+        "createModeration",
+        "OpenAI",
+        "Classify text against the moderation policy",
+    )
+    .request(Content::Json("ModerationRequest"))
+    .returns(200, Content::Json("Moderation")),
+    Endpoint::new(
+        "post",
+        "/api/chat",
+        "ollamaChat",
+        "Ollama",
+        "Ollama-compatible chat",
+    )
+    .request(Content::Json("OllamaChatRequest"))
+    .returns(200, Content::Json("OllamaResponse"))
+    .streams(Content::NdJson),
+    Endpoint::new(
+        "post",
+        "/api/generate",
+        "ollamaGenerate",
+        "Ollama",
+        "Ollama-compatible text generation",
+    )
+    .request(Content::Json("OllamaGenerateRequest"))
+    .returns(200, Content::Json("OllamaResponse"))
+    .streams(Content::NdJson),
+    Endpoint::new(
+        "get",
+        "/api/tags",
+        "ollamaTags",
+        "Ollama",
+        "List models, Ollama style",
+    )
+    .returns(200, Content::Json("OllamaTags")),
+    Endpoint::new(
+        "post",
+        "/openai/deployments/{deployment}/chat/completions",
+        "azureChatCompletion",
+        "Azure OpenAI",
+        "Create a chat completion on a deployment, Azure style",
+    )
+    .request(Content::Json("ChatCompletionRequest"))
+    .returns(200, Content::Json("ChatCompletion"))
+    .streams(Content::EventStream)
+    .query(&[(
+        "api-version",
+        "string",
+        "Azure OpenAI API version; accepted and ignored",
+    )]),
+    Endpoint::new(
+        "get",
+        "/health",
+        "health",
+        "Server",
+        "Health of the server and its components",
+    )
+    .returns(200, Content::Json("Health")),
+    Endpoint::new("get", "/metrics", "metrics", "Server", "Prometheus metrics")
+        .returns(200, Content::Text),
+    Endpoint::new(
+        "get",
+        "/playground",
+        "playground",
+        "Server",
+        "Chat playground on this API",
+    )
+    .returns(200, Content::Html),
+    Endpoint::new("get", "/openapi.json", "openapi", "Server", "This document")
+        .returns(200, Content::Json("OpenApi")),
+    Endpoint::new(
+        "get",
+        "/docs",
+        "docs",
+        "Server",
+        "Interactive API documentation",
+    )
+    .returns(200, Content::Html),
+];
+
+fn schemas() -> Value {
+    let nullable = |kind: &str| json!({ "type": [kind, "null"] });
+    json!({
+        "Error": {
+            "type": "object",
+            "required": ["error"],
+            "properties": {
+                "error": {
+                    "type": "object",
+                    "required": ["message", "type"],
+                    "properties": {
+                        "message": { "type": "string" },
+                        "type": { "type": "string" },
+                        "param": nullable("string"),
+                        "code": nullable("string")
+                    }
+                }
+            }
+        },
+        "OllamaError": {
+            "type": "object",
+            "required": ["error"],
+            "properties": { "error": { "type": "string" } }
+        },
+        "Message": {
+            "type": "object",
+            "required": ["role"],
+            "properties": {
+                "role": {
+                    "type": "string",
+                    "enum": ["system", "developer", "user", "assistant", "tool", "function"]
+                },
+                "content": {
+                    "description": "Text, or a list of content parts of which only text and refusal parts are supported",
+                    "oneOf": [
+                        { "type": "string" },
+                        { "type": "array", "items": { "type": "object" } },
+                        { "type": "null" }
+                    ]
+                },
+                "name": { "type": "string" },
+                "tool_calls": { "type": "array", "items": { "type": "object" } },
+                "tool_call_id": { "type": "string" }
+            }
+        },
+        "ChatCompletionRequest": {
+            "type": "object",
+            "required": ["model", "messages"],
+            "additionalProperties": true,
+            "properties": {
+                "model": { "type": "string" },
+                "messages": { "type": "array", "items": schema_ref("Message") },
+                "temperature": { "type": "number" },
+                "max_tokens": { "type": "integer" },
+                "stream": { "type": "boolean" },
+                "tools": { "type": "array", "items": { "type": "object" } }
+            }
+        },
+        "ChatCompletion": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "object": { "const": "chat.completion" },
+                "created": { "type": "integer" },
+                "model": { "type": "string" },
+                "choices": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "index": { "type": "integer" },
+                            "message": schema_ref("Message"),
+                            "finish_reason": nullable("string")
+                        }
+                    }
+                },
+                "usage": {
+                    "type": ["object", "null"],
+                    "properties": {
+                        "prompt_tokens": { "type": "integer" },
+                        "completion_tokens": { "type": "integer" },
+                        "total_tokens": { "type": "integer" }
+                    }
+                }
+            }
+        },
+        "Model": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "object": { "const": "model" },
+                "created": { "type": "integer" },
+                "owned_by": { "type": "string" }
+            }
+        },
+        "ModelList": {
+            "type": "object",
+            "properties": {
+                "object": { "const": "list" },
+                "data": { "type": "array", "items": schema_ref("Model") }
+            }
+        },
+        "FileUpload": {
+            "type": "object",
+            "required": ["file", "purpose"],
+            "properties": {
+                "file": { "type": "string", "contentMediaType": "application/octet-stream" },
+                "purpose": { "const": "batch" }
+            }
+        },
+        "File": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "object": { "const": "file" },
+                "bytes": { "type": "integer" },
+                "created_at": { "type": "integer" },
+                "filename": { "type": "string" },
+                "purpose": { "type": "string" }
+            }
+        },
+        "CreateBatchRequest": {
+            "type": "object",
+            "required": ["input_file_id", "endpoint", "completion_window"],
+            "properties": {
+                "input_file_id": { "type": "string" },
+                "endpoint": { "const": "/v1/chat/completions" },
+                "completion_window": { "const": "24h" },
+                "metadata": { "type": ["object", "null"], "additionalProperties": { "type": "string" } }
+            }
+        },
+        "Batch": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "object": { "const": "batch" },
+                "endpoint": { "type": "string" },
+                "input_file_id": { "type": "string" },
+                "completion_window": { "type": "string" },
+                "status": {
+                    "type": "string",
+                    "enum": ["validating", "failed", "in_progress", "finalizing", "completed", "expired", "cancelling", "cancelled"]
+                },
+                "output_file_id": nullable("string"),
+                "error_file_id": nullable("string"),
+                "created_at": { "type": "integer" },
+                "request_counts": {
+                    "type": "object",
+                    "properties": {
+                        "total": { "type": "integer" },
+                        "completed": { "type": "integer" },
+                        "failed": { "type": "integer" }
+                    }
+                }
+            }
+        },
+        "BatchList": {
+            "type": "object",
+            "properties": {
+                "object": { "const": "list" },
+                "data": { "type": "array", "items": schema_ref("Batch") },
+                "first_id": nullable("string"),
+                "last_id": nullable("string"),
+                "has_more": { "type": "boolean" }
+            }
+        },
+        "Job": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "object": { "const": "job" },
+                "status": {
+                    "type": "string",
+                    "enum": ["queued", "in_progress", "completed", "failed", "cancelled"]
+                },
+                "model": { "type": "string" },
+                "created_at": { "type": "integer" },
+                "started_at": nullable("integer"),
+                "finished_at": nullable("integer"),
+                "status_code": nullable("integer"),
+                "result": { "oneOf": [schema_ref("ChatCompletion"), { "type": "null" }] },
+                "error": {}
+            }
+        },
+        "Pong": {
+            "type": "object",
+            "properties": {
+                "object": { "const": "pong" },
+                "sequence": { "type": "integer" },
+                "server_time": { "type": "string", "format": "date-time" },
+                "server_time_ms": { "type": "integer" },
+                "queue": {
+                    "type": "object",
+                    "properties": {
+                        "in_flight": { "type": "integer" },
+                        "active_streams": { "type": "integer" },
+                        "queued_jobs": { "type": "integer" }
+                    }
+                }
+            }
+        },
+        "ConnectionReport": {
+            "type": "object",
+            "required": ["client", "rtt_ms"],
+            "properties": {
+                "client": { "type": "string", "minLength": 1, "maxLength": 64 },
+                "rtt_ms": { "type": "number", "minimum": 0 },
+                "jitter_ms": { "type": "number", "minimum": 0 },
+                "loss": { "type": "number", "minimum": 0, "maximum": 1 }
+            }
+        },
+        "ModerationRequest": {
+            "type": "object",
+            "required": ["input"],
+            "properties": {
+                "input": {
+                    "description": "Text, several texts, or one input made of content parts of which the text parts are checked",
+                    "oneOf": [
+                        { "type": "string" },
+                        { "type": "array", "items": { "type": "string" } },
+                        { "type": "array", "items": { "type": "object" } }
+                    ]
+                },
+                "model": { "type": "string" }
+            }
+        },
+        "Moderation": {
+            "type": "object",
+            "properties": {
+                "id": { "type": "string" },
+                "model": { "type": "string" },
+                "results": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "flagged": { "type": "boolean" },
+                            "categories": { "type": "object", "additionalProperties": { "type": "boolean" } },
+                            "category_scores": { "type": "object", "additionalProperties": { "type": "number" } }
+                        }
+                    }
+                }
+            }
+        },
+        "OllamaMessage": {
+            "type": "object",
+            "required": ["role"],
+            "properties": {
+                "role": { "type": "string" },
+                "content": { "type": "string" }
+            }
+        },
+        "OllamaOptions": {
+            "type": "object",
+            "properties": {
+                "temperature": { "type": "number" },
+                "top_p": { "type": "number" },
+                "num_predict": { "type": "integer" },
+                "seed": { "type": "integer" },
+                "stop": { "type": "array", "items": { "type": "string" } }
+            }
+        },
+        "OllamaChatRequest": {
+            "type": "object",
+            "required": ["model"],
+            "properties": {
+                "model": { "type": "string" },
+                "messages": { "type": "array", "items": schema_ref("OllamaMessage") },
+                "stream": { "type": "boolean", "default": true },
+                "format": {},
+                "options": schema_ref("OllamaOptions")
+            }
+        },
+        "OllamaGenerateRequest": {
+            "type": "object",
+            "required": ["model"],
+            "properties": {
+                "model": { "type": "string" },
+                "prompt": { "type": "string" },
+                "system": { "type": "string" },
+                "stream": { "type": "boolean", "default": true },
+                "format": {},
+                "options": schema_ref("OllamaOptions")
+            }
+        },
+        "OllamaResponse": {
+            "type": "object",
+            "description": "`message` for /api/chat, `response` for /api/generate",
+            "properties": {
+                "model": { "type": "string" },
+                "created_at": { "type": "string", "format": "date-time" },
+                "message": schema_ref("OllamaMessage"),
+                "response": { "type": "string" },
+                "done": { "type": "boolean" },
+                "done_reason": { "type": "string" }
+            }
+        },
+        "OllamaTags": {
+            "type": "object",
+            "properties": {
+                "models": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string" },
+                            "model": { "type": "string" },
+                            "modified_at": { "type": "string", "format": "date-time" }
+                        }
+                    }
+                }
+            }
+        },
+        "HealthLevel": { "type": "string", "enum": ["ok", "degraded", "down"] },
+        "ComponentHealth": {
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": "server, auth, upstream, queue, tunnel, bifrost or dashboard"
+                },
+                "level": schema_ref("HealthLevel"),
+                "reason": nullable("string"),
+                "critical": { "type": "boolean" },
+                "checked_at": { "type": "string", "format": "date-time" },
+                "details": {
+                    "type": "object",
+                    "description": "State of the component, e.g. expires_in_secs for auth or in_flight for queue"
+                }
+            }
+        },
+        "Health": {
+            "type": "object",
+            "description": "Answered with 200 whatever the levels",
+            "properties": {
+                "status": schema_ref("HealthLevel"),
+                "reason": nullable("string"),
+                "components": { "type": "array", "items": schema_ref("ComponentHealth") },
+                "timestamp": { "type": "integer" },
+                "service": { "type": "string" }
+            }
+        },
+        "OpenApi": {
+            "type": "object",
+            "description": "An OpenAPI 3.1 document"
+        }
+    })
+}
+
+/// The OpenAPI document for a server reachable at `server_url`
+pub fn document(server_url: &str) -> Value {
+    let mut paths = Map::new();
+    for endpoint in ENDPOINTS {
+        let path = paths
+            .entry(endpoint.path.to_string())
+            .or_insert_with(|| json!({}));
+        path[endpoint.method] = endpoint.operation();
+    }
+
+    json!({
+        "openapi": OPENAPI_VERSION,
+        "info": {
+            "title": "MindLink API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "OpenAI- and Ollama-compatible API backed by ChatGPT"
+        },
+        "servers": [{ "url": server_url }],
+        "paths": paths,
+        "components": {
+            "schemas": schemas(),
+            "securitySchemes": {
+                "apiKey": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "Key of an authorized app. Requests without a key are served with the default settings."
+                }
+            }
+        },
+        // The key is optional
+        "security": [{}, { "apiKey": [] }]
+    })
+}
+
+/// Swagger UI for the document at `/openapi.json`
+pub fn docs_page() -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>MindLink API Docs</title>
+    <link rel="stylesheet" href="{ui}/swagger-ui.css">
+</head>
+<body>
+    <div id="swagger-ui"></div>
+    <script src="{ui}/swagger-ui-bundle.js"></script>
+    <script>
+        SwaggerUIBundle({{ url: '/openapi.json', dom_id: '#swagger-ui' }});
+    </script>
+</body>
+</html>
+"#,
+        ui = SWAGGER_UI
+    )
+}
+
+/// Entries of the playground's endpoint list
+pub fn endpoint_list_html() -> String {
+    ENDPOINTS
+        .iter()
+        .map(|endpoint| {
+            format!(
+                "            <div class=\"endpoint\">\n                <h3>{}</h3>\n                <code>{} {}</code>\n            </div>\n",
+                endpoint.summary,
+                endpoint.method.to_uppercase(),
+                endpoint.path
+            )
+        })
+        .collect()
+}
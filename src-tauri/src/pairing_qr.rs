@@ -0,0 +1,97 @@
+// QR code of the pairing data
+//
+// Clients pair by scanning the tunnel URL and tokens. The code is rendered
+// here instead of by the frontend, as PNG and as SVG, so it can also be saved
+// to a file to print it or send it to a device that is not at hand.
+
+use crate::error::{MindLinkError, MindLinkResult};
+use qrcode::render::svg;
+use qrcode::{Color, QrCode};
+use std::path::Path;
+
+/// Pixels per module of the PNG
+const MODULE_PIXELS: usize = 8;
+
+/// Light modules around the code, which scanners need to find it
+const QUIET_ZONE: usize = 4;
+
+/// Least width and height of the SVG, in pixels
+const SVG_MIN_SIZE: u32 = 256;
+
+fn qr_error(message: &str, source: Option<anyhow::Error>) -> MindLinkError {
+    MindLinkError::Internal {
+        message: message.to_string(),
+        component: Some("PairingQr".to_string()),
+        source,
+    }
+}
+
+fn encode(data: &str) -> MindLinkResult<QrCode> {
+    QrCode::new(data.as_bytes())
+        .map_err(|e| qr_error("The pairing data does not fit in a QR code", Some(e.into())))
+}
+
+/// `data` as an SVG QR code
+pub fn svg(data: &str) -> MindLinkResult<String> {
+    Ok(encode(data)?
+        .render::<svg::Color>()
+        .min_dimensions(SVG_MIN_SIZE, SVG_MIN_SIZE)
+        .build())
+}
+
+/// `data` as a black and white PNG QR code
+pub fn png(data: &str) -> MindLinkResult<Vec<u8>> {
+    let code = encode(data)?;
+    let width = code.width();
+    let size = (width + 2 * QUIET_ZONE) * MODULE_PIXELS;
+
+    let mut pixels = vec![u8::MAX; size * size];
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color == Color::Light {
+            continue;
+        }
+        let x = (i % width + QUIET_ZONE) * MODULE_PIXELS;
+        let y = (i / width + QUIET_ZONE) * MODULE_PIXELS;
+        for row in y..y + MODULE_PIXELS {
+            let start = row * size + x;
+            pixels[start..start + MODULE_PIXELS].fill(0);
+        }
+    }
+
+    let mut image = Vec::new();
+    let mut encoder = png::Encoder::new(&mut image, size as u32, size as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| {
+            writer.write_image_data(&pixels)?;
+            writer.finish()
+        })
+        .map_err(|e| qr_error("Failed to encode the QR code as PNG", Some(e.into())))?;
+    Ok(image)
+}
+
+/// Save `data` as a QR code to `path`, in the format its extension names:
+/// `.png` or `.svg`
+pub async fn save(path: &Path, data: &str) -> MindLinkResult<()> {
+    let fs_error = |message: &str, source: Option<anyhow::Error>| MindLinkError::FileSystem {
+        message: message.to_string(),
+        path: Some(path.to_string_lossy().to_string()),
+        operation: "write".to_string(),
+        source,
+    };
+
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    let image = match extension.as_deref() {
+        Some("png") => png(data)?,
+        Some("svg") => svg(data)?.into_bytes(),
+        _ => return Err(fs_error("QR codes are saved as .png or .svg files", None)),
+    };
+    tokio::fs::write(path, image)
+        .await
+        .map_err(|e| fs_error("Failed to save the QR code", Some(e.into())))
+}
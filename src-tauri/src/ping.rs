@@ -0,0 +1,106 @@
+// Connection quality heartbeats for companion apps
+//
+// `GET /v1/ping` is answered without touching the ChatGPT backend, with the
+// server time, how busy the server is and a sequence number that grows with
+// every ping. Apps ping through the tunnel at a steady rate and derive round
+// trip time and jitter from the answers; gaps in the sequence reveal pings of
+// other clients. Apps may post their measurements to `/v1/ping/report`, and
+// the most recent reports are kept for the dashboard.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Reports kept before the oldest are dropped
+pub const REPORT_CAPACITY: usize = 500;
+
+/// Work the server has on hand when answering a ping
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueueDepth {
+    /// Upstream requests in flight across all accounts
+    pub in_flight: usize,
+    pub active_streams: i64,
+    /// Jobs waiting for a free slot
+    pub queued_jobs: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pong {
+    pub object: String,
+    pub sequence: u64,
+    pub server_time: DateTime<Utc>,
+    /// Milliseconds since the Unix epoch, for arithmetic on the client
+    pub server_time_ms: i64,
+    pub queue: QueueDepth,
+}
+
+/// Measurements an app took with a series of pings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionReport {
+    /// Name the app reports under, e.g. `phone`
+    pub client: String,
+    pub rtt_ms: f64,
+    #[serde(default)]
+    pub jitter_ms: Option<f64>,
+    /// Share of pings without an answer, 0 to 1
+    #[serde(default)]
+    pub loss: Option<f64>,
+    /// Set by the server when the report arrives
+    #[serde(default)]
+    pub received_at: Option<DateTime<Utc>>,
+}
+
+impl ConnectionReport {
+    /// Check that the numbers are plausible measurements
+    pub fn validate(&self) -> Result<(), String> {
+        if self.client.trim().is_empty() || self.client.len() > 64 {
+            return Err("client must be 1 to 64 characters".to_string());
+        }
+        let valid_ms = |ms: f64| ms.is_finite() && ms >= 0.0;
+        if !valid_ms(self.rtt_ms) || !self.jitter_ms.map_or(true, valid_ms) {
+            return Err("rtt_ms and jitter_ms must be non-negative numbers".to_string());
+        }
+        if !self.loss.map_or(true, |loss| (0.0..=1.0).contains(&loss)) {
+            return Err("loss must be between 0 and 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Ping sequence and reported connection quality. Lives for the lifetime of
+/// the server manager, so the sequence keeps growing across restarts.
+#[derive(Debug, Default)]
+pub struct PingTracker {
+    sequence: AtomicU64,
+    reports: Mutex<VecDeque<ConnectionReport>>,
+}
+
+impl PingTracker {
+    pub fn pong(&self, queue: QueueDepth) -> Pong {
+        let now = Utc::now();
+        Pong {
+            object: "pong".to_string(),
+            sequence: self.sequence.fetch_add(1, Ordering::Relaxed) + 1,
+            server_time: now,
+            server_time_ms: now.timestamp_millis(),
+            queue,
+        }
+    }
+
+    pub fn report(&self, mut report: ConnectionReport) {
+        report.received_at = Some(Utc::now());
+        let mut reports = self.reports.lock().unwrap_or_else(|e| e.into_inner());
+        if reports.len() >= REPORT_CAPACITY {
+            reports.pop_back();
+        }
+        reports.push_front(report);
+    }
+
+    /// Up to `limit` reports, newest first
+    pub fn recent(&self, limit: usize) -> Vec<ConnectionReport> {
+        let reports = self.reports.lock().unwrap_or_else(|e| e.into_inner());
+        reports.iter().take(limit).cloned().collect()
+    }
+}
@@ -0,0 +1,91 @@
+// ChatGPT plans and what they allow
+//
+// How much the ChatGPT backend lets an account ask of it depends on the
+// subscription: a Pro account sustains far more concurrent requests than a
+// free one. The plan comes from the `chatgpt_plan_type` claim of the ID token
+// and the end of the subscription from `chatgpt_subscription_active_until`.
+// OpenAI does not publish the caps, so the concurrency per plan below is a
+// conservative estimate. Unless `backpressure.adapt_to_plan` is off, the
+// server caps `backpressure.max_in_flight` at what the signed-in accounts
+// together are expected to sustain.
+
+use crate::managers::auth_manager::UserIdentity;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum AccountPlan {
+    Free,
+    Plus,
+    Pro,
+    /// Team and Business workspaces
+    Team,
+    /// Enterprise and Edu workspaces
+    Enterprise,
+    /// A plan MindLink does not know, or none in the ID token
+    Unknown,
+}
+
+impl AccountPlan {
+    /// Plan named by the `chatgpt_plan_type` claim
+    pub fn parse(plan: &str) -> Self {
+        match plan.to_ascii_lowercase().as_str() {
+            "free" => Self::Free,
+            "plus" => Self::Plus,
+            "pro" => Self::Pro,
+            "team" | "business" => Self::Team,
+            "enterprise" | "edu" => Self::Enterprise,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// Concurrent requests one account on this plan is expected to sustain
+    pub fn max_in_flight(self) -> Option<usize> {
+        match self {
+            Self::Free => Some(2),
+            Self::Plus => Some(4),
+            Self::Team => Some(6),
+            Self::Pro | Self::Enterprise => Some(16),
+            Self::Unknown => None,
+        }
+    }
+}
+
+/// Plan of an account and the caps that come with it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+pub struct PlanQuota {
+    pub plan: AccountPlan,
+    /// Concurrent requests the account is expected to sustain, unknown for
+    /// plans MindLink does not know
+    pub max_in_flight: Option<usize>,
+    /// When the subscription ends, if the ID token tells
+    pub active_until: Option<DateTime<Utc>>,
+}
+
+impl PlanQuota {
+    pub fn from_identity(identity: &UserIdentity) -> Self {
+        let plan = identity
+            .plan
+            .as_deref()
+            .map_or(AccountPlan::Unknown, AccountPlan::parse);
+        Self {
+            plan,
+            max_in_flight: plan.max_in_flight(),
+            active_until: identity.subscription_until,
+        }
+    }
+}
+
+/// Concurrent requests a pool of accounts is expected to sustain: the sum
+/// over its accounts, or `None` when the plan of any of them is unknown
+pub fn pool_max_in_flight<'a>(quotas: impl IntoIterator<Item = &'a PlanQuota>) -> Option<usize> {
+    let mut total = 0;
+    for quota in quotas {
+        total += quota.max_in_flight?;
+    }
+    (total > 0).then_some(total)
+}
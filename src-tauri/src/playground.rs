@@ -0,0 +1,326 @@
+// Chat playground served by the API server
+//
+// `GET /playground` is a single page that talks to the API of the server that
+// served it, so completions can be tried end to end in a browser on this
+// machine without a tunnel or an API client. The conversation and the API key
+// entered live in the tab's `sessionStorage`: nothing is stored on the server,
+// and closing the tab forgets both. Replies are streamed from
+// `/v1/chat/completions` exactly as any other client would receive them.
+//
+// The page replaces the former status page at `/dashboard`, which now
+// redirects here, and keeps its list of endpoints.
+
+use crate::openapi;
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>MindLink Playground</title>
+    <style>
+        * { box-sizing: border-box; }
+        body {
+            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif;
+            margin: 0;
+            padding: 20px;
+            background: linear-gradient(135deg, #667eea 0%, #764ba2 100%);
+            min-height: 100vh;
+            color: white;
+        }
+        .container {
+            max-width: 860px;
+            margin: 0 auto;
+            background: rgba(255, 255, 255, 0.1);
+            backdrop-filter: blur(10px);
+            border-radius: 15px;
+            padding: 30px;
+            box-shadow: 0 8px 32px rgba(0, 0, 0, 0.3);
+        }
+        .header { text-align: center; }
+        .status {
+            display: flex;
+            align-items: center;
+            justify-content: center;
+            gap: 8px;
+        }
+        .status-dot {
+            width: 12px;
+            height: 12px;
+            border-radius: 50%;
+            background: #9ca3af;
+        }
+        .status-dot.ok { background: #4ade80; }
+        .status-dot.degraded { background: #fbbf24; }
+        .status-dot.down { background: #f87171; }
+        .settings {
+            display: flex;
+            gap: 10px;
+            margin: 25px 0 15px;
+        }
+        .settings label { flex: 1; font-size: 14px; }
+        input, select, textarea, button {
+            font: inherit;
+            color: inherit;
+            background: rgba(0, 0, 0, 0.25);
+            border: 1px solid rgba(255, 255, 255, 0.25);
+            border-radius: 8px;
+            padding: 8px 10px;
+        }
+        .settings input, .settings select { width: 100%; margin-top: 4px; }
+        select option { color: black; }
+        button { cursor: pointer; }
+        button:disabled { opacity: 0.5; cursor: default; }
+        #messages {
+            min-height: 240px;
+            max-height: 60vh;
+            overflow-y: auto;
+            display: flex;
+            flex-direction: column;
+            gap: 10px;
+            padding: 15px;
+            background: rgba(0, 0, 0, 0.2);
+            border-radius: 10px;
+        }
+        .message {
+            max-width: 85%;
+            padding: 10px 14px;
+            border-radius: 10px;
+            white-space: pre-wrap;
+            word-wrap: break-word;
+        }
+        .message.user { align-self: flex-end; background: rgba(251, 191, 36, 0.25); }
+        .message.assistant { align-self: flex-start; background: rgba(255, 255, 255, 0.15); }
+        .message.error { align-self: stretch; background: rgba(248, 113, 113, 0.3); }
+        .empty { margin: auto; opacity: 0.7; }
+        form { display: flex; gap: 10px; margin-top: 15px; }
+        form textarea { flex: 1; resize: vertical; min-height: 44px; }
+        .endpoints {
+            display: grid;
+            gap: 15px;
+            margin-top: 15px;
+        }
+        .endpoint {
+            background: rgba(255, 255, 255, 0.1);
+            padding: 15px;
+            border-radius: 10px;
+            border: 1px solid rgba(255, 255, 255, 0.2);
+        }
+        .endpoint h3 { margin: 0 0 10px 0; color: #fbbf24; }
+        .endpoint code {
+            background: rgba(0, 0, 0, 0.3);
+            padding: 4px 8px;
+            border-radius: 4px;
+            font-family: 'SF Mono', Monaco, monospace;
+        }
+        details { margin-top: 25px; }
+        summary { cursor: pointer; }
+        .docs-link { text-align: center; margin-top: 20px; }
+        .docs-link a { color: #fbbf24; }
+    </style>
+</head>
+<body>
+    <div class="container">
+        <div class="header">
+            <h1>MindLink Playground</h1>
+            <div class="status">
+                <div id="status-dot" class="status-dot"></div>
+                <span id="status-text">Checking server…</span>
+            </div>
+        </div>
+
+        <div class="settings">
+            <label>Model
+                <select id="model"></select>
+            </label>
+            <label>API key (optional, to test an app's limits)
+                <input id="api-key" type="password" autocomplete="off" placeholder="sk-mindlink-…">
+            </label>
+        </div>
+
+        <div id="messages"></div>
+
+        <form id="composer">
+            <textarea id="prompt" rows="2" placeholder="Send a message (Enter to send, Shift+Enter for a new line)"></textarea>
+            <button id="send" type="submit">Send</button>
+            <button id="stop" type="button" disabled>Stop</button>
+            <button id="clear" type="button">Clear</button>
+        </form>
+
+        <details>
+            <summary>Endpoints</summary>
+            <div class="endpoints">
+<!-- endpoints -->            </div>
+        </details>
+        <p class="docs-link">Schemas and a playground for every endpoint: <a href="/docs">API docs</a></p>
+    </div>
+
+    <script>
+        const HISTORY = 'mindlink.playground.messages';
+        const API_KEY = 'mindlink.playground.apiKey';
+
+        const messagesEl = document.getElementById('messages');
+        const modelEl = document.getElementById('model');
+        const apiKeyEl = document.getElementById('api-key');
+        const promptEl = document.getElementById('prompt');
+        const sendEl = document.getElementById('send');
+        const stopEl = document.getElementById('stop');
+
+        let messages = JSON.parse(sessionStorage.getItem(HISTORY) || '[]');
+        let controller = null;
+
+        apiKeyEl.value = sessionStorage.getItem(API_KEY) || '';
+        apiKeyEl.addEventListener('change', () => sessionStorage.setItem(API_KEY, apiKeyEl.value.trim()));
+
+        const headers = () => {
+            const result = { 'Content-Type': 'application/json' };
+            const key = apiKeyEl.value.trim();
+            if (key) result['Authorization'] = 'Bearer ' + key;
+            return result;
+        };
+
+        const addBubble = (role, text) => {
+            messagesEl.querySelector('.empty')?.remove();
+            const bubble = document.createElement('div');
+            bubble.className = 'message ' + role;
+            bubble.textContent = text;
+            messagesEl.appendChild(bubble);
+            messagesEl.scrollTop = messagesEl.scrollHeight;
+            return bubble;
+        };
+
+        const render = () => {
+            messagesEl.replaceChildren();
+            if (messages.length === 0) {
+                const empty = document.createElement('p');
+                empty.className = 'empty';
+                empty.textContent = 'Replies come from this server, the same way API clients get them.';
+                messagesEl.appendChild(empty);
+            }
+            messages.forEach((message) => addBubble(message.role, message.content));
+        };
+
+        const save = () => sessionStorage.setItem(HISTORY, JSON.stringify(messages));
+
+        const errorMessage = async (response) => {
+            try {
+                const body = await response.json();
+                return body.error?.message || JSON.stringify(body);
+            } catch {
+                return response.status + ' ' + response.statusText;
+            }
+        };
+
+        const checkHealth = async () => {
+            const dot = document.getElementById('status-dot');
+            const text = document.getElementById('status-text');
+            try {
+                const health = await (await fetch('/health')).json();
+                dot.className = 'status-dot ' + health.status;
+                text.textContent = health.reason || 'Server is running';
+            } catch {
+                dot.className = 'status-dot down';
+                text.textContent = 'Server is not answering';
+            }
+        };
+
+        const loadModels = async () => {
+            try {
+                const response = await fetch('/v1/models', { headers: headers() });
+                if (!response.ok) throw new Error(await errorMessage(response));
+                const { data } = await response.json();
+                modelEl.replaceChildren(...data.map((model) => new Option(model.id, model.id)));
+            } catch (error) {
+                addBubble('error', 'Could not load models: ' + error.message);
+            }
+        };
+
+        const send = async (text) => {
+            messages.push({ role: 'user', content: text });
+            save();
+            addBubble('user', text);
+            const bubble = addBubble('assistant', '');
+            let reply = '';
+
+            controller = new AbortController();
+            sendEl.disabled = true;
+            stopEl.disabled = false;
+            try {
+                const response = await fetch('/v1/chat/completions', {
+                    method: 'POST',
+                    headers: headers(),
+                    body: JSON.stringify({ model: modelEl.value, messages, stream: true }),
+                    signal: controller.signal,
+                });
+                if (!response.ok) throw new Error(await errorMessage(response));
+
+                const reader = response.body.pipeThrough(new TextDecoderStream()).getReader();
+                let buffer = '';
+                for (;;) {
+                    const { value, done } = await reader.read();
+                    if (done) break;
+                    buffer += value;
+                    const events = buffer.split('\n\n');
+                    buffer = events.pop();
+                    for (const event of events) {
+                        const data = event.split('\n')
+                            .filter((line) => line.startsWith('data:'))
+                            .map((line) => line.slice(5).trim())
+                            .join('');
+                        if (!data || data === '[DONE]') continue;
+                        const chunk = JSON.parse(data);
+                        if (chunk.error) throw new Error(chunk.error.message);
+                        reply += chunk.choices?.[0]?.delta?.content || '';
+                        bubble.textContent = reply;
+                        messagesEl.scrollTop = messagesEl.scrollHeight;
+                    }
+                }
+            } catch (error) {
+                if (error.name !== 'AbortError') addBubble('error', error.message);
+            } finally {
+                if (reply) {
+                    messages.push({ role: 'assistant', content: reply });
+                    save();
+                } else {
+                    bubble.remove();
+                }
+                controller = null;
+                sendEl.disabled = false;
+                stopEl.disabled = true;
+            }
+        };
+
+        document.getElementById('composer').addEventListener('submit', (event) => {
+            event.preventDefault();
+            const text = promptEl.value.trim();
+            if (!text || controller) return;
+            promptEl.value = '';
+            send(text);
+        });
+        promptEl.addEventListener('keydown', (event) => {
+            if (event.key === 'Enter' && !event.shiftKey) {
+                event.preventDefault();
+                document.getElementById('composer').requestSubmit();
+            }
+        });
+        stopEl.addEventListener('click', () => controller?.abort());
+        document.getElementById('clear').addEventListener('click', () => {
+            controller?.abort();
+            messages = [];
+            save();
+            render();
+        });
+
+        render();
+        checkHealth();
+        loadModels();
+        setInterval(checkHealth, 30000);
+    </script>
+</body>
+</html>
+"#;
+
+/// The playground page, with the endpoint list of `openapi`
+pub fn page() -> String {
+    PAGE.replace("<!-- endpoints -->", &openapi::endpoint_list_html())
+}
@@ -0,0 +1,189 @@
+// Post-processing of completions
+//
+// The rules in `post_processing.rules` rewrite the text of non-streamed
+// completions, in order, before it is returned: regex replacements, cutting
+// the text off at stop sequences, reducing Markdown to plain text and keeping
+// only the JSON in it. A rule can be limited to some models, as the client
+// asked for them. Streamed completions are sent as they arrive and are not
+// post-processed.
+//
+// The rules can be edited while the server runs; the completion routes pick
+// up the new rules with the next response.
+
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::managers::config_manager::{PostProcessingAction, PostProcessingConfig};
+use regex::Regex;
+use serde_json::Value;
+use std::sync::OnceLock;
+
+/// Markdown syntax and what it is replaced by, in the order it is removed:
+/// code fences before inline code, images before links
+fn markdown_syntax() -> &'static [(Regex, &'static str)] {
+    static SYNTAX: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    SYNTAX.get_or_init(|| {
+        [
+            (r"(?m)^[ \t]*(?:```|~~~)[^\n]*\n?", ""),
+            (r"!\[([^\]]*)\]\([^)]*\)", "$1"),
+            (r"\[([^\]]+)\]\([^)]*\)", "$1"),
+            (r"(?m)^[ \t]{0,3}#{1,6}[ \t]+", ""),
+            (r"(?m)^[ \t]{0,3}>[ \t]?", ""),
+            (r"(?m)^[ \t]{0,3}(?:-{3,}|\*{3,}|_{3,})[ \t]*$", ""),
+            (r"\*\*([^*\n]+)\*\*", "$1"),
+            (r"__([^_\n]+)__", "$1"),
+            (r"\*([^*\n]+)\*", "$1"),
+            (r"\b_([^_\n]+)_\b", "$1"),
+            (r"~~([^~\n]+)~~", "$1"),
+            (r"`([^`\n]+)`", "$1"),
+        ]
+        .into_iter()
+        .map(|(pattern, replacement)| {
+            (
+                Regex::new(pattern).expect("Markdown patterns are valid"),
+                replacement,
+            )
+        })
+        .collect()
+    })
+}
+
+#[derive(Debug)]
+enum Step {
+    Replace { pattern: Regex, replacement: String },
+    Stop(Vec<String>),
+    StripMarkdown,
+    ExtractJson,
+}
+
+impl Step {
+    fn apply(&self, text: &str) -> String {
+        match self {
+            Step::Replace {
+                pattern,
+                replacement,
+            } => pattern.replace_all(text, replacement.as_str()).into_owned(),
+            Step::Stop(sequences) => {
+                let end = sequences
+                    .iter()
+                    .filter_map(|sequence| text.find(sequence.as_str()))
+                    .min()
+                    .unwrap_or(text.len());
+                text[..end].to_string()
+            },
+            Step::StripMarkdown => {
+                markdown_syntax()
+                    .iter()
+                    .fold(text.to_string(), |text, (pattern, replacement)| {
+                        pattern.replace_all(&text, *replacement).into_owned()
+                    })
+            },
+            Step::ExtractJson => extract_json(text).unwrap_or(text).to_string(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Rule {
+    models: Vec<String>,
+    step: Step,
+}
+
+/// The post-processing rules in effect, compiled
+#[derive(Debug, Default)]
+pub struct PostProcessor {
+    rules: Vec<Rule>,
+}
+
+impl PostProcessor {
+    pub fn from_config(config: &PostProcessingConfig) -> MindLinkResult<Self> {
+        let mut rules = Vec::new();
+        for rule in &config.rules {
+            let step = match &rule.action {
+                PostProcessingAction::RegexReplace {
+                    pattern,
+                    replacement,
+                } => Step::Replace {
+                    pattern: Regex::new(pattern).map_err(|e| MindLinkError::Configuration {
+                        message: format!("Invalid post-processing pattern '{}'", pattern),
+                        config_key: Some("post_processing.rules".to_string()),
+                        source: Some(e.into()),
+                    })?,
+                    replacement: replacement.clone(),
+                },
+                PostProcessingAction::StopSequences { sequences } => Step::Stop(
+                    sequences
+                        .iter()
+                        .filter(|s| !s.is_empty())
+                        .cloned()
+                        .collect(),
+                ),
+                PostProcessingAction::StripMarkdown => Step::StripMarkdown,
+                PostProcessingAction::ExtractJson => Step::ExtractJson,
+            };
+            rules.push(Rule {
+                models: rule.models.clone(),
+                step,
+            });
+        }
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// `text` after every rule for `model`
+    pub fn apply(&self, model: &str, text: &str) -> String {
+        self.rules
+            .iter()
+            .filter(|rule| rule.models.is_empty() || rule.models.iter().any(|m| m == model))
+            .fold(text.to_string(), |text, rule| rule.step.apply(&text))
+    }
+
+    /// Rewrite the completion text of a chat completion, or of an Ollama
+    /// chat or generate response, in place. Returns whether it changed.
+    pub fn process_response(&self, body: &mut Value) -> bool {
+        let model = body
+            .get("model")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+
+        let texts: Vec<&mut Value> = if body.get("choices").is_some() {
+            body["choices"]
+                .as_array_mut()
+                .into_iter()
+                .flatten()
+                .filter_map(|choice| choice.pointer_mut("/message/content"))
+                .collect()
+        } else if body.get("message").is_some() {
+            body.pointer_mut("/message/content").into_iter().collect()
+        } else {
+            body.get_mut("response").into_iter().collect()
+        };
+
+        let mut changed = false;
+        for text in texts {
+            let Value::String(content) = text else {
+                continue;
+            };
+            let processed = self.apply(&model, content);
+            if processed != *content {
+                *content = processed;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+/// The first JSON object or array in `text`, e.g. in a fenced code block
+/// surrounded by an explanation
+fn extract_json(text: &str) -> Option<&str> {
+    text.match_indices(['{', '[']).find_map(|(start, _)| {
+        let mut values = serde_json::Deserializer::from_str(&text[start..]).into_iter::<Value>();
+        match values.next() {
+            Some(Ok(_)) => Some(&text[start..start + values.byte_offset()]),
+            _ => None,
+        }
+    })
+}
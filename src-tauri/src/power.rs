@@ -0,0 +1,223 @@
+// Power source detection and the battery power saver
+//
+// While the power saver is active the health monitor checks less often,
+// analytics are only written in full batches and, when configured, Bifrost is
+// stopped. Everything is restored once the machine is back on AC power.
+
+use crate::events::{self, AppEvent};
+use crate::managers::config_manager::{PowerSaverConfig, PowerSaverMode};
+use crate::AppState;
+use crate::{log_error, log_info};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use ts_rs::TS;
+
+/// Health check interval while not saving power
+pub const HEALTH_CHECK_INTERVAL_SECS: u64 = 30;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+pub enum PowerSource {
+    Ac,
+    Battery,
+    /// Detection failed or the platform is not supported; treated like AC
+    #[default]
+    Unknown,
+}
+
+/// Current power saver state, shown in the UI
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+pub struct PowerStatus {
+    pub source: PowerSource,
+    pub mode: PowerSaverMode,
+    pub active: bool,
+    /// Bifrost was stopped by the power saver and is started again on AC power
+    pub bifrost_stopped: bool,
+}
+
+/// Whether the power saver should be active
+pub fn saver_active(mode: PowerSaverMode, source: PowerSource) -> bool {
+    match mode {
+        PowerSaverMode::On => true,
+        PowerSaverMode::Off => false,
+        PowerSaverMode::Auto => source == PowerSource::Battery,
+    }
+}
+
+/// Health check interval for the current state
+pub fn health_check_interval(status: &PowerStatus, config: &PowerSaverConfig) -> u64 {
+    if status.active {
+        config.health_check_interval_secs
+    } else {
+        HEALTH_CHECK_INTERVAL_SECS
+    }
+}
+
+/// One entry of `/sys/class/power_supply`
+#[derive(Debug, Clone)]
+pub struct PowerSupply {
+    /// `Mains`, `USB`, `Battery`, ...
+    pub kind: String,
+    pub online: Option<bool>,
+    pub status: Option<String>,
+}
+
+/// Power source from Linux power supply entries. Any online external supply
+/// means AC; otherwise a battery means battery power.
+pub fn linux_power_source(supplies: &[PowerSupply]) -> PowerSource {
+    let external_online = supplies
+        .iter()
+        .any(|supply| supply.kind != "Battery" && supply.online == Some(true));
+    if external_online {
+        return PowerSource::Ac;
+    }
+
+    match supplies.iter().find(|supply| supply.kind == "Battery") {
+        Some(battery) if battery.status.as_deref() == Some("Discharging") => PowerSource::Battery,
+        Some(_) if supplies.iter().any(|supply| supply.kind != "Battery") => PowerSource::Battery,
+        Some(_) => PowerSource::Unknown,
+        None => PowerSource::Ac,
+    }
+}
+
+/// Power source from `pmset -g batt` output on macOS
+pub fn parse_pmset(output: &str) -> PowerSource {
+    if output.contains("'Battery Power'") {
+        PowerSource::Battery
+    } else if output.contains("'AC Power'") {
+        PowerSource::Ac
+    } else {
+        PowerSource::Unknown
+    }
+}
+
+/// Detect the current power source. Blocking; run it on the blocking pool.
+pub fn detect_power_source() -> PowerSource {
+    #[cfg(target_os = "linux")]
+    {
+        let Ok(entries) = std::fs::read_dir("/sys/class/power_supply") else {
+            return PowerSource::Unknown;
+        };
+        let read = |path: &std::path::Path, name: &str| {
+            std::fs::read_to_string(path.join(name))
+                .ok()
+                .map(|value| value.trim().to_string())
+        };
+        let supplies: Vec<PowerSupply> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                Some(PowerSupply {
+                    kind: read(&path, "type")?,
+                    online: read(&path, "online").map(|value| value == "1"),
+                    status: read(&path, "status"),
+                })
+            })
+            .collect();
+        linux_power_source(&supplies)
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("pmset")
+            .args(["-g", "batt"])
+            .output()
+            .map(|output| parse_pmset(&String::from_utf8_lossy(&output.stdout)))
+            .unwrap_or(PowerSource::Unknown)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // Win32_Battery.BatteryStatus is 1 while discharging; no battery prints nothing
+        let output = std::process::Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-Command",
+                "(Get-CimInstance Win32_Battery).BatteryStatus",
+            ])
+            .output();
+        match output {
+            Ok(output) if String::from_utf8_lossy(&output.stdout).trim() == "1" => {
+                PowerSource::Battery
+            },
+            Ok(_) => PowerSource::Ac,
+            Err(_) => PowerSource::Unknown,
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        PowerSource::Unknown
+    }
+}
+
+/// Re-detect the power source and switch the power saver on or off when the
+/// result changed. Emits `power-saver-changed` on every switch.
+pub async fn refresh_power_saver(app_handle: &AppHandle) -> PowerStatus {
+    let state = app_handle.state::<AppState>();
+    let config = state
+        .config_manager
+        .read()
+        .await
+        .get_power_saver_config()
+        .await;
+    let source = tokio::task::spawn_blocking(detect_power_source)
+        .await
+        .unwrap_or_default();
+    let active = saver_active(config.mode, source);
+
+    let mut status = state.power_status.write().await;
+    status.source = source;
+    status.mode = config.mode;
+    if status.active == active {
+        return status.clone();
+    }
+
+    status.active = active;
+    state
+        .server_manager
+        .read()
+        .await
+        .set_analytics_paused(active);
+
+    if active && config.stop_bifrost {
+        let mut bifrost_manager = state.bifrost_manager.write().await;
+        if bifrost_manager.is_running().await {
+            match bifrost_manager.stop().await {
+                Ok(()) => {
+                    status.bifrost_stopped = true;
+                    state
+                        .server_manager
+                        .read()
+                        .await
+                        .set_bifrost_url(None)
+                        .await;
+                },
+                Err(e) => log_error!("PowerSaver", e),
+            }
+        }
+    } else if !active && status.bifrost_stopped {
+        status.bifrost_stopped = false;
+        let mut bifrost_manager = state.bifrost_manager.write().await;
+        match bifrost_manager.start().await {
+            Ok(()) => {
+                let url = bifrost_manager.get_local_url().await;
+                state.server_manager.read().await.set_bifrost_url(url).await;
+            },
+            Err(e) => log_error!("PowerSaver", e),
+        }
+    }
+
+    log_info!(
+        "PowerSaver",
+        &format!(
+            "Power saver {} ({:?} power, mode {:?})",
+            if active { "enabled" } else { "disabled" },
+            source,
+            config.mode
+        )
+    );
+    events::emit(app_handle, AppEvent::PowerSaverChanged(status.clone()));
+
+    status.clone()
+}
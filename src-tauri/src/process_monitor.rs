@@ -204,6 +204,7 @@ impl ProcessMonitor {
                     "stdout".to_string(),
                     stdout,
                     config.output_buffer_size,
+                    None,
                 )
                 .await;
             }
@@ -216,6 +217,7 @@ impl ProcessMonitor {
                     "stderr".to_string(),
                     stderr,
                     config.output_buffer_size,
+                    None,
                 )
                 .await;
             }
@@ -249,6 +251,36 @@ impl ProcessMonitor {
         Ok(())
     }
 
+    /// Capture an output stream of a process the caller runs itself. The
+    /// lines are logged and sent as events like those of monitored processes,
+    /// and forwarded to `lines` so the caller can read what the process says.
+    pub async fn capture_output<T>(
+        &self,
+        process_id: &str,
+        output_type: &str,
+        stream: T,
+        lines: mpsc::UnboundedSender<String>,
+    ) where
+        T: tokio::io::AsyncRead + Unpin + Send + 'static,
+    {
+        let buffer_size = {
+            let configs = self.configs.read().await;
+            configs
+                .get(process_id)
+                .cloned()
+                .unwrap_or_default()
+                .output_buffer_size
+        };
+        self.monitor_output(
+            process_id.to_string(),
+            output_type.to_string(),
+            stream,
+            buffer_size,
+            Some(lines),
+        )
+        .await;
+    }
+
     /// Monitor output from a process
     async fn monitor_output<T>(
         &self,
@@ -256,6 +288,7 @@ impl ProcessMonitor {
         output_type: String,
         stream: T,
         buffer_size: usize,
+        forward_to: Option<mpsc::UnboundedSender<String>>,
     ) where
         T: tokio::io::AsyncRead + Unpin + Send + 'static,
     {
@@ -275,6 +308,10 @@ impl ProcessMonitor {
                     logger.log_process_output(&process_id, &output_type, &line, None);
                 }
 
+                if let Some(forward_to) = &forward_to {
+                    let _ = forward_to.send(line.clone());
+                }
+
                 // Send event for real-time monitoring
                 let _ = event_sender.send(ProcessEvent::OutputReceived {
                     process_id: process_id.clone(),
@@ -419,6 +456,19 @@ impl ProcessMonitor {
         Ok(())
     }
 
+    /// Stop every process that is still running, returning the IDs of those
+    /// that failed to stop
+    pub async fn stop_all(&self) -> Vec<String> {
+        let process_ids: Vec<String> = self.child_handles.read().await.keys().cloned().collect();
+        let mut failed = Vec::new();
+        for process_id in process_ids {
+            if self.stop_process(&process_id).await.is_err() {
+                failed.push(process_id);
+            }
+        }
+        failed
+    }
+
     /// Get information about a monitored process
     pub async fn get_process_info(&self, process_id: &str) -> Option<ProcessInfo> {
         let processes = self.processes.read().await;
@@ -0,0 +1,99 @@
+// System prompts and named prompt templates
+//
+// A system prompt can be configured for every request or per authorized app,
+// and clients can pick one of the named templates from the config instead by
+// sending `prompt_template` (and optionally `prompt_variables`) alongside the
+// usual chat completion fields. Prompts are rendered with `{{variable}}`
+// placeholders before being prepended to the conversation.
+
+use crate::managers::config_manager::PromptConfig;
+use chrono::Utc;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Request field naming the template to use as system prompt
+pub const TEMPLATE_FIELD: &str = "prompt_template";
+/// Request field with the values for the template's variables
+pub const VARIABLES_FIELD: &str = "prompt_variables";
+
+/// Replace `{{name}}` placeholders with their values. Unknown variables are an
+/// error so that a typo does not silently reach the model.
+pub fn render(template: &str, variables: &HashMap<String, String>) -> Result<String, String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + len].trim();
+        let value = variables
+            .get(name)
+            .ok_or_else(|| format!("Unknown prompt template variable '{}'", name))?;
+
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(value);
+        rest = &rest[start + 2 + len + 2..];
+    }
+
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// Variables available to a request: the built-ins `date`, `time` and `model`,
+/// overridden by the configured variables, overridden by the request's own
+fn variables(
+    config: &PromptConfig,
+    model: &str,
+    fields: &Map<String, Value>,
+) -> Result<HashMap<String, String>, String> {
+    let now = Utc::now();
+    let mut variables = HashMap::from([
+        ("date".to_string(), now.format("%Y-%m-%d").to_string()),
+        ("time".to_string(), now.format("%H:%M UTC").to_string()),
+        ("model".to_string(), model.to_string()),
+    ]);
+    variables.extend(config.variables.clone());
+
+    match fields.get(VARIABLES_FIELD) {
+        None | Some(Value::Null) => {},
+        Some(Value::Object(values)) => {
+            for (name, value) in values {
+                let value = match value {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                variables.insert(name.clone(), value);
+            }
+        },
+        Some(_) => return Err(format!("{} must be an object", VARIABLES_FIELD)),
+    }
+
+    Ok(variables)
+}
+
+/// The rendered system prompt for a request: the template the client asked
+/// for, else the app's own prompt, else the configured default
+pub fn system_prompt(
+    config: &PromptConfig,
+    app_prompt: Option<&str>,
+    model: &str,
+    fields: &Map<String, Value>,
+) -> Result<Option<String>, String> {
+    let template = match fields.get(TEMPLATE_FIELD) {
+        None | Some(Value::Null) => app_prompt.or(config.system_prompt.as_deref()),
+        Some(Value::String(name)) => Some(
+            config
+                .templates
+                .get(name)
+                .map(String::as_str)
+                .ok_or_else(|| format!("Unknown prompt template '{}'", name))?,
+        ),
+        Some(_) => return Err(format!("{} must be a template name", TEMPLATE_FIELD)),
+    };
+
+    match template.filter(|template| !template.trim().is_empty()) {
+        Some(template) => render(template, &variables(config, model, fields)?).map(Some),
+        None => Ok(None),
+    }
+}
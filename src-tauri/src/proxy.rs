@@ -0,0 +1,61 @@
+// Outbound proxy for OpenAI and ChatGPT traffic
+//
+// Behind a corporate proxy neither the OAuth login nor chatgpt.com can be
+// reached directly. The proxy from the `proxy` config section is kept here
+// for the whole process, so every client talking to OpenAI picks it up,
+// including those of signed-in accounts opened later. Without one, reqwest
+// falls back to the `HTTPS_PROXY`, `ALL_PROXY` and `NO_PROXY` environment
+// variables.
+
+use crate::log_warn;
+use crate::managers::config_manager::ProxyConfig;
+use reqwest::{Client, ClientBuilder, NoProxy, Proxy};
+use std::sync::{PoisonError, RwLock};
+
+/// URL schemes a proxy can have
+pub const SCHEMES: [&str; 4] = ["http", "https", "socks5", "socks5h"];
+
+/// Local backends such as Bifrost and Ollama share the clients and are
+/// never reached through the proxy
+const LOCAL_HOSTS: [&str; 3] = ["localhost", "127.0.0.1", "::1"];
+
+static PROXY: RwLock<ProxyConfig> = RwLock::new(ProxyConfig {
+    url: None,
+    no_proxy: Vec::new(),
+});
+
+/// Send requests of clients built from now on through the proxy of `config`
+pub fn configure(config: ProxyConfig) {
+    *PROXY.write().unwrap_or_else(PoisonError::into_inner) = config;
+}
+
+/// The proxy `config` describes, if it names one
+pub fn proxy_for(config: &ProxyConfig) -> reqwest::Result<Option<Proxy>> {
+    let Some(url) = config.url.as_deref().filter(|url| !url.is_empty()) else {
+        return Ok(None);
+    };
+    let bypass: Vec<&str> = LOCAL_HOSTS
+        .into_iter()
+        .chain(config.no_proxy.iter().map(String::as_str))
+        .collect();
+    let no_proxy = NoProxy::from_string(&bypass.join(","));
+    Ok(Some(Proxy::all(url)?.no_proxy(no_proxy)))
+}
+
+/// `builder` sending its requests through the configured proxy
+pub fn apply(builder: ClientBuilder) -> ClientBuilder {
+    let config = PROXY.read().unwrap_or_else(PoisonError::into_inner).clone();
+    match proxy_for(&config) {
+        Ok(Some(proxy)) => builder.proxy(proxy),
+        Ok(None) => builder,
+        Err(e) => {
+            log_warn!("Proxy", &format!("Ignoring invalid proxy: {}", e));
+            builder
+        },
+    }
+}
+
+/// Client for requests to OpenAI, through the configured proxy
+pub fn client() -> Client {
+    apply(Client::builder()).build().unwrap_or_default()
+}
@@ -0,0 +1,88 @@
+// Reasoning parameters and summaries of o-series models
+//
+// Clients of reasoning models send `reasoning_effort` (or `reasoning.effort`,
+// as in the Responses API) and `max_completion_tokens` instead of
+// `max_tokens`; both are forwarded to the ChatGPT backend. The backend reports
+// the model's thinking as `thoughts` messages ahead of the answer. Their
+// summaries are returned in a `reasoning_content` field, on the message or on
+// stream deltas, which is where clients of other reasoning APIs look for them.
+
+use serde_json::{Map, Value};
+
+/// Requested reasoning effort, e.g. `low` or `high`
+pub fn reasoning_effort(fields: &Map<String, Value>) -> Option<String> {
+    fields
+        .get("reasoning_effort")
+        .or_else(|| fields.get("reasoning").and_then(|r| r.get("effort")))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+}
+
+/// Output token limit including reasoning tokens
+pub fn max_completion_tokens(fields: &Map<String, Value>) -> Option<u32> {
+    fields
+        .get("max_completion_tokens")
+        .and_then(Value::as_u64)
+        .and_then(|tokens| u32::try_from(tokens).ok())
+}
+
+/// The reasoning summary carried by a ChatGPT `thoughts` message, each
+/// thought's heading followed by its text
+pub fn reasoning_text(event: &Value) -> Option<String> {
+    let content = event.get("message")?.get("content")?;
+    if content.get("content_type").and_then(Value::as_str) != Some("thoughts") {
+        return None;
+    }
+
+    let thoughts: Vec<String> = content
+        .get("thoughts")?
+        .as_array()?
+        .iter()
+        .filter_map(|thought| {
+            let summary = thought.get("summary").and_then(Value::as_str);
+            let text = thought.get("content").and_then(Value::as_str);
+            match (summary, text) {
+                (Some(summary), Some(text)) => Some(format!("{}\n{}", summary, text)),
+                (Some(only), None) | (None, Some(only)) => Some(only.to_string()),
+                (None, None) => None,
+            }
+        })
+        .collect();
+    (!thoughts.is_empty()).then(|| thoughts.join("\n\n"))
+}
+
+/// Turns the thoughts the backend streams into the pieces not sent yet. Every
+/// update repeats the thoughts so far; a new thoughts message starts over.
+#[derive(Debug, Default)]
+pub struct ReasoningStream {
+    snapshot: String,
+}
+
+impl ReasoningStream {
+    pub fn push(&mut self, snapshot: String) -> Option<String> {
+        let piece = match snapshot.strip_prefix(self.snapshot.as_str()) {
+            Some(rest) => rest.to_string(),
+            None => format!("\n\n{}", snapshot),
+        };
+        self.snapshot = snapshot;
+        (!piece.is_empty()).then_some(piece)
+    }
+}
+
+/// SSE chunk carrying a piece of reasoning
+pub fn reasoning_chunk(id: &str, model: &str, reasoning: &str) -> String {
+    format!(
+        "data: {}\n\n",
+        serde_json::json!({
+            "id": id,
+            "object": "chat.completion.chunk",
+            "created": chrono::Utc::now().timestamp(),
+            "model": model,
+            "choices": [{
+                "index": 0,
+                "delta": { "reasoning_content": reasoning },
+                "finish_reason": null
+            }]
+        })
+    )
+}
@@ -0,0 +1,115 @@
+// Masking of personal data in outgoing prompts
+//
+// When `redaction.enabled` is set, every chat completion is scanned before it
+// is converted for the ChatGPT backend. Email addresses, phone numbers, API
+// keys and matches of the configured custom patterns are replaced by a mask
+// such as `[REDACTED_EMAIL]`, so they never leave this machine. Each built-in
+// rule can be switched off on its own. Masked values are counted in the log,
+// never logged themselves.
+
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::managers::config_manager::RedactionConfig;
+use crate::managers::server_manager::Message;
+use regex::Regex;
+
+/// OpenAI, Anthropic, GitHub, AWS, Slack and Google key formats
+const API_KEY_PATTERN: &str = r"\b(?:sk-(?:proj-|ant-)?[A-Za-z0-9_-]{20,}|gh[pousr]_[A-Za-z0-9]{36,}|AKIA[0-9A-Z]{16}|xox[abprs]-[A-Za-z0-9-]{10,}|AIza[0-9A-Za-z_-]{35})";
+const EMAIL_PATTERN: &str = r"(?i)\b[a-z0-9._%+-]+@[a-z0-9.-]+\.[a-z]{2,}\b";
+/// Numbers need a separator or parentheses after the area code, so that order
+/// numbers and other long digit runs are left alone
+const PHONE_PATTERN: &str =
+    r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{2,4}\)[ .-]?|\b\d{2,4}[ .-])\d{3,4}[ .-]?\d{3,4}\b";
+
+#[derive(Debug)]
+struct Rule {
+    mask: String,
+    pattern: Regex,
+}
+
+impl Rule {
+    fn new(name: &str, pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            mask: format!("[REDACTED_{}]", name.to_uppercase().replace('-', "_")),
+            pattern: Regex::new(pattern)?,
+        })
+    }
+}
+
+/// The enabled redaction rules, applied in order: API keys first, since they
+/// may contain digit runs that look like phone numbers
+#[derive(Debug)]
+pub struct Redactor {
+    rules: Vec<Rule>,
+}
+
+impl Redactor {
+    /// `None` when redaction is disabled or no rule is enabled
+    pub fn from_config(config: &RedactionConfig) -> MindLinkResult<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        let builtin = [
+            (config.api_keys, "api_key", API_KEY_PATTERN),
+            (config.emails, "email", EMAIL_PATTERN),
+            (config.phone_numbers, "phone_number", PHONE_PATTERN),
+        ];
+        let custom = config
+            .custom_rules
+            .iter()
+            .map(|rule| (rule.enabled, rule.name.as_str(), rule.pattern.as_str()));
+
+        let mut rules = Vec::new();
+        for (enabled, name, pattern) in builtin.into_iter().chain(custom) {
+            if !enabled {
+                continue;
+            }
+            let rule = Rule::new(name, pattern).map_err(|e| MindLinkError::Configuration {
+                message: format!("Invalid redaction pattern for rule {}", name),
+                config_key: Some("redaction.custom_rules".to_string()),
+                source: Some(e.into()),
+            })?;
+            rules.push(rule);
+        }
+
+        Ok((!rules.is_empty()).then_some(Self { rules }))
+    }
+
+    /// Only the API key rule, whatever the configuration says
+    pub fn api_keys() -> Self {
+        Self {
+            rules: vec![Rule::new("api_key", API_KEY_PATTERN).expect("built-in pattern is valid")],
+        }
+    }
+
+    /// `text` with every match masked, and the number of masked values
+    pub fn redact(&self, text: &str) -> (String, usize) {
+        let mut text = text.to_string();
+        let mut masked = 0;
+        for rule in &self.rules {
+            let count = rule.pattern.find_iter(&text).count();
+            if count > 0 {
+                text = rule
+                    .pattern
+                    .replace_all(&text, rule.mask.as_str())
+                    .into_owned();
+                masked += count;
+            }
+        }
+        (text, masked)
+    }
+
+    /// Mask the content of `messages` in place. Returns the number of masked
+    /// values.
+    pub fn redact_messages(&self, messages: &mut [Message]) -> usize {
+        let mut masked = 0;
+        for message in messages.iter_mut() {
+            let (content, count) = self.redact(&message.content);
+            if count > 0 {
+                message.content = content;
+                masked += count;
+            }
+        }
+        masked
+    }
+}
@@ -0,0 +1,268 @@
+// Per-request timing of API requests
+//
+// Every `/v1/...` and `/api/...` request is timed from the moment it reaches
+// the router. Handlers mark where each phase ends as they go: the request is
+// validated, an account is authenticated, the request is sent upstream and the
+// upstream answers. The response body ending closes the last phase. The marks
+// travel with the task as a task-local, like the correlation ID, so background
+// tasks that stream the upstream response take them along through
+// [`with_timer`]. Outside a timed request, marking does nothing.
+//
+// Streamed replies also record when their first token went out and how many
+// tokens they had, for the time to first token and the generation speed.
+//
+// The breakdown is logged as the details of one entry per request and kept
+// in memory for the request inspector, which shows the most recent ones, and
+// for the streaming statistics of the dashboard.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Summaries kept for the request inspector
+pub const MAX_SUMMARIES: usize = 200;
+
+tokio::task_local! {
+    static TIMER: Arc<RequestTimer>;
+}
+
+/// Points in a request where a phase ends
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// The request is parsed and allowed
+    Validated,
+    /// An account with a valid access token is at hand
+    Authenticated,
+    /// The request goes out to the upstream
+    UpstreamSent,
+    /// The upstream answered with its status and headers
+    FirstByte,
+    /// The first token of a streamed reply went out to the client
+    FirstToken,
+}
+
+/// When each phase of one request ended. Only the first mark of a phase
+/// counts, so retries and continuations don't stretch it.
+#[derive(Debug)]
+pub struct RequestTimer {
+    started: Instant,
+    validated: OnceLock<Instant>,
+    authenticated: OnceLock<Instant>,
+    upstream_sent: OnceLock<Instant>,
+    first_byte: OnceLock<Instant>,
+    first_token: OnceLock<Instant>,
+    completion_tokens: OnceLock<u64>,
+}
+
+impl Default for RequestTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RequestTimer {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            validated: OnceLock::new(),
+            authenticated: OnceLock::new(),
+            upstream_sent: OnceLock::new(),
+            first_byte: OnceLock::new(),
+            first_token: OnceLock::new(),
+            completion_tokens: OnceLock::new(),
+        }
+    }
+
+    pub fn mark(&self, phase: Phase) {
+        let mark = match phase {
+            Phase::Validated => &self.validated,
+            Phase::Authenticated => &self.authenticated,
+            Phase::UpstreamSent => &self.upstream_sent,
+            Phase::FirstByte => &self.first_byte,
+            Phase::FirstToken => &self.first_token,
+        };
+        let _ = mark.set(Instant::now());
+    }
+
+    /// Tokens of the streamed reply, counted once it is complete
+    pub fn set_completion_tokens(&self, tokens: u64) {
+        let _ = self.completion_tokens.set(tokens);
+    }
+
+    /// Time to first token and generation speed of a streamed reply, once its
+    /// first token went out. The speed is known once the tokens are counted.
+    pub fn throughput(&self) -> Option<StreamThroughput> {
+        let first_token = self.first_token.get()?;
+        let generating = first_token.elapsed();
+        let tokens = self.completion_tokens.get().copied();
+        Some(StreamThroughput {
+            ttft_ms: millis(first_token.saturating_duration_since(self.started)),
+            generation_ms: millis(generating),
+            completion_tokens: tokens,
+            tokens_per_second: tokens
+                .filter(|_| !generating.is_zero())
+                .map(|tokens| (tokens as f64 / generating.as_secs_f64() * 10.0).round() / 10.0),
+        })
+    }
+
+    /// Phases of a request whose response has been sent in full. `streamed`
+    /// responses spend the time after the first byte streaming.
+    pub fn phases(&self, streamed: bool) -> PhaseTimings {
+        let finished = Instant::now();
+        let between = |from: Option<&Instant>, to: Option<&Instant>| match (from, to) {
+            (Some(from), Some(to)) => Some(millis(to.saturating_duration_since(*from))),
+            _ => None,
+        };
+        let started = Some(&self.started);
+
+        PhaseTimings {
+            validation_ms: between(started, self.validated.get()),
+            auth_ms: between(self.validated.get(), self.authenticated.get()),
+            upstream_ttfb_ms: between(self.upstream_sent.get(), self.first_byte.get()),
+            stream_ms: streamed
+                .then(|| between(self.first_byte.get(), Some(&finished)))
+                .flatten(),
+            total_ms: millis(finished.saturating_duration_since(self.started)),
+        }
+    }
+}
+
+/// Milliseconds, to a tenth
+fn millis(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 10_000.0).round() / 10.0
+}
+
+/// Mark the end of `phase` for the request the current task serves
+pub fn mark(phase: Phase) {
+    let _ = TIMER.try_with(|timer| timer.mark(phase));
+}
+
+/// Record the tokens of the streamed reply of the request the current task
+/// serves
+pub fn set_completion_tokens(tokens: u64) {
+    let _ = TIMER.try_with(|timer| timer.set_completion_tokens(tokens));
+}
+
+/// Timer of the request the current task serves, to pass to spawned tasks
+pub fn current_timer() -> Option<Arc<RequestTimer>> {
+    TIMER.try_with(Arc::clone).ok()
+}
+
+/// Run a future with `timer` receiving its marks
+pub async fn with_timer<F: Future>(timer: Option<Arc<RequestTimer>>, future: F) -> F::Output {
+    match timer {
+        Some(timer) => TIMER.scope(timer, future).await,
+        None => future.await,
+    }
+}
+
+/// Milliseconds spent in each phase; `None` for phases the request never
+/// reached, e.g. the upstream phases of a request refused by validation
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PhaseTimings {
+    pub validation_ms: Option<f64>,
+    pub auth_ms: Option<f64>,
+    pub upstream_ttfb_ms: Option<f64>,
+    pub stream_ms: Option<f64>,
+    pub total_ms: f64,
+}
+
+/// How fast a streamed reply started and went on
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StreamThroughput {
+    /// From the request arriving to the first token going out
+    pub ttft_ms: f64,
+    /// From the first token to the end of the reply
+    pub generation_ms: f64,
+    /// Estimated tokens of the reply; `None` when it ended early
+    pub completion_tokens: Option<u64>,
+    pub tokens_per_second: Option<f64>,
+}
+
+/// One API request as shown in the request inspector
+#[derive(Debug, Clone, Serialize)]
+pub struct RequestSummary {
+    pub request_id: Option<String>,
+    pub at: DateTime<Utc>,
+    pub method: String,
+    pub route: String,
+    pub status: u16,
+    /// Model of a completion, as the client asked for it
+    pub model: Option<String>,
+    pub streamed: bool,
+    #[serde(flatten)]
+    pub phases: PhaseTimings,
+    /// Of streamed replies that got as far as their first token
+    pub throughput: Option<StreamThroughput>,
+}
+
+/// Time to first token and generation speed of streamed replies
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StreamStats {
+    /// Streamed replies measured
+    pub streams: usize,
+    pub ttft_p50_ms: Option<f64>,
+    pub ttft_p90_ms: Option<f64>,
+    pub tokens_per_second_p50: Option<f64>,
+    /// The slowest tenth of the replies were generated at most this fast
+    pub tokens_per_second_p10: Option<f64>,
+}
+
+/// Nearest-rank percentile of sorted values
+fn percentile(sorted: &[f64], percent: usize) -> Option<f64> {
+    let rank = (sorted.len() * percent).div_ceil(100);
+    sorted.get(rank.saturating_sub(1)).copied()
+}
+
+/// The most recent request summaries. Lives as long as the
+/// [`crate::managers::server_manager::ServerManager`], so restarts keep them.
+#[derive(Debug, Default)]
+pub struct RequestLog {
+    summaries: Mutex<VecDeque<RequestSummary>>,
+}
+
+impl RequestLog {
+    pub fn record(&self, summary: RequestSummary) {
+        let mut summaries = self.summaries.lock().unwrap_or_else(|e| e.into_inner());
+        if summaries.len() == MAX_SUMMARIES {
+            summaries.pop_back();
+        }
+        summaries.push_front(summary);
+    }
+
+    /// Up to `limit` summaries, newest first
+    pub fn recent(&self, limit: usize) -> Vec<RequestSummary> {
+        let summaries = self.summaries.lock().unwrap_or_else(|e| e.into_inner());
+        summaries.iter().take(limit).cloned().collect()
+    }
+
+    /// Statistics of the streamed replies among the kept summaries, of those
+    /// since `since` when given
+    pub fn stream_stats(&self, since: Option<DateTime<Utc>>) -> StreamStats {
+        let summaries = self.summaries.lock().unwrap_or_else(|e| e.into_inner());
+        let measured: Vec<&StreamThroughput> = summaries
+            .iter()
+            .filter(|summary| since.is_none_or(|since| summary.at >= since))
+            .filter_map(|summary| summary.throughput.as_ref())
+            .collect();
+
+        let mut ttft: Vec<f64> = measured.iter().map(|t| t.ttft_ms).collect();
+        let mut speed: Vec<f64> = measured
+            .iter()
+            .filter_map(|t| t.tokens_per_second)
+            .collect();
+        ttft.sort_by(f64::total_cmp);
+        speed.sort_by(f64::total_cmp);
+
+        StreamStats {
+            streams: measured.len(),
+            ttft_p50_ms: percentile(&ttft, 50),
+            ttft_p90_ms: percentile(&ttft, 90),
+            tokens_per_second_p50: percentile(&speed, 50),
+            tokens_per_second_p10: percentile(&speed, 10),
+        }
+    }
+}
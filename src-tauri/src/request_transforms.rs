@@ -0,0 +1,154 @@
+// Transformation of incoming completion requests
+//
+// The rules in `request_transforms.rules` fix up the requests of clients that
+// cannot be configured to send what the backend accepts: forcing parameters
+// such as `temperature`, removing unsupported ones, asking for another model
+// or changing headers. A rule can be limited to clients by their `User-Agent`
+// and to the models requested. Rules run in order, each on the request as the
+// rules before it left it.
+//
+// The rules can be edited while the server runs; the completion routes pick
+// up the new rules with the next request.
+
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::managers::config_manager::{RequestTransformAction, RequestTransformConfig};
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue};
+use regex::Regex;
+use serde_json::Value;
+
+#[derive(Debug)]
+enum Step {
+    SetField(String, Value),
+    RemoveFields(Vec<String>),
+    SetHeader(HeaderName, HeaderValue),
+    RemoveHeader(HeaderName),
+}
+
+impl Step {
+    /// Apply the step, returning whether the request changed
+    fn apply(&self, headers: &mut HeaderMap, body: Option<&mut Value>) -> bool {
+        let object = body.and_then(Value::as_object_mut);
+        match (self, object) {
+            (Step::SetField(field, value), Some(object)) => {
+                object.insert(field.clone(), value.clone()).as_ref() != Some(value)
+            },
+            (Step::RemoveFields(fields), Some(object)) => {
+                let mut removed = false;
+                for field in fields {
+                    removed |= object.remove(field).is_some();
+                }
+                removed
+            },
+            (Step::SetHeader(name, value), _) => {
+                headers.insert(name.clone(), value.clone()).as_ref() != Some(value)
+            },
+            (Step::RemoveHeader(name), _) => headers.remove(name).is_some(),
+            // Body steps on requests without a JSON object
+            (Step::SetField(..) | Step::RemoveFields(_), None) => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Rule {
+    user_agent: Option<Regex>,
+    models: Vec<String>,
+    step: Step,
+}
+
+impl Rule {
+    fn matches(&self, headers: &HeaderMap, body: Option<&Value>) -> bool {
+        let user_agent = headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+        let model = body
+            .and_then(|body| body.get("model"))
+            .and_then(Value::as_str);
+        self.user_agent
+            .as_ref()
+            .is_none_or(|pattern| pattern.is_match(user_agent))
+            && (self.models.is_empty()
+                || model.is_some_and(|model| self.models.iter().any(|m| m == model)))
+    }
+}
+
+/// The request transformation rules in effect, compiled
+#[derive(Debug, Default)]
+pub struct RequestTransformer {
+    rules: Vec<Rule>,
+}
+
+fn invalid(message: String, source: Option<anyhow::Error>) -> MindLinkError {
+    MindLinkError::Configuration {
+        message,
+        config_key: Some("request_transforms.rules".to_string()),
+        source,
+    }
+}
+
+impl RequestTransformer {
+    pub fn from_config(config: &RequestTransformConfig) -> MindLinkResult<Self> {
+        let mut rules = Vec::new();
+        for rule in &config.rules {
+            let user_agent = match &rule.user_agent {
+                Some(pattern) => Some(Regex::new(pattern).map_err(|e| {
+                    invalid(
+                        format!("Invalid User-Agent pattern '{}'", pattern),
+                        Some(e.into()),
+                    )
+                })?),
+                None => None,
+            };
+            let header_name = |name: &str| {
+                HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|e| invalid(format!("Invalid header name '{}'", name), Some(e.into())))
+            };
+            let step = match &rule.action {
+                RequestTransformAction::SetField { field, value } => {
+                    Step::SetField(field.clone(), value.clone())
+                },
+                RequestTransformAction::RemoveFields { fields } => {
+                    Step::RemoveFields(fields.clone())
+                },
+                RequestTransformAction::RenameModel { to } => {
+                    Step::SetField("model".to_string(), Value::String(to.clone()))
+                },
+                RequestTransformAction::SetHeader { name, value } => Step::SetHeader(
+                    header_name(name)?,
+                    HeaderValue::from_str(value).map_err(|e| {
+                        invalid(
+                            format!("Invalid value for header '{}'", name),
+                            Some(e.into()),
+                        )
+                    })?,
+                ),
+                RequestTransformAction::RemoveHeader { name } => {
+                    Step::RemoveHeader(header_name(name)?)
+                },
+            };
+            rules.push(Rule {
+                user_agent,
+                models: rule.models.clone(),
+                step,
+            });
+        }
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Apply the rules to the headers and, when it is JSON, the body of a
+    /// request. Returns whether anything changed.
+    pub fn apply(&self, headers: &mut HeaderMap, mut body: Option<&mut Value>) -> bool {
+        let mut changed = false;
+        for rule in &self.rules {
+            if rule.matches(headers, body.as_deref()) {
+                changed |= rule.step.apply(headers, body.as_deref_mut());
+            }
+        }
+        changed
+    }
+}
@@ -0,0 +1,149 @@
+// Encryption of credentials at rest
+//
+// Secrets that end up in files, the OAuth tokens when the OS keyring cannot
+// hold them, the API keys of fallback providers in the configuration and the
+// keys of authorized apps in the settings, are encrypted with AES-256-GCM
+// under a master key. The key is derived with Argon2id from the passphrase in
+// `MINDLINK_PASSPHRASE` when it is set, and from the identifier of the
+// machine otherwise, so the files cannot be read once copied to another
+// machine. A passphrase makes them portable, and must then be set for every
+// start of MindLink.
+//
+// Encrypted values carry a prefix, so plaintext written by earlier versions is
+// still read; it is encrypted the next time it is saved. Where no key can be
+// derived, secrets are stored in plaintext as before, with a warning.
+
+use crate::log_warn;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use tokio::sync::OnceCell;
+
+/// Environment variable holding the optional passphrase
+pub const PASSPHRASE_ENV: &str = "MINDLINK_PASSPHRASE";
+
+/// Marks an encrypted value, followed by the base64 of nonce and ciphertext
+const PREFIX: &str = "mindlink-aes256gcm:";
+
+/// Argon2 salt. Fixed, as the key has to be derived again from the same input
+/// on every start with nothing else stored.
+const SALT: &[u8] = b"MindLink credentials at rest";
+
+const NONCE_LEN: usize = 12;
+
+/// AES-256-GCM key for secrets at rest
+pub struct MasterKey(Aes256Gcm);
+
+impl MasterKey {
+    /// Derive the key from a passphrase or machine identifier
+    pub fn derive(secret: &str) -> Result<Self> {
+        let mut key = [0u8; 32];
+        argon2::Argon2::default()
+            .hash_password_into(secret.as_bytes(), SALT, &mut key)
+            .map_err(|e| anyhow!("Failed to derive the master key: {}", e))?;
+        Ok(Self(Aes256Gcm::new(&key.into())))
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> Result<String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .0
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|e| anyhow!("Failed to encrypt secret: {}", e))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        Ok(format!("{}{}", PREFIX, STANDARD.encode(sealed)))
+    }
+
+    /// Decrypt a value from [`MasterKey::encrypt`]. Plaintext is returned
+    /// unchanged.
+    pub fn decrypt(&self, value: &str) -> Result<String> {
+        let Some(encoded) = value.strip_prefix(PREFIX) else {
+            return Ok(value.to_string());
+        };
+
+        let sealed = STANDARD
+            .decode(encoded.trim_end())
+            .map_err(|e| anyhow!("Encrypted secret is malformed: {}", e))?;
+        if sealed.len() < NONCE_LEN {
+            return Err(anyhow!("Encrypted secret is truncated"));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = self
+            .0
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                anyhow!(
+                    "Secret was encrypted with another key; check {} or whether the file comes from another machine",
+                    PASSPHRASE_ENV
+                )
+            })?;
+        String::from_utf8(plaintext).map_err(|e| anyhow!("Decrypted secret is not text: {}", e))
+    }
+}
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+/// Master key of this machine, derived on first use. `None` when neither a
+/// passphrase nor a machine identifier is available.
+pub async fn master_key() -> Option<&'static MasterKey> {
+    static KEY: OnceCell<Option<MasterKey>> = OnceCell::const_new();
+
+    KEY.get_or_init(|| async {
+        let derived = tokio::task::spawn_blocking(|| {
+            let secret = match std::env::var(PASSPHRASE_ENV) {
+                Ok(passphrase) if !passphrase.is_empty() => passphrase,
+                _ => machine_uid::get().map_err(|e| anyhow!("No machine identifier ({})", e))?,
+            };
+            MasterKey::derive(&secret)
+        })
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|derived| derived);
+
+        match derived {
+            Ok(key) => Some(key),
+            Err(e) => {
+                log_warn!(
+                    "SecretBox",
+                    &format!(
+                        "{}; secrets are stored unencrypted. Set {} to encrypt them.",
+                        e, PASSPHRASE_ENV
+                    )
+                );
+                None
+            },
+        }
+    })
+    .await
+    .as_ref()
+}
+
+/// `value` encrypted with the master key, or unchanged without one
+pub async fn seal(value: &str) -> String {
+    let Some(key) = master_key().await.filter(|_| !is_encrypted(value)) else {
+        return value.to_string();
+    };
+    key.encrypt(value).unwrap_or_else(|e| {
+        log_warn!("SecretBox", &format!("{}; storing it unencrypted", e));
+        value.to_string()
+    })
+}
+
+/// `value` decrypted with the master key. Plaintext is returned unchanged.
+pub async fn open(value: &str) -> Result<String> {
+    if !is_encrypted(value) {
+        return Ok(value.to_string());
+    }
+    match master_key().await {
+        Some(key) => key.decrypt(value),
+        None => Err(anyhow!(
+            "Secret is encrypted but no master key is available; set {}",
+            PASSPHRASE_ENV
+        )),
+    }
+}
@@ -0,0 +1,346 @@
+// Security review of the externally reachable surface
+//
+// Collects what is listening where (local listeners and the public tunnel)
+// together with the policies in front of it, and turns that into findings
+// with remediation steps and an overall score for the dashboard.
+
+use crate::managers::config_manager::{AccessControlConfig, TunnelConfig, TunnelService};
+use crate::managers::tunnel_manager::ServiceTunnel;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+}
+
+impl Severity {
+    /// Points deducted from the score of 100
+    fn penalty(self) -> u8 {
+        match self {
+            Severity::Info => 0,
+            Severity::Low => 5,
+            Severity::Medium => 15,
+            Severity::High => 30,
+        }
+    }
+}
+
+/// Who can reach an endpoint, narrowest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Reach {
+    /// Only processes on this machine
+    Loopback,
+    /// Other devices on the local network
+    Network,
+    /// Anyone on the internet
+    Public,
+}
+
+/// A local listener, as configured or as reported by its manager
+#[derive(Debug, Clone)]
+pub struct Listener {
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+}
+
+impl Listener {
+    /// Listener from a base URL such as `http://127.0.0.1:3003`
+    pub fn from_url(name: &str, url: &str) -> Option<Self> {
+        let url = url::Url::parse(url).ok()?;
+        Some(Self {
+            name: name.to_string(),
+            host: url.host_str()?.trim_matches(['[', ']']).to_string(),
+            port: url.port_or_known_default()?,
+            tls: url.scheme() == "https",
+        })
+    }
+
+    fn reach(&self) -> Reach {
+        match self.host.parse::<IpAddr>() {
+            Ok(ip) if ip.is_loopback() => Reach::Loopback,
+            Ok(_) => Reach::Network,
+            Err(_) if self.host == "localhost" => Reach::Loopback,
+            Err(_) => Reach::Network,
+        }
+    }
+}
+
+/// Everything the report is based on
+#[derive(Debug, Clone)]
+pub struct ExposureInputs {
+    pub listeners: Vec<Listener>,
+    /// The API's tunnel first, then those of the other services
+    pub tunnels: Vec<ServiceTunnel>,
+    pub access_control: AccessControlConfig,
+    /// Whether requests through the API's tunnel must carry a token
+    pub tunnel_token_required: bool,
+    /// Whether Cloudflare Access checks requests through the API's tunnel
+    pub cloudflare_access: bool,
+    /// Whether the API answers cross-origin requests from any website
+    pub cors_any_origin: bool,
+}
+
+impl ExposureInputs {
+    /// Inputs for the tunnel configuration `tunnel`, with `tunnels` as the
+    /// tunnel manager reports them. Local clients are never authenticated;
+    /// app keys only restrict models.
+    pub fn new(
+        listeners: Vec<Listener>,
+        tunnels: Vec<ServiceTunnel>,
+        tunnel: &TunnelConfig,
+        access_control: AccessControlConfig,
+        api_running: bool,
+    ) -> Self {
+        let access = &tunnel.cloudflare_access;
+        Self {
+            listeners,
+            tunnels,
+            access_control,
+            tunnel_token_required: tunnel.require_token,
+            // Only the named tunnel can be put behind an Access application
+            cloudflare_access: tunnel.tunnel_type == "named"
+                && access.enabled
+                && access.team_domain.is_some()
+                && access.audience.is_some(),
+            // The API server's CORS layer allows any origin while it runs
+            cors_any_origin: api_running,
+        }
+    }
+
+    /// Public URL of the API's tunnel, if it is up
+    fn api_tunnel_url(&self) -> Option<&str> {
+        self.tunnels
+            .iter()
+            .find(|tunnel| tunnel.service == TunnelService::Api)
+            .and_then(|tunnel| tunnel.url.as_deref())
+    }
+
+    /// Whether requests through the API's tunnel have to authenticate
+    fn tunnel_authenticated(&self) -> bool {
+        self.tunnel_token_required || self.cloudflare_access
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExposedEndpoint {
+    pub name: String,
+    pub address: String,
+    pub reach: Reach,
+    pub tls: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Finding {
+    pub id: String,
+    pub severity: Severity,
+    pub title: String,
+    pub detail: String,
+    pub remediation: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityReport {
+    pub generated_at: DateTime<Utc>,
+    /// 100 minus the penalties of all findings
+    pub score: u8,
+    pub surface: Vec<ExposedEndpoint>,
+    /// Most severe first
+    pub findings: Vec<Finding>,
+}
+
+impl SecurityReport {
+    /// Widest reach of any endpoint in the report
+    pub fn reach(&self) -> Reach {
+        self.surface
+            .iter()
+            .map(|endpoint| endpoint.reach)
+            .max()
+            .unwrap_or(Reach::Loopback)
+    }
+}
+
+fn finding(
+    id: &str,
+    severity: Severity,
+    title: &str,
+    detail: String,
+    remediation: &str,
+) -> Finding {
+    Finding {
+        id: id.to_string(),
+        severity,
+        title: title.to_string(),
+        detail,
+        remediation: remediation.to_string(),
+    }
+}
+
+pub fn build_report(inputs: &ExposureInputs) -> SecurityReport {
+    let mut surface: Vec<ExposedEndpoint> = inputs
+        .listeners
+        .iter()
+        .map(|listener| ExposedEndpoint {
+            name: listener.name.clone(),
+            address: format!("{}:{}", listener.host, listener.port),
+            reach: listener.reach(),
+            tls: listener.tls,
+        })
+        .collect();
+    for tunnel in &inputs.tunnels {
+        let Some(url) = &tunnel.url else {
+            continue;
+        };
+        let name = match tunnel.service {
+            TunnelService::Api => "Tunnel".to_string(),
+            service => format!("Tunnel ({})", service),
+        };
+        surface.push(ExposedEndpoint {
+            name,
+            address: url.clone(),
+            reach: Reach::Public,
+            tls: url.starts_with("https://"),
+        });
+    }
+
+    let mut report = SecurityReport {
+        generated_at: Utc::now(),
+        score: 100,
+        surface,
+        findings: Vec::new(),
+    };
+    let reach = report.reach();
+    let mut findings = Vec::new();
+
+    for endpoint in report.surface.iter().filter(|e| e.reach == Reach::Network) {
+        findings.push(finding(
+            "network_listener",
+            Severity::Medium,
+            &format!("{} is reachable from the network", endpoint.name),
+            format!(
+                "{} listens on {}, so other devices on the network can connect.",
+                endpoint.name, endpoint.address
+            ),
+            "Bind to 127.0.0.1 unless other devices need access.",
+        ));
+        if !endpoint.tls {
+            findings.push(finding(
+                "plaintext_network_traffic",
+                Severity::Low,
+                &format!("{} traffic is not encrypted", endpoint.name),
+                format!(
+                    "Requests to {} cross the network in plain HTTP.",
+                    endpoint.address
+                ),
+                "Enable TLS for listeners reachable from the network.",
+            ));
+        }
+    }
+
+    // Widest reach without authentication: local listeners never ask for
+    // one, the tunnel does when a token or Cloudflare Access is required
+    let api_tunnel = inputs.api_tunnel_url();
+    let unauthenticated_reach = if api_tunnel.is_some() && !inputs.tunnel_authenticated() {
+        Reach::Public
+    } else {
+        inputs
+            .listeners
+            .iter()
+            .map(Listener::reach)
+            .max()
+            .unwrap_or(Reach::Loopback)
+    };
+    let (severity, remediation) = match unauthenticated_reach {
+        Reach::Public => (
+            Severity::High,
+            "Turn on tunnel.require_token so requests through the tunnel need a token.",
+        ),
+        Reach::Network => (
+            Severity::Medium,
+            "Keep the API on loopback, or limit who can reach it with an access policy.",
+        ),
+        Reach::Loopback => (
+            Severity::Low,
+            "Keep the API on loopback, or limit who can reach it with an access policy.",
+        ),
+    };
+    findings.push(finding(
+        "unauthenticated_api",
+        severity,
+        "API requests are not authenticated",
+        "Anyone who can reach the API can send requests on your ChatGPT account. \
+         Authorized app keys only select which models an app may use."
+            .to_string(),
+        remediation,
+    ));
+
+    if inputs.cors_any_origin {
+        findings.push(finding(
+            "cors_any_origin",
+            Severity::Medium,
+            "Any website can call the API",
+            "CORS allows every origin, so pages open in a browser on this machine can \
+             send requests to the local API."
+                .to_string(),
+            "Stop the API server while browsing untrusted sites.",
+        ));
+    }
+
+    if reach != Reach::Loopback && inputs.access_control.allow.is_empty() {
+        findings.push(finding(
+            "no_access_policy",
+            Severity::Medium,
+            "No client allow list",
+            "Every client address that can reach the API is accepted.".to_string(),
+            "Add the addresses of your devices to access_control.allow.",
+        ));
+    }
+
+    if let Some(url) = api_tunnel {
+        findings.push(finding(
+            "public_tunnel",
+            Severity::Info,
+            "The API is published through a tunnel",
+            format!(
+                "{} forwards to the local API. The URL is hard to guess but is not a secret.",
+                url
+            ),
+            "Stop the tunnel when it is not needed and avoid sharing its URL.",
+        ));
+    }
+
+    for tunnel in &inputs.tunnels {
+        let (TunnelService::Bifrost | TunnelService::Dashboard, Some(url)) =
+            (tunnel.service, &tunnel.url)
+        else {
+            continue;
+        };
+        findings.push(finding(
+            "unauthenticated_service_tunnel",
+            Severity::High,
+            &format!("The {} tunnel is not authenticated", tunnel.service),
+            format!(
+                "Nothing checks the requests through {}, so whoever knows the URL reaches {}.",
+                url, tunnel.service
+            ),
+            "Remove the service from tunnel.services unless it has to be reachable from outside.",
+        ));
+    }
+
+    findings.sort_by(|a, b| b.severity.cmp(&a.severity));
+    let penalty: u32 = findings
+        .iter()
+        .map(|f| u32::from(f.severity.penalty()))
+        .sum();
+    report.score = 100u32.saturating_sub(penalty) as u8;
+    report.findings = findings;
+    report
+}
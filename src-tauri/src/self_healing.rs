@@ -0,0 +1,165 @@
+// Self-healing policy used by the health monitor
+//
+// The monitor gathers a `HealthReport`; this policy turns the transition from
+// the previous report into decisions: whether to notify the UI, what to do
+// with `last_error`, and which components to restart. Restarts are budgeted
+// per component so a component that keeps dying is not restarted forever.
+// The policy has no I/O and takes the current time as an argument, which
+// keeps it deterministic for scenario tests. `health_check_actions` is the
+// whole of what one check decides, so the monitor only carries it out.
+
+use crate::health::{HealthLevel, HealthReport};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, VecDeque};
+
+/// Prefix of `last_error` messages written by the health monitor
+pub const HEALTH_ERROR_PREFIX: &str = "Health check";
+
+/// How the health monitor should update `last_error`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LastErrorUpdate {
+    Set(String),
+    /// Clear the error only if the health monitor wrote it
+    ClearHealthError,
+}
+
+/// Outcome of evaluating one health check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealingDecision {
+    /// Emit `health-changed` because some level changed
+    pub notify: bool,
+    pub last_error: LastErrorUpdate,
+    /// Components to restart now
+    pub restart: Vec<String>,
+    /// Unhealthy components not restarted because their budget is spent
+    pub suppressed: Vec<String>,
+}
+
+/// Decides restarts for a fixed set of restartable components
+#[derive(Debug, Clone)]
+pub struct SelfHealingPolicy {
+    restartable: Vec<String>,
+    max_restarts: usize,
+    window: Duration,
+    history: HashMap<String, VecDeque<DateTime<Utc>>>,
+}
+
+impl Default for SelfHealingPolicy {
+    /// Bifrost and the dashboard, at most 3 restarts each per 10 minutes
+    fn default() -> Self {
+        Self::new(&["bifrost", "dashboard"], 3, Duration::minutes(10))
+    }
+}
+
+impl SelfHealingPolicy {
+    pub fn new(restartable: &[&str], max_restarts: usize, window: Duration) -> Self {
+        Self {
+            restartable: restartable.iter().map(|name| name.to_string()).collect(),
+            max_restarts,
+            window,
+            history: HashMap::new(),
+        }
+    }
+
+    /// Restarts of a component still counted against its budget at `now`
+    pub fn restarts_in_window(&self, component: &str, now: DateTime<Utc>) -> usize {
+        self.history
+            .get(component)
+            .map_or(0, |h| h.iter().filter(|t| now - **t < self.window).count())
+    }
+
+    pub fn evaluate(
+        &mut self,
+        previous: &HealthReport,
+        report: &HealthReport,
+        now: DateTime<Utc>,
+    ) -> HealingDecision {
+        let notify = report.levels_differ(previous);
+
+        if report.overall == HealthLevel::Ok {
+            return HealingDecision {
+                notify,
+                last_error: LastErrorUpdate::ClearHealthError,
+                restart: Vec::new(),
+                suppressed: Vec::new(),
+            };
+        }
+
+        let last_error = LastErrorUpdate::Set(format!(
+            "{} {} - {}",
+            HEALTH_ERROR_PREFIX,
+            report.overall,
+            report.summary()
+        ));
+
+        let mut restart = Vec::new();
+        let mut suppressed = Vec::new();
+
+        for name in &self.restartable {
+            let unhealthy = report
+                .component(name)
+                .is_some_and(|c| c.level != HealthLevel::Ok);
+            if !unhealthy {
+                continue;
+            }
+
+            let history = self.history.entry(name.clone()).or_default();
+            while history.front().is_some_and(|t| now - *t >= self.window) {
+                history.pop_front();
+            }
+
+            if history.len() < self.max_restarts {
+                history.push_back(now);
+                restart.push(name.clone());
+            } else {
+                suppressed.push(name.clone());
+            }
+        }
+
+        HealingDecision {
+            notify,
+            last_error,
+            restart,
+            suppressed,
+        }
+    }
+}
+
+/// What the health monitor does after one health check
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthCheckActions {
+    /// Report to emit as `health-changed`, when some level changed
+    pub notify: Option<HealthReport>,
+    /// `last_error` after the check
+    pub last_error: Option<String>,
+    /// Components to restart now
+    pub restart: Vec<String>,
+    /// Unhealthy components not restarted because their budget is spent
+    pub suppressed: Vec<String>,
+}
+
+/// Decide what the health monitor does about `report`, coming after
+/// `previous` while `last_error` is set as given
+pub fn health_check_actions(
+    policy: &mut SelfHealingPolicy,
+    previous: &HealthReport,
+    report: &HealthReport,
+    last_error: Option<&str>,
+    now: DateTime<Utc>,
+) -> HealthCheckActions {
+    let decision = policy.evaluate(previous, report, now);
+    let last_error = match decision.last_error {
+        LastErrorUpdate::Set(message) => Some(message),
+        // Only errors raised by the health monitor itself are cleared
+        LastErrorUpdate::ClearHealthError => last_error
+            .filter(|error| !error.starts_with(HEALTH_ERROR_PREFIX))
+            .map(str::to_string),
+    };
+
+    HealthCheckActions {
+        notify: decision.notify.then(|| report.clone()),
+        last_error,
+        restart: decision.restart,
+        suppressed: decision.suppressed,
+    }
+}
@@ -0,0 +1,115 @@
+// Warnings before the ChatGPT session runs out
+//
+// Access tokens are refreshed by a background task shortly before they
+// expire, rather than by the next API request finding them expired. When the
+// session cannot be extended, because a refresh failed or because there is no
+// refresh token and the access token expires soon, the user gets a desktop
+// notification asking to log in again, and the `auth` health component turns
+// degraded, which the tray shows. A refresh token the OAuth server rejects
+// for good ends the session right away: requests fail without reaching
+// ChatGPT until the user logs in again, which the tray asks for.
+
+use crate::health::{ComponentHealth, HealthLevel};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+
+/// Tokens expiring within this are refreshed
+pub const REFRESH_AHEAD_MINUTES: i64 = 10;
+
+/// Tokens that cannot be refreshed are warned about this long before they
+/// expire
+pub const EXPIRY_WARNING_HOURS: i64 = 24;
+
+/// Seconds between two looks at the tokens
+pub const CHECK_INTERVAL_SECS: u64 = 60;
+
+/// Why the user has to log in again soon
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SessionWarning {
+    /// There is no refresh token, so the session ends with the access token
+    Expiring { expires_at: DateTime<Utc> },
+    /// Refreshing the tokens failed
+    RefreshFailed { reason: String },
+    /// The refresh token was rejected, so the session is over
+    ReauthRequired { reason: String },
+}
+
+impl SessionWarning {
+    pub fn message(&self, now: DateTime<Utc>) -> String {
+        match self {
+            SessionWarning::Expiring { expires_at } if *expires_at <= now => {
+                "ChatGPT session expired - please log in again".to_string()
+            },
+            SessionWarning::Expiring { expires_at } => format!(
+                "ChatGPT session expires in {} - log in again to keep serving requests",
+                remaining(*expires_at - now)
+            ),
+            SessionWarning::RefreshFailed { reason } => format!(
+                "Could not refresh the ChatGPT session ({}) - please log in again",
+                reason
+            ),
+            SessionWarning::ReauthRequired { reason } => format!(
+                "ChatGPT session ended ({}) - log in again to keep serving requests",
+                reason
+            ),
+        }
+    }
+
+    /// Whether `other` is a warning of the same kind, which is not notified
+    /// about again
+    pub fn same_kind(&self, other: &SessionWarning) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+fn remaining(duration: Duration) -> String {
+    match (duration.num_hours(), duration.num_minutes()) {
+        (0, minutes) if minutes <= 1 => "a minute".to_string(),
+        (0, minutes) => format!("{} minutes", minutes),
+        (1, _) => "an hour".to_string(),
+        (hours, _) => format!("{} hours", hours),
+    }
+}
+
+/// Whether tokens expiring at `expires_at` are due for a refresh
+pub fn needs_refresh(expires_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    expires_at - now <= Duration::minutes(REFRESH_AHEAD_MINUTES)
+}
+
+/// The warning for tokens expiring at `expires_at`, given the error of the
+/// last refresh of these tokens if it failed
+pub fn session_warning(
+    expires_at: DateTime<Utc>,
+    has_refresh_token: bool,
+    refresh_error: Option<&str>,
+    now: DateTime<Utc>,
+) -> Option<SessionWarning> {
+    if let Some(reason) = refresh_error {
+        return Some(SessionWarning::RefreshFailed {
+            reason: reason.to_string(),
+        });
+    }
+    if !has_refresh_token && expires_at - now <= Duration::hours(EXPIRY_WARNING_HOURS) {
+        return Some(SessionWarning::Expiring { expires_at });
+    }
+    None
+}
+
+/// `health` of the `auth` component, degraded by `warning` when it is
+/// otherwise ok
+pub fn with_warning(
+    health: ComponentHealth,
+    warning: Option<&SessionWarning>,
+    now: DateTime<Utc>,
+) -> ComponentHealth {
+    match warning {
+        Some(warning) if health.level == HealthLevel::Ok => {
+            let mut health = health.with_detail("session_warning", warning);
+            health.level = HealthLevel::Degraded;
+            health.reason = Some(warning.message(now));
+            health
+        },
+        _ => health,
+    }
+}
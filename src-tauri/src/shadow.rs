@@ -0,0 +1,154 @@
+// Shadow traffic to a secondary backend
+//
+// To judge whether a secondary backend, typically a local model, could take
+// over from ChatGPT, a share of the live chat completions is mirrored to it.
+// Clients only ever get the primary's answer. The mirrored answer is compared
+// with it once both are complete and then discarded; what is kept is how far
+// the two diverged: word overlap, relative length, latency and failures.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Mutex;
+
+/// Comparisons kept for the report
+pub const MAX_RECENT: usize = 50;
+
+/// Bytes of a response read for comparison; longer ones are not compared
+pub const MAX_RESPONSE_BYTES: usize = 1024 * 1024;
+
+/// One mirrored request and how the two answers compared
+#[derive(Debug, Clone, Serialize)]
+pub struct ShadowComparison {
+    pub at: DateTime<Utc>,
+    /// Model the client asked for
+    pub model: String,
+    pub primary_latency_ms: u64,
+    /// `None` when the shadow backend failed
+    pub shadow_latency_ms: Option<u64>,
+    /// Word overlap of the two answers, 0 to 1
+    pub similarity: Option<f64>,
+    /// Length of the shadow answer relative to the primary's
+    pub length_ratio: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// Divergence of the shadow backend from the primary so far
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ShadowReport {
+    pub compared: u64,
+    pub failed: u64,
+    pub mean_similarity: Option<f64>,
+    pub mean_length_ratio: Option<f64>,
+    pub mean_primary_latency_ms: Option<f64>,
+    pub mean_shadow_latency_ms: Option<f64>,
+    /// Newest first
+    pub recent: Vec<ShadowComparison>,
+}
+
+#[derive(Debug, Default)]
+struct Totals {
+    compared: u64,
+    failed: u64,
+    similarity: f64,
+    length_ratio: f64,
+    primary_latency_ms: u64,
+    shadow_latency_ms: u64,
+    recent: VecDeque<ShadowComparison>,
+}
+
+/// Comparisons of the mirrored requests. Lives as long as the
+/// [`crate::managers::server_manager::ServerManager`], so restarts keep them.
+#[derive(Debug, Default)]
+pub struct ShadowStats {
+    totals: Mutex<Totals>,
+}
+
+impl ShadowStats {
+    pub fn record(&self, comparison: ShadowComparison) {
+        let mut totals = self.totals.lock().unwrap_or_else(|e| e.into_inner());
+        match (comparison.similarity, comparison.length_ratio) {
+            (Some(similarity), Some(length_ratio)) => {
+                totals.compared += 1;
+                totals.similarity += similarity;
+                totals.length_ratio += length_ratio;
+                totals.primary_latency_ms += comparison.primary_latency_ms;
+                totals.shadow_latency_ms += comparison.shadow_latency_ms.unwrap_or(0);
+            },
+            _ => totals.failed += 1,
+        }
+        if totals.recent.len() == MAX_RECENT {
+            totals.recent.pop_back();
+        }
+        totals.recent.push_front(comparison);
+    }
+
+    pub fn report(&self) -> ShadowReport {
+        let totals = self.totals.lock().unwrap_or_else(|e| e.into_inner());
+        let mean = |sum: f64| (totals.compared > 0).then(|| sum / totals.compared as f64);
+        ShadowReport {
+            compared: totals.compared,
+            failed: totals.failed,
+            mean_similarity: mean(totals.similarity),
+            mean_length_ratio: mean(totals.length_ratio),
+            mean_primary_latency_ms: mean(totals.primary_latency_ms as f64),
+            mean_shadow_latency_ms: mean(totals.shadow_latency_ms as f64),
+            recent: totals.recent.iter().cloned().collect(),
+        }
+    }
+}
+
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Share of distinct words the two texts have in common (Jaccard index)
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (words(a), words(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Characters of `shadow` per character of `primary`
+pub fn length_ratio(primary: &str, shadow: &str) -> f64 {
+    let primary = primary.chars().count();
+    let shadow = shadow.chars().count();
+    if primary == 0 {
+        return if shadow == 0 { 1.0 } else { shadow as f64 };
+    }
+    shadow as f64 / primary as f64
+}
+
+/// Text of a complete chat completion response, either a JSON body or an SSE
+/// stream. `None` if the response is malformed or the stream did not finish.
+pub fn completion_text(body: &[u8]) -> Option<String> {
+    if let Ok(value) = serde_json::from_slice::<Value>(body) {
+        return value["choices"][0]["message"]["content"]
+            .as_str()
+            .map(str::to_string);
+    }
+
+    let body = String::from_utf8_lossy(body);
+    let mut text = String::new();
+    for line in body.lines() {
+        let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+            continue;
+        };
+        if data == "[DONE]" {
+            return Some(text);
+        }
+        if let Ok(chunk) = serde_json::from_str::<Value>(data) {
+            if let Some(content) = chunk["choices"][0]["delta"]["content"].as_str() {
+                text.push_str(content);
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,204 @@
+// Coordinated shutdown
+//
+// Quitting stops the app's services in dependency order instead of tearing
+// the process down under them: the API server drains the requests in flight
+// before the tunnel that carries them is closed and before the child
+// processes they may be proxied to are stopped, and analytics are written
+// once the last request has recorded its own. Every step is bounded by what
+// is left of `HARD_TIMEOUT`; steps that run out of time are cut off and the
+// remaining ones are skipped. The outcome of each step is logged, and the
+// process exits with a code that tells whether the shutdown was clean.
+
+use crate::error::MindLinkResult;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Longest the whole shutdown may take before the app exits regardless
+pub const HARD_TIMEOUT: Duration = Duration::from_secs(30);
+/// Longest the API server waits for requests in flight
+pub const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Every step finished
+pub const EXIT_CLEAN: i32 = 0;
+/// A step failed, but the shutdown ran to the end
+pub const EXIT_STEP_FAILED: i32 = 1;
+/// The hard timeout cut the shutdown short
+pub const EXIT_TIMED_OUT: i32 = 2;
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+static FINISHED: AtomicBool = AtomicBool::new(false);
+
+/// Claim the shutdown. Only the first caller gets `true`.
+pub fn begin() -> bool {
+    !STARTED.swap(true, Ordering::SeqCst)
+}
+
+/// Mark the shutdown as done, so the exit it ends with is let through
+pub fn finish() {
+    FINISHED.store(true, Ordering::SeqCst);
+}
+
+pub fn is_finished() -> bool {
+    FINISHED.load(Ordering::SeqCst)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownStep {
+    /// Stop accepting connections and let requests in flight finish
+    DrainServer,
+    CloseTunnel,
+    /// Stop the child processes registered with the process monitor
+    StopChildren,
+    FlushAnalytics,
+}
+
+impl ShutdownStep {
+    pub const ALL: [ShutdownStep; 4] = [
+        ShutdownStep::DrainServer,
+        ShutdownStep::CloseTunnel,
+        ShutdownStep::StopChildren,
+        ShutdownStep::FlushAnalytics,
+    ];
+
+    /// Steps that have to be done before this one
+    pub fn after(self) -> &'static [ShutdownStep] {
+        match self {
+            ShutdownStep::DrainServer => &[],
+            // Responses to tunnelled requests still travel through it
+            ShutdownStep::CloseTunnel => &[ShutdownStep::DrainServer],
+            // Requests may be served by Bifrost or a local model
+            ShutdownStep::StopChildren => &[ShutdownStep::DrainServer],
+            // Drained requests record their analytics as they finish
+            ShutdownStep::FlushAnalytics => &[ShutdownStep::DrainServer],
+        }
+    }
+}
+
+impl std::fmt::Display for ShutdownStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShutdownStep::DrainServer => write!(f, "drain API server"),
+            ShutdownStep::CloseTunnel => write!(f, "close tunnel"),
+            ShutdownStep::StopChildren => write!(f, "stop child processes"),
+            ShutdownStep::FlushAnalytics => write!(f, "flush analytics"),
+        }
+    }
+}
+
+/// `steps` ordered so that every step comes after the steps it depends on.
+/// Independent steps keep their order in `steps`; dependencies that are not
+/// part of `steps` are ignored.
+pub fn plan(steps: &[ShutdownStep]) -> Vec<ShutdownStep> {
+    let mut ordered: Vec<ShutdownStep> = Vec::with_capacity(steps.len());
+    while ordered.len() < steps.len() {
+        let ready = steps.iter().copied().find(|step| {
+            !ordered.contains(step)
+                && step
+                    .after()
+                    .iter()
+                    .all(|dependency| ordered.contains(dependency) || !steps.contains(dependency))
+        });
+        match ready {
+            Some(step) => ordered.push(step),
+            // The dependencies are fixed and acyclic
+            None => unreachable!("cyclic shutdown dependencies"),
+        }
+    }
+    ordered
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StepOutcome {
+    Completed,
+    Failed {
+        error: String,
+    },
+    /// Cut off by the hard timeout
+    TimedOut,
+    /// Not started because the hard timeout had passed
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StepReport {
+    pub step: ShutdownStep,
+    pub outcome: StepOutcome,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShutdownReport {
+    pub steps: Vec<StepReport>,
+    pub elapsed_ms: u64,
+}
+
+impl ShutdownReport {
+    pub fn exit_code(&self) -> i32 {
+        let outcomes = || self.steps.iter().map(|report| &report.outcome);
+        if outcomes().any(|outcome| matches!(outcome, StepOutcome::TimedOut | StepOutcome::Skipped))
+        {
+            EXIT_TIMED_OUT
+        } else if outcomes().any(|outcome| matches!(outcome, StepOutcome::Failed { .. })) {
+            EXIT_STEP_FAILED
+        } else {
+            EXIT_CLEAN
+        }
+    }
+
+    /// One line per step that did not complete
+    pub fn problems(&self) -> Vec<String> {
+        self.steps
+            .iter()
+            .filter_map(|report| match &report.outcome {
+                StepOutcome::Completed => None,
+                StepOutcome::Failed { error } => Some(format!("{} failed: {}", report.step, error)),
+                StepOutcome::TimedOut => Some(format!("{} timed out", report.step)),
+                StepOutcome::Skipped => Some(format!("{} skipped", report.step)),
+            })
+            .collect()
+    }
+}
+
+/// Run `steps` in dependency order with `run_step`, within `hard_timeout`
+pub async fn run<F, Fut>(
+    steps: &[ShutdownStep],
+    hard_timeout: Duration,
+    mut run_step: F,
+) -> ShutdownReport
+where
+    F: FnMut(ShutdownStep) -> Fut,
+    Fut: Future<Output = MindLinkResult<()>>,
+{
+    let started = Instant::now();
+    let mut reports = Vec::new();
+
+    for step in plan(steps) {
+        let step_started = Instant::now();
+        let remaining = hard_timeout.saturating_sub(started.elapsed());
+        let outcome = if remaining.is_zero() {
+            StepOutcome::Skipped
+        } else {
+            match tokio::time::timeout(remaining, run_step(step)).await {
+                Ok(Ok(())) => StepOutcome::Completed,
+                Ok(Err(e)) => StepOutcome::Failed {
+                    error: e.to_string(),
+                },
+                Err(_) => StepOutcome::TimedOut,
+            }
+        };
+        reports.push(StepReport {
+            step,
+            outcome,
+            elapsed_ms: step_started.elapsed().as_millis() as u64,
+        });
+    }
+
+    ShutdownReport {
+        steps: reports,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    }
+}
@@ -0,0 +1,137 @@
+// Effective configuration summary logged once at startup
+//
+// Support requests and diagnostics usually start with "what was running";
+// this answers it from a single log line. Values that may carry secrets are
+// reduced to whether they are set, or have their credentials masked.
+
+use crate::managers::config_manager::{ConfigSchema, PowerSaverMode};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ListenerSummary {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutingSummary {
+    pub model_aliases: BTreeMap<String, String>,
+    pub language_routes: BTreeMap<String, String>,
+    /// Models with function-calling emulation enabled
+    pub tool_emulation: Vec<String>,
+    /// Fallback providers in the order they are tried, empty when failover is off
+    pub failover: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeatureSummary {
+    pub analytics: bool,
+    pub language_detection: bool,
+    pub stream_continuation: bool,
+    pub conversations: bool,
+    pub batches: bool,
+    pub power_saver: PowerSaverMode,
+    pub reasoning_effort: String,
+    pub reasoning_summaries: String,
+    pub reasoning_compatibility: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupSummary {
+    pub app_version: String,
+    pub config_version: u32,
+    pub api: ListenerSummary,
+    /// `None` when Bifrost is disabled
+    pub bifrost: Option<ListenerSummary>,
+    pub auth_mode: String,
+    pub authenticated: bool,
+    /// Tunnel provider, `None` when the tunnel is disabled
+    pub tunnel: Option<String>,
+    pub routing: RoutingSummary,
+    pub features: FeatureSummary,
+    pub access_rules: usize,
+    pub ollama_url: String,
+    pub system_prompt: bool,
+    pub prompt_templates: usize,
+}
+
+/// `url` with any password replaced by `***`
+pub fn redact_url(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(mut parsed) if parsed.password().is_some() => {
+            let _ = parsed.set_password(Some("***"));
+            parsed.to_string()
+        },
+        Ok(_) => url.to_string(),
+        // Unparseable values might be anything, so keep them out of the log
+        Err(_) => "***".to_string(),
+    }
+}
+
+impl StartupSummary {
+    pub fn new(config: &ConfigSchema, authenticated: bool) -> Self {
+        let mut tool_emulation: Vec<String> = config
+            .tool_emulation
+            .routes
+            .iter()
+            .filter(|(_, route)| route.enabled)
+            .map(|(model, _)| model.clone())
+            .collect();
+        tool_emulation.sort();
+
+        Self {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            config_version: config.version,
+            api: ListenerSummary {
+                host: config.server.host.clone(),
+                port: config.server.port,
+                tls: config.server.tls.enabled,
+            },
+            bifrost: config.bifrost.enabled.then(|| ListenerSummary {
+                host: config.bifrost.host.clone(),
+                port: config.bifrost.port,
+                tls: false,
+            }),
+            auth_mode: "chatgpt_oauth".to_string(),
+            authenticated,
+            tunnel: config
+                .tunnel
+                .enabled
+                .then(|| config.tunnel.tunnel_type.clone()),
+            routing: RoutingSummary {
+                model_aliases: config.model_aliases.aliases.clone().into_iter().collect(),
+                language_routes: config
+                    .language_detection
+                    .routes
+                    .clone()
+                    .into_iter()
+                    .collect(),
+                tool_emulation,
+                failover: config
+                    .failover
+                    .providers
+                    .iter()
+                    .filter(|_| config.failover.enabled)
+                    .map(|provider| provider.name.clone())
+                    .collect(),
+            },
+            features: FeatureSummary {
+                analytics: config.analytics.enabled,
+                language_detection: config.language_detection.enabled,
+                stream_continuation: config.stream_continuation.enabled,
+                conversations: config.conversations.enabled,
+                batches: config.batches.enabled,
+                power_saver: config.power_saver.mode,
+                reasoning_effort: config.features.reasoning_effort.clone(),
+                reasoning_summaries: config.features.reasoning_summaries.clone(),
+                reasoning_compatibility: config.features.reasoning_compatibility.clone(),
+            },
+            access_rules: config.access_control.allow.len() + config.access_control.deny.len(),
+            ollama_url: redact_url(&config.local_models.ollama_url),
+            system_prompt: config.prompts.system_prompt.is_some(),
+            prompt_templates: config.prompts.templates.len(),
+        }
+    }
+}
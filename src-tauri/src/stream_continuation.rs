@@ -0,0 +1,80 @@
+// Continuation of truncated streams
+//
+// When the upstream stream dies mid-response the server can ask the backend
+// to pick up where it stopped and keep writing into the same client stream.
+// Models often repeat the last few words they were shown, so the start of a
+// continuation is held back until it can be checked against what the client
+// already received and the repeated part is dropped.
+
+/// Prompt used when the backend cannot resume its own message directly
+pub const CONTINUE_PROMPT: &str = "Your previous reply was cut off. Continue exactly where it \
+     stopped, without repeating anything or adding commentary.";
+
+/// How much of the received text a continuation is compared against
+const OVERLAP_WINDOW: usize = 64;
+
+/// Shorter matches are more likely coincidence than a repeat
+const MIN_OVERLAP: usize = 8;
+
+/// Removes text a continuation repeats from the end of the previous stream.
+/// The default stitcher passes everything through unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct ContinuationStitcher {
+    tail: String,
+    buffer: String,
+    resolved: bool,
+}
+
+impl ContinuationStitcher {
+    /// Stitcher for a continuation of `received`
+    pub fn new(received: &str) -> Self {
+        let mut start = received.len().saturating_sub(OVERLAP_WINDOW);
+        while !received.is_char_boundary(start) {
+            start += 1;
+        }
+
+        Self {
+            tail: received[start..].to_string(),
+            buffer: String::new(),
+            resolved: received.is_empty(),
+        }
+    }
+
+    /// Text to forward for an upstream delta. Returns `None` while the start of
+    /// a continuation is still being held back.
+    pub fn push(&mut self, delta: &str) -> Option<String> {
+        if self.resolved || self.tail.is_empty() {
+            return Some(delta.to_string()).filter(|text| !text.is_empty());
+        }
+
+        self.buffer.push_str(delta);
+        if self.buffer.len() < self.tail.len() {
+            return None;
+        }
+        self.resolve()
+    }
+
+    /// Release whatever is still held back once the stream has ended
+    pub fn finish(&mut self) -> Option<String> {
+        if self.resolved {
+            return None;
+        }
+        self.resolve()
+    }
+
+    fn resolve(&mut self) -> Option<String> {
+        self.resolved = true;
+        let buffer = std::mem::take(&mut self.buffer);
+        let overlap = overlap(&self.tail, &buffer);
+        Some(buffer[overlap..].to_string()).filter(|text| !text.is_empty())
+    }
+}
+
+/// Length of the longest prefix of `next` that `tail` ends with
+pub fn overlap(tail: &str, next: &str) -> usize {
+    let max = tail.len().min(next.len());
+    (MIN_OVERLAP..=max)
+        .rev()
+        .find(|&len| next.is_char_boundary(len) && tail.ends_with(&next[..len]))
+        .unwrap_or(0)
+}
@@ -0,0 +1,221 @@
+#[cfg(test)]
+mod access_control_tests {
+    use crate::managers::config_manager::AccessControlConfig;
+    use crate::middleware::access_control::{
+        resolve_client_ip, AccessPolicy, ClientIp, ClientIpResolver, IpCidr, TrustedProxies,
+    };
+    use axum::body::Body;
+    use axum::extract::{ConnectInfo, Extension};
+    use axum::http::{header, HeaderMap, Request};
+    use axum::routing::get;
+    use axum::Router;
+    use std::net::{IpAddr, SocketAddr};
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().expect("valid IP")
+    }
+
+    #[test]
+    fn test_cidr_matching() {
+        println!("🧪 Test: CIDR matching");
+
+        let lan = IpCidr::parse("192.168.1.0/24").expect("valid CIDR");
+        assert!(lan.contains(&ip("192.168.1.42")));
+        assert!(!lan.contains(&ip("192.168.2.1")));
+        // IPv4-mapped IPv6 addresses from dual-stack sockets match IPv4 networks
+        assert!(lan.contains(&ip("::ffff:192.168.1.7")));
+
+        let odd_prefix = IpCidr::parse("10.0.0.0/12").expect("valid CIDR");
+        assert!(odd_prefix.contains(&ip("10.15.255.255")));
+        assert!(!odd_prefix.contains(&ip("10.16.0.0")));
+
+        let single = IpCidr::parse("fd00::1").expect("valid address");
+        assert!(single.contains(&ip("fd00::1")));
+        assert!(!single.contains(&ip("fd00::2")));
+
+        assert!(IpCidr::parse("10.0.0.0/33").is_err());
+        assert!(IpCidr::parse("not-an-ip").is_err());
+
+        println!("✅ CIDR matching successful");
+    }
+
+    #[test]
+    fn test_policy_deny_takes_precedence() {
+        println!("🧪 Test: Access policy precedence");
+
+        let policy = AccessPolicy::from_config(&AccessControlConfig {
+            allow: vec!["192.168.0.0/16".to_string()],
+            deny: vec!["192.168.1.13".to_string()],
+            ..AccessControlConfig::default()
+        })
+        .expect("valid policy");
+
+        assert!(policy.is_allowed(&ip("192.168.5.5")));
+        assert!(!policy.is_allowed(&ip("192.168.1.13")));
+        assert!(!policy.is_allowed(&ip("8.8.8.8")));
+
+        let open = AccessPolicy::from_config(&AccessControlConfig::default()).expect("valid");
+        assert!(open.is_allowed(&ip("8.8.8.8")), "Empty policy allows all");
+
+        println!("✅ Access policy precedence successful");
+    }
+
+    #[test]
+    fn test_forwarded_for_only_trusted_from_loopback() {
+        println!("🧪 Test: X-Forwarded-For handling");
+
+        let proxies = TrustedProxies::default();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "203.0.113.9".parse().expect("header"));
+
+        // Tunneled traffic arrives on loopback
+        assert_eq!(
+            proxies.client_ip(Some(ip("127.0.0.1")), &headers, false),
+            Some(ip("203.0.113.9"))
+        );
+
+        // LAN clients cannot spoof their address with headers
+        assert_eq!(
+            proxies.client_ip(Some(ip("192.168.1.20")), &headers, false),
+            Some(ip("192.168.1.20"))
+        );
+
+        // Entries left of the proxy's own are the client's to write
+        headers.insert(
+            "x-forwarded-for",
+            "127.0.0.1, 10.0.0.1, 203.0.113.9".parse().expect("header"),
+        );
+        assert_eq!(
+            proxies.client_ip(Some(ip("127.0.0.1")), &headers, false),
+            Some(ip("203.0.113.9"))
+        );
+        // Hops through further trusted proxies are skipped
+        headers.insert(
+            "x-forwarded-for",
+            "10.0.0.1, 203.0.113.9, 127.0.0.1".parse().expect("header"),
+        );
+        assert_eq!(
+            proxies.client_ip(Some(ip("127.0.0.1")), &headers, false),
+            Some(ip("203.0.113.9"))
+        );
+
+        // Without a peer address nothing is believed
+        assert_eq!(proxies.client_ip(None, &headers, false), None);
+
+        println!("✅ X-Forwarded-For handling successful");
+    }
+
+    #[test]
+    fn test_configured_trusted_proxies() {
+        println!("🧪 Test: Configured trusted proxies");
+
+        let proxies = TrustedProxies::from_config(&AccessControlConfig {
+            trusted_proxies: vec!["10.0.0.0/8".to_string()],
+            ..AccessControlConfig::default()
+        })
+        .expect("valid proxies");
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", "198.51.100.4".parse().expect("header"));
+        headers.insert("cf-connecting-ip", "203.0.113.9".parse().expect("header"));
+
+        // Cloudflare's header wins over X-Forwarded-For when cloudflared sent it
+        assert_eq!(
+            proxies.client_ip(Some(ip("10.1.2.3")), &headers, true),
+            Some(ip("203.0.113.9"))
+        );
+        assert_eq!(
+            proxies.client_ip(Some(ip("10.1.2.3")), &headers, false),
+            Some(ip("198.51.100.4"))
+        );
+        // Loopback is no longer trusted once the list is replaced
+        assert_eq!(
+            proxies.client_ip(Some(ip("127.0.0.1")), &headers, true),
+            Some(ip("127.0.0.1"))
+        );
+
+        assert!(TrustedProxies::from_config(&AccessControlConfig {
+            trusted_proxies: vec!["proxy.local".to_string()],
+            ..AccessControlConfig::default()
+        })
+        .is_err());
+
+        println!("✅ Configured trusted proxies successful");
+    }
+
+    /// Client IP the router resolves for a request from a loopback tunnel
+    async fn resolved_through_tunnel(host: &str, headers: &[(&str, &str)]) -> String {
+        let resolver = ClientIpResolver {
+            proxies: Arc::new(TrustedProxies::default()),
+            cloudflared_url: Arc::new(RwLock::new(Some(
+                "https://mindlink.example.com".to_string(),
+            ))),
+        };
+        let router = Router::new()
+            .route(
+                "/v1/models",
+                get(|Extension(ClientIp(ip)): Extension<ClientIp>| async move {
+                    ip.map(|ip| ip.to_string()).unwrap_or_default()
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                resolver,
+                resolve_client_ip,
+            ));
+
+        let mut request = Request::builder()
+            .uri("/v1/models")
+            .header(header::HOST, host);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let mut request = request.body(Body::empty()).unwrap();
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 40000))));
+        let response = router.oneshot(request).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_spoofed_headers_through_tunnels() {
+        println!("🧪 Test: Spoofed forwarding headers through loopback tunnels");
+
+        // cloudflared reports the client it connected to
+        let through_cloudflared = resolved_through_tunnel(
+            "mindlink.example.com",
+            &[
+                ("cf-connecting-ip", "203.0.113.9"),
+                ("x-forwarded-for", "192.168.1.5, 203.0.113.9"),
+            ],
+        )
+        .await;
+        assert_eq!(through_cloudflared, "203.0.113.9");
+
+        // ngrok appends the client's address to whatever the client sent, and
+        // has no say in CF-Connecting-IP
+        let through_ngrok = resolved_through_tunnel(
+            "mindlink.ngrok.app",
+            &[
+                ("cf-connecting-ip", "192.168.1.5"),
+                ("x-forwarded-for", "192.168.1.5, 198.51.100.4"),
+            ],
+        )
+        .await;
+        assert_eq!(through_ngrok, "198.51.100.4");
+
+        // Local clients without forwarding headers are themselves
+        assert_eq!(
+            resolved_through_tunnel("127.0.0.1:3001", &[]).await,
+            "127.0.0.1"
+        );
+
+        println!("✅ Spoofed forwarding headers successful");
+    }
+}
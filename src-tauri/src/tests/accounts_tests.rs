@@ -0,0 +1,196 @@
+#[cfg(test)]
+mod accounts_tests {
+    use crate::accounts::{prefer, rank, AccountLoad, AccountPool, RequestOutcome, SessionPins};
+    use crate::managers::config_manager::BalancingStrategy;
+    use std::time::{Duration, Instant};
+
+    const COOLDOWN: Duration = Duration::from_secs(60);
+    const SESSION_TTL: Duration = Duration::from_secs(3600);
+
+    #[test]
+    fn test_round_robin_takes_turns() {
+        println!("🧪 Test: Round-robin account rotation");
+
+        let now = Instant::now();
+        let loads = [
+            AccountLoad::default(),
+            AccountLoad::default(),
+            AccountLoad::default(),
+        ];
+        let refs: Vec<&AccountLoad> = loads.iter().collect();
+
+        assert_eq!(
+            rank(BalancingStrategy::RoundRobin, &refs, 0, now),
+            vec![0, 1, 2]
+        );
+        assert_eq!(
+            rank(BalancingStrategy::RoundRobin, &refs, 1, now),
+            vec![1, 2, 0]
+        );
+        assert_eq!(
+            rank(BalancingStrategy::RoundRobin, &refs, 5, now),
+            vec![2, 0, 1]
+        );
+        assert!(rank(BalancingStrategy::RoundRobin, &[], 3, now).is_empty());
+
+        println!("✅ Round-robin account rotation successful");
+    }
+
+    #[test]
+    fn test_least_loaded_prefers_idle_accounts() {
+        println!("🧪 Test: Least-loaded account selection");
+
+        let now = Instant::now();
+        let mut busy = AccountLoad::default();
+        busy.start(now);
+        busy.start(now);
+        let mut one = AccountLoad::default();
+        one.start(now);
+        let idle = AccountLoad::default();
+
+        let refs = vec![&busy, &one, &idle];
+        assert_eq!(
+            rank(BalancingStrategy::LeastLoaded, &refs, 0, now),
+            vec![2, 1, 0]
+        );
+
+        // Finished requests no longer count
+        busy.end();
+        busy.end();
+        let refs = vec![&busy, &one, &idle];
+        assert_eq!(
+            rank(BalancingStrategy::LeastLoaded, &refs, 0, now),
+            vec![0, 2, 1]
+        );
+        assert_eq!(
+            rank(BalancingStrategy::LeastLoaded, &refs, 1, now),
+            vec![2, 0, 1]
+        );
+
+        println!("✅ Least-loaded account selection successful");
+    }
+
+    #[test]
+    fn test_rate_limited_accounts_cool_down() {
+        println!("🧪 Test: Rate limit cooldown ordering");
+
+        let now = Instant::now();
+        let mut first = AccountLoad::default();
+        first.record(RequestOutcome::RateLimited, now, COOLDOWN);
+        let mut second = AccountLoad::default();
+        second.record(RequestOutcome::RateLimited, now, Duration::from_secs(10));
+        let third = AccountLoad::default();
+
+        let refs = vec![&first, &second, &third];
+        assert_eq!(
+            rank(BalancingStrategy::RoundRobin, &refs, 0, now),
+            vec![2, 1, 0]
+        );
+        assert_eq!(first.cooldown_left(now), Some(COOLDOWN));
+
+        // Once the cooldown is over the account takes its turn again
+        let later = now + COOLDOWN;
+        assert_eq!(first.cooldown_left(later), None);
+        assert_eq!(
+            rank(BalancingStrategy::RoundRobin, &refs, 0, later),
+            vec![0, 1, 2]
+        );
+
+        println!("✅ Rate limit cooldown ordering successful");
+    }
+
+    #[test]
+    fn test_load_tracks_request_rate() {
+        println!("🧪 Test: Per-account request rate");
+
+        let now = Instant::now();
+        let mut load = AccountLoad::default();
+        load.start(now);
+        load.start(now + Duration::from_secs(30));
+        load.record(RequestOutcome::Failed, now, COOLDOWN);
+        assert_eq!(load.in_flight(), 2);
+        assert_eq!(load.requests_per_minute(now + Duration::from_secs(30)), 2);
+        assert_eq!(load.requests_per_minute(now + Duration::from_secs(61)), 1);
+        assert_eq!(load.cooldown_left(now), None);
+
+        load.end();
+        load.end();
+        load.end();
+        assert_eq!(load.in_flight(), 0);
+
+        println!("✅ Per-account request rate successful");
+    }
+
+    #[test]
+    fn test_account_names_are_validated() {
+        println!("🧪 Test: Account name validation");
+
+        let path = AccountPool::account_path("work-pro_2").unwrap();
+        assert!(path.ends_with("accounts/work-pro_2.json"));
+
+        for invalid in ["", "primary", "../secrets", "has space", &"a".repeat(65)] {
+            assert!(
+                AccountPool::account_path(invalid).is_err(),
+                "'{}' should be rejected",
+                invalid
+            );
+        }
+
+        println!("✅ Account name validation successful");
+    }
+
+    #[test]
+    fn test_sessions_stay_on_their_account() {
+        println!("🧪 Test: Sticky session pinning");
+
+        let now = Instant::now();
+        let mut pins = SessionPins::default();
+        assert_eq!(pins.get("chat-1", SESSION_TTL, now), None);
+
+        assert_eq!(pins.pin("chat-1", "work", SESSION_TTL, now), None);
+        assert_eq!(pins.pin("chat-2", "primary", SESSION_TTL, now), None);
+        assert_eq!(pins.get("chat-1", SESSION_TTL, now), Some("work"));
+        assert_eq!(pins.count("work", SESSION_TTL, now), 1);
+
+        // Pinning again to the same account is not a move
+        let later = now + Duration::from_secs(60);
+        assert_eq!(pins.pin("chat-1", "work", SESSION_TTL, later), None);
+        assert_eq!(
+            pins.pin("chat-1", "primary", SESSION_TTL, later).as_deref(),
+            Some("work")
+        );
+        assert_eq!(pins.count("primary", SESSION_TTL, later), 2);
+
+        // Idle sessions are free to move
+        let idle = now + SESSION_TTL;
+        assert_eq!(pins.get("chat-2", SESSION_TTL, idle), None);
+        assert_eq!(pins.pin("chat-2", "work", SESSION_TTL, idle), None);
+
+        println!("✅ Sticky session pinning successful");
+    }
+
+    #[test]
+    fn test_pinned_account_is_tried_first() {
+        println!("🧪 Test: Pinned account ordering");
+
+        let now = Instant::now();
+        let loads = [
+            AccountLoad::default(),
+            AccountLoad::default(),
+            AccountLoad::default(),
+        ];
+        let refs: Vec<&AccountLoad> = loads.iter().collect();
+        let order = rank(BalancingStrategy::RoundRobin, &refs, 0, now);
+        assert_eq!(prefer(order.clone(), Some(2), &refs, now), vec![2, 0, 1]);
+        assert_eq!(prefer(order.clone(), None, &refs, now), vec![0, 1, 2]);
+
+        // A session moves off an account that is cooling down
+        let mut limited = AccountLoad::default();
+        limited.record(RequestOutcome::RateLimited, now, COOLDOWN);
+        let refs = vec![&loads[0], &loads[1], &limited];
+        let order = rank(BalancingStrategy::RoundRobin, &refs, 0, now);
+        assert_eq!(prefer(order, Some(2), &refs, now), vec![0, 1, 2]);
+
+        println!("✅ Pinned account ordering successful");
+    }
+}
@@ -0,0 +1,345 @@
+#[cfg(test)]
+mod analytics_tests {
+    use crate::analytics::{
+        AnalyticsRecorder, AnalyticsStats, AnalyticsStore, QuotaAttribution, RequestRecord,
+    };
+    use crate::api_error::ApiError;
+    use crate::managers::config_manager::AnalyticsConfig;
+    use crate::middleware::analytics::{key_fingerprint, record_analytics};
+    use crate::middleware::request_id::assign_request_id;
+    use axum::body::Body;
+    use axum::http::{header::AUTHORIZATION, HeaderMap, HeaderValue, Request, StatusCode};
+    use axum::routing::post;
+    use axum::{middleware, Router};
+    use chrono::{Duration as ChronoDuration, Utc};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tempfile::TempDir;
+    use tower::ServiceExt;
+
+    fn record() -> RequestRecord {
+        RequestRecord {
+            timestamp: Utc::now(),
+            method: "POST".to_string(),
+            route: "/v1/chat/completions".to_string(),
+            status: 200,
+            latency_ms: 42,
+            sample_weight: 1,
+            request_id: None,
+            model: None,
+            tokens: 0,
+            client: None,
+            api_key: None,
+            language: None,
+            variant: None,
+            error: None,
+        }
+    }
+
+    fn usage(api_key: &str, client: &str, model: &str, tokens: u64) -> RequestRecord {
+        RequestRecord {
+            model: Some(model.to_string()),
+            tokens,
+            client: Some(client.to_string()),
+            api_key: Some(api_key.to_string()),
+            ..record()
+        }
+    }
+
+    async fn wait_for_flush(stats: &AnalyticsStats, expected: u64) {
+        for _ in 0..100 {
+            if stats.snapshot().flushed >= expected {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("records were not flushed: {:?}", stats.snapshot());
+    }
+
+    #[test]
+    fn test_store_batches_and_reopens() {
+        println!("🧪 Test: Analytics store batch insert");
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("analytics.db");
+
+        let mut store = AnalyticsStore::open(&path).expect("open store");
+        let mut sampled = record();
+        sampled.sample_weight = 10;
+        assert_eq!(
+            store.insert_batch(&[record(), record(), sampled]).unwrap(),
+            3
+        );
+        drop(store);
+
+        // Data survives reopening (including WAL recovery on open)
+        let mut store = AnalyticsStore::open(&path).expect("reopen store");
+        assert_eq!(store.estimated_requests().unwrap(), 12);
+
+        // Records can be traced back by request ID
+        let mut traced = record();
+        traced.request_id = Some("req_trace".to_string());
+        traced.language = Some("jpn".to_string());
+        store.insert_batch(&[traced]).unwrap();
+        let found = store
+            .find_by_request_id("req_trace")
+            .unwrap()
+            .expect("record");
+        assert_eq!(found.route, "/v1/chat/completions");
+        assert_eq!(found.language.as_deref(), Some("jpn"));
+        assert!(store.find_by_request_id("missing").unwrap().is_none());
+
+        // The most recent records come first
+        let recent = store.recent(2).unwrap();
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].request_id.as_deref(), Some("req_trace"));
+
+        println!("✅ Analytics store batch insert successful");
+    }
+
+    #[tokio::test]
+    async fn test_recorder_flushes_when_dropped() {
+        println!("🧪 Test: Analytics recorder final flush");
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("analytics.db");
+        let stats = Arc::new(AnalyticsStats::default());
+        let config = AnalyticsConfig {
+            flush_interval_ms: 60_000,
+            ..AnalyticsConfig::default()
+        };
+
+        let recorder = AnalyticsRecorder::spawn(&path, &config, stats.clone()).expect("spawn");
+        for _ in 0..5 {
+            recorder.record(record());
+        }
+        drop(recorder);
+
+        wait_for_flush(&stats, 5).await;
+        assert_eq!(stats.snapshot().pending, 0);
+        let store = AnalyticsStore::open(&path).expect("reopen store");
+        assert_eq!(store.estimated_requests().unwrap(), 5);
+
+        println!("✅ Analytics recorder final flush successful");
+    }
+
+    #[tokio::test]
+    async fn test_failed_request_is_recorded_with_reason() {
+        println!("🧪 Test: Failure reason recorded under the request ID");
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("analytics.db");
+        let stats = Arc::new(AnalyticsStats::default());
+        let recorder = AnalyticsRecorder::spawn(&path, &AnalyticsConfig::default(), stats.clone())
+            .expect("spawn");
+
+        let app = Router::new()
+            .route(
+                "/v1/chat/completions",
+                post(|| async { ApiError::new(StatusCode::BAD_GATEWAY, "Upstream timed out") }),
+            )
+            .layer(middleware::from_fn_with_state(recorder, record_analytics))
+            .layer(middleware::from_fn(assign_request_id));
+
+        let response = app
+            .oneshot(
+                Request::post("/v1/chat/completions")
+                    .header("x-request-id", "req_audit")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+
+        // The router held the last recorder, so its final flush follows
+        wait_for_flush(&stats, 1).await;
+        let store = AnalyticsStore::open(&path).expect("reopen store");
+        let found = store
+            .find_by_request_id("req_audit")
+            .unwrap()
+            .expect("record");
+        assert_eq!(found.status, 502);
+        assert_eq!(found.error.as_deref(), Some("Upstream timed out"));
+
+        println!("✅ Failure reason recorded under the request ID successful");
+    }
+
+    #[tokio::test]
+    async fn test_recorder_samples_under_pressure() {
+        println!("🧪 Test: Analytics sampling under backpressure");
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("analytics.db");
+        let stats = Arc::new(AnalyticsStats::default());
+        let config = AnalyticsConfig {
+            enabled: true,
+            flush_interval_ms: 60_000,
+            batch_size: 10,
+            buffer_capacity: 10,
+            pressure_sample_rate: 5,
+        };
+
+        let recorder = AnalyticsRecorder::spawn(&path, &config, stats.clone()).expect("spawn");
+
+        // The flusher cannot run until this task yields, so the buffer fills up:
+        // 5 records go straight in, then only every 5th of the next 25 is kept
+        for _ in 0..30 {
+            recorder.record(record());
+        }
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.recorded, 10);
+        assert_eq!(snapshot.sampled_out, 20);
+        assert_eq!(snapshot.dropped, 0);
+
+        drop(recorder);
+        wait_for_flush(&stats, 10).await;
+
+        // Sample weights keep the estimate honest: 5 * 1 + 5 * 5
+        let store = AnalyticsStore::open(&path).expect("reopen store");
+        assert_eq!(store.estimated_requests().unwrap(), 30);
+
+        println!("✅ Analytics sampling under backpressure successful");
+    }
+
+    #[tokio::test]
+    async fn test_paused_recorder_waits_for_full_batch() {
+        println!("🧪 Test: Paused analytics flushing");
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("analytics.db");
+        let stats = Arc::new(AnalyticsStats::default());
+        stats.set_paused(true);
+        let config = AnalyticsConfig {
+            flush_interval_ms: 20,
+            batch_size: 4,
+            ..AnalyticsConfig::default()
+        };
+
+        let recorder = AnalyticsRecorder::spawn(&path, &config, stats.clone()).expect("spawn");
+        for _ in 0..3 {
+            recorder.record(record());
+        }
+
+        // Several flush intervals pass without a write
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(stats.snapshot().flushed, 0);
+        assert!(stats.snapshot().paused);
+
+        // A full batch is still written
+        recorder.record(record());
+        wait_for_flush(&stats, 4).await;
+
+        // Resuming brings back the periodic flush
+        stats.set_paused(false);
+        recorder.record(record());
+        wait_for_flush(&stats, 5).await;
+
+        println!("✅ Paused analytics flushing successful");
+    }
+
+    #[test]
+    fn test_quota_attribution_groups_recent_usage() {
+        println!("🧪 Test: Quota attribution by key, device and model");
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("analytics.db");
+        let mut store = AnalyticsStore::open(&path).expect("open store");
+
+        let mut sampled = usage("key_a", "10.0.0.2", "gpt-4o", 50);
+        sampled.sample_weight = 4;
+        let mut stale = usage("key_b", "10.0.0.3", "gpt-4o", 10_000);
+        stale.timestamp = Utc::now() - ChronoDuration::hours(2);
+
+        store
+            .insert_batch(&[
+                usage("key_a", "10.0.0.2", "gpt-4o", 100),
+                sampled,
+                usage("key_a", "10.0.0.2", "gpt-4o-mini", 30),
+                usage("key_b", "10.0.0.3", "gpt-4o", 120),
+                stale,
+                // Requests without a model (e.g. /v1/models) are not attributed
+                record(),
+            ])
+            .unwrap();
+
+        let since = Utc::now() - ChronoDuration::hours(1);
+        let entries = store.quota_attribution(since).unwrap();
+        let attribution = QuotaAttribution::new(since, entries, 2);
+
+        assert_eq!(attribution.entries.len(), 3);
+        let top = &attribution.entries[0];
+        assert_eq!(top.api_key.as_deref(), Some("key_a"));
+        assert_eq!(top.model.as_deref(), Some("gpt-4o"));
+        // 100 + 50 * 4, scaled by the sample weight
+        assert_eq!(top.tokens, 300);
+        assert_eq!(top.requests, 5);
+        // Budgets are those of authorized apps, filled in by the server
+        assert!(top.budgets.is_empty());
+
+        assert_eq!(attribution.total_tokens, 300 + 120 + 30);
+        assert_eq!(attribution.total_requests, 7);
+        assert_eq!(attribution.active_streams, 2);
+
+        println!("✅ Quota attribution successful");
+    }
+
+    #[test]
+    fn test_variant_stats_count_server_errors() {
+        println!("🧪 Test: Per-variant request and error counts");
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("analytics.db");
+        let mut store = AnalyticsStore::open(&path).expect("open store");
+
+        let variant = |name: &str, status: u16, sample_weight: u32| RequestRecord {
+            variant: Some(name.to_string()),
+            status,
+            sample_weight,
+            ..record()
+        };
+        store
+            .insert_batch(&[
+                variant("stable", 200, 1),
+                variant("stable", 502, 1),
+                variant("canary", 200, 3),
+                // Client errors are not held against a variant
+                variant("canary", 400, 1),
+                variant("canary", 500, 2),
+                record(),
+            ])
+            .unwrap();
+
+        let stats = store
+            .variant_stats(Utc::now() - ChronoDuration::hours(1))
+            .unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats["stable"].requests, 2);
+        assert_eq!(stats["stable"].errors, 1);
+        assert_eq!(stats["canary"].requests, 6);
+        assert_eq!(stats["canary"].errors, 2);
+        assert!((stats["canary"].error_rate() - 2.0 / 6.0).abs() < f64::EPSILON);
+
+        println!("✅ Per-variant request and error counts successful");
+    }
+
+    #[test]
+    fn test_key_fingerprint_hides_key() {
+        println!("🧪 Test: Client key fingerprinting");
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(key_fingerprint(&headers), None);
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer sk-secret"));
+        let fingerprint = key_fingerprint(&headers).expect("fingerprint");
+        assert!(fingerprint.starts_with("key_"));
+        assert_eq!(fingerprint.len(), "key_".len() + 12);
+        assert!(!fingerprint.contains("secret"));
+        assert_eq!(key_fingerprint(&headers), Some(fingerprint));
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer "));
+        assert_eq!(key_fingerprint(&headers), None);
+
+        println!("✅ Client key fingerprinting successful");
+    }
+}
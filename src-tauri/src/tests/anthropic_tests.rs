@@ -0,0 +1,159 @@
+#[cfg(test)]
+mod anthropic_tests {
+    use crate::anthropic::{
+        count_stream_usage, finish_reason, is_claude_model, messages_request, response_text,
+        response_usage, stream_event, uses_tools, StreamEvent, DEFAULT_MAX_TOKENS,
+    };
+    use crate::managers::server_manager::{ChatCompletionRequest, Usage};
+    use serde_json::json;
+
+    fn request(body: serde_json::Value) -> ChatCompletionRequest {
+        serde_json::from_value(body).unwrap()
+    }
+
+    #[test]
+    fn test_claude_models() {
+        println!("🧪 Test: Claude models are recognized");
+
+        assert!(is_claude_model("claude-sonnet-4-5"));
+        assert!(!is_claude_model("gpt-4o"));
+        assert!(!is_claude_model("claude"));
+
+        println!("✅ Claude models are recognized successful");
+    }
+
+    #[test]
+    fn test_messages_request() {
+        println!("🧪 Test: Chat completions become Messages requests");
+
+        let chat = request(json!({
+            "model": "claude-sonnet-4-5",
+            "messages": [
+                {"role": "system", "content": "Be brief."},
+                {"role": "user", "content": "Hi"},
+                {"role": "assistant", "content": "Hello"},
+                {"role": "user", "content": [{"type": "text", "text": "Bye"}]}
+            ],
+            "temperature": 0.5,
+            "stop": "END"
+        }));
+        let body = messages_request(
+            &chat,
+            "claude-sonnet-4-5-canary",
+            Some("Answer in English."),
+            true,
+        );
+
+        assert_eq!(body["model"], "claude-sonnet-4-5-canary");
+        assert_eq!(body["system"], "Answer in English.\n\nBe brief.");
+        assert_eq!(body["messages"].as_array().unwrap().len(), 3);
+        assert_eq!(body["messages"][1]["role"], "assistant");
+        assert_eq!(body["messages"][2]["content"], "Bye");
+        assert_eq!(body["max_tokens"], DEFAULT_MAX_TOKENS);
+        assert_eq!(body["stop_sequences"], json!(["END"]));
+        assert_eq!(body["stream"], true);
+
+        // The Messages API takes no system role and wants an output limit
+        let chat = request(json!({
+            "model": "claude-haiku-4-5",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "max_completion_tokens": 100
+        }));
+        let body = messages_request(&chat, "claude-haiku-4-5", None, false);
+        assert!(body.get("system").is_none());
+        assert_eq!(body["max_tokens"], 100);
+        assert!(!uses_tools(&chat));
+
+        // Tools are refused rather than silently dropped
+        let chat = request(json!({
+            "model": "claude-haiku-4-5",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "tools": [{"type": "function", "function": {"name": "lookup"}}]
+        }));
+        assert!(uses_tools(&chat));
+        let chat = request(json!({
+            "model": "claude-haiku-4-5",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "tools": []
+        }));
+        assert!(!uses_tools(&chat));
+
+        println!("✅ Chat completions become Messages requests successful");
+    }
+
+    #[test]
+    fn test_reply() {
+        println!("🧪 Test: Messages replies become chat completions");
+
+        let reply = json!({
+            "content": [
+                {"type": "thinking", "thinking": "..."},
+                {"type": "text", "text": "Hello"},
+                {"type": "text", "text": " there"}
+            ],
+            "stop_reason": "max_tokens",
+            "usage": {"input_tokens": 12, "output_tokens": 3}
+        });
+
+        assert_eq!(response_text(&reply), "Hello there");
+        let usage = response_usage(&reply).unwrap();
+        assert_eq!(usage.prompt_tokens, 12);
+        assert_eq!(usage.total_tokens, 15);
+        assert_eq!(finish_reason(Some("max_tokens")), "length");
+        assert_eq!(finish_reason(Some("end_turn")), "stop");
+        assert_eq!(finish_reason(None), "stop");
+
+        println!("✅ Messages replies become chat completions successful");
+    }
+
+    #[test]
+    fn test_stream_events() {
+        println!("🧪 Test: Messages stream events");
+
+        let text = json!({
+            "type": "content_block_delta",
+            "index": 0,
+            "delta": {"type": "text_delta", "text": "Hi"}
+        });
+        assert_eq!(
+            stream_event(&text),
+            Some(StreamEvent::Text("Hi".to_string()))
+        );
+
+        let stop = json!({
+            "type": "message_delta",
+            "delta": {"stop_reason": "end_turn"},
+            "usage": {"output_tokens": 5}
+        });
+        assert_eq!(stream_event(&stop), Some(StreamEvent::Finished("stop")));
+
+        let error = json!({
+            "type": "error",
+            "error": {"type": "overloaded_error", "message": "Overloaded"}
+        });
+        assert_eq!(
+            stream_event(&error),
+            Some(StreamEvent::Error("Overloaded".to_string()))
+        );
+
+        assert_eq!(stream_event(&json!({"type": "ping"})), None);
+
+        // Token counts arrive as the message starts and ends
+        let mut usage = Usage {
+            prompt_tokens: 3,
+            completion_tokens: 0,
+            total_tokens: 0,
+        };
+        let start = json!({
+            "type": "message_start",
+            "message": {"usage": {"input_tokens": 12, "output_tokens": 1}}
+        });
+        count_stream_usage(&start, &mut usage);
+        count_stream_usage(&text, &mut usage);
+        assert_eq!(usage.prompt_tokens, 12);
+        count_stream_usage(&stop, &mut usage);
+        assert_eq!((usage.completion_tokens, usage.total_tokens), (5, 17));
+
+        println!("✅ Messages stream events successful");
+    }
+}
@@ -0,0 +1,99 @@
+#[cfg(test)]
+mod api_error_tests {
+    use crate::api_error::{upstream_status, ApiError, UpstreamStatus};
+    use crate::error::MindLinkError;
+    use axum::http::StatusCode;
+    use serde_json::json;
+
+    fn upstream_failure(status: u16, body: &str) -> MindLinkError {
+        MindLinkError::Network {
+            message: format!("ChatGPT API returned status: {}", status),
+            url: None,
+            source: Some(UpstreamStatus::new(status, body).into()),
+        }
+    }
+
+    #[test]
+    fn test_error_body_schema() {
+        println!("🧪 Test: OpenAI error body schema");
+
+        let error = ApiError::new(StatusCode::BAD_REQUEST, "messages array cannot be empty");
+        assert_eq!(
+            error.body(),
+            json!({
+                "error": {
+                    "message": "messages array cannot be empty",
+                    "type": "invalid_request_error",
+                    "param": null,
+                    "code": null,
+                }
+            })
+        );
+
+        let error = ApiError::new(StatusCode::FORBIDDEN, "Model not allowed").with_param("model");
+        assert_eq!(error.body()["error"]["param"], "model");
+        assert_eq!(error.body()["error"]["code"], "permission_denied");
+
+        println!("✅ OpenAI error body schema successful");
+    }
+
+    #[test]
+    fn test_upstream_status_mapping() {
+        println!("🧪 Test: Upstream status mapping");
+
+        let cases = [
+            (401, "invalid_request_error", Some("invalid_api_key")),
+            (403, "invalid_request_error", Some("permission_denied")),
+            (404, "invalid_request_error", Some("model_not_found")),
+            (429, "requests", Some("rate_limit_exceeded")),
+            (500, "server_error", None),
+            (503, "server_error", None),
+        ];
+        for (status, error_type, code) in cases {
+            let error = ApiError::from_error(&upstream_failure(status, ""));
+            assert_eq!(error.status.as_u16(), status);
+            assert_eq!(error.error_type, error_type, "type for {}", status);
+            assert_eq!(error.code, code, "code for {}", status);
+        }
+        assert_eq!(
+            ApiError::from_error(&upstream_failure(404, ""))
+                .param
+                .as_deref(),
+            Some("model")
+        );
+
+        // A usage cap is a quota problem, not a transient rate limit
+        let capped = upstream_failure(
+            429,
+            r#"{"detail": {"code": "usage_limit_reached", "message": "Limit reached"}}"#,
+        );
+        let error = ApiError::from_error(&capped);
+        assert_eq!(error.error_type, "insufficient_quota");
+        assert_eq!(error.code, Some("insufficient_quota"));
+        assert_eq!(error.message, "Limit reached");
+
+        println!("✅ Upstream status mapping successful");
+    }
+
+    #[test]
+    fn test_errors_without_upstream_status() {
+        println!("🧪 Test: Errors without an upstream status");
+
+        let unreachable = MindLinkError::Network {
+            message: "ChatGPT API request failed".to_string(),
+            url: None,
+            source: None,
+        };
+        assert!(upstream_status(&unreachable).is_none());
+        let error = ApiError::from_error(&unreachable);
+        assert_eq!(error.status, StatusCode::BAD_GATEWAY);
+        assert_eq!(error.error_type, "server_error");
+
+        // The backend's own explanation is passed on
+        let failure = upstream_failure(429, r#"{"detail": "Slow down"}"#);
+        assert_eq!(upstream_status(&failure).map(|u| u.status), Some(429));
+        assert_eq!(ApiError::from_error(&failure).message, "Slow down");
+
+        println!("✅ Errors without an upstream status successful");
+    }
+}
@@ -1,8 +1,21 @@
 #[cfg(test)]
 mod auth_manager_tests {
-    use crate::managers::auth_manager::AuthManager;
+    use crate::managers::auth_manager::{
+        await_callback, bind_callback_listener, callback_path, rejected_refresh_token,
+        revoke_token, AuthEvent, AuthManager, AuthProvider, AuthTokens, UserIdentity,
+    };
+    use crate::managers::config_manager::AuthConfig;
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+    use chrono::{Duration, TimeZone, Utc};
+    use serde_json::json;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::Instant;
     use tempfile::TempDir;
     use tokio::fs;
+    use tokio::sync::RwLock;
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
     /// Helper to create a test directory with proper auth structure
     async fn create_test_auth_dir() -> TempDir {
@@ -85,7 +98,7 @@ mod auth_manager_tests {
             .expect("Failed to create auth manager");
 
         // Without authentication, ensure_valid_tokens should handle gracefully
-        let result = auth_manager.ensure_valid_tokens().await;
+        let result = auth_manager.ensure_valid_tokens(Instant::now()).await;
         // This might fail or succeed depending on implementation, but should not panic
         assert!(
             result.is_ok() || result.is_err(),
@@ -285,7 +298,7 @@ mod auth_manager_tests {
         }
 
         // Test ensure valid tokens without network
-        let ensure_result = auth_manager.ensure_valid_tokens().await;
+        let ensure_result = auth_manager.ensure_valid_tokens(Instant::now()).await;
         match ensure_result {
             Ok(_) => println!("   Ensure valid tokens succeeded"),
             Err(e) => {
@@ -369,4 +382,449 @@ mod auth_manager_tests {
 
         println!("✅ Concurrent token operations test successful");
     }
+
+    async fn write_tokens(path: &Path, account_id: &str) {
+        let tokens = AuthTokens {
+            access_token: format!("access-{}", account_id),
+            refresh_token: format!("refresh-{}", account_id),
+            id_token: String::new(),
+            expires_at: Utc::now() + Duration::days(1),
+            token_type: "Bearer".to_string(),
+            account_id: account_id.to_string(),
+        };
+        fs::create_dir_all(path.parent().unwrap()).await.unwrap();
+        fs::write(path, serde_json::to_string(&tokens).unwrap())
+            .await
+            .unwrap();
+    }
+
+    async fn ids(manager: &AuthManager) -> Vec<(String, bool)> {
+        manager
+            .accounts()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|account| (account.account_id, account.active))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_switching_between_accounts() {
+        println!("🧪 Test: Switching between ChatGPT accounts");
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let auth_path = temp_dir.path().join("auth.json");
+        let accounts_dir = temp_dir.path().join("chatgpt_accounts");
+        write_tokens(&auth_path, "acct-a").await;
+        write_tokens(&accounts_dir.join("acct-b.json"), "acct-b").await;
+
+        let mut auth_manager = AuthManager::open(auth_path.clone())
+            .await
+            .unwrap()
+            .with_saved_accounts(accounts_dir.clone());
+        assert_eq!(
+            ids(&auth_manager).await,
+            vec![("acct-a".to_string(), true), ("acct-b".to_string(), false)]
+        );
+
+        auth_manager.select_account("acct-b").await.unwrap();
+        assert_eq!(auth_manager.get_access_token(), Some("access-acct-b"));
+        assert_eq!(
+            ids(&auth_manager).await,
+            vec![("acct-b".to_string(), true), ("acct-a".to_string(), false)]
+        );
+        // Each account's tokens are kept in one place only
+        let reopened = AuthManager::open(auth_path.clone()).await.unwrap();
+        assert_eq!(reopened.get_access_token(), Some("access-acct-b"));
+        assert!(accounts_dir.join("acct-a.json").exists());
+        assert!(!accounts_dir.join("acct-b.json").exists());
+
+        assert!(auth_manager.select_account("acct-c").await.is_err());
+        assert!(auth_manager.select_account("../auth").await.is_err());
+        assert_eq!(auth_manager.get_access_token(), Some("access-acct-b"));
+
+        auth_manager.remove_account("acct-a").await.unwrap();
+        assert!(auth_manager.remove_account("acct-a").await.is_err());
+        assert_eq!(ids(&auth_manager).await, vec![("acct-b".to_string(), true)]);
+
+        // Removing the active account signs out
+        auth_manager.remove_account("acct-b").await.unwrap();
+        assert!(!auth_manager.is_authenticated().await);
+        assert!(ids(&auth_manager).await.is_empty());
+
+        // Logins of the load-balancing pool keep no other accounts
+        let pooled = AuthManager::open(temp_dir.path().join("pooled.json"))
+            .await
+            .unwrap();
+        assert!(pooled.accounts().await.unwrap().is_empty());
+
+        println!("✅ Switching between ChatGPT accounts successful");
+    }
+
+    /// Unsigned JWT with `claims`, shaped like an OpenAI ID token
+    fn id_token(claims: serde_json::Value) -> String {
+        let encode = |value: serde_json::Value| URL_SAFE_NO_PAD.encode(value.to_string());
+        format!(
+            "{}.{}.signature",
+            encode(json!({ "alg": "RS256", "typ": "JWT" })),
+            encode(claims)
+        )
+    }
+
+    #[tokio::test]
+    async fn test_identity_from_id_token() {
+        println!("🧪 Test: User identity from the ID token");
+
+        let mut tokens = AuthTokens {
+            access_token: "access".to_string(),
+            refresh_token: "refresh".to_string(),
+            id_token: id_token(json!({
+                "email": "ada@example.com",
+                "exp": (Utc::now() + Duration::hours(1)).timestamp(),
+                "https://api.openai.com/auth": {
+                    "chatgpt_account_id": "acct-123",
+                    "chatgpt_plan_type": "pro",
+                    "chatgpt_subscription_active_until": "2030-01-01T00:00:00+00:00"
+                }
+            })),
+            expires_at: Utc::now() + Duration::days(1),
+            token_type: "Bearer".to_string(),
+            account_id: "acct-123".to_string(),
+        };
+        assert_eq!(
+            UserIdentity::from_tokens(&tokens),
+            UserIdentity {
+                email: Some("ada@example.com".to_string()),
+                plan: Some("pro".to_string()),
+                account_id: "acct-123".to_string(),
+                subscription_until: Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).single(),
+            }
+        );
+
+        // A token that cannot be decoded still names the account
+        tokens.id_token = "not-a-jwt".to_string();
+        assert_eq!(
+            UserIdentity::from_tokens(&tokens),
+            UserIdentity {
+                email: None,
+                plan: None,
+                account_id: "acct-123".to_string(),
+                subscription_until: None,
+            }
+        );
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let auth_path = temp_dir.path().join("auth.json");
+        let auth_manager = AuthManager::open(auth_path.clone()).await.unwrap();
+        assert_eq!(auth_manager.get_auth_status().await, (false, None));
+
+        write_tokens(&auth_path, "acct-a").await;
+        let auth_manager = AuthManager::open(auth_path).await.unwrap();
+        let (is_authenticated, user) = auth_manager.get_auth_status().await;
+        assert!(is_authenticated);
+        assert_eq!(user.unwrap().account_id, "acct-a");
+
+        println!("✅ User identity from the ID token successful");
+    }
+
+    #[tokio::test]
+    async fn test_callback_port_fallback() {
+        println!("🧪 Test: OAuth callback falls back to a free port");
+
+        let taken = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let taken_port = taken.local_addr().unwrap().port();
+
+        let listener = bind_callback_listener(taken_port).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        assert_ne!(port, taken_port);
+        assert_ne!(port, 0);
+
+        // A free port is used as configured
+        drop(listener);
+        let listener = bind_callback_listener(port).await.unwrap();
+        assert_eq!(listener.local_addr().unwrap().port(), port);
+
+        println!("✅ OAuth callback falls back to a free port successful");
+    }
+
+    #[tokio::test]
+    async fn test_callback_server() {
+        println!("🧪 Test: OAuth callback server");
+
+        assert_eq!(callback_path(None), "/auth/callback");
+        assert_eq!(callback_path(Some("abc")), "/auth/callback/abc");
+
+        let listener = bind_callback_listener(0).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let login = tokio::spawn(async move {
+            await_callback(
+                listener,
+                "/auth/callback/abc",
+                "expected-state",
+                std::time::Duration::from_secs(10),
+            )
+            .await
+        });
+
+        let client = reqwest::Client::builder().no_proxy().build().unwrap();
+        let callback = |path: &str, state: &str| {
+            let url = format!(
+                "http://127.0.0.1:{}{}?code=auth-code&state={}",
+                port, path, state
+            );
+            let request = client.get(url);
+            async move { request.send().await.unwrap() }
+        };
+
+        // Without the path token the callback is not found
+        let response = callback("/auth/callback", "expected-state").await;
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+
+        // A mismatched state gets a page saying so and the login keeps waiting
+        let page = callback("/auth/callback/abc", "other-state").await;
+        assert!(page.text().await.unwrap().contains("Not Recognized"));
+        assert!(!login.is_finished());
+
+        let page = callback("/auth/callback/abc", "expected-state").await;
+        assert!(page.text().await.unwrap().contains("Successful"));
+        assert_eq!(login.await.unwrap().unwrap(), "auth-code");
+
+        // The listener is gone once the login is over
+        let connect = tokio::net::TcpStream::connect(("127.0.0.1", port)).await;
+        assert!(connect.is_err());
+
+        println!("✅ OAuth callback server successful");
+    }
+
+    #[tokio::test]
+    async fn test_callback_timeout_closes_listener() {
+        println!("🧪 Test: OAuth callback server closes on timeout");
+
+        let listener = bind_callback_listener(0).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let error = await_callback(
+            listener,
+            "/auth/callback",
+            "state",
+            std::time::Duration::from_millis(200),
+        )
+        .await
+        .unwrap_err();
+        assert!(error.to_string().contains("timed out"));
+
+        // The port is free for the next login
+        let listener = bind_callback_listener(port).await.unwrap();
+        assert_eq!(listener.local_addr().unwrap().port(), port);
+
+        println!("✅ OAuth callback server closes on timeout successful");
+    }
+
+    #[tokio::test]
+    async fn test_import_codex_login() {
+        println!("🧪 Test: Importing the Codex CLI login");
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let expires_at = Utc::now() + Duration::hours(1);
+        let access_token = id_token(json!({ "exp": expires_at.timestamp() }));
+        let codex_path = temp_dir.path().join("codex-auth.json");
+        fs::write(
+            &codex_path,
+            json!({
+                "OPENAI_API_KEY": null,
+                "tokens": {
+                    "id_token": id_token(json!({
+                        "exp": expires_at.timestamp(),
+                        "https://api.openai.com/auth": { "chatgpt_account_id": "acct-codex" }
+                    })),
+                    "access_token": access_token,
+                    "refresh_token": "codex-refresh"
+                },
+                "last_refresh": Utc::now()
+            })
+            .to_string(),
+        )
+        .await
+        .unwrap();
+
+        let auth_path = temp_dir.path().join("auth.json");
+        let mut auth_manager = AuthManager::open(auth_path.clone()).await.unwrap();
+        let mut events = auth_manager.subscribe_events();
+        auth_manager.import_codex_login(&codex_path).await.unwrap();
+        assert!(auth_manager.is_authenticated().await);
+        assert_eq!(
+            events.try_recv().unwrap(),
+            AuthEvent::TokensSaved {
+                account_id: "acct-codex".to_string()
+            }
+        );
+
+        let tokens = auth_manager.get_tokens().unwrap();
+        assert_eq!(tokens.access_token, access_token);
+        assert_eq!(tokens.refresh_token, "codex-refresh");
+        assert_eq!(tokens.account_id, "acct-codex");
+        assert_eq!(tokens.expires_at.timestamp(), expires_at.timestamp());
+
+        // The imported login is MindLink's own from now on
+        let reopened = AuthManager::open(auth_path).await.unwrap();
+        assert_eq!(reopened.get_tokens().unwrap().account_id, "acct-codex");
+
+        // A Codex CLI using an API key has no ChatGPT login to import
+        let api_key_path = temp_dir.path().join("api-key.json");
+        fs::write(&api_key_path, r#"{"OPENAI_API_KEY": "sk-test"}"#)
+            .await
+            .unwrap();
+        assert!(auth_manager
+            .import_codex_login(&api_key_path)
+            .await
+            .is_err());
+
+        println!("✅ Importing the Codex CLI login successful");
+    }
+
+    #[tokio::test]
+    async fn test_refresh_failure_event() {
+        println!("🧪 Test: Failed refreshes are reported");
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let mut auth_manager = AuthManager::open(temp_dir.path().join("auth.json"))
+            .await
+            .unwrap();
+        let mut events = auth_manager.subscribe_events();
+
+        assert!(auth_manager.refresh_tokens().await.is_err());
+        assert!(matches!(
+            events.try_recv().unwrap(),
+            AuthEvent::RefreshFailed { reason } if reason.contains("No tokens")
+        ));
+
+        println!("✅ Failed refreshes are reported successful");
+    }
+
+    #[tokio::test]
+    async fn test_single_flight_refresh() {
+        println!("🧪 Test: Concurrent refreshes share one attempt");
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let auth_manager = AuthManager::open(temp_dir.path().join("auth.json"))
+            .await
+            .unwrap();
+        let mut events = auth_manager.subscribe_events();
+        let auth_manager = Arc::new(RwLock::new(auth_manager));
+
+        // Both callers arrive before either gets the lock
+        let since = Instant::now();
+        let refresh = || {
+            let auth_manager = auth_manager.clone();
+            tokio::spawn(async move { auth_manager.write().await.refresh_once(since).await })
+        };
+        let (first, second) = tokio::join!(refresh(), refresh());
+        assert!(first.unwrap().is_err());
+        let shared = second.unwrap().unwrap_err();
+        assert!(shared.to_string().contains("No tokens"));
+
+        // Only one refresh ran
+        assert!(matches!(
+            events.try_recv(),
+            Ok(AuthEvent::RefreshFailed { .. })
+        ));
+        assert!(events.try_recv().is_err());
+
+        // Callers arriving after it finished refresh again
+        let later = auth_manager
+            .write()
+            .await
+            .refresh_once(Instant::now())
+            .await;
+        assert!(later.is_err());
+        assert!(matches!(
+            events.try_recv(),
+            Ok(AuthEvent::RefreshFailed { .. })
+        ));
+
+        println!("✅ Concurrent refreshes share one attempt successful");
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_login_needs_client_id() {
+        println!("🧪 Test: Claude logins need a client ID");
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let config = AuthConfig {
+            client_id: String::new(),
+            redirect_port: 0,
+            ..AuthConfig::default()
+        };
+        let mut auth_manager =
+            AuthManager::open_anthropic(temp_dir.path().join("anthropic_auth.json"), config)
+                .await
+                .unwrap();
+        assert_eq!(auth_manager.provider(), AuthProvider::Anthropic);
+
+        let error = auth_manager.login().await.unwrap_err();
+        assert!(error.to_string().contains("client ID"));
+        assert!(error.to_string().contains("Claude"));
+
+        println!("✅ Claude logins need a client ID successful");
+    }
+
+    #[tokio::test]
+    async fn test_revoke_token() {
+        println!("🧪 Test: Tokens are revoked upstream");
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/oauth/revoke"))
+            .and(body_string_contains("token=refresh-token"))
+            .and(body_string_contains("token_type_hint=refresh_token"))
+            .and(body_string_contains("client_id=mindlink"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let url = format!("{}/oauth/revoke", server.uri());
+        let client = reqwest::Client::new();
+
+        revoke_token(&client, &url, "mindlink", "refresh-token", "refresh_token")
+            .await
+            .unwrap();
+        // Refusals are reported to the caller, which logs out anyway
+        assert!(
+            revoke_token(&client, &url, "mindlink", "other-token", "refresh_token")
+                .await
+                .is_err()
+        );
+
+        println!("✅ Tokens are revoked upstream successful");
+    }
+
+    #[test]
+    fn test_rejected_refresh_token() {
+        println!("🧪 Test: Rejected refresh tokens are recognized");
+
+        let oauth = json!({
+            "error": "invalid_grant",
+            "error_description": "Refresh token expired"
+        });
+        assert_eq!(
+            rejected_refresh_token(&oauth.to_string()).as_deref(),
+            Some("Refresh token expired")
+        );
+        assert_eq!(
+            rejected_refresh_token(r#"{"error": "invalid_grant"}"#).as_deref(),
+            Some("invalid_grant")
+        );
+        let openai = json!({
+            "error": {"code": "refresh_token_reused", "message": "Already used"}
+        });
+        assert_eq!(
+            rejected_refresh_token(&openai.to_string()).as_deref(),
+            Some("Already used")
+        );
+
+        // Other failures may go away on their own
+        assert_eq!(rejected_refresh_token(r#"{"error": "server_error"}"#), None);
+        assert_eq!(rejected_refresh_token("Bad Gateway"), None);
+
+        println!("✅ Rejected refresh tokens are recognized successful");
+    }
 }
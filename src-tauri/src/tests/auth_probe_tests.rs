@@ -0,0 +1,193 @@
+#[cfg(test)]
+mod auth_probe_tests {
+    use crate::auth_probe::{
+        auth_health, cached_probe, probe, upstream_health, with_token_expiry, ProbeOutcome,
+        ProbeResult,
+    };
+    use crate::health::HealthLevel;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_outcome_for_status() {
+        println!("🧪 Test: Probe status mapping");
+
+        assert_eq!(ProbeOutcome::for_status(200), ProbeOutcome::Valid);
+        assert_eq!(
+            ProbeOutcome::for_status(401),
+            ProbeOutcome::Rejected { status: 401 }
+        );
+        assert_eq!(
+            ProbeOutcome::for_status(403),
+            ProbeOutcome::Rejected { status: 403 }
+        );
+        assert!(matches!(
+            ProbeOutcome::for_status(503),
+            ProbeOutcome::Unreachable { .. }
+        ));
+
+        println!("✅ Probe status mapping successful");
+    }
+
+    #[test]
+    fn test_auth_health_levels() {
+        println!("🧪 Test: Auth health from token state and probe");
+
+        assert_eq!(auth_health(false, false, None).level, HealthLevel::Down);
+        assert_eq!(auth_health(true, false, None).level, HealthLevel::Down);
+        assert_eq!(auth_health(true, true, None).level, HealthLevel::Ok);
+        assert_eq!(
+            auth_health(true, true, Some(&ProbeOutcome::Valid)).level,
+            HealthLevel::Ok
+        );
+
+        let rejected = auth_health(true, true, Some(&ProbeOutcome::Rejected { status: 401 }));
+        assert_eq!(rejected.level, HealthLevel::Down);
+        assert!(rejected.critical);
+
+        let unreachable = auth_health(
+            true,
+            true,
+            Some(&ProbeOutcome::Unreachable {
+                reason: "timed out".to_string(),
+            }),
+        );
+        assert_eq!(unreachable.level, HealthLevel::Degraded);
+        assert!(unreachable.reason.unwrap().contains("timed out"));
+
+        // An expired token stays down whatever the last probe said
+        assert_eq!(
+            auth_health(true, false, Some(&ProbeOutcome::Valid)).level,
+            HealthLevel::Down
+        );
+
+        println!("✅ Auth health from token state and probe successful");
+    }
+
+    #[test]
+    fn test_expiry_and_upstream_details() {
+        println!("🧪 Test: Token expiry and upstream reachability");
+
+        let now = chrono::Utc::now();
+        let expires_at = now + chrono::Duration::minutes(10);
+        let auth = with_token_expiry(auth_health(true, true, None), expires_at, now);
+        assert_eq!(auth.details["expires_in_secs"], serde_json::json!(600));
+        assert_eq!(
+            auth.details["expires_at"],
+            serde_json::to_value(expires_at).unwrap()
+        );
+
+        // A rejected session still means ChatGPT answered
+        let rejected = upstream_health(Some(&ProbeOutcome::Rejected { status: 401 }));
+        assert_eq!(rejected.level, HealthLevel::Ok);
+
+        let unreachable = upstream_health(Some(&ProbeOutcome::Unreachable {
+            reason: "dns error".to_string(),
+        }));
+        assert_eq!(unreachable.name, "upstream");
+        assert_eq!(unreachable.level, HealthLevel::Down);
+        assert!(!unreachable.critical);
+        assert!(unreachable.reason.unwrap().contains("dns error"));
+
+        let unprobed = upstream_health(None);
+        assert_eq!(unprobed.level, HealthLevel::Ok);
+        assert!(unprobed.details.contains_key("probe_url"));
+
+        println!("✅ Token expiry and upstream reachability successful");
+    }
+
+    #[test]
+    fn test_result_applies_to_probed_token_only() {
+        println!("🧪 Test: Probe result is tied to its token");
+
+        let result = ProbeResult::new(ProbeOutcome::Rejected { status: 401 }, "old-token");
+        assert!(result.applies_to("old-token"));
+        assert!(!result.applies_to("new-token"));
+
+        println!("✅ Probe result is tied to its token successful");
+    }
+
+    #[tokio::test]
+    async fn test_probe_against_backend() {
+        println!("🧪 Test: Probe against a mock backend");
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/me"))
+            .and(header("authorization", "Bearer good"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/me"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let url = format!("{}/me", server.uri());
+
+        let valid = probe(&client, &url, "good").await;
+        assert_eq!(valid.outcome, ProbeOutcome::Valid);
+        assert!(valid.applies_to("good"));
+
+        let rejected = probe(&client, &url, "revoked").await;
+        assert_eq!(rejected.outcome, ProbeOutcome::Rejected { status: 401 });
+
+        let unreachable = probe(&client, "http://127.0.0.1:1/me", "good").await;
+        assert!(matches!(
+            unreachable.outcome,
+            ProbeOutcome::Unreachable { .. }
+        ));
+
+        println!("✅ Probe against a mock backend successful");
+    }
+
+    #[tokio::test]
+    async fn test_cached_probe() {
+        println!("🧪 Test: Status queries reuse recent probe results");
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/me"))
+            .and(header("authorization", "Bearer revoked"))
+            .respond_with(ResponseTemplate::new(401))
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/me"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let url = format!("{}/me", server.uri());
+        let latest = tokio::sync::RwLock::new(None);
+        let max_age = std::time::Duration::from_secs(300);
+
+        let rejected = ProbeOutcome::Rejected { status: 401 };
+        assert_eq!(
+            cached_probe(&latest, &client, &url, "revoked", max_age).await,
+            rejected
+        );
+        // Answered from the latest result
+        assert_eq!(
+            cached_probe(&latest, &client, &url, "revoked", max_age).await,
+            rejected
+        );
+        // Another token is probed, and so is a result that is too old
+        assert_eq!(
+            cached_probe(&latest, &client, &url, "good", max_age).await,
+            ProbeOutcome::Valid
+        );
+        assert!(latest.read().await.as_ref().unwrap().applies_to("good"));
+        assert_eq!(
+            cached_probe(&latest, &client, &url, "revoked", std::time::Duration::ZERO).await,
+            rejected
+        );
+
+        println!("✅ Status queries reuse recent probe results successful");
+    }
+}
@@ -0,0 +1,140 @@
+#[cfg(test)]
+mod authorized_apps_tests {
+    use crate::authorized_apps::{bearer_token, find_app, generate_api_key, AuthorizedApp};
+    use crate::commands::{encrypt_app_keys, read_settings, write_settings, Settings};
+    use crate::secret_box;
+    use axum::http::{header::AUTHORIZATION, HeaderMap, HeaderValue};
+
+    fn app(name: &str, model: &str, allowed_models: &[&str]) -> AuthorizedApp {
+        AuthorizedApp {
+            id: format!("{}-id", name),
+            name: name.to_string(),
+            model: model.to_string(),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            api_key: generate_api_key(),
+            allowed_models: allowed_models.iter().map(|m| m.to_string()).collect(),
+            system_prompt: None,
+            daily_token_budget: None,
+            monthly_token_budget: None,
+        }
+    }
+
+    #[test]
+    fn test_pinned_app_overrides_model() {
+        println!("🧪 Test: App pinned to a single model");
+
+        let editor = app("editor", "gpt-4o", &[]);
+        assert_eq!(editor.model_for("gpt-4o"), Ok("gpt-4o".to_string()));
+        assert_eq!(editor.model_for("gpt-5"), Ok("gpt-4o".to_string()));
+
+        println!("✅ Pinned app model override successful");
+    }
+
+    #[test]
+    fn test_restricted_app_rejects_other_models() {
+        println!("🧪 Test: App with an allow list");
+
+        let agent = app("agent", "gpt-4o", &["o3", "codex-mini"]);
+        assert_eq!(agent.model_for("o3"), Ok("o3".to_string()));
+        assert_eq!(agent.model_for("gpt-4o"), Ok("gpt-4o".to_string()));
+
+        let error = agent.model_for("gpt-5").expect_err("gpt-5 is not allowed");
+        assert!(error.contains("gpt-5"));
+        assert!(error.contains("agent"));
+
+        println!("✅ App allow list enforcement successful");
+    }
+
+    #[test]
+    fn test_keys_identify_apps() {
+        println!("🧪 Test: Matching API keys to apps");
+
+        let editor = app("editor", "gpt-4o", &[]);
+        let mut legacy = app("legacy", "gpt-4", &[]);
+        legacy.api_key.clear();
+        let apps = vec![legacy, editor.clone()];
+
+        assert!(editor.api_key.starts_with("sk-mindlink-"));
+        assert_ne!(editor.api_key, generate_api_key());
+        assert_eq!(
+            find_app(&apps, &editor.api_key).map(|a| a.name.as_str()),
+            Some("editor")
+        );
+        // Apps without a key never match, not even an empty one
+        assert!(find_app(&apps, "").is_none());
+        assert!(find_app(&apps, "sk-unknown").is_none());
+
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", editor.api_key)).unwrap(),
+        );
+        assert_eq!(bearer_token(&headers), Some(editor.api_key.as_str()));
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer  "));
+        assert_eq!(bearer_token(&headers), None);
+
+        // Azure OpenAI clients send the key on its own
+        let mut headers = HeaderMap::new();
+        headers.insert("api-key", HeaderValue::from_str(&editor.api_key).unwrap());
+        assert_eq!(bearer_token(&headers), Some(editor.api_key.as_str()));
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer sk-other"));
+        assert_eq!(bearer_token(&headers), Some("sk-other"));
+
+        println!("✅ API key matching successful");
+    }
+
+    #[tokio::test]
+    async fn test_keys_encrypted_in_settings() {
+        println!("🧪 Test: App keys encrypted in the settings file");
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("settings.json");
+        let editor = app("editor", "gpt-4o", &[]);
+        let encrypts = secret_box::master_key().await.is_some();
+
+        // Keys saved in plaintext by earlier versions, next to a key set
+        // through `update_setting`
+        let mut legacy = serde_json::to_value(Settings {
+            default_model: None,
+            authorized_apps: vec![editor.clone()],
+            extra: serde_json::Map::new(),
+        })
+        .unwrap();
+        legacy["theme"] = serde_json::json!("dark");
+        std::fs::write(&path, legacy.to_string()).unwrap();
+
+        // Reading leaves the file alone
+        let settings = read_settings(&path).await.unwrap().unwrap();
+        assert_eq!(settings.authorized_apps[0].api_key, editor.api_key);
+        assert_eq!(settings.extra["theme"], "dark");
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), legacy.to_string());
+
+        assert_eq!(encrypt_app_keys(&path).await.unwrap(), encrypts);
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content.contains(&editor.api_key), !encrypts);
+        assert!(content.contains("\"theme\": \"dark\""));
+        // Nothing left to migrate
+        assert!(!encrypt_app_keys(&path).await.unwrap());
+
+        write_settings(&path, &settings).await.unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content.contains(&editor.api_key), !encrypts);
+        let settings = read_settings(&path).await.unwrap().unwrap();
+        assert_eq!(settings.authorized_apps[0].api_key, editor.api_key);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        assert!(read_settings(&dir.path().join("missing.json"))
+            .await
+            .unwrap()
+            .is_none());
+
+        println!("✅ App keys encrypted in the settings file successful");
+    }
+}
@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod azure_tests {
+    use crate::middleware::azure::{deployment_as_model, DEPLOYMENT_COMPLETIONS_PATH};
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+        response::Json,
+        routing::post,
+        Router,
+    };
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    fn router() -> Router {
+        Router::new().route(
+            DEPLOYMENT_COMPLETIONS_PATH,
+            post(|Json(body): Json<Value>| async move { Json(body) })
+                .layer(axum::middleware::from_fn(deployment_as_model)),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_deployment_becomes_the_model() {
+        println!("🧪 Test: Azure OpenAI deployment routes");
+
+        let request = Request::post(
+            "/openai/deployments/gpt-4o-prod/chat/completions?api-version=2024-10-21",
+        )
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("api-key", "sk-mindlink-test")
+        .body(Body::from(
+            r#"{"messages":[{"role":"user","content":"Hi"}],"model":"ignored"}"#,
+        ))
+        .unwrap();
+        let response = router().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&body).unwrap(),
+            json!({
+                "model": "gpt-4o-prod",
+                "messages": [{ "role": "user", "content": "Hi" }]
+            })
+        );
+
+        // Malformed bodies are left to the handler
+        let request = Request::post("/openai/deployments/gpt-4o-prod/chat/completions")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from("{"))
+            .unwrap();
+        let response = router().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        println!("✅ Azure OpenAI deployment routes successful");
+    }
+}
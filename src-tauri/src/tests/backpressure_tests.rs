@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod backpressure_tests {
+    use crate::accounts::AccountPool;
+    use crate::managers::config_manager::AccountsConfig;
+    use crate::middleware::backpressure::{
+        apply_backpressure, Backpressure, OverloadEvent, OverloadReason,
+    };
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+        routing::post,
+        Router,
+    };
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::broadcast;
+    use tower::ServiceExt;
+
+    fn backpressure(
+        max_in_flight: usize,
+    ) -> (Arc<Backpressure>, broadcast::Receiver<OverloadEvent>) {
+        let (events, overloads) = broadcast::channel(16);
+        let accounts = Arc::new(AccountPool::new(AccountsConfig::default()));
+        (
+            Arc::new(Backpressure::new(max_in_flight, accounts, events)),
+            overloads,
+        )
+    }
+
+    fn completion() -> Request<Body> {
+        Request::post("/v1/chat/completions")
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_full_server_answers_429_with_retry_after() {
+        println!("🧪 Test: Backpressure when the server is full");
+
+        let (backpressure, mut overloads) = backpressure(1);
+        let router = Router::new()
+            .route("/v1/chat/completions", post(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(
+                backpressure.clone(),
+                apply_backpressure,
+            ));
+
+        // The slot is held until the first response body is gone
+        let first = router.clone().oneshot(completion()).await.unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(backpressure.in_flight(), 1);
+
+        let rejected = router.clone().oneshot(completion()).await.unwrap();
+        assert_eq!(rejected.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(rejected.headers()[header::RETRY_AFTER], "1");
+        let body = axum::body::to_bytes(rejected.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], "rate_limit_exceeded");
+
+        let overload = overloads.try_recv().unwrap();
+        assert_eq!(overload.reason, OverloadReason::QueueFull);
+        assert_eq!(overload.max_in_flight, 1);
+
+        drop(first);
+        assert_eq!(backpressure.in_flight(), 0);
+        let admitted = router.oneshot(completion()).await.unwrap();
+        assert_eq!(admitted.status(), StatusCode::OK);
+
+        println!("✅ Backpressure when the server is full successful");
+    }
+
+    #[test]
+    fn test_retry_after_follows_request_durations() {
+        println!("🧪 Test: Retry-After from request durations");
+
+        let (backpressure, mut overloads) = backpressure(4);
+        assert_eq!(backpressure.queue_retry_after(), 1);
+
+        backpressure.record_duration(Duration::from_secs(10));
+        assert_eq!(backpressure.queue_retry_after(), 10);
+        backpressure.record_duration(Duration::from_secs(20));
+        assert_eq!(backpressure.queue_retry_after(), 12);
+
+        // Overload events are throttled
+        backpressure.publish(OverloadReason::UpstreamRateLimited, 30);
+        backpressure.publish(OverloadReason::QueueFull, 12);
+        assert_eq!(
+            overloads.try_recv().unwrap().reason,
+            OverloadReason::UpstreamRateLimited
+        );
+        assert!(overloads.try_recv().is_err());
+
+        println!("✅ Retry-After from request durations successful");
+    }
+}
@@ -0,0 +1,214 @@
+#[cfg(test)]
+mod batches_tests {
+    use crate::batches::{
+        is_retryable, parse_input, retry_delay, run_batch, BatchStatus, BatchStore,
+        CreateBatchRequest, BATCH_PURPOSE, CHAT_COMPLETIONS_ENDPOINT, COMPLETION_WINDOW,
+    };
+    use crate::managers::config_manager::BatchConfig;
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn request_line(custom_id: &str) -> String {
+        json!({
+            "custom_id": custom_id,
+            "method": "POST",
+            "url": CHAT_COMPLETIONS_ENDPOINT,
+            "body": {"model": "gpt-4o", "messages": [{"role": "user", "content": custom_id}]},
+        })
+        .to_string()
+    }
+
+    fn create_request(input_file_id: &str) -> CreateBatchRequest {
+        CreateBatchRequest {
+            input_file_id: input_file_id.to_string(),
+            endpoint: CHAT_COMPLETIONS_ENDPOINT.to_string(),
+            completion_window: COMPLETION_WINDOW.to_string(),
+            metadata: None,
+        }
+    }
+
+    fn fast_config() -> BatchConfig {
+        BatchConfig {
+            enabled: true,
+            min_interval_ms: 0,
+            max_retries: 2,
+        }
+    }
+
+    #[test]
+    fn test_parse_input_reports_every_problem() {
+        println!("🧪 Test: Batch input validation");
+
+        let valid = format!("{}\n\n{}\n", request_line("a"), request_line("b"));
+        let requests = parse_input(&valid, CHAT_COMPLETIONS_ENDPOINT).expect("valid input");
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].custom_id, "b");
+
+        let wrong_url = request_line("c").replace(CHAT_COMPLETIONS_ENDPOINT, "/v1/embeddings");
+        let invalid = [
+            request_line("a"),
+            "not json".to_string(),
+            request_line("a"),
+            wrong_url,
+        ]
+        .join("\n");
+        let errors = parse_input(&invalid, CHAT_COMPLETIONS_ENDPOINT).expect_err("invalid input");
+        let codes: Vec<(&str, Option<usize>)> = errors
+            .iter()
+            .map(|error| (error.code.as_str(), error.line))
+            .collect();
+        assert_eq!(
+            codes,
+            vec![
+                ("invalid_json_line", Some(2)),
+                ("duplicate_custom_id", Some(3)),
+                ("mismatched_endpoint", Some(4)),
+            ]
+        );
+
+        let errors = parse_input("\n", CHAT_COMPLETIONS_ENDPOINT).expect_err("empty input");
+        assert_eq!(errors[0].code, "empty_file");
+
+        println!("✅ Batch input validation successful");
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        println!("🧪 Test: Batch retry policy");
+
+        assert!(is_retryable(429));
+        assert!(is_retryable(502));
+        assert!(!is_retryable(400));
+        assert!(!is_retryable(200));
+
+        assert_eq!(retry_delay(0), Duration::from_secs(1));
+        assert_eq!(retry_delay(3), Duration::from_secs(8));
+        assert_eq!(retry_delay(10), Duration::from_secs(60));
+
+        println!("✅ Batch retry policy successful");
+    }
+
+    #[tokio::test]
+    async fn test_batch_runs_and_persists() {
+        println!("🧪 Test: Batch execution and persistence");
+
+        let dir = TempDir::new().expect("temp dir");
+        let store = Arc::new(BatchStore::open(dir.path()).await.expect("store"));
+        let input = format!("{}\n{}\n", request_line("ok"), request_line("bad"));
+        let file = store
+            .create_file("input.jsonl", BATCH_PURPOSE, input.as_bytes())
+            .await
+            .expect("file");
+
+        let batch = store
+            .create_batch(create_request(&file.id), None)
+            .await
+            .expect("batch");
+        assert_eq!(batch.status, BatchStatus::Validating);
+        assert_eq!(batch.request_counts.total, 2);
+        assert_eq!(store.unfinished().await, vec![batch.id.clone()]);
+
+        // The first request is rate limited once, the second is rejected
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        run_batch(
+            store.clone(),
+            batch.id.clone(),
+            fast_config(),
+            move |body: Value| {
+                let call = counter.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    let content = body["messages"][0]["content"].as_str().unwrap_or_default();
+                    match (call, content) {
+                        (0, _) => (429, json!({"error": {"message": "slow down"}})),
+                        (_, "ok") => (200, json!({"id": "chatcmpl-1"})),
+                        _ => (400, json!({"error": {"message": "bad request"}})),
+                    }
+                }
+            },
+        )
+        .await
+        .expect("run");
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        let batch = store.batch(&batch.id).await.expect("batch");
+        assert_eq!(batch.status, BatchStatus::Completed);
+        assert_eq!(batch.request_counts.completed, 1);
+        assert_eq!(batch.request_counts.failed, 1);
+
+        let output = store
+            .file_content(batch.output_file_id.as_deref().expect("output file"))
+            .await
+            .expect("read")
+            .expect("content");
+        let line: Value =
+            serde_json::from_str(String::from_utf8_lossy(&output).trim()).expect("json line");
+        assert_eq!(line["custom_id"], "ok");
+        assert_eq!(line["response"]["status_code"], 200);
+        assert!(batch.error_file_id.is_some());
+
+        // Everything survives reopening the store
+        let reopened = BatchStore::open(dir.path()).await.expect("reopen");
+        assert_eq!(reopened.batch(&batch.id).await, Some(batch.clone()));
+        assert!(reopened.unfinished().await.is_empty());
+        assert_eq!(reopened.list(None, 10).await.len(), 1);
+
+        println!("✅ Batch execution and persistence successful");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_and_cancelled_batches() {
+        println!("🧪 Test: Failed and cancelled batches");
+
+        let dir = TempDir::new().expect("temp dir");
+        let store = Arc::new(BatchStore::open(dir.path()).await.expect("store"));
+
+        let bad = store
+            .create_file("bad.jsonl", BATCH_PURPOSE, b"{}")
+            .await
+            .expect("file");
+        let batch = store
+            .create_batch(create_request(&bad.id), None)
+            .await
+            .expect("batch");
+        assert_eq!(batch.status, BatchStatus::Failed);
+        assert!(batch.errors.is_some());
+
+        let good = store
+            .create_file("good.jsonl", BATCH_PURPOSE, request_line("a").as_bytes())
+            .await
+            .expect("file");
+        let batch = store
+            .create_batch(create_request(&good.id), None)
+            .await
+            .expect("batch");
+        let cancelled = store
+            .cancel(&batch.id)
+            .await
+            .expect("cancel")
+            .expect("batch");
+        assert_eq!(cancelled.status, BatchStatus::Cancelled);
+
+        // A cancelled batch never runs
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        run_batch(store.clone(), batch.id.clone(), fast_config(), move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async { (200, Value::Null) }
+        })
+        .await
+        .expect("run");
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        assert_eq!(store.list(None, 10).await.len(), 2);
+        assert!(store
+            .cancel("batch_missing")
+            .await
+            .expect("cancel")
+            .is_none());
+
+        println!("✅ Failed and cancelled batches successful");
+    }
+}
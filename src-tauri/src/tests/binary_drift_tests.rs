@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod binary_drift_tests {
+    use crate::binary_drift::{
+        check, find_in_path, sha256_file, BinaryManifest, BinaryRecord, Drift,
+    };
+    use std::collections::BTreeMap;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    fn install(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn manifest(binary: &str, path: &Path) -> BinaryManifest {
+        let mut manifest = BinaryManifest::default();
+        manifest.binaries.insert(
+            binary.to_string(),
+            BinaryRecord {
+                path: path.to_path_buf(),
+                sha256: sha256_file(path).unwrap(),
+                version: Some("cloudflared version 2024.1.0".to_string()),
+                recorded_at: chrono::Utc::now(),
+                verified: true,
+                release: Some("2024.1.0".to_string()),
+            },
+        );
+        manifest
+    }
+
+    #[tokio::test]
+    async fn test_modified_and_missing_binaries() {
+        println!("🧪 Test: Drift of recorded binaries");
+
+        let dir = TempDir::new().unwrap();
+        let path = install(dir.path(), "cloudflared", "release 1");
+        let manifest = manifest("cloudflared", &path);
+        assert!(check(&manifest, &BTreeMap::new()).await.is_empty());
+
+        std::fs::write(&path, "release 2").unwrap();
+        let findings = check(&manifest, &BTreeMap::new()).await;
+        assert_eq!(findings.len(), 1);
+        assert!(matches!(findings[0].drift, Drift::Modified { .. }));
+        assert!(findings[0]
+            .remediation
+            .contains(&path.display().to_string()));
+
+        std::fs::remove_file(&path).unwrap();
+        let findings = check(&manifest, &BTreeMap::new()).await;
+        assert_eq!(findings[0].drift, Drift::Missing);
+        assert!(findings[0].summary().contains("missing"));
+
+        println!("✅ Drift of recorded binaries successful");
+    }
+
+    #[tokio::test]
+    async fn test_shadowed_binary() {
+        println!("🧪 Test: Shadowed binary detection");
+
+        let managed = TempDir::new().unwrap();
+        let system = TempDir::new().unwrap();
+        let path = install(managed.path(), "cloudflared", "release 1");
+        let manifest = manifest("cloudflared", &path);
+
+        // An identical copy elsewhere is not drift
+        let copy = install(system.path(), "cloudflared", "release 1");
+        let resolved = BTreeMap::from([("cloudflared".to_string(), copy.clone())]);
+        assert!(check(&manifest, &resolved).await.is_empty());
+
+        std::fs::write(&copy, "packaged release").unwrap();
+        let findings = check(&manifest, &resolved).await;
+        assert_eq!(findings.len(), 1);
+        match &findings[0].drift {
+            Drift::Shadowed { path, .. } => assert_eq!(path, &copy),
+            other => panic!("expected shadowing, got {:?}", other),
+        }
+        assert!(findings[0].remediation.ends_with("update"));
+
+        println!("✅ Shadowed binary detection successful");
+    }
+
+    #[test]
+    fn test_path_lookup_and_manifest_round_trip() {
+        println!("🧪 Test: PATH lookup and binary manifest");
+
+        let first = TempDir::new().unwrap();
+        let second = TempDir::new().unwrap();
+        let name = format!("cloudflared{}", std::env::consts::EXE_SUFFIX);
+        let expected = install(second.path(), &name, "binary");
+        let search_path = std::env::join_paths([first.path(), second.path()]).unwrap();
+        assert_eq!(
+            find_in_path("cloudflared", &search_path),
+            Some(expected.clone())
+        );
+        assert!(find_in_path("bifrost-http", &search_path).is_none());
+
+        let manifest_path = first.path().join("binaries.json");
+        assert!(BinaryManifest::load(&manifest_path).binaries.is_empty());
+        manifest("cloudflared", &expected)
+            .save(&manifest_path)
+            .unwrap();
+        let loaded = BinaryManifest::load(&manifest_path);
+        assert_eq!(loaded.binaries["cloudflared"].path, expected);
+        assert!(loaded.binaries["cloudflared"].verified);
+
+        // Records written before downloads were verified count as unverified
+        let mut json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&manifest_path).unwrap()).unwrap();
+        json["binaries"]["cloudflared"]
+            .as_object_mut()
+            .unwrap()
+            .remove("verified");
+        std::fs::write(&manifest_path, json.to_string()).unwrap();
+        assert!(!BinaryManifest::load(&manifest_path).binaries["cloudflared"].verified);
+
+        println!("✅ PATH lookup and binary manifest successful");
+    }
+}
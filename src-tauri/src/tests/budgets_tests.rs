@@ -0,0 +1,221 @@
+#[cfg(test)]
+mod budgets_tests {
+    use crate::authorized_apps::{generate_api_key, AuthorizedApp};
+    use crate::budgets::{BudgetLedger, BudgetPeriod, StreamTally};
+    use crate::middleware::analytics::TokenUsage;
+    use crate::middleware::budget::{enforce_budget, TokenBudgets, REMAINING_HEADER};
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+        response::{IntoResponse, Response},
+        routing::post,
+        Router,
+    };
+    use chrono::{DateTime, TimeZone, Utc};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    fn app(daily: Option<u64>, monthly: Option<u64>) -> AuthorizedApp {
+        AuthorizedApp {
+            id: "app-id".to_string(),
+            name: "editor".to_string(),
+            model: "gpt-4o".to_string(),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            api_key: generate_api_key(),
+            allowed_models: Vec::new(),
+            system_prompt: None,
+            daily_token_budget: daily,
+            monthly_token_budget: monthly,
+        }
+    }
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 12, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_budgets_reset_per_day_and_month() {
+        println!("🧪 Test: Daily and monthly token budgets");
+
+        let ledger = BudgetLedger::default();
+        let app = app(Some(100), Some(250));
+
+        ledger.record(&app.id, 60, at(30, 10));
+        ledger.record(&app.id, 40, at(30, 23));
+        let budget = ledger.budget(&app, at(30, 23));
+        assert_eq!(budget.remaining(), Some(0));
+        let exhausted = budget.exhausted().expect("the daily budget is used up");
+        assert_eq!(exhausted.period, BudgetPeriod::Daily);
+        assert_eq!(exhausted.resets_at, at(31, 0));
+
+        // A new day, but the same month
+        ledger.record(&app.id, 150, at(31, 8));
+        let budget = ledger.budget(&app, at(31, 8));
+        assert_eq!(budget.budgets[0].used, 150);
+        assert_eq!(budget.budgets[1].used, 250);
+        let exhausted = budget.exhausted().expect("both budgets are used up");
+        assert_eq!(exhausted.period, BudgetPeriod::Monthly);
+        assert_eq!(
+            exhausted.resets_at,
+            Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+        );
+
+        let next_year = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 1).unwrap();
+        assert_eq!(ledger.budget(&app, next_year).remaining(), Some(100));
+
+        println!("✅ Token budget periods successful");
+    }
+
+    #[test]
+    fn test_consumption_survives_a_restart() {
+        println!("🧪 Test: Token budget persistence");
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("token_budgets.json");
+        let app = app(Some(1000), None);
+
+        let ledger = BudgetLedger::open(&path);
+        ledger.record(&app.id, 300, Utc::now());
+        ledger.save().unwrap();
+
+        let reopened = BudgetLedger::open(&path);
+        assert_eq!(reopened.budget(&app, Utc::now()).remaining(), Some(700));
+
+        // Saves replace the file whole, never leaving a torn copy behind
+        let names: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, vec!["token_budgets.json"]);
+
+        // A ledger that cannot be read is no fresh budget
+        std::fs::write(&path, "{\"app-id\": {\"day\": \"20").unwrap();
+        let corrupt = BudgetLedger::open(&path);
+        assert_eq!(corrupt.budget(&app, Utc::now()).remaining(), Some(0));
+        corrupt.record(&app.id, 1, Utc::now());
+        corrupt.save().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "{\"app-id\": {\"day\": \"20",
+            "Left for repair"
+        );
+
+        // The repaired file counts without a restart
+        std::fs::write(&path, "{}").unwrap();
+        assert_eq!(corrupt.budget(&app, Utc::now()).remaining(), Some(1000));
+
+        println!("✅ Token budget persistence successful");
+    }
+
+    #[test]
+    fn test_streamed_text_is_tallied() {
+        println!("🧪 Test: Completion tokens of streams");
+
+        let mut sse = StreamTally::default();
+        let body = "data: {\"choices\":[{\"delta\":{\"content\":\"Hello, wo\"}}]}\n\n\
+                    data: {\"choices\":[{\"delta\":{\"content\":\"rld!\"}}]}\n\ndata: [DONE]\n\n";
+        let (first, rest) = body.split_at(20);
+        sse.push(first.as_bytes());
+        sse.push(rest.as_bytes());
+        assert_eq!(sse.tokens(), 4);
+
+        let mut ndjson = StreamTally::default();
+        ndjson.push(
+            b"{\"message\":{\"role\":\"assistant\",\"content\":\"abcdefgh\"},\"done\":false}\n",
+        );
+        ndjson.push(b"{\"response\":\"ijkl\",\"done\":false}\n{\"done\":true}\n");
+        assert_eq!(ndjson.tokens(), 3);
+
+        println!("✅ Stream tally successful");
+    }
+
+    #[tokio::test]
+    async fn test_exhausted_budget_is_refused() {
+        println!("🧪 Test: Requests beyond the token budget");
+
+        let app = app(Some(100), None);
+        let api_key = app.api_key.clone();
+        let budgets = Arc::new(TokenBudgets::new(
+            Arc::new(BudgetLedger::default()),
+            Arc::new(RwLock::new(vec![app])),
+        ));
+        let handler = || async {
+            let mut response: Response = "ok".into_response();
+            response.extensions_mut().insert(TokenUsage {
+                model: "gpt-4o".to_string(),
+                total_tokens: 60,
+            });
+            response
+        };
+        let router = Router::new()
+            .route("/v1/chat/completions", post(handler))
+            .route("/api/chat", post(handler))
+            .layer(axum::middleware::from_fn_with_state(
+                budgets,
+                enforce_budget,
+            ));
+        let request = |path: &str, key: &str| {
+            Request::post(path)
+                .header(header::AUTHORIZATION, format!("Bearer {}", key))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let first = router
+            .clone()
+            .oneshot(request("/v1/chat/completions", &api_key))
+            .await
+            .unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(first.headers()[REMAINING_HEADER], "40");
+
+        // Admitted while tokens are left, even if it then overdraws
+        let second = router
+            .clone()
+            .oneshot(request("/v1/chat/completions", &api_key))
+            .await
+            .unwrap();
+        assert_eq!(second.headers()[REMAINING_HEADER], "0");
+
+        let refused = router
+            .clone()
+            .oneshot(request("/v1/chat/completions", &api_key))
+            .await
+            .unwrap();
+        assert_eq!(refused.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(refused.headers().contains_key(header::RETRY_AFTER));
+        let body = axum::body::to_bytes(refused.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["code"], "insufficient_quota");
+        assert!(body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("daily token budget of 100 tokens"));
+
+        let ollama = router
+            .clone()
+            .oneshot(request("/api/chat", &api_key))
+            .await
+            .unwrap();
+        assert_eq!(ollama.status(), StatusCode::TOO_MANY_REQUESTS);
+        let body = axum::body::to_bytes(ollama.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(body["error"].is_string());
+
+        // Other keys have no budget
+        let other = router
+            .oneshot(request("/v1/chat/completions", "sk-other"))
+            .await
+            .unwrap();
+        assert_eq!(other.status(), StatusCode::OK);
+        assert!(!other.headers().contains_key(REMAINING_HEADER));
+
+        println!("✅ Token budget enforcement successful");
+    }
+}
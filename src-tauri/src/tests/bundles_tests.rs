@@ -0,0 +1,185 @@
+#[cfg(test)]
+mod bundles_tests {
+    use crate::bundles::{
+        export, merge, validate_namespace, verify, BundleSigner, ConflictStrategy, EntryKind,
+        MergeOutcome,
+    };
+    use crate::managers::config_manager::{
+        LanguageDetectionConfig, ModelAliasConfig, PromptConfig, TrustedSigner,
+    };
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn source_config() -> (PromptConfig, ModelAliasConfig, LanguageDetectionConfig) {
+        let mut prompts = PromptConfig::default();
+        prompts
+            .templates
+            .insert("summarize".to_string(), "Summarize: {{text}}".to_string());
+
+        let model_aliases = ModelAliasConfig {
+            aliases: HashMap::from([
+                ("fast".to_string(), "gpt-4o-mini".to_string()),
+                ("*".to_string(), "gpt-4".to_string()),
+            ]),
+        };
+
+        let mut language_detection = LanguageDetectionConfig::default();
+        language_detection
+            .routes
+            .insert("de".to_string(), "fast".to_string());
+
+        (prompts, model_aliases, language_detection)
+    }
+
+    #[test]
+    fn test_export_leaves_out_wildcard_alias() {
+        println!("🧪 Test: Export bundle contents");
+
+        let (prompts, aliases, language) = source_config();
+        let contents = export("team", None, &prompts, &aliases, &language).unwrap();
+
+        assert_eq!(contents.namespace, "team");
+        assert_eq!(contents.templates.len(), 1);
+        assert!(contents.model_aliases.contains_key("fast"));
+        assert!(!contents.model_aliases.contains_key("*"));
+        assert_eq!(contents.language_routes.get("de").unwrap(), "fast");
+
+        assert!(validate_namespace("team-a_1").is_ok());
+        assert!(validate_namespace("").is_err());
+        assert!(validate_namespace("team/x").is_err());
+
+        println!("✅ Export bundle contents successful");
+    }
+
+    #[test]
+    fn test_verify_signature_and_trust() {
+        println!("🧪 Test: Bundle signature and signer trust");
+
+        let (prompts, aliases, language) = source_config();
+        let contents = export(
+            "team",
+            Some("Ana".to_string()),
+            &prompts,
+            &aliases,
+            &language,
+        )
+        .unwrap();
+        let teammate = BundleSigner::from_seed([7; 32]);
+        let own_key = BundleSigner::from_seed([1; 32]).public_key();
+        let bundle = teammate.sign(contents).unwrap();
+
+        // Untrusted signer
+        assert!(verify(&bundle, &own_key, &[]).is_err());
+
+        let trusted = vec![TrustedSigner {
+            name: "Ana".to_string(),
+            public_key: teammate.public_key(),
+        }];
+        assert_eq!(verify(&bundle, &own_key, &trusted).unwrap(), "Ana");
+
+        // Own bundles need no trust entry
+        assert!(verify(&bundle, &teammate.public_key(), &[]).is_ok());
+
+        // Survives a JSON round trip, but not tampering
+        let json = serde_json::to_string(&bundle).unwrap();
+        let mut parsed: crate::bundles::SignedBundle = serde_json::from_str(&json).unwrap();
+        assert!(verify(&parsed, &own_key, &trusted).is_ok());
+        parsed
+            .contents
+            .model_aliases
+            .insert("fast".to_string(), "gpt-5-pro".to_string());
+        assert!(verify(&parsed, &own_key, &trusted).is_err());
+
+        println!("✅ Bundle signature and signer trust successful");
+    }
+
+    #[test]
+    fn test_merge_applies_namespace_and_conflicts() {
+        println!("🧪 Test: Bundle merge with namespaces and conflicts");
+
+        let (prompts, aliases, language) = source_config();
+        let contents = export("team", None, &prompts, &aliases, &language).unwrap();
+
+        let mut target_prompts = PromptConfig::default();
+        target_prompts
+            .templates
+            .insert("team/summarize".to_string(), "Mine".to_string());
+        let mut target_aliases = ModelAliasConfig {
+            aliases: HashMap::new(),
+        };
+        let mut target_language = LanguageDetectionConfig::default();
+
+        let entries = merge(
+            &contents,
+            "team",
+            ConflictStrategy::Skip,
+            &mut target_prompts,
+            &mut target_aliases,
+            &mut target_language,
+        );
+        let template = entries
+            .iter()
+            .find(|e| e.kind == EntryKind::Template)
+            .unwrap();
+        assert_eq!(template.outcome, MergeOutcome::Skipped);
+        assert_eq!(target_prompts.templates["team/summarize"], "Mine");
+        assert_eq!(target_aliases.aliases["team/fast"], "gpt-4o-mini");
+        // The route follows the alias into the namespace
+        assert_eq!(target_language.routes["de"], "team/fast");
+
+        let entries = merge(
+            &contents,
+            "team",
+            ConflictStrategy::Rename,
+            &mut target_prompts,
+            &mut target_aliases,
+            &mut target_language,
+        );
+        let template = entries
+            .iter()
+            .find(|e| e.kind == EntryKind::Template)
+            .unwrap();
+        assert_eq!(
+            template.outcome,
+            MergeOutcome::Renamed {
+                to: "team/summarize-2".to_string()
+            }
+        );
+        assert!(entries
+            .iter()
+            .filter(|e| e.kind != EntryKind::Template)
+            .all(|e| e.outcome == MergeOutcome::Unchanged));
+
+        merge(
+            &contents,
+            "team",
+            ConflictStrategy::Overwrite,
+            &mut target_prompts,
+            &mut target_aliases,
+            &mut target_language,
+        );
+        assert_eq!(
+            target_prompts.templates["team/summarize"],
+            "Summarize: {{text}}"
+        );
+
+        println!("✅ Bundle merge with namespaces and conflicts successful");
+    }
+
+    #[tokio::test]
+    async fn test_signing_key_is_created_once() {
+        println!("🧪 Test: Bundle signing key persistence");
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("keys").join("bundle_signing.key");
+
+        let first = BundleSigner::load_or_create(&path).await.unwrap();
+        let second = BundleSigner::load_or_create(&path).await.unwrap();
+        assert_eq!(first.public_key(), second.public_key());
+
+        tokio::fs::write(&path, "not a key").await.unwrap();
+        assert!(BundleSigner::load_or_create(&path).await.is_err());
+
+        println!("✅ Bundle signing key persistence successful");
+    }
+}
@@ -0,0 +1,96 @@
+#[cfg(test)]
+mod canary_tests {
+    use crate::analytics::VariantStats;
+    use crate::canary::{Canary, CanaryArm, CanarySpec, CanaryState, Verdict};
+    use crate::middleware::analytics::fingerprint;
+    use chrono::{Duration, Utc};
+    use std::collections::HashMap;
+
+    fn spec(percentage: u8, api_key: Option<&str>) -> CanarySpec {
+        CanarySpec {
+            model_aliases: HashMap::from([("*".to_string(), "gpt-4o".to_string())]),
+            percentage,
+            api_key: api_key.map(str::to_string),
+            trial_minutes: 30,
+            max_error_rate_increase: 0.05,
+            min_requests: 20,
+        }
+    }
+
+    fn stats(requests: u64, errors: u64) -> VariantStats {
+        VariantStats { requests, errors }
+    }
+
+    #[test]
+    fn test_start_validates_spec() {
+        println!("🧪 Test: Canary spec validation");
+
+        assert!(Canary::start(spec(0, None), fingerprint).is_err());
+        assert!(Canary::start(spec(101, None), fingerprint).is_err());
+        let mut no_window = spec(10, None);
+        no_window.trial_minutes = 0;
+        assert!(Canary::start(no_window, fingerprint).is_err());
+
+        let canary = Canary::start(spec(0, Some("sk-canary")), fingerprint).expect("valid");
+        assert_eq!(canary.state, CanaryState::Running);
+        assert_eq!(canary.api_key, Some(fingerprint("sk-canary")));
+        // The key itself is never reported back
+        let json = serde_json::to_string(&canary).expect("serialize");
+        assert!(!json.contains("sk-canary"));
+        assert_eq!(canary.aliases().resolve("anything"), "gpt-4o");
+
+        println!("✅ Canary spec validation successful");
+    }
+
+    #[test]
+    fn test_arm_assignment() {
+        println!("🧪 Test: Canary traffic assignment");
+
+        let key = fingerprint("sk-canary");
+        let keyed = Canary::start(spec(0, Some("sk-canary")), fingerprint).expect("valid");
+        assert_eq!(keyed.arm(Some(&key)), Some(CanaryArm::Canary));
+        assert_eq!(keyed.arm(Some("key_other")), Some(CanaryArm::Stable));
+        assert_eq!(keyed.arm(None), Some(CanaryArm::Stable));
+
+        let everyone = Canary::start(spec(100, None), fingerprint).expect("valid");
+        assert!((0..50).all(|_| everyone.arm(None) == Some(CanaryArm::Canary)));
+
+        let mut ended = everyone.clone();
+        ended.finish(CanaryState::RolledBack, None);
+        assert_eq!(ended.arm(Some(&key)), None);
+
+        println!("✅ Canary traffic assignment successful");
+    }
+
+    #[test]
+    fn test_evaluation() {
+        println!("🧪 Test: Canary evaluation");
+
+        let canary = Canary::start(spec(10, None), fingerprint).expect("valid");
+        let now = Utc::now();
+
+        // Too few canary requests to judge, however bad they look
+        assert_eq!(
+            canary.evaluate(&stats(100, 0), &stats(5, 5), now),
+            Verdict::Continue
+        );
+        // Within the allowed increase
+        assert_eq!(
+            canary.evaluate(&stats(100, 2), &stats(50, 3), now),
+            Verdict::Continue
+        );
+        assert!(matches!(
+            canary.evaluate(&stats(100, 2), &stats(50, 10), now),
+            Verdict::Rollback(_)
+        ));
+
+        // A healthy canary is promoted once its window has passed
+        let later = now + Duration::minutes(31);
+        assert_eq!(
+            canary.evaluate(&stats(100, 2), &stats(50, 1), later),
+            Verdict::Promote
+        );
+
+        println!("✅ Canary evaluation successful");
+    }
+}
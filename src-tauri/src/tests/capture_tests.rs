@@ -0,0 +1,166 @@
+#[cfg(test)]
+mod capture_tests {
+    use crate::capture::{
+        new_capture_id, replay_headers, sanitize_headers, Capture, CaptureStore, CapturedMessage,
+        Sanitizer, MASK, REPLAY_HEADER,
+    };
+    use crate::middleware::capture::{capture_exchange, Capturer};
+    use axum::{
+        body::Body,
+        http::{header, HeaderMap, HeaderValue, Request},
+        routing::post,
+        Router,
+    };
+    use chrono::{Duration as ChronoDuration, Utc};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tempfile::TempDir;
+    use tower::ServiceExt;
+
+    const API_KEY: &str = "sk-proj-abcdefghijklmnopqrstuvwxyz123456";
+
+    fn capture(id: String) -> Capture {
+        Capture {
+            id,
+            request_id: None,
+            replay_of: None,
+            captured_at: Utc::now(),
+            method: "POST".to_string(),
+            path: "/v1/chat/completions".to_string(),
+            status: 200,
+            duration_ms: 12,
+            request: CapturedMessage::default(),
+            response: CapturedMessage::default(),
+        }
+    }
+
+    #[test]
+    fn test_headers_are_sanitized_for_storage_and_replay() {
+        println!("🧪 Test: Capture header sanitizing");
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", API_KEY)).unwrap(),
+        );
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+        headers.insert(header::HOST, HeaderValue::from_static("localhost:3001"));
+        headers.append("x-tag", HeaderValue::from_static("a"));
+        headers.append("x-tag", HeaderValue::from_static("b"));
+
+        let sanitized = sanitize_headers(&headers);
+        assert_eq!(sanitized["authorization"], MASK);
+        assert_eq!(sanitized["x-tag"], "a, b");
+        assert!(!format!("{:?}", sanitized).contains(API_KEY));
+
+        let mut captured = capture(new_capture_id(Utc::now()));
+        captured.request.headers = sanitized;
+        let replayed = replay_headers(&captured);
+        let names: Vec<&str> = replayed.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["content-type", "x-tag"]);
+
+        println!("✅ Capture header sanitizing successful");
+    }
+
+    #[test]
+    fn test_store_keeps_newest_captures() {
+        println!("🧪 Test: Capture store retention");
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let store = CaptureStore::open(temp_dir.path(), 2).expect("open store");
+
+        let start = Utc::now();
+        let ids: Vec<String> = (0..3)
+            .map(|i| new_capture_id(start + ChronoDuration::milliseconds(i)))
+            .collect();
+        for id in &ids {
+            store.save(&capture(id.clone())).expect("save capture");
+        }
+
+        let listed: Vec<String> = store
+            .list(10)
+            .unwrap()
+            .into_iter()
+            .map(|summary| summary.id)
+            .collect();
+        assert_eq!(listed, vec![ids[2].clone(), ids[1].clone()]);
+        assert!(store.load(&ids[0]).unwrap().is_none());
+        assert!(store.load(&ids[2]).unwrap().is_some());
+
+        // IDs never escape the capture directory
+        assert!(store.load("cap_../../etc/passwd").unwrap().is_none());
+
+        println!("✅ Capture store retention successful");
+    }
+
+    #[tokio::test]
+    async fn test_streamed_exchange_is_captured_without_secrets() {
+        println!("🧪 Test: Capturing a streamed exchange");
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let store = CaptureStore::open(temp_dir.path(), 10).expect("open store");
+        let capturer = Arc::new(Capturer::new(store, Sanitizer::new(None)));
+
+        let chunks = [
+            "data: {\"delta\":\"Hel\"}\n\n",
+            "data: {\"delta\":\"lo\"}\n\n",
+        ];
+        let app = Router::new()
+            .route(
+                "/v1/chat/completions",
+                post(move || async move {
+                    let stream =
+                        futures_util::stream::iter(chunks.map(Ok::<_, std::convert::Infallible>));
+                    Body::from_stream(stream)
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                capturer,
+                capture_exchange,
+            ));
+
+        let request = Request::post("/v1/chat/completions?trace=1")
+            .header(header::AUTHORIZATION, format!("Bearer {}", API_KEY))
+            .header(REPLAY_HEADER, "cap_20260101000000000_abcdef12")
+            .body(Body::from(format!(
+                "{{\"messages\":[{{\"role\":\"user\",\"content\":\"my key is {}\"}}]}}",
+                API_KEY
+            )))
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(body, chunks.concat().as_bytes());
+
+        // The capture is written on the blocking pool once the body is done
+        let reader = CaptureStore::open(temp_dir.path(), 10).expect("open store");
+        let mut summaries = Vec::new();
+        for _ in 0..100 {
+            summaries = reader.list(10).unwrap();
+            if !summaries.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(summaries.len(), 1);
+
+        let captured = reader.load(&summaries[0].id).unwrap().unwrap();
+        assert_eq!(captured.path, "/v1/chat/completions?trace=1");
+        assert_eq!(captured.status, 200);
+        assert_eq!(
+            captured.replay_of.as_deref(),
+            Some("cap_20260101000000000_abcdef12")
+        );
+        assert_eq!(captured.response.body, chunks.concat());
+        assert!(captured.request.body.contains("[REDACTED_API_KEY]"));
+        assert_eq!(captured.request.headers["authorization"], MASK);
+        let json = serde_json::to_string(&captured).unwrap();
+        assert!(!json.contains(API_KEY));
+
+        println!("✅ Capturing a streamed exchange successful");
+    }
+}
@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod cloudflared_release_tests {
+    use crate::cloudflared_release::{asset_name, is_newer, verify, version_parts, Release};
+    use sha2::{Digest, Sha256};
+
+    const ASSET: &str = "cloudflared-linux-amd64";
+
+    fn sha256(bytes: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(bytes))
+    }
+
+    fn published(body: Option<String>, digest: Option<String>) -> Release {
+        serde_json::from_value(serde_json::json!({
+            "tag_name": "2024.6.1",
+            "body": body,
+            "assets": [{
+                "name": ASSET,
+                "browser_download_url":
+                    "https://github.com/cloudflare/cloudflared/releases/download/2024.6.1/cloudflared-linux-amd64",
+                "digest": digest,
+            }],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_checksum_from_release_notes() {
+        println!("🧪 Test: Checksums listed in the release notes");
+
+        let binary = sha256(b"cloudflared");
+        let archive = sha256(b"cloudflared.tgz");
+        let notes = format!(
+            "### Fixes\n- Something\n\nSHA256 Checksums:\n```\n\
+             cloudflared-darwin-amd64.tgz: {}\n{}: {}\n```\n",
+            archive,
+            ASSET,
+            binary.to_uppercase()
+        );
+        let release = published(Some(notes), Some(format!("sha256:{}", archive)));
+        assert_eq!(release.checksum(ASSET), Some(binary), "Notes come first");
+        assert_eq!(
+            release.checksum("cloudflared-darwin-amd64.tgz"),
+            Some(archive)
+        );
+        assert_eq!(release.checksum("cloudflared-windows-amd64.exe"), None);
+
+        // sha256sum's layout
+        let binary = sha256(b"other");
+        let release = published(Some(format!("{}  {}", binary, ASSET)), None);
+        assert_eq!(release.checksum(ASSET), Some(binary));
+
+        println!("✅ Checksums listed in the release notes successful");
+    }
+
+    #[test]
+    fn test_checksum_from_asset_digest() {
+        println!("🧪 Test: Checksum from the asset's digest");
+
+        let binary = sha256(b"cloudflared");
+        let release = published(
+            Some("No checksums this time".to_string()),
+            Some(format!("sha256:{}", binary)),
+        );
+        assert_eq!(release.checksum(ASSET), Some(binary));
+
+        // Nothing to verify against
+        let release = published(None, None);
+        assert_eq!(release.checksum(ASSET), None);
+        let release = published(None, Some("md5:abc".to_string()));
+        assert_eq!(release.checksum(ASSET), None);
+
+        assert!(release.asset(ASSET).is_ok());
+        assert!(release.asset("cloudflared-linux-386").is_err());
+
+        println!("✅ Checksum from the asset's digest successful");
+    }
+
+    #[test]
+    fn test_verify_download() {
+        println!("🧪 Test: Verify downloaded bytes");
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(ASSET);
+        std::fs::write(&path, b"cloudflared").unwrap();
+
+        let expected = sha256(b"cloudflared");
+        assert_eq!(verify(&path, &expected).unwrap(), expected);
+        assert!(verify(&path, &expected.to_uppercase()).is_ok());
+
+        std::fs::write(&path, b"tampered").unwrap();
+        let error = verify(&path, &expected).unwrap_err().to_string();
+        assert!(error.contains(&expected));
+        assert!(error.contains("refusing"));
+        assert!(verify(&dir.path().join("missing"), &expected).is_err());
+
+        assert_eq!(asset_name("linux", "x86_64"), Some(ASSET));
+        assert_eq!(asset_name("plan9", "x86_64"), None);
+
+        println!("✅ Verify downloaded bytes successful");
+    }
+
+    #[test]
+    fn test_release_versions() {
+        println!("🧪 Test: Compare cloudflared releases");
+
+        assert_eq!(version_parts("2024.6.1"), Some(vec![2024, 6, 1]));
+        assert_eq!(version_parts("2024.6"), None);
+        assert_eq!(version_parts("v2024.6.1"), None);
+        assert_eq!(version_parts(""), None);
+
+        // Numerically, not as text
+        assert!(is_newer("2024.10.0", "2024.9.1"));
+        assert!(!is_newer("2024.9.1", "2024.10.0"));
+        assert!(!is_newer("2024.6.1", "2024.6.1"));
+        // Copies of no known release are updated
+        assert!(is_newer("2024.6.1", ""));
+        assert!(!is_newer("nightly", "2024.6.1"));
+
+        println!("✅ Compare cloudflared releases successful");
+    }
+}
@@ -0,0 +1,156 @@
+#[cfg(test)]
+mod config_dry_run_tests {
+    use crate::analytics::RequestRecord;
+    use crate::config_dry_run::{dry_run, simulate, Outcome};
+    use crate::managers::config_manager::ConfigSchema;
+    use chrono::Utc;
+    use serde_json::json;
+
+    fn config() -> ConfigSchema {
+        serde_json::from_value(json!({
+            "version": 1,
+            "server": { "host": "127.0.0.1", "port": 8080 },
+            "bifrost": { "host": "127.0.0.1", "port": 3001, "enabled": true },
+            "tunnel": { "enabled": false, "tunnel_type": "cloudflare" },
+            "features": {
+                "reasoning_effort": "medium",
+                "reasoning_summaries": "enabled",
+                "reasoning_compatibility": "compatible"
+            },
+            "monitoring": {
+                "health_check_interval": 30,
+                "error_threshold": 5,
+                "notifications": true
+            }
+        }))
+        .expect("test config")
+    }
+
+    fn request(route: &str, client: &str, model: Option<&str>) -> RequestRecord {
+        RequestRecord {
+            timestamp: Utc::now(),
+            method: "POST".to_string(),
+            route: route.to_string(),
+            status: 200,
+            latency_ms: 42,
+            sample_weight: 1,
+            request_id: Some(format!("req_{}", client)),
+            model: model.map(str::to_string),
+            tokens: 0,
+            client: Some(client.to_string()),
+            api_key: None,
+            language: None,
+            variant: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_simulated_routing() {
+        println!("🧪 Test: Simulated request routing");
+
+        let mut config = config();
+        config.model_aliases.aliases = [("fast", "gpt-4o-mini"), ("*", "gpt-4o")]
+            .into_iter()
+            .map(|(alias, model)| (alias.to_string(), model.to_string()))
+            .collect();
+        config.language_detection.enabled = true;
+        config
+            .language_detection
+            .routes
+            .insert("jpn".to_string(), "fast".to_string());
+
+        let chat = request("/v1/chat/completions", "10.0.0.2", Some("gpt-5"));
+        assert_eq!(
+            simulate(&config, &chat),
+            Outcome::Accepted {
+                model: Some("gpt-4o".to_string())
+            }
+        );
+
+        // Language routes apply before aliases
+        let japanese = RequestRecord {
+            language: Some("jpn".to_string()),
+            ..chat.clone()
+        };
+        assert_eq!(
+            simulate(&config, &japanese),
+            Outcome::Accepted {
+                model: Some("gpt-4o-mini".to_string())
+            }
+        );
+
+        let models = request("/v1/models", "10.0.0.2", None);
+        assert_eq!(
+            simulate(&config, &models),
+            Outcome::Accepted { model: None }
+        );
+
+        config.access_control.deny = vec!["10.0.0.0/24".to_string()];
+        assert!(matches!(
+            simulate(&config, &models),
+            Outcome::Rejected { .. }
+        ));
+
+        println!("✅ Simulated request routing successful");
+    }
+
+    #[test]
+    fn test_dry_run_reports_changes() {
+        println!("🧪 Test: Config dry run report");
+
+        let current = config();
+        let mut proposed = config();
+        proposed.access_control.allow = vec!["10.0.0.0/24".to_string()];
+        proposed
+            .model_aliases
+            .aliases
+            .insert("gpt-5".to_string(), "gpt-4o".to_string());
+        proposed.batches.enabled = true;
+
+        let records = [
+            // Routed to a different model
+            request("/v1/chat/completions", "10.0.0.2", Some("gpt-5")),
+            // Unchanged
+            request("/v1/chat/completions", "10.0.0.3", Some("gpt-4")),
+            // Outside the new allow list
+            request("/v1/models", "192.168.1.5", None),
+            // The batch API becomes available
+            request("/v1/batches", "10.0.0.4", None),
+        ];
+
+        let report = dry_run(&current, &proposed, &records);
+        assert!(report.error.is_none());
+        assert_eq!(report.replayed, 4);
+        assert_eq!(report.rerouted.len(), 1);
+        assert_eq!(report.rerouted[0].client.as_deref(), Some("10.0.0.2"));
+        assert_eq!(
+            report.rerouted[0].proposed,
+            Outcome::Accepted {
+                model: Some("gpt-4o".to_string())
+            }
+        );
+        assert_eq!(report.rejected.len(), 1);
+        assert_eq!(report.rejected[0].route, "/v1/models");
+        assert_eq!(report.accepted.len(), 1);
+        assert_eq!(report.accepted[0].route, "/v1/batches");
+
+        println!("✅ Config dry run report successful");
+    }
+
+    #[test]
+    fn test_invalid_config_is_not_replayed() {
+        println!("🧪 Test: Config dry run of an invalid config");
+
+        let mut proposed = config();
+        proposed.access_control.deny = vec!["not-an-address".to_string()];
+
+        let records = [request("/v1/models", "10.0.0.2", None)];
+        let report = dry_run(&config(), &proposed, &records);
+        assert!(report.error.is_some());
+        assert_eq!(report.replayed, 0);
+        assert!(report.rejected.is_empty());
+
+        println!("✅ Config dry run of an invalid config successful");
+    }
+}
@@ -0,0 +1,104 @@
+#[cfg(test)]
+mod conversations_tests {
+    use crate::conversations::{upstream_ids, ConversationStore, UpstreamConversation};
+    use crate::managers::config_manager::ConversationConfig;
+    use crate::managers::server_manager::Message;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    fn message(role: &str, content: &str) -> Message {
+        Message {
+            role: role.to_string(),
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            name: None,
+            reasoning_content: None,
+        }
+    }
+
+    fn upstream(id: &str) -> UpstreamConversation {
+        UpstreamConversation {
+            conversation_id: format!("conv-{}", id),
+            message_id: format!("msg-{}", id),
+        }
+    }
+
+    #[test]
+    fn test_follow_up_resumes_conversation() {
+        println!("🧪 Test: Follow-up requests continue the upstream conversation");
+
+        let store = Arc::new(ConversationStore::new(ConversationConfig::default()));
+        let mut history = vec![message("system", "Be brief"), message("user", "Hi")];
+
+        let (resumption, turn) = store.begin("key-a", &history);
+        assert!(resumption.is_none());
+        turn.expect("turn").complete("Hello! ", upstream("1"));
+
+        // The client sends back the reply (trimmed) and a new question
+        history.push(message("assistant", "Hello!"));
+        history.push(message("user", "How are you?"));
+        let (resumption, turn) = store.begin("key-a", &history);
+        let resumption = resumption.expect("resumed");
+        assert_eq!(resumption.conversation, upstream("1"));
+        assert_eq!(resumption.new_messages, 1);
+        turn.expect("turn").complete("Fine.", upstream("2"));
+
+        // The third turn continues from the latest reply
+        history.push(message("assistant", "Fine."));
+        history.push(message("user", "Bye"));
+        let (resumption, _) = store.begin("key-a", &history);
+        assert_eq!(resumption.expect("resumed").conversation, upstream("2"));
+
+        // Other clients and edited histories start over
+        assert!(store.begin("key-b", &history).0.is_none());
+        history[1].content = "Hey".to_string();
+        assert!(store.begin("key-a", &history).0.is_none());
+
+        println!("✅ Follow-up requests continue the upstream conversation successful");
+    }
+
+    #[test]
+    fn test_store_limits() {
+        println!("🧪 Test: Conversation store capacity and opt-out");
+
+        let store = Arc::new(ConversationStore::new(ConversationConfig {
+            max_conversations: 1,
+            ..ConversationConfig::default()
+        }));
+        for (id, question) in [("1", "First"), ("2", "Second")] {
+            let (_, turn) = store.begin("", &[message("user", question)]);
+            turn.expect("turn").complete("Answer", upstream(id));
+        }
+        assert_eq!(store.len(), 1);
+        let evicted = [
+            message("user", "First"),
+            message("assistant", "Answer"),
+            message("user", "More"),
+        ];
+        assert!(store.begin("", &evicted).0.is_none());
+
+        let disabled = Arc::new(ConversationStore::new(ConversationConfig {
+            enabled: false,
+            ..ConversationConfig::default()
+        }));
+        let (resumption, turn) = disabled.begin("", &[message("user", "Hi")]);
+        assert!(resumption.is_none() && turn.is_none());
+
+        println!("✅ Conversation store capacity and opt-out successful");
+    }
+
+    #[test]
+    fn test_upstream_ids() {
+        println!("🧪 Test: Upstream id extraction");
+
+        let event = json!({
+            "conversation_id": "conv-1",
+            "message": {"id": "msg-1", "content": {"parts": ["Hi"]}}
+        });
+        assert_eq!(upstream_ids(&event), Some(upstream("1")));
+        assert_eq!(upstream_ids(&json!({"message": {"id": "msg-1"}})), None);
+
+        println!("✅ Upstream id extraction successful");
+    }
+}
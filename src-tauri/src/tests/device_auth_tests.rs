@@ -0,0 +1,134 @@
+#[cfg(test)]
+mod device_auth_tests {
+    use crate::device_auth::{request_code, wait_for_authorization, DeviceCode};
+    use reqwest::Client;
+    use serde_json::json;
+    use std::time::Duration;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn code(interval: u64) -> DeviceCode {
+        DeviceCode {
+            device_auth_id: "dev-123".to_string(),
+            user_code: "ABCD-EFGH".to_string(),
+            interval,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_request_code() {
+        println!("🧪 Test: Device code request");
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/usercode"))
+            .and(body_json(json!({ "client_id": "client-1" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "device_auth_id": "dev-123",
+                "user_code": "ABCD-EFGH",
+                "interval": "3"
+            })))
+            .mount(&server)
+            .await;
+
+        let code = request_code(&Client::new(), &server.uri(), "client-1")
+            .await
+            .unwrap();
+        assert_eq!(code.device_auth_id, "dev-123");
+        assert_eq!(code.user_code, "ABCD-EFGH");
+        assert_eq!(code.interval, 3);
+
+        // Without an interval the default applies
+        let code: DeviceCode =
+            serde_json::from_value(json!({ "device_auth_id": "d", "usercode": "C" })).unwrap();
+        assert_eq!(code.user_code, "C");
+        assert_eq!(code.interval, 5);
+
+        let refused = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("unknown client"))
+            .mount(&refused)
+            .await;
+        let error = request_code(&Client::new(), &refused.uri(), "client-1")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("unknown client"));
+
+        println!("✅ Device code request successful");
+    }
+
+    #[tokio::test]
+    async fn test_polls_until_the_code_is_entered() {
+        println!("🧪 Test: Device authorization polling");
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .and(body_json(json!({
+                "device_auth_id": "dev-123",
+                "user_code": "ABCD-EFGH"
+            })))
+            .respond_with(ResponseTemplate::new(403))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "authorization_code": "auth-code",
+                "code_challenge": "challenge",
+                "code_verifier": "verifier"
+            })))
+            .mount(&server)
+            .await;
+
+        let authorization = wait_for_authorization(
+            &Client::new(),
+            &server.uri(),
+            &code(1),
+            Duration::from_secs(30),
+        )
+        .await
+        .unwrap();
+        assert_eq!(authorization.authorization_code, "auth-code");
+        assert_eq!(authorization.code_verifier, "verifier");
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+
+        println!("✅ Device authorization polling successful");
+    }
+
+    #[tokio::test]
+    async fn test_polling_gives_up() {
+        println!("🧪 Test: Device authorization failures");
+
+        // Never entered
+        let pending = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&pending)
+            .await;
+        let error =
+            wait_for_authorization(&Client::new(), &pending.uri(), &code(1), Duration::ZERO)
+                .await
+                .unwrap_err();
+        assert!(error.to_string().contains("not entered"));
+
+        // Denied or expired
+        let denied = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("expired_token"))
+            .mount(&denied)
+            .await;
+        let error = wait_for_authorization(
+            &Client::new(),
+            &denied.uri(),
+            &code(1),
+            Duration::from_secs(30),
+        )
+        .await
+        .unwrap_err();
+        assert!(error.to_string().contains("expired_token"));
+
+        println!("✅ Device authorization failures successful");
+    }
+}
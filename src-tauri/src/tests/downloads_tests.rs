@@ -0,0 +1,168 @@
+#[cfg(test)]
+mod downloads_tests {
+    use crate::downloads::{download, download_client, subscribe, DownloadProgress};
+    use axum::http::{header, HeaderMap, StatusCode};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::get;
+    use axum::Router;
+    use tokio::sync::broadcast;
+
+    /// Contents of the file the test servers offer
+    fn contents() -> Vec<u8> {
+        (0..64 * 1024).map(|i| (i % 251) as u8).collect()
+    }
+
+    /// Offer `contents()` at `/binary`, honouring `Range` unless
+    /// `ranges` is false
+    async fn serve(ranges: bool) -> String {
+        let app = Router::new().route(
+            "/binary",
+            get(move |headers: HeaderMap| async move { respond(&headers, ranges) }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        format!("http://{}/binary", address)
+    }
+
+    fn respond(headers: &HeaderMap, ranges: bool) -> Response {
+        let contents = contents();
+        let start = headers
+            .get(header::RANGE)
+            .and_then(|range| range.to_str().ok())
+            .and_then(|range| {
+                range
+                    .strip_prefix("bytes=")?
+                    .strip_suffix('-')?
+                    .parse()
+                    .ok()
+            })
+            .filter(|_| ranges);
+        match start {
+            None => contents.into_response(),
+            Some(start) if start >= contents.len() => {
+                StatusCode::RANGE_NOT_SATISFIABLE.into_response()
+            },
+            Some(start) => (
+                StatusCode::PARTIAL_CONTENT,
+                [(
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", start, contents.len() - 1, contents.len()),
+                )],
+                contents[start..].to_vec(),
+            )
+                .into_response(),
+        }
+    }
+
+    /// Progress reported for `binary`, up to and including the last report
+    fn progress_of(
+        receiver: &mut broadcast::Receiver<DownloadProgress>,
+        binary: &str,
+    ) -> Vec<DownloadProgress> {
+        let mut reports = Vec::new();
+        while let Ok(progress) = receiver.try_recv() {
+            if progress.binary == binary {
+                reports.push(progress);
+            }
+        }
+        reports
+    }
+
+    #[tokio::test]
+    async fn test_download_reports_progress() {
+        println!("🧪 Test: Download progress");
+
+        let url = serve(true).await;
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("fresh.partial");
+        let mut receiver = subscribe();
+
+        download(&download_client(), &url, &path, "fresh")
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), contents());
+
+        let total = contents().len() as u64;
+        let reports = progress_of(&mut receiver, "fresh");
+        let first = reports.first().unwrap();
+        assert_eq!((first.downloaded, first.total), (0, Some(total)));
+        assert!(!first.done);
+        let last = reports.last().unwrap();
+        assert!(last.done);
+        assert_eq!(last.downloaded, total);
+        assert_eq!(last.percent, Some(100.0));
+        assert_eq!(last.resumed_from, 0);
+
+        println!("✅ Download progress successful");
+    }
+
+    #[tokio::test]
+    async fn test_resume_interrupted_download() {
+        println!("🧪 Test: Resume an interrupted download");
+
+        let url = serve(true).await;
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("resumed.partial");
+        std::fs::write(&path, &contents()[..1000]).unwrap();
+        let mut receiver = subscribe();
+
+        download(&download_client(), &url, &path, "resumed")
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), contents());
+
+        let reports = progress_of(&mut receiver, "resumed");
+        assert_eq!(reports.first().unwrap().downloaded, 1000);
+        let last = reports.last().unwrap();
+        assert!(last.done);
+        assert_eq!(last.resumed_from, 1000);
+        assert_eq!(last.total, Some(contents().len() as u64));
+
+        // A partial file longer than the download is no part of it
+        let mut longer = contents();
+        longer.extend_from_slice(b"stale");
+        std::fs::write(&path, longer).unwrap();
+        download(&download_client(), &url, &path, "resumed")
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), contents());
+
+        println!("✅ Resume an interrupted download successful");
+    }
+
+    #[tokio::test]
+    async fn test_restart_when_range_ignored() {
+        println!("🧪 Test: Restart a download the server cannot resume");
+
+        let url = serve(false).await;
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("restarted.partial");
+        std::fs::write(&path, b"left over from before").unwrap();
+        let mut receiver = subscribe();
+
+        download(&download_client(), &url, &path, "restarted")
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), contents());
+        assert_eq!(
+            progress_of(&mut receiver, "restarted")
+                .last()
+                .unwrap()
+                .resumed_from,
+            0
+        );
+
+        // Failed requests leave the partial file alone
+        let missing = url.replace("/binary", "/missing");
+        let error = download(&download_client(), &missing, &path, "restarted")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("404"));
+        assert_eq!(std::fs::read(&path).unwrap(), contents());
+
+        println!("✅ Restart a download the server cannot resume successful");
+    }
+}
@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod error_feed_tests {
+    use crate::error::{MindLinkError, RecoveryAction};
+    use crate::error_feed::ErrorFeed;
+
+    fn network_error(message: &str) -> MindLinkError {
+        MindLinkError::Network {
+            message: message.to_string(),
+            url: None,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_feed_is_newest_first_and_bounded() {
+        println!("🧪 Test: Error feed ordering and capacity");
+
+        let feed = ErrorFeed::new(3);
+        for i in 0..5 {
+            feed.record(
+                "ServerManager",
+                &network_error(&format!("failure {}", i)),
+                None,
+            );
+        }
+
+        let entries = feed.recent(10);
+        assert_eq!(entries.len(), 3);
+        assert!(entries[0].user_message.contains("failure 4"));
+        assert!(entries[2].user_message.contains("failure 2"));
+        assert_eq!(feed.recent(1).len(), 1);
+
+        feed.clear();
+        assert!(feed.recent(10).is_empty());
+
+        println!("✅ Error feed ordering and capacity successful");
+    }
+
+    #[test]
+    fn test_entries_describe_the_remedy() {
+        println!("🧪 Test: Error feed remedies");
+
+        let feed = ErrorFeed::default();
+        feed.record(
+            "AuthManager",
+            &MindLinkError::Authentication {
+                message: "Refresh token expired".to_string(),
+                source: None,
+            },
+            Some("req_123"),
+        );
+        feed.record(
+            "BifrostManager",
+            &MindLinkError::BinaryExecution {
+                message: "exec format error".to_string(),
+                binary_name: "bifrost".to_string(),
+                binary_path: None,
+                source: None,
+            },
+            None,
+        );
+
+        let entries = feed.recent(10);
+        assert_eq!(entries[0].component, "BifrostManager");
+        assert_eq!(entries[0].kind, "BinaryExecution");
+        assert_eq!(
+            entries[0].action,
+            Some(RecoveryAction::ReinstallBinary {
+                binary: "bifrost".to_string()
+            })
+        );
+        assert!(entries[0].hint.as_deref().unwrap().contains("bifrost"));
+
+        assert_eq!(entries[1].action, Some(RecoveryAction::ReAuthenticate));
+        assert_eq!(entries[1].correlation_id.as_deref(), Some("req_123"));
+        assert!(entries[1]
+            .technical_details
+            .contains("Refresh token expired"));
+
+        let json = serde_json::to_value(&entries[0]).unwrap();
+        assert_eq!(json["action"]["action"], "reinstall_binary");
+        assert_eq!(json["action"]["binary"], "bifrost");
+
+        println!("✅ Error feed remedies successful");
+    }
+}
@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod error_tests {
+    use crate::api_error::UpstreamStatus;
+    use crate::error::{MindLinkError, RecoveryAction};
+
+    fn configuration_error(config_key: Option<&str>) -> MindLinkError {
+        MindLinkError::Configuration {
+            message: "invalid".to_string(),
+            config_key: config_key.map(str::to_string),
+            source: None,
+        }
+    }
+
+    #[test]
+    fn test_recovery_actions() {
+        println!("🧪 Test: Recovery actions per error kind");
+
+        let auth = MindLinkError::Authentication {
+            message: "Refresh token expired".to_string(),
+            source: None,
+        };
+        assert_eq!(auth.recovery_action(), Some(RecoveryAction::ReAuthenticate));
+
+        let binary = MindLinkError::BinaryExecution {
+            message: "exec format error".to_string(),
+            binary_name: "cloudflared".to_string(),
+            binary_path: None,
+            source: None,
+        };
+        assert_eq!(
+            binary.recovery_action(),
+            Some(RecoveryAction::ReinstallBinary {
+                binary: "cloudflared".to_string()
+            })
+        );
+
+        let tunnel = MindLinkError::Tunnel {
+            message: "Connection refused".to_string(),
+            tunnel_type: None,
+            local_port: None,
+            source: None,
+        };
+        assert_eq!(tunnel.recovery_action(), Some(RecoveryAction::RetryLater));
+
+        let internal = MindLinkError::Internal {
+            message: "lock poisoned".to_string(),
+            component: None,
+            source: None,
+        };
+        assert_eq!(internal.recovery_action(), None);
+
+        println!("✅ Recovery actions per error kind successful");
+    }
+
+    #[test]
+    fn test_settings_section_from_config_key() {
+        println!("🧪 Test: Settings section of configuration errors");
+
+        let section = |key| match configuration_error(key).recovery_action() {
+            Some(RecoveryAction::OpenSettings { section }) => section,
+            other => panic!("expected OpenSettings, got {:?}", other),
+        };
+        assert_eq!(section(Some("server.port")), "server");
+        assert_eq!(section(Some("tunnel")), "tunnel");
+        assert_eq!(section(None), "general");
+
+        println!("✅ Settings section of configuration errors successful");
+    }
+
+    #[test]
+    fn test_revoked_upstream_session_needs_sign_in() {
+        println!("🧪 Test: Upstream 401 asks for a new sign-in");
+
+        let upstream = |status| MindLinkError::Network {
+            message: "Request failed".to_string(),
+            url: None,
+            source: Some(UpstreamStatus::new(status, "").into()),
+        };
+        assert_eq!(
+            upstream(401).recovery_action(),
+            Some(RecoveryAction::ReAuthenticate)
+        );
+        assert_eq!(
+            upstream(503).recovery_action(),
+            Some(RecoveryAction::RetryLater)
+        );
+
+        let json = serde_json::to_value(RecoveryAction::OpenSettings {
+            section: "server".to_string(),
+        })
+        .unwrap();
+        assert_eq!(json["action"], "open_settings");
+        assert_eq!(json["section"], "server");
+
+        println!("✅ Upstream 401 asks for a new sign-in successful");
+    }
+}
@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod events_tests {
+    use crate::downloads::DownloadProgress;
+    use crate::events::{AppEvent, EventEnvelope, Notification, NotificationKind, EVENT_VERSION};
+    use crate::health::HealthReport;
+    use crate::managers::auth_manager::AuthEvent;
+    use crate::managers::tunnel_manager::TunnelUrlChanged;
+    use crate::middleware::backpressure::{OverloadEvent, OverloadReason};
+    use crate::power::PowerStatus;
+    use crate::tunnel_limits::{TunnelLimit, TunnelLimitWarning};
+    use crate::tunnel_tokens::TunnelTokenRotated;
+    use crate::TrayState;
+
+    #[test]
+    fn test_envelope_shape() {
+        println!("🧪 Test: Event envelope serialization");
+
+        let event = AppEvent::Notification(Notification::new(
+            NotificationKind::Warning,
+            "Bifrost Warning",
+            "Binary not found",
+        ));
+        let value = serde_json::to_value(EventEnvelope::new(event)).unwrap();
+
+        assert_eq!(value["version"], EVENT_VERSION);
+        assert_eq!(value["event"]["kind"], "notification");
+        assert_eq!(value["event"]["data"]["title"], "Bifrost Warning");
+        assert_eq!(value["event"]["data"]["type"], "warning");
+        assert!(value["event"]["data"]["timestamp"].is_string());
+
+        println!("✅ Event envelope serialization successful");
+    }
+
+    #[test]
+    fn test_event_names_match_kind() {
+        println!("🧪 Test: Event names match their kind tag");
+
+        let events = [
+            AppEvent::Notification(Notification::new(NotificationKind::Info, "Title", "Body")),
+            AppEvent::TrayStateChanged(TrayState::Connected),
+            AppEvent::HealthChanged(HealthReport::default()),
+            AppEvent::PowerSaverChanged(PowerStatus::default()),
+            AppEvent::Overloaded(OverloadEvent {
+                reason: OverloadReason::QueueFull,
+                retry_after_secs: 5,
+                in_flight: 16,
+                max_in_flight: 16,
+                at: chrono::Utc::now(),
+            }),
+            AppEvent::Auth(AuthEvent::LoginStarted),
+            AppEvent::TunnelTokenRotated(TunnelTokenRotated {
+                expires_at: chrono::Utc::now(),
+                manual: false,
+            }),
+            AppEvent::TunnelUrlChanged(TunnelUrlChanged {
+                url: Some("https://new-words.trycloudflare.com".to_string()),
+                previous_url: Some("https://old-words.trycloudflare.com".to_string()),
+                changed_at: chrono::Utc::now(),
+            }),
+            AppEvent::TunnelLimit(TunnelLimitWarning {
+                limit: TunnelLimit::MonthlyEgress,
+                used: 900,
+                max: 1000,
+                reached: false,
+                at: chrono::Utc::now(),
+            }),
+            AppEvent::DownloadProgress(DownloadProgress {
+                binary: "cloudflared".to_string(),
+                downloaded: 1024,
+                total: Some(4096),
+                percent: Some(25.0),
+                resumed_from: 0,
+                done: false,
+            }),
+        ];
+        for event in events {
+            let value = serde_json::to_value(&event).unwrap();
+            assert_eq!(value["kind"], event.name());
+        }
+
+        println!("✅ Event names match their kind tag successful");
+    }
+}
@@ -0,0 +1,298 @@
+#[cfg(test)]
+mod failover_tests {
+    use crate::api_error::UpstreamStatus;
+    use crate::error::MindLinkError;
+    use crate::failover::{
+        completions_url, forward_stream, provider_request, should_fail_over, FallbackChain,
+    };
+    use crate::managers::config_manager::{FailoverConfig, FallbackProvider, LocalModelsConfig};
+    use crate::managers::local_model_manager::LocalModelManager;
+    use crate::managers::server_manager::{ChatCompletionRequest, Message};
+    use std::time::Duration;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn provider(name: &str, base_url: Option<&str>) -> FallbackProvider {
+        FallbackProvider {
+            name: name.to_string(),
+            base_url: base_url.map(str::to_string),
+            api_key: None,
+            model: None,
+        }
+    }
+
+    fn request() -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "gpt-5".to_string(),
+            messages: vec![Message {
+                role: "user".to_string(),
+                content: "Hello".to_string(),
+                tool_calls: None,
+                tool_call_id: None,
+                name: None,
+                reasoning_content: None,
+            }],
+            temperature: None,
+            max_tokens: None,
+            stream: None,
+            other: Default::default(),
+        }
+    }
+
+    fn upstream(status: u16) -> MindLinkError {
+        MindLinkError::Network {
+            message: "Request failed".to_string(),
+            url: None,
+            source: Some(UpstreamStatus::new(status, "").into()),
+        }
+    }
+
+    #[test]
+    fn test_only_backend_failures_fail_over() {
+        println!("🧪 Test: Errors that trigger failover");
+
+        for status in [401, 403, 429, 500, 503] {
+            assert!(should_fail_over(&upstream(status)), "status {}", status);
+        }
+        for status in [400, 404, 413] {
+            assert!(!should_fail_over(&upstream(status)), "status {}", status);
+        }
+
+        let unreachable = MindLinkError::Network {
+            message: "connection refused".to_string(),
+            url: None,
+            source: None,
+        };
+        assert!(should_fail_over(&unreachable));
+        assert!(should_fail_over(&MindLinkError::Authentication {
+            message: "No ChatGPT account is signed in".to_string(),
+            source: None,
+        }));
+        assert!(!should_fail_over(&MindLinkError::Configuration {
+            message: "bad".to_string(),
+            config_key: None,
+            source: None,
+        }));
+
+        println!("✅ Errors that trigger failover successful");
+    }
+
+    #[test]
+    fn test_provider_requests_and_urls() {
+        println!("🧪 Test: Fallback provider requests");
+
+        let mut local = provider("local", None);
+        local.model = Some("llama3.1:8b".to_string());
+        let sent = provider_request(&local, &request(), true);
+        assert_eq!(sent.model, "llama3.1:8b");
+        assert_eq!(sent.stream, Some(true));
+        assert_eq!(
+            provider_request(&provider("openai", None), &request(), false).model,
+            "gpt-5"
+        );
+
+        assert_eq!(completions_url(&local, None), None);
+        assert_eq!(
+            completions_url(&local, Some("http://127.0.0.1:3003/")).as_deref(),
+            Some("http://127.0.0.1:3003/v1/chat/completions")
+        );
+        assert_eq!(
+            completions_url(
+                &provider("openai", Some("https://api.openai.com/v1/")),
+                None
+            )
+            .as_deref(),
+            Some("https://api.openai.com/v1/chat/completions")
+        );
+
+        assert!(!FallbackChain::new(FailoverConfig {
+            enabled: true,
+            providers: Vec::new(),
+        })
+        .is_enabled());
+
+        println!("✅ Fallback provider requests successful");
+    }
+
+    #[tokio::test]
+    async fn test_chain_tries_providers_in_order() {
+        println!("🧪 Test: Fallback chain order");
+
+        let failing = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&failing)
+            .await;
+
+        let working = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer sk-test"))
+            .and(body_partial_json(
+                serde_json::json!({ "model": "gpt-4o-mini" }),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "id": "chatcmpl-1", "choices": [] })),
+            )
+            .expect(1)
+            .mount(&working)
+            .await;
+
+        let mut openai = provider("openai", Some(&format!("{}/v1", working.uri())));
+        openai.api_key = Some("sk-test".to_string());
+        openai.model = Some("gpt-4o-mini".to_string());
+        let chain = FallbackChain::new(FailoverConfig {
+            enabled: true,
+            providers: vec![
+                // Skipped: Bifrost is not running
+                provider("bifrost", None),
+                provider("backup", Some(&format!("{}/v1", failing.uri()))),
+                openai,
+            ],
+        });
+
+        let client = reqwest::Client::new();
+        let fallback = chain
+            .send(
+                &client,
+                &request(),
+                false,
+                None,
+                None,
+                Duration::from_secs(5),
+            )
+            .await
+            .unwrap();
+        assert_eq!(fallback.provider, "openai");
+        let body: serde_json::Value = fallback.response.json().await.unwrap();
+        assert_eq!(body["id"], "chatcmpl-1");
+
+        println!("✅ Fallback chain order successful");
+    }
+
+    #[tokio::test]
+    async fn test_local_provider_warms_its_model() {
+        println!("🧪 Test: Fallback to a local model through the warm pool");
+
+        let ollama = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/generate"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&ollama)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(serde_json::json!({ "model": "llama3" })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "id": "chatcmpl-1", "choices": [] })),
+            )
+            .mount(&ollama)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&ollama)
+            .await;
+
+        let local_models = LocalModelManager::new(&LocalModelsConfig {
+            ollama_url: ollama.uri(),
+            warm_pool_size: 1,
+        });
+        let local = |model: &str| {
+            let mut local = provider("ollama", Some(&format!("{}/v1", ollama.uri())));
+            local.model = Some(model.to_string());
+            FallbackChain::new(FailoverConfig {
+                enabled: true,
+                providers: vec![local],
+            })
+        };
+        let client = reqwest::Client::new();
+        let send = |chain: FallbackChain| {
+            let client = client.clone();
+            let local_models = &local_models;
+            async move {
+                chain
+                    .send(
+                        &client,
+                        &request(),
+                        false,
+                        None,
+                        Some(local_models),
+                        Duration::from_secs(5),
+                    )
+                    .await
+            }
+        };
+
+        assert_eq!(send(local("llama3")).await.unwrap().provider, "ollama");
+        let warm: Vec<String> = local_models
+            .warm_models()
+            .await
+            .into_iter()
+            .map(|model| model.name)
+            .collect();
+        assert_eq!(warm, vec!["llama3".to_string()]);
+
+        // A model the provider fails on takes no place in the pool
+        assert!(send(local("qwen")).await.is_err());
+        assert!(local_models.warm_models().await.is_empty());
+
+        // llama3 loaded, then evicted for qwen, which is released again
+        let keep_alive: Vec<i64> = ollama
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .filter(|request| request.url.path() == "/api/generate")
+            .map(|request| {
+                request.body_json::<serde_json::Value>().unwrap()["keep_alive"]
+                    .as_i64()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(keep_alive, vec![-1, -1, 0, 0]);
+
+        println!("✅ Fallback to a local model through the warm pool successful");
+    }
+
+    #[tokio::test]
+    async fn test_streams_are_forwarded_per_event() {
+        println!("🧪 Test: Forwarding fallback streams");
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                "data: {\"choices\":[{\"delta\":{\"content\":\"Hé\"}}]}\n\n: keep-alive\n\ndata: [DONE]\n\n",
+                "text/event-stream",
+            ))
+            .mount(&server)
+            .await;
+
+        let response = reqwest::Client::new()
+            .post(server.uri())
+            .send()
+            .await
+            .unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        assert!(forward_stream(response, &tx, Duration::from_secs(5)).await);
+        drop(tx);
+
+        let mut events = Vec::new();
+        while let Some(Ok(event)) = rx.recv().await {
+            events.push(event);
+        }
+        assert_eq!(
+            events,
+            vec![
+                "data: {\"choices\":[{\"delta\":{\"content\":\"Hé\"}}]}\n\n".to_string(),
+                "data: [DONE]\n\n".to_string(),
+            ]
+        );
+
+        println!("✅ Forwarding fallback streams successful");
+    }
+}
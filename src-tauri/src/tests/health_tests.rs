@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod health_tests {
+    use crate::health::{queue_health, ComponentHealth, HealthLevel, HealthReport};
+    use serde_json::json;
+
+    #[test]
+    fn test_all_components_ok() {
+        println!("🧪 Test: Overall health with all components ok");
+
+        let report = HealthReport::from_components(vec![
+            ComponentHealth::ok("server", true),
+            ComponentHealth::ok("tunnel", false),
+        ]);
+
+        assert_eq!(report.overall, HealthLevel::Ok);
+        assert!(
+            report.reason.is_none(),
+            "Healthy report should have no reason"
+        );
+
+        println!("✅ All-ok health report successful");
+    }
+
+    #[test]
+    fn test_non_critical_down_degrades_overall() {
+        println!("🧪 Test: Non-critical component down degrades overall health");
+
+        let report = HealthReport::from_components(vec![
+            ComponentHealth::ok("server", true),
+            ComponentHealth::ok("auth", true),
+            ComponentHealth::down("tunnel", "Tunnel is not connected", false),
+        ]);
+
+        assert_eq!(report.overall, HealthLevel::Degraded);
+        let reason = report.reason.expect("Degraded report should have a reason");
+        assert!(
+            reason.contains("tunnel down"),
+            "Reason should name the component"
+        );
+
+        println!("✅ Degraded health report successful");
+    }
+
+    #[test]
+    fn test_critical_down_takes_overall_down() {
+        println!("🧪 Test: Critical component down takes overall health down");
+
+        let report = HealthReport::from_components(vec![
+            ComponentHealth::ok("server", true),
+            ComponentHealth::down("auth", "Not logged in to ChatGPT", true),
+            ComponentHealth::degraded("tunnel", "Public URL unreachable", false),
+        ]);
+
+        assert_eq!(report.overall, HealthLevel::Down);
+
+        let json = serde_json::to_value(&report).expect("Report should serialize");
+        assert_eq!(json["overall"], "down");
+        assert_eq!(json["components"][2]["level"], "degraded");
+
+        println!("✅ Down health report successful");
+    }
+
+    #[test]
+    fn test_level_change_detection() {
+        println!("🧪 Test: Health level change detection");
+
+        let healthy = HealthReport::from_components(vec![ComponentHealth::ok("tunnel", false)]);
+        let same = healthy.with_component(ComponentHealth::ok("tunnel", false));
+        let changed = healthy.with_component(ComponentHealth::down(
+            "tunnel",
+            "Tunnel is not connected",
+            false,
+        ));
+
+        assert!(
+            !healthy.levels_differ(&same),
+            "Same levels should not differ"
+        );
+        assert!(
+            healthy.levels_differ(&changed),
+            "Changed levels should differ"
+        );
+
+        println!("✅ Health level change detection successful");
+    }
+
+    #[test]
+    fn test_queue_health_and_details() {
+        println!("🧪 Test: Queue health and component details");
+
+        let idle = queue_health(2, 1, 0, Some(8));
+        assert_eq!(idle.level, HealthLevel::Ok);
+        assert_eq!(idle.details["in_flight"], json!(2));
+        assert_eq!(idle.details["max_in_flight"], json!(8));
+        assert_eq!(idle.details["active_streams"], json!(1));
+
+        let full = queue_health(8, 3, 5, Some(8));
+        assert_eq!(full.level, HealthLevel::Degraded);
+        assert!(!full.critical);
+        assert_eq!(full.details["queued_jobs"], json!(5));
+
+        // Without backpressure there is no limit to reach
+        let unlimited = queue_health(500, 0, 0, None);
+        assert_eq!(unlimited.level, HealthLevel::Ok);
+        assert_eq!(unlimited.details["max_in_flight"], json!(null));
+
+        // Details are part of the body monitors read, not of the level
+        let report = HealthReport::from_components(vec![idle.clone()]);
+        let body = serde_json::to_value(&report).unwrap();
+        assert_eq!(body["components"][0]["details"]["in_flight"], json!(2));
+        assert!(!report.levels_differ(&report.with_component(queue_health(3, 1, 0, Some(8)))));
+
+        println!("✅ Queue health and component details successful");
+    }
+}
@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod jobs_tests {
+    use crate::jobs::{events, JobStatus, JobStore};
+    use crate::managers::config_manager::JobConfig;
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::oneshot;
+
+    fn store(max_concurrent: usize) -> Arc<JobStore> {
+        Arc::new(JobStore::new(&JobConfig {
+            enabled: true,
+            max_concurrent,
+            retention_secs: 3600,
+        }))
+    }
+
+    async fn wait_for(store: &JobStore, id: &str, status: JobStatus) {
+        for _ in 0..100 {
+            if store.job(id).map(|job| job.status) == Some(status) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("job {} never reached {:?}", id, status);
+    }
+
+    #[tokio::test]
+    async fn test_job_completes_with_result() {
+        println!("🧪 Test: Job completion");
+
+        let store = store(2);
+        let job = store.submit("gpt-5", async {
+            (200, json!({"object": "chat.completion"}))
+        });
+        assert!(job.id.starts_with("job_"));
+        assert_eq!(job.model, "gpt-5");
+
+        wait_for(&store, &job.id, JobStatus::Completed).await;
+        let job = store.job(&job.id).unwrap();
+        assert_eq!(job.status_code, Some(200));
+        assert_eq!(job.result.unwrap()["object"], "chat.completion");
+        assert!(job.error.is_none());
+        assert!(job.finished_at.is_some());
+
+        let failed = store.submit("gpt-5", async { (429, json!({"error": {}})) });
+        wait_for(&store, &failed.id, JobStatus::Failed).await;
+        assert!(store.job(&failed.id).unwrap().error.is_some());
+        assert!(store.job("job_missing").is_none());
+
+        println!("✅ Job completion successful");
+    }
+
+    #[tokio::test]
+    async fn test_jobs_wait_for_a_free_slot_and_can_be_cancelled() {
+        println!("🧪 Test: Job queueing and cancellation");
+
+        let store = store(1);
+        let (release, released) = oneshot::channel::<()>();
+        let first = store.submit("gpt-5", async move {
+            let _ = released.await;
+            (200, json!({}))
+        });
+        let second = store.submit("gpt-5", async { (200, json!({})) });
+
+        wait_for(&store, &first.id, JobStatus::InProgress).await;
+        assert_eq!(store.job(&second.id).unwrap().status, JobStatus::Queued);
+
+        let cancelled = store.cancel(&second.id).unwrap();
+        assert_eq!(cancelled.status, JobStatus::Cancelled);
+
+        release.send(()).unwrap();
+        wait_for(&store, &first.id, JobStatus::Completed).await;
+        // A finished job stays as it is
+        assert_eq!(
+            store.cancel(&first.id).unwrap().status,
+            JobStatus::Completed
+        );
+        assert_eq!(store.job(&second.id).unwrap().status, JobStatus::Cancelled);
+        assert!(store.cancel("job_missing").is_none());
+
+        println!("✅ Job queueing and cancellation successful");
+    }
+
+    #[tokio::test]
+    async fn test_events_end_with_done() {
+        println!("🧪 Test: Job events");
+
+        let store = store(1);
+        let (release, released) = oneshot::channel::<()>();
+        let job = store.submit("gpt-5", async move {
+            let _ = released.await;
+            (200, json!({"id": "chatcmpl-1"}))
+        });
+
+        let mut rx = events(store.subscribe(&job.id).unwrap());
+        let first = rx.recv().await.unwrap().unwrap();
+        assert!(first.starts_with("event: status\n"));
+
+        release.send(()).unwrap();
+        let mut last = String::new();
+        while let Some(Ok(event)) = rx.recv().await {
+            last = event;
+        }
+        assert!(last.starts_with("event: done\n"));
+        assert!(last.contains("chatcmpl-1"));
+
+        println!("✅ Job events successful");
+    }
+}
@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod language_tests {
+    use crate::language::{detect_language, prompt_text, route_for_language, DetectedLanguage};
+    use crate::managers::config_manager::LanguageDetectionConfig;
+    use crate::managers::server_manager::Message;
+
+    fn message(role: &str, content: &str) -> Message {
+        Message {
+            role: role.to_string(),
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            name: None,
+            reasoning_content: None,
+        }
+    }
+
+    #[test]
+    fn test_detects_prompt_language() {
+        println!("🧪 Test: Prompt language detection");
+
+        let japanese = detect_language("今日はとても良い天気ですね。散歩に行きましょうか。", 10)
+            .expect("Japanese should be detected");
+        assert_eq!(japanese.code, "jpn");
+        assert_eq!(japanese.name, "Japanese");
+
+        let german = detect_language(
+            "Könnten Sie mir bitte erklären, wie diese Funktion mit großen Dateien umgeht?",
+            20,
+        )
+        .expect("German should be detected");
+        assert_eq!(german.code, "deu");
+
+        // Too short to classify
+        assert!(detect_language("Hi there", 20).is_none());
+
+        println!("✅ Prompt language detection successful");
+    }
+
+    #[test]
+    fn test_prompt_text_uses_latest_user_message() {
+        println!("🧪 Test: Prompt text selection");
+
+        let messages = vec![
+            message("system", "You are a helpful assistant"),
+            message("user", "First question"),
+            message("assistant", "An answer"),
+            message("user", "Second question"),
+            message("assistant", ""),
+        ];
+        assert_eq!(prompt_text(&messages), Some("Second question"));
+        assert_eq!(
+            prompt_text(&[message("system", "Only a system prompt")]),
+            None
+        );
+
+        println!("✅ Prompt text selection successful");
+    }
+
+    #[test]
+    fn test_language_routes_require_confidence() {
+        println!("🧪 Test: Language routing thresholds");
+
+        let mut config = LanguageDetectionConfig {
+            enabled: true,
+            ..LanguageDetectionConfig::default()
+        };
+        config
+            .routes
+            .insert("jpn".to_string(), "gpt-4o".to_string());
+
+        let mut detected = DetectedLanguage {
+            code: "jpn".to_string(),
+            name: "Japanese".to_string(),
+            confidence: 0.9,
+            reliable: true,
+        };
+        assert_eq!(route_for_language(&config, &detected), Some("gpt-4o"));
+
+        detected.confidence = 0.3;
+        assert_eq!(route_for_language(&config, &detected), None);
+
+        detected.confidence = 0.9;
+        detected.reliable = false;
+        assert_eq!(route_for_language(&config, &detected), None);
+
+        detected.reliable = true;
+        detected.code = "fra".to_string();
+        assert_eq!(route_for_language(&config, &detected), None);
+
+        println!("✅ Language routing thresholds successful");
+    }
+}
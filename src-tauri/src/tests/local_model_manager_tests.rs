@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod local_model_manager_tests {
+    use crate::managers::local_model_manager::WarmPool;
+
+    #[test]
+    fn test_warm_pool_evicts_least_recently_used() {
+        println!("🧪 Test: Warm pool LRU eviction");
+
+        let mut pool = WarmPool::new(2);
+        assert!(pool.touch("llama3").is_empty());
+        assert!(pool.touch("mistral").is_empty());
+
+        // Using llama3 again makes mistral the least recently used
+        assert!(pool.touch("llama3").is_empty());
+        let evicted = pool.touch("qwen");
+
+        assert_eq!(evicted, vec!["mistral".to_string()]);
+        assert!(
+            pool.contains("llama3"),
+            "Recently used model should stay warm"
+        );
+        assert!(pool.contains("qwen"), "New model should be warm");
+
+        let names: Vec<String> = pool.models().into_iter().map(|m| m.name).collect();
+        assert_eq!(names, vec!["qwen".to_string(), "llama3".to_string()]);
+
+        println!("✅ Warm pool LRU eviction successful");
+    }
+
+    #[test]
+    fn test_warm_pool_resize() {
+        println!("🧪 Test: Warm pool resize");
+
+        let mut pool = WarmPool::new(3);
+        pool.touch("a");
+        pool.touch("b");
+        pool.touch("c");
+
+        let evicted = pool.resize(1);
+        assert_eq!(evicted, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(pool.models().len(), 1);
+
+        // A zero-sized pool keeps nothing warm
+        assert_eq!(pool.resize(0), vec!["c".to_string()]);
+        assert_eq!(pool.touch("d"), vec!["d".to_string()]);
+
+        println!("✅ Warm pool resize successful");
+    }
+}
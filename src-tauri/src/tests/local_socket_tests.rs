@@ -0,0 +1,123 @@
+#[cfg(all(test, unix))]
+mod local_socket_tests {
+    use crate::local_socket::LocalSocket;
+    use axum::{extract::ConnectInfo, routing::get, Router};
+    use hyper_util::rt::TokioExecutor;
+    use hyper_util::server::conn::auto::Builder;
+    use std::net::SocketAddr;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::UnixStream;
+    use tokio::sync::oneshot;
+
+    async fn fetch(socket: &Path, route: &str) -> String {
+        let mut stream = UnixStream::connect(socket).await.unwrap();
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+            route
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    fn app() -> Router {
+        Router::new()
+            .route(
+                "/peer",
+                get(|ConnectInfo(peer): ConnectInfo<SocketAddr>| async move {
+                    peer.ip().to_string()
+                }),
+            )
+            .route(
+                "/slow",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                    "done"
+                }),
+            )
+    }
+
+    #[tokio::test]
+    async fn test_api_is_served_on_the_socket() {
+        println!("🧪 Test: API on a Unix socket");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("api.sock");
+        let socket = LocalSocket::bind(path.to_str().unwrap()).await.unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let (stop, stopped) = oneshot::channel::<()>();
+        let server = tokio::spawn(
+            socket.serve(app(), Builder::new(TokioExecutor::new()), async {
+                let _ = stopped.await;
+            }),
+        );
+
+        // Clients on the socket count as local to the access policy
+        let response = fetch(&path, "/peer").await;
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.ends_with("127.0.0.1"));
+
+        // A second server doesn't take the socket over
+        assert!(LocalSocket::bind(path.to_str().unwrap()).await.is_err());
+
+        stop.send(()).unwrap();
+        server.await.unwrap();
+        assert!(!path.exists());
+
+        println!("✅ API on a Unix socket successful");
+    }
+
+    #[tokio::test]
+    async fn test_stale_socket_is_replaced() {
+        println!("🧪 Test: Stale socket files");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("api.sock");
+        // Left behind by a server that is gone
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+        assert!(LocalSocket::bind(path.to_str().unwrap()).await.is_ok());
+
+        // Anything else is left alone
+        let notes = dir.path().join("notes.txt");
+        std::fs::write(&notes, "keep").unwrap();
+        assert!(LocalSocket::bind(notes.to_str().unwrap()).await.is_err());
+        assert_eq!(std::fs::read_to_string(&notes).unwrap(), "keep");
+
+        println!("✅ Stale socket files successful");
+    }
+
+    #[tokio::test]
+    async fn test_requests_finish_on_shutdown() {
+        println!("🧪 Test: Local socket shutdown");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("api.sock");
+        let socket = LocalSocket::bind(path.to_str().unwrap()).await.unwrap();
+        let (stop, stopped) = oneshot::channel::<()>();
+        let server = tokio::spawn(
+            socket.serve(app(), Builder::new(TokioExecutor::new()), async {
+                let _ = stopped.await;
+            }),
+        );
+
+        let client_path = path.clone();
+        let slow = tokio::spawn(async move { fetch(&client_path, "/slow").await });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        stop.send(()).unwrap();
+
+        // No new clients while the open request finishes
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(UnixStream::connect(&path).await.is_err());
+        assert!(slow.await.unwrap().ends_with("done"));
+        server.await.unwrap();
+
+        println!("✅ Local socket shutdown successful");
+    }
+}
@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod localtunnel_tests {
+    use crate::localtunnel::{lease_client, request_lease, serve, Lease};
+    use serde_json::json;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_request_lease() {
+        println!("🧪 Test: localtunnel lease");
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/"))
+            .and(query_param("new", ""))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "quiet-fox",
+                "port": 40123,
+                "max_conn_count": 10,
+                "url": "https://quiet-fox.loca.lt",
+            })))
+            .mount(&server)
+            .await;
+
+        let lease = request_lease(&lease_client(), &format!("{}/", server.uri()))
+            .await
+            .unwrap();
+        assert_eq!(
+            lease,
+            Lease {
+                id: "quiet-fox".to_string(),
+                url: "https://quiet-fox.loca.lt".to_string(),
+                port: 40123,
+                max_conn_count: Some(10),
+                ip: None,
+            }
+        );
+
+        let refusing = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&refusing)
+            .await;
+        let refused = request_lease(&lease_client(), &refusing.uri()).await;
+        assert!(refused.is_err());
+
+        println!("✅ localtunnel lease successful");
+    }
+
+    #[tokio::test]
+    async fn test_relay_connections() {
+        println!("🧪 Test: localtunnel relay");
+
+        let remote = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let local = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let lease = Lease {
+            id: "quiet-fox".to_string(),
+            url: "https://quiet-fox.loca.lt".to_string(),
+            port: remote.local_addr().unwrap().port(),
+            max_conn_count: Some(1),
+            ip: None,
+        };
+        let relays = serve(
+            "http://127.0.0.1",
+            &lease,
+            local.local_addr().unwrap().port(),
+        )
+        .unwrap();
+
+        // A request for the public URL arrives over the relay connection
+        for _ in 0..2 {
+            let (mut inbound, _) = remote.accept().await.unwrap();
+            inbound.write_all(b"ping").await.unwrap();
+
+            let (mut served, _) = local.accept().await.unwrap();
+            let mut request = [0; 4];
+            served.read_exact(&mut request).await.unwrap();
+            assert_eq!(&request, b"ping");
+            served.write_all(b"pong").await.unwrap();
+            drop(served);
+
+            let mut response = Vec::new();
+            inbound.read_to_end(&mut response).await.unwrap();
+            assert_eq!(response, b"pong");
+        }
+
+        relays.abort();
+        assert!(relays.await.unwrap_err().is_cancelled());
+
+        println!("✅ localtunnel relay successful");
+    }
+}
@@ -0,0 +1,184 @@
+#[cfg(test)]
+mod metrics_tests {
+    use crate::middleware::metrics::{track_tunnel_traffic, Metrics, TunnelTracking};
+    use crate::request_log::StreamThroughput;
+    use axum::{
+        body::Body,
+        http::{header, Request},
+        routing::post,
+        Router,
+    };
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_request_counters_and_histogram() {
+        println!("🧪 Test: Request counters and latency histogram");
+
+        let metrics = Metrics::new();
+        metrics.record_request("POST", "/v1/chat/completions", 200, 0.03);
+        metrics.record_request("POST", "/v1/chat/completions", 200, 2.0);
+        metrics.record_request("GET", "/health", 403, 0.001);
+
+        let output = metrics.render();
+        assert!(output.contains(
+            "mindlink_http_requests_total{method=\"POST\",route=\"/v1/chat/completions\",status=\"200\"} 2"
+        ));
+        assert!(output.contains(
+            "mindlink_http_requests_total{method=\"GET\",route=\"/health\",status=\"403\"} 1"
+        ));
+        // Buckets are cumulative
+        assert!(output.contains(
+            "mindlink_http_request_duration_seconds_bucket{route=\"/v1/chat/completions\",le=\"0.05\"} 1"
+        ));
+        assert!(output.contains(
+            "mindlink_http_request_duration_seconds_bucket{route=\"/v1/chat/completions\",le=\"2.5\"} 2"
+        ));
+        assert!(output.contains(
+            "mindlink_http_request_duration_seconds_count{route=\"/v1/chat/completions\"} 2"
+        ));
+
+        println!("✅ Request counters and latency histogram successful");
+    }
+
+    #[test]
+    fn test_upstream_errors_and_active_streams() {
+        println!("🧪 Test: Upstream errors and active stream gauge");
+
+        let metrics = Arc::new(Metrics::new());
+        metrics.record_upstream_error("chatgpt");
+        metrics.record_upstream_error("chatgpt");
+
+        let first = metrics.stream_started();
+        let second = metrics.stream_started();
+        assert_eq!(metrics.active_streams(), 2);
+        drop(first);
+        assert_eq!(metrics.active_streams(), 1);
+
+        let output = metrics.render();
+        assert!(output.contains("mindlink_upstream_errors_total{upstream=\"chatgpt\"} 2"));
+        assert!(output.contains("mindlink_active_streams 1"));
+
+        drop(second);
+        assert_eq!(metrics.active_streams(), 0);
+
+        metrics.record_stream(&StreamThroughput {
+            ttft_ms: 800.0,
+            generation_ms: 4000.0,
+            completion_tokens: Some(200),
+            tokens_per_second: Some(50.0),
+        });
+        let output = metrics.render();
+        assert!(output.contains("mindlink_stream_first_token_seconds_bucket{le=\"0.5\"} 0"));
+        assert!(output.contains("mindlink_stream_first_token_seconds_bucket{le=\"1\"} 1"));
+        assert!(output.contains("mindlink_stream_tokens_total 200"));
+        assert!(output.contains("mindlink_stream_generation_seconds_total 4"));
+
+        println!("✅ Upstream errors and active stream gauge successful");
+    }
+
+    #[test]
+    fn test_tunnel_probes() {
+        println!("🧪 Test: Tunnel probe round trips");
+
+        let metrics = Metrics::new();
+        let tunnel = "mindlink.example.com";
+        metrics.record_tunnel_probe(tunnel, Some(Duration::from_millis(120)));
+        metrics.record_tunnel_probe(tunnel, Some(Duration::from_millis(80)));
+        metrics.record_tunnel_probe(tunnel, None);
+
+        let traffic = metrics.tunnel_traffic();
+        assert_eq!(traffic.len(), 1);
+        assert_eq!(traffic[0].tunnel, tunnel);
+        assert_eq!(traffic[0].probes, 3);
+        assert_eq!(traffic[0].probe_failures, 1);
+        assert_eq!(traffic[0].last_round_trip_ms, Some(80.0));
+        assert!((traffic[0].mean_round_trip_ms.unwrap() - 100.0).abs() < 1e-9);
+
+        let output = metrics.render();
+        assert!(output.contains(
+            "mindlink_tunnel_round_trip_seconds_bucket{tunnel=\"mindlink.example.com\",le=\"0.1\"} 1"
+        ));
+        assert!(output.contains(
+            "mindlink_tunnel_round_trip_seconds_count{tunnel=\"mindlink.example.com\"} 2"
+        ));
+        assert!(output
+            .contains("mindlink_tunnel_probe_failures_total{tunnel=\"mindlink.example.com\"} 1"));
+
+        println!("✅ Tunnel probe round trips successful");
+    }
+
+    #[tokio::test]
+    async fn test_tunnel_traffic() {
+        println!("🧪 Test: Traffic through the tunnel counted apart from local requests");
+
+        let metrics = Arc::new(Metrics::new());
+        let tunnel_url = Arc::new(RwLock::new(None));
+        let app = Router::new()
+            .route("/echo", post(|body: String| async move { body }))
+            .route(
+                "/stream",
+                post(|| async {
+                    let chunks = ["data: a\n\n", "data: bc\n\n"].map(Ok::<_, std::io::Error>);
+                    Body::from_stream(futures_util::stream::iter(chunks))
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                TunnelTracking {
+                    metrics: metrics.clone(),
+                    tunnel_url: tunnel_url.clone(),
+                },
+                track_tunnel_traffic,
+            ));
+        let send = |host: &str, body: &str| {
+            let path = if body.is_empty() { "/stream" } else { "/echo" };
+            let request = Request::post(path)
+                .header(header::HOST, host)
+                .header(header::CONTENT_LENGTH, body.len())
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            app.clone().oneshot(request)
+        };
+
+        // Nothing is tunnel traffic before a tunnel is up
+        send("random-words.trycloudflare.com", "hello")
+            .await
+            .unwrap();
+        assert!(metrics.tunnel_traffic().is_empty());
+
+        *tunnel_url.write().await = Some("https://random-words.trycloudflare.com".to_string());
+        let response = send("Random-Words.trycloudflare.com:443", "hello")
+            .await
+            .unwrap();
+        axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        // Streamed replies are counted once they end
+        let response = send("random-words.trycloudflare.com", "").await.unwrap();
+        axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        send("127.0.0.1:3001", "local request").await.unwrap();
+
+        let traffic = metrics.tunnel_traffic();
+        assert_eq!(traffic.len(), 1);
+        assert_eq!(traffic[0].tunnel, "random-words.trycloudflare.com");
+        assert_eq!(traffic[0].requests, 2);
+        assert_eq!(traffic[0].bytes_received, 5);
+        assert_eq!(traffic[0].bytes_sent, 5 + 19);
+        assert_eq!(traffic[0].probes, 0);
+        assert_eq!(traffic[0].mean_round_trip_ms, None);
+
+        let output = metrics.render();
+        assert!(output.contains(
+            "mindlink_tunnel_requests_total{tunnel=\"random-words.trycloudflare.com\"} 2"
+        ));
+        assert!(output.contains(
+            "mindlink_tunnel_bytes_total{tunnel=\"random-words.trycloudflare.com\",direction=\"sent\"} 24"
+        ));
+
+        println!("✅ Traffic through the tunnel counted apart from local requests successful");
+    }
+}
@@ -13,12 +13,78 @@
 //! - [`bifrost_manager_tests`] - Binary management and process control
 //! - [`tunnel_manager_tests`] - Cloudflare tunnel operations
 //! - [`server_manager_tests`] - HTTP server lifecycle and configuration
+//! - [`health_tests`] - Health level aggregation and change detection
+//! - [`local_model_manager_tests`] - Local model warm pool eviction
+//! - [`access_control_tests`] - Client IP allow/deny lists and forwarding headers
+//! - [`tool_emulation_tests`] - Function-calling emulation prompt and output parsing
+//! - [`metrics_tests`] - Prometheus metric recording and rendering
+//! - [`plugin_manager_tests`] - Plugin manifest validation and discovery
+//! - [`analytics_tests`] - Analytics write-behind flushing and sampling
+//! - [`request_id_tests`] - Request ID assignment and log correlation
+//! - [`ollama_tests`] - Ollama request translation and response shapes
+//! - [`model_catalog_tests`] - Model list merging and caching
+//! - [`language_tests`] - Prompt language detection and routing
+//! - [`stream_continuation_tests`] - Stitching continuations of truncated streams
+//! - [`authorized_apps_tests`] - Per-app API keys and model restrictions
+//! - [`security_report_tests`] - Exposure findings and scoring
+//! - [`power_tests`] - Power source detection and the battery power saver
+//! - [`prompt_templates_tests`] - System prompts and prompt template rendering
+//! - [`conversations_tests`] - Upstream conversation continuity
+//! - [`startup_summary_tests`] - Startup configuration summary and redaction
+//! - [`events_tests`] - Typed frontend event contract
+//! - [`batches_tests`] - Batch input validation, execution and persistence
+//! - [`canary_tests`] - Canary traffic assignment and automatic rollback
+//! - [`api_error_tests`] - OpenAI error bodies and upstream status mapping
+//! - [`config_dry_run_tests`] - Replaying journaled requests against a proposed config
+//! - [`error_feed_tests`] - Recent error history and suggested remedies
+//! - [`accounts_tests`] - Account ranking, rate limit cooldowns and account names
+//! - [`error_tests`] - Recovery actions offered for each error
+//! - [`failover_tests`] - Fallback provider selection and stream forwarding
+//! - [`auth_probe_tests`] - Session probe outcomes and their effect on auth health
+//! - [`bundles_tests`] - Signed export bundles, signer trust and merge conflicts
+//! - [`jobs_tests`] - Background job queueing, cancellation and events
+//! - [`reasoning_tests`] - Reasoning parameters and streamed reasoning summaries
+//! - [`ping_tests`] - Ping sequence and connection quality reports
+//! - [`redaction_tests`] - Prompt redaction rules and masking
+//! - [`binary_drift_tests`] - Managed binary drift and PATH shadowing
+//! - [`backpressure_tests`] - 429 and Retry-After under overload, overload events
+//! - [`shutdown_tests`] - Shutdown step ordering, hard timeout and exit codes
+//! - [`capture_tests`] - Sanitized request capture, retention and replay headers
+//! - [`openapi_tests`] - OpenAPI document coverage and schema references
+//! - [`websocket_tests`] - WebSocket request mapping and SSE-to-message translation
+//! - [`budgets_tests`] - Token budget periods, persistence and enforcement
+//! - [`shadow_tests`] - Shadow traffic mirroring and answer comparison
+//! - [`moderation_tests`] - Moderation rules, provider and pre-flight refusal
+//! - [`request_log_tests`] - Request phase timing and the request inspector log
+//! - [`local_socket_tests`] - API server on a Unix domain socket
+//! - [`post_processing_tests`] - Post-processing rules for completion text
+//! - [`request_transforms_tests`] - Transformation rules for incoming requests
+//! - [`azure_tests`] - Azure OpenAI deployment routes
+//! - [`token_store_tests`] - Tokens in the OS keyring and the file fallback
+//! - [`secret_box_tests`] - Encryption of credentials at rest
+//! - [`device_auth_tests`] - Device-code sign-in requests and polling
+//! - [`session_expiry_tests`] - Warnings before the ChatGPT session runs out
+//! - [`proxy_tests`] - Outbound proxy for OpenAI and ChatGPT traffic
+//! - [`anthropic_tests`] - Chat completions translated to and from the Claude Messages API
+//! - [`tunnel_tokens_tests`] - Rotation and storage of the tokens of tunnel clients
+//! - [`plan_tests`] - ChatGPT plans and the concurrency they sustain
+//! - [`cloudflare_access_tests`] - Cloudflare Access provisioning and JWT checks
+//! - [`tunnel_webhooks_tests`] - Signed delivery of tunnel URL changes to webhooks
+//! - [`pairing_qr_tests`] - PNG and SVG QR codes of the pairing data
+//! - [`tunnel_auth_tests`] - Tokens required of requests through the tunnel
+//! - [`tunnel_pids_tests`] - PID files and orphan cleanup of tunnel processes
+//! - [`localtunnel_tests`] - Leases and relays of the fallback tunnel
+//! - [`tunnel_limits_tests`] - Connection and egress limits of the tunnel
+//! - [`tunnel_schedule_tests`] - Availability windows of the tunnel
+//! - [`cloudflared_release_tests`] - Checksums of cloudflared releases and download verification
+//! - [`downloads_tests`] - Download progress and resumption of binaries
 //!
 //! ### Integration Tests  
 //! Test component interactions and cross-system workflows:
 //! - [`bifrost_integration_test`] - End-to-end binary management workflows
 //! - [`login_and_serve_integration_test`] - Complete service startup flows
 //! - [`tauri_commands_integration_test`] - Command handler integration
+//! - [`self_healing_scenarios`] - Declarative failure scenarios for the self-healing policy
 //!
 //! ### End-to-End Tests
 //! Test complete user workflows and system behavior:
@@ -59,11 +125,77 @@
 //! - **Error Simulation**: Comprehensive error condition testing
 
 // Unit test modules
+pub mod access_control_tests;
+pub mod accounts_tests;
+pub mod analytics_tests;
+pub mod anthropic_tests;
+pub mod api_error_tests;
 pub mod auth_manager_tests;
+pub mod auth_probe_tests;
+pub mod authorized_apps_tests;
+pub mod azure_tests;
+pub mod backpressure_tests;
+pub mod batches_tests;
 pub mod bifrost_manager_tests;
+pub mod binary_drift_tests;
+pub mod budgets_tests;
+pub mod bundles_tests;
+pub mod canary_tests;
+pub mod capture_tests;
+pub mod cloudflare_access_tests;
+pub mod cloudflared_release_tests;
+pub mod config_dry_run_tests;
 pub mod config_manager_tests;
+pub mod conversations_tests;
+pub mod device_auth_tests;
+pub mod downloads_tests;
+pub mod error_feed_tests;
+pub mod error_tests;
+pub mod events_tests;
+pub mod failover_tests;
+pub mod health_tests;
+pub mod jobs_tests;
+pub mod language_tests;
+pub mod local_model_manager_tests;
+pub mod local_socket_tests;
+pub mod localtunnel_tests;
+pub mod metrics_tests;
+pub mod model_catalog_tests;
+pub mod moderation_tests;
+pub mod ollama_tests;
+pub mod openapi_tests;
+pub mod pairing_qr_tests;
+pub mod ping_tests;
+pub mod plan_tests;
+pub mod plugin_manager_tests;
+pub mod post_processing_tests;
+pub mod power_tests;
+pub mod prompt_templates_tests;
+pub mod proxy_tests;
+pub mod reasoning_tests;
+pub mod redaction_tests;
+pub mod request_id_tests;
+pub mod request_log_tests;
+pub mod request_transforms_tests;
+pub mod secret_box_tests;
+pub mod security_report_tests;
+pub mod self_healing_scenarios;
 pub mod server_manager_tests;
+pub mod session_expiry_tests;
+pub mod shadow_tests;
+pub mod shutdown_tests;
+pub mod startup_summary_tests;
+pub mod stream_continuation_tests;
+pub mod token_store_tests;
+pub mod tool_emulation_tests;
+pub mod tunnel_auth_tests;
+pub mod tunnel_limits_tests;
 pub mod tunnel_manager_tests;
+pub mod tunnel_pids_tests;
+pub mod tunnel_schedule_tests;
+pub mod tunnel_tokens_tests;
+pub mod tunnel_webhooks_tests;
+pub mod websocket_tests;
 
 // Integration test modules
 // pub mod bifrost_integration_test; // Disabled for coverage - service dependencies
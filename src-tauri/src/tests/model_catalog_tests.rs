@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod model_catalog_tests {
+    use crate::model_catalog::{
+        merge_models, parse_model_ids, parse_upstream_models, ModelCatalog,
+    };
+    use serde_json::json;
+    use std::time::Duration;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn ids(models: &[crate::managers::server_manager::Model]) -> Vec<&str> {
+        models.iter().map(|m| m.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_parse_model_listings() {
+        println!("🧪 Test: Parsing account and Bifrost model listings");
+
+        let account = json!({
+            "models": [
+                { "slug": "gpt-4o", "title": "GPT-4o" },
+                { "slug": "o3", "title": "o3" },
+                { "title": "missing slug" }
+            ]
+        });
+        assert_eq!(parse_upstream_models(&account), vec!["gpt-4o", "o3"]);
+
+        let bifrost = json!({
+            "object": "list",
+            "data": [{ "id": "claude-sonnet" }, { "id": "llama3" }]
+        });
+        assert_eq!(parse_model_ids(&bifrost), vec!["claude-sonnet", "llama3"]);
+        assert!(parse_model_ids(&json!({})).is_empty());
+
+        println!("✅ Model listing parsing successful");
+    }
+
+    #[test]
+    fn test_merge_prefers_account_models() {
+        println!("🧪 Test: Merging account and Bifrost models");
+
+        let upstream = vec!["gpt-4o".to_string(), "o3".to_string()];
+        let bifrost = vec!["llama3".to_string(), "gpt-4o".to_string()];
+        let models = merge_models(&upstream, &bifrost);
+
+        assert_eq!(ids(&models), vec!["gpt-4o", "o3", "llama3"]);
+        assert_eq!(models[0].owned_by, "mindlink");
+        assert_eq!(models[2].owned_by, "bifrost");
+
+        println!("✅ Model merging successful");
+    }
+
+    #[tokio::test]
+    async fn test_catalog_caches_and_invalidates() {
+        println!("🧪 Test: Model catalog caching");
+
+        let catalog = ModelCatalog::new(Duration::from_secs(60));
+        assert!(catalog.cached().await.is_none());
+
+        // Without a token nothing is fetched and the defaults are served
+        let models = catalog.refresh(None).await;
+        assert_eq!(ids(&models), vec!["gpt-5", "codex-mini"]);
+        let cached = catalog.cached().await.expect("fresh cache");
+        assert_eq!(ids(&cached), ids(&models));
+
+        // A Bifrost change invalidates the cache
+        catalog
+            .set_bifrost_url(Some("http://127.0.0.1:1".to_string()))
+            .await;
+        assert!(catalog.cached().await.is_none());
+
+        // An unreachable Bifrost contributes nothing
+        let models = catalog.refresh(None).await;
+        assert_eq!(ids(&models), vec!["gpt-5", "codex-mini"]);
+
+        let expired = ModelCatalog::new(Duration::ZERO);
+        expired.refresh(None).await;
+        assert!(expired.cached().await.is_none());
+
+        println!("✅ Model catalog caching successful");
+    }
+
+    #[tokio::test]
+    async fn test_bifrost_models_are_told_apart() {
+        println!("🧪 Test: Telling Bifrost models from account models");
+
+        let bifrost = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": [{ "id": "llama3" }, { "id": "gpt-5" }]
+            })))
+            .mount(&bifrost)
+            .await;
+
+        let catalog = ModelCatalog::new(Duration::from_secs(60));
+        catalog.set_bifrost_url(Some(bifrost.uri())).await;
+        // Nothing is known before the first listing
+        assert!(!catalog.is_bifrost_model("llama3").await);
+
+        let models = catalog.refresh(None).await;
+        assert_eq!(ids(&models), vec!["gpt-5", "codex-mini", "llama3"]);
+        assert!(catalog.is_bifrost_model("llama3").await);
+        // The account serves the models it lists itself
+        assert!(!catalog.is_bifrost_model("gpt-5").await);
+        assert!(!catalog.is_bifrost_model("unknown").await);
+
+        println!("✅ Bifrost model lookup successful");
+    }
+}
@@ -0,0 +1,235 @@
+#[cfg(test)]
+mod moderation_tests {
+    use crate::managers::config_manager::{FallbackProvider, ModerationConfig, ModerationRule};
+    use crate::middleware::moderation::screen_prompts;
+    use crate::moderation::{moderation_inputs, prompt_texts, Moderator, RULES_MODEL};
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+        routing::post,
+        Router,
+    };
+    use serde_json::json;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tower::ServiceExt;
+    use wiremock::matchers::{body_partial_json, header as header_matcher, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn config(provider: Option<String>, fail_closed: bool) -> ModerationConfig {
+        ModerationConfig {
+            preflight: true,
+            provider: provider.map(|base_url| FallbackProvider {
+                name: "openai".to_string(),
+                base_url: Some(base_url),
+                api_key: Some("sk-test".to_string()),
+                model: Some("omni-moderation-latest".to_string()),
+            }),
+            rules: vec![ModerationRule {
+                category: "internal-project".to_string(),
+                pattern: r"(?i)\bproject\s+falcon\b".to_string(),
+            }],
+            fail_closed,
+        }
+    }
+
+    fn moderator(config: &ModerationConfig) -> Moderator {
+        Moderator::from_config(config, reqwest::Client::new(), Duration::from_secs(5))
+            .unwrap()
+            .expect("the config has a rule")
+    }
+
+    #[test]
+    fn test_inputs_and_prompts_are_extracted() {
+        println!("🧪 Test: Moderation input extraction");
+
+        assert_eq!(
+            moderation_inputs(&json!("hi")),
+            Some(vec!["hi".to_string()])
+        );
+        assert_eq!(
+            moderation_inputs(&json!(["a", "b"])),
+            Some(vec!["a".to_string(), "b".to_string()])
+        );
+        // Content parts make up one input; images are not checked
+        let parts = json!([
+            { "type": "text", "text": "look at" },
+            { "type": "image_url", "image_url": { "url": "https://example.com/a.png" } },
+            { "type": "text", "text": "this" }
+        ]);
+        assert_eq!(
+            moderation_inputs(&parts),
+            Some(vec!["look at\nthis".to_string()])
+        );
+        assert_eq!(moderation_inputs(&json!([])), None);
+        assert_eq!(moderation_inputs(&json!(["a", 1])), None);
+        assert_eq!(moderation_inputs(&json!(42)), None);
+
+        let chat = json!({
+            "model": "gpt-5",
+            "messages": [
+                { "role": "system", "content": "Be brief" },
+                { "role": "user", "content": [{ "type": "text", "text": "Hello" }] },
+                { "role": "assistant", "content": "Hi, how can I help?" },
+                { "role": "user", "content": "  " }
+            ]
+        });
+        assert_eq!(prompt_texts(&chat), vec!["Be brief", "Hello"]);
+        let generate = json!({ "model": "llama3", "system": "Terse", "prompt": "Why?" });
+        assert_eq!(prompt_texts(&generate), vec!["Terse", "Why?"]);
+
+        println!("✅ Moderation input extraction successful");
+    }
+
+    #[tokio::test]
+    async fn test_rules_flag_their_category() {
+        println!("🧪 Test: Local moderation rules");
+
+        let moderator = moderator(&config(None, false));
+        assert_eq!(moderator.model(Some("text-moderation-stable")), RULES_MODEL);
+
+        let results = moderator
+            .moderate(
+                &["What is Project Falcon?".to_string(), "Hello".to_string()],
+                None,
+            )
+            .await
+            .unwrap();
+        assert!(results[0].flagged);
+        assert_eq!(results[0].flagged_categories(), vec!["internal-project"]);
+        assert_eq!(results[0].category_scores["internal-project"], 1.0);
+        assert!(!results[1].flagged);
+
+        let disabled = ModerationConfig::default();
+        assert!(
+            Moderator::from_config(&disabled, reqwest::Client::new(), Duration::from_secs(5))
+                .unwrap()
+                .is_none()
+        );
+
+        println!("✅ Local moderation rules successful");
+    }
+
+    #[tokio::test]
+    async fn test_provider_results_are_merged_with_rules() {
+        println!("🧪 Test: Moderation provider");
+
+        let provider = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/moderations"))
+            .and(header_matcher("authorization", "Bearer sk-test"))
+            .and(body_partial_json(json!({
+                "model": "omni-moderation-latest",
+                "input": ["I will hurt you", "Project Falcon"]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "id": "modr-1",
+                "model": "omni-moderation-latest",
+                "results": [
+                    {
+                        "flagged": true,
+                        "categories": { "violence": true, "harassment": false },
+                        "category_scores": { "violence": 0.92, "harassment": 0.1 }
+                    },
+                    {
+                        "flagged": false,
+                        "categories": { "violence": false },
+                        "category_scores": { "violence": 0.01 }
+                    }
+                ]
+            })))
+            .expect(1)
+            .mount(&provider)
+            .await;
+
+        let moderator = moderator(&config(Some(format!("{}/v1", provider.uri())), false));
+        let results = moderator
+            .moderate(
+                &["I will hurt you".to_string(), "Project Falcon".to_string()],
+                None,
+            )
+            .await
+            .unwrap();
+        assert_eq!(results[0].flagged_categories(), vec!["violence"]);
+        assert!(results[1].flagged);
+        assert_eq!(results[1].flagged_categories(), vec!["internal-project"]);
+        assert_eq!(results[1].category_scores["violence"], 0.01);
+
+        println!("✅ Moderation provider successful");
+    }
+
+    fn screened(moderator: Moderator) -> Router {
+        Router::new()
+            .route("/v1/chat/completions", post(|| async { "answered" }))
+            .route("/api/chat", post(|| async { "answered" }))
+            .layer(axum::middleware::from_fn_with_state(
+                Arc::new(moderator),
+                screen_prompts,
+            ))
+    }
+
+    async fn send(router: &Router, path: &str, prompt: &str) -> (StatusCode, serde_json::Value) {
+        let body = json!({
+            "model": "gpt-5",
+            "messages": [{ "role": "user", "content": prompt }]
+        });
+        let request = Request::post(path)
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = serde_json::from_slice(&body).unwrap_or(json!(String::from_utf8_lossy(&body)));
+        (status, body)
+    }
+
+    #[tokio::test]
+    async fn test_flagged_prompts_are_refused() {
+        println!("🧪 Test: Pre-flight moderation");
+
+        let router = screened(moderator(&config(None, false)));
+
+        let (status, body) = send(&router, "/v1/chat/completions", "Hello").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "answered");
+
+        let (status, body) = send(&router, "/v1/chat/completions", "Leak project falcon").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"]["code"], "content_policy_violation");
+        let message = body["error"]["message"].as_str().unwrap();
+        assert!(message.contains("internal-project"));
+        assert!(!message.contains("falcon"));
+
+        let (status, body) = send(&router, "/api/chat", "Leak project falcon").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].is_string());
+
+        println!("✅ Pre-flight moderation successful");
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_provider_fails_open_unless_closed() {
+        println!("🧪 Test: Pre-flight moderation without a provider");
+
+        let provider = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&provider)
+            .await;
+        let base_url = format!("{}/v1", provider.uri());
+
+        let open = screened(moderator(&config(Some(base_url.clone()), false)));
+        let (status, _) = send(&open, "/v1/chat/completions", "Hello").await;
+        assert_eq!(status, StatusCode::OK);
+
+        let closed = screened(moderator(&config(Some(base_url), true)));
+        let (status, body) = send(&closed, "/v1/chat/completions", "Hello").await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body["error"]["code"], "moderation_unavailable");
+
+        println!("✅ Pre-flight moderation without a provider successful");
+    }
+}
@@ -0,0 +1,125 @@
+#[cfg(test)]
+mod ollama_tests {
+    use crate::ollama::{
+        parse_openai_chunk, response_object, tags_response, DoneStats, OllamaChatRequest,
+        OllamaEndpoint, OllamaGenerateRequest, StreamEvent,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn test_chat_request_translation() {
+        println!("🧪 Test: Ollama chat request translation");
+
+        let request: OllamaChatRequest = serde_json::from_value(json!({
+            "model": "gpt-5",
+            "messages": [
+                { "role": "system", "content": "Be brief" },
+                { "role": "user", "content": "Hello", "images": [] }
+            ],
+            "format": "json",
+            "options": { "temperature": 0.2, "top_p": 0.9, "num_predict": 64, "num_ctx": 4096 }
+        }))
+        .expect("valid chat request");
+
+        let openai = request.to_openai();
+        assert_eq!(openai.model, "gpt-5");
+        assert_eq!(openai.messages.len(), 2);
+        assert_eq!(openai.messages[1].content, "Hello");
+        assert_eq!(openai.temperature, Some(0.2));
+        assert_eq!(openai.max_tokens, Some(64));
+        // Ollama streams by default
+        assert_eq!(openai.stream, Some(true));
+        assert_eq!(openai.other["response_format"]["type"], "json_object");
+        assert!(openai.other.contains_key("top_p"));
+
+        println!("✅ Ollama chat request translation successful");
+    }
+
+    #[test]
+    fn test_generate_request_translation() {
+        println!("🧪 Test: Ollama generate request translation");
+
+        let request: OllamaGenerateRequest = serde_json::from_value(json!({
+            "model": "codex-mini",
+            "prompt": "Write a haiku",
+            "system": "You are a poet",
+            "stream": false,
+            "options": { "num_predict": -1 }
+        }))
+        .expect("valid generate request");
+
+        let openai = request.to_openai();
+        let roles: Vec<&str> = openai.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, vec!["system", "user"]);
+        assert_eq!(openai.stream, Some(false));
+        // -1 means unlimited in Ollama
+        assert_eq!(openai.max_tokens, None);
+        assert!(!openai.other.contains_key("response_format"));
+
+        println!("✅ Ollama generate request translation successful");
+    }
+
+    #[test]
+    fn test_response_objects() {
+        println!("🧪 Test: Ollama response shapes");
+
+        let chunk = response_object(OllamaEndpoint::Chat, "gpt-5", "Hi", None);
+        assert_eq!(chunk["message"]["content"], "Hi");
+        assert_eq!(chunk["done"], false);
+        assert!(chunk.get("eval_count").is_none());
+
+        let stats = DoneStats {
+            done_reason: "stop".to_string(),
+            total_duration_ns: 1_000,
+            prompt_eval_count: 12,
+            eval_count: 34,
+        };
+        let done = response_object(OllamaEndpoint::Generate, "gpt-5", "", Some(&stats));
+        assert_eq!(done["response"], "");
+        assert_eq!(done["done"], true);
+        assert_eq!(done["done_reason"], "stop");
+        assert_eq!(done["prompt_eval_count"], 12);
+        assert_eq!(done["eval_count"], 34);
+
+        let tags = tags_response(&["gpt-5".to_string()]);
+        assert_eq!(tags["models"][0]["name"], "gpt-5");
+
+        println!("✅ Ollama response shapes successful");
+    }
+
+    #[test]
+    fn test_parse_openai_chunks() {
+        println!("🧪 Test: OpenAI stream chunk parsing");
+
+        let content = format!(
+            "data: {}\n\n",
+            json!({ "choices": [{ "delta": { "content": "Hel" }, "finish_reason": null }] })
+        );
+        assert_eq!(
+            parse_openai_chunk(&content),
+            Some(StreamEvent::Content("Hel".to_string()))
+        );
+
+        let finished = format!(
+            "data: {}\n\n",
+            json!({ "choices": [{ "delta": {}, "finish_reason": "stop" }] })
+        );
+        assert_eq!(
+            parse_openai_chunk(&finished),
+            Some(StreamEvent::Finished("stop".to_string()))
+        );
+
+        let error = format!(
+            "data: {}\n\n",
+            json!({ "error": { "message": "boom", "type": "server_error" } })
+        );
+        assert_eq!(
+            parse_openai_chunk(&error),
+            Some(StreamEvent::Error("boom".to_string()))
+        );
+
+        assert_eq!(parse_openai_chunk("data: [DONE]\n\n"), None);
+
+        println!("✅ OpenAI stream chunk parsing successful");
+    }
+}
@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod openapi_tests {
+    use crate::openapi::{document, endpoint_list_html, ENDPOINTS, OPENAPI_VERSION};
+    use serde_json::Value;
+    use std::collections::HashSet;
+
+    /// Every `$ref` in `value`
+    fn refs<'a>(value: &'a Value, found: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(target)) = map.get("$ref") {
+                    found.push(target);
+                }
+                map.values().for_each(|value| refs(value, found));
+            },
+            Value::Array(items) => items.iter().for_each(|value| refs(value, found)),
+            _ => {},
+        }
+    }
+
+    #[test]
+    fn test_document_covers_every_endpoint() {
+        println!("🧪 Test: OpenAPI document covers every endpoint");
+
+        let doc = document("/");
+        assert_eq!(doc["openapi"], OPENAPI_VERSION);
+        assert_eq!(doc["servers"][0]["url"], "/");
+
+        let mut operation_ids = HashSet::new();
+        for endpoint in ENDPOINTS {
+            let operation = &doc["paths"][endpoint.path][endpoint.method];
+            assert_eq!(
+                operation["operationId"], endpoint.operation_id,
+                "{} {} is missing",
+                endpoint.method, endpoint.path
+            );
+            assert!(
+                operation_ids.insert(endpoint.operation_id),
+                "duplicate operationId {}",
+                endpoint.operation_id
+            );
+            assert!(operation["responses"][endpoint.status.to_string()].is_object());
+
+            let path_params: Vec<&str> = operation["parameters"]
+                .as_array()
+                .map(|parameters| {
+                    parameters
+                        .iter()
+                        .filter(|parameter| parameter["in"] == "path")
+                        .filter_map(|parameter| parameter["name"].as_str())
+                        .collect()
+                })
+                .unwrap_or_default();
+            assert_eq!(path_params, endpoint.path_params());
+        }
+
+        let completions = &doc["paths"]["/v1/chat/completions"]["post"];
+        assert!(completions["requestBody"]["content"]["application/json"].is_object());
+        assert!(completions["responses"]["200"]["content"]["text/event-stream"].is_object());
+        assert_eq!(
+            doc["paths"]["/v1/batches"]["get"]["parameters"]
+                .as_array()
+                .map(Vec::len),
+            Some(2)
+        );
+        assert_eq!(
+            doc["paths"]["/api/tags"]["get"]["responses"]["default"]["content"]["application/json"]
+                ["schema"]["$ref"],
+            "#/components/schemas/OllamaError"
+        );
+
+        println!("✅ OpenAPI endpoint coverage successful");
+    }
+
+    #[test]
+    fn test_every_schema_reference_resolves() {
+        println!("🧪 Test: OpenAPI schema references resolve");
+
+        let doc = document("/");
+        let mut found = Vec::new();
+        refs(&doc, &mut found);
+        assert!(!found.is_empty());
+
+        for target in found {
+            let name = target
+                .strip_prefix("#/components/schemas/")
+                .unwrap_or_else(|| panic!("unexpected reference {}", target));
+            assert!(
+                doc["components"]["schemas"][name].is_object(),
+                "{} is not defined",
+                target
+            );
+        }
+
+        println!("✅ OpenAPI schema references successful");
+    }
+
+    #[test]
+    fn test_playground_lists_every_endpoint() {
+        println!("🧪 Test: Playground endpoint list");
+
+        let html = endpoint_list_html();
+        for endpoint in ENDPOINTS {
+            let entry = format!("{} {}", endpoint.method.to_uppercase(), endpoint.path);
+            assert!(html.contains(&entry), "{} is not listed", entry);
+        }
+        assert_eq!(html.matches("class=\"endpoint\"").count(), ENDPOINTS.len());
+
+        println!("✅ Playground endpoint list successful");
+    }
+}
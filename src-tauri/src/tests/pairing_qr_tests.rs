@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod pairing_qr_tests {
+    use crate::pairing_qr::{png, save, svg};
+    use tempfile::TempDir;
+
+    const DATA: &str = r#"{"url":"https://mindlink.example.com","token":"instance-token"}"#;
+    const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+    #[test]
+    fn test_png_qr_code() {
+        println!("🧪 Test: PNG QR code");
+
+        let image = png(DATA).unwrap();
+        let decoder = png::Decoder::new(image.as_slice());
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let frame = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(frame.width, frame.height);
+        assert_eq!(frame.width % 8, 0, "Modules are 8 pixels wide");
+
+        let pixel = |x: u32, y: u32| pixels[(y * frame.width + x) as usize];
+        // The quiet zone is light, the finder pattern in the corner after it dark
+        assert_eq!(pixel(0, 0), u8::MAX);
+        assert_eq!(pixel(31, 31), u8::MAX);
+        assert_eq!(pixel(32, 32), 0);
+
+        println!("✅ PNG QR code test successful");
+    }
+
+    #[test]
+    fn test_svg_qr_code() {
+        println!("🧪 Test: SVG QR code");
+
+        let image = svg(DATA).unwrap();
+        assert!(image.contains("<svg"));
+        assert!(image.trim_end().ends_with("</svg>"));
+
+        println!("✅ SVG QR code test successful");
+    }
+
+    #[tokio::test]
+    async fn test_save_qr_code() {
+        println!("🧪 Test: Save QR code");
+
+        let dir = TempDir::new().unwrap();
+        let png_path = dir.path().join("pairing.PNG");
+        save(&png_path, DATA).await.unwrap();
+        assert!(std::fs::read(&png_path).unwrap().starts_with(PNG_SIGNATURE));
+
+        let svg_path = dir.path().join("pairing.svg");
+        save(&svg_path, DATA).await.unwrap();
+        assert!(std::fs::read_to_string(&svg_path).unwrap().contains("<svg"));
+
+        let txt_path = dir.path().join("pairing.txt");
+        assert!(save(&txt_path, DATA).await.is_err());
+        assert!(!txt_path.exists());
+
+        println!("✅ Save QR code test successful");
+    }
+}
@@ -0,0 +1,75 @@
+#[cfg(test)]
+mod ping_tests {
+    use crate::ping::{ConnectionReport, PingTracker, QueueDepth, REPORT_CAPACITY};
+
+    fn report(client: &str, rtt_ms: f64) -> ConnectionReport {
+        ConnectionReport {
+            client: client.to_string(),
+            rtt_ms,
+            jitter_ms: None,
+            loss: None,
+            received_at: None,
+        }
+    }
+
+    #[test]
+    fn test_pong_sequence_grows() {
+        println!("🧪 Test: Ping sequence");
+
+        let tracker = PingTracker::default();
+        let queue = QueueDepth {
+            in_flight: 2,
+            active_streams: 1,
+            queued_jobs: 3,
+        };
+        let first = tracker.pong(queue.clone());
+        let second = tracker.pong(QueueDepth::default());
+
+        assert_eq!(first.object, "pong");
+        assert_eq!(first.sequence, 1);
+        assert_eq!(second.sequence, 2);
+        assert_eq!(first.queue, queue);
+        assert_eq!(first.server_time_ms, first.server_time.timestamp_millis());
+
+        println!("✅ Ping sequence successful");
+    }
+
+    #[test]
+    fn test_report_validation() {
+        println!("🧪 Test: Connection report validation");
+
+        let mut valid = report("phone", 84.5);
+        valid.jitter_ms = Some(12.0);
+        valid.loss = Some(0.02);
+        assert!(valid.validate().is_ok());
+
+        assert!(report(" ", 10.0).validate().is_err());
+        assert!(report(&"x".repeat(65), 10.0).validate().is_err());
+        assert!(report("phone", -1.0).validate().is_err());
+        assert!(report("phone", f64::NAN).validate().is_err());
+
+        let mut lossy = report("phone", 10.0);
+        lossy.loss = Some(1.5);
+        assert!(lossy.validate().is_err());
+
+        println!("✅ Connection report validation successful");
+    }
+
+    #[test]
+    fn test_recent_reports_newest_first_and_capped() {
+        println!("🧪 Test: Connection report history");
+
+        let tracker = PingTracker::default();
+        for i in 0..REPORT_CAPACITY + 5 {
+            tracker.report(report("phone", i as f64));
+        }
+
+        let recent = tracker.recent(3);
+        assert_eq!(recent.len(), 3);
+        assert_eq!(recent[0].rtt_ms, (REPORT_CAPACITY + 4) as f64);
+        assert!(recent.iter().all(|r| r.received_at.is_some()));
+        assert_eq!(tracker.recent(usize::MAX).len(), REPORT_CAPACITY);
+
+        println!("✅ Connection report history successful");
+    }
+}
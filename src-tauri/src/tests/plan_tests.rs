@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod plan_tests {
+    use crate::managers::auth_manager::UserIdentity;
+    use crate::plan::{pool_max_in_flight, AccountPlan, PlanQuota};
+    use chrono::{TimeZone, Utc};
+
+    fn identity(plan: Option<&str>) -> UserIdentity {
+        UserIdentity {
+            email: None,
+            plan: plan.map(str::to_string),
+            account_id: "acct-123".to_string(),
+            subscription_until: None,
+        }
+    }
+
+    #[test]
+    fn test_plan_parsing() {
+        println!("🧪 Test: Plans from the ID token claim");
+
+        assert_eq!(AccountPlan::parse("plus"), AccountPlan::Plus);
+        assert_eq!(AccountPlan::parse("Pro"), AccountPlan::Pro);
+        assert_eq!(AccountPlan::parse("business"), AccountPlan::Team);
+        assert_eq!(AccountPlan::parse("edu"), AccountPlan::Enterprise);
+        assert_eq!(AccountPlan::parse("something-new"), AccountPlan::Unknown);
+        assert!(AccountPlan::Unknown.max_in_flight().is_none());
+        assert!(AccountPlan::Free.max_in_flight() < AccountPlan::Plus.max_in_flight());
+        assert!(AccountPlan::Plus.max_in_flight() < AccountPlan::Pro.max_in_flight());
+
+        println!("✅ Plans from the ID token claim successful");
+    }
+
+    #[test]
+    fn test_quota_from_identity() {
+        println!("🧪 Test: Quota of a signed-in account");
+
+        let mut user = identity(Some("plus"));
+        user.subscription_until = Utc.with_ymd_and_hms(2030, 1, 1, 0, 0, 0).single();
+        let quota = PlanQuota::from_identity(&user);
+        assert_eq!(quota.plan, AccountPlan::Plus);
+        assert_eq!(quota.max_in_flight, AccountPlan::Plus.max_in_flight());
+        assert_eq!(quota.active_until, user.subscription_until);
+
+        let quota = PlanQuota::from_identity(&identity(None));
+        assert_eq!(quota.plan, AccountPlan::Unknown);
+        assert!(quota.max_in_flight.is_none());
+
+        println!("✅ Quota of a signed-in account successful");
+    }
+
+    #[test]
+    fn test_pool_max_in_flight() {
+        println!("🧪 Test: Concurrency of a pool of accounts");
+
+        let plus = PlanQuota::from_identity(&identity(Some("plus")));
+        let pro = PlanQuota::from_identity(&identity(Some("pro")));
+        let unknown = PlanQuota::from_identity(&identity(None));
+
+        assert_eq!(
+            pool_max_in_flight([&plus, &pro]),
+            Some(plus.max_in_flight.unwrap() + pro.max_in_flight.unwrap())
+        );
+        // One account of unknown plan leaves the pool uncapped
+        assert!(pool_max_in_flight([&plus, &unknown]).is_none());
+        assert!(pool_max_in_flight([]).is_none());
+
+        println!("✅ Concurrency of a pool of accounts successful");
+    }
+}
@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod plugin_manager_tests {
+    use crate::managers::plugin_manager::{validate_manifest, PluginManager, MANIFEST_FILE};
+    use serde_json::json;
+    use std::path::Path;
+    use tempfile::TempDir;
+
+    fn write_plugin(root: &Path, dir: &str, manifest: &serde_json::Value) {
+        let plugin_dir = root.join(dir);
+        std::fs::create_dir_all(&plugin_dir).expect("create plugin dir");
+        std::fs::write(plugin_dir.join(MANIFEST_FILE), manifest.to_string())
+            .expect("write manifest");
+        std::fs::write(plugin_dir.join("index.js"), "export default {}").expect("write entry");
+    }
+
+    fn manifest(id: &str, mindlink_version: &str) -> serde_json::Value {
+        json!({
+            "id": id,
+            "name": "Example",
+            "version": "1.2.0",
+            "main": "index.js",
+            "mindlink_version": mindlink_version,
+        })
+    }
+
+    #[test]
+    fn test_manifest_validation() {
+        println!("🧪 Test: Plugin manifest validation");
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        write_plugin(temp_dir.path(), "example", &manifest("example", "^1.0"));
+        let dir = temp_dir.path().join("example");
+
+        assert!(validate_manifest(&manifest("example", "^1.0"), &dir, "1.4.0").is_ok());
+
+        // Incompatible MindLink version
+        let err = validate_manifest(&manifest("example", ">=2.0"), &dir, "1.4.0").unwrap_err();
+        assert!(err.contains("Requires MindLink"));
+
+        // Missing and mistyped fields
+        let err = validate_manifest(&json!({ "id": "example" }), &dir, "1.0.0").unwrap_err();
+        assert!(err.contains("Missing required field 'name'"));
+        let mut bad = manifest("example", "^1.0");
+        bad["dependencies"] = json!([1, 2]);
+        assert!(validate_manifest(&bad, &dir, "1.0.0").is_err());
+
+        // Invalid id, non-semver version and entry points outside the plugin
+        assert!(validate_manifest(&manifest("Bad Id", "^1.0"), &dir, "1.0.0").is_err());
+        let mut bad = manifest("example", "^1.0");
+        bad["version"] = json!("latest");
+        assert!(validate_manifest(&bad, &dir, "1.0.0").is_err());
+        let mut bad = manifest("example", "^1.0");
+        bad["main"] = json!("../outside.js");
+        assert!(validate_manifest(&bad, &dir, "1.0.0").is_err());
+
+        println!("✅ Plugin manifest validation successful");
+    }
+
+    #[tokio::test]
+    async fn test_discovery_reports_errors_and_caches() {
+        println!("🧪 Test: Plugin discovery and registry cache");
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        write_plugin(temp_dir.path(), "good", &manifest("good", "*"));
+        write_plugin(temp_dir.path(), "future", &manifest("future", ">=99.0"));
+        std::fs::create_dir_all(temp_dir.path().join("empty")).expect("create dir");
+
+        let mut manager = PluginManager::new(temp_dir.path().to_path_buf());
+        let registry = manager.registry().await.expect("scan should succeed");
+        assert_eq!(registry.manifests.len(), 1);
+        assert_eq!(registry.manifests[0].id, "good");
+        assert_eq!(registry.errors.len(), 2);
+        assert!(registry
+            .errors
+            .iter()
+            .any(|e| e.plugin_id.as_deref() == Some("future")));
+
+        // Cached until refreshed
+        write_plugin(temp_dir.path(), "later", &manifest("later", "*"));
+        assert_eq!(manager.registry().await.unwrap().manifests.len(), 1);
+        assert_eq!(manager.refresh().await.unwrap().manifests.len(), 2);
+
+        // A missing directory is an empty registry
+        let mut missing = PluginManager::new(temp_dir.path().join("does-not-exist"));
+        assert!(missing.refresh().await.unwrap().manifests.is_empty());
+
+        println!("✅ Plugin discovery and registry cache successful");
+    }
+}
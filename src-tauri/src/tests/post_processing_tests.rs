@@ -0,0 +1,185 @@
+#[cfg(test)]
+mod post_processing_tests {
+    use crate::managers::config_manager::{
+        PostProcessingAction, PostProcessingConfig, PostProcessingRule,
+    };
+    use crate::middleware::post_processing::post_process;
+    use crate::post_processing::PostProcessor;
+    use axum::{
+        body::Body,
+        http::{header, Request},
+        response::{IntoResponse, Json},
+        routing::post,
+        Router,
+    };
+    use serde_json::json;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    fn rule(action: PostProcessingAction) -> PostProcessingRule {
+        PostProcessingRule {
+            models: Vec::new(),
+            action,
+        }
+    }
+
+    fn processor(rules: Vec<PostProcessingRule>) -> PostProcessor {
+        PostProcessor::from_config(&PostProcessingConfig { rules }).unwrap()
+    }
+
+    fn processor_for(action: PostProcessingAction) -> PostProcessor {
+        processor(vec![rule(action)])
+    }
+
+    #[test]
+    fn test_rules_rewrite_text_in_order() {
+        println!("🧪 Test: Post-processing rules");
+
+        let processor = processor(vec![
+            rule(PostProcessingAction::RegexReplace {
+                pattern: r"(?i)as an ai language model,\s*".to_string(),
+                replacement: String::new(),
+            }),
+            rule(PostProcessingAction::StopSequences {
+                sequences: vec!["\n\nUser:".to_string(), "<|end|>".to_string()],
+            }),
+        ]);
+        assert_eq!(
+            processor.apply("gpt-5", "As an AI language model, hi.<|end|>\n\nUser: more"),
+            "hi."
+        );
+
+        let markdown = processor_for(PostProcessingAction::StripMarkdown);
+        let text = "# Title\n\nSome **bold**, _italic_ and `code` with a [link](https://example.com).\n\n> quoted\n\n```rust\nlet snake_case = 1;\n```";
+        assert_eq!(
+            markdown.apply("gpt-5", text),
+            "Title\n\nSome bold, italic and code with a link.\n\nquoted\n\nlet snake_case = 1;\n"
+        );
+
+        let json = processor_for(PostProcessingAction::ExtractJson);
+        assert_eq!(
+            json.apply(
+                "gpt-5",
+                "Sure! Here it is:\n```json\n{\"name\": \"Ada\", \"tags\": [\"a\"]}\n```\nAnything else?"
+            ),
+            "{\"name\": \"Ada\", \"tags\": [\"a\"]}"
+        );
+        // Text without JSON is left as it is
+        assert_eq!(json.apply("gpt-5", "No {json here"), "No {json here");
+
+        println!("✅ Post-processing rules successful");
+    }
+
+    #[test]
+    fn test_rules_apply_to_their_models() {
+        println!("🧪 Test: Post-processing rules per model");
+
+        let processor = processor(vec![PostProcessingRule {
+            models: vec!["json-mode".to_string()],
+            action: PostProcessingAction::ExtractJson,
+        }]);
+        let mut completion = json!({
+            "model": "json-mode",
+            "choices": [{ "message": { "role": "assistant", "content": "Result: [1, 2]" } }]
+        });
+        assert!(processor.process_response(&mut completion));
+        assert_eq!(completion["choices"][0]["message"]["content"], "[1, 2]");
+
+        let mut other = json!({
+            "model": "gpt-5",
+            "choices": [{ "message": { "role": "assistant", "content": "Result: [1, 2]" } }]
+        });
+        assert!(!processor.process_response(&mut other));
+
+        // Ollama chat and generate responses
+        let mut chat = json!({ "model": "json-mode", "message": { "content": "x {}" } });
+        assert!(processor.process_response(&mut chat));
+        assert_eq!(chat["message"]["content"], "{}");
+        let mut generate = json!({ "model": "json-mode", "response": "x {}" });
+        assert!(processor.process_response(&mut generate));
+        assert_eq!(generate["response"], "{}");
+
+        let invalid = PostProcessingConfig {
+            rules: vec![rule(PostProcessingAction::RegexReplace {
+                pattern: "(".to_string(),
+                replacement: String::new(),
+            })],
+        };
+        assert!(PostProcessor::from_config(&invalid).is_err());
+
+        println!("✅ Post-processing rules per model successful");
+    }
+
+    #[test]
+    fn test_rules_are_read_from_config() {
+        println!("🧪 Test: Post-processing config format");
+
+        let config: PostProcessingConfig = serde_json::from_value(json!({
+            "rules": [
+                { "type": "regex_replace", "pattern": "foo", "replacement": "bar" },
+                { "type": "stop_sequences", "sequences": ["END"], "models": ["gpt-5"] },
+                { "type": "strip_markdown" },
+                { "type": "extract_json" }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(config.rules.len(), 4);
+        assert_eq!(config.rules[1].models, vec!["gpt-5"]);
+        assert_eq!(config.rules[2].action, PostProcessingAction::StripMarkdown);
+
+        println!("✅ Post-processing config format successful");
+    }
+
+    #[tokio::test]
+    async fn test_completions_are_post_processed() {
+        println!("🧪 Test: Post-processing middleware");
+
+        let rules = Arc::new(RwLock::new(PostProcessor::default()));
+        let router = Router::new()
+            .route(
+                "/v1/chat/completions",
+                post(|| async {
+                    Json(json!({
+                        "model": "gpt-5",
+                        "choices": [{ "message": { "role": "assistant", "content": "**Hi**" } }]
+                    }))
+                }),
+            )
+            .route(
+                "/api/chat",
+                post(|| async {
+                    ([(header::CONTENT_TYPE, "application/x-ndjson")], "**Hi**\n").into_response()
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                rules.clone(),
+                post_process,
+            ));
+
+        let send = |path: &str| {
+            let router = router.clone();
+            let request = Request::post(path).body(Body::empty()).unwrap();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                String::from_utf8(body.to_vec()).unwrap()
+            }
+        };
+
+        assert!(send("/v1/chat/completions").await.contains("**Hi**"));
+
+        // Edits apply to the next response
+        *rules.write().await = processor_for(PostProcessingAction::StripMarkdown);
+        let body: serde_json::Value =
+            serde_json::from_str(&send("/v1/chat/completions").await).unwrap();
+        assert_eq!(body["choices"][0]["message"]["content"], "Hi");
+
+        // Streams pass through
+        assert_eq!(send("/api/chat").await, "**Hi**\n");
+
+        println!("✅ Post-processing middleware successful");
+    }
+}
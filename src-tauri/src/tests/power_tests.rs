@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod power_tests {
+    use crate::managers::config_manager::{PowerSaverConfig, PowerSaverMode};
+    use crate::power::{
+        health_check_interval, linux_power_source, parse_pmset, saver_active, PowerSource,
+        PowerStatus, PowerSupply, HEALTH_CHECK_INTERVAL_SECS,
+    };
+
+    fn supply(kind: &str, online: Option<bool>, status: Option<&str>) -> PowerSupply {
+        PowerSupply {
+            kind: kind.to_string(),
+            online,
+            status: status.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_linux_power_source() {
+        println!("🧪 Test: Linux power source detection");
+
+        let battery = supply("Battery", None, Some("Discharging"));
+        let mains_on = supply("Mains", Some(true), None);
+        let mains_off = supply("Mains", Some(false), None);
+
+        assert_eq!(
+            linux_power_source(&[mains_on, battery.clone()]),
+            PowerSource::Ac
+        );
+        assert_eq!(
+            linux_power_source(&[mains_off.clone(), battery.clone()]),
+            PowerSource::Battery
+        );
+        // Some laptops report "Unknown" or "Not charging" while unplugged
+        assert_eq!(
+            linux_power_source(&[mains_off, supply("Battery", None, Some("Unknown"))]),
+            PowerSource::Battery
+        );
+        assert_eq!(linux_power_source(&[battery]), PowerSource::Battery);
+        assert_eq!(
+            linux_power_source(&[supply("Battery", None, Some("Full"))]),
+            PowerSource::Unknown
+        );
+        // Desktops have no battery at all
+        assert_eq!(linux_power_source(&[]), PowerSource::Ac);
+
+        println!("✅ Linux power source detection successful");
+    }
+
+    #[test]
+    fn test_parse_pmset() {
+        println!("🧪 Test: macOS pmset parsing");
+
+        assert_eq!(
+            parse_pmset("Now drawing from 'Battery Power'\n -InternalBattery-0 85%; discharging"),
+            PowerSource::Battery
+        );
+        assert_eq!(
+            parse_pmset("Now drawing from 'AC Power'\n -InternalBattery-0 100%; charged"),
+            PowerSource::Ac
+        );
+        assert_eq!(parse_pmset(""), PowerSource::Unknown);
+
+        println!("✅ macOS pmset parsing successful");
+    }
+
+    #[test]
+    fn test_power_saver_modes() {
+        println!("🧪 Test: Power saver modes and intervals");
+
+        assert!(saver_active(PowerSaverMode::Auto, PowerSource::Battery));
+        assert!(!saver_active(PowerSaverMode::Auto, PowerSource::Ac));
+        assert!(!saver_active(PowerSaverMode::Auto, PowerSource::Unknown));
+        assert!(saver_active(PowerSaverMode::On, PowerSource::Ac));
+        assert!(!saver_active(PowerSaverMode::Off, PowerSource::Battery));
+
+        let config = PowerSaverConfig::default();
+        let mut status = PowerStatus::default();
+        assert_eq!(
+            health_check_interval(&status, &config),
+            HEALTH_CHECK_INTERVAL_SECS
+        );
+        status.active = true;
+        assert_eq!(
+            health_check_interval(&status, &config),
+            config.health_check_interval_secs
+        );
+
+        println!("✅ Power saver modes and intervals successful");
+    }
+}
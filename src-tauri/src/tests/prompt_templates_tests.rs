@@ -0,0 +1,99 @@
+#[cfg(test)]
+mod prompt_templates_tests {
+    use crate::managers::config_manager::PromptConfig;
+    use crate::prompt_templates::{render, system_prompt};
+    use serde_json::{json, Map, Value};
+    use std::collections::HashMap;
+
+    fn config() -> PromptConfig {
+        PromptConfig {
+            system_prompt: Some("You are {{ assistant }}, answering with {{model}}.".to_string()),
+            templates: HashMap::from([(
+                "reviewer".to_string(),
+                "Review {{language}} code for {{team}}.".to_string(),
+            )]),
+            variables: HashMap::from([
+                ("assistant".to_string(), "MindLink".to_string()),
+                ("team".to_string(), "platform".to_string()),
+            ]),
+        }
+    }
+
+    fn fields(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_render_variables() {
+        println!("🧪 Test: Prompt template rendering");
+
+        let variables = HashMap::from([("name".to_string(), "Ada".to_string())]);
+        assert_eq!(
+            render("Hello {{name}}, {{ name }}!", &variables).unwrap(),
+            "Hello Ada, Ada!"
+        );
+        assert_eq!(
+            render("No placeholders", &variables).unwrap(),
+            "No placeholders"
+        );
+        // An unterminated placeholder is left as text
+        assert_eq!(render("Open {{name", &variables).unwrap(), "Open {{name");
+        assert!(render("Hi {{missing}}", &variables)
+            .unwrap_err()
+            .contains("missing"));
+
+        println!("✅ Prompt template rendering successful");
+    }
+
+    #[test]
+    fn test_system_prompt_precedence() {
+        println!("🧪 Test: System prompt precedence");
+
+        let config = config();
+        let none = Map::new();
+
+        assert_eq!(
+            system_prompt(&config, None, "gpt-4", &none).unwrap(),
+            Some("You are MindLink, answering with gpt-4.".to_string())
+        );
+        assert_eq!(
+            system_prompt(&config, Some("App prompt for {{model}}"), "gpt-4", &none).unwrap(),
+            Some("App prompt for gpt-4".to_string())
+        );
+        assert_eq!(
+            system_prompt(&PromptConfig::default(), None, "gpt-4", &none).unwrap(),
+            None
+        );
+
+        // A selected template wins over the app prompt; request variables win
+        // over configured ones
+        let request = fields(json!({
+            "prompt_template": "reviewer",
+            "prompt_variables": {"language": "Rust", "team": "infra"}
+        }));
+        assert_eq!(
+            system_prompt(&config, Some("App prompt"), "gpt-4", &request).unwrap(),
+            Some("Review Rust code for infra.".to_string())
+        );
+
+        println!("✅ System prompt precedence successful");
+    }
+
+    #[test]
+    fn test_invalid_template_requests() {
+        println!("🧪 Test: Invalid template requests");
+
+        let config = config();
+        let unknown = fields(json!({"prompt_template": "missing"}));
+        assert!(system_prompt(&config, None, "gpt-4", &unknown).is_err());
+
+        let bad_variables = fields(json!({"prompt_template": "reviewer", "prompt_variables": [1]}));
+        assert!(system_prompt(&config, None, "gpt-4", &bad_variables).is_err());
+
+        // The reviewer template needs a language
+        let missing = fields(json!({"prompt_template": "reviewer"}));
+        assert!(system_prompt(&config, None, "gpt-4", &missing).is_err());
+
+        println!("✅ Invalid template requests successful");
+    }
+}
@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod proxy_tests {
+    use crate::managers::config_manager::ProxyConfig;
+    use crate::proxy::proxy_for;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_proxy_for_config() {
+        println!("🧪 Test: Proxy from the config");
+
+        assert!(proxy_for(&ProxyConfig::default()).unwrap().is_none());
+        let empty = ProxyConfig {
+            url: Some(String::new()),
+            no_proxy: Vec::new(),
+        };
+        assert!(proxy_for(&empty).unwrap().is_none());
+
+        let socks = ProxyConfig {
+            url: Some("socks5h://127.0.0.1:1080".to_string()),
+            no_proxy: vec!["internal.example".to_string()],
+        };
+        assert!(proxy_for(&socks).unwrap().is_some());
+
+        println!("✅ Proxy from the config successful");
+    }
+
+    #[tokio::test]
+    async fn test_requests_go_through_proxy() {
+        println!("🧪 Test: Requests go through the proxy");
+
+        let proxy_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/backend-api/me"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&proxy_server)
+            .await;
+        let local_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/health"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&local_server)
+            .await;
+
+        let config = ProxyConfig {
+            url: Some(proxy_server.uri()),
+            no_proxy: Vec::new(),
+        };
+        let client = reqwest::Client::builder()
+            .proxy(proxy_for(&config).unwrap().unwrap())
+            .build()
+            .unwrap();
+
+        // The host does not resolve; only the proxy can answer
+        let upstream = client
+            .get("http://chatgpt.invalid/backend-api/me")
+            .send()
+            .await
+            .unwrap();
+        assert!(upstream.status().is_success());
+
+        // Local backends are reached directly
+        let local = client
+            .get(format!("{}/health", local_server.uri()))
+            .send()
+            .await
+            .unwrap();
+        assert!(local.status().is_success());
+
+        println!("✅ Requests go through the proxy successful");
+    }
+}
@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod reasoning_tests {
+    use crate::reasoning::{
+        max_completion_tokens, reasoning_chunk, reasoning_effort, reasoning_text, ReasoningStream,
+    };
+    use serde_json::{json, Map, Value};
+
+    fn fields(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    fn thoughts(thoughts: Value) -> Value {
+        json!({
+            "message": {
+                "author": {"role": "assistant"},
+                "content": {"content_type": "thoughts", "thoughts": thoughts}
+            }
+        })
+    }
+
+    #[test]
+    fn test_reasoning_parameters() {
+        println!("🧪 Test: Reasoning parameter extraction");
+
+        let request = fields(json!({"reasoning_effort": "high", "max_completion_tokens": 4096}));
+        assert_eq!(reasoning_effort(&request).as_deref(), Some("high"));
+        assert_eq!(max_completion_tokens(&request), Some(4096));
+
+        // Responses API style
+        let request = fields(json!({"reasoning": {"effort": "low"}}));
+        assert_eq!(reasoning_effort(&request).as_deref(), Some("low"));
+        assert_eq!(max_completion_tokens(&request), None);
+
+        let request = fields(json!({"max_completion_tokens": -1}));
+        assert_eq!(max_completion_tokens(&request), None);
+
+        println!("✅ Reasoning parameter extraction successful");
+    }
+
+    #[test]
+    fn test_reasoning_text_from_thoughts() {
+        println!("🧪 Test: Reasoning summary from thoughts messages");
+
+        let event = thoughts(json!([
+            {"summary": "Planning", "content": "Check the units first."},
+            {"summary": "Answering"}
+        ]));
+        assert_eq!(
+            reasoning_text(&event).as_deref(),
+            Some("Planning\nCheck the units first.\n\nAnswering")
+        );
+
+        let answer = json!({
+            "message": {"content": {"content_type": "text", "parts": ["42"]}}
+        });
+        assert!(reasoning_text(&answer).is_none());
+        assert!(reasoning_text(&thoughts(json!([]))).is_none());
+
+        println!("✅ Reasoning summary from thoughts messages successful");
+    }
+
+    #[test]
+    fn test_reasoning_stream_sends_only_new_text() {
+        println!("🧪 Test: Reasoning stream deduplication");
+
+        let mut stream = ReasoningStream::default();
+        assert_eq!(stream.push("Plan".to_string()).as_deref(), Some("Plan"));
+        assert_eq!(stream.push("Planning".to_string()).as_deref(), Some("ning"));
+        assert_eq!(stream.push("Planning".to_string()), None);
+        // A new thoughts message
+        assert_eq!(
+            stream.push("Verify".to_string()).as_deref(),
+            Some("\n\nVerify")
+        );
+
+        let chunk = reasoning_chunk("chatcmpl-1", "o3", "ning");
+        let data: Value =
+            serde_json::from_str(chunk.strip_prefix("data: ").unwrap().trim()).unwrap();
+        assert_eq!(data["choices"][0]["delta"]["reasoning_content"], "ning");
+        assert!(data["choices"][0]["delta"].get("content").is_none());
+
+        println!("✅ Reasoning stream deduplication successful");
+    }
+}
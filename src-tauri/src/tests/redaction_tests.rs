@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod redaction_tests {
+    use crate::managers::config_manager::{RedactionConfig, RedactionRule};
+    use crate::managers::server_manager::Message;
+    use crate::redaction::Redactor;
+
+    fn enabled() -> RedactionConfig {
+        RedactionConfig {
+            enabled: true,
+            ..RedactionConfig::default()
+        }
+    }
+
+    fn redact(config: &RedactionConfig, text: &str) -> (String, usize) {
+        Redactor::from_config(config).unwrap().unwrap().redact(text)
+    }
+
+    #[test]
+    fn test_builtin_rules_mask_personal_data() {
+        println!("🧪 Test: Built-in redaction rules");
+
+        let (text, masked) = redact(
+            &enabled(),
+            "Mail jane.doe@example.com or call +1 (555) 123-4567, key sk-proj-abcdefghijklmnopqrstuvwx",
+        );
+        assert_eq!(
+            text,
+            "Mail [REDACTED_EMAIL] or call [REDACTED_PHONE_NUMBER], key [REDACTED_API_KEY]"
+        );
+        assert_eq!(masked, 3);
+
+        // Long digit runs without separators are not phone numbers
+        let (text, masked) = redact(&enabled(), "Order 20240117 shipped");
+        assert_eq!(text, "Order 20240117 shipped");
+        assert_eq!(masked, 0);
+
+        println!("✅ Built-in redaction rules successful");
+    }
+
+    #[test]
+    fn test_rules_can_be_switched_off_and_extended() {
+        println!("🧪 Test: Redaction rule selection");
+
+        let config = RedactionConfig {
+            emails: false,
+            custom_rules: vec![
+                RedactionRule {
+                    name: "employee_id".to_string(),
+                    pattern: r"\bEMP-\d{6}\b".to_string(),
+                    enabled: true,
+                },
+                RedactionRule {
+                    name: "project".to_string(),
+                    pattern: "Falcon".to_string(),
+                    enabled: false,
+                },
+            ],
+            ..enabled()
+        };
+        let (text, masked) = redact(&config, "EMP-004211 (ana@example.com) on Falcon");
+        assert_eq!(text, "[REDACTED_EMPLOYEE_ID] (ana@example.com) on Falcon");
+        assert_eq!(masked, 1);
+
+        assert!(Redactor::from_config(&RedactionConfig::default())
+            .unwrap()
+            .is_none());
+        let nothing_enabled = RedactionConfig {
+            emails: false,
+            phone_numbers: false,
+            api_keys: false,
+            ..enabled()
+        };
+        assert!(Redactor::from_config(&nothing_enabled).unwrap().is_none());
+
+        let invalid = RedactionConfig {
+            custom_rules: vec![RedactionRule {
+                name: "broken".to_string(),
+                pattern: "(unclosed".to_string(),
+                enabled: true,
+            }],
+            ..enabled()
+        };
+        assert!(Redactor::from_config(&invalid).is_err());
+
+        println!("✅ Redaction rule selection successful");
+    }
+
+    #[test]
+    fn test_messages_are_redacted_in_place() {
+        println!("🧪 Test: Message redaction");
+
+        let redactor = Redactor::from_config(&enabled()).unwrap().unwrap();
+        let mut messages = vec![
+            Message {
+                role: "user".to_string(),
+                content: "I am bob@example.org".to_string(),
+                name: None,
+                tool_calls: None,
+                tool_call_id: None,
+                reasoning_content: None,
+            },
+            Message {
+                role: "assistant".to_string(),
+                content: "Hello!".to_string(),
+                name: None,
+                tool_calls: None,
+                tool_call_id: None,
+                reasoning_content: None,
+            },
+        ];
+
+        assert_eq!(redactor.redact_messages(&mut messages), 1);
+        assert_eq!(messages[0].content, "I am [REDACTED_EMAIL]");
+        assert_eq!(messages[1].content, "Hello!");
+
+        println!("✅ Message redaction successful");
+    }
+}
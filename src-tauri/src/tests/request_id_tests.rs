@@ -0,0 +1,78 @@
+#[cfg(test)]
+mod request_id_tests {
+    use crate::logging::{
+        current_correlation_id, with_correlation_id, LogCategory, LogEntry, LogLevel,
+    };
+    use crate::middleware::request_id::{assign_request_id, request_id_for, REQUEST_ID_HEADER};
+    use axum::{body::Body, http::HeaderMap, http::Request, routing::get, Router};
+    use tower::ServiceExt;
+
+    #[test]
+    fn test_request_id_reuse_and_generation() {
+        println!("🧪 Test: Request ID reuse and generation");
+
+        let mut headers = HeaderMap::new();
+        headers.insert(&REQUEST_ID_HEADER, "client-123".parse().unwrap());
+        assert_eq!(request_id_for(&headers), "client-123");
+
+        // Unsafe or oversized IDs are replaced
+        headers.insert(&REQUEST_ID_HEADER, "bad id\"".parse().unwrap());
+        assert!(request_id_for(&headers).starts_with("req_"));
+        headers.insert(&REQUEST_ID_HEADER, "a".repeat(200).parse().unwrap());
+        assert!(request_id_for(&headers).starts_with("req_"));
+
+        let first = request_id_for(&HeaderMap::new());
+        let second = request_id_for(&HeaderMap::new());
+        assert_ne!(first, second);
+
+        println!("✅ Request ID reuse and generation successful");
+    }
+
+    #[tokio::test]
+    async fn test_log_entries_carry_correlation_id() {
+        println!("🧪 Test: Log entries carry the correlation ID");
+
+        let entry = with_correlation_id(Some("req_abc".to_string()), async {
+            LogEntry::new(LogLevel::Info, LogCategory::Network, "inside".to_string())
+        })
+        .await;
+        assert_eq!(entry.correlation_id.as_deref(), Some("req_abc"));
+        assert!(entry.format_for_file().contains("[req_abc]"));
+
+        let outside = LogEntry::new(LogLevel::Info, LogCategory::Network, "out".to_string());
+        assert!(outside.correlation_id.is_none());
+
+        println!("✅ Log entries carry the correlation ID successful");
+    }
+
+    #[tokio::test]
+    async fn test_middleware_sets_header_and_scope() {
+        println!("🧪 Test: Request ID middleware");
+
+        let app = Router::new()
+            .route(
+                "/v1/echo",
+                get(|| async { current_correlation_id().unwrap_or_default() }),
+            )
+            .layer(axum::middleware::from_fn(assign_request_id));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/v1/echo")
+                    .header("x-request-id", "trace-42")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.headers()[&REQUEST_ID_HEADER], "trace-42");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"trace-42");
+
+        println!("✅ Request ID middleware successful");
+    }
+}
@@ -0,0 +1,261 @@
+#[cfg(test)]
+mod request_log_tests {
+    use crate::middleware::analytics::TokenUsage;
+    use crate::middleware::request_log::log_requests;
+    use crate::request_log::{
+        self, current_timer, with_timer, Phase, PhaseTimings, RequestLog, RequestSummary,
+        RequestTimer, StreamThroughput, MAX_SUMMARIES,
+    };
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+        response::{IntoResponse, Response},
+        routing::{get, post},
+        Router,
+    };
+    use chrono::Utc;
+    use futures_util::StreamExt;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    fn summary(route: &str) -> RequestSummary {
+        RequestSummary {
+            request_id: None,
+            at: Utc::now(),
+            method: "POST".to_string(),
+            route: route.to_string(),
+            status: 200,
+            model: None,
+            streamed: false,
+            phases: PhaseTimings::default(),
+            throughput: None,
+        }
+    }
+
+    fn stream(ttft_ms: f64, tokens_per_second: Option<f64>) -> RequestSummary {
+        RequestSummary {
+            streamed: true,
+            throughput: Some(StreamThroughput {
+                ttft_ms,
+                generation_ms: 1000.0,
+                completion_tokens: tokens_per_second.map(|speed| speed as u64),
+                tokens_per_second,
+            }),
+            ..summary("/v1/chat/completions")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_phases_are_timed_between_marks() {
+        println!("🧪 Test: Request phase timing");
+
+        let timer = Arc::new(RequestTimer::new());
+        // Marks outside a timed request go nowhere
+        request_log::mark(Phase::Validated);
+        assert!(current_timer().is_none());
+
+        with_timer(Some(timer.clone()), async {
+            request_log::mark(Phase::Validated);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            request_log::mark(Phase::Authenticated);
+
+            // Background tasks take the timer along
+            let timer = current_timer();
+            tokio::spawn(with_timer(timer, async {
+                request_log::mark(Phase::UpstreamSent);
+                tokio::time::sleep(Duration::from_millis(30)).await;
+                request_log::mark(Phase::FirstByte);
+                // Later attempts don't move the first mark
+                request_log::mark(Phase::UpstreamSent);
+            }))
+            .await
+            .unwrap();
+        })
+        .await;
+
+        let phases = timer.phases(true);
+        assert!(phases.validation_ms.unwrap() < 20.0);
+        assert!(phases.auth_ms.unwrap() >= 20.0);
+        assert!(phases.upstream_ttfb_ms.unwrap() >= 30.0);
+        assert!(phases.stream_ms.is_some());
+        assert!(phases.total_ms >= 50.0);
+
+        let refused = RequestTimer::new();
+        let phases = refused.phases(false);
+        assert_eq!(phases.validation_ms, None);
+        assert_eq!(phases.upstream_ttfb_ms, None);
+        assert_eq!(phases.stream_ms, None);
+
+        println!("✅ Request phase timing successful");
+    }
+
+    #[test]
+    fn test_log_keeps_the_latest_summaries() {
+        println!("🧪 Test: Request log retention");
+
+        let log = RequestLog::default();
+        for i in 0..MAX_SUMMARIES + 5 {
+            log.record(summary(&format!("/v1/{}", i)));
+        }
+        assert_eq!(log.recent(usize::MAX).len(), MAX_SUMMARIES);
+        let recent = log.recent(2);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].route, format!("/v1/{}", MAX_SUMMARIES + 4));
+
+        // Phases are flattened into the summary for the inspector
+        let json = serde_json::to_value(summary("/v1/models")).unwrap();
+        assert!(json.get("total_ms").is_some());
+        assert!(json.get("phases").is_none());
+
+        println!("✅ Request log retention successful");
+    }
+
+    #[tokio::test]
+    async fn test_stream_throughput_is_measured() {
+        println!("🧪 Test: Stream throughput");
+
+        let timer = Arc::new(RequestTimer::new());
+        assert_eq!(timer.throughput(), None);
+
+        with_timer(Some(timer.clone()), async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            request_log::mark(Phase::FirstToken);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            request_log::mark(Phase::FirstToken);
+        })
+        .await;
+
+        // Known before the reply ends, speed only once its tokens are counted
+        let started = timer.throughput().unwrap();
+        assert!(started.ttft_ms >= 20.0 && started.ttft_ms < 70.0);
+        assert_eq!(started.tokens_per_second, None);
+
+        timer.set_completion_tokens(100);
+        let finished = timer.throughput().unwrap();
+        assert_eq!(finished.ttft_ms, started.ttft_ms);
+        assert_eq!(finished.completion_tokens, Some(100));
+        assert!(finished.generation_ms >= 50.0);
+        let speed = finished.tokens_per_second.unwrap();
+        assert!(speed > 0.0 && speed <= 2000.0);
+
+        println!("✅ Stream throughput successful");
+    }
+
+    #[test]
+    fn test_stream_stats_aggregate_recent_streams() {
+        println!("🧪 Test: Stream statistics");
+
+        let log = RequestLog::default();
+        assert_eq!(log.stream_stats(None).streams, 0);
+        assert_eq!(log.stream_stats(None).ttft_p50_ms, None);
+
+        let mut earlier = stream(5000.0, Some(2.0));
+        earlier.at = Utc::now() - chrono::Duration::hours(1);
+        log.record(earlier);
+        for i in 1..=10 {
+            log.record(stream(f64::from(i) * 100.0, Some(f64::from(i) * 10.0)));
+        }
+        // Ended before their tokens were counted, or never streamed a token
+        log.record(stream(200.0, None));
+        log.record(summary("/v1/models"));
+
+        let overall = log.stream_stats(None);
+        assert_eq!(overall.streams, 12);
+        assert_eq!(overall.ttft_p90_ms, Some(1000.0));
+        assert_eq!(overall.tokens_per_second_p10, Some(10.0));
+
+        let recent = log.stream_stats(Some(Utc::now() - chrono::Duration::minutes(10)));
+        assert_eq!(recent.streams, 11);
+        assert_eq!(recent.ttft_p50_ms, Some(500.0));
+        assert_eq!(recent.ttft_p90_ms, Some(900.0));
+        assert_eq!(recent.tokens_per_second_p50, Some(50.0));
+        assert_eq!(recent.tokens_per_second_p10, Some(10.0));
+
+        println!("✅ Stream statistics successful");
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_logged_when_complete() {
+        println!("🧪 Test: Request logging middleware");
+
+        let log = Arc::new(RequestLog::default());
+        let router = Router::new()
+            .route(
+                "/v1/chat/completions",
+                post(|| async {
+                    request_log::mark(Phase::Validated);
+                    request_log::mark(Phase::Authenticated);
+                    request_log::mark(Phase::UpstreamSent);
+                    request_log::mark(Phase::FirstByte);
+                    let mut response: Response = "{}".into_response();
+                    response.extensions_mut().insert(TokenUsage {
+                        model: "gpt-5".to_string(),
+                        total_tokens: 10,
+                    });
+                    response
+                }),
+            )
+            .route(
+                "/api/chat",
+                post(|| async {
+                    request_log::mark(Phase::FirstByte);
+                    request_log::mark(Phase::FirstToken);
+                    request_log::set_completion_tokens(2);
+                    let chunks =
+                        futures_util::stream::iter(["{\"done\":false}\n", "{\"done\":true}\n"])
+                            .map(Ok::<_, std::convert::Infallible>);
+                    Body::from_stream(chunks)
+                }),
+            )
+            .route("/health", get(|| async { "ok" }))
+            .layer(axum::middleware::from_fn_with_state(
+                log.clone(),
+                log_requests,
+            ));
+
+        let send = |path: &str, method: &str| {
+            Request::builder()
+                .method(method)
+                .uri(path)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = router
+            .clone()
+            .oneshot(send("/v1/chat/completions", "POST"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let summary = &log.recent(1)[0];
+        assert_eq!(summary.route, "/v1/chat/completions");
+        assert_eq!(summary.model.as_deref(), Some("gpt-5"));
+        assert!(!summary.streamed);
+        assert!(summary.phases.upstream_ttfb_ms.is_some());
+        assert_eq!(summary.phases.stream_ms, None);
+
+        // A stream is logged once its body ends
+        let response = router
+            .clone()
+            .oneshot(send("/api/chat", "POST"))
+            .await
+            .unwrap();
+        assert_eq!(log.recent(usize::MAX).len(), 1);
+        axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let summary = &log.recent(1)[0];
+        assert_eq!(summary.route, "/api/chat");
+        assert!(summary.streamed);
+        assert!(summary.phases.stream_ms.is_some());
+        let throughput = summary.throughput.as_ref().unwrap();
+        assert_eq!(throughput.completion_tokens, Some(2));
+
+        // Only API routes are logged
+        router.oneshot(send("/health", "GET")).await.unwrap();
+        assert_eq!(log.recent(usize::MAX).len(), 2);
+
+        println!("✅ Request logging middleware successful");
+    }
+}
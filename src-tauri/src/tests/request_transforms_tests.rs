@@ -0,0 +1,235 @@
+#[cfg(test)]
+mod request_transforms_tests {
+    use crate::managers::config_manager::{
+        RequestTransformAction, RequestTransformConfig, RequestTransformRule,
+    };
+    use crate::middleware::request_transforms::transform_requests;
+    use crate::request_transforms::RequestTransformer;
+    use axum::{
+        body::Body,
+        http::{header, HeaderMap, HeaderValue, Request},
+        response::Json,
+        routing::post,
+        Router,
+    };
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    fn rule(action: RequestTransformAction) -> RequestTransformRule {
+        RequestTransformRule {
+            user_agent: None,
+            models: Vec::new(),
+            action,
+        }
+    }
+
+    fn transformer(rules: Vec<RequestTransformRule>) -> RequestTransformer {
+        RequestTransformer::from_config(&RequestTransformConfig { rules }).unwrap()
+    }
+
+    fn headers(user_agent: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::USER_AGENT, HeaderValue::from_static(user_agent));
+        headers
+    }
+
+    #[test]
+    fn test_rules_rewrite_requests_in_order() {
+        println!("🧪 Test: Request transformation rules");
+
+        let transformer = transformer(vec![
+            rule(RequestTransformAction::SetField {
+                field: "temperature".to_string(),
+                value: json!(0.2),
+            }),
+            rule(RequestTransformAction::RemoveFields {
+                fields: vec!["logit_bias".to_string(), "seed".to_string()],
+            }),
+            rule(RequestTransformAction::SetHeader {
+                name: "X-Client".to_string(),
+                value: "legacy".to_string(),
+            }),
+            rule(RequestTransformAction::RemoveHeader {
+                name: "x-debug".to_string(),
+            }),
+        ]);
+
+        let mut headers = headers("curl/8.0");
+        headers.insert("x-debug", HeaderValue::from_static("1"));
+        let mut body = json!({
+            "model": "gpt-5",
+            "temperature": 1.5,
+            "logit_bias": {},
+            "messages": []
+        });
+        assert!(transformer.apply(&mut headers, Some(&mut body)));
+        assert_eq!(
+            body,
+            json!({ "model": "gpt-5", "temperature": 0.2, "messages": [] })
+        );
+        assert_eq!(headers["x-client"], "legacy");
+        assert!(!headers.contains_key("x-debug"));
+
+        // Applying them again changes nothing
+        assert!(!transformer.apply(&mut headers, Some(&mut body)));
+
+        // Header rules also apply to requests without a JSON body
+        let mut headers = HeaderMap::new();
+        assert!(transformer.apply(&mut headers, None));
+        assert_eq!(headers["x-client"], "legacy");
+
+        println!("✅ Request transformation rules successful");
+    }
+
+    #[test]
+    fn test_rules_apply_to_their_clients_and_models() {
+        println!("🧪 Test: Request transformation rules per client");
+
+        let transformer = transformer(vec![
+            RequestTransformRule {
+                user_agent: Some("^OldEditor/".to_string()),
+                models: vec!["gpt-4".to_string()],
+                action: RequestTransformAction::RenameModel {
+                    to: "gpt-5".to_string(),
+                },
+            },
+            // Sees the model the rule before asked for
+            RequestTransformRule {
+                user_agent: None,
+                models: vec!["gpt-5".to_string()],
+                action: RequestTransformAction::RemoveFields {
+                    fields: vec!["max_tokens".to_string()],
+                },
+            },
+        ]);
+
+        let mut body = json!({ "model": "gpt-4", "max_tokens": 10 });
+        assert!(transformer.apply(&mut headers("OldEditor/1.2"), Some(&mut body)));
+        assert_eq!(body, json!({ "model": "gpt-5" }));
+
+        let mut body = json!({ "model": "gpt-4", "max_tokens": 10 });
+        assert!(!transformer.apply(&mut headers("NewEditor/3.0"), Some(&mut body)));
+        assert_eq!(body, json!({ "model": "gpt-4", "max_tokens": 10 }));
+
+        // Model-scoped rules need a model to match
+        assert!(!transformer.apply(&mut headers("OldEditor/1.2"), None));
+
+        for action in [
+            RequestTransformAction::SetHeader {
+                name: "bad header".to_string(),
+                value: "x".to_string(),
+            },
+            RequestTransformAction::SetHeader {
+                name: "x-client".to_string(),
+                value: "line\nbreak".to_string(),
+            },
+        ] {
+            let config = RequestTransformConfig {
+                rules: vec![rule(action)],
+            };
+            assert!(RequestTransformer::from_config(&config).is_err());
+        }
+        let config = RequestTransformConfig {
+            rules: vec![RequestTransformRule {
+                user_agent: Some("(".to_string()),
+                ..rule(RequestTransformAction::RemoveHeader {
+                    name: "x-debug".to_string(),
+                })
+            }],
+        };
+        assert!(RequestTransformer::from_config(&config).is_err());
+
+        println!("✅ Request transformation rules per client successful");
+    }
+
+    #[test]
+    fn test_rules_are_read_from_config() {
+        println!("🧪 Test: Request transformation config format");
+
+        let config: RequestTransformConfig = serde_json::from_value(json!({
+            "rules": [
+                { "type": "set_field", "field": "temperature", "value": 0 },
+                { "type": "remove_fields", "fields": ["seed"], "models": ["gpt-5"] },
+                { "type": "rename_model", "to": "gpt-5", "user_agent": "^Cursor/" },
+                { "type": "set_header", "name": "x-client", "value": "legacy" },
+                { "type": "remove_header", "name": "x-debug" }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(config.rules.len(), 5);
+        assert_eq!(config.rules[1].models, vec!["gpt-5"]);
+        assert_eq!(config.rules[2].user_agent.as_deref(), Some("^Cursor/"));
+        assert_eq!(
+            config.rules[0].action,
+            RequestTransformAction::SetField {
+                field: "temperature".to_string(),
+                value: json!(0),
+            }
+        );
+
+        println!("✅ Request transformation config format successful");
+    }
+
+    #[tokio::test]
+    async fn test_requests_are_transformed() {
+        println!("🧪 Test: Request transformation middleware");
+
+        let rules = Arc::new(RwLock::new(RequestTransformer::default()));
+        let router = Router::new()
+            .route(
+                "/v1/chat/completions",
+                post(|headers: HeaderMap, Json(body): Json<Value>| async move {
+                    Json(json!({
+                        "body": body,
+                        "client": headers.get("x-client").and_then(|v| v.to_str().ok()),
+                    }))
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                rules.clone(),
+                transform_requests,
+            ));
+
+        let send = || {
+            let router = router.clone();
+            let request = Request::post("/v1/chat/completions")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"model":"gpt-4","temperature":2}"#))
+                .unwrap();
+            async move {
+                let response = router.oneshot(request).await.unwrap();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                serde_json::from_slice::<Value>(&body).unwrap()
+            }
+        };
+
+        assert_eq!(
+            send().await,
+            json!({ "body": { "model": "gpt-4", "temperature": 2 }, "client": null })
+        );
+
+        // Edits apply to the next request
+        *rules.write().await = transformer(vec![
+            rule(RequestTransformAction::RenameModel {
+                to: "gpt-5".to_string(),
+            }),
+            rule(RequestTransformAction::RemoveFields {
+                fields: vec!["temperature".to_string()],
+            }),
+            rule(RequestTransformAction::SetHeader {
+                name: "x-client".to_string(),
+                value: "legacy".to_string(),
+            }),
+        ]);
+        assert_eq!(
+            send().await,
+            json!({ "body": { "model": "gpt-5" }, "client": "legacy" })
+        );
+
+        println!("✅ Request transformation middleware successful");
+    }
+}
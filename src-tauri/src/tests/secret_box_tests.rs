@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod secret_box_tests {
+    use crate::secret_box::{self, MasterKey};
+
+    #[test]
+    fn test_master_key_round_trip() {
+        println!("🧪 Test: Secrets encrypted with the master key");
+
+        let key = MasterKey::derive("correct horse battery staple").unwrap();
+        let sealed = key.encrypt("sk-provider-key").unwrap();
+        assert!(secret_box::is_encrypted(&sealed));
+        assert!(!sealed.contains("sk-provider-key"));
+        assert_eq!(key.decrypt(&sealed).unwrap(), "sk-provider-key");
+
+        // A fresh nonce for every value
+        assert_ne!(key.encrypt("sk-provider-key").unwrap(), sealed);
+
+        // The same passphrase derives the same key
+        let again = MasterKey::derive("correct horse battery staple").unwrap();
+        assert_eq!(again.decrypt(&sealed).unwrap(), "sk-provider-key");
+
+        let other = MasterKey::derive("another passphrase").unwrap();
+        assert!(other.decrypt(&sealed).is_err());
+
+        // Tampering is detected
+        let mut tampered = sealed.clone();
+        let last = tampered.pop().unwrap();
+        tampered.push(if last == 'A' { 'B' } else { 'A' });
+        assert!(key.decrypt(&tampered).is_err());
+        assert!(key.decrypt("mindlink-aes256gcm:AAAA").is_err());
+
+        // Plaintext from before encryption is read as is
+        assert_eq!(key.decrypt("sk-legacy").unwrap(), "sk-legacy");
+
+        println!("✅ Secrets encrypted with the master key successful");
+    }
+
+    #[tokio::test]
+    async fn test_seal_and_open() {
+        println!("🧪 Test: Sealing secrets with the machine key");
+
+        let sealed = secret_box::seal("sk-provider-key").await;
+        if secret_box::master_key().await.is_some() {
+            assert!(secret_box::is_encrypted(&sealed));
+            // Sealing twice does not encrypt again
+            assert_eq!(secret_box::seal(&sealed).await, sealed);
+        } else {
+            assert_eq!(sealed, "sk-provider-key");
+        }
+        assert_eq!(secret_box::open(&sealed).await.unwrap(), "sk-provider-key");
+        assert_eq!(secret_box::open("sk-legacy").await.unwrap(), "sk-legacy");
+
+        println!("✅ Sealing secrets with the machine key successful");
+    }
+}
@@ -0,0 +1,188 @@
+#[cfg(test)]
+mod security_report_tests {
+    use crate::managers::config_manager::{AccessControlConfig, TunnelConfig, TunnelService};
+    use crate::managers::tunnel_manager::ServiceTunnel;
+    use crate::security_report::{build_report, ExposureInputs, Listener, Reach, Severity};
+
+    fn inputs(api_host: &str) -> ExposureInputs {
+        ExposureInputs {
+            listeners: vec![Listener {
+                name: "API server".to_string(),
+                host: api_host.to_string(),
+                port: 3001,
+                tls: false,
+            }],
+            tunnels: Vec::new(),
+            access_control: AccessControlConfig::default(),
+            tunnel_token_required: false,
+            cloudflare_access: false,
+            cors_any_origin: false,
+        }
+    }
+
+    fn tunnel(service: TunnelService, url: &str) -> ServiceTunnel {
+        ServiceTunnel {
+            service,
+            local_port: 3001,
+            url: Some(url.to_string()),
+            connected: true,
+        }
+    }
+
+    fn tunnel_config(config: serde_json::Value) -> TunnelConfig {
+        serde_json::from_value(config).expect("valid tunnel config")
+    }
+
+    fn ids(report: &crate::security_report::SecurityReport) -> Vec<&str> {
+        report.findings.iter().map(|f| f.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_loopback_only_scores_high() {
+        println!("🧪 Test: Loopback-only exposure");
+
+        let report = build_report(&inputs("127.0.0.1"));
+        assert_eq!(report.reach(), Reach::Loopback);
+        assert_eq!(ids(&report), vec!["unauthenticated_api"]);
+        assert_eq!(report.findings[0].severity, Severity::Low);
+        assert_eq!(report.score, 95);
+
+        println!("✅ Loopback-only exposure successful");
+    }
+
+    #[test]
+    fn test_network_and_tunnel_exposure() {
+        println!("🧪 Test: Network and tunnel exposure");
+
+        let mut exposed = inputs("0.0.0.0");
+        exposed.tunnels = vec![tunnel(
+            TunnelService::Api,
+            "https://calm-river.trycloudflare.com",
+        )];
+        exposed.cors_any_origin = true;
+        let report = build_report(&exposed);
+
+        assert_eq!(report.reach(), Reach::Public);
+        assert_eq!(report.surface.len(), 2);
+        assert_eq!(report.surface[0].reach, Reach::Network);
+        assert!(report.surface[1].tls);
+
+        // Most severe first
+        assert_eq!(report.findings[0].id, "unauthenticated_api");
+        assert_eq!(report.findings[0].severity, Severity::High);
+        for id in [
+            "network_listener",
+            "plaintext_network_traffic",
+            "cors_any_origin",
+            "no_access_policy",
+            "public_tunnel",
+        ] {
+            assert!(ids(&report).contains(&id), "missing {}", id);
+        }
+        assert_eq!(report.score, 100 - 30 - 15 - 5 - 15 - 15);
+
+        // An allow list and TLS clear their findings
+        exposed.access_control.allow = vec!["192.168.1.0/24".to_string()];
+        exposed.listeners[0].tls = true;
+        let report = build_report(&exposed);
+        assert!(!ids(&report).contains(&"no_access_policy"));
+        assert!(!ids(&report).contains(&"plaintext_network_traffic"));
+
+        println!("✅ Network and tunnel exposure successful");
+    }
+
+    #[test]
+    fn test_inputs_follow_tunnel_config() {
+        println!("🧪 Test: Report follows the tunnel configuration");
+
+        let listeners = inputs("127.0.0.1").listeners;
+        let tunnels = vec![
+            tunnel(TunnelService::Api, "https://api.example.com"),
+            tunnel(TunnelService::Bifrost, "https://bifrost.example.com"),
+        ];
+        let report_for = |config: &TunnelConfig| {
+            build_report(&ExposureInputs::new(
+                listeners.clone(),
+                tunnels.clone(),
+                config,
+                AccessControlConfig::default(),
+                true,
+            ))
+        };
+        let severity_of = |report: &crate::security_report::SecurityReport, id: &str| {
+            report
+                .findings
+                .iter()
+                .find(|f| f.id == id)
+                .map(|f| f.severity)
+        };
+
+        // Tokens are required by default, so only loopback is unauthenticated
+        let mut config = tunnel_config(serde_json::json!({
+            "enabled": true,
+            "tunnel_type": "named",
+        }));
+        let report = report_for(&config);
+        assert_eq!(report.surface.len(), 3);
+        assert_eq!(
+            severity_of(&report, "unauthenticated_api"),
+            Some(Severity::Low)
+        );
+        assert_eq!(
+            severity_of(&report, "cors_any_origin"),
+            Some(Severity::Medium)
+        );
+        // Nothing protects the tunnels of other services
+        assert_eq!(
+            severity_of(&report, "unauthenticated_service_tunnel"),
+            Some(Severity::High)
+        );
+
+        // Without tokens the tunnel opens the API to everyone
+        config.require_token = false;
+        let report = report_for(&config);
+        assert_eq!(
+            severity_of(&report, "unauthenticated_api"),
+            Some(Severity::High)
+        );
+
+        // Cloudflare Access protects the named tunnel once its application exists
+        config.cloudflare_access.enabled = true;
+        config.cloudflare_access.team_domain = Some("team.cloudflareaccess.com".to_string());
+        config.cloudflare_access.audience = Some("aud".to_string());
+        let report = report_for(&config);
+        assert_eq!(
+            severity_of(&report, "unauthenticated_api"),
+            Some(Severity::Low)
+        );
+
+        // but not a quick tunnel
+        config.tunnel_type = "quick".to_string();
+        let report = report_for(&config);
+        assert_eq!(
+            severity_of(&report, "unauthenticated_api"),
+            Some(Severity::High)
+        );
+
+        println!("✅ Report follows the tunnel configuration successful");
+    }
+
+    #[test]
+    fn test_listener_from_url() {
+        println!("🧪 Test: Listener parsing");
+
+        let bifrost = Listener::from_url("Bifrost", "http://127.0.0.1:3003").expect("valid url");
+        assert_eq!(bifrost.host, "127.0.0.1");
+        assert_eq!(bifrost.port, 3003);
+        assert!(!bifrost.tls);
+
+        let ipv6 = Listener::from_url("Dashboard", "https://[::1]").expect("valid url");
+        assert_eq!(ipv6.host, "::1");
+        assert_eq!(ipv6.port, 443);
+        assert!(ipv6.tls);
+
+        assert!(Listener::from_url("Broken", "not a url").is_none());
+
+        println!("✅ Listener parsing successful");
+    }
+}
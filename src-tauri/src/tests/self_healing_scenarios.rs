@@ -0,0 +1,313 @@
+#[cfg(test)]
+mod self_healing_scenarios {
+    //! Declarative scenarios for the health monitor's self-healing logic.
+    //!
+    //! Each scenario declares how components fail over simulated time. The
+    //! harness runs periodic health checks against a fake clock, feeds every
+    //! report through `health_check_actions` exactly like `perform_health_check`
+    //! does, and applies the resulting restarts back to the simulation.
+
+    use crate::health::{ComponentHealth, HealthLevel, HealthReport};
+    use crate::self_healing::{health_check_actions, SelfHealingPolicy, HEALTH_ERROR_PREFIX};
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+    use std::collections::HashMap;
+
+    /// How a simulated component behaves over time (in seconds)
+    #[derive(Debug, Clone, Copy)]
+    enum Behavior {
+        Healthy,
+        /// Exits once after the given time; a restart brings it back for good
+        ExitsAfter(i64),
+        /// Alternates up and down every `period`, ignoring restarts
+        Flaps {
+            period: i64,
+        },
+        /// Never recovers, even when restarted
+        StaysDown,
+    }
+
+    struct SimComponent {
+        name: String,
+        critical: bool,
+        behavior: Behavior,
+        restarted: bool,
+    }
+
+    impl SimComponent {
+        fn health_at(&self, t: i64) -> ComponentHealth {
+            let up = match self.behavior {
+                Behavior::Healthy => true,
+                Behavior::ExitsAfter(secs) => self.restarted || t < secs,
+                Behavior::Flaps { period } => (t / period) % 2 == 0,
+                Behavior::StaysDown => false,
+            };
+
+            if up {
+                ComponentHealth::ok(&self.name, self.critical)
+            } else {
+                ComponentHealth::down(&self.name, "simulated failure", self.critical)
+            }
+        }
+    }
+
+    struct Scenario {
+        name: String,
+        components: Vec<SimComponent>,
+        policy: SelfHealingPolicy,
+        check_every: i64,
+        run_for: i64,
+        last_error: Option<String>,
+    }
+
+    #[derive(Debug)]
+    struct Outcome {
+        restarts: HashMap<String, usize>,
+        suppressed: HashMap<String, usize>,
+        /// Overall level of each `health-changed` report, in order
+        notifications: Vec<HealthLevel>,
+        worst_overall: HealthLevel,
+        final_report: HealthReport,
+        last_error: Option<String>,
+    }
+
+    impl Outcome {
+        fn restarts_of(&self, name: &str) -> usize {
+            self.restarts.get(name).copied().unwrap_or(0)
+        }
+
+        fn suppressed_of(&self, name: &str) -> usize {
+            self.suppressed.get(name).copied().unwrap_or(0)
+        }
+    }
+
+    impl Scenario {
+        fn new(name: &str) -> Self {
+            Self {
+                name: name.to_string(),
+                components: Vec::new(),
+                policy: SelfHealingPolicy::default(),
+                check_every: 30,
+                run_for: 300,
+                last_error: None,
+            }
+        }
+
+        fn component(mut self, name: &str, critical: bool, behavior: Behavior) -> Self {
+            self.components.push(SimComponent {
+                name: name.to_string(),
+                critical,
+                behavior,
+                restarted: false,
+            });
+            self
+        }
+
+        fn check_every(mut self, secs: i64) -> Self {
+            self.check_every = secs;
+            self
+        }
+
+        fn run_for(mut self, secs: i64) -> Self {
+            self.run_for = secs;
+            self
+        }
+
+        /// `last_error` as set by something other than the health monitor
+        fn last_error(mut self, error: &str) -> Self {
+            self.last_error = Some(error.to_string());
+            self
+        }
+
+        fn run(mut self) -> Outcome {
+            println!("   Scenario: {}", self.name);
+
+            let start: DateTime<Utc> = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+            // The health store starts out with an empty report, as in the app
+            let mut previous = HealthReport::default();
+            let mut outcome = Outcome {
+                restarts: HashMap::new(),
+                suppressed: HashMap::new(),
+                notifications: Vec::new(),
+                worst_overall: HealthLevel::Ok,
+                final_report: HealthReport::default(),
+                last_error: self.last_error.clone(),
+            };
+
+            let mut t = 0;
+            while t <= self.run_for {
+                let report = HealthReport::from_components(
+                    self.components.iter().map(|c| c.health_at(t)).collect(),
+                );
+                let now = start + Duration::seconds(t);
+                let actions = health_check_actions(
+                    &mut self.policy,
+                    &previous,
+                    &report,
+                    outcome.last_error.as_deref(),
+                    now,
+                );
+
+                if let Some(notified) = actions.notify {
+                    assert_eq!(notified, report, "The checked report is notified");
+                    outcome.notifications.push(notified.overall);
+                }
+                outcome.last_error = actions.last_error;
+                for name in actions.restart {
+                    if let Some(component) = self.components.iter_mut().find(|c| c.name == name) {
+                        component.restarted = true;
+                    }
+                    *outcome.restarts.entry(name).or_insert(0) += 1;
+                }
+                for name in actions.suppressed {
+                    *outcome.suppressed.entry(name).or_insert(0) += 1;
+                }
+
+                outcome.worst_overall = outcome.worst_overall.max(report.overall);
+                previous = report;
+                t += self.check_every;
+            }
+
+            outcome.final_report = previous;
+            outcome
+        }
+    }
+
+    #[test]
+    fn test_bifrost_exit_is_restarted_once() {
+        println!("🧪 Test: Bifrost exiting after 10s is restarted and recovers");
+
+        let outcome = Scenario::new("bifrost exits after 10s")
+            .component("server", true, Behavior::Healthy)
+            .component("bifrost", false, Behavior::ExitsAfter(10))
+            .check_every(5)
+            .run_for(60)
+            .run();
+
+        assert_eq!(outcome.restarts_of("bifrost"), 1);
+        assert_eq!(outcome.suppressed_of("bifrost"), 0);
+        // Initial report, bifrost going down, bifrost coming back
+        assert_eq!(
+            outcome.notifications,
+            [HealthLevel::Ok, HealthLevel::Degraded, HealthLevel::Ok]
+        );
+        assert_eq!(outcome.worst_overall, HealthLevel::Degraded);
+        assert_eq!(outcome.final_report.overall, HealthLevel::Ok);
+        assert!(outcome.last_error.is_none());
+
+        println!("✅ Bifrost restart scenario successful");
+    }
+
+    #[test]
+    fn test_flapping_tunnel_notifies_without_restarts() {
+        println!("🧪 Test: Tunnel flapping every 30s notifies on each transition");
+
+        let outcome = Scenario::new("tunnel health flaps every 30s")
+            .component("server", true, Behavior::Healthy)
+            .component("tunnel", false, Behavior::Flaps { period: 30 })
+            .check_every(10)
+            .run_for(120)
+            .run();
+
+        // The tunnel is not restartable by the policy
+        assert!(outcome.restarts.is_empty());
+        assert!(outcome.suppressed.is_empty());
+        // Initial report plus down/up at 30s, 60s, 90s and 120s
+        assert_eq!(
+            outcome.notifications,
+            [
+                HealthLevel::Ok,
+                HealthLevel::Degraded,
+                HealthLevel::Ok,
+                HealthLevel::Degraded,
+                HealthLevel::Ok
+            ]
+        );
+        assert!(outcome.last_error.is_none());
+        assert_eq!(outcome.worst_overall, HealthLevel::Degraded);
+        assert_eq!(outcome.final_report.overall, HealthLevel::Ok);
+
+        println!("✅ Flapping tunnel scenario successful");
+    }
+
+    #[test]
+    fn test_restart_budget_is_enforced() {
+        println!("🧪 Test: Dashboard that stays down is restarted at most 3 times");
+
+        let outcome = Scenario::new("dashboard stays down")
+            .component("server", true, Behavior::Healthy)
+            .component("dashboard", false, Behavior::StaysDown)
+            .check_every(30)
+            .run_for(300)
+            .run();
+
+        assert_eq!(outcome.restarts_of("dashboard"), 3);
+        assert_eq!(outcome.suppressed_of("dashboard"), 8);
+        // Only the first transition to degraded is notified
+        assert_eq!(outcome.notifications, [HealthLevel::Degraded]);
+        assert_eq!(outcome.final_report.overall, HealthLevel::Degraded);
+        let last_error = outcome.last_error.expect("Degraded health sets last_error");
+        assert!(last_error.starts_with(HEALTH_ERROR_PREFIX));
+        assert!(last_error.contains("dashboard"));
+
+        println!("✅ Restart budget scenario successful");
+    }
+
+    #[test]
+    fn test_restart_budget_refills_after_window() {
+        println!("🧪 Test: Restart budget refills once the window has passed");
+
+        let outcome = Scenario::new("dashboard stays down past the budget window")
+            .component("dashboard", false, Behavior::StaysDown)
+            .check_every(60)
+            .run_for(660)
+            .run();
+
+        // 0s, 60s and 120s use the budget; 600s and 660s see the first two expire
+        assert_eq!(outcome.restarts_of("dashboard"), 5);
+        assert_eq!(outcome.suppressed_of("dashboard"), 7);
+        assert_eq!(outcome.notifications, [HealthLevel::Degraded]);
+        assert_eq!(outcome.final_report.overall, HealthLevel::Degraded);
+
+        println!("✅ Restart budget window scenario successful");
+    }
+
+    #[test]
+    fn test_critical_component_down_takes_app_down() {
+        println!("🧪 Test: Critical server failure takes overall health down");
+
+        let outcome = Scenario::new("server exits after 20s")
+            .component("server", true, Behavior::ExitsAfter(20))
+            .component("bifrost", false, Behavior::Healthy)
+            .check_every(10)
+            .run_for(60)
+            .run();
+
+        // The server is not restartable, so it stays down
+        assert!(outcome.restarts.is_empty());
+        assert_eq!(outcome.worst_overall, HealthLevel::Down);
+        assert_eq!(outcome.notifications, [HealthLevel::Ok, HealthLevel::Down]);
+        assert_eq!(outcome.final_report.overall, HealthLevel::Down);
+        let last_error = outcome.last_error.expect("Down health sets last_error");
+        assert!(last_error.contains("server"));
+
+        println!("✅ Critical failure scenario successful");
+    }
+
+    #[test]
+    fn test_recovery_keeps_other_errors() {
+        println!("🧪 Test: Healthy checks leave errors of other components alone");
+
+        let outcome = Scenario::new("healthy after a failed login")
+            .component("server", true, Behavior::Healthy)
+            .last_error("Login failed")
+            .check_every(30)
+            .run_for(90)
+            .run();
+
+        assert_eq!(outcome.notifications, [HealthLevel::Ok]);
+        assert_eq!(outcome.last_error.as_deref(), Some("Login failed"));
+        assert_eq!(outcome.final_report.overall, HealthLevel::Ok);
+
+        println!("✅ Other errors scenario successful");
+    }
+}
@@ -1,7 +1,11 @@
 #[cfg(test)]
 mod server_manager_tests {
     use crate::managers::auth_manager::AuthManager;
-    use crate::managers::server_manager::ServerManager;
+    use crate::managers::config_manager::{BindAddress, HttpConfig, TlsConfig};
+    use crate::managers::server_manager::{
+        self_signed_names, ChatCompletionRequest, ServerManager,
+    };
+    use serde_json::json;
     use std::sync::Arc;
     use tokio::sync::RwLock;
 
@@ -457,4 +461,299 @@ mod server_manager_tests {
 
         println!("✅ Network error handling test successful");
     }
+
+    #[tokio::test]
+    async fn test_http1_and_http2_share_the_port() {
+        println!("🧪 Test: HTTP/1.1 and h2c on the API port");
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("Failed to find a free port")
+            .port();
+        let mut manager = ServerManager::new().await;
+        manager
+            .configure("127.0.0.1".to_string(), port)
+            .await
+            .unwrap();
+        manager
+            .configure_http(HttpConfig {
+                http2_max_concurrent_streams: 8,
+                ..HttpConfig::default()
+            })
+            .await
+            .unwrap();
+
+        let auth_manager = Arc::new(RwLock::new(
+            AuthManager::new()
+                .await
+                .expect("Failed to create auth manager"),
+        ));
+        let url = manager.start(auth_manager).await.expect("Server starts");
+
+        let h2c = reqwest::Client::builder()
+            .http2_prior_knowledge()
+            .build()
+            .unwrap();
+        let requests = (0..20).map(|_| h2c.get(format!("{}/health", url)).send());
+        for response in futures::future::join_all(requests).await {
+            assert_eq!(response.unwrap().version(), reqwest::Version::HTTP_2);
+        }
+
+        let http1 = reqwest::Client::builder().http1_only().build().unwrap();
+        let response = http1.get(format!("{}/health", url)).send().await.unwrap();
+        assert_eq!(response.version(), reqwest::Version::HTTP_11);
+
+        manager.stop().await.unwrap();
+        println!("✅ HTTP/1.1 and h2c on the API port successful");
+    }
+
+    #[tokio::test]
+    async fn test_responses_are_compressed() {
+        println!("🧪 Test: Response compression");
+
+        let auth_manager = Arc::new(RwLock::new(
+            AuthManager::new()
+                .await
+                .expect("Failed to create auth manager"),
+        ));
+        let client = reqwest::Client::new();
+        let encoding = |url: String, accept: &'static str| {
+            let request = client.get(url).header("Accept-Encoding", accept).send();
+            async move {
+                let response = request.await.unwrap();
+                assert!(response.status().is_success());
+                response
+                    .headers()
+                    .get("Content-Encoding")
+                    .map(|value| value.to_str().unwrap().to_string())
+            }
+        };
+
+        for compression in [true, false] {
+            let port = std::net::TcpListener::bind("127.0.0.1:0")
+                .and_then(|listener| listener.local_addr())
+                .expect("Failed to find a free port")
+                .port();
+            let mut manager = ServerManager::new().await;
+            manager
+                .configure("127.0.0.1".to_string(), port)
+                .await
+                .unwrap();
+            manager
+                .configure_http(HttpConfig {
+                    compression,
+                    ..HttpConfig::default()
+                })
+                .await
+                .unwrap();
+            let url = manager
+                .start(auth_manager.clone())
+                .await
+                .expect("Server starts");
+
+            let document = format!("{}/openapi.json", url);
+            if compression {
+                assert_eq!(
+                    encoding(document.clone(), "br").await.as_deref(),
+                    Some("br")
+                );
+                assert_eq!(
+                    encoding(document.clone(), "gzip").await.as_deref(),
+                    Some("gzip")
+                );
+                assert_eq!(encoding(document, "identity").await, None);
+                // Too small to be worth it
+                assert_eq!(encoding(format!("{}/test", url), "gzip").await, None);
+            } else {
+                assert_eq!(encoding(document, "gzip").await, None);
+            }
+
+            manager.stop().await.unwrap();
+        }
+
+        println!("✅ Response compression successful");
+    }
+
+    #[tokio::test]
+    async fn test_playground_replaces_dashboard() {
+        println!("🧪 Test: Chat playground");
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("Failed to find a free port")
+            .port();
+        let mut manager = ServerManager::new().await;
+        manager
+            .configure("127.0.0.1".to_string(), port)
+            .await
+            .unwrap();
+        let auth_manager = Arc::new(RwLock::new(
+            AuthManager::new()
+                .await
+                .expect("Failed to create auth manager"),
+        ));
+        let url = manager.start(auth_manager).await.expect("Server starts");
+
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+        let response = client
+            .get(format!("{}/playground", url))
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+        let page = response.text().await.unwrap();
+        // Talks to this server, and still lists its endpoints
+        assert!(page.contains("fetch('/v1/chat/completions'"));
+        assert!(page.contains("POST /v1/chat/completions"));
+
+        let response = client
+            .get(format!("{}/dashboard", url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(response.headers()["location"], "/playground");
+
+        manager.stop().await.unwrap();
+        println!("✅ Chat playground successful");
+    }
+
+    #[tokio::test]
+    async fn test_additional_binds_share_the_server() {
+        println!("🧪 Test: API on several ports");
+
+        let free_port = || {
+            std::net::TcpListener::bind("127.0.0.1:0")
+                .and_then(|listener| listener.local_addr())
+                .expect("Failed to find a free port")
+                .port()
+        };
+        let (port, legacy_port) = (free_port(), free_port());
+        let mut manager = ServerManager::new().await;
+        manager
+            .configure("127.0.0.1".to_string(), port)
+            .await
+            .unwrap();
+        manager
+            .configure_additional_binds(vec![BindAddress {
+                host: "127.0.0.1".to_string(),
+                port: legacy_port,
+            }])
+            .await
+            .unwrap();
+
+        let auth_manager = Arc::new(RwLock::new(
+            AuthManager::new()
+                .await
+                .expect("Failed to create auth manager"),
+        ));
+        let url = manager.start(auth_manager).await.expect("Server starts");
+        assert_eq!(url, format!("http://127.0.0.1:{}", port));
+
+        let client = reqwest::Client::new();
+        for port in [port, legacy_port] {
+            client
+                .get(format!("http://127.0.0.1:{}/health", port))
+                .send()
+                .await
+                .expect("Every port answers");
+        }
+
+        // Stopping closes every port
+        manager.stop().await.unwrap();
+        for port in [port, legacy_port] {
+            assert!(std::net::TcpStream::connect(("127.0.0.1", port)).is_err());
+        }
+
+        println!("✅ API on several ports successful");
+    }
+
+    #[tokio::test]
+    async fn test_tls_failure_fails_the_start() {
+        println!("🧪 Test: Broken TLS setup");
+
+        let mut manager = ServerManager::new().await;
+        manager
+            .configure_tls(TlsConfig {
+                enabled: true,
+                cert_path: Some("/nonexistent/cert.pem".to_string()),
+                key_path: None,
+            })
+            .await
+            .unwrap();
+
+        let auth_manager = Arc::new(RwLock::new(
+            AuthManager::new()
+                .await
+                .expect("Failed to create auth manager"),
+        ));
+        // No falling back to plain HTTP
+        assert!(manager.start(auth_manager).await.is_err());
+        assert!(!manager.is_running().await);
+
+        println!("✅ Broken TLS setup successful");
+    }
+
+    #[test]
+    fn test_self_signed_names() {
+        println!("🧪 Test: Self-signed certificate names");
+
+        assert_eq!(
+            self_signed_names(["127.0.0.1", "0.0.0.0", "::", "192.168.1.20"]),
+            vec!["localhost", "127.0.0.1", "192.168.1.20"]
+        );
+        assert_eq!(
+            self_signed_names(["mindlink.lan"]),
+            vec!["localhost", "127.0.0.1", "mindlink.lan"]
+        );
+
+        println!("✅ Self-signed certificate names successful");
+    }
+
+    #[test]
+    fn test_full_message_schema_parsing() {
+        println!("🧪 Test: Message schema parsing");
+
+        let request: ChatCompletionRequest = serde_json::from_value(json!({
+            "model": "gpt-5",
+            "messages": [
+                {"role": "developer", "content": "Be brief."},
+                {"role": "user", "name": "ana", "content": [
+                    {"type": "text", "text": "First line"},
+                    {"type": "text", "text": "Second line"}
+                ]},
+                {"role": "assistant", "content": null, "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "lookup", "arguments": "{}"}
+                }]},
+                {"role": "tool", "tool_call_id": "call_1", "content": [
+                    {"type": "text", "text": "42"}
+                ]}
+            ]
+        }))
+        .unwrap();
+
+        let messages = &request.messages;
+        assert_eq!(messages[0].role, "developer");
+        assert_eq!(messages[1].name.as_deref(), Some("ana"));
+        assert_eq!(messages[1].content, "First line\nSecond line");
+        assert_eq!(messages[2].content, "");
+        assert_eq!(messages[3].content, "42");
+        assert_eq!(messages[3].tool_call_id.as_deref(), Some("call_1"));
+
+        let image = serde_json::from_value::<ChatCompletionRequest>(json!({
+            "model": "gpt-5",
+            "messages": [{"role": "user", "content": [
+                {"type": "image_url", "image_url": {"url": "https://example.com/a.png"}}
+            ]}]
+        }));
+        let error = image.unwrap_err().to_string();
+        assert!(error.contains("image_url content parts are not supported"));
+
+        println!("✅ Message schema parsing successful");
+    }
 }
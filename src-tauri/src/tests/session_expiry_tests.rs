@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod session_expiry_tests {
+    use crate::health::{ComponentHealth, HealthLevel};
+    use crate::session_expiry::{needs_refresh, session_warning, with_warning, SessionWarning};
+    use chrono::{Duration, Utc};
+
+    #[test]
+    fn test_needs_refresh() {
+        println!("🧪 Test: Refresh is due shortly before expiry");
+
+        let now = Utc::now();
+        assert!(!needs_refresh(now + Duration::hours(1), now));
+        assert!(needs_refresh(now + Duration::minutes(5), now));
+        assert!(needs_refresh(now - Duration::minutes(5), now));
+
+        println!("✅ Refresh is due shortly before expiry successful");
+    }
+
+    #[test]
+    fn test_session_warning() {
+        println!("🧪 Test: Session warnings from token state");
+
+        let now = Utc::now();
+        let soon = now + Duration::hours(2);
+        let later = now + Duration::days(3);
+
+        // Refreshable tokens are not warned about
+        assert_eq!(session_warning(soon, true, None, now), None);
+        // Without a refresh token only an expiry within the warning window is
+        assert_eq!(session_warning(later, false, None, now), None);
+        assert_eq!(
+            session_warning(soon, false, None, now),
+            Some(SessionWarning::Expiring { expires_at: soon })
+        );
+        // A failed refresh is always warned about
+        assert_eq!(
+            session_warning(later, true, Some("invalid_grant"), now),
+            Some(SessionWarning::RefreshFailed {
+                reason: "invalid_grant".to_string()
+            })
+        );
+
+        println!("✅ Session warnings from token state successful");
+    }
+
+    #[test]
+    fn test_warning_messages() {
+        println!("🧪 Test: Session warning messages");
+
+        let now = Utc::now();
+        let expiring = |expires_at| SessionWarning::Expiring { expires_at }.message(now);
+        assert!(expiring(now + Duration::minutes(30)).contains("30 minutes"));
+        assert!(expiring(now + Duration::minutes(90)).contains("an hour"));
+        assert!(expiring(now + Duration::hours(5)).contains("5 hours"));
+        assert!(expiring(now - Duration::minutes(1)).contains("expired"));
+
+        let failed = SessionWarning::RefreshFailed {
+            reason: "invalid_grant".to_string(),
+        };
+        assert!(failed.message(now).contains("invalid_grant"));
+        assert!(failed.message(now).contains("log in again"));
+
+        let ended = SessionWarning::ReauthRequired {
+            reason: "Refresh token expired".to_string(),
+        };
+        assert!(ended.message(now).contains("Refresh token expired"));
+        assert!(ended.message(now).contains("log in again"));
+
+        println!("✅ Session warning messages successful");
+    }
+
+    #[test]
+    fn test_same_kind() {
+        println!("🧪 Test: Warnings of the same kind");
+
+        let now = Utc::now();
+        let expiring = SessionWarning::Expiring { expires_at: now };
+        let failed = |reason: &str| SessionWarning::RefreshFailed {
+            reason: reason.to_string(),
+        };
+
+        assert!(failed("timed out").same_kind(&failed("invalid_grant")));
+        assert!(expiring.same_kind(&SessionWarning::Expiring {
+            expires_at: now + Duration::hours(1)
+        }));
+        assert!(!expiring.same_kind(&failed("timed out")));
+
+        println!("✅ Warnings of the same kind successful");
+    }
+
+    #[test]
+    fn test_with_warning() {
+        println!("🧪 Test: Session warning degrades auth health");
+
+        let now = Utc::now();
+        let warning = SessionWarning::RefreshFailed {
+            reason: "invalid_grant".to_string(),
+        };
+
+        let healthy = with_warning(ComponentHealth::ok("auth", true), None, now);
+        assert_eq!(healthy.level, HealthLevel::Ok);
+
+        let degraded = with_warning(ComponentHealth::ok("auth", true), Some(&warning), now);
+        assert_eq!(degraded.level, HealthLevel::Degraded);
+        assert!(degraded.reason.unwrap().contains("invalid_grant"));
+        assert!(degraded.details.contains_key("session_warning"));
+
+        // A session that is already down stays down with its own reason
+        let down = with_warning(
+            ComponentHealth::down("auth", "Not authenticated", true),
+            Some(&warning),
+            now,
+        );
+        assert_eq!(down.level, HealthLevel::Down);
+        assert_eq!(down.reason.as_deref(), Some("Not authenticated"));
+
+        println!("✅ Session warning degrades auth health successful");
+    }
+}
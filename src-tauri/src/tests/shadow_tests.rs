@@ -0,0 +1,212 @@
+#[cfg(test)]
+mod shadow_tests {
+    use crate::managers::config_manager::{FallbackProvider, ShadowConfig};
+    use crate::middleware::metrics::Metrics;
+    use crate::middleware::shadow::{mirror_traffic, Shadow};
+    use crate::model_catalog::ModelCatalog;
+    use crate::shadow::{
+        completion_text, length_ratio, similarity, ShadowComparison, ShadowReport, ShadowStats,
+        MAX_RECENT,
+    };
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+        response::Json,
+        routing::post,
+        Router,
+    };
+    use chrono::Utc;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tower::ServiceExt;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn completion(content: &str) -> serde_json::Value {
+        serde_json::json!({
+            "choices": [{ "message": { "role": "assistant", "content": content } }]
+        })
+    }
+
+    fn comparison(similarity: Option<f64>) -> ShadowComparison {
+        ShadowComparison {
+            at: Utc::now(),
+            model: "gpt-5".to_string(),
+            primary_latency_ms: 200,
+            shadow_latency_ms: similarity.map(|_| 400),
+            similarity,
+            length_ratio: similarity.map(|_| 1.0),
+            error: similarity
+                .is_none()
+                .then(|| "returned status 500".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_answers_are_compared_by_words_and_length() {
+        println!("🧪 Test: Shadow answer comparison");
+
+        assert_eq!(similarity("The cat sat.", "the CAT sat"), 1.0);
+        assert_eq!(similarity("a b c d", "a b x y"), 2.0 / 6.0);
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("hello", ""), 0.0);
+
+        assert_eq!(length_ratio("abcd", "ab"), 0.5);
+        assert_eq!(length_ratio("", ""), 1.0);
+
+        println!("✅ Shadow answer comparison successful");
+    }
+
+    #[test]
+    fn test_completion_text_of_bodies_and_streams() {
+        println!("🧪 Test: Completion text extraction");
+
+        let body = serde_json::to_vec(&completion("Hello there")).unwrap();
+        assert_eq!(completion_text(&body).as_deref(), Some("Hello there"));
+
+        let stream = "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n\
+                      data: {\"choices\":[{\"delta\":{\"content\":\"Hello\"}}]}\n\n\
+                      data: {\"choices\":[{\"delta\":{\"content\":\" there\"}}]}\n\n";
+        // Cut off before the end
+        assert_eq!(completion_text(stream.as_bytes()), None);
+        let finished = format!("{}data: [DONE]\n\n", stream);
+        assert_eq!(
+            completion_text(finished.as_bytes()).as_deref(),
+            Some("Hello there")
+        );
+
+        assert_eq!(completion_text(b"{\"error\":{}}"), None);
+
+        println!("✅ Completion text extraction successful");
+    }
+
+    #[test]
+    fn test_report_averages_compared_requests() {
+        println!("🧪 Test: Shadow report");
+
+        let stats = ShadowStats::default();
+        assert_eq!(stats.report().mean_similarity, None);
+
+        stats.record(comparison(Some(0.5)));
+        stats.record(comparison(Some(1.0)));
+        stats.record(comparison(None));
+        let report = stats.report();
+        assert_eq!(report.compared, 2);
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.mean_similarity, Some(0.75));
+        assert_eq!(report.mean_shadow_latency_ms, Some(400.0));
+        assert!(report.recent[0].error.is_some());
+
+        for _ in 0..MAX_RECENT {
+            stats.record(comparison(Some(1.0)));
+        }
+        assert_eq!(stats.report().recent.len(), MAX_RECENT);
+
+        println!("✅ Shadow report successful");
+    }
+
+    async fn mirror(target: &MockServer) -> (ShadowReport, serde_json::Value, String) {
+        let stats = Arc::new(ShadowStats::default());
+        let metrics = Arc::new(Metrics::new());
+        let shadow = Arc::new(Shadow::new(
+            ShadowConfig {
+                enabled: true,
+                percentage: 100,
+                target: FallbackProvider {
+                    name: "local".to_string(),
+                    base_url: Some(format!("{}/v1", target.uri())),
+                    api_key: None,
+                    model: Some("llama3".to_string()),
+                },
+            },
+            reqwest::Client::new(),
+            Duration::from_secs(5),
+            None,
+            Arc::new(ModelCatalog::default()),
+            stats.clone(),
+            metrics.clone(),
+        ));
+        let router = Router::new()
+            .route(
+                "/v1/chat/completions",
+                post(|| async { Json(completion("The sky is blue today")) }),
+            )
+            .layer(axum::middleware::from_fn_with_state(shadow, mirror_traffic));
+
+        let request = Request::post("/v1/chat/completions")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                r#"{"model":"gpt-5","messages":[{"role":"user","content":"Sky?"}]}"#,
+            ))
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        // Compared in the background once the response is sent
+        for _ in 0..100 {
+            let report = stats.report();
+            if !report.recent.is_empty() {
+                return (report, body, metrics.render());
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("the mirrored request was not compared");
+    }
+
+    #[tokio::test]
+    async fn test_mirrored_request_is_compared() {
+        println!("🧪 Test: Mirroring to the shadow backend");
+
+        let target = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(body_partial_json(
+                serde_json::json!({ "model": "llama3", "stream": false }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(completion("The sky is grey")))
+            .expect(1)
+            .mount(&target)
+            .await;
+
+        let (report, body, metrics) = mirror(&target).await;
+        // The client gets the primary's answer
+        assert_eq!(
+            body["choices"][0]["message"]["content"],
+            "The sky is blue today"
+        );
+        assert_eq!(report.compared, 1);
+        let comparison = &report.recent[0];
+        assert_eq!(comparison.model, "gpt-5");
+        assert_eq!(comparison.similarity, Some(3.0 / 6.0));
+        assert!(comparison.length_ratio.unwrap() < 1.0);
+        assert!(metrics.contains("mindlink_shadow_requests_total{outcome=\"compared\"} 1"));
+
+        println!("✅ Mirroring to the shadow backend successful");
+    }
+
+    #[tokio::test]
+    async fn test_failing_shadow_backend_is_recorded() {
+        println!("🧪 Test: Failing shadow backend");
+
+        let target = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&target)
+            .await;
+
+        let (report, body, metrics) = mirror(&target).await;
+        assert_eq!(
+            body["choices"][0]["message"]["content"],
+            "The sky is blue today"
+        );
+        assert_eq!(report.failed, 1);
+        assert!(report.recent[0].error.as_deref().unwrap().contains("500"));
+        assert!(metrics.contains("mindlink_shadow_requests_total{outcome=\"failed\"} 1"));
+
+        println!("✅ Failing shadow backend successful");
+    }
+}
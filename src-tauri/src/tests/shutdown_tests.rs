@@ -0,0 +1,117 @@
+#[cfg(test)]
+mod shutdown_tests {
+    use crate::error::MindLinkError;
+    use crate::shutdown::{
+        plan, run, ShutdownStep, StepOutcome, EXIT_CLEAN, EXIT_STEP_FAILED, EXIT_TIMED_OUT,
+    };
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[test]
+    fn test_plan_puts_dependencies_first() {
+        println!("🧪 Test: Shutdown plan ordering");
+
+        let shuffled = [
+            ShutdownStep::FlushAnalytics,
+            ShutdownStep::CloseTunnel,
+            ShutdownStep::DrainServer,
+            ShutdownStep::StopChildren,
+        ];
+        assert_eq!(
+            plan(&shuffled),
+            vec![
+                ShutdownStep::DrainServer,
+                ShutdownStep::FlushAnalytics,
+                ShutdownStep::CloseTunnel,
+                ShutdownStep::StopChildren,
+            ]
+        );
+
+        // Missing dependencies do not hold a step back
+        assert_eq!(
+            plan(&[ShutdownStep::CloseTunnel]),
+            vec![ShutdownStep::CloseTunnel]
+        );
+
+        let ordered = plan(&ShutdownStep::ALL);
+        for (index, step) in ordered.iter().enumerate() {
+            for dependency in step.after() {
+                assert!(ordered[..index].contains(dependency));
+            }
+        }
+
+        println!("✅ Shutdown plan ordering successful");
+    }
+
+    #[tokio::test]
+    async fn test_failed_step_does_not_stop_the_rest() {
+        println!("🧪 Test: Shutdown continues past a failed step");
+
+        let ran = Arc::new(Mutex::new(Vec::new()));
+        let report = run(&ShutdownStep::ALL, Duration::from_secs(5), |step| {
+            let ran = ran.clone();
+            async move {
+                ran.lock().unwrap().push(step);
+                if step == ShutdownStep::CloseTunnel {
+                    return Err(MindLinkError::Internal {
+                        message: "cloudflared did not exit".to_string(),
+                        component: None,
+                        source: None,
+                    });
+                }
+                Ok(())
+            }
+        })
+        .await;
+
+        assert_eq!(*ran.lock().unwrap(), ShutdownStep::ALL.to_vec());
+        assert_eq!(report.exit_code(), EXIT_STEP_FAILED);
+        let problems = report.problems();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("close tunnel failed"));
+
+        let clean = run(&ShutdownStep::ALL, Duration::from_secs(5), |_| async {
+            Ok(())
+        })
+        .await;
+        assert_eq!(clean.exit_code(), EXIT_CLEAN);
+
+        println!("✅ Shutdown continues past a failed step successful");
+    }
+
+    #[tokio::test]
+    async fn test_hard_timeout_cuts_off_and_skips() {
+        println!("🧪 Test: Shutdown hard timeout");
+
+        let report = run(
+            &ShutdownStep::ALL,
+            Duration::from_millis(100),
+            |step| async move {
+                if step == ShutdownStep::DrainServer {
+                    tokio::time::sleep(Duration::from_secs(10)).await;
+                }
+                Ok(())
+            },
+        )
+        .await;
+
+        let outcomes: Vec<StepOutcome> = report
+            .steps
+            .iter()
+            .map(|report| report.outcome.clone())
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                StepOutcome::TimedOut,
+                StepOutcome::Skipped,
+                StepOutcome::Skipped,
+                StepOutcome::Skipped,
+            ]
+        );
+        assert_eq!(report.exit_code(), EXIT_TIMED_OUT);
+        assert!(report.elapsed_ms < 5_000);
+
+        println!("✅ Shutdown hard timeout successful");
+    }
+}
@@ -0,0 +1,61 @@
+#[cfg(test)]
+mod stream_continuation_tests {
+    use crate::stream_continuation::{overlap, ContinuationStitcher};
+
+    #[test]
+    fn test_overlap_detection() {
+        println!("🧪 Test: Continuation overlap detection");
+
+        let received = "The quick brown fox jumps over the lazy";
+        assert_eq!(overlap(received, "over the lazy dog."), 13);
+        assert_eq!(overlap(received, " dog."), 0);
+        // Short matches are treated as coincidence
+        assert_eq!(overlap(received, "lazy dog"), 0);
+        assert_eq!(overlap("", "anything at all"), 0);
+
+        println!("✅ Continuation overlap detection successful");
+    }
+
+    #[test]
+    fn test_stitcher_drops_repeated_text() {
+        println!("🧪 Test: Stitching a continuation onto a truncated stream");
+
+        let received = "Rust ownership means every value has a single owner, and when";
+        let mut stitcher = ContinuationStitcher::new(received);
+
+        // The start is held back until it can be compared with the tail
+        assert_eq!(stitcher.push("a single owner, "), None);
+        assert_eq!(stitcher.push("and when"), None);
+        let released = stitcher
+            .push(" the owner goes out of scope the value is dropped.")
+            .expect("buffer covers the tail");
+        assert_eq!(
+            released,
+            " the owner goes out of scope the value is dropped."
+        );
+
+        // After that, deltas pass straight through
+        assert_eq!(stitcher.push(" Next"), Some(" Next".to_string()));
+        assert_eq!(stitcher.finish(), None);
+
+        println!("✅ Continuation stitching successful");
+    }
+
+    #[test]
+    fn test_stitcher_flushes_short_continuations() {
+        println!("🧪 Test: Flushing a short continuation");
+
+        let mut stitcher =
+            ContinuationStitcher::new("A long enough answer that ends abruptly in the mid");
+        assert_eq!(stitcher.push("dle."), None);
+        assert_eq!(stitcher.finish(), Some("dle.".to_string()));
+
+        // The default stitcher forwards everything
+        let mut passthrough = ContinuationStitcher::default();
+        assert_eq!(passthrough.push("Hello"), Some("Hello".to_string()));
+        assert_eq!(passthrough.push(""), None);
+        assert_eq!(passthrough.finish(), None);
+
+        println!("✅ Short continuation flushing successful");
+    }
+}
@@ -0,0 +1,172 @@
+#[cfg(test)]
+mod token_store_tests {
+    use crate::{secret_box, token_store};
+    use keyring::credential::{Credential, CredentialApi, CredentialBuilderApi};
+    use std::any::Any;
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex, Once};
+    use tempfile::TempDir;
+    use tokio::fs;
+
+    type Secrets = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+    /// Entry of [`MemoryKeyring`]
+    #[derive(Debug)]
+    struct MemoryCredential {
+        user: String,
+        secrets: Secrets,
+    }
+
+    impl CredentialApi for MemoryCredential {
+        fn set_secret(&self, secret: &[u8]) -> keyring::Result<()> {
+            self.secrets
+                .lock()
+                .unwrap()
+                .insert(self.user.clone(), secret.to_vec());
+            Ok(())
+        }
+
+        fn get_secret(&self) -> keyring::Result<Vec<u8>> {
+            let secrets = self.secrets.lock().unwrap();
+            secrets
+                .get(&self.user)
+                .cloned()
+                .ok_or(keyring::Error::NoEntry)
+        }
+
+        fn delete_credential(&self) -> keyring::Result<()> {
+            let removed = self.secrets.lock().unwrap().remove(&self.user);
+            removed.map(|_| ()).ok_or(keyring::Error::NoEntry)
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    /// Keyring kept in memory, so tests never touch the one of the OS.
+    /// Entries under a `no-keyring` directory fail like an unavailable
+    /// keyring does.
+    #[derive(Debug, Default)]
+    struct MemoryKeyring {
+        secrets: Secrets,
+    }
+
+    impl CredentialBuilderApi for MemoryKeyring {
+        fn build(
+            &self,
+            _target: Option<&str>,
+            _service: &str,
+            user: &str,
+        ) -> keyring::Result<Box<Credential>> {
+            if user.contains("no-keyring") {
+                return Err(keyring::Error::NoStorageAccess("no keyring".into()));
+            }
+            Ok(Box::new(MemoryCredential {
+                user: user.to_string(),
+                secrets: self.secrets.clone(),
+            }))
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+    }
+
+    fn in_memory_keyring() {
+        static INSTALL: Once = Once::new();
+        INSTALL.call_once(|| {
+            keyring::set_default_credential_builder(Box::new(MemoryKeyring::default()))
+        });
+    }
+
+    #[tokio::test]
+    async fn test_token_files_move_into_the_keyring() {
+        println!("🧪 Test: Tokens in the keyring");
+        in_memory_keyring();
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("auth.json");
+        let tokens = r#"{"access_token":"secret-access"}"#;
+        fs::write(&path, tokens).await.unwrap();
+
+        // A file from before the keyring is read, then moved
+        assert_eq!(
+            token_store::read(&path).await.unwrap().as_deref(),
+            Some(tokens)
+        );
+        let on_disk = fs::read_to_string(&path).await.unwrap();
+        assert!(!on_disk.contains("secret-access"));
+        assert_eq!(
+            token_store::read(&path).await.unwrap().as_deref(),
+            Some(tokens)
+        );
+
+        let refreshed = r#"{"access_token":"refreshed-access"}"#;
+        token_store::write(&path, refreshed).await.unwrap();
+        assert_eq!(fs::read_to_string(&path).await.unwrap(), on_disk);
+        assert_eq!(
+            token_store::read(&path).await.unwrap().as_deref(),
+            Some(refreshed)
+        );
+
+        assert!(token_store::remove(&path).await.unwrap());
+        assert!(!path.exists());
+        assert_eq!(token_store::read(&path).await.unwrap(), None);
+        assert!(!token_store::remove(&path).await.unwrap());
+
+        println!("✅ Tokens in the keyring successful");
+    }
+
+    #[tokio::test]
+    async fn test_tokens_fall_back_to_files() {
+        println!("🧪 Test: Tokens without a keyring");
+        in_memory_keyring();
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("no-keyring").join("auth.json");
+        let tokens = r#"{"access_token":"secret-access"}"#;
+
+        token_store::write(&path, tokens).await.unwrap();
+        let on_disk = fs::read_to_string(&path).await.unwrap();
+        if secret_box::master_key().await.is_some() {
+            assert!(secret_box::is_encrypted(&on_disk));
+            assert!(!on_disk.contains("secret-access"));
+        } else {
+            assert_eq!(on_disk, tokens);
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).await.unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert_eq!(
+            token_store::read(&path).await.unwrap().as_deref(),
+            Some(tokens)
+        );
+
+        // A plaintext file from before encryption is encrypted once read
+        fs::write(&path, tokens).await.unwrap();
+        assert_eq!(
+            token_store::read(&path).await.unwrap().as_deref(),
+            Some(tokens)
+        );
+        if secret_box::master_key().await.is_some() {
+            let on_disk = fs::read_to_string(&path).await.unwrap();
+            assert!(secret_box::is_encrypted(&on_disk));
+            // The readable file is restricted before the tokens go in
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                let mode = fs::metadata(&path).await.unwrap().permissions().mode();
+                assert_eq!(mode & 0o777, 0o600);
+            }
+        }
+
+        assert!(token_store::remove(&path).await.unwrap());
+        assert!(!path.exists());
+
+        println!("✅ Tokens without a keyring successful");
+    }
+}
@@ -0,0 +1,184 @@
+#[cfg(test)]
+mod tool_emulation_tests {
+    use crate::managers::config_manager::{ToolCallStrictness, ToolEmulationConfig};
+    use crate::managers::server_manager::{FunctionCall, Message, ToolCall};
+    use crate::tool_emulation::{flatten_tool_messages, EmulatedOutput, ToolEmulation};
+    use serde_json::json;
+
+    fn weather_emulation(strictness: ToolCallStrictness) -> ToolEmulation {
+        ToolEmulation {
+            tools: vec![json!({
+                "type": "function",
+                "function": {
+                    "name": "get_weather",
+                    "parameters": {
+                        "type": "object",
+                        "properties": { "city": { "type": "string" } }
+                    }
+                }
+            })],
+            tool_choice: None,
+            strictness,
+        }
+    }
+
+    fn message(role: &str, content: &str) -> Message {
+        Message {
+            role: role.to_string(),
+            content: content.to_string(),
+            tool_calls: None,
+            tool_call_id: None,
+            name: None,
+            reasoning_content: None,
+        }
+    }
+
+    #[test]
+    fn test_lenient_parsing_extracts_tool_calls() {
+        println!("🧪 Test: Lenient tool call parsing");
+
+        let emulation = weather_emulation(ToolCallStrictness::Lenient);
+        let output = emulation
+            .parse_output(
+                "Sure!\n```json\n{\"tool_calls\": [{\"name\": \"get_weather\", \
+                 \"arguments\": {\"city\": \"Paris\"}}, {\"name\": \"unknown\"}]}\n```",
+            )
+            .expect("lenient parsing should not fail");
+
+        match output {
+            EmulatedOutput::ToolCalls(calls) => {
+                assert_eq!(calls.len(), 1);
+                assert_eq!(calls[0].function.name, "get_weather");
+                assert_eq!(calls[0].call_type, "function");
+                assert!(calls[0].id.starts_with("call_"));
+                let args: serde_json::Value =
+                    serde_json::from_str(&calls[0].function.arguments).expect("JSON arguments");
+                assert_eq!(args["city"], "Paris");
+            },
+            EmulatedOutput::Text(text) => panic!("expected tool calls, got text: {}", text),
+        }
+
+        // Plain answers pass through untouched
+        match emulation.parse_output("It is sunny.").expect("plain text") {
+            EmulatedOutput::Text(text) => assert_eq!(text, "It is sunny."),
+            EmulatedOutput::ToolCalls(_) => panic!("expected text"),
+        }
+
+        println!("✅ Lenient tool call parsing successful");
+    }
+
+    #[test]
+    fn test_strict_parsing_rejects_malformed_output() {
+        println!("🧪 Test: Strict tool call parsing");
+
+        let emulation = weather_emulation(ToolCallStrictness::Strict);
+
+        let valid = emulation
+            .parse_output("{\"tool_calls\": [{\"name\": \"get_weather\", \"arguments\": {}}]}")
+            .expect("exact envelope should parse");
+        assert!(matches!(valid, EmulatedOutput::ToolCalls(ref calls) if calls.len() == 1));
+
+        // Unknown functions, non-object arguments and prose around the JSON are errors
+        assert!(emulation
+            .parse_output("{\"tool_calls\": [{\"name\": \"delete_all\", \"arguments\": {}}]}")
+            .is_err());
+        assert!(emulation
+            .parse_output("{\"tool_calls\": [{\"name\": \"get_weather\", \"arguments\": \"x\"}]}")
+            .is_err());
+        assert!(emulation
+            .parse_output("Calling: {\"tool_calls\": [{\"name\": \"get_weather\"}]}")
+            .is_err());
+
+        // A forced tool call must be honoured
+        let mut forced = weather_emulation(ToolCallStrictness::Strict);
+        forced.tool_choice = Some(json!("required"));
+        assert!(forced.parse_output("I don't need a tool").is_err());
+
+        println!("✅ Strict tool call parsing successful");
+    }
+
+    #[test]
+    fn test_prompt_injection_and_history_flattening() {
+        println!("🧪 Test: Tool prompt injection and history flattening");
+
+        let emulation = weather_emulation(ToolCallStrictness::Lenient);
+        let mut messages = vec![
+            message("user", "Weather in Paris?"),
+            Message {
+                tool_calls: Some(vec![ToolCall {
+                    id: "call_1".to_string(),
+                    call_type: "function".to_string(),
+                    function: FunctionCall {
+                        name: "get_weather".to_string(),
+                        arguments: "{\"city\":\"Paris\"}".to_string(),
+                    },
+                }]),
+                ..message("assistant", "")
+            },
+            Message {
+                tool_call_id: Some("call_1".to_string()),
+                ..message("tool", "18C and sunny")
+            },
+        ];
+
+        flatten_tool_messages(&mut messages);
+        emulation.inject_prompt(&mut messages);
+
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0].role, "system");
+        assert!(messages[0].content.contains("get_weather"));
+        assert!(messages[2].content.contains("\"tool_calls\""));
+        assert!(messages[2].tool_calls.is_none());
+        assert_eq!(messages[3].role, "user");
+        assert!(messages[3].content.contains("call_1"));
+        assert!(messages[3].content.contains("18C and sunny"));
+
+        println!("✅ Tool prompt injection and history flattening successful");
+    }
+
+    #[test]
+    fn test_legacy_function_messages_are_flattened() {
+        println!("🧪 Test: Legacy function message flattening");
+
+        let mut messages = vec![Message {
+            name: Some("get_weather".to_string()),
+            ..message("function", "18C and sunny")
+        }];
+        flatten_tool_messages(&mut messages);
+
+        assert_eq!(messages[0].role, "user");
+        assert!(messages[0].name.is_none());
+        assert_eq!(
+            messages[0].content,
+            "Result of function get_weather:\n18C and sunny"
+        );
+
+        println!("✅ Legacy function message flattening successful");
+    }
+
+    #[test]
+    fn test_route_selection() {
+        println!("🧪 Test: Tool emulation route selection");
+
+        // Off unless a route enables it
+        let mut config = ToolEmulationConfig::default();
+        assert!(config.route_for("gpt-5").is_none());
+
+        config.routes.insert(
+            "*".to_string(),
+            serde_json::from_value(json!({ "enabled": true })).expect("valid route"),
+        );
+        assert!(config.route_for("gpt-5").is_some());
+        config.routes.insert(
+            "codex-mini".to_string(),
+            serde_json::from_value(json!({ "enabled": false })).expect("valid route"),
+        );
+        assert!(config.route_for("codex-mini").is_none());
+        assert_eq!(
+            config.route_for("gpt-5").map(|route| route.strictness),
+            Some(ToolCallStrictness::Lenient)
+        );
+
+        println!("✅ Tool emulation route selection successful");
+    }
+}
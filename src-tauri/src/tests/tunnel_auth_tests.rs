@@ -0,0 +1,136 @@
+#[cfg(test)]
+mod tunnel_auth_tests {
+    use crate::authorized_apps::{generate_api_key, AuthorizedApp};
+    use crate::middleware::tunnel_auth::{
+        is_tunneled, require_tunnel_token, TunnelAuth, TunnelTokenPolicy,
+    };
+    use crate::tunnel_tokens::TunnelTokens;
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use std::net::IpAddr;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+    use tower::ServiceExt;
+
+    const TUNNEL_HOST: &str = "mindlink.example.com";
+
+    fn ip(value: &str) -> Option<IpAddr> {
+        Some(value.parse().unwrap())
+    }
+
+    fn app(api_key: &str) -> AuthorizedApp {
+        AuthorizedApp {
+            id: "app-id".to_string(),
+            name: "App".to_string(),
+            model: "gpt-5".to_string(),
+            created_at: "2024-01-01T00:00:00Z".to_string(),
+            api_key: api_key.to_string(),
+            allowed_models: Vec::new(),
+            system_prompt: None,
+            daily_token_budget: None,
+            monthly_token_budget: None,
+        }
+    }
+
+    #[test]
+    fn test_tunneled_requests() {
+        println!("🧪 Test: Tunneled requests");
+
+        let local = ip("127.0.0.1");
+        let tunnel = Some(TUNNEL_HOST);
+        assert!(is_tunneled(tunnel, Some(TUNNEL_HOST), local, local));
+        assert!(is_tunneled(
+            tunnel,
+            Some("MindLink.example.com:443"),
+            local,
+            local
+        ));
+        assert!(!is_tunneled(tunnel, Some("127.0.0.1:3001"), local, local));
+        assert!(!is_tunneled(None, Some(TUNNEL_HOST), local, local));
+
+        // Forwarded by cloudflared for a remote client, whatever the host
+        let remote = ip("203.0.113.7");
+        assert!(is_tunneled(tunnel, Some("localhost"), local, remote));
+        assert!(is_tunneled(None, None, ip("::ffff:127.0.0.1"), remote));
+        assert!(is_tunneled(None, None, None, remote));
+
+        // Direct clients on the network are not the tunnel's
+        let lan = ip("192.168.1.20");
+        assert!(!is_tunneled(tunnel, Some("192.168.1.10:3001"), lan, lan));
+
+        println!("✅ Tunneled requests successful");
+    }
+
+    #[tokio::test]
+    async fn test_tunnel_token_middleware() {
+        println!("🧪 Test: Tunnel token middleware");
+
+        let mut tokens = TunnelTokens::default();
+        let now = chrono::Utc::now();
+        let previous = tokens.rotate(chrono::Duration::hours(1), false, now).token;
+        let current = tokens.rotate(chrono::Duration::hours(1), false, now).token;
+        let api_key = generate_api_key();
+
+        let policy = Arc::new(RwLock::new(TunnelTokenPolicy {
+            required: true,
+            instance_token: Some("instance-token".to_string()),
+        }));
+        let auth = TunnelAuth {
+            policy: policy.clone(),
+            tunnel_url: Arc::new(RwLock::new(Some(format!("https://{}", TUNNEL_HOST)))),
+            tunnel_tokens: Arc::new(RwLock::new(tokens)),
+            authorized_apps: Arc::new(RwLock::new(vec![app(&api_key)])),
+        };
+        let app = Router::new()
+            .route("/health", get(|| async { "ok" }))
+            .route("/v1/models", get(|| async { "models" }))
+            .layer(axum::middleware::from_fn_with_state(
+                auth,
+                require_tunnel_token,
+            ));
+        let status = |host: &'static str, path: &'static str, token: Option<String>| {
+            let app = app.clone();
+            async move {
+                let mut builder = Request::builder().uri(path).header(header::HOST, host);
+                if let Some(token) = token {
+                    builder = builder.header(header::AUTHORIZATION, format!("Bearer {}", token));
+                }
+                let request = builder.body(Body::empty()).unwrap();
+                app.oneshot(request).await.unwrap().status()
+            }
+        };
+
+        assert_eq!(
+            status("127.0.0.1:3001", "/v1/models", None).await,
+            StatusCode::OK
+        );
+        assert_eq!(status(TUNNEL_HOST, "/health", None).await, StatusCode::OK);
+        assert_eq!(
+            status(TUNNEL_HOST, "/v1/models", None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(TUNNEL_HOST, "/v1/models", Some("sk-wrong".to_string())).await,
+            StatusCode::UNAUTHORIZED
+        );
+        for token in ["instance-token".to_string(), current, previous, api_key] {
+            assert_eq!(
+                status(TUNNEL_HOST, "/v1/models", Some(token)).await,
+                StatusCode::OK
+            );
+        }
+
+        // Turned off in the settings
+        policy.write().await.required = false;
+        assert_eq!(
+            status(TUNNEL_HOST, "/v1/models", None).await,
+            StatusCode::OK
+        );
+
+        println!("✅ Tunnel token middleware successful");
+    }
+}
@@ -0,0 +1,182 @@
+#[cfg(test)]
+mod tunnel_limits_tests {
+    use crate::managers::config_manager::TunnelLimitsConfig;
+    use crate::middleware::tunnel_limits::{limit_tunnel_traffic, TunnelLimiter};
+    use crate::tunnel_limits::{EgressLedger, TunnelLimit, TunnelLimitWarning};
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+        response::Response,
+        routing::get,
+        Router,
+    };
+    use chrono::{TimeZone, Utc};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+    use tokio::sync::{broadcast, RwLock};
+    use tower::ServiceExt;
+
+    const TUNNEL_HOST: &str = "mindlink.example.com";
+    const LOCAL_HOST: &str = "127.0.0.1:3001";
+
+    fn router(
+        config: TunnelLimitsConfig,
+        ledger: Arc<EgressLedger>,
+    ) -> (Router, broadcast::Receiver<TunnelLimitWarning>) {
+        let (events, warnings) = broadcast::channel(16);
+        let limiter = Arc::new(TunnelLimiter::new(
+            &config,
+            ledger,
+            Arc::new(RwLock::new(Some(format!("https://{}", TUNNEL_HOST)))),
+            events,
+        ));
+        let router = Router::new()
+            .route("/health", get(|| async { "ok" }))
+            .route("/v1/models", get(|| async { "models" }))
+            .route(
+                "/stream",
+                get(|| async {
+                    let chunks = vec![Ok::<_, std::io::Error>("data: {}\n\n")];
+                    Body::from_stream(futures_util::stream::iter(chunks))
+                }),
+            )
+            .layer(axum::middleware::from_fn_with_state(
+                limiter,
+                limit_tunnel_traffic,
+            ));
+        (router, warnings)
+    }
+
+    async fn send(router: &Router, host: &str, path: &str) -> Response {
+        let request = Request::builder()
+            .uri(path)
+            .header(header::HOST, host)
+            .body(Body::empty())
+            .unwrap();
+        router.clone().oneshot(request).await.unwrap()
+    }
+
+    #[test]
+    fn test_egress_ledger() {
+        println!("🧪 Test: Tunnel egress ledger");
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("tunnel_egress.json");
+        let may = Utc.with_ymd_and_hms(2024, 5, 31, 23, 0, 0).unwrap();
+        let june = Utc.with_ymd_and_hms(2024, 6, 1, 1, 0, 0).unwrap();
+
+        let ledger = EgressLedger::open(&path);
+        assert_eq!(ledger.used(may), 0);
+        assert_eq!(ledger.record(100, may), 100);
+        assert_eq!(ledger.record(150, may), 250);
+        assert_eq!(ledger.used(june), 0, "Every month starts afresh");
+        ledger.save().unwrap();
+
+        let reopened = EgressLedger::open(&path);
+        assert_eq!(reopened.used(may), 250);
+        assert_eq!(reopened.record(10, june), 10);
+
+        // A ledger that cannot be read does not lift the cap
+        std::fs::write(&path, "{\"month\": \"2024-0").unwrap();
+        let corrupt = EgressLedger::open(&path);
+        assert_eq!(corrupt.used(may), u64::MAX);
+        assert_eq!(corrupt.record(10, may), u64::MAX);
+        corrupt.save().unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "{\"month\": \"2024-0"
+        );
+
+        // Removing the file lifts the cap without a restart
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(corrupt.used(may), 0);
+
+        println!("✅ Tunnel egress ledger successful");
+    }
+
+    #[tokio::test]
+    async fn test_connection_limit() {
+        println!("🧪 Test: Tunnel connection limit");
+
+        let config = TunnelLimitsConfig {
+            max_connections: Some(4),
+            max_monthly_egress_mb: None,
+            warn_at_percent: 50,
+        };
+        let (router, mut warnings) = router(config, Arc::new(EgressLedger::default()));
+
+        // Streams hold their connection until they are dropped
+        let mut streams = Vec::new();
+        for _ in 0..4 {
+            let response = send(&router, TUNNEL_HOST, "/stream").await;
+            assert_eq!(response.status(), StatusCode::OK);
+            streams.push(response);
+        }
+        let warning = warnings.try_recv().unwrap();
+        assert_eq!(warning.limit, TunnelLimit::Connections);
+        assert_eq!((warning.used, warning.max, warning.reached), (2, 4, false));
+        assert!(warnings.try_recv().is_err(), "Warned once");
+
+        let refused = send(&router, TUNNEL_HOST, "/v1/models").await;
+        assert_eq!(refused.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(refused.headers()[header::RETRY_AFTER], "5");
+        let warning = warnings.try_recv().unwrap();
+        assert_eq!((warning.used, warning.reached), (4, true));
+
+        // Local clients are not limited
+        let local = send(&router, LOCAL_HOST, "/v1/models").await;
+        assert_eq!(local.status(), StatusCode::OK);
+
+        drop(streams);
+        let response = send(&router, TUNNEL_HOST, "/v1/models").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(warnings.try_recv().is_err());
+
+        println!("✅ Tunnel connection limit successful");
+    }
+
+    #[tokio::test]
+    async fn test_egress_limit() {
+        println!("🧪 Test: Tunnel egress limit");
+
+        let config = TunnelLimitsConfig {
+            max_connections: None,
+            max_monthly_egress_mb: Some(1),
+            warn_at_percent: 50,
+        };
+        let ledger = Arc::new(EgressLedger::default());
+        let (router, mut warnings) = router(config, ledger.clone());
+
+        let response = send(&router, TUNNEL_HOST, "/v1/models").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(ledger.used(Utc::now()), "models".len() as u64);
+        send(&router, LOCAL_HOST, "/v1/models").await;
+        assert_eq!(ledger.used(Utc::now()), "models".len() as u64);
+        assert!(warnings.try_recv().is_err());
+
+        ledger.record(600 * 1024, Utc::now());
+        send(&router, TUNNEL_HOST, "/v1/models").await;
+        let warning = warnings.try_recv().unwrap();
+        assert_eq!(warning.limit, TunnelLimit::MonthlyEgress);
+        assert_eq!((warning.max, warning.reached), (1024 * 1024, false));
+
+        ledger.record(1024 * 1024, Utc::now());
+        let refused = send(&router, TUNNEL_HOST, "/v1/models").await;
+        assert_eq!(refused.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: i64 = refused.headers()[header::RETRY_AFTER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(retry_after > 0 && retry_after <= 31 * 24 * 3600);
+        assert!(warnings.try_recv().unwrap().reached);
+
+        // The tunnel can still be probed, and local clients are not limited
+        let health = send(&router, TUNNEL_HOST, "/health").await;
+        assert_eq!(health.status(), StatusCode::OK);
+        let local = send(&router, LOCAL_HOST, "/v1/models").await;
+        assert_eq!(local.status(), StatusCode::OK);
+
+        println!("✅ Tunnel egress limit successful");
+    }
+}
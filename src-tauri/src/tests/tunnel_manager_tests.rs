@@ -1,7 +1,14 @@
 #[cfg(test)]
 mod tunnel_manager_tests {
-    use crate::managers::tunnel_manager::{TunnelManager, TunnelType};
+    use crate::managers::config_manager::{CloudflaredOptions, NamedTunnelConfig, TunnelService};
+    use crate::managers::tunnel_manager::{
+        cloudflared_args, named_tunnel_config, parse_created_tunnel_id, parse_listed_tunnel_id,
+        parse_ngrok_log, parse_quick_tunnel_url, probe_round_trip, NgrokEvent, PublicCheck,
+        ReachFailure, TunnelManager, TunnelType,
+    };
     use regex::Regex;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[tokio::test]
     async fn test_tunnel_manager_creation() {
@@ -118,6 +125,65 @@ mod tunnel_manager_tests {
         println!("✅ Named tunnel type configuration successful");
     }
 
+    #[tokio::test]
+    async fn test_named_tunnel_needs_hostname() {
+        println!("🧪 Test: Named tunnel needs a hostname");
+
+        let mut manager = TunnelManager::new()
+            .await
+            .expect("Failed to create tunnel manager");
+        manager
+            .set_named_tunnel(NamedTunnelConfig {
+                name: "mindlink-home".to_string(),
+                hostname: String::new(),
+                tunnel_id: None,
+            })
+            .await;
+
+        let error = manager.create_tunnel().await.unwrap_err();
+        assert!(error.to_string().contains("No hostname"));
+        assert!(manager.named_tunnel_id().is_none());
+        assert!(!manager.is_connected().await);
+
+        println!("✅ Named tunnel needs a hostname successful");
+    }
+
+    #[test]
+    fn test_named_tunnel_setup_parsing() {
+        println!("🧪 Test: Named tunnel setup parsing");
+
+        let id = "6ff42ae2-765d-4adf-8112-31c55c1551ef";
+        let created = format!(
+            "Tunnel credentials written to /home/ada/.mindlink/tunnels/home.json.\n\
+             Created tunnel home with id {}\n",
+            id
+        );
+        assert_eq!(parse_created_tunnel_id(&created).as_deref(), Some(id));
+        assert!(parse_created_tunnel_id("error: tunnel with name already exists").is_none());
+
+        let listed = format!(
+            r#"[{{"id":"{}","name":"home","created_at":"2024-01-01T00:00:00Z","connections":[]}}]"#,
+            id
+        );
+        assert_eq!(parse_listed_tunnel_id(&listed, "home").as_deref(), Some(id));
+        assert!(parse_listed_tunnel_id(&listed, "other").is_none());
+        assert!(parse_listed_tunnel_id("not json", "home").is_none());
+
+        let config = named_tunnel_config(
+            id,
+            std::path::Path::new("/tunnels/home.json"),
+            "mindlink.example.com",
+            3001,
+        );
+        assert!(config.starts_with(&format!("tunnel: {}\n", id)));
+        assert!(config.contains("credentials-file: /tunnels/home.json\n"));
+        assert!(config
+            .contains("  - hostname: mindlink.example.com\n    service: http://localhost:3001\n"));
+        assert!(config.ends_with("  - service: http_status:404\n"));
+
+        println!("✅ Named tunnel setup parsing successful");
+    }
+
     #[tokio::test]
     async fn test_create_tunnel() {
         println!("🧪 Test: Create tunnel");
@@ -176,6 +242,105 @@ mod tunnel_manager_tests {
         println!("✅ Close tunnel when not connected test successful");
     }
 
+    #[tokio::test]
+    async fn test_service_tunnels_start_closed() {
+        println!("🧪 Test: Service tunnels start closed");
+
+        let mut manager = TunnelManager::new()
+            .await
+            .expect("Failed to create tunnel manager");
+
+        let tunnels = manager.tunnels().await;
+        assert_eq!(tunnels.len(), 1, "Only the API's tunnel without services");
+        assert_eq!(tunnels[0].service, TunnelService::Api);
+        assert!(!tunnels[0].connected);
+
+        // Nothing is configured, so nothing is started
+        let failed = manager.create_service_tunnels().await;
+        assert!(failed.is_empty());
+        assert!(manager.restart_crashed_services().await.is_empty());
+
+        println!("✅ Service tunnels start closed test successful");
+    }
+
+    #[test]
+    fn test_cloudflared_args() {
+        println!("🧪 Test: cloudflared args");
+
+        assert!(cloudflared_args(&CloudflaredOptions::default()).is_empty());
+
+        let options = CloudflaredOptions {
+            region: Some("us".to_string()),
+            edge_ip_version: None,
+            protocol: Some("http2".to_string()),
+        };
+        assert_eq!(
+            cloudflared_args(&options),
+            vec!["--region", "us", "--protocol", "http2"]
+        );
+
+        println!("✅ cloudflared args test successful");
+    }
+
+    #[tokio::test]
+    async fn test_public_url_probe() {
+        println!("🧪 Test: Public URL probe");
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/health"))
+            .and(header("CF-Access-Client-Id", "client-id"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        let client = reqwest::Client::new();
+
+        let round_trip = probe_round_trip(
+            &client,
+            &server.uri(),
+            &[("CF-Access-Client-Id", "client-id")],
+        )
+        .await;
+        assert!(round_trip.is_ok());
+        let check = PublicCheck::new(&server.uri(), &round_trip);
+        assert!(check.reachable());
+        assert!(check.round_trip_ms.is_some());
+
+        // Reached the edge, but it did not get to MindLink
+        let refused = probe_round_trip(&client, &server.uri(), &[]).await;
+        assert_eq!(refused, Err(ReachFailure::Routing(404)));
+        assert!(!PublicCheck::new(&server.uri(), &refused).reachable());
+
+        let unresolved = probe_round_trip(&client, "http://mindlink-probe.invalid", &[]).await;
+        assert!(
+            matches!(unresolved, Err(ReachFailure::Dns(_))),
+            "Expected a DNS failure, got {:?}",
+            unresolved
+        );
+
+        println!("✅ Public URL probe test successful");
+    }
+
+    #[tokio::test]
+    async fn test_closed_tunnel_is_not_restarted() {
+        println!("🧪 Test: Closed tunnel is not restarted");
+
+        let mut manager = TunnelManager::new()
+            .await
+            .expect("Failed to create tunnel manager");
+
+        // Nothing was started, so nothing can have crashed
+        let restarted = manager.restart_if_crashed().await.unwrap();
+        assert_eq!(restarted, None);
+
+        manager.close_tunnel().await.unwrap();
+        let restarted = manager.restart_if_crashed().await.unwrap();
+        assert_eq!(restarted, None);
+        assert!(!manager.is_connected().await);
+
+        println!("✅ Closed tunnel is not restarted test successful");
+    }
+
     #[tokio::test]
     async fn test_recreate_tunnel_when_not_connected() {
         println!("🧪 Test: Recreate tunnel when not connected");
@@ -272,6 +437,62 @@ mod tunnel_manager_tests {
         println!("✅ Tunnel URL regex parsing successful");
     }
 
+    #[test]
+    fn test_quick_tunnel_url_from_output() {
+        println!("🧪 Test: Quick tunnel URL from cloudflared output");
+
+        let announced =
+            "2024-05-01T10:00:00Z INF |  https://sharp-river-fox-42.trycloudflare.com  |";
+        assert_eq!(
+            parse_quick_tunnel_url(announced).as_deref(),
+            Some("https://sharp-river-fox-42.trycloudflare.com")
+        );
+
+        let api_error = "2024-05-01T10:00:00Z ERR Error unmarshaling QuickTunnel response: \
+                         Post \"https://api.trycloudflare.com/tunnel\": dial tcp: i/o timeout";
+        assert!(parse_quick_tunnel_url(api_error).is_none());
+        assert!(parse_quick_tunnel_url(
+            "2024-05-01T10:00:00Z INF Requesting new quick Tunnel on trycloudflare.com..."
+        )
+        .is_none());
+
+        println!("✅ Quick tunnel URL from cloudflared output successful");
+    }
+
+    #[test]
+    fn test_ngrok_log_parsing() {
+        println!("🧪 Test: ngrok log parsing");
+
+        let started = r#"{"addr":"http://localhost:3001","lvl":"info","msg":"started tunnel","name":"command_line","obj":"tunnels","t":"2024-05-01T10:00:00Z","url":"https://1a2b-203-0-113-7.ngrok-free.app"}"#;
+        assert_eq!(
+            parse_ngrok_log(started),
+            Some(NgrokEvent::Started(
+                "https://1a2b-203-0-113-7.ngrok-free.app".to_string()
+            ))
+        );
+
+        let failed = r#"{"err":"authentication failed: The authtoken you specified is invalid","lvl":"eror","msg":"failed to reconnect session","obj":"tunnels.session"}"#;
+        assert_eq!(
+            parse_ngrok_log(failed),
+            Some(NgrokEvent::Failed(
+                "authentication failed: The authtoken you specified is invalid".to_string()
+            ))
+        );
+
+        assert_eq!(
+            parse_ngrok_log("ERROR:  failed to start tunnel: domain not reserved"),
+            Some(NgrokEvent::Failed(
+                "failed to start tunnel: domain not reserved".to_string()
+            ))
+        );
+
+        let info = r#"{"lvl":"info","msg":"client session established","obj":"tunnels.session"}"#;
+        assert_eq!(parse_ngrok_log(info), None);
+        assert_eq!(parse_ngrok_log("not json"), None);
+
+        println!("✅ ngrok log parsing successful");
+    }
+
     #[tokio::test]
     async fn test_binary_dependency_errors() {
         println!("🧪 Test: Binary dependency errors");
@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod tunnel_pids_tests {
+    use crate::tunnel_pids::{forget, reap_orphans, record};
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_pid_files() {
+        println!("🧪 Test: Tunnel PID files");
+
+        let dir = TempDir::new().unwrap();
+        let run_dir = dir.path().join("run");
+        record(&run_dir, "cloudflared-bifrost", 4242).await.unwrap();
+        let pid_file = run_dir.join("cloudflared-bifrost.pid");
+        assert_eq!(std::fs::read_to_string(&pid_file).unwrap(), "4242");
+
+        forget(&run_dir, "cloudflared-bifrost").await.unwrap();
+        assert!(!pid_file.exists());
+        // Closing a tunnel twice is fine
+        forget(&run_dir, "cloudflared-bifrost").await.unwrap();
+
+        println!("✅ Tunnel PID files successful");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_reap_orphans() {
+        println!("🧪 Test: Reap orphaned tunnel processes");
+
+        let dir = TempDir::new().unwrap();
+        // Stands in for a cloudflared that outlived the app
+        let cloudflared = dir.path().join("cloudflared");
+        std::fs::copy("/bin/sleep", &cloudflared).unwrap();
+        let mut orphan = tokio::process::Command::new(&cloudflared)
+            .arg("30")
+            .spawn()
+            .unwrap();
+        let orphan_pid = orphan.id().unwrap();
+        // Reaped as soon as it exits, so it does not linger as a zombie
+        let orphan_exit = tokio::spawn(async move { orphan.wait().await });
+
+        // A PID that now belongs to another program
+        let mut other = tokio::process::Command::new("sleep")
+            .arg("30")
+            .kill_on_drop(true)
+            .spawn()
+            .unwrap();
+        let other_pid = other.id().unwrap();
+
+        let run_dir = dir.path().join("run");
+        record(&run_dir, "cloudflared", orphan_pid).await.unwrap();
+        record(&run_dir, "ngrok", other_pid).await.unwrap();
+        std::fs::write(run_dir.join("cloudflared-bifrost.pid"), "not a pid").unwrap();
+
+        assert_eq!(reap_orphans(&run_dir).await, vec![orphan_pid]);
+        assert!(orphan_exit.await.unwrap().is_ok());
+        assert!(
+            other.try_wait().unwrap().is_none(),
+            "Other programs are left alone"
+        );
+        assert_eq!(std::fs::read_dir(&run_dir).unwrap().count(), 0);
+
+        // Nothing left to do on the next start
+        assert!(reap_orphans(&run_dir).await.is_empty());
+        assert!(reap_orphans(&dir.path().join("missing")).await.is_empty());
+
+        println!("✅ Reap orphaned tunnel processes successful");
+    }
+}
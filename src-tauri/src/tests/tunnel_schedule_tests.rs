@@ -0,0 +1,135 @@
+#[cfg(test)]
+mod tunnel_schedule_tests {
+    use crate::managers::config_manager::{TunnelConfig, TunnelScheduleConfig, TunnelWindow};
+    use crate::managers::tunnel_manager::TunnelManager;
+    use crate::tunnel_schedule::{Schedule, Window};
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    fn window(days: &[&str], start: &str, end: &str) -> TunnelWindow {
+        TunnelWindow {
+            days: days.iter().map(|day| day.to_string()).collect(),
+            start: start.to_string(),
+            end: end.to_string(),
+        }
+    }
+
+    fn scheduled(windows: Vec<TunnelWindow>) -> TunnelScheduleConfig {
+        TunnelScheduleConfig {
+            enabled: true,
+            windows,
+        }
+    }
+
+    /// A time in the week of Monday, 2024-06-03
+    fn at(day: u32, time: &str) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 6, day)
+            .unwrap()
+            .and_time(time.parse().unwrap())
+    }
+
+    #[test]
+    fn test_parse_windows() {
+        println!("🧪 Test: Parse tunnel schedule windows");
+
+        assert!(Window::parse(&window(&["Mon", "fri"], "09:00", "17:00")).is_ok());
+        assert!(Window::parse(&window(&[], "22:00", "06:00")).is_ok());
+        assert!(Window::parse(&window(&["weekdays"], "09:00", "17:00")).is_err());
+        assert!(Window::parse(&window(&[], "9am", "17:00")).is_err());
+        assert!(Window::parse(&window(&[], "09:00", "09:00")).is_err());
+
+        // Not scheduled unless enabled with windows
+        let mut config = scheduled(vec![window(&[], "09:00", "17:00")]);
+        assert!(Schedule::from_config(&config).is_some());
+        config.enabled = false;
+        assert!(Schedule::from_config(&config).is_none());
+        assert!(Schedule::from_config(&scheduled(Vec::new())).is_none());
+
+        println!("✅ Parse tunnel schedule windows successful");
+    }
+
+    #[test]
+    fn test_work_hours() {
+        println!("🧪 Test: Tunnel open during work hours");
+
+        let weekdays = ["mon", "tue", "wed", "thu", "fri"];
+        let schedule =
+            Schedule::from_config(&scheduled(vec![window(&weekdays, "09:00", "17:00")])).unwrap();
+
+        assert!(!schedule.is_open(at(3, "08:59:59")));
+        assert!(schedule.is_open(at(3, "09:00:00")));
+        assert!(!schedule.is_open(at(3, "17:00:00")));
+        assert!(!schedule.is_open(at(8, "12:00:00")), "Saturday");
+
+        let status = schedule.status(at(3, "10:30:00"));
+        assert!(status.open);
+        assert_eq!(status.until, Some(at(3, "17:00:00")));
+        assert_eq!(
+            status.describe(at(3, "10:30:00")),
+            "Tunnel open until 17:00"
+        );
+
+        // Friday evening waits for Monday
+        let status = schedule.status(at(7, "18:00:00"));
+        assert!(!status.open);
+        assert_eq!(status.until, Some(at(10, "09:00:00")));
+        assert_eq!(
+            status.describe(at(7, "18:00:00")),
+            "Tunnel closed until Mon 09:00"
+        );
+
+        println!("✅ Tunnel open during work hours successful");
+    }
+
+    #[test]
+    fn test_overnight_window() {
+        println!("🧪 Test: Tunnel window past midnight");
+
+        let schedule =
+            Schedule::from_config(&scheduled(vec![window(&["fri"], "22:00", "02:00")])).unwrap();
+        assert!(schedule.is_open(at(7, "23:00:00")));
+        assert!(schedule.is_open(at(8, "01:59:00")), "Saturday morning");
+        assert!(!schedule.is_open(at(8, "02:00:00")));
+        assert!(!schedule.is_open(at(7, "01:00:00")), "Thursday's night");
+        assert_eq!(
+            schedule.status(at(7, "23:00:00")).until,
+            Some(at(8, "02:00:00"))
+        );
+
+        // Adjacent windows are one stretch
+        let schedule = Schedule::from_config(&scheduled(vec![
+            window(&[], "08:00", "12:00"),
+            window(&[], "12:00", "18:00"),
+        ]))
+        .unwrap();
+        assert_eq!(
+            schedule.status(at(4, "09:00:00")).until,
+            Some(at(4, "18:00:00"))
+        );
+
+        println!("✅ Tunnel window past midnight successful");
+    }
+
+    #[tokio::test]
+    async fn test_tunnel_kept_closed_outside_windows() {
+        println!("🧪 Test: Tunnel kept closed outside its windows");
+
+        let mut manager = TunnelManager::new()
+            .await
+            .expect("Failed to create tunnel manager");
+        let mut config: TunnelConfig =
+            serde_json::from_value(serde_json::json!({ "enabled": true, "tunnel_type": "quick" }))
+                .unwrap();
+        config.schedule = scheduled(vec![window(&["mon"], "09:00", "17:00")]);
+        manager.configure(&config).await;
+
+        let sunday = at(9, "12:00:00");
+        assert_eq!(manager.create_scheduled_tunnel(sunday).await.unwrap(), None);
+        assert!(!manager.is_connected().await);
+        assert!(manager.closed_by_schedule(sunday).await);
+        // Nothing to do until the window begins
+        assert_eq!(manager.apply_schedule(sunday).await.unwrap(), None);
+        assert!(!manager.schedule_status(sunday).unwrap().open);
+
+        println!("✅ Tunnel kept closed outside its windows successful");
+    }
+}
@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tunnel_tokens_tests {
+    use crate::tunnel_tokens::{TunnelTokens, ROTATION_LEAD_MINUTES};
+    use chrono::{Duration, Utc};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_rotation_schedule() {
+        println!("🧪 Test: Tunnel tokens are rotated ahead of expiry");
+
+        let now = Utc::now();
+        let mut tokens = TunnelTokens::default();
+        assert!(!tokens.issued());
+        assert!(tokens.needs_rotation(now));
+        assert!(tokens.current(now).is_none());
+
+        let token = tokens.rotate(Duration::hours(24), false, now);
+        assert!(token.token.starts_with("sk-mindlink-tunnel-"));
+        assert_eq!(token.expires_at, now + Duration::hours(24));
+        assert!(tokens.issued());
+        assert_eq!(tokens.current(now), Some(&token));
+        assert!(!tokens.needs_rotation(now));
+
+        let due = token.expires_at - Duration::minutes(ROTATION_LEAD_MINUTES);
+        assert!(!tokens.needs_rotation(due - Duration::minutes(1)));
+        assert!(tokens.needs_rotation(due));
+        assert!(tokens.current(token.expires_at).is_none());
+
+        println!("✅ Tunnel tokens are rotated ahead of expiry successful");
+    }
+
+    #[test]
+    fn test_replaced_tokens() {
+        println!("🧪 Test: Replaced tunnel tokens");
+
+        let now = Utc::now();
+        let mut tokens = TunnelTokens::default();
+        let first = tokens.rotate(Duration::hours(1), false, now);
+
+        // Scheduled rotations leave the old token valid until it expires
+        let later = now + Duration::minutes(50);
+        let second = tokens.rotate(Duration::hours(1), false, later);
+        assert_ne!(first.token, second.token);
+        assert_eq!(tokens.current(later), Some(&second));
+        assert_eq!(tokens.previous(later), Some(&first));
+        assert!(tokens.previous(first.expires_at).is_none());
+
+        // Rotating by hand revokes it
+        tokens.rotate(Duration::hours(1), true, later);
+        assert!(tokens.previous(later).is_none());
+
+        println!("✅ Replaced tunnel tokens successful");
+    }
+
+    #[tokio::test]
+    async fn test_tokens_persist() {
+        println!("🧪 Test: Tunnel tokens persist");
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("tunnel_tokens.json");
+        let missing = TunnelTokens::load(&path).await.unwrap();
+        assert!(!missing.issued());
+
+        let now = Utc::now();
+        let mut tokens = TunnelTokens::default();
+        let token = tokens.rotate(Duration::hours(24), false, now);
+        tokens.save(&path).await.unwrap();
+
+        let loaded = TunnelTokens::load(&path).await.unwrap();
+        assert_eq!(loaded.current(now), Some(&token));
+
+        println!("✅ Tunnel tokens persist successful");
+    }
+}
@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod tunnel_webhooks_tests {
+    use crate::managers::config_manager::TunnelWebhook;
+    use crate::managers::tunnel_manager::TunnelUrlChanged;
+    use crate::tunnel_webhooks::{
+        deliver, signature, webhook_client, EVENT_HEADER, SIGNATURE_HEADER,
+    };
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+    fn change() -> TunnelUrlChanged {
+        TunnelUrlChanged {
+            url: Some("https://new-words.trycloudflare.com".to_string()),
+            previous_url: Some("https://old-words.trycloudflare.com".to_string()),
+            changed_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_webhook_signature() {
+        println!("🧪 Test: Webhook payload signature");
+
+        // Well-known HMAC-SHA256 test vector
+        assert_eq!(
+            signature("key", b"The quick brown fox jumps over the lazy dog"),
+            "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+
+        println!("✅ Webhook payload signature successful");
+    }
+
+    #[tokio::test]
+    async fn test_webhook_delivery() {
+        println!("🧪 Test: Tunnel URL change delivered to a webhook");
+
+        let server = MockServer::start().await;
+        let change = change();
+        // Signed over the body exactly as sent
+        let signed = |request: &Request| {
+            let expected = signature("hook-secret", &request.body);
+            request
+                .headers
+                .get(SIGNATURE_HEADER)
+                .is_some_and(|value| value.as_bytes() == expected.as_bytes())
+        };
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(header(EVENT_HEADER, "tunnel-url-changed"))
+            .and(signed)
+            .and(body_partial_json(json!({
+                "event": "tunnel-url-changed",
+                "url": "https://new-words.trycloudflare.com",
+                "previous_url": "https://old-words.trycloudflare.com",
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let webhook = TunnelWebhook {
+            url: format!("{}/hook", server.uri()),
+            secret: Some("hook-secret".to_string()),
+        };
+        deliver(&webhook_client(), &webhook, &change).await.unwrap();
+
+        println!("✅ Tunnel URL change delivered to a webhook successful");
+    }
+
+    #[tokio::test]
+    async fn test_webhook_retries() {
+        println!("🧪 Test: Webhook retried after server errors only");
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/flaky"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/refusing"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = webhook_client();
+        let flaky = TunnelWebhook {
+            url: format!("{}/flaky", server.uri()),
+            secret: None,
+        };
+        deliver(&client, &flaky, &change()).await.unwrap();
+
+        let refusing = TunnelWebhook {
+            url: format!("{}/refusing", server.uri()),
+            secret: None,
+        };
+        assert!(deliver(&client, &refusing, &change()).await.is_err());
+
+        println!("✅ Webhook retried after server errors only successful");
+    }
+}
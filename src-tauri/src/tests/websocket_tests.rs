@@ -0,0 +1,159 @@
+#[cfg(test)]
+mod websocket_tests {
+    use crate::websocket::{
+        completion_request, run_request, ClientMessage, ServerMessage, SseParser, COMPLETIONS_PATH,
+    };
+    use axum::{
+        body::Body,
+        http::{header, HeaderMap, HeaderValue, StatusCode},
+        response::{IntoResponse, Response},
+        routing::post,
+        Router,
+    };
+    use serde_json::{json, Value};
+    use tokio::sync::mpsc;
+
+    async fn replies(route: Router, body: Value) -> Vec<ServerMessage> {
+        let (request, stream) = completion_request(&HeaderMap::new(), body);
+        let (tx, mut rx) = mpsc::channel(16);
+        run_request(route, request, stream, "r1".to_string(), tx).await;
+
+        let mut messages = Vec::new();
+        while let Some(message) = rx.recv().await {
+            messages.push(message);
+        }
+        messages
+    }
+
+    #[test]
+    fn test_sse_events_are_reassembled_across_chunks() {
+        println!("🧪 Test: SSE parsing across chunk boundaries");
+
+        let mut parser = SseParser::default();
+        let body = "data: {\"n\":1}\n\n: comment\n\ndata: {\"text\":\"héllo\"}\n\ndata: [DONE]\n\n";
+        let bytes = body.as_bytes();
+        // Splits inside the `é` as well as inside the framing
+        let split = body.find('é').unwrap() + 1;
+
+        let mut payloads = parser.push(&bytes[..5]);
+        payloads.extend(parser.push(&bytes[5..split]));
+        payloads.extend(parser.push(&bytes[split..]));
+        assert_eq!(
+            payloads,
+            vec!["{\"n\":1}", "{\"text\":\"héllo\"}", "[DONE]"]
+        );
+        assert!(parser.push(b"data: partial").is_empty());
+
+        println!("✅ SSE parsing successful");
+    }
+
+    #[test]
+    fn test_socket_messages_become_completion_requests() {
+        println!("🧪 Test: WebSocket messages as completion requests");
+
+        let message: ClientMessage = serde_json::from_value(json!({
+            "type": "request",
+            "id": "r1",
+            "body": { "model": "gpt-5", "messages": [] }
+        }))
+        .unwrap();
+        let ClientMessage::Request { id, body } = message else {
+            panic!("expected a request");
+        };
+        assert_eq!(id, "r1");
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::AUTHORIZATION,
+            HeaderValue::from_static("Bearer app-key"),
+        );
+        headers.insert(header::UPGRADE, HeaderValue::from_static("websocket"));
+        headers.insert("sec-websocket-key", HeaderValue::from_static("abc"));
+
+        let (request, stream) = completion_request(&headers, body);
+        assert!(stream, "stream defaults to true on a socket");
+        assert_eq!(request.uri().path(), COMPLETIONS_PATH);
+        assert_eq!(request.headers()[header::AUTHORIZATION], "Bearer app-key");
+        assert_eq!(request.headers()[header::CONTENT_TYPE], "application/json");
+        assert!(request.headers().get(header::UPGRADE).is_none());
+        assert!(request.headers().get("sec-websocket-key").is_none());
+
+        let (_, stream) = completion_request(&headers, json!({ "stream": false }));
+        assert!(!stream);
+
+        let cancel: ClientMessage =
+            serde_json::from_str(r#"{"type": "cancel", "id": "r1"}"#).unwrap();
+        assert!(matches!(cancel, ClientMessage::Cancel { id } if id == "r1"));
+
+        println!("✅ WebSocket request mapping successful");
+    }
+
+    #[tokio::test]
+    async fn test_completion_responses_become_socket_messages() {
+        println!("🧪 Test: Completion responses as WebSocket messages");
+
+        let streaming = Router::new().route(
+            COMPLETIONS_PATH,
+            post(|| async {
+                let chunks = ["data: {\"choices\":[]}\n", "\ndata: [DO", "NE]\n\n"]
+                    .map(|chunk| Ok::<_, std::convert::Infallible>(chunk.to_string()));
+                Response::new(Body::from_stream(futures_util::stream::iter(chunks)))
+            }),
+        );
+        assert_eq!(
+            replies(streaming, json!({})).await,
+            vec![
+                ServerMessage::Chunk {
+                    id: "r1".to_string(),
+                    data: json!({ "choices": [] })
+                },
+                ServerMessage::Done {
+                    id: "r1".to_string()
+                },
+            ]
+        );
+
+        let complete = Router::new().route(
+            COMPLETIONS_PATH,
+            post(|| async { axum::Json(json!({ "object": "chat.completion" })) }),
+        );
+        let messages = replies(complete, json!({ "stream": false })).await;
+        assert_eq!(messages.len(), 2);
+        assert!(
+            matches!(&messages[0], ServerMessage::Response { data, .. } if data["object"] == "chat.completion")
+        );
+
+        let saturated = Router::new().route(
+            COMPLETIONS_PATH,
+            post(|| async {
+                (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(header::RETRY_AFTER, "7")],
+                    axum::Json(json!({ "error": { "message": "busy", "type": "requests" } })),
+                )
+                    .into_response()
+            }),
+        );
+        assert_eq!(
+            replies(saturated, json!({})).await,
+            vec![ServerMessage::Error {
+                id: Some("r1".to_string()),
+                status: 429,
+                error: json!({ "message": "busy", "type": "requests" }),
+                retry_after_secs: Some(7),
+            }]
+        );
+
+        let truncated = Router::new().route(
+            COMPLETIONS_PATH,
+            post(|| async { "data: {\"choices\":[]}\n\n" }),
+        );
+        let messages = replies(truncated, json!({})).await;
+        assert!(matches!(
+            messages.last(),
+            Some(ServerMessage::Error { status: 502, .. })
+        ));
+
+        println!("✅ WebSocket response mapping successful");
+    }
+}
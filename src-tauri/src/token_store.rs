@@ -0,0 +1,159 @@
+// Storage of OAuth tokens in the OS keyring
+//
+// The tokens of an account are kept in the platform keychain (Keychain on
+// macOS, Credential Manager on Windows, the Secret Service on Linux) under the
+// service `MindLink`, one entry per token file. The file itself then only
+// records that its tokens are in the keyring, so accounts can still be listed
+// by their files. Where the keyring cannot take the tokens, e.g. on a headless
+// Linux without a Secret Service or when they exceed the size limit of the
+// Windows Credential Manager, they are written to the file encrypted with the
+// master key of `secret_box`, readable by the user only.
+//
+// A token file from before the keyring, holding the tokens themselves, is
+// moved into the keyring the first time it is read, or encrypted where there
+// is no keyring.
+
+use crate::secret_box;
+use crate::{log_info, log_warn};
+use anyhow::{anyhow, Result};
+use std::path::Path;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+/// Keyring service the entries are stored under
+const SERVICE: &str = "MindLink";
+
+/// Content of a token file whose tokens are in the keyring
+const IN_KEYRING: &str = "{\"stored_in\":\"keyring\"}\n";
+
+fn entry(path: &Path) -> keyring::Result<keyring::Entry> {
+    keyring::Entry::new(SERVICE, &path.to_string_lossy())
+}
+
+/// Run a keyring operation off the async runtime; keyring backends block
+async fn with_entry<T: Send + 'static>(
+    path: &Path,
+    operation: impl FnOnce(keyring::Entry) -> keyring::Result<T> + Send + 'static,
+) -> keyring::Result<T> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || operation(entry(&path)?))
+        .await
+        .map_err(|e| keyring::Error::PlatformFailure(Box::new(e)))?
+}
+
+/// The tokens stored for `path`, or `None` when there are none
+pub async fn read(path: &Path) -> Result<Option<String>> {
+    let content = match fs::read_to_string(path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    if content != IN_KEYRING {
+        let secret = secret_box::open(&content)
+            .await
+            .map_err(|e| anyhow!("Cannot read the tokens of {}: {}", path.display(), e))?;
+        let value = secret.clone();
+        if with_entry(path, move |entry| entry.set_password(&value))
+            .await
+            .is_ok()
+        {
+            write_file(path, IN_KEYRING).await?;
+            log_info!(
+                "TokenStore",
+                &format!("Moved the tokens of {} into the OS keyring", path.display())
+            );
+        } else if !secret_box::is_encrypted(&content) {
+            let sealed = secret_box::seal(&secret).await;
+            if sealed != content {
+                write_file(path, &sealed).await?;
+            }
+        }
+        return Ok(Some(secret));
+    }
+
+    match with_entry(path, |entry| entry.get_password()).await {
+        Ok(secret) => Ok(Some(secret)),
+        // Deleted from the keyring outside MindLink
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(anyhow!(
+            "The tokens of {} are in the OS keyring, which cannot be read: {}",
+            path.display(),
+            e
+        )),
+    }
+}
+
+/// Store `secret` as the tokens of `path`
+pub async fn write(path: &Path, secret: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).await?;
+    }
+
+    let value = secret.to_string();
+    match with_entry(path, move |entry| entry.set_password(&value)).await {
+        Ok(()) => write_file(path, IN_KEYRING).await,
+        Err(e) => {
+            log_warn!(
+                "TokenStore",
+                &format!(
+                    "OS keyring unavailable ({}), storing the tokens of {} in the file",
+                    e,
+                    path.display()
+                )
+            );
+            write_file(path, &secret_box::seal(secret).await).await
+        },
+    }
+}
+
+/// Delete the tokens of `path`. Returns whether there were any.
+pub async fn remove(path: &Path) -> Result<bool> {
+    let content = match fs::read_to_string(path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+
+    if content == IN_KEYRING {
+        match with_entry(path, |entry| entry.delete_credential()).await {
+            Ok(()) | Err(keyring::Error::NoEntry) => {},
+            Err(e) => log_warn!(
+                "TokenStore",
+                &format!(
+                    "Failed to delete the keyring entry of {}: {}",
+                    path.display(),
+                    e
+                )
+            ),
+        }
+    }
+    fs::remove_file(path).await?;
+    Ok(true)
+}
+
+async fn write_file(path: &Path, content: &str) -> Result<()> {
+    write_private(path, content.as_bytes()).await
+}
+
+/// Write `content` to `path`, readable by the user only. A new file is
+/// created that way and an existing one is restricted before it is
+/// truncated, so the content is never readable by others.
+pub async fn write_private(path: &Path, content: &[u8]) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(path).await?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))
+            .await?;
+    }
+    file.set_len(0).await?;
+    file.write_all(content).await?;
+    file.flush().await?;
+    Ok(())
+}
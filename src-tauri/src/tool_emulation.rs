@@ -0,0 +1,230 @@
+// Function-calling emulation for backends without native tool support
+//
+// Tool schemas are injected into the prompt as a system message, the model is
+// asked to answer with a JSON tool-call envelope, and that envelope is parsed
+// back into OpenAI-shaped `tool_calls`.
+
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::managers::config_manager::ToolCallStrictness;
+use crate::managers::server_manager::{FunctionCall, Message, ToolCall};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// Emulation settings resolved for a single request
+#[derive(Debug, Clone)]
+pub struct ToolEmulation {
+    pub tools: Vec<Value>,
+    pub tool_choice: Option<Value>,
+    pub strictness: ToolCallStrictness,
+}
+
+/// Result of interpreting the model output
+#[derive(Debug, Clone)]
+pub enum EmulatedOutput {
+    Text(String),
+    ToolCalls(Vec<ToolCall>),
+}
+
+impl ToolEmulation {
+    /// Names of the functions declared in the request
+    pub fn tool_names(&self) -> Vec<&str> {
+        self.tools
+            .iter()
+            .filter_map(|tool| tool.get("function")?.get("name")?.as_str())
+            .collect()
+    }
+
+    /// Whether the request forces a tool call (`required` or a named function)
+    fn requires_tool_call(&self) -> bool {
+        match &self.tool_choice {
+            Some(Value::String(choice)) => choice == "required",
+            Some(Value::Object(_)) => true,
+            _ => false,
+        }
+    }
+
+    /// Build the system prompt describing the tools and the answer format
+    pub fn system_prompt(&self) -> String {
+        let schemas: Vec<Value> = self
+            .tools
+            .iter()
+            .filter_map(|tool| tool.get("function").cloned())
+            .collect();
+
+        let choice_instruction = match &self.tool_choice {
+            Some(Value::String(choice)) if choice == "required" => {
+                "You MUST call at least one function.".to_string()
+            },
+            Some(Value::Object(choice)) => format!(
+                "You MUST call the function `{}`.",
+                choice
+                    .get("function")
+                    .and_then(|f| f.get("name"))
+                    .and_then(|n| n.as_str())
+                    .unwrap_or_default()
+            ),
+            _ => "Call a function only when it is needed to answer; otherwise reply normally."
+                .to_string(),
+        };
+
+        format!(
+            "You have access to the following functions, described as JSON schema:\n{}\n\n\
+             To call functions, reply with ONLY a JSON object of the form \
+             {{\"tool_calls\": [{{\"name\": \"<function name>\", \"arguments\": {{...}}}}]}} \
+             and no other text. {}",
+            serde_json::to_string_pretty(&schemas).unwrap_or_default(),
+            choice_instruction
+        )
+    }
+
+    /// Inject the tool prompt ahead of the conversation
+    pub fn inject_prompt(&self, messages: &mut Vec<Message>) {
+        messages.insert(
+            0,
+            Message {
+                role: "system".to_string(),
+                content: self.system_prompt(),
+                tool_calls: None,
+                tool_call_id: None,
+                name: None,
+                reasoning_content: None,
+            },
+        );
+    }
+
+    /// Interpret the model output, honouring the configured strictness
+    pub fn parse_output(&self, content: &str) -> MindLinkResult<EmulatedOutput> {
+        let strict = self.strictness == ToolCallStrictness::Strict;
+        let declared = self.tool_names();
+
+        let candidate = if strict {
+            strip_code_fence(content.trim())
+        } else {
+            extract_json_object(content)
+        };
+
+        let envelope = candidate
+            .and_then(|json| serde_json::from_str::<Value>(json).ok())
+            .filter(|value| value.get("tool_calls").is_some());
+
+        let Some(envelope) = envelope else {
+            if strict && (content.contains("\"tool_calls\"") || self.requires_tool_call()) {
+                return Err(invalid_output("model did not return a valid tool call"));
+            }
+            return Ok(EmulatedOutput::Text(content.to_string()));
+        };
+
+        let entries = envelope
+            .get("tool_calls")
+            .and_then(|calls| calls.as_array())
+            .cloned()
+            .unwrap_or_default();
+
+        let mut tool_calls = Vec::new();
+        for entry in entries {
+            let name = entry.get("name").and_then(|n| n.as_str());
+            let arguments = entry.get("arguments").cloned().unwrap_or(Value::Null);
+
+            let arguments = match arguments {
+                Value::Object(_) => arguments,
+                // Some models double-encode the arguments
+                Value::String(encoded) if !strict => {
+                    match serde_json::from_str::<Value>(&encoded) {
+                        Ok(value @ Value::Object(_)) => value,
+                        _ => continue,
+                    }
+                },
+                Value::Null if !strict => Value::Object(serde_json::Map::new()),
+                _ if strict => return Err(invalid_output("tool call arguments must be an object")),
+                _ => continue,
+            };
+
+            match name {
+                Some(name) if declared.contains(&name) => tool_calls.push(ToolCall {
+                    id: format!("call_{}", Uuid::new_v4().simple()),
+                    call_type: "function".to_string(),
+                    function: FunctionCall {
+                        name: name.to_string(),
+                        arguments: arguments.to_string(),
+                    },
+                }),
+                Some(name) if strict => {
+                    return Err(invalid_output(&format!("unknown function `{}`", name)))
+                },
+                None if strict => return Err(invalid_output("tool call is missing a name")),
+                _ => {},
+            }
+        }
+
+        if tool_calls.is_empty() {
+            if strict {
+                return Err(invalid_output("model returned no usable tool calls"));
+            }
+            return Ok(EmulatedOutput::Text(content.to_string()));
+        }
+
+        Ok(EmulatedOutput::ToolCalls(tool_calls))
+    }
+}
+
+/// Rewrite tool-related history into plain text the backend understands:
+/// assistant tool calls become the JSON envelope, tool results become user turns
+pub fn flatten_tool_messages(messages: &mut [Message]) {
+    for message in messages.iter_mut() {
+        if let Some(tool_calls) = message.tool_calls.take() {
+            let calls: Vec<Value> = tool_calls
+                .iter()
+                .map(|call| {
+                    serde_json::json!({
+                        "name": call.function.name,
+                        "arguments": serde_json::from_str::<Value>(&call.function.arguments)
+                            .unwrap_or(Value::String(call.function.arguments.clone())),
+                    })
+                })
+                .collect();
+            let envelope = serde_json::json!({ "tool_calls": calls }).to_string();
+            message.content = if message.content.is_empty() {
+                envelope
+            } else {
+                format!("{}\n{}", message.content, envelope)
+            };
+        }
+
+        if message.role == "tool" {
+            let call_id = message.tool_call_id.take().unwrap_or_default();
+            message.role = "user".to_string();
+            message.content = format!("Result of tool call {}:\n{}", call_id, message.content);
+        } else if message.role == "function" {
+            // Legacy function results name the function instead of a call
+            let name = message.name.take().unwrap_or_default();
+            message.role = "user".to_string();
+            message.content = format!("Result of function {}:\n{}", name, message.content);
+        }
+    }
+}
+
+fn invalid_output(reason: &str) -> MindLinkError {
+    MindLinkError::Internal {
+        message: format!("Invalid emulated tool call: {}", reason),
+        component: Some("ToolEmulation".to_string()),
+        source: None,
+    }
+}
+
+/// Accept the JSON either bare or wrapped in a single markdown code fence
+fn strip_code_fence(content: &str) -> Option<&str> {
+    match content.strip_prefix("```") {
+        Some(rest) => {
+            let rest = rest.strip_prefix("json").unwrap_or(rest);
+            rest.strip_suffix("```").map(str::trim)
+        },
+        None => Some(content),
+    }
+}
+
+/// Find the outermost JSON object in free-form model output
+fn extract_json_object(content: &str) -> Option<&str> {
+    let start = content.find('{')?;
+    let end = content.rfind('}')?;
+    (end > start).then(|| &content[start..=end])
+}
@@ -0,0 +1,137 @@
+// Connection and egress limits of the tunnel
+//
+// Whoever has the tunnel's URL can keep the machine busy and use up its
+// bandwidth, so the tunnel may be capped at a number of requests served at
+// once and at the bytes it sends per calendar month (UTC). What was sent in
+// the current month is kept in `~/.mindlink/tunnel_egress.json`, a
+// `json_ledger`, so that restarting MindLink does not lift the cap. The middleware enforcing both
+// lives in `middleware::tunnel_limits`.
+
+use crate::error::MindLinkResult;
+use crate::json_ledger::{self, JsonLedger};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum TunnelLimit {
+    Connections,
+    MonthlyEgress,
+}
+
+/// Traffic through the tunnel is nearing a limit, or reached it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+pub struct TunnelLimitWarning {
+    pub limit: TunnelLimit,
+    /// Connections open, or bytes sent this month
+    #[ts(type = "number")]
+    pub used: u64,
+    #[ts(type = "number")]
+    pub max: u64,
+    /// Whether requests through the tunnel are being refused
+    pub reached: bool,
+    pub at: DateTime<Utc>,
+}
+
+impl TunnelLimitWarning {
+    /// What the user is told
+    pub fn message(&self) -> String {
+        let mb = |bytes: u64| bytes / (1024 * 1024);
+        match (self.limit, self.reached) {
+            (TunnelLimit::Connections, false) => format!(
+                "The tunnel is serving {} of at most {} requests at once",
+                self.used, self.max
+            ),
+            (TunnelLimit::Connections, true) => format!(
+                "The tunnel is serving its limit of {} requests at once; further ones are refused",
+                self.max
+            ),
+            (TunnelLimit::MonthlyEgress, false) => format!(
+                "{} MB of this month's {} MB of tunnel traffic are used",
+                mb(self.used),
+                mb(self.max)
+            ),
+            (TunnelLimit::MonthlyEgress, true) => format!(
+                "This month's {} MB of tunnel traffic are used up; requests through the tunnel \
+                 are refused until the month is over",
+                mb(self.max)
+            ),
+        }
+    }
+}
+
+/// Names the month `now` falls in, e.g. `2024-05`
+fn month(now: DateTime<Utc>) -> String {
+    now.format("%Y-%m").to_string()
+}
+
+/// Bytes sent through the tunnel in one month
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct MonthlyEgress {
+    month: String,
+    bytes: u64,
+}
+
+/// Bytes sent through the tunnel in the current month. Without a path
+/// nothing is persisted.
+#[derive(Debug, Default)]
+pub struct EgressLedger {
+    egress: Arc<JsonLedger<MonthlyEgress>>,
+}
+
+impl EgressLedger {
+    /// Default location: `~/.mindlink/tunnel_egress.json`
+    pub fn default_path() -> MindLinkResult<PathBuf> {
+        json_ledger::default_path("tunnel_egress.json")
+    }
+
+    /// The ledger saved at `path`; empty when there is none yet. When it
+    /// cannot be read, the month's egress counts as used up.
+    pub fn open(path: &Path) -> Self {
+        Self {
+            egress: Arc::new(JsonLedger::open(path)),
+        }
+    }
+
+    /// Bytes sent in the month `now` falls in
+    pub fn used(&self, now: DateTime<Utc>) -> u64 {
+        let egress = self.egress.lock();
+        if self.egress.is_unreadable() {
+            u64::MAX
+        } else if egress.month == month(now) {
+            egress.bytes
+        } else {
+            0
+        }
+    }
+
+    /// Count `bytes` as sent at `now`, returning the month's new total
+    pub fn record(&self, bytes: u64, now: DateTime<Utc>) -> u64 {
+        let mut egress = self.egress.lock();
+        let month = month(now);
+        if egress.month != month {
+            *egress = MonthlyEgress { month, bytes: 0 };
+        }
+        egress.bytes = egress.bytes.saturating_add(bytes);
+        if self.egress.is_unreadable() {
+            u64::MAX
+        } else {
+            egress.bytes
+        }
+    }
+
+    /// Write the ledger to its file. Blocks, so async callers use
+    /// [`EgressLedger::save_in_background`].
+    pub fn save(&self) -> MindLinkResult<()> {
+        self.egress.save()
+    }
+
+    pub fn save_in_background(&self) {
+        self.egress.save_in_background();
+    }
+}
@@ -0,0 +1,140 @@
+// PID files of the tunnel processes
+//
+// cloudflared and ngrok run as child processes, which outlive MindLink when it
+// crashes or is killed. Such an orphan keeps serving the old tunnel, and a
+// named tunnel started again next to it fights it over the same config. Every
+// tunnel process is therefore recorded in `~/.mindlink/run/<process>.pid`
+// while it runs, and on startup the processes of leftover files are stopped.
+// A PID is only acted on while it still belongs to the program the file is
+// named after, so a PID the system handed to another process since is left
+// alone.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+#[cfg(unix)]
+use std::time::Duration;
+
+/// How long an orphan may take to exit before it is killed
+#[cfg(unix)]
+const EXIT_GRACE: Duration = Duration::from_secs(5);
+
+/// Where the PID files are kept, `~/.mindlink/run`
+pub fn pid_files_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".mindlink").join("run"))
+}
+
+fn pid_file(dir: &Path, process_id: &str) -> PathBuf {
+    dir.join(format!("{}.pid", process_id))
+}
+
+/// Program a process ID such as `cloudflared-bifrost` runs
+fn program(process_id: &str) -> &str {
+    process_id
+        .split_once('-')
+        .map_or(process_id, |(program, _)| program)
+}
+
+/// Record that the tunnel process `process_id` runs as `pid`
+pub async fn record(dir: &Path, process_id: &str, pid: u32) -> Result<()> {
+    tokio::fs::create_dir_all(dir).await?;
+    tokio::fs::write(pid_file(dir, process_id), pid.to_string()).await?;
+    Ok(())
+}
+
+/// Drop the record of `process_id`, which was stopped
+pub async fn forget(dir: &Path, process_id: &str) -> Result<()> {
+    match tokio::fs::remove_file(pid_file(dir, process_id)).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Stop the tunnel processes recorded in `dir`, left behind by a run that
+/// did not close its tunnels. Returns the PIDs stopped.
+pub async fn reap_orphans(dir: &Path) -> Vec<u32> {
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return Vec::new();
+    };
+
+    let mut reaped = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().is_none_or(|extension| extension != "pid") {
+            continue;
+        }
+        let Some(process_id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+
+        let pid = tokio::fs::read_to_string(&path)
+            .await
+            .ok()
+            .and_then(|content| content.trim().parse::<u32>().ok());
+        if let Some(pid) = pid {
+            let expected = program(process_id);
+            let running = running_program(pid).await;
+            if running.is_some_and(|running| running.starts_with(expected)) {
+                terminate(pid).await;
+                reaped.push(pid);
+            }
+        }
+        let _ = tokio::fs::remove_file(&path).await;
+    }
+    reaped
+}
+
+/// Name of the program running as `pid`, if any
+#[cfg(unix)]
+async fn running_program(pid: u32) -> Option<String> {
+    let output = tokio::process::Command::new("ps")
+        .args(["-p", &pid.to_string(), "-o", "comm="])
+        .output()
+        .await
+        .ok()?;
+    // macOS prints the whole path of the executable
+    let name = String::from_utf8_lossy(&output.stdout);
+    let name = name.trim().rsplit('/').next()?;
+    Some(name.to_ascii_lowercase()).filter(|name| output.status.success() && !name.is_empty())
+}
+
+#[cfg(windows)]
+async fn running_program(pid: u32) -> Option<String> {
+    let output = tokio::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+        .output()
+        .await
+        .ok()?;
+    // "cloudflared.exe","1234",...; no process found is told in plain text
+    let output = String::from_utf8_lossy(&output.stdout);
+    let name = output.trim().strip_prefix('"')?.split('"').next()?;
+    let name = name.to_ascii_lowercase();
+    Some(name.trim_end_matches(".exe").to_string())
+}
+
+/// Send `signal` to `pid`, telling whether the process exists
+#[cfg(unix)]
+#[allow(unsafe_code)]
+fn send_signal(pid: u32, signal: i32) -> bool {
+    unsafe { libc::kill(pid as i32, signal) == 0 }
+}
+
+/// Ask `pid` to exit, and kill it when it does not in time
+#[cfg(unix)]
+async fn terminate(pid: u32) {
+    send_signal(pid, libc::SIGTERM);
+    let deadline = tokio::time::Instant::now() + EXIT_GRACE;
+    while send_signal(pid, 0) && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    if send_signal(pid, 0) {
+        send_signal(pid, libc::SIGKILL);
+    }
+}
+
+#[cfg(windows)]
+async fn terminate(pid: u32) {
+    let _ = tokio::process::Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/F"])
+        .output()
+        .await;
+}
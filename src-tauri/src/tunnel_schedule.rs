@@ -0,0 +1,161 @@
+// Availability windows of the tunnel
+//
+// A schedule lists the windows of the week, in local time, during which the
+// tunnel is up, e.g. weekdays from 09:00 to 17:00. While MindLink serves, a
+// background task asks the tunnel manager to apply the schedule every
+// `CHECK_INTERVAL`; the tunnel is opened when a window begins and closed when
+// it ends. Only those changes are acted on, so a tunnel the user closes or
+// opens by hand stays that way until the next one.
+
+use crate::managers::config_manager::{TunnelScheduleConfig, TunnelWindow};
+use chrono::{Datelike, Days, NaiveDateTime, NaiveTime, Weekday};
+use std::time::Duration;
+
+/// How often the schedule is applied
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+const DAYS: [(&str, Weekday); 7] = [
+    ("mon", Weekday::Mon),
+    ("tue", Weekday::Tue),
+    ("wed", Weekday::Wed),
+    ("thu", Weekday::Thu),
+    ("fri", Weekday::Fri),
+    ("sat", Weekday::Sat),
+    ("sun", Weekday::Sun),
+];
+
+/// A window of the schedule, parsed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Window {
+    /// Days the window starts on; every day when empty
+    days: Vec<Weekday>,
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl Window {
+    pub fn parse(window: &TunnelWindow) -> Result<Self, String> {
+        let days = window
+            .days
+            .iter()
+            .map(|day| {
+                DAYS.iter()
+                    .find(|(name, _)| day.eq_ignore_ascii_case(name))
+                    .map(|(_, weekday)| *weekday)
+                    .ok_or_else(|| {
+                        format!(
+                            "Invalid day '{}' in the tunnel schedule. Must be one of: mon, tue, \
+                             wed, thu, fri, sat, sun",
+                            day
+                        )
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        let time = |value: &str| {
+            NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| {
+                format!(
+                    "Invalid time '{}' in the tunnel schedule, expected HH:MM",
+                    value
+                )
+            })
+        };
+        let (start, end) = (time(&window.start)?, time(&window.end)?);
+        if start == end {
+            return Err(format!(
+                "Tunnel schedule window {}-{} is empty",
+                window.start, window.end
+            ));
+        }
+        Ok(Self { days, start, end })
+    }
+
+    fn starts_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    fn contains(&self, now: NaiveDateTime) -> bool {
+        let (today, time) = (now.weekday(), now.time());
+        if self.start < self.end {
+            return self.starts_on(today) && self.start <= time && time < self.end;
+        }
+        // Past midnight: the evening of its day, or the morning after
+        (self.starts_on(today) && time >= self.start)
+            || (self.starts_on(today.pred()) && time < self.end)
+    }
+}
+
+/// Whether the tunnel is up at a given time, and until when
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduleStatus {
+    pub open: bool,
+    /// When the tunnel opens or closes next
+    pub until: Option<NaiveDateTime>,
+}
+
+impl ScheduleStatus {
+    /// Told in the tray, relative to `now`
+    pub fn describe(&self, now: NaiveDateTime) -> String {
+        let state = if self.open {
+            "Tunnel open"
+        } else {
+            "Tunnel closed"
+        };
+        match self.until {
+            Some(until) if until.date() == now.date() => {
+                format!("{} until {}", state, until.format("%H:%M"))
+            },
+            Some(until) => format!("{} until {}", state, until.format("%a %H:%M")),
+            None => format!("{} by schedule", state),
+        }
+    }
+}
+
+/// The windows of the tunnel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    windows: Vec<Window>,
+}
+
+impl Schedule {
+    /// The schedule `config` describes, or `None` when the tunnel is not
+    /// scheduled. Windows that do not parse are left out; validation
+    /// refuses them before they are saved.
+    pub fn from_config(config: &TunnelScheduleConfig) -> Option<Self> {
+        if !config.enabled || config.windows.is_empty() {
+            return None;
+        }
+        let windows = config
+            .windows
+            .iter()
+            .filter_map(|window| Window::parse(window).ok())
+            .collect();
+        Some(Self { windows })
+    }
+
+    pub fn is_open(&self, now: NaiveDateTime) -> bool {
+        self.windows.iter().any(|window| window.contains(now))
+    }
+
+    /// Whether the tunnel is up at `now`, and when that changes within the
+    /// next week
+    pub fn status(&self, now: NaiveDateTime) -> ScheduleStatus {
+        let open = self.is_open(now);
+        // Windows only begin and end at their boundaries
+        let mut boundaries: Vec<NaiveDateTime> = (0..=7)
+            .filter_map(|offset| now.date().checked_add_days(Days::new(offset)))
+            .flat_map(|date| {
+                self.windows.iter().flat_map(move |window| {
+                    [date.and_time(window.start), date.and_time(window.end)]
+                })
+            })
+            .filter(|boundary| *boundary > now)
+            .collect();
+        boundaries.sort();
+        ScheduleStatus {
+            open,
+            until: boundaries
+                .into_iter()
+                .find(|boundary| self.is_open(*boundary) != open),
+        }
+    }
+}
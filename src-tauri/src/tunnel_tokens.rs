@@ -0,0 +1,114 @@
+// Access tokens for clients that reach MindLink through the tunnel
+//
+// Devices paired over the public tunnel authenticate with a token of their
+// own, separate from the ChatGPT credentials and from the API keys of
+// authorized apps, so a leaked pairing code gives away neither. The tokens are
+// short-lived: a new one is issued shortly before the current one expires,
+// which keeps working until then so paired clients have time to pick up its
+// successor from the pairing QR code. Rotating by hand revokes the old token
+// at once. The tokens are kept in the keyring like the OAuth tokens.
+
+use crate::token_store;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use ts_rs::TS;
+
+/// Tokens are rotated this long before they expire, and clients of the old
+/// token have this long to switch
+pub const ROTATION_LEAD_MINUTES: i64 = 15;
+
+/// Seconds between two looks at the expiry of the current token
+pub const CHECK_INTERVAL_SECS: u64 = 60;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TunnelToken {
+    pub token: String,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A new tunnel token was issued; pairing data shown before is outdated
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, TS)]
+#[ts(export)]
+pub struct TunnelTokenRotated {
+    pub expires_at: DateTime<Utc>,
+    /// Whether the user rotated it, revoking the previous token
+    pub manual: bool,
+}
+
+/// The current tunnel token and the one it replaced, which clients may still
+/// use until it expires
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TunnelTokens {
+    current: Option<TunnelToken>,
+    previous: Option<TunnelToken>,
+}
+
+/// New token, shaped like the keys OpenAI clients expect
+pub fn generate_tunnel_token() -> String {
+    format!("sk-mindlink-tunnel-{}", uuid::Uuid::new_v4().simple())
+}
+
+/// Where the tunnel tokens are kept, `~/.mindlink/tunnel_tokens.json`
+pub fn tunnel_tokens_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(".mindlink").join("tunnel_tokens.json"))
+}
+
+impl TunnelTokens {
+    /// The tokens stored at `path`, none when nothing is stored yet
+    pub async fn load(path: &Path) -> Result<Self> {
+        match token_store::read(path).await? {
+            Some(content) => serde_json::from_str(&content)
+                .map_err(|e| anyhow!("Tunnel tokens are unreadable: {}", e)),
+            None => Ok(Self::default()),
+        }
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        token_store::write(path, &serde_json::to_string(self)?).await
+    }
+
+    /// The token to hand out to clients, unless it expired
+    pub fn current(&self, now: DateTime<Utc>) -> Option<&TunnelToken> {
+        self.current.as_ref().filter(|token| token.expires_at > now)
+    }
+
+    /// Whether a token was ever issued, i.e. clients were paired
+    pub fn issued(&self) -> bool {
+        self.current.is_some()
+    }
+
+    /// Whether a new token should be issued, because there is none or the
+    /// current one expires within `ROTATION_LEAD_MINUTES`
+    pub fn needs_rotation(&self, now: DateTime<Utc>) -> bool {
+        let lead = Duration::minutes(ROTATION_LEAD_MINUTES);
+        self.current
+            .as_ref()
+            .is_none_or(|token| token.expires_at - now <= lead)
+    }
+
+    /// Issue a new token valid for `lifetime`. Unless `revoke`, the token it
+    /// replaces stays valid until it expires.
+    pub fn rotate(&mut self, lifetime: Duration, revoke: bool, now: DateTime<Utc>) -> TunnelToken {
+        let token = TunnelToken {
+            token: generate_tunnel_token(),
+            issued_at: now,
+            expires_at: now + lifetime,
+        };
+        let replaced = self.current.replace(token.clone());
+        self.previous = replaced.filter(|_| !revoke);
+        token
+    }
+
+    /// The token the current one replaced, while clients may still use it
+    pub fn previous(&self, now: DateTime<Utc>) -> Option<&TunnelToken> {
+        self.previous
+            .as_ref()
+            .filter(|token| token.expires_at > now)
+    }
+}
@@ -0,0 +1,128 @@
+// Webhooks told about changes of the tunnel's public URL
+//
+// Quick tunnels come back under a new URL after every restart, which breaks
+// paired clients. Every configured webhook gets each change posted as JSON,
+// so scripts and devices can follow the tunnel to its new URL. Payloads are
+// signed with the webhook's secret when it has one, so receivers can tell them
+// from forgeries. Failed deliveries are retried a few times, then given up on.
+
+use crate::error::{MindLinkError, MindLinkResult};
+use crate::managers::config_manager::TunnelWebhook;
+use crate::managers::tunnel_manager::TunnelUrlChanged;
+use crate::{log_warn, network_error, proxy};
+use futures_util::future::join_all;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::Serialize;
+use sha2::Sha256;
+use std::time::Duration;
+
+/// Name of the event, sent in [`EVENT_HEADER`] and the payload
+pub const EVENT: &str = "tunnel-url-changed";
+
+pub const EVENT_HEADER: &str = "X-MindLink-Event";
+
+/// Header carrying `sha256=` and the hex HMAC-SHA256 of the body
+pub const SIGNATURE_HEADER: &str = "X-MindLink-Signature";
+
+/// Attempts per delivery, the first one included
+const ATTEMPTS: u32 = 3;
+
+/// Wait before the second attempt, doubled before each further one
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    event: &'static str,
+    #[serde(flatten)]
+    change: &'a TunnelUrlChanged,
+}
+
+/// Client webhooks are delivered with, through the configured proxy
+pub fn webhook_client() -> Client {
+    proxy::apply(Client::builder())
+        .timeout(DELIVERY_TIMEOUT)
+        .user_agent("MindLink/1.0")
+        .build()
+        .unwrap_or_default()
+}
+
+/// Value of [`SIGNATURE_HEADER`] for `body` signed with `secret`
+pub fn signature(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+    let hex: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("sha256={}", hex)
+}
+
+/// Post `change` to `webhook`. Server errors and failed connections are
+/// retried; the receiver refusing the payload is not.
+pub async fn deliver(
+    client: &Client,
+    webhook: &TunnelWebhook,
+    change: &TunnelUrlChanged,
+) -> MindLinkResult<()> {
+    let body = serde_json::to_vec(&Payload {
+        event: EVENT,
+        change,
+    })?;
+
+    let mut delay = RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        let mut request = client
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, EVENT)
+            .body(body.clone());
+        if let Some(secret) = &webhook.secret {
+            request = request.header(SIGNATURE_HEADER, signature(secret, &body));
+        }
+
+        let error = match request.send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            Ok(response) if !response.status().is_server_error() => {
+                return Err(MindLinkError::Network {
+                    message: format!("Webhook refused the payload: {}", response.status()),
+                    url: Some(webhook.url.clone()),
+                    source: None,
+                });
+            },
+            Ok(response) => MindLinkError::Network {
+                message: format!("Webhook failed: {}", response.status()),
+                url: Some(webhook.url.clone()),
+                source: None,
+            },
+            Err(e) => network_error!("Webhook is unreachable", &webhook.url, e),
+        };
+        if attempt == ATTEMPTS {
+            return Err(error);
+        }
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+        attempt += 1;
+    }
+}
+
+/// Post `change` to every webhook at once, logging those that failed
+pub async fn notify_all(client: &Client, webhooks: &[TunnelWebhook], change: &TunnelUrlChanged) {
+    let deliveries = webhooks
+        .iter()
+        .map(|webhook| async move { (webhook, deliver(client, webhook, change).await) });
+    for (webhook, result) in join_all(deliveries).await {
+        if let Err(e) = result {
+            log_warn!(
+                "TunnelWebhooks",
+                format!("Failed to notify {}: {}", webhook.url, e)
+            );
+        }
+    }
+}
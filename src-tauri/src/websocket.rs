@@ -0,0 +1,401 @@
+// Chat completions over WebSocket
+//
+// `/v1/ws` is for clients that cannot consume SSE, such as some mobile
+// frameworks. A socket carries any number of requests, each tagged with an ID
+// chosen by the client, and the replies to them interleave:
+//
+// - client: `{"type": "request", "id": "...", "body": {chat completion request}}`
+//   and `{"type": "cancel", "id": "..."}`
+// - server: `chunk` messages with one `chat.completion.chunk` each, or a
+//   `response` with the `chat.completion` when the body sets `stream: false`,
+//   then `done`. A request that fails ends with `error` instead, one that is
+//   cancelled with `cancelled`.
+//
+// `stream` defaults to true on a socket. Every request is served by the same
+// route as `POST /v1/chat/completions`, with the headers of the upgrade
+// request, so translation, app policies, capture and backpressure apply
+// exactly as they do over HTTP.
+
+use crate::api_error::ApiError;
+use crate::log_debug;
+use axum::{
+    body::{Body, Bytes},
+    extract::ws::{Message, WebSocket},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode, Uri},
+    Router,
+};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tower::Service;
+
+pub const COMPLETIONS_PATH: &str = "/v1/chat/completions";
+
+/// Requests one socket may have running at once
+pub const MAX_ACTIVE_REQUESTS: usize = 16;
+
+/// Server messages waiting for a slow client before requests stop reading
+/// their upstream streams
+const OUTGOING_BUFFER: usize = 64;
+
+/// Headers of the upgrade request that do not describe a completion request
+const SKIPPED_HEADERS: &[&str] = &[
+    "host",
+    "connection",
+    "upgrade",
+    "content-length",
+    "content-type",
+    "transfer-encoding",
+    "accept-encoding",
+];
+
+/// The completion route on its own, without the HTTP layers around the
+/// router: those already ran for the upgrade request
+#[derive(Clone)]
+pub struct CompletionRoute {
+    pub route: Router,
+    /// The body limit of the route
+    pub max_message_bytes: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    /// `body` is a chat completion request in OpenAI's format
+    Request {
+        id: String,
+        body: Value,
+    },
+    Cancel {
+        id: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    /// One `chat.completion.chunk`
+    Chunk {
+        id: String,
+        data: Value,
+    },
+    /// The `chat.completion` of a request that did not stream
+    Response {
+        id: String,
+        data: Value,
+    },
+    Done {
+        id: String,
+    },
+    /// `error` is the error object of OpenAI's format. Errors about a message
+    /// that could not be read have no ID.
+    Error {
+        id: Option<String>,
+        status: u16,
+        error: Value,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        retry_after_secs: Option<u64>,
+    },
+    Cancelled {
+        id: String,
+    },
+}
+
+impl ServerMessage {
+    fn error(id: Option<String>, error: ApiError) -> Self {
+        ServerMessage::Error {
+            id,
+            status: error.status.as_u16(),
+            error: error.body()["error"].clone(),
+            retry_after_secs: None,
+        }
+    }
+}
+
+/// Splits an SSE body into the payloads of its `data:` lines. Events may be
+/// split anywhere across chunks.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: Vec<u8>,
+}
+
+impl SseParser {
+    /// Payloads of the events completed by `chunk`
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(chunk);
+        let mut payloads = Vec::new();
+        while let Some(end) = self.buffer.windows(2).position(|pair| pair == b"\n\n") {
+            let event: Vec<u8> = self.buffer.drain(..end + 2).collect();
+            let event = String::from_utf8_lossy(&event);
+            let data: Vec<&str> = event
+                .lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .map(str::trim_start)
+                .collect();
+            if !data.is_empty() {
+                payloads.push(data.join("\n"));
+            }
+        }
+        payloads
+    }
+}
+
+fn forwarded(name: &HeaderName) -> bool {
+    let name = name.as_str();
+    !SKIPPED_HEADERS.contains(&name) && !name.starts_with("sec-websocket-")
+}
+
+/// The HTTP request `body` stands for on a socket opened with `headers`, and
+/// whether it streams
+pub fn completion_request(headers: &HeaderMap, mut body: Value) -> (Request<Body>, bool) {
+    if let Some(object) = body.as_object_mut() {
+        object.entry("stream").or_insert(Value::Bool(true));
+    }
+    let stream = body.get("stream").and_then(Value::as_bool).unwrap_or(false);
+
+    let mut request = Request::new(Body::from(body.to_string()));
+    *request.method_mut() = Method::POST;
+    *request.uri_mut() = Uri::from_static(COMPLETIONS_PATH);
+    let request_headers = request.headers_mut();
+    for (name, value) in headers.iter().filter(|(name, _)| forwarded(name)) {
+        request_headers.append(name, value.clone());
+    }
+    request_headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    (request, stream)
+}
+
+/// The error object of a failed response
+fn response_error(status: StatusCode, body: &Bytes) -> Value {
+    let parsed: Option<Value> = serde_json::from_slice(body).ok();
+    match parsed.as_ref().map(|body| &body["error"]) {
+        Some(error @ Value::Object(_)) => error.clone(),
+        Some(Value::String(message)) => {
+            ApiError::new(status, message.as_str()).body()["error"].clone()
+        },
+        _ => {
+            let text = String::from_utf8_lossy(body);
+            let message = if text.trim().is_empty() {
+                status
+                    .canonical_reason()
+                    .unwrap_or("Request failed")
+                    .to_string()
+            } else {
+                text.trim().to_string()
+            };
+            ApiError::new(status, message).body()["error"].clone()
+        },
+    }
+}
+
+/// Serve one request through `route` and send its replies to `tx`. Returns
+/// early, dropping the upstream stream, when the socket is gone.
+pub async fn run_request(
+    mut route: Router,
+    request: Request<Body>,
+    stream: bool,
+    id: String,
+    tx: mpsc::Sender<ServerMessage>,
+) {
+    // A router is always ready
+    let ready = std::future::poll_fn(|cx| Service::<Request<Body>>::poll_ready(&mut route, cx));
+    if let Err(never) = ready.await {
+        match never {}
+    }
+    let response = match route.call(request).await {
+        Ok(response) => response,
+        Err(never) => match never {},
+    };
+    let status = response.status();
+    let retry_after_secs = response
+        .headers()
+        .get(header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+    let body = response.into_body();
+
+    if !status.is_success() || !stream {
+        let message = match axum::body::to_bytes(body, usize::MAX).await {
+            Ok(bytes) if status.is_success() => match serde_json::from_slice(&bytes) {
+                Ok(data) => ServerMessage::Response {
+                    id: id.clone(),
+                    data,
+                },
+                Err(_) => ServerMessage::error(
+                    Some(id.clone()),
+                    ApiError::new(StatusCode::BAD_GATEWAY, "The completion was not valid JSON"),
+                ),
+            },
+            Ok(bytes) => ServerMessage::Error {
+                id: Some(id.clone()),
+                status: status.as_u16(),
+                error: response_error(status, &bytes),
+                retry_after_secs,
+            },
+            Err(e) => ServerMessage::error(
+                Some(id.clone()),
+                ApiError::new(
+                    StatusCode::BAD_GATEWAY,
+                    format!("Failed to read the completion: {}", e),
+                ),
+            ),
+        };
+        let succeeded = matches!(message, ServerMessage::Response { .. });
+        if tx.send(message).await.is_ok() && succeeded {
+            let _ = tx.send(ServerMessage::Done { id }).await;
+        }
+        return;
+    }
+
+    let mut parser = SseParser::default();
+    let mut chunks = body.into_data_stream();
+    while let Some(chunk) = chunks.next().await {
+        let Ok(chunk) = chunk else {
+            break;
+        };
+        for payload in parser.push(&chunk) {
+            let message = if payload == "[DONE]" {
+                ServerMessage::Done { id: id.clone() }
+            } else {
+                match serde_json::from_str::<Value>(&payload) {
+                    Ok(data) if data.get("error").is_some() => ServerMessage::Error {
+                        id: Some(id.clone()),
+                        status: StatusCode::BAD_GATEWAY.as_u16(),
+                        error: data["error"].clone(),
+                        retry_after_secs: None,
+                    },
+                    Ok(data) => ServerMessage::Chunk {
+                        id: id.clone(),
+                        data,
+                    },
+                    // Not a chunk, so not something the client asked for
+                    Err(_) => continue,
+                }
+            };
+            let finished = !matches!(message, ServerMessage::Chunk { .. });
+            if tx.send(message).await.is_err() || finished {
+                return;
+            }
+        }
+    }
+
+    let _ = tx
+        .send(ServerMessage::error(
+            Some(id),
+            ApiError::new(
+                StatusCode::BAD_GATEWAY,
+                "The stream ended before it was finished",
+            ),
+        ))
+        .await;
+}
+
+/// Serve the requests sent over `socket` until the client closes it. Requests
+/// still running then are cancelled.
+pub async fn serve(
+    socket: WebSocket,
+    CompletionRoute { route, .. }: CompletionRoute,
+    headers: HeaderMap,
+) {
+    log_debug!("WebSocket", "Client connected");
+    let (mut sink, mut incoming) = socket.split();
+    let (tx, mut rx) = mpsc::channel::<ServerMessage>(OUTGOING_BUFFER);
+
+    let writer = tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            let Ok(text) = serde_json::to_string(&message) else {
+                continue;
+            };
+            if sink.send(Message::Text(text)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut active: HashMap<String, JoinHandle<()>> = HashMap::new();
+    while let Some(Ok(message)) = incoming.next().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Binary(_) => {
+                let reply = ServerMessage::error(
+                    None,
+                    ApiError::new(StatusCode::BAD_REQUEST, "Messages must be JSON text"),
+                );
+                if tx.send(reply).await.is_err() {
+                    break;
+                }
+                continue;
+            },
+            Message::Close(_) => break,
+            // Pings are answered by axum
+            Message::Ping(_) | Message::Pong(_) => continue,
+        };
+        active.retain(|_, task| !task.is_finished());
+
+        let reply = match serde_json::from_str::<ClientMessage>(&text) {
+            Err(e) => Some(ServerMessage::error(
+                None,
+                ApiError::new(StatusCode::BAD_REQUEST, format!("Invalid message: {}", e)),
+            )),
+            Ok(ClientMessage::Request { id, .. }) if active.contains_key(&id) => {
+                Some(ServerMessage::error(
+                    Some(id),
+                    ApiError::new(
+                        StatusCode::CONFLICT,
+                        "A request with this ID is still running",
+                    ),
+                ))
+            },
+            Ok(ClientMessage::Request { id, .. }) if active.len() >= MAX_ACTIVE_REQUESTS => {
+                Some(ServerMessage::error(
+                    Some(id),
+                    ApiError::new(
+                        StatusCode::TOO_MANY_REQUESTS,
+                        format!(
+                            "At most {} requests may run at once on a socket",
+                            MAX_ACTIVE_REQUESTS
+                        ),
+                    ),
+                ))
+            },
+            Ok(ClientMessage::Request { id, body }) => {
+                let (request, stream) = completion_request(&headers, body);
+                let task = tokio::spawn(run_request(
+                    route.clone(),
+                    request,
+                    stream,
+                    id.clone(),
+                    tx.clone(),
+                ));
+                active.insert(id, task);
+                None
+            },
+            Ok(ClientMessage::Cancel { id }) => match active.remove(&id) {
+                Some(task) => {
+                    task.abort();
+                    Some(ServerMessage::Cancelled { id })
+                },
+                None => Some(ServerMessage::error(
+                    Some(id),
+                    ApiError::new(StatusCode::NOT_FOUND, "No request with this ID is running"),
+                )),
+            },
+        };
+        if let Some(reply) = reply {
+            if tx.send(reply).await.is_err() {
+                break;
+            }
+        }
+    }
+
+    for task in active.values() {
+        task.abort();
+    }
+    writer.abort();
+    log_debug!("WebSocket", "Client disconnected");
+}
@@ -3,7 +3,10 @@ import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
 import Dashboard from './components/Dashboard'
 import UnifiedNavigation from './components/UnifiedNavigation'
+import { listenAppEvent } from './services/events'
 import type { ServiceResponse } from './types/api'
+import type { AuthEvent } from './types/generated/AuthEvent'
+import type { RecoveryAction } from './types/generated/RecoveryAction'
 import './design-system/index.css'
 import './App.css'
 
@@ -15,6 +18,41 @@ interface AppState {
   autoStartAttempted: boolean
   authCheckComplete: boolean
   errorMessage: string | null
+  errorAction: RecoveryAction | null
+  authProgress: string | null
+}
+
+/** Line shown while a login is in progress, or null once it is over */
+function describeAuthProgress(event: AuthEvent): string | null {
+  switch (event.stage) {
+    case 'login_started':
+      return 'Starting ChatGPT login...'
+    case 'browser_opened':
+      return `Continue the login in your browser. If it did not open, visit ${event.url}`
+    case 'device_code_issued':
+      return `Enter the code ${event.user_code} at ${event.verification_url}`
+    case 'callback_received':
+      return 'Finishing login...'
+    case 'tokens_saved':
+    case 'login_failed':
+    case 'refresh_failed':
+    case 'reauth_required':
+      return null
+  }
+}
+
+/** Button label for a recovery the banner can start, or a hint for the rest */
+function describeRecovery(action: RecoveryAction): { label: string, runnable: boolean } {
+  switch (action.action) {
+    case 're_authenticate':
+      return { label: 'Sign In Again', runnable: true }
+    case 'reinstall_binary':
+      return { label: `Reinstall ${action.binary}`, runnable: true }
+    case 'open_settings':
+      return { label: `Check the ${action.section} settings`, runnable: false }
+    case 'retry_later':
+      return { label: 'Try again in a moment', runnable: false }
+  }
 }
 
 function App() {
@@ -25,7 +63,9 @@ function App() {
     isAuthenticated: false,
     autoStartAttempted: false,
     authCheckComplete: false,
-    errorMessage: null
+    errorMessage: null,
+    errorAction: null,
+    authProgress: null
   })
 
   useEffect(() => {
@@ -119,6 +159,35 @@ function App() {
       }))
     }).then(unsub => unsubscribeListeners.push(unsub))
 
+    // Show how far a login got, and failed logins
+    listenAppEvent('auth', (event) => {
+      setState(prev => {
+        const next = { ...prev, authProgress: describeAuthProgress(event) }
+        if (event.stage === 'tokens_saved') {
+          next.isAuthenticated = true
+        } else if (event.stage === 'login_failed') {
+          next.errorMessage = `Login failed: ${event.reason}`
+          next.errorAction = null
+        } else if (event.stage === 'reauth_required') {
+          next.isAuthenticated = false
+          next.errorMessage = `ChatGPT session ended: ${event.reason}`
+          next.errorAction = { action: 're_authenticate' }
+        }
+        return next
+      })
+    }).then(unsub => unsubscribeListeners.push(unsub))
+
+    // Listen for error notifications, which may carry a recovery action
+    listenAppEvent('notification', (notification) => {
+      if (notification.type === 'error') {
+        setState(prev => ({
+          ...prev,
+          errorMessage: notification.message,
+          errorAction: notification.action,
+        }))
+      }
+    }).then(unsub => unsubscribeListeners.push(unsub))
+
     // Cleanup listeners on unmount
     return () => {
       unsubscribeListeners.forEach(unsub => unsub())
@@ -192,13 +261,31 @@ function App() {
 
 
   const handleBifrostError = (message: string) => {
-    setState(prev => ({ ...prev, errorMessage: message }))
+    setState(prev => ({ ...prev, errorMessage: message, errorAction: null }))
     // Auto-clear error after 5 seconds
     setTimeout(() => {
       setState(prev => ({ ...prev, errorMessage: null }))
     }, 5000)
   }
 
+  const dismissError = () => {
+    setState(prev => ({ ...prev, errorMessage: null, errorAction: null }))
+  }
+
+  const handleRecovery = async (action: RecoveryAction) => {
+    dismissError()
+    try {
+      if (action.action === 're_authenticate') {
+        await invoke('authenticate_chatgpt')
+        await handleAuthSuccess()
+      } else if (action.action === 'reinstall_binary') {
+        await invoke(action.binary === 'cloudflared' ? 'install_cloudflared_binary' : 'reinstall_bifrost_binary')
+      }
+    } catch (error) {
+      setState(prev => ({ ...prev, errorMessage: String(error), errorAction: null }))
+    }
+  }
+
   return (
     <div className="app">
 
@@ -210,9 +297,20 @@ function App() {
             <div className="error-banner">
               <div className="error-banner__content">
                 <span className="error-banner__text">{state.errorMessage}</span>
+                {state.errorAction && (() => {
+                  const action = state.errorAction
+                  const { label, runnable } = describeRecovery(action)
+                  return runnable ? (
+                    <button className="error-banner__action" onClick={() => handleRecovery(action)}>
+                      {label}
+                    </button>
+                  ) : (
+                    <span className="error-banner__hint">{label}</span>
+                  )
+                })()}
                 <button 
                   className="error-banner__close"
-                  onClick={() => setState(prev => ({ ...prev, errorMessage: null }))}
+                  onClick={dismissError}
                   title="Dismiss"
                 >
                   ×
@@ -274,6 +372,9 @@ function App() {
                   <p className="auth-welcome__cta">
                     Click <strong>"Login with ChatGPT"</strong> in the navigation bar above to get started.
                   </p>
+                  {state.authProgress && (
+                    <p className="auth-welcome__progress">{state.authProgress}</p>
+                  )}
                 </div>
               </div>
             )}
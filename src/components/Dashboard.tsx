@@ -3,6 +3,9 @@ import ProvidersCard from './ProvidersCard'
 import QRCodeCard from './QRCodeCard'
 import AppsCard from './AppsCard'
 import AppDetailsModal from './AppDetailsModal'
+import SecurityReportCard from './SecurityReportCard'
+import TokenBudgetsCard from './TokenBudgetsCard'
+import StreamStatsCard from './StreamStatsCard'
 import './Dashboard.css'
 
 interface App {
@@ -68,6 +71,21 @@ const Dashboard: React.FC<DashboardProps> = ({
         <div className="dashboard-section">
           <ProvidersCard />
         </div>
+
+        {/* Security Review */}
+        <div className="dashboard-section">
+          <SecurityReportCard />
+        </div>
+
+        {/* Token Budgets */}
+        <div className="dashboard-section">
+          <TokenBudgetsCard />
+        </div>
+
+        {/* Streaming Performance */}
+        <div className="dashboard-section">
+          <StreamStatsCard />
+        </div>
       </div>
       
       {/* App Details Modal */}
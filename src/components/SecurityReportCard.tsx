@@ -0,0 +1,95 @@
+import React, { useState, useEffect } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import type { SecurityReport, Severity } from '../types/api'
+import './SecurityReportCard.css'
+
+const scoreBadge = (score: number) => {
+  if (score >= 80) return 'success'
+  if (score >= 50) return 'warning'
+  return 'error'
+}
+
+const SecurityReportCard: React.FC = () => {
+  const [report, setReport] = useState<SecurityReport | null>(null)
+  const [error, setError] = useState<string | null>(null)
+
+  const refreshReport = async () => {
+    try {
+      setReport(await invoke<SecurityReport>('get_security_report'))
+      setError(null)
+    } catch (err) {
+      console.error('❌ Failed to load security report:', err)
+      setError(String(err))
+    }
+  }
+
+  useEffect(() => {
+    refreshReport()
+
+    // Exposure changes when services or the tunnel start and stop
+    const interval = setInterval(refreshReport, 30000)
+
+    return () => {
+      clearInterval(interval)
+    }
+  }, [])
+
+  const severityLabel = (severity: Severity) =>
+    severity.charAt(0).toUpperCase() + severity.slice(1)
+
+  return (
+    <div className="card card--elevated">
+      <div className="card__header">
+        <div className="card__header-flex">
+          <h2 className="card__title">Security Review</h2>
+          {report && (
+            <div className={`status-badge status-badge--${scoreBadge(report.score)}`}>
+              Score {report.score}/100
+            </div>
+          )}
+        </div>
+      </div>
+
+      <div className="card__content">
+        {error && <p className="security-report__error">{error}</p>}
+
+        {report && (
+          <>
+            <ul className="security-report__surface">
+              {report.surface.length === 0 && <li>No services are listening</li>}
+              {report.surface.map((endpoint) => (
+                <li key={`${endpoint.name}-${endpoint.address}`}>
+                  <span className="security-report__endpoint">{endpoint.name}</span>
+                  <code>{endpoint.address}</code>
+                  <span className={`security-report__reach security-report__reach--${endpoint.reach}`}>
+                    {endpoint.reach}
+                  </span>
+                </li>
+              ))}
+            </ul>
+
+            <ul className="security-report__findings">
+              {report.findings.map((finding, index) => (
+                <li
+                  key={`${finding.id}-${index}`}
+                  className={`security-finding security-finding--${finding.severity}`}
+                >
+                  <div className="security-finding__header">
+                    <span className="security-finding__severity">
+                      {severityLabel(finding.severity)}
+                    </span>
+                    <span className="security-finding__title">{finding.title}</span>
+                  </div>
+                  <p className="security-finding__detail">{finding.detail}</p>
+                  <p className="security-finding__remediation">{finding.remediation}</p>
+                </li>
+              ))}
+            </ul>
+          </>
+        )}
+      </div>
+    </div>
+  )
+}
+
+export default SecurityReportCard
@@ -1,5 +1,7 @@
 import React, { useState, useEffect } from 'react'
 import { invoke } from '@tauri-apps/api/core'
+import type { UserIdentity } from '../types/api'
+import type { PlanQuota } from '../types/generated/PlanQuota'
 import './Settings.css'
 
 interface SettingsData {
@@ -9,6 +11,8 @@ interface SettingsData {
   log_level?: string
   auto_start_server?: boolean
   auto_create_tunnel?: boolean
+  user?: UserIdentity | null
+  quota?: PlanQuota | null
 }
 
 const Settings: React.FC = () => {
@@ -115,6 +119,17 @@ const Settings: React.FC = () => {
                 <div className={`status-indicator ${isAuthenticated ? 'connected' : 'stopped'}`}>
                   {isAuthenticated ? 'Authenticated' : 'Not Authenticated'}
                 </div>
+                {settings.user && (
+                  <small className="form-help">
+                    Signed in as {settings.user.email ?? settings.user.account_id}
+                    {settings.user.plan && ` (${settings.user.plan} plan)`}
+                  </small>
+                )}
+                {settings.quota?.max_in_flight != null && (
+                  <small className="form-help">
+                    Up to {settings.quota.max_in_flight} requests at a time on this plan
+                  </small>
+                )}
               </div>
 
               <div className="form-group">
@@ -0,0 +1,86 @@
+import React, { useState, useEffect } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import type { StreamStats, StreamStatsReport } from '../types/api'
+import './StreamStatsCard.css'
+
+const formatMs = (ms: number | null) => {
+  if (ms === null) return '–'
+  return ms >= 1000 ? `${(ms / 1000).toFixed(1)} s` : `${Math.round(ms)} ms`
+}
+
+const formatSpeed = (tokensPerSecond: number | null) =>
+  tokensPerSecond === null ? '–' : `${tokensPerSecond.toFixed(1)} tok/s`
+
+const StatsRow: React.FC<{ label: string; stats: StreamStats }> = ({ label, stats }) => (
+  <tr>
+    <td>{label}</td>
+    <td>{stats.streams}</td>
+    <td>{formatMs(stats.ttft_p50_ms)}</td>
+    <td>{formatMs(stats.ttft_p90_ms)}</td>
+    <td>{formatSpeed(stats.tokens_per_second_p50)}</td>
+    <td>{formatSpeed(stats.tokens_per_second_p10)}</td>
+  </tr>
+)
+
+const StreamStatsCard: React.FC = () => {
+  const [report, setReport] = useState<StreamStatsReport | null>(null)
+  const [error, setError] = useState<string | null>(null)
+
+  const refreshStats = async () => {
+    try {
+      setReport(await invoke<StreamStatsReport>('get_stream_stats'))
+      setError(null)
+    } catch (err) {
+      console.error('❌ Failed to load stream statistics:', err)
+      setError(String(err))
+    }
+  }
+
+  useEffect(() => {
+    refreshStats()
+
+    // Every finished stream moves the percentiles
+    const interval = setInterval(refreshStats, 15000)
+
+    return () => {
+      clearInterval(interval)
+    }
+  }, [])
+
+  return (
+    <div className="card card--elevated">
+      <div className="card__header">
+        <h2 className="card__title">Streaming Performance</h2>
+      </div>
+
+      <div className="card__content">
+        {error && <p className="stream-stats__error">{error}</p>}
+
+        {!error && report?.overall.streams === 0 && (
+          <p className="text-secondary">No streamed replies yet</p>
+        )}
+
+        {report && report.overall.streams > 0 && (
+          <table className="stream-stats__table">
+            <thead>
+              <tr>
+                <th />
+                <th>Streams</th>
+                <th>TTFT p50</th>
+                <th>TTFT p90</th>
+                <th>Speed p50</th>
+                <th>Speed p10</th>
+              </tr>
+            </thead>
+            <tbody>
+              <StatsRow label={`Last ${report.recent_minutes} min`} stats={report.recent} />
+              <StatsRow label="All recent requests" stats={report.overall} />
+            </tbody>
+          </table>
+        )}
+      </div>
+    </div>
+  )
+}
+
+export default StreamStatsCard
@@ -0,0 +1,81 @@
+import React, { useState, useEffect } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import type { AppBudget, PeriodBudget } from '../types/api'
+import './TokenBudgetsCard.css'
+
+const fillModifier = (budget: PeriodBudget) => {
+  if (budget.remaining === 0) return 'token-budget__fill--exhausted'
+  if (budget.used >= budget.limit * 0.8) return 'token-budget__fill--warning'
+  return ''
+}
+
+const TokenBudgetsCard: React.FC = () => {
+  const [budgets, setBudgets] = useState<AppBudget[]>([])
+  const [error, setError] = useState<string | null>(null)
+
+  const refreshBudgets = async () => {
+    try {
+      setBudgets(await invoke<AppBudget[]>('get_token_budgets'))
+      setError(null)
+    } catch (err) {
+      console.error('❌ Failed to load token budgets:', err)
+      setError(String(err))
+    }
+  }
+
+  useEffect(() => {
+    refreshBudgets()
+
+    // Consumption grows with every request of the apps
+    const interval = setInterval(refreshBudgets, 15000)
+
+    return () => {
+      clearInterval(interval)
+    }
+  }, [])
+
+  return (
+    <div className="card card--elevated">
+      <div className="card__header">
+        <h2 className="card__title">Token Budgets</h2>
+      </div>
+
+      <div className="card__content">
+        {error && <p className="token-budgets__error">{error}</p>}
+
+        {!error && budgets.length === 0 && (
+          <p className="text-secondary">No app has a token budget</p>
+        )}
+
+        <ul className="token-budgets__list">
+          {budgets.map((app) => (
+            <li key={app.app_id}>
+              <span className="token-budgets__app">{app.app_name}</span>
+              {app.budgets.map((budget) => (
+                <div key={budget.period} className="token-budget">
+                  <div className="token-budget__label">
+                    <span className="token-budget__period">{budget.period}</span>
+                    <span>
+                      {budget.remaining.toLocaleString()} of {budget.limit.toLocaleString()} left
+                    </span>
+                  </div>
+                  <div
+                    className="token-budget__bar"
+                    title={`Resets ${new Date(budget.resets_at).toLocaleString()}`}
+                  >
+                    <div
+                      className={`token-budget__fill ${fillModifier(budget)}`}
+                      style={{ width: `${Math.min(100, (budget.used / budget.limit) * 100)}%` }}
+                    />
+                  </div>
+                </div>
+              ))}
+            </li>
+          ))}
+        </ul>
+      </div>
+    </div>
+  )
+}
+
+export default TokenBudgetsCard
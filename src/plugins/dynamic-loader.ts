@@ -137,7 +137,15 @@ export class DynamicPluginLoader {
         console.log('🔌 Plugin discovery response:', response)
         
         if (response.success && response.manifests) {
-          return response.manifests
+          for (const failure of response.errors ?? []) {
+            console.warn(`Plugin in ${failure.directory} failed to load: ${failure.message}`)
+          }
+          // Plugins discovered on disk override built-in providers with the same ID
+          const discovered = new Map<string, PluginManifest>(
+            response.manifests.map((manifest: PluginManifest) => [manifest.id, manifest])
+          )
+          const builtins = this.getBuiltinManifests().filter(m => !discovered.has(m.id))
+          return [...builtins, ...discovered.values()]
         } else {
           console.warn('Plugin discovery failed:', response.error)
           return this.getBuiltinManifests()
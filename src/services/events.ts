@@ -0,0 +1,29 @@
+// Typed listeners for backend events
+//
+// The event contract is defined in Rust (src-tauri/src/events.rs); the types in
+// ../types/generated are generated from it by ts-rs and must not be edited.
+
+import { listen, UnlistenFn } from '@tauri-apps/api/event'
+import type { AppEvent } from '../types/generated/AppEvent'
+import type { EventEnvelope } from '../types/generated/EventEnvelope'
+
+/** Event contract version this frontend was built against */
+export const EVENT_VERSION = 1
+
+export type AppEventKind = AppEvent['kind']
+export type AppEventData<K extends AppEventKind> = Extract<AppEvent, { kind: K }>['data']
+
+/** Listen for one backend event with a typed payload */
+export function listenAppEvent<K extends AppEventKind>(
+  kind: K,
+  handler: (data: AppEventData<K>) => void
+): Promise<UnlistenFn> {
+  return listen<EventEnvelope>(kind, ({ payload }) => {
+    if (payload.version !== EVENT_VERSION) {
+      console.warn(`Event ${kind} uses contract v${payload.version}, expected v${EVENT_VERSION}`)
+    }
+    if (payload.event.kind === kind) {
+      handler(payload.event.data as AppEventData<K>)
+    }
+  })
+}
@@ -1,5 +1,7 @@
 // API Response Types for Tauri Commands
 
+import type { PlanQuota } from './generated/PlanQuota'
+
 export interface StatusResponse {
   is_serving: boolean
   is_authenticated: boolean
@@ -8,6 +10,39 @@ export interface StatusResponse {
   bifrost_url?: string
   instance_token?: string
   last_error?: string
+  accounts: SavedAccount[]
+  user: UserIdentity | null
+  quota: PlanQuota | null
+}
+
+export interface UserIdentity {
+  email: string | null
+  plan: string | null
+  account_id: string
+  subscription_until: string | null
+}
+
+export interface SavedAccount {
+  account_id: string
+  active: boolean
+  authenticated: boolean
+  expires_at: string
+}
+
+export interface TunnelToken {
+  token: string
+  issued_at: string
+  expires_at: string
+}
+
+export interface TunnelTokenResponse {
+  token: TunnelToken
+  previous_expires_at: string | null
+}
+
+export interface CloudflareAccessResponse {
+  client_id: string
+  client_secret: string
 }
 
 export interface ServiceResponse {
@@ -23,4 +58,152 @@ export interface BinaryInstallationResponse {
   message?: string
   binary_path?: string
   is_installed: boolean
-}
\ No newline at end of file
+}
+export type Severity = 'info' | 'low' | 'medium' | 'high'
+
+export interface ExposedEndpoint {
+  name: string
+  address: string
+  reach: 'loopback' | 'network' | 'public'
+  tls: boolean
+}
+
+export interface SecurityFinding {
+  id: string
+  severity: Severity
+  title: string
+  detail: string
+  remediation: string
+}
+
+export interface SecurityReport {
+  generated_at: string
+  score: number
+  surface: ExposedEndpoint[]
+  findings: SecurityFinding[]
+}
+
+export type BudgetPeriod = 'daily' | 'monthly'
+
+export interface PeriodBudget {
+  period: BudgetPeriod
+  limit: number
+  used: number
+  remaining: number
+  resets_at: string
+}
+
+export interface AppBudget {
+  app_id: string
+  app_name: string
+  budgets: PeriodBudget[]
+}
+
+export interface RequestSummary {
+  request_id: string | null
+  at: string
+  method: string
+  route: string
+  status: number
+  model: string | null
+  streamed: boolean
+  validation_ms: number | null
+  auth_ms: number | null
+  upstream_ttfb_ms: number | null
+  stream_ms: number | null
+  total_ms: number
+  throughput: StreamThroughput | null
+}
+
+export interface StreamThroughput {
+  ttft_ms: number
+  generation_ms: number
+  completion_tokens: number | null
+  tokens_per_second: number | null
+}
+
+export interface StreamStats {
+  streams: number
+  ttft_p50_ms: number | null
+  ttft_p90_ms: number | null
+  tokens_per_second_p50: number | null
+  tokens_per_second_p10: number | null
+}
+
+export interface StreamStatsReport {
+  recent_minutes: number
+  recent: StreamStats
+  overall: StreamStats
+}
+
+export interface TunnelTraffic {
+  tunnel: string
+  requests: number
+  bytes_received: number
+  bytes_sent: number
+  probes: number
+  probe_failures: number
+  last_round_trip_ms: number | null
+  mean_round_trip_ms: number | null
+}
+
+export type TunnelService = 'api' | 'bifrost' | 'dashboard'
+
+export interface ServiceTunnel {
+  service: TunnelService
+  local_port: number
+  url: string | null
+  connected: boolean
+}
+
+export type PostProcessingAction =
+  | { type: 'regex_replace'; pattern: string; replacement: string }
+  | { type: 'stop_sequences'; sequences: string[] }
+  | { type: 'strip_markdown' }
+  | { type: 'extract_json' }
+
+export type PostProcessingRule = PostProcessingAction & {
+  models: string[]
+}
+
+export interface PostProcessingConfig {
+  rules: PostProcessingRule[]
+}
+
+export type RequestTransformAction =
+  | { type: 'set_field'; field: string; value: unknown }
+  | { type: 'remove_fields'; fields: string[] }
+  | { type: 'rename_model'; to: string }
+  | { type: 'set_header'; name: string; value: string }
+  | { type: 'remove_header'; name: string }
+
+export type RequestTransformRule = RequestTransformAction & {
+  user_agent: string | null
+  models: string[]
+}
+
+export interface RequestTransformConfig {
+  rules: RequestTransformRule[]
+}
+
+export type LoginFlow = 'browser' | 'device_code'
+
+export interface AuthConfig {
+  login_flow: LoginFlow
+  client_id: string
+  redirect_port: number
+  scopes: string[]
+  callback_path_token: boolean
+}
+
+export interface ProxyConfig {
+  url: string | null
+  no_proxy: string[]
+}
+
+export interface AnthropicConfig {
+  enabled: boolean
+  client_id: string
+  redirect_port: number
+  scopes: string[]
+}
@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AuthEvent } from "./AuthEvent";
+import type { DownloadProgress } from "./DownloadProgress";
+import type { HealthReport } from "./HealthReport";
+import type { Notification } from "./Notification";
+import type { OverloadEvent } from "./OverloadEvent";
+import type { PowerStatus } from "./PowerStatus";
+import type { TrayState } from "./TrayState";
+import type { TunnelLimitWarning } from "./TunnelLimitWarning";
+import type { TunnelTokenRotated } from "./TunnelTokenRotated";
+import type { TunnelUrlChanged } from "./TunnelUrlChanged";
+
+/**
+ * Every event the backend emits. The Tauri event name equals `kind`.
+ */
+export type AppEvent = { "kind": "notification", "data": Notification } | { "kind": "tray-state-changed", "data": TrayState } | { "kind": "health-changed", "data": HealthReport } | { "kind": "power-saver-changed", "data": PowerStatus } | { "kind": "overloaded", "data": OverloadEvent } | { "kind": "auth", "data": AuthEvent } | { "kind": "tunnel-token-rotated", "data": TunnelTokenRotated } | { "kind": "tunnel-url-changed", "data": TunnelUrlChanged } | { "kind": "tunnel-limit", "data": TunnelLimitWarning } | { "kind": "download-progress", "data": DownloadProgress };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Progress of a login and other changes of the ChatGPT session, for the
+ * frontend to show
+ */
+export type AuthEvent = { "stage": "login_started" } | { "stage": "browser_opened", url: string, } | { "stage": "device_code_issued", verification_url: string, user_code: string, } | { "stage": "callback_received" } | { "stage": "tokens_saved", account_id: string, } | { "stage": "login_failed", reason: string, } | { "stage": "refresh_failed", reason: string, } | { "stage": "reauth_required", reason: string, };
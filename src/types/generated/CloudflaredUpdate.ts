@@ -0,0 +1,22 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Outcome of updating the managed cloudflared
+ */
+export type CloudflaredUpdate = { 
+/**
+ * Release installed before, if MindLink had a verified copy
+ */
+previous: string | null, 
+/**
+ * Release installed now
+ */
+installed: string, 
+/**
+ * Whether a new release was downloaded
+ */
+updated: boolean, 
+/**
+ * Whether `installed` is the pinned release rather than the latest
+ */
+pinned: boolean, };
@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { HealthLevel } from "./HealthLevel";
+
+/**
+ * Health of a single component (server, tunnel, auth, ...)
+ */
+export type ComponentHealth = { name: string, level: HealthLevel, reason: string | null, 
+/**
+ * Critical components take the whole application down when they fail;
+ * non-critical ones only degrade it.
+ */
+critical: boolean, checked_at: string, 
+/**
+ * Component-specific state, e.g. `expires_at` for `auth`
+ */
+details: Record<string, unknown>, };
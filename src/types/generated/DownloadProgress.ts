@@ -0,0 +1,26 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * How far the download of a binary got
+ */
+export type DownloadProgress = { 
+/**
+ * Binary being downloaded, e.g. `cloudflared`
+ */
+binary: string, 
+/**
+ * Bytes downloaded so far, those of an interrupted attempt included
+ */
+downloaded: number, 
+/**
+ * Size of the download, when the server tells it
+ */
+total: number | null, 
+/**
+ * Share downloaded, from 0 to 100, when the size is known
+ */
+percent: number | null, 
+/**
+ * Bytes taken over from an interrupted attempt
+ */
+resumed_from: number, done: boolean, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AppEvent } from "./AppEvent";
+
+/**
+ * Payload of every emitted event
+ */
+export type EventEnvelope = { version: number, event: AppEvent, };
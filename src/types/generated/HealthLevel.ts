@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Health level of a single component or of the application as a whole
+ */
+export type HealthLevel = "ok" | "degraded" | "down";
@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ComponentHealth } from "./ComponentHealth";
+import type { HealthLevel } from "./HealthLevel";
+
+/**
+ * Aggregated health of all components
+ */
+export type HealthReport = { overall: HealthLevel, reason: string | null, components: Array<ComponentHealth>, timestamp: string, };
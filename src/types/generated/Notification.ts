@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { NotificationKind } from "./NotificationKind";
+import type { RecoveryAction } from "./RecoveryAction";
+
+/**
+ * Non-blocking notification shown by the frontend
+ */
+export type Notification = { title: string, message: string, type: NotificationKind, timestamp: string, 
+/**
+ * Recovery the frontend offers as a button on error notifications
+ */
+action: RecoveryAction | null, };
@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { OverloadReason } from "./OverloadReason";
+
+/**
+ * Requests are being turned away with 429
+ */
+export type OverloadEvent = { reason: OverloadReason, retry_after_secs: number, in_flight: number, max_in_flight: number, at: string, };
@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { AccountPlan } from "./AccountPlan";
+
+/**
+ * Plan of an account and the caps that come with it
+ */
+export type PlanQuota = { plan: AccountPlan, 
+/**
+ * Concurrent requests the account is expected to sustain, unknown for
+ * plans MindLink does not know
+ */
+max_in_flight: number | null, 
+/**
+ * When the subscription ends, if the ID token tells
+ */
+active_until: string | null, };
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { PowerSaverMode } from "./PowerSaverMode";
+import type { PowerSource } from "./PowerSource";
+
+/**
+ * Current power saver state, shown in the UI
+ */
+export type PowerStatus = { source: PowerSource, mode: PowerSaverMode, active: boolean, 
+/**
+ * Bifrost was stopped by the power saver and is started again on AC power
+ */
+bifrost_stopped: boolean, };
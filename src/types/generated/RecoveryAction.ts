@@ -0,0 +1,7 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * What the user can do to recover from an error. The UI renders it as a
+ * button instead of a generic dialog.
+ */
+export type RecoveryAction = { "action": "re_authenticate" } | { "action": "retry_later" } | { "action": "reinstall_binary", binary: string, } | { "action": "open_settings", section: string, };
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Application states for tray icon management
+ */
+export type TrayState = "Disconnected" | "Connecting" | "Connected" | "Degraded" | "Error" | "ReauthRequired";
@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TunnelLimit } from "./TunnelLimit";
+
+/**
+ * Traffic through the tunnel is nearing a limit, or reached it
+ */
+export type TunnelLimitWarning = { limit: TunnelLimit, 
+/**
+ * Connections open, or bytes sent this month
+ */
+used: number, max: number, 
+/**
+ * Whether requests through the tunnel are being refused
+ */
+reached: boolean, at: string, };
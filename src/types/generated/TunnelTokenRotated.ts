@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * A new tunnel token was issued; pairing data shown before is outdated
+ */
+export type TunnelTokenRotated = { expires_at: string, 
+/**
+ * Whether the user rotated it, revoking the previous token
+ */
+manual: boolean, };
@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * The public URL changed: a tunnel came up, moved or closed
+ */
+export type TunnelUrlChanged = { 
+/**
+ * `None` once the tunnel is closed
+ */
+url: string | null, previous_url: string | null, changed_at: string, };